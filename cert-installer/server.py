@@ -271,6 +271,11 @@ def track_install_complete(visitor_id: str):
         INSTALLATIONS[visitor_id]["install_time"] = datetime.now().isoformat()
 
 
+def report_event(event: str, **data):
+    """One JSON line on stdout per request, which the app counts to show activity and stop the server when idle."""
+    print(json.dumps({"event": event, **data}), flush=True)
+
+
 def get_theme() -> Dict[str, Any]:
     """Get current theme configuration."""
     theme_name = CONFIG.get("theme", "wifi_security")
@@ -585,6 +590,30 @@ def api_set_theme(theme_name: str):
     return jsonify({"success": False, "error": "Unknown theme"}), 400
 
 
+@app.before_request
+def remember_install_token():
+    """Keep the token of the install link a visitor arrived through, for the app's attribution."""
+    if not session.get("visitor_id"):
+        session["visitor_id"] = str(uuid.uuid4())
+    token = request.args.get("t")
+    if token:
+        session["install_token"] = token
+
+
+@app.after_request
+def report_request(response):
+    """Report every request served, and certificate downloads separately."""
+    download = request.endpoint == "download_cert" and response.status_code == 200
+    report_event(
+        "download" if download else "request",
+        path=request.path,
+        status=response.status_code,
+        visitor=session.get("visitor_id"),
+        token=session.get("install_token"),
+    )
+    return response
+
+
 # Error handlers
 
 @app.errorhandler(404)
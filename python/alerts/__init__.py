@@ -4,6 +4,7 @@ Alert and Notification Module.
 Provides comprehensive alerting capabilities:
 - Keyword detection (including fuzzy/leetspeak matching)
 - Alert generation and management
+- Correlation of related alerts into incidents
 - Desktop notifications (toast, sound, email, webhook)
 - Predefined keywords for concerning content
 """
@@ -13,6 +14,10 @@ from .alert_engine import (
     AlertEngine,
     AlertRule,
 )
+from .incidents import (
+    Incident,
+    IncidentStore,
+)
 from .keywords import (
     AlertCategory,
     AlertSeverity,
@@ -33,6 +38,9 @@ __all__ = [
     "AlertEngine",
     "Alert",
     "AlertRule",
+    # Incidents
+    "Incident",
+    "IncidentStore",
     # Keywords
     "KeywordMatcher",
     "Keyword",
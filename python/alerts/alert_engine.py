@@ -5,6 +5,9 @@ Central alert processing system that:
 - Monitors traffic for keyword matches
 - Detects concerning patterns
 - Generates and stores alerts
+- Folds repeats of an alert into an occurrence count and rate-limits alert storms
+- Remaps severities per rule and escalates bursts on a device to critical incidents
+- Correlates related alerts into incidents
 - Triggers notifications
 """
 
@@ -17,6 +20,15 @@ from pathlib import Path
 from typing import Any, Callable, Dict, List, Optional, Set
 from queue import Queue
 
+# Defaults for the settings.json keys that tune deduplication and rate limits
+DEFAULT_DEDUP_WINDOW_SECONDS = 300
+DEFAULT_MAX_ALERTS_PER_HOUR = 100
+
+from ..database.retention import cutoffs as retention_cutoffs
+from ..utils.clock import local_now, parse_timestamp, to_iso, to_utc_iso, utc_now, utc_now_iso
+from ..utils.config import get_config_path, get_data_path, get_setting
+from ..utils.i18n import t
+from .incidents import SEVERITY_ORDER, Incident, IncidentStore
 from .keywords import (
     AlertCategory,
     AlertSeverity,
@@ -47,6 +59,10 @@ class Alert:
     matched_text: Optional[str] = None
     context: Optional[str] = None
     
+    # Repeats folded into this alert by deduplication
+    occurrences: int = 1
+    last_seen: Optional[str] = None
+    
     # Status
     acknowledged: bool = False
     acknowledged_at: Optional[str] = None
@@ -71,6 +87,8 @@ class Alert:
             "matched_keyword": self.matched_keyword,
             "matched_text": self.matched_text,
             "context": self.context,
+            "occurrences": self.occurrences,
+            "last_seen": self.last_seen or self.timestamp,
             "acknowledged": self.acknowledged,
             "acknowledged_at": self.acknowledged_at,
             "notes": self.notes,
@@ -94,6 +112,8 @@ class Alert:
             matched_keyword=data.get("matched_keyword"),
             matched_text=data.get("matched_text"),
             context=data.get("context"),
+            occurrences=data.get("occurrences", 1),
+            last_seen=data.get("last_seen"),
             acknowledged=data.get("acknowledged", False),
             acknowledged_at=data.get("acknowledged_at"),
             notes=data.get("notes", ""),
@@ -149,12 +169,10 @@ class AlertEngine:
             alerts_file: Path to store alerts
         """
         if config_file is None:
-            project_root = Path(__file__).parent.parent.parent
-            config_file = project_root / "config" / "alerts.json"
+            config_file = get_config_path() / "alerts.json"
         
         if alerts_file is None:
-            project_root = Path(__file__).parent.parent.parent
-            alerts_file = project_root / "database" / "alerts.json"
+            alerts_file = get_data_path() / "alerts.json"
         
         self.config_file = Path(config_file)
         self.alerts_file = Path(alerts_file)
@@ -162,6 +180,7 @@ class AlertEngine:
         self.keyword_matcher = KeywordMatcher()
         self.rules: Dict[str, AlertRule] = {}
         self.alerts: List[Alert] = []
+        self.incidents = IncidentStore(self.alerts_file.parent / "incidents.json")
         
         # Callbacks for real-time notifications
         self._alert_callbacks: List[Callable[[Alert], None]] = []
@@ -237,6 +256,10 @@ class AlertEngine:
             data = json.loads(self.alerts_file.read_text())
             for alert_data in data.get("alerts", []):
                 alert = Alert.from_dict(alert_data)
+                # Alerts saved before UTC storage have naive local timestamps
+                alert.timestamp = to_utc_iso(alert.timestamp)
+                alert.last_seen = to_utc_iso(alert.last_seen)
+                alert.acknowledged_at = to_utc_iso(alert.acknowledged_at)
                 self.alerts.append(alert)
         except Exception as e:
             print(json.dumps({"error": f"Failed to load alerts: {e}"}))
@@ -245,10 +268,10 @@ class AlertEngine:
         """Save alerts to file."""
         self.alerts_file.parent.mkdir(parents=True, exist_ok=True)
         
-        # Only save recent alerts (last 7 days)
-        cutoff = datetime.now() - timedelta(days=7)
-        recent = [a for a in self.alerts 
-                  if datetime.fromisoformat(a.timestamp) > cutoff]
+        # Only save alerts within the retention window
+        cutoff = retention_cutoffs()["alerts"]
+        recent = [a for a in self.alerts
+                  if not cutoff or parse_timestamp(a.timestamp) > parse_timestamp(cutoff)]
         
         data = {
             "alerts": [a.to_dict() for a in recent]
@@ -341,8 +364,7 @@ class AlertEngine:
                 "source_ip": source_ip,
                 "source_device": source_device
             })
-            if alert:
-                self._add_alert(alert)
+            if alert and self._add_alert(alert) is alert:
                 alerts.append(alert)
         
         # Check content
@@ -354,8 +376,7 @@ class AlertEngine:
                 "source_ip": source_ip,
                 "source_device": source_device
             })
-            if alert:
-                self._add_alert(alert)
+            if alert and self._add_alert(alert) is alert:
                 alerts.append(alert)
         
         return alerts
@@ -375,7 +396,7 @@ class AlertEngine:
         
         alert = Alert(
             id=alert_id,
-            timestamp=datetime.now().isoformat(),
+            timestamp=utc_now_iso(),
             severity=match.severity,
             category=match.keyword.category,
             title=self._generate_title(match),
@@ -405,13 +426,126 @@ class AlertEngine:
         
         # Check max alerts per hour
         count = self._alert_counter.get(key, 0)
-        if count >= 100:  # Default max
+        if count >= DEFAULT_MAX_ALERTS_PER_HOUR:
             return False
         
         self._alert_counter[key] = count + 1
         
         return True
     
+    @staticmethod
+    def _rule_of(alert: Alert) -> str:
+        """The rule an alert counts against: its keyword, or its category for subsystem alerts."""
+        return alert.matched_keyword or alert.category.value
+    
+    @staticmethod
+    def _fingerprint(alert: Alert) -> tuple:
+        return (
+            alert.title, alert.category, alert.severity, alert.source_device or alert.source_ip,
+            alert.domain, alert.matched_keyword
+        )
+    
+    def _find_duplicate(self, alert: Alert) -> Optional[Alert]:
+        """An unacknowledged identical alert last seen within the dedup window."""
+        window = timedelta(seconds=get_setting("alert_dedup_window_seconds", DEFAULT_DEDUP_WINDOW_SECONDS))
+        if window <= timedelta(0):
+            return None
+        
+        cutoff = to_iso(parse_timestamp(alert.timestamp) - window)
+        fingerprint = self._fingerprint(alert)
+        for existing in reversed(self.alerts):
+            if (
+                not existing.acknowledged
+                and (existing.last_seen or existing.timestamp) >= cutoff
+                and self._fingerprint(existing) == fingerprint
+            ):
+                return existing
+        return None
+    
+    def _over_rate_limit(self, alert: Alert) -> bool:
+        """Whether the alert's rule already raised its hourly limit of alerts."""
+        rule = self._rule_of(alert)
+        limits = get_setting("alert_rule_limits", {}) or {}
+        limit = limits.get(rule, get_setting("alert_max_per_hour", DEFAULT_MAX_ALERTS_PER_HOUR))
+        if not limit:
+            return False
+        
+        cutoff = to_iso(utc_now() - timedelta(hours=1))
+        recent = sum(1 for a in self.alerts if a.timestamp >= cutoff and self._rule_of(a) == rule)
+        return recent >= limit
+    
+    def _map_severity(self, alert: Alert):
+        """Apply the `alert_severity_map` setting, keyed by rule (keyword) or category."""
+        mapping = get_setting("alert_severity_map", {}) or {}
+        severity = mapping.get(self._rule_of(alert)) or mapping.get(alert.category.value)
+        if not severity or severity == alert.severity.value:
+            return
+        try:
+            mapped = AlertSeverity(severity)
+        except ValueError:
+            return
+        alert.metadata["original_severity"] = alert.severity.value
+        alert.severity = mapped
+    
+    def _escalate(self, alert: Alert, incident: Incident):
+        """
+        Check the `alert_escalations` setting after a new alert.
+        
+        A rule fires once `count` alerts of at least `min_severity` (and of
+        `category`, if set) came from one device within `window_minutes`:
+        the alert's incident becomes critical and, if `notify` is set, a
+        notification goes out. A rule fires at most once per device per window.
+        """
+        device = alert.source_device or alert.source_ip
+        if not device:
+            return
+        
+        for rule in get_setting("alert_escalations", []) or []:
+            name = rule.get("name") or "escalation"
+            min_severity = rule.get("min_severity", "high")
+            category = rule.get("category")
+            if min_severity not in SEVERITY_ORDER:
+                continue
+            
+            def counts(a: Alert) -> bool:
+                return (
+                    SEVERITY_ORDER.index(a.severity.value) >= SEVERITY_ORDER.index(min_severity)
+                    and (not category or a.category.value == category)
+                )
+            
+            if not counts(alert):
+                continue
+            
+            cutoff = to_iso(parse_timestamp(alert.timestamp) - timedelta(minutes=rule.get("window_minutes", 60)))
+            if any(
+                i.device == device and i.escalated_by == name and (i.escalated_at or "") >= cutoff
+                for i in self.incidents.incidents
+            ):
+                continue
+            
+            recent = [
+                a for a in self.alerts
+                if (a.source_device or a.source_ip) == device and a.timestamp >= cutoff and counts(a)
+            ]
+            if len(recent) < max(1, rule.get("count", 3)):
+                continue
+            
+            incident.severity = AlertSeverity.CRITICAL.value
+            incident.escalated_by = name
+            incident.escalated_at = alert.timestamp
+            output_json({"type": "incident_escalated", "rule": name, "incident": incident.to_dict()})
+            
+            if rule.get("notify", True) and get_setting("notifications_enabled", True):
+                from .notifier import Notifier
+                Notifier().notify(
+                    title=t("alert.escalation.title", device=device),
+                    message=t("alert.escalation.description", device=device, count=len(recent), rule=name, title=incident.title),
+                    severity=AlertSeverity.CRITICAL,
+                    category=alert.category,
+                    alert_id=incident.id,
+                    async_send=False
+                )
+    
     def _generate_title(self, match: KeywordMatch) -> str:
         """Generate alert title."""
         severity_emoji = {
@@ -422,31 +556,59 @@ class AlertEngine:
         }
         
         emoji = severity_emoji.get(match.severity, "")
-        category = match.keyword.category.value.replace("_", " ").title()
+        value = match.keyword.category.value
+        category = t(f"category.{value}", default=value.replace("_", " ").title())
         
-        return f"{emoji} {category} Alert: {match.keyword.word}"
+        return t("alert.keyword.title", emoji=emoji, category=category, keyword=match.keyword.word).strip()
     
     def _generate_description(self, match: KeywordMatch, context_info: dict) -> str:
         """Generate alert description."""
         parts = [
-            f"Keyword '{match.matched_text}' detected",
-            f"in {match.location}",
+            t("alert.keyword.detected", text=match.matched_text),
+            t("alert.keyword.location", location=match.location),
         ]
         
         if context_info.get("domain"):
-            parts.append(f"on {context_info['domain']}")
+            parts.append(t("alert.keyword.domain", domain=context_info["domain"]))
         
         if context_info.get("source_device"):
-            parts.append(f"from device {context_info['source_device']}")
+            parts.append(t("alert.keyword.device", device=context_info["source_device"]))
         elif context_info.get("source_ip"):
-            parts.append(f"from {context_info['source_ip']}")
+            parts.append(t("alert.keyword.ip", ip=context_info["source_ip"]))
         
         return " ".join(parts)
     
-    def _add_alert(self, alert: Alert):
-        """Add an alert and trigger notifications."""
+    def _add_alert(self, alert: Alert) -> Optional[Alert]:
+        """
+        Add an alert, file it under an incident and trigger notifications.
+        
+        Its severity is remapped first (see _map_severity). A repeat of a
+        recent alert only bumps that alert's occurrence count, and an alert
+        whose rule is over its hourly limit is dropped. A new alert may
+        escalate its incident (see _escalate).
+        
+        Returns:
+            The stored alert (the existing one for a repeat), or None if dropped
+        """
+        self._map_severity(alert)
+        duplicate = self._find_duplicate(alert)
+        if duplicate:
+            duplicate.occurrences += 1
+            duplicate.last_seen = alert.timestamp
+            self._save_alerts()
+            output_json({"type": "alert_updated", "alert": duplicate.to_dict()})
+            return duplicate
+        
+        if self._over_rate_limit(alert):
+            output_json({"type": "alert_suppressed", "rule": self._rule_of(alert), "title": alert.title})
+            return None
+        
+        incident = self.incidents.correlate(alert)
+        alert.metadata["incident_id"] = incident.id
         self.alerts.append(alert)
+        self._escalate(alert, incident)
         self._save_alerts()
+        self.incidents.save()
         
         # Output to stdout for Tauri IPC
         output_json({
@@ -460,6 +622,36 @@ class AlertEngine:
                 callback(alert)
             except Exception:
                 pass
+        
+        return alert
+    
+    def create_alert(
+        self,
+        severity: AlertSeverity,
+        category: AlertCategory,
+        title: str,
+        description: str,
+        source_ip: Optional[str] = None,
+        source_device: Optional[str] = None,
+        domain: Optional[str] = None,
+        url: Optional[str] = None,
+        metadata: Optional[Dict[str, Any]] = None
+    ) -> Optional[Alert]:
+        """Create an alert raised by another subsystem (not a keyword match). See _add_alert for the return value."""
+        alert = Alert(
+            id=f"alert_{datetime.now().strftime('%Y%m%d_%H%M%S_%f')}",
+            timestamp=utc_now_iso(),
+            severity=severity,
+            category=category,
+            title=title,
+            description=description,
+            source_ip=source_ip,
+            source_device=source_device,
+            domain=domain,
+            url=url,
+            metadata=metadata or {}
+        )
+        return self._add_alert(alert)
     
     def add_callback(self, callback: Callable[[Alert], None]):
         """Add a callback for new alerts."""
@@ -470,7 +662,7 @@ class AlertEngine:
         for alert in self.alerts:
             if alert.id == alert_id:
                 alert.acknowledged = True
-                alert.acknowledged_at = datetime.now().isoformat()
+                alert.acknowledged_at = utc_now_iso()
                 alert.notes = notes
                 self._save_alerts()
                 return True
@@ -491,7 +683,7 @@ class AlertEngine:
             severity: Filter by severity
             category: Filter by category
             acknowledged: Filter by acknowledgment status
-            since: Only alerts after this time
+            since: Only alerts after this time (timezone-aware)
             limit: Maximum number to return
             
         Returns:
@@ -511,7 +703,7 @@ class AlertEngine:
         if since:
             filtered = [
                 a for a in filtered
-                if datetime.fromisoformat(a.timestamp) > since
+                if parse_timestamp(a.timestamp) > since
             ]
         
         # Sort by timestamp descending
@@ -519,6 +711,31 @@ class AlertEngine:
         
         return filtered[:limit]
     
+    def get_incident_details(self, incident_id: str) -> Optional[dict]:
+        """An incident with its alerts as a timeline, oldest first."""
+        incident = self.incidents.get(incident_id)
+        if not incident:
+            return None
+        
+        ids = set(incident.alert_ids)
+        timeline = sorted((a for a in self.alerts if a.id in ids), key=lambda a: a.timestamp)
+        return {**incident.to_dict(), "alerts": [a.to_dict() for a in timeline]}
+    
+    def resolve_incident(self, incident_id: str) -> bool:
+        """Resolve an incident and acknowledge all of its alerts."""
+        incident = self.incidents.resolve(incident_id)
+        if not incident:
+            return False
+        
+        ids = set(incident.alert_ids)
+        for alert in self.alerts:
+            if alert.id in ids and not alert.acknowledged:
+                alert.acknowledged = True
+                alert.acknowledged_at = incident.resolved_at
+        self._save_alerts()
+        self.incidents.save()
+        return True
+    
     def get_unacknowledged_count(self) -> Dict[str, int]:
         """Get count of unacknowledged alerts by severity."""
         counts = {s.value: 0 for s in AlertSeverity}
@@ -545,18 +762,18 @@ class AlertEngine:
     
     def get_stats(self) -> dict:
         """Get alert statistics."""
-        now = datetime.now()
+        now = local_now()
         today = now.replace(hour=0, minute=0, second=0, microsecond=0)
         week_ago = now - timedelta(days=7)
         
         today_alerts = [
             a for a in self.alerts
-            if datetime.fromisoformat(a.timestamp) > today
+            if parse_timestamp(a.timestamp) > today
         ]
         
         week_alerts = [
             a for a in self.alerts
-            if datetime.fromisoformat(a.timestamp) > week_ago
+            if parse_timestamp(a.timestamp) > week_ago
         ]
         
         return {
@@ -583,16 +800,23 @@ def main():
     
     parser = argparse.ArgumentParser(description="Alert engine")
     parser.add_argument("--action", choices=[
-        "stats", "list", "process", "acknowledge", "acknowledge-all", "delete", "unacknowledged"
+        "stats", "list", "process", "acknowledge", "acknowledge-all", "delete", "unacknowledged",
+        "create", "incidents", "incident", "resolve-incident"
     ], default="stats", help="Action to perform")
     parser.add_argument("--content", help="Content to process")
     parser.add_argument("--url", help="URL to process")
     parser.add_argument("--domain", help="Domain")
-    parser.add_argument("--id", dest="alert_id", help="Alert ID to acknowledge/delete")
+    parser.add_argument("--id", dest="alert_id", help="Alert or incident ID to acknowledge/delete/resolve")
     parser.add_argument("--alert-id", dest="alert_id_legacy", help="Alert ID (legacy)")
     parser.add_argument("--severity", help="Filter by severity")
     parser.add_argument("--category", help="Filter by category")
     parser.add_argument("--limit", type=int, default=100, help="Max results")
+    parser.add_argument("--title", help="Alert title (create)")
+    parser.add_argument("--description", help="Alert description (create)")
+    parser.add_argument("--device", help="Source device ID (create)")
+    parser.add_argument("--ip", help="Source IP (create)")
+    parser.add_argument("--metadata", help="JSON metadata (create)")
+    parser.add_argument("--status", choices=["open", "resolved"], help="Filter incidents by status")
     
     args = parser.parse_args()
     
@@ -656,7 +880,7 @@ def main():
             for alert in engine.alerts:
                 if not alert.acknowledged:
                     alert.acknowledged = True
-                    alert.acknowledged_at = datetime.now().isoformat()
+                    alert.acknowledged_at = utc_now_iso()
                     count += 1
             engine._save_alerts()
             output_json({"success": True, "action": "acknowledged-all", "count": count})
@@ -676,6 +900,51 @@ def main():
             else:
                 output_json({"success": False, "error": f"Alert not found: {alert_id}"})
         
+        elif args.action == "create":
+            if not args.title or not args.severity or not args.category:
+                output_json({"success": False, "error": "Title, severity and category are required"})
+                return
+            
+            alert = engine.create_alert(
+                severity=AlertSeverity(args.severity),
+                category=AlertCategory(args.category),
+                title=args.title,
+                description=args.description or "",
+                source_ip=args.ip,
+                source_device=args.device,
+                domain=args.domain,
+                url=args.url,
+                metadata=json.loads(args.metadata) if args.metadata else None
+            )
+            if alert:
+                output_json({"success": True, "action": "created", "alert": alert.to_dict()})
+            else:
+                output_json({"success": True, "action": "suppressed"})
+        
+        elif args.action == "incidents":
+            incidents = engine.incidents.list(status=args.status, device=args.device, limit=args.limit)
+            output_json({
+                "success": True,
+                "incidents": [i.to_dict() for i in incidents]
+            })
+        
+        elif args.action == "incident":
+            details = engine.get_incident_details(alert_id) if alert_id else None
+            if details:
+                output_json({"success": True, "incident": details})
+            else:
+                output_json({"success": False, "error": f"Incident not found: {alert_id}"})
+        
+        elif args.action == "resolve-incident":
+            if not alert_id:
+                output_json({"success": False, "error": "No incident ID specified"})
+                return
+            
+            if engine.resolve_incident(alert_id):
+                output_json({"success": True, "action": "resolved", "id": alert_id})
+            else:
+                output_json({"success": False, "error": f"Incident not found: {alert_id}"})
+        
         elif args.action == "unacknowledged":
             output_json({
                 "success": True,
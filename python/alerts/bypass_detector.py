@@ -0,0 +1,582 @@
+"""
+VPN / Tor / Proxy Bypass Detector.
+
+Watches forwarded traffic for devices tunnelling around the filter:
+- Known endpoint lists (Tor relays, VPN servers, resolved VPN provider domains)
+- Port/protocol heuristics (OpenVPN, WireGuard, IPsec, PPTP, SOCKS)
+- TLS ClientHello fingerprints (JA3)
+
+Detections are stored, raised as alerts, and the device's recorded
+traffic is tagged so the bypass is visible next to what was captured.
+
+Every packet is also counted per device and protocol (HTTP, HTTPS, DNS,
+QUIC, other), which shows how much traffic never reaches the proxy, and
+grouped into connection-level flows (see flow_logger).
+"""
+
+import hashlib
+import ipaddress
+import json
+import sys
+import threading
+import time
+import urllib.request
+from dataclasses import dataclass
+from datetime import datetime, timedelta
+from pathlib import Path
+from typing import Dict, List, Optional, Set, Tuple
+
+from scapy.all import IP, TCP, UDP, Ether, Raw, conf, get_if_hwaddr, sniff
+
+from .alert_engine import AlertEngine
+from .flow_logger import FlowTable
+from .keywords import AlertCategory, AlertSeverity
+from ..blocking.categories import CATEGORY_DEFINITIONS, BlockCategory
+from ..database.db_manager import DatabaseManager
+from ..utils.clock import to_iso, utc_now, utc_now_iso
+from ..utils.i18n import t
+
+
+PROJECT_ROOT = Path(__file__).parent.parent.parent
+ENDPOINTS_FILE = PROJECT_ROOT / "config" / "vpn_endpoints.json"
+TOR_RELAYS_FILE = PROJECT_ROOT / "config" / "tor_relays.json"
+TOR_RELAYS_URL = "https://onionoo.torproject.org/summary?running=true"
+
+# GREASE values (RFC 8701) are excluded from JA3
+GREASE = {0x0a0a + 0x1010 * i for i in range(16)}
+
+# Re-alert for the same device/protocol at most this often
+ALERT_INTERVAL = timedelta(hours=1)
+
+# How often learned endpoints are refreshed from captured DNS
+REFRESH_INTERVAL = 300
+
+# Record the same device/host/TLS version at most this often
+TLS_RECORD_INTERVAL = timedelta(hours=1)
+
+# How often protocol counters are written to the database (seconds)
+PROTOCOL_FLUSH_INTERVAL = 60
+
+# Protocol for a (transport, server port); anything else is "other"
+PROTOCOL_PORTS = {
+    ("tcp", 80): "http",
+    ("tcp", 443): "https",
+    ("udp", 443): "quic",
+    ("udp", 53): "dns",
+    ("tcp", 53): "dns",
+    ("tcp", 853): "dns",
+}
+
+TLS_VERSION_NAMES = {0x0300: "SSLv3", 0x0301: "TLSv1", 0x0302: "TLSv1.1", 0x0303: "TLSv1.2", 0x0304: "TLSv1.3"}
+
+
+@dataclass
+class Detection:
+    """A single bypass observation."""
+    device_ip: str
+    method: str  # "vpn", "tor", "proxy"
+    protocol: str
+    endpoint: str
+    evidence: str  # "endpoint_list", "port", "tls_fingerprint", "domain"
+    detail: str = ""
+
+
+def _parse_client_hello(payload: bytes) -> Optional[Tuple[str, Optional[str], int]]:
+    """
+    Parse a TLS ClientHello into its JA3 string, SNI and the highest TLS
+    version the client offers.
+
+    Returns None if the payload is not a complete ClientHello record.
+    """
+    if len(payload) < 43 or payload[0] != 0x16 or payload[5] != 0x01:
+        return None
+
+    try:
+        pos = 9
+        version = int.from_bytes(payload[pos:pos + 2], "big")
+        pos += 2 + 32  # version + random
+
+        session_len = payload[pos]
+        pos += 1 + session_len
+
+        cipher_len = int.from_bytes(payload[pos:pos + 2], "big")
+        pos += 2
+        ciphers = [
+            int.from_bytes(payload[pos + i:pos + i + 2], "big")
+            for i in range(0, cipher_len, 2)
+        ]
+        pos += cipher_len
+
+        compression_len = payload[pos]
+        pos += 1 + compression_len
+
+        extensions: List[int] = []
+        curves: List[int] = []
+        point_formats: List[int] = []
+        sni = None
+        max_version = version
+
+        if pos + 2 <= len(payload):
+            ext_end = pos + 2 + int.from_bytes(payload[pos:pos + 2], "big")
+            pos += 2
+            while pos + 4 <= min(ext_end, len(payload)):
+                ext_type = int.from_bytes(payload[pos:pos + 2], "big")
+                ext_len = int.from_bytes(payload[pos + 2:pos + 4], "big")
+                data = payload[pos + 4:pos + 4 + ext_len]
+                pos += 4 + ext_len
+
+                if ext_type in GREASE:
+                    continue
+                extensions.append(ext_type)
+
+                if ext_type == 0x0000 and len(data) > 5:
+                    name_len = int.from_bytes(data[3:5], "big")
+                    sni = data[5:5 + name_len].decode("ascii", errors="ignore")
+                elif ext_type == 0x000a and len(data) >= 2:
+                    curves = [
+                        int.from_bytes(data[2 + i:4 + i], "big")
+                        for i in range(0, int.from_bytes(data[0:2], "big"), 2)
+                    ]
+                elif ext_type == 0x000b and len(data) >= 1:
+                    point_formats = list(data[1:1 + data[0]])
+                elif ext_type == 0x002b and len(data) >= 1:
+                    offered = [
+                        int.from_bytes(data[1 + i:3 + i], "big")
+                        for i in range(0, data[0], 2)
+                    ]
+                    offered = [v for v in offered if v not in GREASE]
+                    if offered:
+                        max_version = max(offered)
+
+        fields = [
+            str(version),
+            "-".join(str(c) for c in ciphers if c not in GREASE),
+            "-".join(str(e) for e in extensions),
+            "-".join(str(c) for c in curves if c not in GREASE),
+            "-".join(str(p) for p in point_formats),
+        ]
+        return ",".join(fields), sni, max_version
+
+    except (IndexError, ValueError):
+        return None
+
+
+class BypassDetector:
+    """
+    Detects VPN, Tor and proxy use by devices on the network.
+
+    Runs alongside the ARP gateway, so it sees every forwarded packet
+    from spoofed devices.
+    """
+
+    def __init__(self, interface: str, local_ip: Optional[str] = None):
+        """
+        Initialize the detector.
+
+        Args:
+            interface: Network interface to watch
+            local_ip: This machine's IP, excluded from detection
+        """
+        self.interface = interface
+        self.local_ip = local_ip
+        self.running = False
+        self.db = DatabaseManager()
+        self.alert_engine = AlertEngine()
+
+        self.ports: Dict[str, Dict[int, str]] = {"tcp": {}, "udp": {}}
+        self.ja3_fingerprints: Dict[str, str] = {}
+        self.endpoint_networks: List[Tuple[ipaddress.IPv4Network, str, str]] = []
+        self.tor_relays: Set[str] = set()
+        self.learned_endpoints: Dict[str, str] = {}
+
+        self._seen: Dict[Tuple[str, str, str], datetime] = {}
+        self._tls_seen: Dict[Tuple[str, str, str], datetime] = {}
+        self._alerted: Dict[Tuple[str, str], datetime] = {}
+        self._protocol_counts: Dict[Tuple[str, str, str], List[int]] = {}
+        self.flows = FlowTable()
+        self._lock = threading.Lock()
+
+        # Forwarded packets are captured twice (in and back out); only the first copy is counted
+        try:
+            self.our_mac = get_if_hwaddr(interface).lower()
+        except Exception:
+            self.our_mac = None
+
+        conf.verb = 0
+        self._load_endpoint_lists()
+
+    def _load_endpoint_lists(self):
+        """Load port, fingerprint and endpoint lists from config."""
+        if ENDPOINTS_FILE.exists():
+            try:
+                data = json.loads(ENDPOINTS_FILE.read_text())
+                for proto in ("tcp", "udp"):
+                    self.ports[proto] = {
+                        int(port): name
+                        for port, name in data.get("ports", {}).get(proto, {}).items()
+                    }
+                self.ja3_fingerprints = data.get("ja3", {})
+                for entry in data.get("networks", []):
+                    try:
+                        self.endpoint_networks.append((
+                            ipaddress.ip_network(entry["cidr"], strict=False),
+                            entry.get("method", "vpn"),
+                            entry.get("name", "")
+                        ))
+                    except (KeyError, ValueError):
+                        pass
+            except Exception as e:
+                print(json.dumps({"error": f"Failed to load VPN endpoints: {e}"}), file=sys.stderr, flush=True)
+
+        if TOR_RELAYS_FILE.exists():
+            try:
+                self.tor_relays = set(json.loads(TOR_RELAYS_FILE.read_text()).get("relays", []))
+            except Exception:
+                pass
+
+    def refresh_learned_endpoints(self):
+        """Learn VPN server IPs from captured lookups of VPN provider domains."""
+        vpn_domains = CATEGORY_DEFINITIONS[BlockCategory.VPN_PROXY].domains
+        since = utc_now() - timedelta(days=1)
+        learned: Dict[str, str] = {}
+
+        with self.db._get_connection() as conn:
+            rows = conn.execute(
+                "SELECT query_name, response_ip FROM dns_queries "
+                "WHERE response_ip IS NOT NULL AND timestamp > ?",
+                (to_iso(since),)
+            ).fetchall()
+
+        for row in rows:
+            name = row["query_name"].lower()
+            if any(name == d or name.endswith("." + d) for d in vpn_domains):
+                learned[row["response_ip"]] = name
+
+        with self._lock:
+            self.learned_endpoints = learned
+
+    def classify(self, packet) -> Optional[Detection]:
+        """Classify a packet as bypass traffic, if it is."""
+        if not packet.haslayer(IP):
+            return None
+
+        src = packet[IP].src
+        dst = packet[IP].dst
+        if src == self.local_ip:
+            return None
+
+        try:
+            src_addr = ipaddress.ip_address(src)
+            dst_addr = ipaddress.ip_address(dst)
+        except ValueError:
+            return None
+
+        # Only outbound traffic from LAN devices
+        if not src_addr.is_private or dst_addr.is_private:
+            return None
+
+        if dst in self.tor_relays:
+            return Detection(src, "tor", "tor", dst, "endpoint_list", "Known Tor relay")
+
+        with self._lock:
+            learned = self.learned_endpoints.get(dst)
+        if learned:
+            return Detection(src, "vpn", "vpn", dst, "domain", f"Resolved from {learned}")
+
+        for network, method, name in self.endpoint_networks:
+            if dst_addr in network:
+                return Detection(src, method, name or method, dst, "endpoint_list", str(network))
+
+        if packet.haslayer(TCP):
+            port = packet[TCP].dport
+            if port in self.ports["tcp"]:
+                protocol = self.ports["tcp"][port]
+                method = "tor" if protocol == "tor" else "proxy" if protocol in ("socks", "http_proxy") else "vpn"
+                return Detection(src, method, protocol, f"{dst}:{port}", "port", f"TCP port {port}")
+
+            if packet.haslayer(Raw):
+                parsed = _parse_client_hello(bytes(packet[Raw].load))
+                if parsed:
+                    ja3_string, sni, _ = parsed
+                    ja3 = hashlib.md5(ja3_string.encode()).hexdigest()
+                    label = self.ja3_fingerprints.get(ja3)
+                    if label:
+                        method = "tor" if "tor" in label.lower() else "vpn"
+                        return Detection(
+                            src, method, label, f"{dst}:{port}", "tls_fingerprint",
+                            f"JA3 {ja3}" + (f" SNI {sni}" if sni else "")
+                        )
+
+        elif packet.haslayer(UDP):
+            port = packet[UDP].dport
+            if port in self.ports["udp"]:
+                protocol = self.ports["udp"][port]
+                return Detection(src, "vpn", protocol, f"{dst}:{port}", "port", f"UDP port {port}")
+
+        return None
+
+    def observe_tls(self, packet):
+        """Record the TLS version a device offers in a ClientHello (passive fingerprinting)."""
+        if not (packet.haslayer(IP) and packet.haslayer(TCP) and packet.haslayer(Raw)):
+            return
+        
+        parsed = _parse_client_hello(bytes(packet[Raw].load))
+        if not parsed:
+            return
+        
+        _, sni, max_version = parsed
+        src = packet[IP].src
+        host = sni or packet[IP].dst
+        version = TLS_VERSION_NAMES.get(max_version, hex(max_version))
+        
+        now = utc_now()
+        key = (src, host, version)
+        with self._lock:
+            last = self._tls_seen.get(key)
+            if last and now - last < TLS_RECORD_INTERVAL:
+                return
+            self._tls_seen[key] = now
+        
+        self.db.add_tls_session(device_ip=src, host=host, source="passive", client_tls_version=version)
+
+    def count_protocol(self, packet):
+        """Add a packet between a LAN device and the internet to the protocol counters and its flow."""
+        if not packet.haslayer(IP):
+            return
+        if self.our_mac and packet.haslayer(Ether) and packet[Ether].src.lower() == self.our_mac:
+            return
+
+        src = packet[IP].src
+        dst = packet[IP].dst
+        if self.local_ip in (src, dst):
+            return
+        try:
+            outbound = ipaddress.ip_address(src).is_private
+            if outbound == ipaddress.ip_address(dst).is_private:
+                return
+        except ValueError:
+            return
+
+        if packet.haslayer(TCP):
+            transport, layer = "tcp", packet[TCP]
+        elif packet.haslayer(UDP):
+            transport, layer = "udp", packet[UDP]
+        else:
+            return
+        port = layer.dport if outbound else layer.sport
+        protocol = PROTOCOL_PORTS.get((transport, port), "other")
+
+        key = (to_iso(utc_now())[:16], src if outbound else dst, protocol)
+        size = len(packet[IP])
+        with self._lock:
+            counts = self._protocol_counts.setdefault(key, [0, 0, 0])
+            counts[0] += 1
+            counts[2 if outbound else 1] += size
+
+        if outbound:
+            device_ip, device_port, remote_ip, remote_port = src, layer.sport, dst, layer.dport
+        else:
+            device_ip, device_port, remote_ip, remote_port = dst, layer.dport, src, layer.sport
+        closing = transport == "tcp" and bool(int(layer.flags) & 0x05)  # FIN or RST
+        self.flows.add(device_ip, device_port, remote_ip, remote_port, transport, outbound, size, closing)
+
+    def flush_protocol_counts(self, final: bool = False):
+        """Write the protocol counters gathered since the last flush, and the flows that have ended."""
+        with self._lock:
+            counts, self._protocol_counts = self._protocol_counts, {}
+        if counts:
+            self.db.add_protocol_usage(counts)
+        flows = self.flows.drain(everything=final)
+        if flows:
+            self.db.add_flows(flows)
+
+    def _process_packet(self, packet):
+        """Handle a captured packet."""
+        try:
+            self.count_protocol(packet)
+            # DNS is only counted; the DNS capture handles the rest
+            layer = packet[TCP] if packet.haslayer(TCP) else packet[UDP] if packet.haslayer(UDP) else None
+            if layer is not None and 53 in (layer.sport, layer.dport):
+                return
+            if packet.haslayer(IP) and packet[IP].src != self.local_ip:
+                self.observe_tls(packet)
+            detection = self.classify(packet)
+            if detection:
+                self.record(detection)
+        except Exception as e:
+            print(json.dumps({"error": str(e), "type": "bypass_parse_error"}), file=sys.stderr, flush=True)
+
+    def record(self, detection: Detection):
+        """Store, tag and alert on a detection (deduplicated per endpoint)."""
+        now = utc_now()
+        key = (detection.device_ip, detection.protocol, detection.endpoint)
+
+        with self._lock:
+            last = self._seen.get(key)
+            if last and now - last < timedelta(minutes=10):
+                return
+            self._seen[key] = now
+
+        self.db.add_bypass_detection(
+            device_ip=detection.device_ip,
+            method=detection.method,
+            protocol=detection.protocol,
+            endpoint=detection.endpoint,
+            evidence=detection.evidence,
+            detail=detection.detail
+        )
+
+        # Anything captured since the tunnel appeared may be incomplete
+        tag = f"{detection.method.upper()}_BYPASS:{detection.protocol}"
+        self.db.tag_traffic(detection.device_ip, now - timedelta(minutes=10), tag)
+
+        print(json.dumps({
+            "type": "bypass_detected",
+            "timestamp": to_iso(now),
+            **detection.__dict__
+        }), flush=True)
+
+        alert_key = (detection.device_ip, detection.method)
+        with self._lock:
+            last_alert = self._alerted.get(alert_key)
+            if last_alert and now - last_alert < ALERT_INTERVAL:
+                return
+            self._alerted[alert_key] = now
+
+        names = {"vpn": "VPN", "tor": "Tor", "proxy": "proxy"}
+        self.alert_engine.create_alert(
+            severity=AlertSeverity.HIGH,
+            category=AlertCategory.SECURITY,
+            title=t("alert.bypass.title", method=names.get(detection.method, detection.method)),
+            description=t(
+                "alert.bypass.description",
+                device=detection.device_ip,
+                endpoint=detection.endpoint,
+                protocol=detection.protocol,
+                detail=detection.detail,
+            ),
+            source_ip=detection.device_ip,
+            metadata=detection.__dict__
+        )
+
+    def start(self):
+        """Start watching traffic."""
+        self.running = True
+
+        def capture_loop():
+            try:
+                sniff(
+                    iface=self.interface,
+                    filter="tcp or udp",
+                    prn=self._process_packet,
+                    store=False,
+                    stop_filter=lambda x: not self.running
+                )
+            except Exception as e:
+                print(json.dumps({"error": str(e), "type": "capture_error"}), file=sys.stderr, flush=True)
+
+        def refresh_loop():
+            while self.running:
+                try:
+                    self.refresh_learned_endpoints()
+                except Exception as e:
+                    print(json.dumps({"error": str(e), "type": "refresh_error"}), file=sys.stderr, flush=True)
+                time.sleep(REFRESH_INTERVAL)
+
+        def flush_loop():
+            while self.running:
+                time.sleep(PROTOCOL_FLUSH_INTERVAL)
+                try:
+                    self.flush_protocol_counts()
+                except Exception as e:
+                    print(json.dumps({"error": str(e), "type": "protocol_flush_error"}), file=sys.stderr, flush=True)
+
+        threading.Thread(target=capture_loop, daemon=True).start()
+        threading.Thread(target=refresh_loop, daemon=True).start()
+        threading.Thread(target=flush_loop, daemon=True).start()
+
+        print(json.dumps({
+            "status": "started",
+            "interface": self.interface,
+            "type": "bypass_detector"
+        }), flush=True)
+
+    def stop(self):
+        """Stop watching traffic."""
+        self.running = False
+        try:
+            self.flush_protocol_counts(final=True)
+        except Exception as e:
+            print(json.dumps({"error": str(e), "type": "protocol_flush_error"}), file=sys.stderr, flush=True)
+        print(json.dumps({"status": "stopped", "type": "bypass_detector"}), flush=True)
+
+
+def update_tor_relays() -> int:
+    """Download the current Tor relay address list."""
+    with urllib.request.urlopen(TOR_RELAYS_URL, timeout=30) as response:
+        data = json.loads(response.read().decode())
+
+    relays: Set[str] = set()
+    for relay in data.get("relays", []):
+        for address in relay.get("a", []):
+            # Onionoo lists IPv6 addresses in brackets; we only match IPv4
+            if not address.startswith("["):
+                relays.add(address)
+
+    TOR_RELAYS_FILE.parent.mkdir(parents=True, exist_ok=True)
+    TOR_RELAYS_FILE.write_text(json.dumps({
+        "updated_at": utc_now_iso(),
+        "relays": sorted(relays)
+    }))
+    return len(relays)
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="VPN/Tor/proxy bypass detector")
+    parser.add_argument("--action", choices=["start", "update-tor-list"], default="start")
+    parser.add_argument("--interface", "-i", help="Network interface")
+    parser.add_argument("--local-ip", help="This machine's IP (excluded)")
+
+    args = parser.parse_args()
+
+    if args.action == "update-tor-list":
+        try:
+            count = update_tor_relays()
+            output_json({"success": True, "relays": count})
+        except Exception as e:
+            output_json({"success": False, "error": f"Failed to update Tor relays: {e}"})
+        sys.exit(0)
+
+    if not args.interface:
+        output_json({"success": False, "error": "No interface specified"})
+        sys.exit(1)
+
+    detector = BypassDetector(args.interface, args.local_ip)
+
+    try:
+        detector.start()
+
+        while detector.running:
+            try:
+                line = sys.stdin.readline().strip()
+                if line:
+                    cmd = json.loads(line)
+                    if cmd.get("action") == "stop":
+                        break
+            except json.JSONDecodeError:
+                pass
+            except EOFError:
+                break
+
+    except KeyboardInterrupt:
+        pass
+    finally:
+        detector.stop()
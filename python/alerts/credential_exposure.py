@@ -0,0 +1,351 @@
+"""
+Plaintext Credential Exposure Detection.
+
+Flags captured requests that leak credentials:
+- password/token fields in URL query strings (logged by servers, proxies
+  and browser history even over HTTPS)
+- password/token fields in plain-HTTP request bodies
+- Authorization headers sent over plain HTTP
+
+Field names are matched against configurable regexes. Values are never
+stored or shown; alerts only name the device, host and field.
+"""
+
+import json
+import re
+import sys
+from dataclasses import asdict, dataclass
+from datetime import timedelta
+from typing import Any, Dict, Iterator, List, Optional, Tuple
+from urllib.parse import parse_qsl, urlsplit
+
+from .alert_engine import AlertEngine
+from .keywords import AlertCategory, AlertSeverity
+from ..database.db_manager import DatabaseManager
+from ..utils.clock import parse_timestamp, to_iso, utc_now
+from ..utils.config import get_config_path, get_data_path
+from ..utils.i18n import t
+
+
+DEFAULT_FIELD_PATTERNS = [
+    r"pass(word|wd|phrase)?$",
+    r"^pwd$",
+    r"secret",
+    r"token",
+    r"api[_-]?key",
+    r"access[_-]?key",
+    r"^auth$",
+    r"session[_-]?id",
+    r"credential",
+    r"private[_-]?key",
+]
+
+# Re-alert for the same device/host/kind at most this often
+ALERT_INTERVAL = timedelta(hours=24)
+
+# Rows checked per scan, so a backlog can't stall the caller
+SCAN_BATCH = 5000
+
+KIND_LABELS = {
+    "query_string": "in a URL query string",
+    "plain_http_body": "in a plain-HTTP request body",
+    "plain_http_header": "in an Authorization header over plain HTTP",
+}
+
+
+@dataclass
+class Exposure:
+    """A credential field seen in a request. The value itself is not kept."""
+    kind: str  # "query_string", "plain_http_body", "plain_http_header"
+    field: str
+    value_length: int
+
+    @property
+    def redacted(self) -> str:
+        return f"[redacted, {self.value_length} chars]"
+
+
+def _json_fields(value: Any, prefix: str = "") -> Iterator[Tuple[str, Any]]:
+    """Walk a JSON document, yielding (dotted key, scalar value)."""
+    if isinstance(value, dict):
+        for key, item in value.items():
+            yield from _json_fields(item, f"{prefix}{key}." if isinstance(item, (dict, list)) else f"{prefix}{key}")
+    elif isinstance(value, list):
+        for item in value:
+            yield from _json_fields(item, prefix)
+    elif prefix:
+        yield prefix.rstrip("."), value
+
+
+class CredentialDetector:
+    """Finds credential fields in captured requests."""
+
+    def __init__(self):
+        self.config_file = get_config_path() / "credential_exposure.json"
+        self.state_file = get_data_path() / "credential_exposure_state.json"
+        self.enabled = True
+        self.field_patterns: List[str] = list(DEFAULT_FIELD_PATTERNS)
+        self._compiled: List[re.Pattern] = []
+        self.state: dict = {"traffic_rowid": None, "alerted": {}}
+
+        self._load_config()
+        self._load_state()
+
+    def _load_config(self):
+        if self.config_file.exists():
+            try:
+                data = json.loads(self.config_file.read_text())
+                self.enabled = data.get("enabled", True)
+                self.field_patterns = data.get("field_patterns", self.field_patterns)
+            except Exception as e:
+                print(json.dumps({"error": f"Failed to load credential exposure config: {e}"}), file=sys.stderr, flush=True)
+        self._compiled = [re.compile(p, re.I) for p in self.field_patterns]
+
+    def get_config(self) -> dict:
+        return {"enabled": self.enabled, "field_patterns": self.field_patterns}
+
+    def set_config(self, enabled: bool, field_patterns: List[str]):
+        """Save the config. Raises ValueError on an invalid pattern."""
+        compiled = []
+        for pattern in field_patterns:
+            try:
+                compiled.append(re.compile(pattern, re.I))
+            except re.error as e:
+                raise ValueError(f"Invalid pattern '{pattern}': {e}")
+
+        self.enabled = enabled
+        self.field_patterns = field_patterns
+        self._compiled = compiled
+        self.config_file.parent.mkdir(parents=True, exist_ok=True)
+        self.config_file.write_text(json.dumps(self.get_config(), indent=2))
+
+    def _load_state(self):
+        if self.state_file.exists():
+            try:
+                self.state.update(json.loads(self.state_file.read_text()))
+            except Exception:
+                pass
+
+    def _save_state(self):
+        self.state_file.parent.mkdir(parents=True, exist_ok=True)
+        self.state_file.write_text(json.dumps(self.state, indent=2))
+
+    def is_credential_field(self, name: str) -> bool:
+        return any(p.search(name) for p in self._compiled)
+
+    def find_exposures(
+        self,
+        url: str,
+        protocol: Optional[str],
+        headers: Optional[str],
+        body: Optional[str],
+        body_type: Optional[str],
+    ) -> List[Exposure]:
+        """Credential fields leaked by one request."""
+        exposures: List[Exposure] = []
+        plain_http = (protocol or "").lower() == "http" or url.lower().startswith("http://")
+
+        for name, value in parse_qsl(urlsplit(url).query, keep_blank_values=False):
+            if self.is_credential_field(name):
+                exposures.append(Exposure("query_string", name, len(value)))
+
+        if not plain_http:
+            return exposures
+
+        try:
+            header_map = json.loads(headers or "{}")
+        except json.JSONDecodeError:
+            header_map = {}
+        for name, value in header_map.items():
+            if name.lower() in ("authorization", "proxy-authorization") and value:
+                scheme = str(value).split(" ", 1)[0]
+                exposures.append(Exposure("plain_http_header", f"{name} ({scheme})", len(str(value))))
+
+        if body:
+            body_type = (body_type or "").lower()
+            fields: List[Tuple[str, Any]] = []
+            if "json" in body_type or body.lstrip().startswith(("{", "[")):
+                try:
+                    fields = list(_json_fields(json.loads(body)))
+                except json.JSONDecodeError:
+                    pass
+            elif "form" in body_type or "=" in body:
+                fields = parse_qsl(body, keep_blank_values=False)
+
+            for name, value in fields:
+                if value not in (None, "") and self.is_credential_field(name.rsplit(".", 1)[-1]):
+                    exposures.append(Exposure("plain_http_body", name, len(str(value))))
+
+        return exposures
+
+    def _rows(self, sql: str, args: tuple) -> list:
+        with DatabaseManager()._get_connection() as conn:
+            return conn.execute(sql, args).fetchall()
+
+    def scan_new(self) -> dict:
+        """Check requests captured since the last scan and alert. The first scan starts from now."""
+        if not self.enabled:
+            return {"checked": 0, "exposures": 0, "alerts": 0}
+
+        latest = self._rows("SELECT COALESCE(MAX(rowid), 0) FROM traffic", ())[0][0]
+        start = self.state.get("traffic_rowid")
+        if start is None or start > latest:
+            start = latest
+
+        rows = self._rows(
+            "SELECT rowid, timestamp, device_id, device_ip, host, url, protocol, "
+            "request_headers, request_body, request_body_type FROM traffic "
+            "WHERE rowid > ? ORDER BY rowid LIMIT ?",
+            (start, SCAN_BATCH)
+        )
+
+        found = 0
+        alerts = 0
+        engine = None
+        for row in rows:
+            exposures = self.find_exposures(
+                row["url"], row["protocol"], row["request_headers"],
+                row["request_body"], row["request_body_type"]
+            )
+            found += len(exposures)
+            if exposures:
+                engine = engine or AlertEngine()
+                alerts += self._raise_alerts(engine, row, exposures)
+
+        self.state["traffic_rowid"] = rows[-1]["rowid"] if rows else start
+        self._save_state()
+        return {"checked": len(rows), "exposures": found, "alerts": alerts}
+
+    def _raise_alerts(self, engine: AlertEngine, row, exposures: List[Exposure]) -> int:
+        """Alert once per device/host/kind per ALERT_INTERVAL."""
+        now = utc_now()
+        alerted: Dict[str, str] = self.state.setdefault("alerted", {})
+        for key, at in list(alerted.items()):
+            if parse_timestamp(at, naive_tz=None) + ALERT_INTERVAL <= now:
+                del alerted[key]
+
+        raised = 0
+        device = row["device_id"] or row["device_ip"]
+        by_kind: Dict[str, List[Exposure]] = {}
+        for exposure in exposures:
+            by_kind.setdefault(exposure.kind, []).append(exposure)
+
+        for kind, items in by_kind.items():
+            key = f"{device}|{row['host']}|{kind}"
+            if key in alerted:
+                continue
+            alerted[key] = to_iso(now)
+
+            fields = ", ".join(sorted({e.field for e in items}))
+            plain = kind != "query_string"
+            parsed = urlsplit(row["url"])
+
+            engine.create_alert(
+                severity=AlertSeverity.HIGH if plain else AlertSeverity.MEDIUM,
+                category=AlertCategory.PRIVACY,
+                title=t("alert.credentials.title", host=row["host"]),
+                description=t(
+                    "alert.credentials.description_plain" if plain
+                    else "alert.credentials.description_query",
+                    device=device, fields=fields, host=row["host"],
+                    kind=t(f"credentials.kind.{kind}", default=KIND_LABELS[kind]),
+                ),
+                source_ip=row["device_ip"],
+                source_device=row["device_id"],
+                domain=row["host"],
+                # Path only: the query string holds the credential
+                url=f"{parsed.scheme}://{parsed.netloc}{parsed.path}",
+                metadata={
+                    "credential_exposure": True,
+                    "kind": kind,
+                    "fields": [{"field": e.field, "value": e.redacted} for e in items],
+                    "timestamp": row["timestamp"],
+                },
+            )
+            raised += 1
+
+        return raised
+
+    def report(self, hours: int, device_id: Optional[str] = None) -> List[dict]:
+        """Exposures in the last `hours`, per device and host (no alerts)."""
+        since = to_iso(utc_now() - timedelta(hours=hours))
+        rows = self._rows(
+            "SELECT timestamp, device_id, device_ip, host, url, protocol, "
+            "request_headers, request_body, request_body_type FROM traffic "
+            "WHERE timestamp >= ? AND (? IS NULL OR device_id = ?) "
+            "AND (url LIKE '%?%' OR protocol = 'http' OR url LIKE 'http://%')",
+            (since, device_id, device_id)
+        )
+
+        grouped: Dict[Tuple[str, str], dict] = {}
+        for row in rows:
+            exposures = self.find_exposures(
+                row["url"], row["protocol"], row["request_headers"],
+                row["request_body"], row["request_body_type"]
+            )
+            if not exposures:
+                continue
+
+            entry = grouped.setdefault((row["device_id"] or row["device_ip"], row["host"]), {
+                "device_id": row["device_id"],
+                "device_ip": row["device_ip"],
+                "host": row["host"],
+                "kinds": set(),
+                "fields": set(),
+                "requests": 0,
+                "first_seen": row["timestamp"],
+                "last_seen": row["timestamp"],
+            })
+            entry["requests"] += 1
+            entry["kinds"].update(e.kind for e in exposures)
+            entry["fields"].update(e.field for e in exposures)
+            entry["first_seen"] = min(entry["first_seen"], row["timestamp"])
+            entry["last_seen"] = max(entry["last_seen"], row["timestamp"])
+
+        results = []
+        for entry in grouped.values():
+            entry["kinds"] = sorted(entry["kinds"])
+            entry["fields"] = sorted(entry["fields"])
+            entry["plain_http"] = any(k != "query_string" for k in entry["kinds"])
+            results.append(entry)
+
+        results.sort(key=lambda e: (not e["plain_http"], -e["requests"]))
+        return results
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Plaintext credential exposure detection")
+    parser.add_argument("--action", choices=["scan", "report", "config", "set-config"], default="scan")
+    parser.add_argument("--hours", type=int, default=24, help="Report window")
+    parser.add_argument("--device", help="Only report this device")
+    parser.add_argument("--config", help="JSON config (set-config)")
+
+    args = parser.parse_args()
+
+    try:
+        detector = CredentialDetector()
+
+        if args.action == "scan":
+            output_json({"success": True, **detector.scan_new()})
+        elif args.action == "report":
+            output_json({"success": True, "exposures": detector.report(args.hours, args.device)})
+        elif args.action == "config":
+            output_json({"success": True, "config": detector.get_config()})
+        elif args.action == "set-config":
+            config = json.loads(args.config or "{}")
+            detector.set_config(
+                bool(config.get("enabled", True)),
+                [str(p) for p in config.get("field_patterns", DEFAULT_FIELD_PATTERNS)],
+            )
+            output_json({"success": True, "config": detector.get_config()})
+
+    except Exception as e:
+        output_json({"success": False, "error": str(e)})
@@ -0,0 +1,193 @@
+"""
+Connection-level (flow) logging.
+
+The proxy only records HTTP(S), so SSH sessions, game traffic, P2P and
+anything else that isn't web traffic would otherwise go unseen. Packets
+between a LAN device and the internet are grouped by their 5-tuple
+(device IP and port, remote IP and port, transport) into flows with
+packet and byte counts in each direction.
+
+A flow is written out once it has been idle for IDLE_TIMEOUT, once a TCP
+FIN or RST ends it, and every ACTIVE_TIMEOUT while it stays busy (as
+NetFlow does), so long sessions show up without waiting for them to end.
+The bypass detector, which already sees every forwarded packet, feeds it.
+"""
+
+import threading
+from dataclasses import dataclass
+from datetime import datetime, timedelta
+from typing import Dict, List, Optional, Tuple
+
+from ..utils.clock import to_iso, utc_now
+
+
+# A flow with no packets for this long has ended
+IDLE_TIMEOUT = timedelta(seconds=60)
+
+# A busy flow is written out (and carries on as a new record) this often
+ACTIVE_TIMEOUT = timedelta(minutes=5)
+
+# Flows kept in memory at most; the oldest are written out early past this
+MAX_FLOWS = 20000
+
+# Protocol for a (transport, server port); anything else is "other"
+FLOW_PORTS = {
+    ("tcp", 20): "ftp",
+    ("tcp", 21): "ftp",
+    ("tcp", 22): "ssh",
+    ("tcp", 23): "telnet",
+    ("tcp", 25): "smtp",
+    ("tcp", 465): "smtp",
+    ("tcp", 587): "smtp",
+    ("tcp", 53): "dns",
+    ("udp", 53): "dns",
+    ("tcp", 853): "dns",
+    ("tcp", 80): "http",
+    ("tcp", 443): "https",
+    ("udp", 443): "quic",
+    ("udp", 123): "ntp",
+    ("tcp", 143): "imap",
+    ("tcp", 993): "imap",
+    ("tcp", 110): "pop3",
+    ("tcp", 995): "pop3",
+    ("tcp", 3389): "rdp",
+    ("udp", 3389): "rdp",
+    ("tcp", 5900): "vnc",
+    ("tcp", 1194): "openvpn",
+    ("udp", 1194): "openvpn",
+    ("udp", 51820): "wireguard",
+    ("udp", 500): "ipsec",
+    ("udp", 4500): "ipsec",
+    ("udp", 3478): "stun",
+    ("udp", 19302): "stun",
+    ("tcp", 5222): "xmpp",
+    ("tcp", 1883): "mqtt",
+    ("tcp", 8883): "mqtt",
+    ("udp", 3074): "gaming",
+    ("tcp", 3074): "gaming",
+    ("udp", 27015): "gaming",
+    ("tcp", 27015): "gaming",
+    ("tcp", 25565): "gaming",
+}
+FLOW_PORTS.update({(transport, port): "bittorrent" for transport in ("tcp", "udp") for port in range(6881, 6890)})
+
+# (device IP, device port, remote IP, remote port, transport)
+FlowKey = Tuple[str, int, str, int, str]
+
+
+@dataclass
+class Flow:
+    """Packets exchanged over one 5-tuple."""
+    device_ip: str
+    device_port: int
+    remote_ip: str
+    remote_port: int
+    transport: str
+    protocol: str
+    start: datetime
+    end: datetime
+    packets: int = 0
+    bytes_in: int = 0
+    bytes_out: int = 0
+
+    def to_record(self) -> Dict:
+        """The row written to the flows table."""
+        return {
+            "start_time": to_iso(self.start),
+            "end_time": to_iso(self.end),
+            "device_ip": self.device_ip,
+            "device_port": self.device_port,
+            "remote_ip": self.remote_ip,
+            "remote_port": self.remote_port,
+            "transport": self.transport,
+            "protocol": self.protocol,
+            "packets": self.packets,
+            "bytes_in": self.bytes_in,
+            "bytes_out": self.bytes_out,
+            "duration_ms": int((self.end - self.start).total_seconds() * 1000),
+        }
+
+
+def classify_flow(transport: str, device_port: int, remote_port: int) -> str:
+    """Protocol of a flow from its ports; the remote (server) port is tried first."""
+    return (
+        FLOW_PORTS.get((transport, remote_port))
+        or FLOW_PORTS.get((transport, device_port))
+        or "other"
+    )
+
+
+class FlowTable:
+    """Flows in progress, and the ones finished since the last drain."""
+
+    def __init__(self):
+        self._active: Dict[FlowKey, Flow] = {}
+        self._finished: List[Flow] = []
+        self._lock = threading.Lock()
+
+    def add(
+        self,
+        device_ip: str,
+        device_port: int,
+        remote_ip: str,
+        remote_port: int,
+        transport: str,
+        outbound: bool,
+        size: int,
+        closing: bool = False,
+        now: Optional[datetime] = None
+    ):
+        """Count a packet; `closing` is set for a TCP FIN or RST."""
+        now = now or utc_now()
+        key = (device_ip, device_port, remote_ip, remote_port, transport)
+        with self._lock:
+            flow = self._active.get(key)
+            if flow is None:
+                if len(self._active) >= MAX_FLOWS:
+                    oldest = min(self._active, key=lambda k: self._active[k].end)
+                    self._finished.append(self._active.pop(oldest))
+                flow = Flow(
+                    device_ip=device_ip,
+                    device_port=device_port,
+                    remote_ip=remote_ip,
+                    remote_port=remote_port,
+                    transport=transport,
+                    protocol=classify_flow(transport, device_port, remote_port),
+                    start=now,
+                    end=now,
+                )
+                self._active[key] = flow
+            flow.end = now
+            flow.packets += 1
+            if outbound:
+                flow.bytes_out += size
+            else:
+                flow.bytes_in += size
+            if closing:
+                self._finished.append(self._active.pop(key))
+
+    def drain(self, now: Optional[datetime] = None, everything: bool = False) -> List[Dict]:
+        """
+        Records for flows that have ended, and for busy ones past the
+        active timeout (whose counters start over). `everything` ends
+        every flow, for shutdown.
+        """
+        now = now or utc_now()
+        with self._lock:
+            finished, self._finished = self._finished, []
+            for key, flow in list(self._active.items()):
+                if everything or now - flow.end >= IDLE_TIMEOUT:
+                    finished.append(self._active.pop(key))
+                elif now - flow.start >= ACTIVE_TIMEOUT:
+                    finished.append(flow)
+                    self._active[key] = Flow(
+                        device_ip=flow.device_ip,
+                        device_port=flow.device_port,
+                        remote_ip=flow.remote_ip,
+                        remote_port=flow.remote_port,
+                        transport=flow.transport,
+                        protocol=flow.protocol,
+                        start=now,
+                        end=now,
+                    )
+        return [flow.to_record() for flow in finished if flow.packets]
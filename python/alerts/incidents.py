@@ -0,0 +1,140 @@
+"""
+Alert Correlation into Incidents.
+
+Related alerts - same device, same category, each within a short window of
+the previous one - are grouped into a single incident so a burst of fifty
+keyword matches reads as one event with a timeline. Incidents are kept next
+to the stored alerts and pruned with them.
+"""
+
+import json
+from dataclasses import asdict, dataclass, field
+from datetime import timedelta
+from pathlib import Path
+from typing import List, Optional
+
+from ..database.retention import cutoffs as retention_cutoffs
+from ..utils.clock import parse_timestamp, to_utc_iso, utc_now_iso
+from ..utils.config import get_data_path
+
+
+# An alert joins an open incident if it arrives within this long of its last alert
+CORRELATION_WINDOW = timedelta(minutes=10)
+
+SEVERITY_ORDER = ["low", "medium", "high", "critical"]
+
+
+@dataclass
+class Incident:
+    """A group of correlated alerts."""
+    id: str
+    device: Optional[str]
+    category: str
+    severity: str
+    title: str
+    first_seen: str
+    last_seen: str
+    alert_ids: List[str] = field(default_factory=list)
+    status: str = "open"  # "open" or "resolved"
+    resolved_at: Optional[str] = None
+    escalated_by: Optional[str] = None  # Name of the escalation rule that made it critical
+    escalated_at: Optional[str] = None
+
+    @property
+    def alert_count(self) -> int:
+        return len(self.alert_ids)
+
+    def to_dict(self) -> dict:
+        data = asdict(self)
+        data["alert_count"] = self.alert_count
+        return data
+
+
+class IncidentStore:
+    """Correlates alerts into incidents and persists them."""
+
+    def __init__(self, incidents_file: Optional[Path] = None):
+        self.incidents_file = Path(incidents_file or get_data_path() / "incidents.json")
+        self.incidents: List[Incident] = []
+        self._load()
+
+    def _load(self):
+        if not self.incidents_file.exists():
+            return
+
+        try:
+            data = json.loads(self.incidents_file.read_text())
+            self.incidents = [Incident(**i) for i in data.get("incidents", [])]
+            # Incidents saved before UTC storage have naive local timestamps
+            for incident in self.incidents:
+                incident.first_seen = to_utc_iso(incident.first_seen)
+                incident.last_seen = to_utc_iso(incident.last_seen)
+                incident.resolved_at = to_utc_iso(incident.resolved_at)
+        except Exception as e:
+            print(json.dumps({"error": f"Failed to load incidents: {e}"}))
+
+    def save(self):
+        self.incidents_file.parent.mkdir(parents=True, exist_ok=True)
+
+        # Incidents go with their alerts, once the last one is past the alert retention window
+        cutoff = retention_cutoffs()["alerts"]
+        if cutoff:
+            self.incidents = [i for i in self.incidents if i.last_seen > cutoff]
+
+        self.incidents_file.write_text(json.dumps(
+            {"incidents": [asdict(i) for i in self.incidents]}, indent=2
+        ))
+
+    def correlate(self, alert) -> Incident:
+        """Attach an alert to a matching open incident, or open a new one. Does not save."""
+        device = alert.source_device or alert.source_ip
+        category = alert.category.value
+        severity = alert.severity.value
+        timestamp = parse_timestamp(alert.timestamp)
+
+        for incident in reversed(self.incidents):
+            if (
+                incident.status == "open"
+                and incident.device == device
+                and incident.category == category
+                and timestamp - parse_timestamp(incident.last_seen) <= CORRELATION_WINDOW
+            ):
+                incident.alert_ids.append(alert.id)
+                incident.last_seen = alert.timestamp
+                if SEVERITY_ORDER.index(severity) > SEVERITY_ORDER.index(incident.severity):
+                    incident.severity = severity
+                    incident.title = alert.title
+                return incident
+
+        incident = Incident(
+            id=f"incident_{timestamp.strftime('%Y%m%d_%H%M%S_%f')}",
+            device=device,
+            category=category,
+            severity=severity,
+            title=alert.title,
+            first_seen=alert.timestamp,
+            last_seen=alert.timestamp,
+            alert_ids=[alert.id],
+        )
+        self.incidents.append(incident)
+        return incident
+
+    def get(self, incident_id: str) -> Optional[Incident]:
+        return next((i for i in self.incidents if i.id == incident_id), None)
+
+    def list(self, status: Optional[str] = None, device: Optional[str] = None, limit: int = 100) -> List[Incident]:
+        """Incidents, most recently active first."""
+        incidents = [
+            i for i in self.incidents
+            if (status is None or i.status == status) and (device is None or i.device == device)
+        ]
+        incidents.sort(key=lambda i: i.last_seen, reverse=True)
+        return incidents[:limit]
+
+    def resolve(self, incident_id: str) -> Optional[Incident]:
+        """Mark an incident resolved; later alerts open a new one. Does not save."""
+        incident = self.get(incident_id)
+        if incident:
+            incident.status = "resolved"
+            incident.resolved_at = utc_now_iso()
+        return incident
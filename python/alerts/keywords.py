@@ -12,11 +12,13 @@ Provides advanced keyword matching for content alerts:
 import json
 import re
 from dataclasses import dataclass, field
-from datetime import datetime
 from enum import Enum
 from pathlib import Path
 from typing import Any, Dict, List, Optional, Set, Tuple
 
+from ..utils.clock import utc_now_iso
+from ..utils.config import get_config_path
+
 
 class MatchType(Enum):
     """Type of keyword match."""
@@ -43,7 +45,11 @@ class AlertCategory(Enum):
     SEXUAL = "sexual"
     PREDATOR = "predator"
     PERSONAL_INFO = "personal_info"
+    PRIVACY = "privacy"
     PROFANITY = "profanity"
+    NETWORK = "network"
+    SECURITY = "security"
+    UNBLOCK_REQUEST = "unblock_request"
     CUSTOM = "custom"
 
 
@@ -218,7 +224,7 @@ class KeywordMatch:
     context: str  # Surrounding text
     location: str  # Where it was found (url, body, etc.)
     severity: AlertSeverity
-    timestamp: str = field(default_factory=lambda: datetime.now().isoformat())
+    timestamp: str = field(default_factory=utc_now_iso)
 
 
 # Predefined keyword sets for common concerns
@@ -401,8 +407,7 @@ class KeywordMatcher:
             config_file: Path to keyword configuration file
         """
         if config_file is None:
-            project_root = Path(__file__).parent.parent.parent
-            config_file = project_root / "config" / "keywords.json"
+            config_file = get_config_path() / "keywords.json"
         
         self.config_file = Path(config_file)
         self.keywords: Dict[str, Keyword] = {}
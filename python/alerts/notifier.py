@@ -3,14 +3,17 @@ Desktop Notification System.
 
 Provides real-time notifications for alerts:
 - Windows toast notifications
-- Sound alerts
+- Sound alerts, per severity
+- Quiet hours, when only critical alerts get through
 - System tray integration
 - Email notifications (optional)
 """
 
 import json
 import os
+import shutil
 import subprocess
+import sys
 import threading
 import time
 from dataclasses import dataclass, field
@@ -19,6 +22,9 @@ from enum import Enum
 from pathlib import Path
 from typing import Any, Callable, Dict, List, Optional
 
+from ..utils.clock import local_now, utc_now_iso
+from ..utils.config import get_config_path
+from ..utils.secrets import get_secret
 from .keywords import AlertSeverity, AlertCategory
 
 
@@ -30,6 +36,31 @@ class NotificationType(Enum):
     WEBHOOK = "webhook"  # Webhook (for custom integrations)
 
 
+# Quiet hours set without explicit hours: 11pm to 7am
+DEFAULT_QUIET_START = 23
+DEFAULT_QUIET_END = 7
+
+# Sounds played when a severity has no sound file of its own
+WINDOWS_SOUNDS = {
+    AlertSeverity.CRITICAL: "SystemHand",
+    AlertSeverity.HIGH: "SystemExclamation",
+    AlertSeverity.MEDIUM: "SystemAsterisk",
+    AlertSeverity.LOW: "SystemAsterisk",
+}
+MAC_SOUNDS = {
+    AlertSeverity.CRITICAL: "/System/Library/Sounds/Sosumi.aiff",
+    AlertSeverity.HIGH: "/System/Library/Sounds/Basso.aiff",
+    AlertSeverity.MEDIUM: "/System/Library/Sounds/Glass.aiff",
+    AlertSeverity.LOW: "/System/Library/Sounds/Tink.aiff",
+}
+FREEDESKTOP_SOUNDS = {
+    AlertSeverity.CRITICAL: "dialog-error",
+    AlertSeverity.HIGH: "dialog-warning",
+    AlertSeverity.MEDIUM: "dialog-information",
+    AlertSeverity.LOW: "message",
+}
+
+
 @dataclass
 class NotificationConfig:
     """Configuration for notifications."""
@@ -47,6 +78,8 @@ class NotificationConfig:
     # Sound settings
     sound_file: Optional[str] = None
     sound_volume: float = 1.0
+    # Per-severity overrides: {"high": {"enabled": True, "sound": None, "volume": 1.0}}
+    severity_sounds: Dict[str, dict] = field(default_factory=dict)
     
     # Email settings
     email_address: Optional[str] = None
@@ -86,8 +119,7 @@ class Notifier:
             config_file: Path to notification configuration
         """
         if config_file is None:
-            project_root = Path(__file__).parent.parent.parent
-            config_file = project_root / "config" / "notifications.json"
+            config_file = get_config_path() / "notifications.json"
         
         self.config_file = Path(config_file)
         self.config = NotificationConfig()
@@ -101,6 +133,7 @@ class Notifier:
         self._queue: List[dict] = []
         self._sending = False
         self._send_thread: Optional[threading.Thread] = None
+        self._config_mtime: Optional[float] = None
         
         self._load_config()
     
@@ -110,6 +143,7 @@ class Notifier:
             return
         
         try:
+            self._config_mtime = self.config_file.stat().st_mtime
             data = json.loads(self.config_file.read_text())
             
             self.config = NotificationConfig(
@@ -121,12 +155,14 @@ class Notifier:
                 webhook_enabled=data.get("webhook_enabled", False),
                 sound_file=data.get("sound_file"),
                 sound_volume=data.get("sound_volume", 1.0),
+                severity_sounds=data.get("severity_sounds", {}),
                 email_address=data.get("email_address"),
                 smtp_server=data.get("smtp_server"),
                 smtp_port=data.get("smtp_port", 587),
                 smtp_user=data.get("smtp_user"),
-                smtp_password=data.get("smtp_password"),
-                webhook_url=data.get("webhook_url"),
+                # Kept in the OS keychain; the file only has them from older versions
+                smtp_password=data.get("smtp_password") or get_secret("smtp_password"),
+                webhook_url=data.get("webhook_url") or get_secret("webhook_url"),
                 webhook_headers=data.get("webhook_headers", {}),
                 cooldown_seconds=data.get("cooldown_seconds", 30),
                 max_per_hour=data.get("max_per_hour", 50),
@@ -150,12 +186,12 @@ class Notifier:
             "webhook_enabled": self.config.webhook_enabled,
             "sound_file": self.config.sound_file,
             "sound_volume": self.config.sound_volume,
+            "severity_sounds": self.config.severity_sounds,
             "email_address": self.config.email_address,
             "smtp_server": self.config.smtp_server,
             "smtp_port": self.config.smtp_port,
             "smtp_user": self.config.smtp_user,
-            # Don't save password in plain text
-            "webhook_url": self.config.webhook_url,
+            # The password and webhook URL stay in the OS keychain
             "webhook_headers": self.config.webhook_headers,
             "cooldown_seconds": self.config.cooldown_seconds,
             "max_per_hour": self.config.max_per_hour,
@@ -165,6 +201,16 @@ class Notifier:
         }
         
         self.config_file.write_text(json.dumps(data, indent=2))
+        self._config_mtime = self.config_file.stat().st_mtime
+    
+    def _reload_if_changed(self):
+        """Pick up settings changed from the app since this notifier loaded them."""
+        try:
+            mtime = self.config_file.stat().st_mtime
+        except OSError:
+            return
+        if mtime != self._config_mtime:
+            self._load_config()
     
     def notify(
         self,
@@ -189,6 +235,8 @@ class Notifier:
         Returns:
             True if notification was sent/queued
         """
+        # Capture processes keep one notifier for their whole run
+        self._reload_if_changed()
         if not self.config.enabled:
             return False
         
@@ -217,7 +265,7 @@ class Notifier:
             "message": message,
             "severity": severity,
             "category": category,
-            "timestamp": datetime.now().isoformat()
+            "timestamp": utc_now_iso()
         }
         
         if async_send:
@@ -227,21 +275,74 @@ class Notifier:
         else:
             return self._send_notification(notification)
     
-    def _is_quiet_hours(self) -> bool:
-        """Check if currently in quiet hours."""
+    def notify_alert(self, alert: dict) -> bool:
+        """Send a notification for an alert raised by the capture engine."""
+        try:
+            severity = AlertSeverity(alert.get("severity", "medium"))
+        except ValueError:
+            severity = AlertSeverity.MEDIUM
+        return self.notify(
+            title=alert.get("title", ""),
+            message=alert.get("description", ""),
+            severity=severity,
+            alert_id=alert.get("id"),
+        )
+    
+    def _is_quiet_hours(self, hour: Optional[int] = None) -> bool:
+        """Check if the hour (now by default) is in quiet hours."""
         if self.config.quiet_start_hour is None or self.config.quiet_end_hour is None:
             return False
         
-        current_hour = datetime.now().hour
+        current_hour = local_now().hour if hour is None else hour
         start = self.config.quiet_start_hour
         end = self.config.quiet_end_hour
         
         if start < end:
-            # Normal range (e.g., 22-07)
-            return start <= current_hour or current_hour < end
-        else:
-            # Overnight range
-            return start <= current_hour < 24 or 0 <= current_hour < end
+            # Same-day range (e.g., 13-15)
+            return start <= current_hour < end
+        elif start > end:
+            # Overnight range (e.g., 23-07)
+            return current_hour >= start or current_hour < end
+        return False
+    
+    def set_quiet_hours(self, start: Optional[int], end: Optional[int]) -> None:
+        """Set quiet hours, or turn them off with no hours."""
+        for hour in (start, end):
+            if hour is not None and not 0 <= hour <= 23:
+                raise ValueError(f"Quiet hours must be between 0 and 23, got {hour}")
+        if (start is None) != (end is None):
+            raise ValueError("Quiet hours need both a start and an end")
+        if start is not None and start == end:
+            raise ValueError("Quiet hours can't start and end at the same hour")
+        
+        self.config.quiet_start_hour = start
+        self.config.quiet_end_hour = end
+        self._save_config()
+    
+    def sound_for(self, severity: AlertSeverity) -> dict:
+        """The sound settings of a severity, falling back to the global ones."""
+        override = self.config.severity_sounds.get(severity.value, {})
+        return {
+            "enabled": self.config.sound_enabled and override.get("enabled", True),
+            "sound": override.get("sound") or self.config.sound_file,
+            "volume": override.get("volume", self.config.sound_volume),
+        }
+    
+    def set_severity_sound(self, severity: AlertSeverity, settings: dict) -> None:
+        """Set whether and how a severity sounds."""
+        volume = float(settings.get("volume", 1.0))
+        if not 0.0 <= volume <= 1.0:
+            raise ValueError(f"Volume must be between 0 and 1, got {volume}")
+        sound = settings.get("sound") or None
+        if sound and not os.path.isfile(sound):
+            raise ValueError(f"Sound file not found: {sound}")
+        
+        self.config.severity_sounds[severity.value] = {
+            "enabled": bool(settings.get("enabled", True)),
+            "sound": sound,
+            "volume": volume,
+        }
+        self._save_config()
     
     def _check_rate_limit(self, key: str) -> bool:
         """Check rate limiting for a notification."""
@@ -368,25 +469,45 @@ class Notifier:
         
         return False
     
-    def _play_sound(self, severity: AlertSeverity):
-        """Play alert sound."""
+    def _play_sound(self, severity: AlertSeverity, preview: bool = False) -> bool:
+        """Play the severity's sound; a preview plays it even when it's turned off."""
+        sound = self.sound_for(severity)
+        if not sound["enabled"] and not preview:
+            return False
+        
+        path = sound["sound"] if sound["sound"] and os.path.exists(sound["sound"]) else None
+        volume = max(0.0, min(1.0, float(sound["volume"])))
         try:
             if os.name == 'nt':
                 import winsound
                 
-                if self.config.sound_file and os.path.exists(self.config.sound_file):
-                    winsound.PlaySound(self.config.sound_file, winsound.SND_FILENAME | winsound.SND_ASYNC)
+                # winsound has no volume; the system mixer applies
+                if path:
+                    winsound.PlaySound(path, winsound.SND_FILENAME | winsound.SND_ASYNC)
                 else:
-                    # Use system sounds based on severity
-                    if severity == AlertSeverity.CRITICAL:
-                        winsound.PlaySound("SystemHand", winsound.SND_ALIAS | winsound.SND_ASYNC)
-                    elif severity == AlertSeverity.HIGH:
-                        winsound.PlaySound("SystemExclamation", winsound.SND_ALIAS | winsound.SND_ASYNC)
-                    else:
-                        winsound.PlaySound("SystemAsterisk", winsound.SND_ALIAS | winsound.SND_ASYNC)
+                    winsound.PlaySound(WINDOWS_SOUNDS[severity], winsound.SND_ALIAS | winsound.SND_ASYNC)
+                return True
+            
+            if sys.platform == "darwin":
+                subprocess.Popen(["afplay", "-v", str(volume), path or MAC_SOUNDS[severity]])
+                return True
+            
+            if path and shutil.which("paplay"):
+                subprocess.Popen(["paplay", f"--volume={int(volume * 65536)}", path])
+                return True
+            if shutil.which("canberra-gtk-play"):
+                subprocess.Popen(["canberra-gtk-play", "--id", FREEDESKTOP_SOUNDS[severity]])
+                return True
+        
+        except Exception as e:
+            print(json.dumps({"error": f"Alert sound failed: {e}"}))
         
-        except Exception:
-            pass
+        return False
+    
+    def preview_sound(self, severity: AlertSeverity) -> dict:
+        """Play a severity's sound now, regardless of quiet hours."""
+        sound = self.sound_for(severity)
+        return {**sound, "severity": severity.value, "played": self._play_sound(severity, preview=True)}
     
     def _send_email(
         self,
@@ -417,7 +538,7 @@ class Notifier:
 Network Monitor Alert
 ---------------------
 Severity: {severity.value.upper()}
-Time: {datetime.now().strftime('%Y-%m-%d %H:%M:%S')}
+Time: {local_now().strftime('%Y-%m-%d %H:%M:%S %Z')}
 
 {message}
             """
@@ -497,6 +618,7 @@ Time: {datetime.now().strftime('%Y-%m-%d %H:%M:%S')}
             "email_enabled": self.config.email_enabled,
             "webhook_enabled": self.config.webhook_enabled,
             "min_severity": self.config.min_severity.value,
+            "sounds": {severity.value: self.sound_for(severity) for severity in AlertSeverity},
             "quiet_hours": {
                 "start": self.config.quiet_start_hour,
                 "end": self.config.quiet_end_hour,
@@ -518,7 +640,8 @@ def main():
     
     parser = argparse.ArgumentParser(description="Desktop notifications")
     parser.add_argument("--action", choices=[
-        "test", "status", "send", "enable", "disable", "configure"
+        "test", "status", "send", "enable", "disable", "configure",
+        "quiet-hours", "sound", "preview-sound"
     ], default="status", help="Action to perform")
     parser.add_argument("--title", help="Notification title")
     parser.add_argument("--message", help="Notification message")
@@ -526,6 +649,9 @@ def main():
     parser.add_argument("--channel", choices=["toast", "sound", "email", "webhook"],
                        help="Channel to enable/disable")
     parser.add_argument("--value", help="Configuration value")
+    parser.add_argument("--start", type=int, help="Hour quiet hours start (0-23)")
+    parser.add_argument("--end", type=int, help="Hour quiet hours end (0-23)")
+    parser.add_argument("--enabled", choices=["true", "false"], default="true", help="Turn quiet hours on or off")
     
     args = parser.parse_args()
     
@@ -583,6 +709,22 @@ def main():
             
             output_json({"success": True, "action": "disabled", "channel": args.channel})
         
+        elif args.action == "quiet-hours":
+            if args.enabled == "false":
+                notifier.set_quiet_hours(None, None)
+            else:
+                start = DEFAULT_QUIET_START if args.start is None else args.start
+                end = DEFAULT_QUIET_END if args.end is None else args.end
+                notifier.set_quiet_hours(start, end)
+            output_json({"success": True, "status": notifier.get_status()})
+        
+        elif args.action == "sound":
+            notifier.set_severity_sound(AlertSeverity(args.severity), json.loads(args.value or "{}"))
+            output_json({"success": True, "status": notifier.get_status()})
+        
+        elif args.action == "preview-sound":
+            output_json({"success": True, "preview": notifier.preview_sound(AlertSeverity(args.severity))})
+        
         elif args.action == "configure":
             output_json({
                 "success": True,
@@ -0,0 +1,348 @@
+"""
+SIEM Forwarder
+Ships alerts and traffic summaries to a syslog collector as RFC 5424 syslog
+or CEF (carried in an RFC 5424 envelope), over UDP, TCP or TLS.
+
+Runs as a long-lived process fed by the Tauri backend over stdin:
+
+    {"action": "alert", "alert": {...}}
+    {"action": "traffic_summary", "summary": {...}}
+    {"action": "status"}   -> one JSON status line on stdout
+    {"action": "stop"}
+
+Messages are queued in a bounded buffer and sent from a background thread;
+while the collector is unreachable they stay queued (oldest dropped first
+once the buffer is full) and go out once the connection comes back.
+"""
+
+import json
+import socket
+import ssl
+import sys
+import threading
+import time
+from collections import deque
+from datetime import datetime, timezone
+from typing import Any, Dict, Optional
+
+APP_NAME = "network-monitor"
+VENDOR = "NetworkMonitor"
+PRODUCT = "Network Monitor"
+VERSION = "1.0"
+
+# Private enterprise number reserved for documentation (RFC 5612)
+SD_ENTERPRISE = 32473
+
+# local0
+FACILITY = 16
+
+# Syslog severities (RFC 5424) and CEF severities (0-10) for alert levels
+SYSLOG_SEVERITY = {"critical": 2, "high": 3, "medium": 4, "low": 5}
+CEF_SEVERITY = {"critical": 10, "high": 8, "medium": 5, "low": 3}
+INFO_SEVERITY = 6
+
+MAX_BACKOFF = 60
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+def _sd_escape(value: Any) -> str:
+    return str(value).replace("\\", "\\\\").replace('"', '\\"').replace("]", "\\]")
+
+
+def _cef_header_escape(value: Any) -> str:
+    return str(value).replace("\\", "\\\\").replace("|", "\\|")
+
+
+def _cef_ext_escape(value: Any) -> str:
+    return (
+        str(value)
+        .replace("\\", "\\\\")
+        .replace("=", "\\=")
+        .replace("\r", "")
+        .replace("\n", "\\n")
+    )
+
+
+def _timestamp(value: Optional[str] = None) -> datetime:
+    """Parse an ISO timestamp (or now) into aware UTC; naive ones are local time."""
+    try:
+        dt = datetime.fromisoformat(value) if value else datetime.now()
+    except ValueError:
+        dt = datetime.now()
+    if dt.tzinfo is None:
+        dt = dt.astimezone()
+    return dt.astimezone(timezone.utc)
+
+
+class MessageFormatter:
+    """Formats alerts and traffic summaries as RFC 5424 or CEF syslog lines."""
+
+    def __init__(self, fmt: str = "rfc5424"):
+        self.fmt = fmt
+        self.hostname = socket.gethostname() or "-"
+
+    def _envelope(self, severity: int, msg_id: str, when: datetime, sd: str, msg: str) -> str:
+        pri = FACILITY * 8 + severity
+        ts = when.isoformat(timespec="milliseconds").replace("+00:00", "Z")
+        return f"<{pri}>1 {ts} {self.hostname} {APP_NAME} - {msg_id} {sd} {msg}"
+
+    def _cef(self, signature: str, name: str, severity: int, extensions: Dict[str, Any]) -> str:
+        ext = " ".join(
+            f"{key}={_cef_ext_escape(value)}"
+            for key, value in extensions.items()
+            if value not in (None, "")
+        )
+        return "CEF:0|{}|{}|{}|{}|{}|{}|{}".format(
+            _cef_header_escape(VENDOR),
+            _cef_header_escape(PRODUCT),
+            _cef_header_escape(VERSION),
+            _cef_header_escape(signature),
+            _cef_header_escape(name),
+            severity,
+            ext,
+        )
+
+    def alert(self, alert: Dict[str, Any]) -> str:
+        level = alert.get("severity", "low")
+        when = _timestamp(alert.get("timestamp"))
+        title = alert.get("title", "")
+        description = alert.get("description", "")
+
+        if self.fmt == "cef":
+            msg = self._cef(alert.get("category", "alert"), title, CEF_SEVERITY.get(level, 3), {
+                "rt": int(when.timestamp() * 1000),
+                "cat": alert.get("category"),
+                "src": alert.get("source_ip"),
+                "shost": alert.get("source_device"),
+                "dhost": alert.get("domain"),
+                "request": alert.get("url"),
+                "msg": description,
+                "cs1Label": "alertId",
+                "cs1": alert.get("id"),
+            })
+            return self._envelope(SYSLOG_SEVERITY.get(level, 5), "ALERT", when, "-", msg)
+
+        params = {
+            "id": alert.get("id"),
+            "severity": level,
+            "category": alert.get("category"),
+            "device": alert.get("source_device"),
+            "src": alert.get("source_ip"),
+            "domain": alert.get("domain"),
+        }
+        sd = "[alert@{} {}]".format(
+            SD_ENTERPRISE,
+            " ".join(f'{k}="{_sd_escape(v)}"' for k, v in params.items() if v),
+        )
+        msg = f"{title} - {description}" if description else title
+        return self._envelope(SYSLOG_SEVERITY.get(level, 5), "ALERT", when, sd, msg)
+
+    def traffic_summary(self, summary: Dict[str, Any]) -> str:
+        when = _timestamp(summary.get("end"))
+
+        if self.fmt == "cef":
+            msg = self._cef("traffic_summary", "Traffic summary", 1, {
+                "rt": int(when.timestamp() * 1000),
+                "src": summary.get("device_ip"),
+                "shost": summary.get("device_id"),
+                "cnt": summary.get("requests"),
+                "out": summary.get("bytes_out"),
+                "in": summary.get("bytes_in"),
+                "cn1Label": "blockedRequests",
+                "cn1": summary.get("blocked"),
+                "start": summary.get("start"),
+                "end": summary.get("end"),
+            })
+            return self._envelope(INFO_SEVERITY, "TRAFFIC", when, "-", msg)
+
+        keys = ("device_id", "device_ip", "requests", "blocked", "bytes_in", "bytes_out", "start", "end")
+        sd = "[traffic@{} {}]".format(
+            SD_ENTERPRISE,
+            " ".join(f'{k}="{_sd_escape(summary[k])}"' for k in keys if summary.get(k) is not None),
+        )
+        msg = "{} requests ({} blocked) from {}".format(
+            summary.get("requests", 0), summary.get("blocked", 0), summary.get("device_ip", "?")
+        )
+        return self._envelope(INFO_SEVERITY, "TRAFFIC", when, sd, msg)
+
+
+class SiemForwarder:
+    """Buffers formatted messages and sends them to the collector."""
+
+    def __init__(
+        self,
+        host: str,
+        port: int = 514,
+        transport: str = "udp",
+        fmt: str = "rfc5424",
+        verify_tls: bool = True,
+        buffer_size: int = 10000
+    ):
+        self.host = host
+        self.port = port
+        self.transport = transport
+        self.verify_tls = verify_tls
+        self.formatter = MessageFormatter(fmt)
+
+        self.buffer: deque = deque()
+        self.buffer_size = buffer_size
+        self.lock = threading.Condition()
+        self.sock: Optional[socket.socket] = None
+
+        self.running = False
+        self.connected = False
+        self.sent = 0
+        self.dropped = 0
+        self.last_error: Optional[str] = None
+        self.last_sent_at: Optional[str] = None
+
+    def start(self):
+        self.running = True
+        threading.Thread(target=self._send_loop, daemon=True).start()
+
+    def stop(self):
+        with self.lock:
+            self.running = False
+            self.lock.notify_all()
+        self._close()
+
+    def enqueue(self, message: str):
+        with self.lock:
+            if len(self.buffer) >= self.buffer_size:
+                self.buffer.popleft()
+                self.dropped += 1
+            self.buffer.append(message)
+            self.lock.notify()
+
+    def status(self) -> Dict[str, Any]:
+        with self.lock:
+            buffered = len(self.buffer)
+        return {
+            "connected": self.connected,
+            "buffered": buffered,
+            "sent": self.sent,
+            "dropped": self.dropped,
+            "last_error": self.last_error,
+            "last_sent_at": self.last_sent_at,
+        }
+
+    def _connect(self):
+        if self.transport == "udp":
+            self.sock = socket.socket(socket.AF_INET, socket.SOCK_DGRAM)
+            self.sock.connect((self.host, self.port))
+            return
+
+        sock = socket.create_connection((self.host, self.port), timeout=10)
+        if self.transport == "tls":
+            context = ssl.create_default_context()
+            if not self.verify_tls:
+                context.check_hostname = False
+                context.verify_mode = ssl.CERT_NONE
+            sock = context.wrap_socket(sock, server_hostname=self.host)
+        self.sock = sock
+
+    def _close(self):
+        if self.sock:
+            try:
+                self.sock.close()
+            except OSError:
+                pass
+        self.sock = None
+        self.connected = False
+
+    def _send(self, message: str):
+        data = message.encode("utf-8")
+        if self.transport == "udp":
+            self.sock.send(data)
+        else:
+            # Octet-counting framing (RFC 6587 / RFC 5425)
+            self.sock.sendall(f"{len(data)} ".encode("ascii") + data)
+
+    def _send_loop(self):
+        backoff = 1
+        while True:
+            with self.lock:
+                while self.running and not self.buffer:
+                    self.lock.wait()
+                if not self.running:
+                    return
+                message = self.buffer[0]
+
+            try:
+                if not self.sock:
+                    self._connect()
+                self._send(message)
+            except (OSError, ssl.SSLError) as e:
+                # Keep the message queued and retry with backoff
+                self.last_error = str(e)
+                self._close()
+                time.sleep(backoff)
+                backoff = min(backoff * 2, MAX_BACKOFF)
+                continue
+
+            backoff = 1
+            self.connected = True
+            self.sent += 1
+            self.last_sent_at = datetime.now(timezone.utc).isoformat()
+            with self.lock:
+                if self.buffer and self.buffer[0] is message:
+                    self.buffer.popleft()
+
+
+def main():
+    """CLI entry point for the SIEM forwarder."""
+    import argparse
+
+    parser = argparse.ArgumentParser(description="SIEM syslog/CEF forwarder")
+    parser.add_argument("--host", required=True, help="Collector host")
+    parser.add_argument("--port", type=int, default=514, help="Collector port")
+    parser.add_argument("--transport", choices=["udp", "tcp", "tls"], default="udp")
+    parser.add_argument("--format", choices=["rfc5424", "cef"], default="rfc5424")
+    parser.add_argument("--no-verify", action="store_true", help="Skip TLS certificate verification")
+    parser.add_argument("--buffer-size", type=int, default=10000, help="Messages kept during outages")
+
+    args = parser.parse_args()
+
+    forwarder = SiemForwarder(
+        host=args.host,
+        port=args.port,
+        transport=args.transport,
+        fmt=args.format,
+        verify_tls=not args.no_verify,
+        buffer_size=args.buffer_size
+    )
+    forwarder.start()
+
+    try:
+        while True:
+            line = sys.stdin.readline()
+            if not line:
+                break
+
+            try:
+                cmd = json.loads(line)
+            except json.JSONDecodeError:
+                continue
+
+            action = cmd.get("action")
+            if action == "stop":
+                break
+            elif action == "alert":
+                forwarder.enqueue(forwarder.formatter.alert(cmd.get("alert", {})))
+            elif action == "traffic_summary":
+                forwarder.enqueue(forwarder.formatter.traffic_summary(cmd.get("summary", {})))
+            elif action == "status":
+                output_json(forwarder.status())
+
+    except KeyboardInterrupt:
+        pass
+    finally:
+        forwarder.stop()
+
+
+if __name__ == "__main__":
+    main()
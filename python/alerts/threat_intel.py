@@ -0,0 +1,459 @@
+"""
+Threat Intelligence Feed Matching.
+
+Downloads indicator-of-compromise feeds (malicious domains and IPs, e.g.
+URLhaus and abuse.ch), keeps them updated, and matches them against
+captured traffic and DNS lookups:
+- "scan" checks entries captured since the last scan (run periodically)
+- "rescan" checks the stored history, including rolled-up traffic
+
+Matches raise high-severity alerts naming the feeds that list the indicator.
+
+Feeds are configured per workspace in threat_feeds.json; downloaded
+indicator lists are shared in config/threat_intel/.
+"""
+
+import ipaddress
+import json
+import re
+import sys
+import urllib.request
+from dataclasses import asdict, dataclass
+from datetime import timedelta
+from pathlib import Path
+from typing import Dict, Iterable, List, Optional, Set, Tuple
+from urllib.parse import urlsplit
+
+from .alert_engine import AlertEngine
+from .keywords import AlertCategory, AlertSeverity
+from ..database.db_manager import DatabaseManager
+from ..utils.clock import parse_timestamp, to_iso, utc_now, utc_now_iso
+from ..utils.config import get_config_path, get_data_path
+from ..utils.i18n import t
+
+
+PROJECT_ROOT = Path(__file__).parent.parent.parent
+FEEDS_DIR = PROJECT_ROOT / "config" / "threat_intel"
+
+# Re-alert for the same device/indicator at most this often
+ALERT_INTERVAL = timedelta(hours=24)
+
+# Rows checked per scan, so a backlog can't stall the caller
+SCAN_BATCH = 5000
+
+DOMAIN_RE = re.compile(r"^(?=.{1,253}$)([a-z0-9_]([a-z0-9_-]{0,61}[a-z0-9])?\.)+[a-z0-9-]{2,63}$")
+
+# Hosts-file addresses that sink a name rather than being an indicator
+SINK_ADDRESSES = {"0.0.0.0", "127.0.0.1", "::", "::1"}
+
+
+@dataclass
+class ThreatFeed:
+    """A downloadable indicator list."""
+    id: str
+    name: str
+    url: str
+    enabled: bool = True
+    update_hours: int = 6
+
+
+DEFAULT_FEEDS = [
+    ThreatFeed(
+        id="urlhaus",
+        name="URLhaus (abuse.ch)",
+        url="https://urlhaus.abuse.ch/downloads/hostfile/",
+    ),
+    ThreatFeed(
+        id="threatfox",
+        name="ThreatFox (abuse.ch)",
+        url="https://threatfox.abuse.ch/downloads/hostfile/",
+    ),
+    ThreatFeed(
+        id="feodo",
+        name="Feodo Tracker botnet C2 (abuse.ch)",
+        url="https://feodotracker.abuse.ch/downloads/ipblocklist.txt",
+    ),
+]
+
+
+@dataclass
+class ThreatMatch:
+    """An indicator seen in captured traffic."""
+    indicator: str
+    indicator_type: str  # "domain" or "ip"
+    feeds: List[str]
+    device_id: Optional[str]
+    device_ip: str
+    host: str
+    timestamp: str
+    source: str  # "traffic", "dns", "aggregate"
+    url: Optional[str] = None
+
+
+def parse_indicators(text: str) -> Tuple[Set[str], Set[str]]:
+    """
+    Parse a feed into (domains, ips).
+
+    Accepts plain lists, hosts files and URL lists; "#" and "!" start comments.
+    """
+    domains: Set[str] = set()
+    ips: Set[str] = set()
+
+    for raw in text.splitlines():
+        line = raw.split("#", 1)[0].strip()
+        if not line or line.startswith("!"):
+            continue
+
+        for token in line.split():
+            if token in SINK_ADDRESSES:
+                continue
+            if "://" in token:
+                token = urlsplit(token).hostname or ""
+            token = token.lower().rstrip(".")
+            # "1.2.3.4:443" style entries
+            if token.count(":") == 1:
+                token = token.split(":", 1)[0]
+
+            try:
+                ipaddress.ip_address(token)
+                ips.add(token)
+                continue
+            except ValueError:
+                pass
+            if DOMAIN_RE.match(token):
+                domains.add(token)
+
+    return domains, ips
+
+
+class ThreatIntel:
+    """Threat feed store and matcher."""
+
+    def __init__(self):
+        self.config_file = get_config_path() / "threat_feeds.json"
+        self.state_file = get_data_path() / "threat_intel_state.json"
+        self.feeds: List[ThreatFeed] = []
+        # indicator -> feed IDs listing it
+        self.domains: Dict[str, Set[str]] = {}
+        self.ips: Dict[str, Set[str]] = {}
+        self.state: dict = {"traffic_rowid": None, "dns_rowid": None, "alerted": {}}
+        self.db = DatabaseManager()
+        self.alert_engine = AlertEngine()
+
+        self._load_config()
+        self._load_state()
+        self._load_indicators()
+
+    def _load_config(self):
+        """Load the feed list, writing the defaults on first use."""
+        if self.config_file.exists():
+            try:
+                data = json.loads(self.config_file.read_text())
+                self.feeds = [ThreatFeed(**feed) for feed in data.get("feeds", [])]
+                return
+            except Exception as e:
+                print(json.dumps({"error": f"Failed to load threat feeds: {e}"}), file=sys.stderr, flush=True)
+
+        self.feeds = list(DEFAULT_FEEDS)
+        self._save_config()
+
+    def _save_config(self):
+        self.config_file.parent.mkdir(parents=True, exist_ok=True)
+        self.config_file.write_text(json.dumps({"feeds": [asdict(f) for f in self.feeds]}, indent=2))
+
+    def _load_state(self):
+        if self.state_file.exists():
+            try:
+                self.state.update(json.loads(self.state_file.read_text()))
+            except Exception:
+                pass
+
+    def _save_state(self):
+        self.state_file.parent.mkdir(parents=True, exist_ok=True)
+        self.state_file.write_text(json.dumps(self.state, indent=2))
+
+    def _feed_file(self, feed: ThreatFeed) -> Path:
+        return FEEDS_DIR / f"{feed.id}.json"
+
+    def _read_feed(self, feed: ThreatFeed) -> dict:
+        path = self._feed_file(feed)
+        if not path.exists():
+            return {}
+        try:
+            return json.loads(path.read_text())
+        except Exception:
+            return {}
+
+    def _load_indicators(self):
+        """Index the downloaded indicators of every enabled feed."""
+        self.domains.clear()
+        self.ips.clear()
+
+        for feed in self.feeds:
+            if not feed.enabled:
+                continue
+            data = self._read_feed(feed)
+            for domain in data.get("domains", []):
+                self.domains.setdefault(domain, set()).add(feed.id)
+            for ip in data.get("ips", []):
+                self.ips.setdefault(ip, set()).add(feed.id)
+
+    # Feed updates
+    def update_feed(self, feed: ThreatFeed) -> dict:
+        """Download one feed. On failure the previous download is kept."""
+        data = self._read_feed(feed)
+        try:
+            request = urllib.request.Request(feed.url, headers={"User-Agent": "network-monitor"})
+            with urllib.request.urlopen(request, timeout=60) as response:
+                text = response.read().decode("utf-8", errors="replace")
+
+            domains, ips = parse_indicators(text)
+            data = {
+                "updated_at": utc_now_iso(),
+                "domains": sorted(domains),
+                "ips": sorted(ips),
+            }
+        except Exception as e:
+            data["error"] = str(e)
+            data["failed_at"] = utc_now_iso()
+
+        FEEDS_DIR.mkdir(parents=True, exist_ok=True)
+        self._feed_file(feed).write_text(json.dumps(data))
+        return data
+
+    def update(self, force: bool = False) -> List[dict]:
+        """Download the enabled feeds that are due (or all of them, with force)."""
+        results = []
+        for feed in self.feeds:
+            if not feed.enabled:
+                continue
+
+            data = self._read_feed(feed)
+            last_attempt = max(data.get("updated_at", ""), data.get("failed_at", ""))
+            due = not last_attempt or (
+                parse_timestamp(last_attempt, naive_tz=None) + timedelta(hours=feed.update_hours) <= utc_now()
+            )
+            if not (force or due):
+                continue
+
+            data = self.update_feed(feed)
+            results.append({
+                "id": feed.id,
+                "indicators": len(data.get("domains", [])) + len(data.get("ips", [])),
+                "error": data.get("error") if data.get("failed_at", "") > data.get("updated_at", "") else None,
+            })
+
+        if results:
+            self._load_indicators()
+        return results
+
+    def get_feeds(self) -> List[dict]:
+        """Feeds with their download status."""
+        feeds = []
+        for feed in self.feeds:
+            data = self._read_feed(feed)
+            failed = data.get("failed_at", "") > data.get("updated_at", "")
+            feeds.append({
+                **asdict(feed),
+                "updated_at": data.get("updated_at"),
+                "domains": len(data.get("domains", [])),
+                "ips": len(data.get("ips", [])),
+                "error": data.get("error") if failed else None,
+            })
+        return feeds
+
+    # Matching
+    def match_host(self, host: str) -> Optional[Tuple[str, str, List[str]]]:
+        """Match a host name or IP against the indicators: (indicator, type, feeds)."""
+        host = (host or "").lower().rstrip(".")
+        if not host:
+            return None
+
+        try:
+            ipaddress.ip_address(host)
+            feeds = self.ips.get(host)
+            return (host, "ip", sorted(feeds)) if feeds else None
+        except ValueError:
+            pass
+
+        # The domain itself or any parent domain
+        labels = host.split(".")
+        for i in range(len(labels) - 1):
+            candidate = ".".join(labels[i:])
+            feeds = self.domains.get(candidate)
+            if feeds:
+                return candidate, "domain", sorted(feeds)
+        return None
+
+    def _traffic_matches(self, rows: Iterable, source: str) -> List[ThreatMatch]:
+        matches = []
+        for row in rows:
+            found = self.match_host(row["host"])
+            if found:
+                indicator, kind, feeds = found
+                matches.append(ThreatMatch(
+                    indicator=indicator, indicator_type=kind, feeds=feeds,
+                    device_id=row["device_id"], device_ip=row["device_ip"] or "",
+                    host=row["host"], timestamp=row["timestamp"], source=source,
+                    url=row["url"] if "url" in row.keys() else None,
+                ))
+        return matches
+
+    def _dns_matches(self, rows: Iterable) -> List[ThreatMatch]:
+        matches = []
+        for row in rows:
+            found = self.match_host(row["query_name"]) or self.match_host(row["response_ip"])
+            if found:
+                indicator, kind, feeds = found
+                matches.append(ThreatMatch(
+                    indicator=indicator, indicator_type=kind, feeds=feeds,
+                    device_id=row["device_id"], device_ip=row["device_ip"] or "",
+                    host=row["query_name"], timestamp=row["timestamp"], source="dns",
+                ))
+        return matches
+
+    def scan_new(self) -> dict:
+        """Match entries captured since the last scan. The first scan starts from now."""
+        with self.db._get_connection() as conn:
+            latest_traffic = conn.execute("SELECT COALESCE(MAX(rowid), 0) FROM traffic").fetchone()[0]
+            latest_dns = conn.execute("SELECT COALESCE(MAX(rowid), 0) FROM dns_queries").fetchone()[0]
+
+            traffic_from = self.state.get("traffic_rowid")
+            dns_from = self.state.get("dns_rowid")
+            if traffic_from is None or traffic_from > latest_traffic:
+                traffic_from = latest_traffic
+            if dns_from is None or dns_from > latest_dns:
+                dns_from = latest_dns
+
+            traffic = conn.execute(
+                "SELECT rowid, timestamp, device_id, device_ip, host, url FROM traffic "
+                "WHERE rowid > ? ORDER BY rowid LIMIT ?",
+                (traffic_from, SCAN_BATCH)
+            ).fetchall()
+            dns = conn.execute(
+                "SELECT rowid, timestamp, device_id, device_ip, query_name, response_ip FROM dns_queries "
+                "WHERE rowid > ? ORDER BY rowid LIMIT ?",
+                (dns_from, SCAN_BATCH)
+            ).fetchall()
+
+        matches = self._traffic_matches(traffic, "traffic") + self._dns_matches(dns)
+        alerts = self._raise_alerts(matches)
+
+        self.state["traffic_rowid"] = traffic[-1]["rowid"] if traffic else traffic_from
+        self.state["dns_rowid"] = dns[-1]["rowid"] if dns else dns_from
+        self._save_state()
+
+        return {"checked": len(traffic) + len(dns), "matches": len(matches), "alerts": alerts}
+
+    def rescan_history(self, days: int) -> dict:
+        """Match stored history (raw, rolled-up and DNS) from the last `days` days."""
+        since = to_iso(utc_now() - timedelta(days=days))
+
+        with self.db._get_connection() as conn:
+            traffic = conn.execute(
+                "SELECT MIN(timestamp) AS timestamp, device_id, device_ip, host, MIN(url) AS url "
+                "FROM traffic WHERE timestamp >= ? GROUP BY device_id, device_ip, host",
+                (since,)
+            ).fetchall()
+            dns = conn.execute(
+                "SELECT MIN(timestamp) AS timestamp, device_id, device_ip, query_name, response_ip "
+                "FROM dns_queries WHERE timestamp >= ? GROUP BY device_id, device_ip, query_name, response_ip",
+                (since,)
+            ).fetchall()
+            try:
+                aggregates = conn.execute(
+                    "SELECT MIN(minute) AS timestamp, device_id, NULL AS device_ip, host "
+                    "FROM traffic_aggregates WHERE minute >= ? GROUP BY device_id, host",
+                    (since[:16],)
+                ).fetchall()
+            except Exception:
+                aggregates = []
+
+        matches = (
+            self._traffic_matches(traffic, "traffic")
+            + self._traffic_matches(aggregates, "aggregate")
+            + self._dns_matches(dns)
+        )
+        alerts = self._raise_alerts(matches, historical=True)
+        self._save_state()
+
+        return {
+            "days": days,
+            "checked": len(traffic) + len(aggregates) + len(dns),
+            "matches": [asdict(m) for m in matches[:500]],
+            "match_count": len(matches),
+            "alerts": alerts,
+        }
+
+    def _raise_alerts(self, matches: List[ThreatMatch], historical: bool = False) -> int:
+        """Alert once per device/indicator per ALERT_INTERVAL."""
+        now = utc_now()
+        alerted: Dict[str, str] = self.state.setdefault("alerted", {})
+        names = {feed.id: feed.name for feed in self.feeds}
+        raised = 0
+
+        # Forget alerts old enough to repeat
+        for key, at in list(alerted.items()):
+            if parse_timestamp(at, naive_tz=None) + ALERT_INTERVAL <= now:
+                del alerted[key]
+
+        for match in matches:
+            key = f"{match.device_id or match.device_ip}|{match.indicator}"
+            if key in alerted:
+                continue
+            alerted[key] = to_iso(now)
+
+            feed_names = ", ".join(names.get(f, f) for f in match.feeds)
+            device = match.device_id or match.device_ip or t("common.a_device")
+            kind = "domain" if match.indicator_type == "domain" else "ip"
+
+            self.alert_engine.create_alert(
+                severity=AlertSeverity.HIGH,
+                category=AlertCategory.SECURITY,
+                title=t(f"alert.threat_intel.title_{kind}", indicator=match.indicator),
+                description=t(
+                    "alert.threat_intel.description_historical" if historical
+                    else "alert.threat_intel.description",
+                    device=device, host=match.host, timestamp=match.timestamp, feeds=feed_names,
+                ),
+                source_ip=match.device_ip or None,
+                source_device=match.device_id,
+                domain=match.host,
+                url=match.url,
+                metadata={"threat_intel": True, "historical": historical, **asdict(match)},
+            )
+            raised += 1
+
+        return raised
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Threat intelligence feed matching")
+    parser.add_argument("--action", choices=["feeds", "update", "scan", "rescan"], default="feeds")
+    parser.add_argument("--force", action="store_true", help="Update feeds even if not due")
+    parser.add_argument("--days", type=int, default=30, help="History to rescan, in days")
+
+    args = parser.parse_args()
+
+    try:
+        intel = ThreatIntel()
+
+        if args.action == "feeds":
+            output_json({"success": True, "feeds": intel.get_feeds()})
+        elif args.action == "update":
+            output_json({"success": True, "updated": intel.update(force=args.force)})
+        elif args.action == "scan":
+            output_json({"success": True, **intel.scan_new()})
+        elif args.action == "rescan":
+            output_json({"success": True, **intel.rescan_history(args.days)})
+
+    except Exception as e:
+        output_json({"success": False, "error": str(e)})
@@ -0,0 +1,320 @@
+"""
+Keyword Watch Lists.
+
+Watch-only keyword lists (self-harm terms, for example): a match raises an
+alert and is recorded with where it was found, but nothing is blocked. Each
+list applies to device groups (or every device), has its own severity and
+chooses how much context is kept:
+- "none": the surrounding text as seen
+- "mask_term": the matched term masked in the snippet and URL
+- "no_snippet": no surrounding text, and the URL without its query string
+
+Terms match whole words, case-insensitively, in the decoded URL, the request
+body and text response bodies of new captures. Every match is stored in the
+`watch_matches` table for the weekly rollup; alerts are raised at most once
+per device, list and term per ALERT_INTERVAL.
+"""
+
+import json
+import re
+import sys
+from datetime import timedelta
+from typing import Any, Dict, Iterator, List, Optional, Tuple
+from urllib.parse import unquote_plus, urlsplit
+
+from .alert_engine import AlertEngine
+from .keywords import AlertCategory, AlertSeverity
+from ..database.db_manager import DatabaseManager
+from ..utils.clock import parse_timestamp, to_iso, utc_now
+from ..utils.config import get_config_path, get_data_path
+from ..utils.i18n import t
+
+
+REDACTION_MODES = ("none", "mask_term", "no_snippet")
+
+# Characters kept on each side of a match
+SNIPPET_CHARS = 80
+
+# Re-alert for the same device/list/term at most this often
+ALERT_INTERVAL = timedelta(hours=1)
+
+# Rows checked per scan, so a backlog can't stall the caller
+SCAN_BATCH = 5000
+
+# Response bodies of these types are searched; others (scripts, styles) are noise
+TEXT_RESPONSE_TYPES = ("text/html", "text/plain", "application/json")
+
+
+def mask(term: str) -> str:
+    """The first letter of a term, the rest starred."""
+    return term[:1] + "*" * (len(term) - 1)
+
+
+class WatchList:
+    """A set of watched terms applied to some device groups."""
+
+    def __init__(self, data: Dict[str, Any]):
+        self.id = str(data.get("id") or "").strip()
+        self.name = str(data.get("name") or self.id)
+        self.enabled = bool(data.get("enabled", True))
+        self.terms = [str(term).strip() for term in data.get("terms", []) if str(term).strip()]
+        self.severity = AlertSeverity(data.get("severity", "high"))
+        self.category = AlertCategory(data.get("category", "custom"))
+        self.groups = [str(g) for g in data.get("groups", [])]
+        self.redaction = data.get("redaction", "mask_term")
+
+        if not self.id:
+            raise ValueError("Watch list without an id")
+        if self.redaction not in REDACTION_MODES:
+            raise ValueError(f"Unknown redaction '{self.redaction}' in watch list '{self.id}'")
+        self._patterns = [
+            (term, re.compile(rf"(?<!\w){re.escape(term)}(?!\w)", re.I)) for term in self.terms
+        ]
+
+    def to_dict(self) -> dict:
+        return {
+            "id": self.id,
+            "name": self.name,
+            "enabled": self.enabled,
+            "terms": self.terms,
+            "severity": self.severity.value,
+            "category": self.category.value,
+            "groups": self.groups,
+            "redaction": self.redaction,
+        }
+
+    def find(self, text: str) -> Iterator[Tuple[str, int, int]]:
+        """(term, start, end) of each match in the text."""
+        for term, pattern in self._patterns:
+            for match in pattern.finditer(text):
+                yield term, match.start(), match.end()
+
+    def snippet(self, text: str, start: int, end: int) -> Optional[str]:
+        """The text around a match, redacted as the list asks."""
+        if self.redaction == "no_snippet":
+            return None
+        before = text[max(0, start - SNIPPET_CHARS):start]
+        found = text[start:end]
+        after = text[end:end + SNIPPET_CHARS]
+        if self.redaction == "mask_term":
+            found = mask(found)
+        snippet = " ".join(f"{before}{found}{after}".split())
+        prefix = "…" if start > SNIPPET_CHARS else ""
+        suffix = "…" if end + SNIPPET_CHARS < len(text) else ""
+        return f"{prefix}{snippet}{suffix}"
+
+    def stored_url(self, url: str) -> str:
+        """A URL as kept with a match."""
+        if self.redaction == "no_snippet":
+            parsed = urlsplit(url)
+            return f"{parsed.scheme}://{parsed.netloc}{parsed.path}"
+        if self.redaction == "mask_term":
+            url = unquote_plus(url)
+            for term, pattern in self._patterns:
+                url = pattern.sub(mask(term), url)
+        return url
+
+
+class WatchListMonitor:
+    """Finds watched terms in captured traffic."""
+
+    def __init__(self):
+        self.config_file = get_config_path() / "watch_lists.json"
+        self.state_file = get_data_path() / "watch_lists_state.json"
+        self.enabled = True
+        self.groups: Dict[str, List[str]] = {}
+        self.lists: List[WatchList] = []
+        self.state: dict = {"traffic_rowid": None, "alerted": {}}
+
+        self._load_config()
+        self._load_state()
+
+    def _load_config(self):
+        if self.config_file.exists():
+            try:
+                data = json.loads(self.config_file.read_text())
+                self.enabled = data.get("enabled", True)
+                self.groups = {str(k): [str(d) for d in v] for k, v in data.get("groups", {}).items()}
+                self.lists = [WatchList(item) for item in data.get("lists", [])]
+            except Exception as e:
+                print(json.dumps({"error": f"Failed to load watch lists: {e}"}), file=sys.stderr, flush=True)
+
+    def get_config(self) -> dict:
+        return {
+            "enabled": self.enabled,
+            "groups": self.groups,
+            "lists": [w.to_dict() for w in self.lists],
+        }
+
+    def set_config(self, config: Dict[str, Any]):
+        """Save the config. Raises ValueError on an invalid list."""
+        groups = {str(k): [str(d) for d in v] for k, v in config.get("groups", {}).items()}
+        lists = [WatchList(item) for item in config.get("lists", [])]
+        ids = [w.id for w in lists]
+        if len(set(ids)) != len(ids):
+            raise ValueError("Watch list ids must be unique")
+        for watch_list in lists:
+            unknown = [g for g in watch_list.groups if g not in groups]
+            if unknown:
+                raise ValueError(f"Watch list '{watch_list.id}' uses unknown groups: {', '.join(unknown)}")
+
+        self.enabled = bool(config.get("enabled", True))
+        self.groups = groups
+        self.lists = lists
+        self.config_file.parent.mkdir(parents=True, exist_ok=True)
+        self.config_file.write_text(json.dumps(self.get_config(), indent=2))
+
+    def _load_state(self):
+        if self.state_file.exists():
+            try:
+                self.state.update(json.loads(self.state_file.read_text()))
+            except Exception:
+                pass
+
+    def _save_state(self):
+        self.state_file.parent.mkdir(parents=True, exist_ok=True)
+        self.state_file.write_text(json.dumps(self.state, indent=2))
+
+    def lists_for(self, device_id: Optional[str]) -> List[WatchList]:
+        """Enabled lists that apply to a device: those without groups, or with one of its groups."""
+        return [
+            w for w in self.lists
+            if w.enabled and w.terms and (
+                not w.groups or any(device_id in self.groups.get(g, []) for g in w.groups)
+            )
+        ]
+
+    def find_matches(self, watch_list: WatchList, row) -> List[Dict[str, Any]]:
+        """Matches of a list in one captured request, first per term and location."""
+        url = row["url"] or ""
+        texts = [("url", unquote_plus(url)), ("request", row["request_body"] or "")]
+        if (row["response_body_type"] or "").lower().startswith(TEXT_RESPONSE_TYPES):
+            texts.append(("response", row["response_body"] or ""))
+
+        matches = []
+        seen = set()
+        for location, text in texts:
+            for term, start, end in watch_list.find(text):
+                if (term, location) in seen:
+                    continue
+                seen.add((term, location))
+                matches.append({
+                    "timestamp": row["timestamp"],
+                    "traffic_id": row["id"],
+                    "list_id": watch_list.id,
+                    "term": term,
+                    "device_id": row["device_id"],
+                    "device_ip": row["device_ip"],
+                    "host": row["host"],
+                    "url": watch_list.stored_url(url),
+                    "location": location,
+                    "snippet": watch_list.snippet(text, start, end),
+                })
+        return matches
+
+    def scan_new(self) -> dict:
+        """Check requests captured since the last scan, store matches and alert. The first scan starts from now."""
+        if not self.enabled or not any(w.enabled and w.terms for w in self.lists):
+            return {"checked": 0, "matches": 0, "alerts": 0}
+
+        db = DatabaseManager()
+        with db._get_connection() as conn:
+            latest = conn.execute("SELECT COALESCE(MAX(rowid), 0) FROM traffic").fetchone()[0]
+            start = self.state.get("traffic_rowid")
+            if start is None or start > latest:
+                start = latest
+            rows = conn.execute(
+                "SELECT rowid, id, timestamp, device_id, device_ip, host, url, request_body, "
+                "response_body, response_body_type FROM traffic "
+                "WHERE rowid > ? ORDER BY rowid LIMIT ?",
+                (start, SCAN_BATCH)
+            ).fetchall()
+
+        matches: List[Dict[str, Any]] = []
+        alerts = 0
+        engine = None
+        for row in rows:
+            for watch_list in self.lists_for(row["device_id"]):
+                found = self.find_matches(watch_list, row)
+                if found:
+                    matches.extend(found)
+                    engine = engine or AlertEngine()
+                    alerts += self._raise_alerts(engine, watch_list, row, found)
+
+        if matches:
+            db.add_watch_matches(matches)
+        self.state["traffic_rowid"] = rows[-1]["rowid"] if rows else start
+        self._save_state()
+        return {"checked": len(rows), "matches": len(matches), "alerts": alerts}
+
+    def _raise_alerts(self, engine: AlertEngine, watch_list: WatchList, row, matches: List[Dict[str, Any]]) -> int:
+        """Alert once per device/list/term per ALERT_INTERVAL. Nothing is blocked."""
+        now = utc_now()
+        alerted: Dict[str, str] = self.state.setdefault("alerted", {})
+        for key, at in list(alerted.items()):
+            if parse_timestamp(at, naive_tz=None) + ALERT_INTERVAL <= now:
+                del alerted[key]
+
+        raised = 0
+        device = row["device_id"] or row["device_ip"]
+        for match in matches:
+            key = f"{device}|{watch_list.id}|{match['term']}"
+            if key in alerted:
+                continue
+            alerted[key] = to_iso(now)
+
+            engine.create_alert(
+                severity=watch_list.severity,
+                category=watch_list.category,
+                title=t("alert.watch_list.title", list=watch_list.name, term=match["term"]),
+                description=t(
+                    "alert.watch_list.description",
+                    device=device, term=match["term"], list=watch_list.name, host=row["host"],
+                    location=t(f"watch_list.location.{match['location']}", default=match["location"]),
+                ),
+                source_ip=row["device_ip"],
+                source_device=row["device_id"],
+                domain=row["host"],
+                url=match["url"],
+                metadata={
+                    "watch_list": watch_list.id,
+                    "term": match["term"],
+                    "location": match["location"],
+                    "snippet": match["snippet"],
+                    "redaction": watch_list.redaction,
+                    "timestamp": row["timestamp"],
+                },
+            )
+            raised += 1
+
+        return raised
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Keyword watch lists")
+    parser.add_argument("--action", choices=["scan", "config", "set-config"], default="scan")
+    parser.add_argument("--config", help="JSON config (set-config)")
+
+    args = parser.parse_args()
+
+    try:
+        monitor = WatchListMonitor()
+
+        if args.action == "scan":
+            output_json({"success": True, **monitor.scan_new()})
+        elif args.action == "config":
+            output_json({"success": True, "config": monitor.get_config()})
+        elif args.action == "set-config":
+            monitor.set_config(json.loads(args.config or "{}"))
+            output_json({"success": True, "config": monitor.get_config()})
+
+    except Exception as e:
+        output_json({"success": False, "error": str(e)})
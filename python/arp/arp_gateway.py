@@ -8,15 +8,16 @@ import json
 import sys
 import time
 import threading
-from datetime import datetime
 from typing import List, Optional, Dict, Set, Callable
 from dataclasses import dataclass, asdict
 
 from scapy.all import (
-    ARP, Ether, sendp, getmacbyip, get_if_hwaddr,
+    ARP, Ether, sendp, getmacbyip, get_if_addr, get_if_hwaddr,
     conf, srp
 )
 
+from ..utils.clock import utc_now_iso
+
 
 @dataclass
 class TargetDevice:
@@ -41,7 +42,8 @@ class ARPGateway:
         targets: Optional[List[str]] = None,
         quiet_mode: bool = True,
         spoof_interval: int = 15,
-        callback: Optional[Callable[[Dict], None]] = None
+        callback: Optional[Callable[[Dict], None]] = None,
+        protected: Optional[List[str]] = None
     ):
         """
         Initialize ARP Gateway
@@ -53,11 +55,17 @@ class ARPGateway:
             quiet_mode: If True, reduce ARP packet frequency
             spoof_interval: Seconds between ARP packets (higher = stealthier)
             callback: Function to call with status updates
+            protected: IPs never spoofed (the gateway and this machine always are)
         """
         self.interface = interface
         self.gateway_ip = gateway_ip
         self.gateway_mac: Optional[str] = None
         self.our_mac = get_if_hwaddr(interface)
+        self.protected: Set[str] = set(protected or [])
+        try:
+            self.protected.add(get_if_addr(interface))
+        except Exception:
+            pass
         self.targets: Dict[str, TargetDevice] = {}
         self.quiet_mode = quiet_mode
         self.spoof_interval = spoof_interval if quiet_mode else 2
@@ -115,6 +123,10 @@ class ARPGateway:
         except Exception:
             return None
     
+    def _is_protected(self, ip: str) -> bool:
+        """Whether spoofing this IP would break the network (gateway, ourselves, infrastructure)"""
+        return ip in self.protected or ip == self._get_gateway()
+    
     def add_target(self, ip: str, hostname: Optional[str] = None) -> bool:
         """
         Add a target device to monitor
@@ -126,6 +138,14 @@ class ARPGateway:
         Returns:
             True if successfully added
         """
+        if self._is_protected(ip):
+            self.callback({
+                "type": "target_refused",
+                "ip": ip,
+                "reason": "protected"
+            })
+            return False
+        
         mac = self._get_mac(ip)
         if not mac:
             self.callback({
@@ -138,7 +158,7 @@ class ARPGateway:
             ip=ip,
             mac=mac.upper(),
             hostname=hostname,
-            last_seen=datetime.now().isoformat(),
+            last_seen=utc_now_iso(),
             active=True
         )
         
@@ -164,6 +184,21 @@ class ARPGateway:
                 "ip": ip
             })
     
+    def set_targets(self, targets: List[Dict]):
+        """
+        Spoof exactly these devices, restoring any others
+        
+        Args:
+            targets: Dicts with "ip" and optional "hostname"
+        """
+        wanted = {t["ip"]: t.get("hostname") for t in targets}
+        for ip in list(self.targets):
+            if ip not in wanted:
+                self.remove_target(ip)
+        for ip, hostname in wanted.items():
+            if ip not in self.targets:
+                self.add_target(ip, hostname)
+    
     def _spoof_target(self, target_ip: str, target_mac: str):
         """
         Send ARP packet to target saying we are the gateway
@@ -226,7 +261,7 @@ class ARPGateway:
                     try:
                         self._spoof_target(ip, target.mac)
                         self._spoof_gateway(ip, target.mac)
-                        target.last_seen = datetime.now().isoformat()
+                        target.last_seen = utc_now_iso()
                     except Exception as e:
                         self.callback({
                             "type": "spoof_error",
@@ -255,9 +290,20 @@ class ARPGateway:
             })
             return False
         
-        # Enable IP forwarding
-        from .ip_forwarding import enable_ip_forwarding
-        enable_ip_forwarding(self.interface)
+        # Without forwarding every target would lose its connection
+        from .ip_forwarding import ensure_ip_forwarding
+        forwarding, message = ensure_ip_forwarding(self.interface)
+        if not forwarding:
+            self.callback({
+                "type": "error",
+                "message": f"Refusing to spoof: {message}"
+            })
+            return False
+        
+        # Targets added before the gateway was known
+        for ip in [ip for ip in self.targets if self._is_protected(ip)]:
+            del self.targets[ip]
+            self.callback({"type": "target_refused", "ip": ip, "reason": "protected"})
         
         self.running = True
         
@@ -356,6 +402,7 @@ if __name__ == "__main__":
     parser.add_argument("--targets", "-t", nargs="*", default=[], help="Target IPs")
     parser.add_argument("--quiet", "-q", action="store_true", default=True, help="Quiet mode")
     parser.add_argument("--interval", type=int, default=15, help="Spoof interval (seconds)")
+    parser.add_argument("--protect", nargs="*", default=[], help="IPs never spoofed (infrastructure)")
     
     args = parser.parse_args()
     
@@ -364,7 +411,8 @@ if __name__ == "__main__":
         gateway_ip=args.gateway,
         targets=args.targets,
         quiet_mode=args.quiet,
-        spoof_interval=args.interval
+        spoof_interval=args.interval,
+        protected=args.protect
     )
     
     try:
@@ -385,6 +433,8 @@ if __name__ == "__main__":
                         gateway.add_target(cmd["ip"], cmd.get("hostname"))
                     elif action == "remove_target":
                         gateway.remove_target(cmd["ip"])
+                    elif action == "set_targets":
+                        gateway.set_targets(cmd.get("targets", []))
                     elif action == "get_targets":
                         print(json.dumps({"targets": gateway.get_targets()}), flush=True)
                     elif action == "set_quiet":
@@ -1,19 +1,27 @@
 """
 Network Device Scanner
-Discovers all devices on the local network
+Discovers all devices on the local networks
+
+Every connected network is swept with ARP on its own interface, so VLAN
+interfaces and secondary subnets are covered, not just a /24 around the
+primary address. Routed ranges (added by hand, or the outer router's network
+behind double NAT) can't be reached with ARP and are pinged instead; devices
+found there have no MAC address.
 """
 
 import json
 import socket
 import subprocess
 import threading
-from datetime import datetime
-from typing import List, Dict, Optional
-from dataclasses import dataclass, asdict
+from typing import List, Dict, Optional, Tuple
+from dataclasses import dataclass, asdict, field
 
-from scapy.all import ARP, Ether, srp, conf
+from scapy.all import ARP, ICMP, IP, Ether, conf, sr, srp
 import psutil
 
+from ..utils.clock import utc_now_iso
+from ..utils.network_utils import detect_networks, get_gateways, scan_targets
+
 
 @dataclass
 class NetworkDevice:
@@ -24,8 +32,12 @@ class NetworkDevice:
     vendor: Optional[str] = None
     is_gateway: bool = False
     is_self: bool = False
+    # Network (CIDR) the device was found in
+    network: Optional[str] = None
     first_seen: Optional[str] = None
     last_seen: Optional[str] = None
+    # Other MACs that answered for the same IP in this scan (an IP conflict)
+    conflict_macs: List[str] = field(default_factory=list)
     
     def to_dict(self) -> Dict:
         return asdict(self)
@@ -115,70 +127,89 @@ class DeviceScanner:
         mac_prefix = mac[:8].upper()
         return oui_map.get(mac_prefix)
     
-    def scan(self, timeout: int = 3) -> List[NetworkDevice]:
+    def _sweep(self, network: Dict, timeout: int) -> List[Tuple[str, str]]:
+        """(IP, MAC) of every device answering in a network; MAC is "" when routed"""
+        cidr = network["scan_cidr"]
+        try:
+            if network["routed"]:
+                answered = sr(IP(dst=cidr) / ICMP(), timeout=timeout, verbose=False)[0]
+                return [(received.src, "") for _, received in answered]
+            
+            packet = Ether(dst="ff:ff:ff:ff:ff:ff") / ARP(pdst=cidr)
+            answered = srp(packet, timeout=timeout, iface=network["interface"], verbose=False)[0]
+            return [(received.psrc, received.hwsrc.upper()) for _, received in answered]
+        except Exception as e:
+            print(json.dumps({"error": f"Scan of {cidr} failed: {e}", "type": "scan_error"}), flush=True)
+            return []
+    
+    def scan(self, timeout: int = 3, networks: Optional[List[Dict]] = None) -> List[NetworkDevice]:
         """
-        Scan network for devices
+        Scan networks for devices
         
         Args:
-            timeout: Timeout in seconds
+            timeout: Timeout in seconds, per network
+            networks: Networks from `scan_targets`; the interface's own when None
             
         Returns:
             List of discovered devices
         """
-        if not self.local_ip:
-            return []
-        
-        # Calculate network range (assume /24)
-        parts = self.local_ip.split('.')
-        parts[3] = '0'
-        network = '.'.join(parts) + '/24'
+        if networks is None:
+            networks = [n for n in detect_networks() if n["interface"] == self.interface]
+        if not networks and self.local_ip:
+            # Interface without a detectable netmask: assume a /24
+            parts = self.local_ip.split('.')
+            parts[3] = '0'
+            cidr = '.'.join(parts) + '/24'
+            networks = [{"cidr": cidr, "scan_cidr": cidr, "interface": self.interface, "routed": False}]
         
-        # Create ARP request
-        arp = ARP(pdst=network)
-        ether = Ether(dst="ff:ff:ff:ff:ff:ff")
-        packet = ether / arp
-        
-        # Send and receive
-        try:
-            result = srp(packet, timeout=timeout, iface=self.interface, verbose=False)[0]
-        except Exception as e:
-            print(json.dumps({"error": str(e), "type": "scan_error"}), flush=True)
-            return []
+        gateways = set(get_gateways())
+        if self.gateway_ip:
+            gateways.add(self.gateway_ip)
+        local_ips = {
+            addr.address
+            for addrs in psutil.net_if_addrs().values()
+            for addr in addrs
+            if addr.family == socket.AF_INET
+        }
         
-        now = datetime.now().isoformat()
-        discovered = []
+        now = utc_now_iso()
+        discovered: Dict[str, NetworkDevice] = {}
         
-        for sent, received in result:
-            ip = received.psrc
-            mac = received.hwsrc.upper()
-            
-            # Check if we've seen this device before
-            if ip in self.devices:
-                device = self.devices[ip]
-                device.last_seen = now
-            else:
-                device = NetworkDevice(
-                    ip=ip,
-                    mac=mac,
-                    hostname=self._get_hostname(ip),
-                    vendor=self._get_vendor(mac),
-                    is_gateway=(ip == self.gateway_ip),
-                    is_self=(ip == self.local_ip),
-                    first_seen=now,
-                    last_seen=now
-                )
-                self.devices[ip] = device
-            
-            discovered.append(device)
+        for network in networks:
+            for ip, mac in self._sweep(network, timeout):
+                if ip in discovered:
+                    device = discovered[ip]
+                    if mac and mac != device.mac and mac not in device.conflict_macs:
+                        device.conflict_macs.append(mac)
+                    continue
+                
+                # Check if we've seen this device before
+                if ip in self.devices:
+                    device = self.devices[ip]
+                    device.last_seen = now
+                    device.conflict_macs = []
+                else:
+                    device = NetworkDevice(
+                        ip=ip,
+                        mac=mac,
+                        hostname=self._get_hostname(ip),
+                        vendor=self._get_vendor(mac) if mac else None,
+                        is_gateway=(ip in gateways),
+                        is_self=(ip in local_ips),
+                        network=network["cidr"],
+                        first_seen=now,
+                        last_seen=now
+                    )
+                    self.devices[ip] = device
+                
+                discovered[ip] = device
         
-        # Sort: gateway first, self second, then by IP
-        discovered.sort(key=lambda d: (
+        # Sort: gateways first, self second, then by IP
+        return sorted(discovered.values(), key=lambda d: (
             not d.is_gateway,
             not d.is_self,
             [int(x) for x in d.ip.split('.')]
         ))
-        
-        return discovered
     
     def get_all_devices(self) -> List[NetworkDevice]:
         """Get all known devices"""
@@ -207,7 +238,7 @@ class DeviceScanner:
                     print(json.dumps({
                         "type": "scan_result",
                         "devices": [d.to_dict() for d in devices],
-                        "timestamp": datetime.now().isoformat()
+                        "timestamp": utc_now_iso()
                     }), flush=True)
                 time.sleep(interval)
         
@@ -238,16 +269,19 @@ if __name__ == "__main__":
     import sys
     
     parser = argparse.ArgumentParser(description="Network Device Scanner")
-    parser.add_argument("--interface", "-i", required=True, help="Network interface")
+    parser.add_argument("--interface", "-i", help="Network interface (every connected network when omitted)")
+    parser.add_argument("--scan", action="store_true", help="Scan once and print the devices")
+    parser.add_argument("--range", action="append", default=[], help="Extra CIDR range to scan (repeatable)")
     parser.add_argument("--timeout", "-t", type=int, default=3, help="Scan timeout")
     parser.add_argument("--continuous", "-c", action="store_true", help="Continuous scanning")
     parser.add_argument("--interval", type=int, default=30, help="Scan interval (seconds)")
     
     args = parser.parse_args()
     
-    scanner = DeviceScanner(args.interface)
-    
     if args.continuous:
+        if not args.interface:
+            parser.error("--continuous needs --interface")
+        scanner = DeviceScanner(args.interface)
         scanner.continuous_scan(args.interval)
         
         # Keep main thread alive
@@ -258,8 +292,23 @@ if __name__ == "__main__":
         except KeyboardInterrupt:
             pass
     else:
-        devices = scanner.scan(args.timeout)
-        print(json.dumps({
-            "devices": [d.to_dict() for d in devices],
-            "count": len(devices)
-        }))
+        try:
+            targets = scan_targets(args.range)
+            networks = targets["networks"]
+            if args.interface:
+                networks = [n for n in networks if n["interface"] in (args.interface, None)]
+            scanner = DeviceScanner(args.interface or "")
+            devices = scanner.scan(args.timeout, networks)
+            print(json.dumps({
+                "success": True,
+                "devices": [d.to_dict() for d in devices],
+                "count": len(devices),
+                "networks": networks,
+                "double_nat": targets["double_nat"],
+            }))
+        except Exception as e:
+            print(json.dumps({
+                "success": False,
+                "error": str(e),
+                "type": type(e).__name__
+            }))
@@ -0,0 +1,178 @@
+"""
+Passive DHCP server watch
+Records every DHCP server seen answering a client on the network: the
+OFFERs and ACKs servers send while devices join or renew. Unlike the app's
+active probe this needs no DHCP client port of its own, so it keeps working
+while the OS DHCP client holds it, and it notices a rogue server between
+probes. Servers are kept by address in the data directory's
+`dhcp_servers.json`, which the app merges with its probe results.
+"""
+
+import json
+import sys
+import threading
+import time
+from pathlib import Path
+from typing import Dict, Optional
+
+from scapy.all import sniff, BOOTP, DHCP, Ether, IP
+
+from ..utils.clock import utc_now_iso
+from ..utils.config import get_data_path
+
+# Shortest time between two writes of the servers file, in seconds
+SAVE_INTERVAL = 10
+
+# Server to client traffic only
+CAPTURE_FILTER = "udp and src port 67 and dst port 68"
+
+MSG_OFFER = 2
+MSG_ACK = 5
+
+
+class DHCPWatcher:
+    """
+    Passive DHCP server watch
+    Records the servers that answer DHCP clients, by address
+    """
+
+    def __init__(self, interface: str, output_file: Optional[str] = None):
+        self.interface = interface
+        self.output_file = Path(output_file) if output_file else get_data_path() / "dhcp_servers.json"
+        self.running = False
+        self.capture_thread: Optional[threading.Thread] = None
+        self.servers: Dict[str, Dict] = self._load()
+        self._lock = threading.Lock()
+        self._dirty = False
+        self._last_save = 0.0
+
+    def _load(self) -> Dict[str, Dict]:
+        try:
+            with open(self.output_file) as f:
+                servers = json.load(f)
+            return servers if isinstance(servers, dict) else {}
+        except (OSError, ValueError):
+            return {}
+
+    def _save(self) -> None:
+        with self._lock:
+            if not self._dirty:
+                return
+            content = json.dumps(self.servers, indent=2)
+            self._dirty = False
+            self._last_save = time.monotonic()
+        try:
+            self.output_file.parent.mkdir(parents=True, exist_ok=True)
+            tmp = self.output_file.with_suffix(".tmp")
+            tmp.write_text(content)
+            tmp.replace(self.output_file)
+        except OSError as e:
+            error_msg = json.dumps({"error": str(e), "type": "dhcp_save_error"})
+            print(error_msg, file=sys.stderr, flush=True)
+
+    def _process_packet(self, packet) -> None:
+        try:
+            if not (packet.haslayer(DHCP) and packet.haslayer(BOOTP) and packet.haslayer(IP)):
+                return
+            message_type = None
+            server_id = None
+            for option in packet[DHCP].options:
+                if not isinstance(option, tuple) or len(option) < 2:
+                    continue
+                if option[0] == "message-type":
+                    message_type = option[1]
+                elif option[0] == "server_id":
+                    server_id = option[1]
+            if message_type not in (MSG_OFFER, MSG_ACK):
+                return
+            self._record(
+                server_id or packet[IP].src,
+                packet[BOOTP].yiaddr,
+                packet[Ether].src if packet.haslayer(Ether) else None,
+            )
+        except Exception as e:
+            error_msg = json.dumps({"error": str(e), "type": "dhcp_watch_error"})
+            print(error_msg, file=sys.stderr, flush=True)
+
+    def _record(self, server_ip: str, offered_ip: str, server_mac: Optional[str]) -> None:
+        with self._lock:
+            server = self.servers.setdefault(server_ip, {"offers": 0})
+            server["offers"] = server.get("offers", 0) + 1
+            server["offered_ip"] = offered_ip
+            server["server_mac"] = server_mac.lower() if server_mac else None
+            server["seen_at"] = utc_now_iso()
+            self._dirty = True
+            due = time.monotonic() - self._last_save >= SAVE_INTERVAL
+        if due:
+            self._save()
+
+    def start(self):
+        """Start watching"""
+        self.running = True
+
+        def capture_loop():
+            try:
+                sniff(
+                    iface=self.interface,
+                    filter=CAPTURE_FILTER,
+                    prn=self._process_packet,
+                    store=False,
+                    stop_filter=lambda x: not self.running
+                )
+            except Exception as e:
+                error_msg = json.dumps({"error": str(e), "type": "capture_error"})
+                print(error_msg, file=sys.stderr, flush=True)
+
+        def save_loop():
+            # Sightings after the last save still get written
+            while self.running:
+                time.sleep(SAVE_INTERVAL)
+                self._save()
+
+        self.capture_thread = threading.Thread(target=capture_loop, daemon=True)
+        self.capture_thread.start()
+        threading.Thread(target=save_loop, daemon=True).start()
+
+        start_msg = json.dumps({
+            "status": "started",
+            "interface": self.interface,
+            "type": "dhcp_watch"
+        })
+        print(start_msg, flush=True)
+
+    def stop(self):
+        """Stop watching and write what was seen"""
+        self.running = False
+        if self.capture_thread:
+            self.capture_thread.join(timeout=2)
+        self._save()
+
+
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Passive DHCP server watch")
+    parser.add_argument("--interface", "-i", required=True, help="Network interface")
+
+    args = parser.parse_args()
+
+    watcher = DHCPWatcher(args.interface)
+
+    try:
+        watcher.start()
+
+        # Keep main thread alive until asked to stop
+        while watcher.running:
+            line = sys.stdin.readline()
+            if not line:
+                break
+            try:
+                if json.loads(line).get("action") == "stop":
+                    break
+            except json.JSONDecodeError:
+                pass
+
+    except KeyboardInterrupt:
+        pass
+    finally:
+        watcher.stop()
@@ -152,6 +152,48 @@ def is_ip_forwarding_enabled() -> bool:
         return False
 
 
+def is_forwarding_active(interface: str = None) -> bool:
+    """
+    Check whether forwarding is in effect right now
+    
+    The registry value only applies after a reboot, so this asks the IP stack
+    for the interface's (or any interface's) live forwarding state.
+    """
+    try:
+        ps_cmd = 'Get-NetIPInterface -AddressFamily IPv4'
+        if interface:
+            ps_cmd += f" -InterfaceAlias '{interface}'"
+        ps_cmd += ' | Select-Object -ExpandProperty Forwarding'
+        result = subprocess.run(
+            ['powershell', '-Command', ps_cmd],
+            capture_output=True,
+            text=True,
+            creationflags=subprocess.CREATE_NO_WINDOW
+        )
+        if result.returncode == 0 and result.stdout.strip():
+            return 'Enabled' in result.stdout.split()
+    except Exception:
+        pass
+    
+    return is_ip_forwarding_enabled()
+
+
+def ensure_ip_forwarding(interface: str = None) -> Tuple[bool, str]:
+    """
+    Enable IP forwarding and confirm it took effect
+    
+    Spoofing without forwarding black-holes every target's traffic, so
+    callers refuse to spoof when this fails.
+    
+    Returns:
+        Tuple of (forwarding active, message)
+    """
+    _, message = enable_ip_forwarding(interface)
+    if is_forwarding_active(interface):
+        return True, message
+    return False, f"IP forwarding is not active on {interface or 'any interface'} ({message})"
+
+
 # CLI interface
 if __name__ == "__main__":
     import argparse
@@ -0,0 +1,244 @@
+"""
+Passive OS fingerprinting
+Guesses each device's operating system from packets it sends anyway, without
+probing it: the TTL, window size and option order of its TCP SYNs, and the
+option list and vendor class of its DHCP requests. Observations are kept per
+MAC address in the data directory's `os_fingerprints.json`, which the app
+combines with the User-Agents seen in each device's traffic.
+"""
+
+import json
+import sys
+import threading
+import time
+from pathlib import Path
+from typing import Dict, List, Optional
+
+from scapy.all import sniff, BOOTP, DHCP, Ether, IP, TCP
+
+from ..utils.clock import utc_now_iso
+from ..utils.config import get_data_path
+
+# Shortest time between two writes of the observations file, in seconds
+SAVE_INTERVAL = 10
+
+# SYNs without ACK, and DHCP client requests
+CAPTURE_FILTER = "(tcp[tcpflags] & (tcp-syn|tcp-ack) == tcp-syn) or (udp and src port 68 and dst port 67)"
+
+# Initial TTLs stacks start from; a packet's is the next one up from what arrives
+INITIAL_TTLS = (32, 64, 128, 255)
+
+# TCP option layouts of a SYN (M=MSS, N=NOP, W=window scale, S=SACK permitted,
+# T=timestamp, E=end of list), by the OS family that sends them
+TCP_LAYOUTS = {
+    "M,N,W,N,N,T,S,E": "apple",
+    "M,S,T,N,W": "linux",
+    "M,N,W,S,T": "linux",
+    "M,N,W,N,N,S": "windows",
+    "M,N,W,S,S": "windows",
+}
+
+# Window sizes typical of a family's SYNs, for when the options don't tell
+TCP_WINDOWS = {
+    65535: "apple",
+    5840: "linux",
+    14600: "linux",
+    29200: "linux",
+}
+
+# Leading DHCP parameter request lists (option 55)
+DHCP_PARAMS = [
+    ((1, 121, 3, 6, 15, 119, 252, 95, 44, 46), "macos"),
+    ((1, 121, 3, 6, 15, 119, 252), "ios"),
+    ((1, 3, 6, 15, 31, 33, 43, 44, 46, 47, 119, 121, 249, 252), "windows"),
+    ((1, 3, 6, 15, 26, 28, 51, 58, 59), "android"),
+    ((1, 33, 3, 6, 15, 28, 51, 58, 59), "android"),
+    ((1, 28, 2, 3, 15, 6, 119, 12, 44, 47, 26, 121, 42), "linux"),
+]
+
+# DHCP vendor class (option 60) prefixes
+DHCP_VENDOR_CLASSES = {
+    "msft": "windows",
+    "android-dhcp": "android",
+    "dhcpcd": "linux",
+    "udhcp": "linux",
+}
+
+TCP_OPTION_CODES = {
+    "MSS": "M",
+    "NOP": "N",
+    "WScale": "W",
+    "SAckOK": "S",
+    "Timestamp": "T",
+    "EOL": "E",
+}
+
+
+def initial_ttl(ttl: int) -> int:
+    """The TTL a packet most likely started out with."""
+    return next((initial for initial in INITIAL_TTLS if ttl <= initial), 255)
+
+
+def tcp_layout(options: list) -> str:
+    """Option order of a SYN, e.g. "M,S,T,N,W"."""
+    return ",".join(TCP_OPTION_CODES.get(name, "?") for name, _ in options)
+
+
+def classify_tcp(ttl: int, window: int, layout: str) -> Optional[str]:
+    """OS family of a SYN, or None if it doesn't look like a known stack."""
+    initial = initial_ttl(ttl)
+    if initial == 128:
+        return "windows"
+    if initial != 64:
+        return None
+    family = TCP_LAYOUTS.get(layout)
+    if family == "windows":
+        # Windows layout with a Unix TTL is something else
+        return None
+    return family or TCP_WINDOWS.get(window)
+
+
+def classify_dhcp(params: List[int], vendor_class: Optional[str]) -> Optional[str]:
+    """Platform or family a DHCP request names, or None if it doesn't."""
+    if vendor_class:
+        vendor = vendor_class.lower()
+        for prefix, family in DHCP_VENDOR_CLASSES.items():
+            if vendor.startswith(prefix):
+                return family
+    for prefix, family in DHCP_PARAMS:
+        if tuple(params[:len(prefix)]) == prefix:
+            return family
+    return None
+
+
+class OSFingerprinter:
+    """
+    Passive OS fingerprinting engine
+    Records TCP SYN and DHCP request fingerprints per device MAC
+    """
+
+    def __init__(self, interface: str, output_file: Optional[str] = None):
+        self.interface = interface
+        self.output_file = Path(output_file) if output_file else get_data_path() / "os_fingerprints.json"
+        self.running = False
+        self.capture_thread: Optional[threading.Thread] = None
+        self.observations: Dict[str, Dict] = self._load()
+        self._lock = threading.Lock()
+        self._dirty = False
+        self._last_save = 0.0
+
+    def _load(self) -> Dict[str, Dict]:
+        try:
+            with open(self.output_file) as f:
+                observations = json.load(f)
+            return observations if isinstance(observations, dict) else {}
+        except (OSError, ValueError):
+            return {}
+
+    def _save(self) -> None:
+        with self._lock:
+            if not self._dirty:
+                return
+            content = json.dumps(self.observations, indent=2)
+            self._dirty = False
+            self._last_save = time.monotonic()
+        try:
+            self.output_file.parent.mkdir(parents=True, exist_ok=True)
+            tmp = self.output_file.with_suffix(".tmp")
+            tmp.write_text(content)
+            tmp.replace(self.output_file)
+        except OSError as e:
+            error_msg = json.dumps({"error": str(e), "type": "fingerprint_save_error"})
+            print(error_msg, file=sys.stderr, flush=True)
+
+    def _record(self, mac: str, kind: str, observation: Dict) -> None:
+        mac = mac.lower()
+        with self._lock:
+            device = self.observations.setdefault(mac, {})
+            previous = device.get(kind) or {}
+            changed = {k: v for k, v in previous.items() if k != "seen_at"} != observation
+            # Only note when a device is seen again once in a while
+            if not changed and time.monotonic() - self._last_save < SAVE_INTERVAL:
+                return
+            device[kind] = {**observation, "seen_at": utc_now_iso()}
+            self._dirty = True
+            due = time.monotonic() - self._last_save >= SAVE_INTERVAL
+        if due:
+            self._save()
+
+    def _process_packet(self, packet) -> None:
+        try:
+            if not packet.haslayer(Ether):
+                return
+            if packet.haslayer(DHCP) and packet.haslayer(BOOTP):
+                self._process_dhcp(packet)
+            elif packet.haslayer(TCP) and packet.haslayer(IP):
+                self._process_syn(packet)
+        except Exception as e:
+            error_msg = json.dumps({"error": str(e), "type": "fingerprint_error"})
+            print(error_msg, file=sys.stderr, flush=True)
+
+    def _process_syn(self, packet) -> None:
+        ttl = packet[IP].ttl
+        window = packet[TCP].window
+        layout = tcp_layout(packet[TCP].options)
+        self._record(packet[Ether].src, "tcp", {
+            "ttl": ttl,
+            "initial_ttl": initial_ttl(ttl),
+            "window": window,
+            "options": layout,
+            "family": classify_tcp(ttl, window, layout),
+        })
+
+    def _process_dhcp(self, packet) -> None:
+        params: List[int] = []
+        vendor_class: Optional[str] = None
+        for option in packet[DHCP].options:
+            if not isinstance(option, tuple) or len(option) < 2:
+                continue
+            name, value = option[0], option[1]
+            if name == "param_req_list":
+                params = list(value) if isinstance(value, (list, tuple, bytes)) else [value]
+            elif name == "vendor_class_id":
+                vendor_class = value.decode(errors="replace") if isinstance(value, bytes) else str(value)
+        if not params and not vendor_class:
+            return
+        self._record(packet[Ether].src, "dhcp", {
+            "params": params,
+            "vendor_class": vendor_class,
+            "family": classify_dhcp(params, vendor_class),
+        })
+
+    def start(self):
+        """Start fingerprinting"""
+        self.running = True
+
+        def capture_loop():
+            try:
+                sniff(
+                    iface=self.interface,
+                    filter=CAPTURE_FILTER,
+                    prn=self._process_packet,
+                    store=False,
+                    stop_filter=lambda x: not self.running
+                )
+            except Exception as e:
+                error_msg = json.dumps({"error": str(e), "type": "capture_error"})
+                print(error_msg, file=sys.stderr, flush=True)
+
+        self.capture_thread = threading.Thread(target=capture_loop, daemon=True)
+        self.capture_thread.start()
+
+        start_msg = json.dumps({
+            "status": "started",
+            "interface": self.interface,
+            "type": "os_fingerprint"
+        })
+        print(start_msg, flush=True)
+
+    def stop(self):
+        """Stop fingerprinting and write what was seen"""
+        self.running = False
+        if self.capture_thread:
+            self.capture_thread.join(timeout=2)
+        self._save()
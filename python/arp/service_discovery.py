@@ -0,0 +1,463 @@
+"""
+Service discovery
+Collects the services devices advertise on the local network: mDNS (Bonjour)
+announcements such as AirPlay, Chromecast, printers and SMB shares, and SSDP
+(UPnP) notifications such as media renderers and DIAL. Announcements are
+sniffed as they happen, and every few minutes the network is asked to
+announce again. UPnP devices' description documents are fetched once for
+their friendly name, model and firmware, and the banner (Server header,
+login realm and page title) of the admin page a device advertises, through
+UPnP or as an mDNS web service, is read once for the firmware advisories.
+What each device advertises is kept per MAC address in the data directory's
+`device_services.json`.
+"""
+
+import json
+import re
+import socket
+import struct
+import sys
+import threading
+import time
+import urllib.error
+import urllib.request
+from urllib.parse import urljoin, urlparse
+import xml.etree.ElementTree as ET
+from pathlib import Path
+from typing import Dict, List, Optional
+
+from scapy.all import sniff, DNS, Ether, IP, UDP, Raw
+
+from ..utils.clock import utc_now_iso
+from ..utils.config import get_data_path
+
+MDNS_ADDR = ("224.0.0.251", 5353)
+SSDP_ADDR = ("239.255.255.250", 1900)
+
+CAPTURE_FILTER = "udp port 5353 or udp port 1900"
+
+# Seconds between asking the network to announce its services
+BROWSE_INTERVAL = 300
+
+# Shortest time between two writes of the services file, in seconds
+SAVE_INTERVAL = 10
+
+# Longest a UPnP description document may take to fetch, in seconds
+DESCRIPTION_TIMEOUT = 3
+
+# Elements of a UPnP description kept as service details
+DESCRIPTION_TAGS = {
+    "friendlyName": "fn",
+    "modelName": "model",
+    "modelNumber": "model_number",
+    "manufacturer": "manufacturer",
+    "firmwareVersion": "firmware",
+    "softwareVersion": "firmware",
+}
+
+TITLE_PATTERN = re.compile(rb"<title[^>]*>(.*?)</title>", re.IGNORECASE | re.DOTALL)
+REALM_PATTERN = re.compile(r'realm="([^"]*)"', re.IGNORECASE)
+
+# mDNS service types by the kind of service they are
+MDNS_KINDS = {
+    "_airplay._tcp": "airplay",
+    "_raop._tcp": "airplay",
+    "_googlecast._tcp": "chromecast",
+    "_ipp._tcp": "printer",
+    "_ipps._tcp": "printer",
+    "_printer._tcp": "printer",
+    "_pdl-datastream._tcp": "printer",
+    "_uscan._tcp": "scanner",
+    "_scanner._tcp": "scanner",
+    "_smb._tcp": "smb",
+    "_afpovertcp._tcp": "file_sharing",
+    "_hap._tcp": "homekit",
+    "_homekit._tcp": "homekit",
+    "_spotify-connect._tcp": "spotify_connect",
+    "_sonos._tcp": "sonos",
+    "_companion-link._tcp": "apple_device",
+    "_ssh._tcp": "ssh",
+    "_http._tcp": "web",
+}
+
+# SSDP notification/search types (NT/ST), by a part of them
+SSDP_KINDS = [
+    ("dial-multiscreen-org:service:dial", "dial"),
+    (":device:MediaRenderer", "media_renderer"),
+    (":device:MediaServer", "media_server"),
+    (":device:Printer", "printer"),
+    (":device:InternetGatewayDevice", "gateway"),
+    (":device:ZonePlayer", "sonos"),
+]
+
+# Types asked for when browsing
+BROWSE_TYPES = [f"{service_type}.local" for service_type in MDNS_KINDS] + ["_services._dns-sd._udp.local"]
+
+# TXT keys kept with a service
+TXT_KEYS = ("fn", "md", "model", "ty", "product", "manufacturer", "am", "name")
+
+
+def _records(dns) -> List:
+    """Answer and additional records of a DNS packet."""
+    records = []
+    for field, count in (("an", dns.ancount), ("ar", dns.arcount)):
+        section = getattr(dns, field)
+        for i in range(count or 0):
+            try:
+                records.append(section[i])
+            except (IndexError, TypeError):
+                break
+    return records
+
+
+def _text(value) -> str:
+    if isinstance(value, bytes):
+        return value.decode("utf-8", errors="replace")
+    return str(value)
+
+
+def _service_type(name: str) -> Optional[str]:
+    """The `_service._proto` part of an mDNS name."""
+    labels = name.rstrip(".").split(".")
+    for i in range(len(labels) - 1):
+        if labels[i].startswith("_") and labels[i + 1] in ("_tcp", "_udp"):
+            return f"{labels[i]}.{labels[i + 1]}"
+    return None
+
+
+def _instance_name(name: str, service_type: str) -> str:
+    """"Living Room TV._googlecast._tcp.local." -> "Living Room TV"."""
+    return name.rstrip(".").split(f".{service_type}")[0]
+
+
+def _txt(rdata) -> Dict[str, str]:
+    entries = rdata if isinstance(rdata, (list, tuple)) else [rdata]
+    values = {}
+    for entry in entries:
+        key, _, value = _text(entry).partition("=")
+        if key.lower() in TXT_KEYS and value:
+            values[key.lower()] = value
+    return values
+
+
+def _ssdp_kind(service_type: str) -> Optional[str]:
+    for part, kind in SSDP_KINDS:
+        if part.lower() in service_type.lower():
+            return kind
+    return None
+
+
+def _ssdp_headers(payload: bytes) -> Dict[str, str]:
+    headers = {}
+    for line in _text(payload).split("\r\n")[1:]:
+        key, sep, value = line.partition(":")
+        if sep:
+            headers[key.strip().upper()] = value.strip()
+    return headers
+
+
+def mdns_services(dns) -> List[Dict]:
+    """Services an mDNS response announces."""
+    instances: Dict[str, Dict] = {}
+    details: Dict[str, Dict] = {}
+    for rr in _records(dns):
+        rrname = _text(getattr(rr, "rrname", b""))
+        if rr.type == 12:  # PTR: service type -> instance
+            instance = _text(rr.rdata)
+            service_type = _service_type(rrname)
+            if service_type and service_type in MDNS_KINDS and instance.rstrip(".") != rrname.rstrip("."):
+                instances[instance.lower()] = {"instance": instance, "type": service_type}
+        elif rr.type == 33:  # SRV: instance -> host and port
+            details.setdefault(rrname.lower(), {}).update({
+                "port": getattr(rr, "port", None),
+                "host": _text(getattr(rr, "target", b"")).rstrip(".") or None,
+            })
+        elif rr.type == 16:  # TXT
+            details.setdefault(rrname.lower(), {}).setdefault("details", {}).update(_txt(rr.rdata))
+
+    services = []
+    for key, entry in instances.items():
+        extra = details.get(key, {})
+        services.append({
+            "kind": MDNS_KINDS[entry["type"]],
+            "type": entry["type"],
+            "name": _instance_name(entry["instance"], entry["type"]),
+            "port": extra.get("port"),
+            "host": extra.get("host"),
+            "details": extra.get("details", {}),
+            "source": "mdns",
+        })
+    return services
+
+
+def ssdp_service(payload: bytes) -> Optional[Dict]:
+    """The service an SSDP notification or search response is about."""
+    text = _text(payload[:12]).upper()
+    if not (text.startswith("NOTIFY") or text.startswith("HTTP/1.1 200")):
+        return None
+    headers = _ssdp_headers(payload)
+    if headers.get("NTS", "").lower() == "ssdp:byebye":
+        return None
+    service_type = headers.get("NT") or headers.get("ST") or ""
+    kind = _ssdp_kind(service_type)
+    if not kind:
+        return None
+    details = {"server": headers["SERVER"]} if headers.get("SERVER") else {}
+    return {
+        "kind": kind,
+        "type": service_type,
+        "name": None,
+        "port": None,
+        "host": None,
+        "location": headers.get("LOCATION"),
+        "details": details,
+        "source": "ssdp",
+    }
+
+
+def describe(location: str) -> Dict[str, str]:
+    """Friendly name, model, manufacturer, firmware and admin page from a UPnP description document."""
+    with urllib.request.urlopen(location, timeout=DESCRIPTION_TIMEOUT) as response:
+        root = ET.fromstring(response.read(256 * 1024))
+    found = {}
+    for element in root.iter():
+        tag = element.tag.rsplit("}", 1)[-1]
+        if not (element.text and element.text.strip()):
+            continue
+        key = DESCRIPTION_TAGS.get(tag)
+        if key and key not in found:
+            found[key] = element.text.strip()
+        elif tag == "presentationURL" and "admin_url" not in found:
+            found["admin_url"] = urljoin(location, element.text.strip())
+    return found
+
+
+def banner(url: str) -> Dict[str, str]:
+    """Server header, login realm and page title of a device's web page."""
+    try:
+        response = urllib.request.urlopen(url, timeout=DESCRIPTION_TIMEOUT)
+    except urllib.error.HTTPError as e:
+        # Admin pages usually answer 401, which still names the server
+        response = e
+    with response:
+        headers = response.headers
+        body = response.read(64 * 1024)
+    found = {}
+    if headers.get("Server"):
+        found["http_server"] = headers["Server"].strip()
+    realm = REALM_PATTERN.search(headers.get("WWW-Authenticate", ""))
+    if realm and realm.group(1).strip():
+        found["http_realm"] = realm.group(1).strip()
+    title = TITLE_PATTERN.search(body)
+    if title:
+        text = " ".join(_text(title.group(1)).split())
+        if text:
+            found["http_title"] = text[:200]
+    return found
+
+
+def _own_http_url(url: Optional[str], ip: str) -> bool:
+    """Whether a URL is plain HTTP served by the device itself."""
+    return bool(url) and urlparse(url).scheme == "http" and urlparse(url).hostname == ip
+
+
+class ServiceDiscovery:
+    """
+    Service discovery engine
+    Records the mDNS and SSDP services each device advertises
+    """
+
+    def __init__(self, interface: str, output_file: Optional[str] = None):
+        self.interface = interface
+        self.output_file = Path(output_file) if output_file else get_data_path() / "device_services.json"
+        self.running = False
+        self.capture_thread: Optional[threading.Thread] = None
+        self.browse_thread: Optional[threading.Thread] = None
+        self.devices: Dict[str, Dict] = self._load()
+        self._described: Dict[str, Dict[str, str]] = {}
+        self._banners: Dict[str, Dict[str, str]] = {}
+        self._lock = threading.Lock()
+        self._dirty = False
+        self._last_save = 0.0
+
+    def _load(self) -> Dict[str, Dict]:
+        try:
+            with open(self.output_file) as f:
+                devices = json.load(f)
+            return devices if isinstance(devices, dict) else {}
+        except (OSError, ValueError):
+            return {}
+
+    def _save(self) -> None:
+        with self._lock:
+            if not self._dirty:
+                return
+            content = json.dumps(self.devices, indent=2)
+            self._dirty = False
+            self._last_save = time.monotonic()
+        try:
+            self.output_file.parent.mkdir(parents=True, exist_ok=True)
+            tmp = self.output_file.with_suffix(".tmp")
+            tmp.write_text(content)
+            tmp.replace(self.output_file)
+        except OSError as e:
+            error_msg = json.dumps({"error": str(e), "type": "services_save_error"})
+            print(error_msg, file=sys.stderr, flush=True)
+
+    def _record(self, mac: str, ip: str, service: Dict) -> None:
+        # A UPnP device's name can arrive later than its first notification
+        name = service.get("name") if service["source"] == "mdns" else None
+        key = f"{service['source']}:{service['type']}:{name or ''}".lower()
+        now = utc_now_iso()
+        with self._lock:
+            device = self.devices.setdefault(mac.lower(), {"services": {}})
+            device["ip"] = ip
+            previous = device["services"].get(key)
+            if previous:
+                service["details"] = {**previous.get("details", {}), **service["details"]}
+                service["first_seen"] = previous.get("first_seen", now)
+            else:
+                service["first_seen"] = now
+            service["last_seen"] = now
+            device["services"][key] = service
+            self._dirty = True
+            due = time.monotonic() - self._last_save >= SAVE_INTERVAL
+        if due:
+            self._save()
+
+    def _apply_description(self, service: Dict) -> None:
+        described = self._described.get(service.get("location"), {})
+        service["name"] = described.get("fn")
+        service["details"].update(described)
+
+    def _describe(self, mac: str, ip: str, service: Dict) -> None:
+        """Fetch a UPnP device's description, then record its service."""
+        location = service["location"]
+        try:
+            described = describe(location)
+            admin_url = described.get("admin_url")
+            if _own_http_url(admin_url, ip):
+                described.update(banner(admin_url))
+            self._described[location] = described
+        except Exception:
+            pass
+        self._apply_description(service)
+        self._record(mac, ip, service)
+
+    def _read_banner(self, mac: str, ip: str, service: Dict, url: str) -> None:
+        """Read a web service's banner, then record the service."""
+        try:
+            self._banners[url] = banner(url)
+        except Exception:
+            pass
+        service["details"].update(self._banners[url])
+        self._record(mac, ip, service)
+
+    def _process_packet(self, packet) -> None:
+        try:
+            if not (packet.haslayer(Ether) and packet.haslayer(IP) and packet.haslayer(UDP)):
+                return
+            mac, ip = packet[Ether].src, packet[IP].src
+            udp = packet[UDP]
+            if udp.sport == 5353 and packet.haslayer(DNS) and packet[DNS].qr == 1:
+                for service in mdns_services(packet[DNS]):
+                    url = f"http://{ip}:{service['port']}/" if service["kind"] == "web" and service["port"] else None
+                    if url and url not in self._banners:
+                        # Read once per address, away from the capture thread
+                        self._banners[url] = {}
+                        threading.Thread(target=self._read_banner, args=(mac, ip, service, url), daemon=True).start()
+                        continue
+                    if url:
+                        service["details"].update(self._banners[url])
+                    self._record(mac, ip, service)
+            elif 1900 in (udp.sport, udp.dport) and packet.haslayer(Raw):
+                service = ssdp_service(bytes(packet[Raw].load))
+                if not service:
+                    return
+                location = service.get("location")
+                # Only descriptions served by the device itself are fetched
+                if location and not _own_http_url(location, ip):
+                    location = service["location"] = None
+                if location and location not in self._described:
+                    # Fetched once per location, away from the capture thread
+                    self._described[location] = {}
+                    threading.Thread(target=self._describe, args=(mac, ip, service), daemon=True).start()
+                else:
+                    self._apply_description(service)
+                    self._record(mac, ip, service)
+        except Exception as e:
+            error_msg = json.dumps({"error": str(e), "type": "service_discovery_error"})
+            print(error_msg, file=sys.stderr, flush=True)
+
+    def browse(self) -> None:
+        """Ask devices to announce their services; the answers are sniffed."""
+        query = struct.pack("!HHHHHH", 0, 0, len(BROWSE_TYPES), 0, 0, 0)
+        for name in BROWSE_TYPES:
+            for label in name.split("."):
+                query += bytes([len(label)]) + label.encode()
+            query += b"\x00" + struct.pack("!HH", 12, 1)
+        search = (
+            "M-SEARCH * HTTP/1.1\r\n"
+            f"HOST: {SSDP_ADDR[0]}:{SSDP_ADDR[1]}\r\n"
+            'MAN: "ssdp:discover"\r\n'
+            "MX: 2\r\n"
+            "ST: ssdp:all\r\n\r\n"
+        ).encode()
+
+        sock = socket.socket(socket.AF_INET, socket.SOCK_DGRAM, socket.IPPROTO_UDP)
+        try:
+            sock.setsockopt(socket.IPPROTO_IP, socket.IP_MULTICAST_TTL, 2)
+            sock.sendto(query, MDNS_ADDR)
+            sock.sendto(search, SSDP_ADDR)
+        finally:
+            sock.close()
+
+    def start(self):
+        """Start collecting service advertisements"""
+        self.running = True
+
+        def capture_loop():
+            try:
+                sniff(
+                    iface=self.interface,
+                    filter=CAPTURE_FILTER,
+                    prn=self._process_packet,
+                    store=False,
+                    stop_filter=lambda x: not self.running
+                )
+            except Exception as e:
+                error_msg = json.dumps({"error": str(e), "type": "capture_error"})
+                print(error_msg, file=sys.stderr, flush=True)
+
+        def browse_loop():
+            while self.running:
+                try:
+                    self.browse()
+                except OSError as e:
+                    error_msg = json.dumps({"error": str(e), "type": "browse_error"})
+                    print(error_msg, file=sys.stderr, flush=True)
+                for _ in range(BROWSE_INTERVAL):
+                    if not self.running:
+                        break
+                    time.sleep(1)
+                self._save()
+
+        self.capture_thread = threading.Thread(target=capture_loop, daemon=True)
+        self.capture_thread.start()
+        self.browse_thread = threading.Thread(target=browse_loop, daemon=True)
+        self.browse_thread.start()
+
+        start_msg = json.dumps({
+            "status": "started",
+            "interface": self.interface,
+            "type": "service_discovery"
+        })
+        print(start_msg, flush=True)
+
+    def stop(self):
+        """Stop collecting and write what was seen"""
+        self.running = False
+        for thread in (self.capture_thread, self.browse_thread):
+            if thread:
+                thread.join(timeout=2)
+        self._save()
@@ -17,11 +17,13 @@ from .categories import (
     BlockCategory,
     CategoryDefinition,
     CATEGORY_DEFINITIONS,
+    DOH_PROVIDERS,
     check_domain_category,
     check_url_keywords,
     get_all_categories,
     get_category,
     get_category_definition,
+    is_doh_provider,
 )
 from .schedules import (
     DayOfWeek,
@@ -48,6 +50,8 @@ __all__ = [
     "get_all_categories",
     "check_domain_category",
     "check_url_keywords",
+    "DOH_PROVIDERS",
+    "is_doh_provider",
     # Schedules
     "Schedule",
     "ScheduleManager",
@@ -12,18 +12,23 @@ Central blocking system that combines:
 import json
 import re
 from dataclasses import dataclass, field
-from datetime import datetime
+from datetime import timedelta
 from pathlib import Path
 from typing import Any, Callable, Dict, List, Optional, Set, Tuple
 
+from ..utils.clock import parse_timestamp, to_iso, utc_now, utc_now_iso
+from ..utils.config import get_config_path
 from .categories import (
     BlockCategory,
     CategoryDefinition,
     CATEGORY_DEFINITIONS,
+    DOH_PROVIDERS,
     check_domain_category,
     check_url_keywords,
     get_category,
+    is_doh_provider,
 )
+from .rule_io import FORMATS, RuleSet, parse as parse_rules, render as render_rules, unsupported_counts
 from .schedules import Schedule, ScheduleManager, ScheduleType
 
 
@@ -47,7 +52,7 @@ class BlockDecision:
     rule_type: Optional[str] = None
     category: Optional[str] = None
     schedule_id: Optional[str] = None
-    timestamp: str = field(default_factory=lambda: datetime.now().isoformat())
+    timestamp: str = field(default_factory=utc_now_iso)
 
 
 class BlockingEngine:
@@ -74,8 +79,7 @@ class BlockingEngine:
             schedule_file: Path to schedule configuration
         """
         if config_file is None:
-            project_root = Path(__file__).parent.parent.parent
-            config_file = project_root / "config" / "blocklist.json"
+            config_file = get_config_path() / "blocklist.json"
         
         self.config_file = Path(config_file)
         self.schedule_manager = ScheduleManager(schedule_file)
@@ -90,6 +94,22 @@ class BlockingEngine:
         # Whitelist (override blocks)
         self.whitelisted_domains: Set[str] = set()
         
+        # Temporary rules: "<rule_type>:<value>" -> local ISO expiry time.
+        # Rule types are "domain", "category", "keyword" and "allow".
+        self.expirations: Dict[str, str] = {}
+        
+        # DNS bypass policy
+        self.block_doh: bool = False
+        self.forced_dns_devices: Set[str] = set()
+        
+        # QUIC (UDP/443) policy: blocked for everyone or not, with per-device
+        # overrides keyed by device IP (True blocks, False allows)
+        self.block_quic: bool = False
+        self.quic_overrides: Dict[str, bool] = {}
+        
+        # Per-device domain blocks (e.g. app time limits), keyed by device IP
+        self.device_blocks: Dict[str, Set[str]] = {}
+        
         # Callbacks for block events
         self._block_callbacks: List[Callable[[BlockDecision], None]] = []
         
@@ -105,7 +125,15 @@ class BlockingEngine:
             
             self.blocked_domains = set(data.get("blocked_domains", []))
             self.whitelisted_domains = set(data.get("whitelisted_domains", []))
+            self.expirations = dict(data.get("expirations", {}))
             self.blocked_keywords = data.get("blocked_keywords", [])
+            self.block_doh = data.get("block_doh", False)
+            self.forced_dns_devices = set(data.get("forced_dns_devices", []))
+            self.block_quic = data.get("block_quic", False)
+            self.quic_overrides = {ip: bool(b) for ip, b in data.get("quic_overrides", {}).items()}
+            self.device_blocks = {
+                ip: set(domains) for ip, domains in data.get("device_blocks", {}).items()
+            }
             
             # Load blocked categories
             for cat_name in data.get("blocked_categories", []):
@@ -136,9 +164,15 @@ class BlockingEngine:
         data = {
             "blocked_domains": list(self.blocked_domains),
             "whitelisted_domains": list(self.whitelisted_domains),
+            "expirations": self.expirations,
             "blocked_categories": [c.value for c in self.blocked_categories],
             "blocked_keywords": self.blocked_keywords,
             "url_patterns": [p.pattern for p in self.url_patterns],
+            "block_doh": self.block_doh,
+            "forced_dns_devices": sorted(self.forced_dns_devices),
+            "block_quic": self.block_quic,
+            "quic_overrides": self.quic_overrides,
+            "device_blocks": {ip: sorted(d) for ip, d in self.device_blocks.items() if d},
             "custom_rules": [
                 {
                     "id": r.id,
@@ -159,7 +193,8 @@ class BlockingEngine:
         domain: str = "",
         url: str = "",
         content: str = "",
-        check_schedule: bool = True
+        check_schedule: bool = True,
+        device_ip: str = ""
     ) -> BlockDecision:
         """
         Check if content should be blocked.
@@ -169,6 +204,7 @@ class BlockingEngine:
             url: Full URL to check
             content: Page content to check for keywords
             check_schedule: Whether to check time-based schedules
+            device_ip: Requesting device, for per-device DNS policy
             
         Returns:
             BlockDecision with blocking determination
@@ -183,6 +219,27 @@ class BlockingEngine:
                 reason="Domain is whitelisted"
             )
         
+        # Check DoH resolvers (global switch or device forced through local DNS)
+        if is_doh_provider(domain) and (self.block_doh or device_ip in self.forced_dns_devices):
+            decision = BlockDecision(
+                should_block=True,
+                reason=f"Encrypted DNS provider blocked: {domain}",
+                rule_type="doh"
+            )
+            self._notify_block(decision)
+            return decision
+        
+        # Check per-device blocks
+        for blocked in self.device_blocks.get(device_ip, set()):
+            if domain == blocked or domain.endswith('.' + blocked):
+                decision = BlockDecision(
+                    should_block=True,
+                    reason=f"Domain blocked for device: {domain}",
+                    rule_type="device"
+                )
+                self._notify_block(decision)
+                return decision
+        
         # Check direct domain blocks
         if self._check_domain_block(domain):
             decision = BlockDecision(
@@ -334,11 +391,73 @@ class BlockingEngine:
         """Add a callback for block events."""
         self._block_callbacks.append(callback)
     
+    # Temporary rules
+    def _set_expiry(self, rule_type: str, value: str, expires_at: Optional[str]):
+        """Make a rule temporary, or permanent when expires_at is None."""
+        key = f"{rule_type}:{value}"
+        if expires_at:
+            self.expirations[key] = expires_at
+        else:
+            self.expirations.pop(key, None)
+    
+    def get_temporary_rules(self) -> List[dict]:
+        """List temporary rules with the time they have left."""
+        now = utc_now()
+        rules = []
+        for key, expires_at in sorted(self.expirations.items(), key=lambda e: e[1]):
+            rule_type, value = key.split(":", 1)
+            try:
+                remaining = max(0, int((parse_timestamp(expires_at, naive_tz=None) - now).total_seconds()))
+            except ValueError:
+                remaining = 0
+            rules.append({
+                "rule_type": rule_type,
+                "value": value,
+                "expires_at": expires_at,
+                "remaining_seconds": remaining
+            })
+        return rules
+    
+    def prune_expired(self) -> List[dict]:
+        """Remove temporary rules whose time is up. Returns the removed rules."""
+        now = utc_now()
+        expired = []
+        for key, at in self.expirations.items():
+            # Expiries set before UTC storage are naive local times
+            try:
+                if parse_timestamp(at, naive_tz=None) <= now:
+                    expired.append(key)
+            except ValueError:
+                continue
+        if not expired:
+            return []
+        
+        removed = []
+        for key in expired:
+            rule_type, value = key.split(":", 1)
+            del self.expirations[key]
+            if rule_type == "domain":
+                self.blocked_domains.discard(value)
+            elif rule_type == "allow":
+                self.whitelisted_domains.discard(value)
+            elif rule_type == "keyword":
+                self.blocked_keywords = [k for k in self.blocked_keywords if k.lower() != value]
+            elif rule_type == "category":
+                try:
+                    self.blocked_categories.discard(get_category(value))
+                except ValueError:
+                    pass
+            removed.append({"rule_type": rule_type, "value": value})
+        
+        self._save_config()
+        return removed
+    
     # Domain management
-    def block_domain(self, domain: str, reason: str = "") -> bool:
-        """Add a domain to the block list."""
+    def block_domain(self, domain: str, reason: str = "", expires_at: Optional[str] = None) -> bool:
+        """Add a domain to the block list, optionally until expires_at."""
         domain = domain.lower().strip()
         self.blocked_domains.add(domain)
+        self._set_expiry("domain", domain, expires_at)
         self._save_config()
         return True
     
@@ -346,13 +465,15 @@ class BlockingEngine:
         """Remove a domain from the block list."""
         domain = domain.lower().strip()
         self.blocked_domains.discard(domain)
+        self._set_expiry("domain", domain, None)
         self._save_config()
         return True
     
-    def whitelist_domain(self, domain: str) -> bool:
-        """Add a domain to the whitelist."""
+    def whitelist_domain(self, domain: str, expires_at: Optional[str] = None) -> bool:
+        """Add a domain to the whitelist, optionally until expires_at."""
         domain = domain.lower().strip()
         self.whitelisted_domains.add(domain)
+        self._set_expiry("allow", domain, expires_at)
         self._save_config()
         return True
     
@@ -360,15 +481,17 @@ class BlockingEngine:
         """Remove a domain from the whitelist."""
         domain = domain.lower().strip()
         self.whitelisted_domains.discard(domain)
+        self._set_expiry("allow", domain, None)
         self._save_config()
         return True
     
     # Category management
-    def block_category(self, category: str) -> bool:
-        """Block an entire category."""
+    def block_category(self, category: str, expires_at: Optional[str] = None) -> bool:
+        """Block an entire category, optionally until expires_at."""
         try:
             cat = get_category(category)
             self.blocked_categories.add(cat)
+            self._set_expiry("category", cat.value, expires_at)
             self._save_config()
             return True
         except ValueError:
@@ -379,19 +502,21 @@ class BlockingEngine:
         try:
             cat = get_category(category)
             self.blocked_categories.discard(cat)
+            self._set_expiry("category", cat.value, None)
             self._save_config()
             return True
         except ValueError:
             return False
     
     # Keyword management
-    def add_keyword(self, keyword: str) -> bool:
-        """Add a blocked keyword."""
-        if keyword.lower() not in [k.lower() for k in self.blocked_keywords]:
+    def add_keyword(self, keyword: str, expires_at: Optional[str] = None) -> bool:
+        """Add a blocked keyword, optionally until expires_at."""
+        added = keyword.lower() not in [k.lower() for k in self.blocked_keywords]
+        if added:
             self.blocked_keywords.append(keyword)
-            self._save_config()
-            return True
-        return False
+        self._set_expiry("keyword", keyword.lower(), expires_at)
+        self._save_config()
+        return added
     
     def remove_keyword(self, keyword: str) -> bool:
         """Remove a blocked keyword."""
@@ -399,6 +524,7 @@ class BlockingEngine:
         for i, k in enumerate(self.blocked_keywords):
             if k.lower() == keyword_lower:
                 self.blocked_keywords.pop(i)
+                self._set_expiry("keyword", keyword_lower, None)
                 self._save_config()
                 return True
         return False
@@ -424,9 +550,69 @@ class BlockingEngine:
         return False
     
     # Custom rules
+    def set_doh_blocking(self, enabled: bool) -> bool:
+        """Enable or disable blocking of known DoH/DoT providers."""
+        self.block_doh = enabled
+        self._save_config()
+        return True
+    
+    def set_force_dns(self, device_ip: str, enabled: bool) -> bool:
+        """Force a device's DNS through the monitor by denying it encrypted resolvers."""
+        if enabled:
+            self.forced_dns_devices.add(device_ip)
+        else:
+            self.forced_dns_devices.discard(device_ip)
+        self._save_config()
+        return True
+    
+    def set_quic_blocking(self, enabled: bool) -> bool:
+        """Block or allow QUIC for devices without an override."""
+        self.block_quic = enabled
+        self._save_config()
+        return True
+    
+    def set_device_quic(self, device_ip: str, blocked: Optional[bool]) -> bool:
+        """Override the QUIC policy for a device (None follows the global setting)."""
+        if blocked is None:
+            self.quic_overrides.pop(device_ip, None)
+        else:
+            self.quic_overrides[device_ip] = blocked
+        self._save_config()
+        return True
+    
+    def get_quic_policy(self) -> dict:
+        """Get the QUIC blocking policy."""
+        return {
+            "block_quic": self.block_quic,
+            "device_overrides": dict(sorted(self.quic_overrides.items()))
+        }
+    
+    def block_for_device(self, device_ip: str, domains: List[str]) -> bool:
+        """Block domains for a single device."""
+        self.device_blocks.setdefault(device_ip, set()).update(d.lower() for d in domains)
+        self._save_config()
+        return True
+    
+    def unblock_for_device(self, device_ip: str, domains: List[str]) -> bool:
+        """Remove per-device domain blocks."""
+        blocked = self.device_blocks.get(device_ip, set())
+        blocked.difference_update(d.lower() for d in domains)
+        if not blocked:
+            self.device_blocks.pop(device_ip, None)
+        self._save_config()
+        return True
+    
+    def get_dns_policy(self) -> dict:
+        """Get the DNS bypass policy."""
+        return {
+            "block_doh": self.block_doh,
+            "forced_dns_devices": sorted(self.forced_dns_devices),
+            "doh_providers": sorted(DOH_PROVIDERS)
+        }
+    
     def add_rule(self, rule: BlockRule) -> bool:
         """Add a custom blocking rule."""
-        rule.created_at = datetime.now().isoformat()
+        rule.created_at = utc_now_iso()
         self.custom_rules[rule.id] = rule
         self._save_config()
         return True
@@ -468,6 +654,115 @@ class BlockingEngine:
             "active_schedules": len(self.schedule_manager.get_active_schedules())
         }
     
+    # Import/export
+    def export_rules(self, path: str, fmt: str) -> dict:
+        """Write the rules to a file. Temporary rules are exported as permanent."""
+        rules = RuleSet(
+            blocked_domains=set(self.blocked_domains),
+            whitelisted_domains=set(self.whitelisted_domains),
+            blocked_categories={c.value for c in self.blocked_categories},
+            blocked_keywords=set(self.blocked_keywords),
+            url_patterns={p.pattern for p in self.url_patterns},
+        )
+        
+        target = Path(path)
+        target.parent.mkdir(parents=True, exist_ok=True)
+        target.write_text(render_rules(rules, fmt), encoding="utf-8")
+        
+        return {
+            "path": str(target),
+            "format": fmt,
+            "omitted": unsupported_counts(rules, fmt),
+        }
+    
+    def import_rules(self, path: str, fmt: str, dry_run: bool = False) -> dict:
+        """
+        Merge rules from a file into the current ones.
+        
+        Entries that contradict an existing rule (blocking an allowed domain or
+        the reverse), or that the file both blocks and allows, are reported as
+        conflicts and left out. With dry_run nothing is changed.
+        """
+        rules = parse_rules(Path(path).read_text(encoding="utf-8", errors="replace"), fmt)
+        
+        changes: Dict[str, List[str]] = {
+            "blocked_domains": [],
+            "whitelisted_domains": [],
+            "blocked_categories": [],
+            "blocked_keywords": [],
+            "url_patterns": [],
+        }
+        conflicts: List[dict] = []
+        unchanged = 0
+        
+        contradictory = rules.blocked_domains & rules.whitelisted_domains
+        for domain in sorted(contradictory):
+            conflicts.append({"rule_type": "domain", "value": domain,
+                              "reason": "The file both blocks and allows this domain"})
+        
+        for domain in sorted(rules.blocked_domains - contradictory):
+            if domain in self.blocked_domains:
+                unchanged += 1
+            elif domain in self.whitelisted_domains:
+                conflicts.append({"rule_type": "domain", "value": domain,
+                                  "reason": "Domain is currently allowed"})
+            else:
+                changes["blocked_domains"].append(domain)
+        
+        for domain in sorted(rules.whitelisted_domains - contradictory):
+            if domain in self.whitelisted_domains:
+                unchanged += 1
+            elif domain in self.blocked_domains:
+                conflicts.append({"rule_type": "allow", "value": domain,
+                                  "reason": "Domain is currently blocked"})
+            else:
+                changes["whitelisted_domains"].append(domain)
+        
+        current_categories = {c.value for c in self.blocked_categories}
+        for category in sorted(rules.blocked_categories):
+            try:
+                value = get_category(category).value
+            except ValueError:
+                rules.skipped.append(f"blocked_categories: {category}")
+                continue
+            if value in current_categories:
+                unchanged += 1
+            else:
+                changes["blocked_categories"].append(value)
+        
+        current_keywords = {k.lower() for k in self.blocked_keywords}
+        for keyword in sorted(rules.blocked_keywords):
+            if keyword.lower() in current_keywords:
+                unchanged += 1
+            else:
+                changes["blocked_keywords"].append(keyword)
+        
+        current_patterns = {p.pattern for p in self.url_patterns}
+        for pattern in sorted(rules.url_patterns):
+            if pattern in current_patterns:
+                unchanged += 1
+            else:
+                changes["url_patterns"].append(pattern)
+        
+        if not dry_run:
+            self.blocked_domains.update(changes["blocked_domains"])
+            self.whitelisted_domains.update(changes["whitelisted_domains"])
+            self.blocked_categories.update(get_category(c) for c in changes["blocked_categories"])
+            self.blocked_keywords.extend(changes["blocked_keywords"])
+            self.url_patterns.extend(re.compile(p, re.I) for p in changes["url_patterns"])
+            self._save_config()
+        
+        return {
+            "dry_run": dry_run,
+            "format": fmt,
+            "changes": changes,
+            "added": sum(len(v) for v in changes.values()),
+            "unchanged": unchanged,
+            "conflicts": conflicts,
+            "skipped": rules.skipped[:100],
+            "skipped_count": len(rules.skipped),
+        }
+    
     def get_full_config(self) -> dict:
         """Get full blocking configuration."""
         return {
@@ -495,7 +790,8 @@ class BlockingEngine:
             ],
             "schedules": [
                 s.to_dict() for s in self.schedule_manager.list_schedules()
-            ]
+            ],
+            "temporary_rules": self.get_temporary_rules()
         }
 
 
@@ -510,18 +806,35 @@ def main():
     
     parser = argparse.ArgumentParser(description="Website blocking engine")
     parser.add_argument("--action", choices=[
-        "check", "block", "unblock", "whitelist", "status",
+        "check", "block", "unblock", "whitelist", "remove-whitelist", "status",
         "block-category", "unblock-category", "add-keyword",
-        "remove-keyword", "config"
+        "remove-keyword", "config", "dns-policy", "set-doh-blocking",
+        "force-dns", "quic-policy", "set-quic-blocking", "set-device-quic",
+        "block-device", "unblock-device", "prune-expired",
+        "export-rules", "import-rules"
     ], default="status", help="Action to perform")
     parser.add_argument("--domain", help="Domain to check/block")
     parser.add_argument("--url", help="URL to check")
     parser.add_argument("--category", help="Category to block/unblock")
     parser.add_argument("--keyword", help="Keyword to add/remove")
+    parser.add_argument("--device", help="Device IP for per-device policy")
+    parser.add_argument("--domains", help="Comma-separated domains for per-device blocks")
+    parser.add_argument("--enabled", choices=["true", "false"], default="true",
+                        help="Enable or disable the policy")
+    parser.add_argument("--clear", action="store_true",
+                        help="Remove a device's QUIC override instead of setting it")
+    parser.add_argument("--duration", type=int,
+                        help="Minutes until a block/whitelist/category/keyword rule expires")
+    parser.add_argument("--path", help="Rules file to import/export")
+    parser.add_argument("--format", choices=FORMATS, default="json", help="Rules file format")
+    parser.add_argument("--dry-run", action="store_true", help="Preview an import without applying it")
     
     args = parser.parse_args()
     
     engine = BlockingEngine()
+    expires_at = None
+    if args.duration:
+        expires_at = to_iso(utc_now() + timedelta(minutes=args.duration))
     
     try:
         if args.action == "check":
@@ -549,8 +862,8 @@ def main():
             if not args.domain:
                 output_json({"success": False, "error": "No domain specified"})
                 return
-            engine.block_domain(args.domain)
-            output_json({"success": True, "action": "blocked", "domain": args.domain})
+            engine.block_domain(args.domain, expires_at=expires_at)
+            output_json({"success": True, "action": "blocked", "domain": args.domain, "expires_at": expires_at})
         
         elif args.action == "unblock":
             if not args.domain:
@@ -563,15 +876,27 @@ def main():
             if not args.domain:
                 output_json({"success": False, "error": "No domain specified"})
                 return
-            engine.whitelist_domain(args.domain)
-            output_json({"success": True, "action": "whitelisted", "domain": args.domain})
+            engine.whitelist_domain(args.domain, expires_at=expires_at)
+            output_json({"success": True, "action": "whitelisted", "domain": args.domain, "expires_at": expires_at})
+        
+        elif args.action == "remove-whitelist":
+            if not args.domain:
+                output_json({"success": False, "error": "No domain specified"})
+                return
+            engine.remove_whitelist(args.domain)
+            output_json({"success": True, "action": "removed_whitelist", "domain": args.domain})
         
         elif args.action == "block-category":
             if not args.category:
                 output_json({"success": False, "error": "No category specified"})
                 return
-            success = engine.block_category(args.category)
-            output_json({"success": success, "action": "block_category", "category": args.category})
+            success = engine.block_category(args.category, expires_at=expires_at)
+            output_json({
+                "success": success,
+                "action": "block_category",
+                "category": args.category,
+                "expires_at": expires_at
+            })
         
         elif args.action == "unblock-category":
             if not args.category:
@@ -584,8 +909,8 @@ def main():
             if not args.keyword:
                 output_json({"success": False, "error": "No keyword specified"})
                 return
-            engine.add_keyword(args.keyword)
-            output_json({"success": True, "action": "add_keyword", "keyword": args.keyword})
+            engine.add_keyword(args.keyword, expires_at=expires_at)
+            output_json({"success": True, "action": "add_keyword", "keyword": args.keyword, "expires_at": expires_at})
         
         elif args.action == "remove-keyword":
             if not args.keyword:
@@ -600,6 +925,79 @@ def main():
                 "status": engine.get_status()
             })
         
+        elif args.action == "dns-policy":
+            output_json({
+                "success": True,
+                "policy": engine.get_dns_policy()
+            })
+        
+        elif args.action == "set-doh-blocking":
+            engine.set_doh_blocking(args.enabled == "true")
+            output_json({"success": True, "action": "set_doh_blocking", "enabled": engine.block_doh})
+        
+        elif args.action == "force-dns":
+            if not args.device:
+                output_json({"success": False, "error": "No device specified"})
+                return
+            engine.set_force_dns(args.device, args.enabled == "true")
+            output_json({
+                "success": True,
+                "action": "force_dns",
+                "device": args.device,
+                "enabled": args.device in engine.forced_dns_devices
+            })
+        
+        elif args.action == "quic-policy":
+            output_json({
+                "success": True,
+                "policy": engine.get_quic_policy()
+            })
+        
+        elif args.action == "set-quic-blocking":
+            engine.set_quic_blocking(args.enabled == "true")
+            output_json({"success": True, "action": "set_quic_blocking", "enabled": engine.block_quic})
+        
+        elif args.action == "set-device-quic":
+            if not args.device:
+                output_json({"success": False, "error": "No device specified"})
+                return
+            engine.set_device_quic(args.device, None if args.clear else args.enabled == "true")
+            output_json({
+                "success": True,
+                "action": "set_device_quic",
+                "device": args.device,
+                "blocked": engine.quic_overrides.get(args.device)
+            })
+        
+        elif args.action in ("block-device", "unblock-device"):
+            if not args.device or not args.domains:
+                output_json({"success": False, "error": "Device and domains are required"})
+                return
+            domains = [d.strip() for d in args.domains.split(",") if d.strip()]
+            if args.action == "block-device":
+                engine.block_for_device(args.device, domains)
+            else:
+                engine.unblock_for_device(args.device, domains)
+            output_json({
+                "success": True,
+                "action": args.action.replace("-", "_"),
+                "device": args.device,
+                "domains": domains
+            })
+        
+        elif args.action in ("export-rules", "import-rules"):
+            if not args.path:
+                output_json({"success": False, "error": "No path specified"})
+                return
+            if args.action == "export-rules":
+                result = engine.export_rules(args.path, args.format)
+            else:
+                result = engine.import_rules(args.path, args.format, dry_run=args.dry_run)
+            output_json({"success": True, **result})
+        
+        elif args.action == "prune-expired":
+            output_json({"success": True, "expired": engine.prune_expired()})
+        
         elif args.action == "config":
             output_json({
                 "success": True,
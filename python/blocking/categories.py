@@ -253,6 +253,26 @@ CATEGORY_DEFINITIONS: Dict[BlockCategory, CategoryDefinition] = {
 }
 
 
+# Public DNS-over-HTTPS / DNS-over-TLS resolvers used to bypass local DNS filtering
+DOH_PROVIDERS: Set[str] = {
+    "dns.google", "dns.google.com", "8888.google",
+    "cloudflare-dns.com", "mozilla.cloudflare-dns.com", "chrome.cloudflare-dns.com",
+    "one.one.one.one", "1dot1dot1dot1.cloudflare-dns.com", "security.cloudflare-dns.com",
+    "family.cloudflare-dns.com",
+    "dns.quad9.net", "dns9.quad9.net", "dns10.quad9.net", "dns11.quad9.net",
+    "doh.opendns.com", "doh.familyshield.opendns.com",
+    "dns.nextdns.io", "doh.cleanbrowsing.org", "dns.adguard.com", "dns.adguard-dns.com",
+    "doh.mullvad.net", "dns.mullvad.net", "doh.dns.sb", "dns.alidns.com", "doh.pub",
+    "dns.controld.com", "freedns.controld.com",
+}
+
+
+def is_doh_provider(domain: str) -> bool:
+    """Check whether a domain belongs to a known DoH/DoT resolver."""
+    domain = domain.lower().rstrip('.')
+    return any(domain == p or domain.endswith('.' + p) for p in DOH_PROVIDERS)
+
+
 def get_category(category: str) -> BlockCategory:
     """
     Get BlockCategory enum from string.
@@ -0,0 +1,217 @@
+"""
+QUIC Blocker
+Denies QUIC (HTTP/3 over UDP/443) to selected devices so they fall back to
+TCP/TLS, which the proxy can intercept.
+
+A device's QUIC packets are answered with an ICMP port-unreachable from the
+server it tried to reach; browsers and apps treat that as QUIC being
+unavailable and retry over TCP. Downgraded packets, bytes and connections
+are counted per device and minute.
+"""
+
+import ipaddress
+import json
+import sys
+import threading
+import time
+from typing import Dict, List, Optional, Tuple
+
+from scapy.all import ICMP, IP, UDP, Ether, conf, get_if_hwaddr, send, sniff
+
+from ..database.db_manager import DatabaseManager
+from ..utils.clock import to_iso, utc_now
+
+# How often downgrade counters are written to the database (seconds)
+FLUSH_INTERVAL = 60
+
+# Answer the same connection at most this often (seconds)
+ANSWER_INTERVAL = 1.0
+
+
+class QuicBlocker:
+    """
+    QUIC Blocker
+    Rejects UDP/443 from blocked devices with ICMP port-unreachable
+    """
+
+    def __init__(
+        self,
+        interface: str,
+        block_all: bool = False,
+        blocked_devices: Optional[List[str]] = None,
+        allowed_devices: Optional[List[str]] = None
+    ):
+        """
+        Initialize QUIC blocker
+
+        Args:
+            interface: Network interface
+            block_all: Block QUIC for devices without an override
+            blocked_devices: Device IPs always blocked
+            allowed_devices: Device IPs never blocked
+        """
+        self.interface = interface
+        self.block_all = block_all
+        self.overrides: Dict[str, bool] = {ip: True for ip in blocked_devices or []}
+        self.overrides.update({ip: False for ip in allowed_devices or []})
+        self.running = False
+        self.db = DatabaseManager()
+
+        # (minute, device IP) -> [packets, bytes, connections]
+        self._counts: Dict[Tuple[str, str], List[int]] = {}
+        # Connection (device IP, device port, server IP) -> last answer time
+        self._answered: Dict[Tuple[str, int, str], float] = {}
+        self._lock = threading.Lock()
+
+        # Forwarded packets are captured twice (in and back out); only the first copy is handled
+        try:
+            self.our_mac = get_if_hwaddr(interface).lower()
+        except Exception:
+            self.our_mac = None
+
+        conf.verb = 0
+
+    def is_blocked(self, device_ip: str) -> bool:
+        """Whether a device's QUIC is blocked."""
+        return self.overrides.get(device_ip, self.block_all)
+
+    def _process_packet(self, packet):
+        """Reject a QUIC packet from a blocked device."""
+        try:
+            if not (packet.haslayer(IP) and packet.haslayer(UDP)):
+                return
+            if self.our_mac and packet.haslayer(Ether) and packet[Ether].src.lower() == self.our_mac:
+                return
+
+            src = packet[IP].src
+            dst = packet[IP].dst
+            if not ipaddress.ip_address(src).is_private or ipaddress.ip_address(dst).is_private:
+                return
+            if not self.is_blocked(src):
+                return
+
+            connection = (src, packet[UDP].sport, dst)
+            now = time.time()
+            with self._lock:
+                new_connection = connection not in self._answered
+                answer = now - self._answered.get(connection, 0) >= ANSWER_INTERVAL
+                if answer:
+                    self._answered[connection] = now
+
+                counts = self._counts.setdefault((to_iso(utc_now())[:16], src), [0, 0, 0])
+                counts[0] += 1
+                counts[1] += len(packet[IP])
+                counts[2] += 1 if new_connection else 0
+
+            if answer:
+                # ICMP errors quote the offending IP header and the first 8 bytes after it
+                quoted = bytes(packet[IP])[:packet[IP].ihl * 4 + 8]
+                send(IP(src=dst, dst=src) / ICMP(type=3, code=3) / quoted, iface=self.interface, verbose=False)
+
+        except Exception as e:
+            print(json.dumps({"error": str(e), "type": "quic_blocker_error"}), flush=True)
+
+    def flush(self):
+        """Write the downgrade counters gathered since the last flush."""
+        cutoff = time.time() - 300
+        with self._lock:
+            counts, self._counts = self._counts, {}
+            self._answered = {c: t for c, t in self._answered.items() if t >= cutoff}
+        if counts:
+            self.db.add_quic_downgrades(counts)
+
+    def start(self):
+        """Start QUIC blocker"""
+        self.running = True
+
+        def capture_loop():
+            sniff(
+                iface=self.interface,
+                filter="udp dst port 443",
+                prn=self._process_packet,
+                store=False,
+                stop_filter=lambda x: not self.running
+            )
+
+        def flush_loop():
+            while self.running:
+                time.sleep(FLUSH_INTERVAL)
+                try:
+                    self.flush()
+                except Exception as e:
+                    print(json.dumps({"error": str(e), "type": "quic_flush_error"}), file=sys.stderr, flush=True)
+
+        threading.Thread(target=capture_loop, daemon=True).start()
+        threading.Thread(target=flush_loop, daemon=True).start()
+
+        print(json.dumps({
+            "status": "started",
+            "type": "quic_blocker",
+            "interface": self.interface,
+            "block_all": self.block_all,
+            "overrides": len(self.overrides)
+        }), flush=True)
+
+    def stop(self):
+        """Stop QUIC blocker"""
+        self.running = False
+        try:
+            self.flush()
+        except Exception as e:
+            print(json.dumps({"error": str(e), "type": "quic_flush_error"}), file=sys.stderr, flush=True)
+        print(json.dumps({"status": "stopped", "type": "quic_blocker"}), flush=True)
+
+    def set_quic_blocking(self, enabled: bool):
+        """Block or allow QUIC for devices without an override"""
+        self.block_all = enabled
+        print(json.dumps({"action": "quic_blocking_set", "enabled": enabled}), flush=True)
+
+    def set_device_quic(self, device_ip: str, blocked: Optional[bool]):
+        """Override (or with None, stop overriding) one device's QUIC policy"""
+        if blocked is None:
+            self.overrides.pop(device_ip, None)
+        else:
+            self.overrides[device_ip] = blocked
+        print(json.dumps({"action": "device_quic_set", "device_ip": device_ip, "blocked": blocked}), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="QUIC Blocker")
+    parser.add_argument("--interface", "-i", required=True, help="Network interface")
+    parser.add_argument("--block-quic", action="store_true", help="Block QUIC for devices without an override")
+    parser.add_argument("--block-device", nargs="*", default=[], help="Device IPs whose QUIC is always blocked")
+    parser.add_argument("--allow-device", nargs="*", default=[], help="Device IPs whose QUIC is never blocked")
+
+    args = parser.parse_args()
+
+    blocker = QuicBlocker(
+        interface=args.interface,
+        block_all=args.block_quic,
+        blocked_devices=args.block_device,
+        allowed_devices=args.allow_device
+    )
+
+    try:
+        blocker.start()
+
+        while blocker.running:
+            try:
+                line = sys.stdin.readline().strip()
+                if line:
+                    cmd = json.loads(line)
+                    if cmd.get("action") == "stop":
+                        break
+                    elif cmd.get("action") == "set_quic_blocking":
+                        blocker.set_quic_blocking(cmd.get("enabled", True))
+                    elif cmd.get("action") == "set_device_quic":
+                        blocker.set_device_quic(cmd["device_ip"], cmd.get("blocked"))
+            except (json.JSONDecodeError, EOFError):
+                pass
+
+    except KeyboardInterrupt:
+        pass
+    finally:
+        blocker.stop()
@@ -0,0 +1,186 @@
+"""
+Rule Import/Export.
+
+Reads and writes blocking rules in three formats:
+- hosts: "0.0.0.0 example.com" lines (block rules only)
+- adblock: AdBlock Plus filter syntax, "||example.com^" blocks and
+  "@@||example.com^" allows; cosmetic and path filters are skipped
+- json: the native format, carrying every rule type
+"""
+
+import json
+import re
+from dataclasses import dataclass, field
+from datetime import datetime
+from typing import Dict, List, Set
+
+FORMATS = ("hosts", "adblock", "json")
+
+NATIVE_FORMAT = "network-monitor-rules"
+NATIVE_VERSION = 1
+
+# Addresses hosts files use to sink a domain
+SINK_ADDRESSES = {"0.0.0.0", "127.0.0.1", "::", "::1", "0", "::0"}
+
+# Hostnames hosts files map to themselves rather than block
+HOSTS_SKIP = {"localhost", "localhost.localdomain", "local", "broadcasthost", "ip6-localhost", "ip6-loopback"}
+
+DOMAIN_RE = re.compile(r"^(?=.{1,253}$)([a-z0-9_]([a-z0-9_-]{0,61}[a-z0-9])?\.)+[a-z0-9-]{2,63}$")
+
+ADBLOCK_DOMAIN_RE = re.compile(r"^(@@)?\|\|([^/^$*|]+)\^?(\$(.*))?$")
+
+
+@dataclass
+class RuleSet:
+    """Rules read from (or to be written to) a file."""
+    blocked_domains: Set[str] = field(default_factory=set)
+    whitelisted_domains: Set[str] = field(default_factory=set)
+    blocked_categories: Set[str] = field(default_factory=set)
+    blocked_keywords: Set[str] = field(default_factory=set)
+    url_patterns: Set[str] = field(default_factory=set)
+    # Lines that couldn't be understood, as "line N: text"
+    skipped: List[str] = field(default_factory=list)
+
+
+def is_valid_domain(domain: str) -> bool:
+    return bool(DOMAIN_RE.match(domain))
+
+
+def _skip(rules: RuleSet, number: int, line: str):
+    rules.skipped.append(f"line {number}: {line[:200]}")
+
+
+def parse_hosts(text: str) -> RuleSet:
+    """Parse a hosts file; every sunk hostname becomes a block rule."""
+    rules = RuleSet()
+    for number, raw in enumerate(text.splitlines(), 1):
+        line = raw.split("#", 1)[0].strip()
+        if not line:
+            continue
+
+        parts = line.split()
+        if len(parts) < 2 or parts[0] not in SINK_ADDRESSES:
+            _skip(rules, number, raw)
+            continue
+
+        for host in parts[1:]:
+            host = host.lower().rstrip(".")
+            if host in HOSTS_SKIP:
+                continue
+            if is_valid_domain(host):
+                rules.blocked_domains.add(host)
+            else:
+                _skip(rules, number, raw)
+    return rules
+
+
+def parse_adblock(text: str) -> RuleSet:
+    """Parse AdBlock filters; only whole-domain block and exception rules apply."""
+    rules = RuleSet()
+    for number, raw in enumerate(text.splitlines(), 1):
+        line = raw.strip()
+        if not line or line.startswith("!") or line.startswith("["):
+            continue
+
+        match = ADBLOCK_DOMAIN_RE.match(line)
+        if not match:
+            _skip(rules, number, raw)
+            continue
+
+        exception, domain, _, options = match.groups()
+        domain = domain.lower().rstrip(".")
+        # Rules limited to some request types or sites can't be applied as-is
+        if options and options not in ("important", "all"):
+            _skip(rules, number, raw)
+            continue
+        if not is_valid_domain(domain):
+            _skip(rules, number, raw)
+            continue
+
+        if exception:
+            rules.whitelisted_domains.add(domain)
+        else:
+            rules.blocked_domains.add(domain)
+    return rules
+
+
+def parse_json(text: str) -> RuleSet:
+    """Parse the native JSON format."""
+    data = json.loads(text)
+    if not isinstance(data, dict) or data.get("format") != NATIVE_FORMAT:
+        raise ValueError("Not a Network Monitor rules file")
+
+    rules = RuleSet()
+    for key in ("blocked_domains", "whitelisted_domains"):
+        for domain in data.get(key, []):
+            domain = str(domain).lower().strip()
+            if is_valid_domain(domain):
+                getattr(rules, key).add(domain)
+            else:
+                rules.skipped.append(f"{key}: {domain}")
+
+    rules.blocked_categories = {str(c) for c in data.get("blocked_categories", [])}
+    rules.blocked_keywords = {str(k) for k in data.get("blocked_keywords", [])}
+
+    for pattern in data.get("url_patterns", []):
+        try:
+            re.compile(pattern)
+            rules.url_patterns.add(pattern)
+        except re.error:
+            rules.skipped.append(f"url_patterns: {pattern}")
+    return rules
+
+
+def parse(text: str, fmt: str) -> RuleSet:
+    if fmt == "hosts":
+        return parse_hosts(text)
+    if fmt == "adblock":
+        return parse_adblock(text)
+    if fmt == "json":
+        return parse_json(text)
+    raise ValueError(f"Unknown rule format: {fmt}")
+
+
+def render(rules: RuleSet, fmt: str) -> str:
+    """Write rules in a format. Rules the format can't express are left out."""
+    generated = datetime.now().isoformat(timespec="seconds")
+
+    if fmt == "hosts":
+        lines = [f"# Network Monitor block list, exported {generated}"]
+        lines += [f"0.0.0.0 {d}" for d in sorted(rules.blocked_domains)]
+        return "\n".join(lines) + "\n"
+
+    if fmt == "adblock":
+        lines = ["[Adblock Plus 2.0]", f"! Title: Network Monitor rules", f"! Exported: {generated}"]
+        lines += [f"||{d}^" for d in sorted(rules.blocked_domains)]
+        lines += [f"@@||{d}^" for d in sorted(rules.whitelisted_domains)]
+        return "\n".join(lines) + "\n"
+
+    if fmt == "json":
+        return json.dumps({
+            "format": NATIVE_FORMAT,
+            "version": NATIVE_VERSION,
+            "exported_at": generated,
+            "blocked_domains": sorted(rules.blocked_domains),
+            "whitelisted_domains": sorted(rules.whitelisted_domains),
+            "blocked_categories": sorted(rules.blocked_categories),
+            "blocked_keywords": sorted(rules.blocked_keywords),
+            "url_patterns": sorted(rules.url_patterns),
+        }, indent=2) + "\n"
+
+    raise ValueError(f"Unknown rule format: {fmt}")
+
+
+def unsupported_counts(rules: RuleSet, fmt: str) -> Dict[str, int]:
+    """Rules of each type that an export to fmt leaves out."""
+    dropped = {
+        "whitelisted_domains": len(rules.whitelisted_domains),
+        "blocked_categories": len(rules.blocked_categories),
+        "blocked_keywords": len(rules.blocked_keywords),
+        "url_patterns": len(rules.url_patterns),
+    }
+    if fmt == "json":
+        return {}
+    if fmt == "adblock":
+        dropped.pop("whitelisted_domains")
+    return {k: v for k, v in dropped.items() if v}
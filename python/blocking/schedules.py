@@ -15,6 +15,9 @@ from enum import Enum
 from pathlib import Path
 from typing import Any, Dict, List, Optional, Set, Tuple
 
+from ..utils.clock import local_now, utc_now_iso
+from ..utils.config import get_config_path
+
 
 class DayOfWeek(Enum):
     """Days of the week."""
@@ -114,7 +117,7 @@ class Schedule:
         Check if this schedule is currently active.
         
         Args:
-            dt: Datetime to check (defaults to now)
+            dt: Datetime to check (defaults to now in the configured timezone)
             
         Returns:
             True if the schedule is active
@@ -123,7 +126,7 @@ class Schedule:
             return False
         
         if dt is None:
-            dt = datetime.now()
+            dt = local_now()
         
         # Check date range
         if self.start_date:
@@ -256,8 +259,7 @@ class ScheduleManager:
             schedule_file: Path to JSON file storing schedules
         """
         if schedule_file is None:
-            project_root = Path(__file__).parent.parent.parent
-            schedule_file = project_root / "config" / "schedules.json"
+            schedule_file = get_config_path() / "schedules.json"
         
         self.schedule_file = Path(schedule_file)
         self.schedules: Dict[str, Schedule] = {}
@@ -292,7 +294,7 @@ class ScheduleManager:
         Returns:
             True if added successfully
         """
-        schedule.created_at = datetime.now().isoformat()
+        schedule.created_at = utc_now_iso()
         schedule.updated_at = schedule.created_at
         self.schedules[schedule.id] = schedule
         self._save_schedules()
@@ -318,7 +320,7 @@ class ScheduleManager:
             if hasattr(schedule, key):
                 setattr(schedule, key, value)
         
-        schedule.updated_at = datetime.now().isoformat()
+        schedule.updated_at = utc_now_iso()
         self._save_schedules()
         return True
     
@@ -350,7 +352,7 @@ class ScheduleManager:
         Args:
             domain: Domain to check
             category: Category to check
-            dt: Datetime to check (defaults to now)
+            dt: Datetime to check (defaults to now in the configured timezone)
             
         Returns:
             Tuple of (should_block, schedule_id that matched)
@@ -14,10 +14,12 @@ import os
 import sqlite3
 import threading
 from contextlib import contextmanager
-from datetime import datetime, timedelta
+from datetime import datetime, timedelta, timezone
 from pathlib import Path
 from typing import Any, Dict, Generator, List, Optional, Tuple
 
+from ..utils.clock import parse_timestamp, to_local, to_utc_iso, utc_now, utc_now_iso
+from ..utils.config import get_data_path
 from .models import (
     Device,
     DeviceType,
@@ -27,6 +29,9 @@ from .models import (
     TrafficEntry,
 )
 
+# Rows exported between progress reports
+EXPORT_PROGRESS_EVERY = 5000
+
 
 class DatabaseManager:
     """
@@ -35,6 +40,41 @@ class DatabaseManager:
     Provides thread-safe database operations with connection pooling.
     """
     
+    # Seconds a statement waits on another process's lock before failing with
+    # "database is locked"; the app retries writes that still fail
+    BUSY_TIMEOUT = 10
+    
+    # Raw traffic and aggregates as one row source for stats queries
+    # Schema version stored in PRAGMA user_version; see _migrate
+    SCHEMA_VERSION = 3
+    
+    # Columns that were written in local time before timestamps were stored as UTC
+    LOCAL_TIME_COLUMNS = [
+        ("devices", "first_seen"),
+        ("devices", "last_seen"),
+        ("dns_queries", "timestamp"),
+        ("bypass_detections", "timestamp"),
+        ("tls_sessions", "timestamp"),
+    ]
+    
+    # Devices gone this long are recorded as absent in the inventory log
+    ABSENCE_DAYS = 7
+    
+    # Two devices seen on the same IP within this many minutes are in conflict
+    CONFLICT_MINUTES = 10
+    
+    # Flow protocols the proxy records itself, left out of bandwidth so they aren't counted twice
+    PROXIED_FLOW_PROTOCOLS = "('http', 'https')"
+    
+    TRAFFIC_UNION = """(
+        SELECT timestamp, host, category, 1 AS requests, request_size AS bytes_out,
+               response_size AS bytes_in, blocked
+        FROM traffic
+        UNION ALL
+        SELECT minute AS timestamp, host, category, requests, bytes_out, bytes_in, blocked
+        FROM traffic_aggregates
+    )"""
+    
     def __init__(self, db_path: Optional[str] = None):
         """
         Initialize the database manager.
@@ -43,8 +83,7 @@ class DatabaseManager:
             db_path: Path to SQLite database file
         """
         if db_path is None:
-            project_root = Path(__file__).parent.parent.parent
-            db_path = project_root / "database" / "network_monitor.db"
+            db_path = get_data_path() / "network_monitor.db"
         
         self.db_path = Path(db_path)
         self.db_path.parent.mkdir(parents=True, exist_ok=True)
@@ -61,9 +100,13 @@ class DatabaseManager:
         if not hasattr(self._local, 'connection') or self._local.connection is None:
             self._local.connection = sqlite3.connect(
                 str(self.db_path),
+                timeout=self.BUSY_TIMEOUT,
                 check_same_thread=False
             )
             self._local.connection.row_factory = sqlite3.Row
+            # WAL lets the capture processes keep writing while the app reads
+            self._local.connection.execute("PRAGMA journal_mode = WAL")
+            self._local.connection.execute(f"PRAGMA busy_timeout = {self.BUSY_TIMEOUT * 1000}")
             # Enable foreign keys
             self._local.connection.execute("PRAGMA foreign_keys = ON")
         
@@ -90,6 +133,7 @@ class DatabaseManager:
                     nickname TEXT,
                     is_monitored INTEGER DEFAULT 1,
                     has_certificate INTEGER DEFAULT 0,
+                    interception_mode TEXT DEFAULT 'full',
                     first_seen TEXT,
                     last_seen TEXT,
                     total_requests INTEGER DEFAULT 0,
@@ -145,6 +189,8 @@ class DatabaseManager:
                     block_reason TEXT,
                     intercepted INTEGER DEFAULT 1,
                     alerts TEXT DEFAULT '[]',
+                    tags TEXT DEFAULT '[]',
+                    note TEXT,
                     FOREIGN KEY (device_id) REFERENCES devices(id)
                 )
             """)
@@ -166,7 +212,157 @@ class DatabaseManager:
                 )
             """)
             
+            # Per-minute rollups of raw traffic past the raw retention window
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS traffic_aggregates (
+                    minute TEXT NOT NULL,
+                    device_id TEXT,
+                    device_ip TEXT NOT NULL,
+                    host TEXT NOT NULL,
+                    category TEXT,
+                    requests INTEGER DEFAULT 0,
+                    bytes_in INTEGER DEFAULT 0,
+                    bytes_out INTEGER DEFAULT 0,
+                    blocked INTEGER DEFAULT 0,
+                    PRIMARY KEY (minute, device_ip, host)
+                )
+            """)
+            
+            # VPN/Tor/proxy bypass detections
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS bypass_detections (
+                    id TEXT PRIMARY KEY,
+                    timestamp TEXT NOT NULL,
+                    device_id TEXT,
+                    device_ip TEXT NOT NULL,
+                    method TEXT NOT NULL,
+                    protocol TEXT,
+                    endpoint TEXT,
+                    evidence TEXT NOT NULL,
+                    detail TEXT,
+                    FOREIGN KEY (device_id) REFERENCES devices(id)
+                )
+            """)
+            
+            # TLS handshakes seen by the proxy or passively (ClientHello only)
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS tls_sessions (
+                    id TEXT PRIMARY KEY,
+                    timestamp TEXT NOT NULL,
+                    device_id TEXT,
+                    device_ip TEXT NOT NULL,
+                    host TEXT NOT NULL,
+                    source TEXT NOT NULL,
+                    client_tls_version TEXT,
+                    server_tls_version TEXT,
+                    cipher TEXT,
+                    cert_subject TEXT,
+                    cert_issuer TEXT,
+                    cert_not_before TEXT,
+                    cert_not_after TEXT,
+                    self_signed INTEGER DEFAULT 0,
+                    FOREIGN KEY (device_id) REFERENCES devices(id)
+                )
+            """)
+            
+            # Changes to the device inventory: new devices, IP/hostname/vendor changes, long absences
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS device_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    timestamp TEXT NOT NULL,
+                    device_id TEXT NOT NULL,
+                    mac_address TEXT NOT NULL,
+                    event TEXT NOT NULL,
+                    old_value TEXT,
+                    new_value TEXT
+                )
+            """)
+            
+            # Connection-level flows (5-tuple) from passive capture, including non-web traffic
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS flows (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    start_time TEXT NOT NULL,
+                    end_time TEXT NOT NULL,
+                    device_id TEXT,
+                    device_ip TEXT NOT NULL,
+                    device_port INTEGER NOT NULL,
+                    remote_ip TEXT NOT NULL,
+                    remote_port INTEGER NOT NULL,
+                    transport TEXT NOT NULL,
+                    protocol TEXT NOT NULL,
+                    packets INTEGER DEFAULT 0,
+                    bytes_in INTEGER DEFAULT 0,
+                    bytes_out INTEGER DEFAULT 0,
+                    duration_ms INTEGER DEFAULT 0
+                )
+            """)
+            
+            # Per-minute packet/byte counters by protocol, from passive capture
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS protocol_usage (
+                    minute TEXT NOT NULL,
+                    device_id TEXT,
+                    device_ip TEXT NOT NULL,
+                    protocol TEXT NOT NULL,
+                    packets INTEGER DEFAULT 0,
+                    bytes_in INTEGER DEFAULT 0,
+                    bytes_out INTEGER DEFAULT 0,
+                    PRIMARY KEY (minute, device_ip, protocol)
+                )
+            """)
+            
+            # Per-minute QUIC packets rejected so the device falls back to TCP
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS quic_downgrades (
+                    minute TEXT NOT NULL,
+                    device_id TEXT,
+                    device_ip TEXT NOT NULL,
+                    packets INTEGER DEFAULT 0,
+                    bytes INTEGER DEFAULT 0,
+                    flows INTEGER DEFAULT 0,
+                    PRIMARY KEY (minute, device_ip)
+                )
+            """)
+            
+            # Direct vs proxied round trips to reference hosts (NULL when the request failed)
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS overhead_samples (
+                    timestamp TEXT NOT NULL,
+                    host TEXT NOT NULL,
+                    direct_ms REAL,
+                    proxied_ms REAL
+                )
+            """)
+            
+            # Watch-list keyword matches, with the context they were found in
+            cursor.execute("""
+                CREATE TABLE IF NOT EXISTS watch_matches (
+                    timestamp TEXT NOT NULL,
+                    traffic_id TEXT,
+                    list_id TEXT NOT NULL,
+                    term TEXT NOT NULL,
+                    device_id TEXT,
+                    device_ip TEXT NOT NULL,
+                    host TEXT,
+                    url TEXT,
+                    location TEXT NOT NULL,
+                    snippet TEXT
+                )
+            """)
+            
             # Create indexes
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_watch_timestamp ON watch_matches(timestamp)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_tls_timestamp ON tls_sessions(timestamp)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_protocol_device ON protocol_usage(device_id, minute)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_flows_device ON flows(device_id, start_time)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_device_events_device ON device_events(device_id, timestamp)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_device_events_timestamp ON device_events(timestamp)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_flows_start ON flows(start_time)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_quic_device ON quic_downgrades(device_id, minute)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_overhead_timestamp ON overhead_samples(timestamp)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_aggregates_device ON traffic_aggregates(device_id, minute)")
+            cursor.execute("CREATE INDEX IF NOT EXISTS idx_bypass_device ON bypass_detections(device_ip, timestamp)")
             cursor.execute("CREATE INDEX IF NOT EXISTS idx_dns_timestamp ON dns_queries(timestamp)")
             cursor.execute("CREATE INDEX IF NOT EXISTS idx_dns_device ON dns_queries(device_id)")
             cursor.execute("CREATE INDEX IF NOT EXISTS idx_dns_domain ON dns_queries(query_name)")
@@ -204,7 +400,136 @@ class DatabaseManager:
                 END
             """)
             
+            self._init_search_index(cursor)
+            self._migrate(cursor)
+            
+            conn.commit()
+    
+    def _migrate(self, cursor: sqlite3.Cursor):
+        """Bring an existing database up to SCHEMA_VERSION."""
+        version = cursor.execute("PRAGMA user_version").fetchone()[0]
+        
+        if version < 1:
+            # Naive local timestamps become UTC; traffic was always written in
+            # UTC, and naive UTC values still compare correctly with new ones
+            for table, column in self.LOCAL_TIME_COLUMNS:
+                self._convert_timestamps(cursor, table, column)
+        
+        if version < 2:
+            self._add_column(cursor, "devices", "interception_mode", "TEXT DEFAULT 'full'")
+        
+        if version < 3:
+            self._add_column(cursor, "traffic", "tags", "TEXT DEFAULT '[]'")
+            self._add_column(cursor, "traffic", "note", "TEXT")
+        
+        cursor.execute(f"PRAGMA user_version = {self.SCHEMA_VERSION}")
+    
+    @staticmethod
+    def _add_column(cursor: sqlite3.Cursor, table: str, column: str, definition: str):
+        """Add a column unless the table already has it (new databases are created with it)."""
+        columns = [row[1] for row in cursor.execute(f"PRAGMA table_info({table})").fetchall()]
+        if column not in columns:
+            cursor.execute(f"ALTER TABLE {table} ADD COLUMN {column} {definition}")
+    
+    @staticmethod
+    def _convert_timestamps(cursor: sqlite3.Cursor, table: str, column: str):
+        """Rewrite the naive (system local) timestamps in one column as UTC."""
+        rows = cursor.execute(
+            f"SELECT rowid, {column} FROM {table} "
+            f"WHERE {column} IS NOT NULL AND {column} != '' "
+            f"AND {column} NOT LIKE '%+%' AND {column} NOT LIKE '%Z'"
+        ).fetchall()
+        
+        updates = []
+        for rowid, value in rows:
+            try:
+                updates.append((to_utc_iso(value), rowid))
+            except ValueError:
+                continue
+        cursor.executemany(f"UPDATE {table} SET {column} = ? WHERE rowid = ?", updates)
+    
+    @staticmethod
+    def _search_columns(row: str = "") -> str:
+        """Indexed values (URL, host, user agent) of a traffic row, e.g. row="new." in triggers."""
+        return f"""
+            {row}url, {row}host,
+            COALESCE(json_extract({row}request_headers, '$."User-Agent"'),
+                     json_extract({row}request_headers, '$."user-agent"'), '')
+        """
+    
+    def _init_search_index(self, cursor: sqlite3.Cursor):
+        """Drop a search index built without the trigram tokenizer.
+        
+        Word tokens can't match inside a URL or host the way LIKE does, so
+        search falls back to LIKE until the index is rebuilt. The index is
+        built by `rebuild_search_index` (the `rebuild-search-index` action,
+        which the app runs in the background when the index is missing), not
+        here: indexing a large traffic table would hold up whichever call
+        opened the database first.
+        """
+        row = cursor.execute(
+            "SELECT sql FROM sqlite_master WHERE name = 'traffic_search'"
+        ).fetchone()
+        if row and "trigram" not in (row[0] or ""):
+            self._drop_search_index(cursor)
+    
+    def _drop_search_index(self, cursor: sqlite3.Cursor):
+        for trigger in ("traffic_search_ai", "traffic_search_ad", "traffic_search_au"):
+            cursor.execute(f"DROP TRIGGER IF EXISTS {trigger}")
+        cursor.execute("DROP TABLE IF EXISTS traffic_search")
+    
+    def _fill_search_index(self, cursor: sqlite3.Cursor) -> int:
+        """Index every traffic row."""
+        cursor.execute(f"""
+            INSERT INTO traffic_search(rowid, url, host, user_agent)
+            SELECT rowid, {self._search_columns()} FROM traffic
+        """)
+        return cursor.rowcount
+    
+    def rebuild_search_index(self) -> Optional[int]:
+        """Build the URL/host/user-agent search index (keyed by traffic rowid) and its triggers.
+        
+        Returns the number of rows indexed, or None when SQLite has no trigram
+        tokenizer, in which case there is no index and search uses LIKE.
+        """
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            self._drop_search_index(cursor)
+            try:
+                cursor.execute("""
+                    CREATE VIRTUAL TABLE traffic_search USING fts5(
+                        url, host, user_agent, tokenize='trigram'
+                    )
+                """)
+            except sqlite3.OperationalError:
+                conn.commit()
+                return None
+            
+            cursor.execute(f"""
+                CREATE TRIGGER traffic_search_ai AFTER INSERT ON traffic BEGIN
+                    INSERT INTO traffic_search(rowid, url, host, user_agent)
+                    SELECT new.rowid, {self._search_columns('new.')};
+                END
+            """)
+            
+            cursor.execute("""
+                CREATE TRIGGER traffic_search_ad AFTER DELETE ON traffic BEGIN
+                    DELETE FROM traffic_search WHERE rowid = old.rowid;
+                END
+            """)
+            
+            cursor.execute(f"""
+                CREATE TRIGGER traffic_search_au AFTER UPDATE OF url, host, request_headers ON traffic BEGIN
+                    DELETE FROM traffic_search WHERE rowid = old.rowid;
+                    INSERT INTO traffic_search(rowid, url, host, user_agent)
+                    SELECT new.rowid, {self._search_columns('new.')};
+                END
+            """)
+            
+            indexed = self._fill_search_index(cursor)
+            cursor.execute("INSERT INTO traffic_search(traffic_search) VALUES ('optimize')")
             conn.commit()
+            return indexed
     
     # Device operations
     def add_device(self, device: Device) -> bool:
@@ -215,20 +540,150 @@ class DatabaseManager:
             cursor.execute("""
                 INSERT OR REPLACE INTO devices (
                     id, mac_address, ip_address, hostname, device_type,
-                    manufacturer, nickname, is_monitored, has_certificate,
+                    manufacturer, nickname, is_monitored, has_certificate, interception_mode,
                     first_seen, last_seen, total_requests, total_bytes, metadata
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             """, (
                 device.id, device.mac_address, device.ip_address, device.hostname,
                 device.device_type.value, device.manufacturer, device.nickname,
-                1 if device.is_monitored else 0, 1 if device.has_certificate else 0,
-                device.first_seen, device.last_seen, device.total_requests,
+                1 if device.is_monitored else 0, 1 if device.has_certificate else 0, device.interception_mode,
+                to_utc_iso(device.first_seen), to_utc_iso(device.last_seen), device.total_requests,
                 device.total_bytes, json.dumps(device.metadata)
             ))
             
             conn.commit()
             return True
     
+    def upsert_device(self, device_data: Dict[str, Any]) -> List[Dict[str, Any]]:
+        """
+        Record a device seen on the network, logging how it changed since
+        it was last seen. Sets `is_new` on device_data for a device seen for
+        the first time, and returns the events logged.
+        """
+        mac = (device_data.get("mac_address") or device_data.get("mac") or "").lower()
+        ip = device_data.get("ip_address") or device_data.get("ip")
+        if not mac or not ip:
+            return []
+        hostname = device_data.get("hostname") or None
+        vendor = device_data.get("manufacturer") or device_data.get("vendor") or None
+        now = utc_now_iso()
+        
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            cursor.execute("SELECT * FROM devices WHERE mac_address = ?", (mac,))
+            row = cursor.fetchone()
+            
+            events = []
+            if row is None:
+                device_id = device_data.get("id") or "dev_" + mac.replace(":", "").replace("-", "")
+                cursor.execute("""
+                    INSERT INTO devices (id, mac_address, ip_address, hostname, manufacturer, first_seen, last_seen)
+                    VALUES (?, ?, ?, ?, ?, ?, ?)
+                """, (device_id, mac, ip, hostname, vendor, now, now))
+                events.append(("new_device", None, ip))
+            else:
+                device_id = row["id"]
+                if row["ip_address"] != ip:
+                    events.append(("ip_changed", row["ip_address"], ip))
+                if hostname and row["hostname"] and row["hostname"] != hostname:
+                    events.append(("hostname_changed", row["hostname"], hostname))
+                if vendor and row["manufacturer"] and row["manufacturer"] != vendor:
+                    events.append(("vendor_changed", row["manufacturer"], vendor))
+                if row["last_seen"]:
+                    last_seen = parse_timestamp(row["last_seen"])
+                    if utc_now() - last_seen >= timedelta(days=self.ABSENCE_DAYS):
+                        events.append(("returned", row["last_seen"], now))
+                cursor.execute("""
+                    UPDATE devices SET
+                        ip_address = ?,
+                        hostname = COALESCE(?, hostname),
+                        manufacturer = COALESCE(?, manufacturer),
+                        last_seen = ?
+                    WHERE id = ?
+                """, (ip, hostname, vendor, now, device_id))
+            
+            # Other MACs that answered for the same address in the scan
+            for other_mac in device_data.get("conflict_macs") or []:
+                other_mac = other_mac.lower()
+                if other_mac != mac and not self._conflict_logged_today(cursor, device_id, other_mac, ip):
+                    events.append(("ip_conflict", other_mac, ip))
+            
+            cursor.executemany("""
+                INSERT INTO device_events (timestamp, device_id, mac_address, event, old_value, new_value)
+                VALUES (?, ?, ?, ?, ?, ?)
+            """, [(now, device_id, mac, event, old, new) for event, old, new in events])
+            conn.commit()
+        
+        device_data["is_new"] = row is None
+        return [
+            {"timestamp": now, "device_id": device_id, "mac_address": mac, "event": event,
+             "old_value": old, "new_value": new}
+            for event, old, new in events
+        ]
+    
+    def _conflict_logged_today(self, cursor, device_id: str, other_mac: str, ip: str) -> bool:
+        cursor.execute("""
+            SELECT 1 FROM device_events
+            WHERE device_id = ? AND event = 'ip_conflict' AND old_value = ? AND new_value = ?
+                  AND substr(timestamp, 1, 10) = substr(?, 1, 10)
+        """, (device_id, other_mac, ip, utc_now_iso()))
+        return cursor.fetchone() is not None
+    
+    def record_ip_conflicts(self) -> List[Dict[str, Any]]:
+        """
+        Log devices recently seen on the same IP, once a day per pair and
+        address, on both devices.
+        """
+        cutoff = to_utc_iso(utc_now() - timedelta(minutes=self.CONFLICT_MINUTES))
+        now = utc_now_iso()
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            cursor.execute("""
+                SELECT a.id, a.mac_address, b.mac_address AS other_mac, a.ip_address
+                FROM devices a JOIN devices b
+                    ON a.ip_address = b.ip_address AND a.mac_address != b.mac_address
+                WHERE a.last_seen >= ? AND b.last_seen >= ?
+            """, (cutoff, cutoff))
+            conflicts = [
+                row for row in cursor.fetchall()
+                if not self._conflict_logged_today(cursor, row["id"], row["other_mac"], row["ip_address"])
+            ]
+            cursor.executemany("""
+                INSERT INTO device_events (timestamp, device_id, mac_address, event, old_value, new_value)
+                VALUES (?, ?, ?, 'ip_conflict', ?, ?)
+            """, [(now, row["id"], row["mac_address"], row["other_mac"], row["ip_address"]) for row in conflicts])
+            conn.commit()
+        return [
+            {"timestamp": now, "device_id": row["id"], "mac_address": row["mac_address"], "event": "ip_conflict",
+             "old_value": row["other_mac"], "new_value": row["ip_address"]}
+            for row in conflicts
+        ]
+    
+    def record_absences(self) -> List[Dict[str, Any]]:
+        """Log devices not seen for ABSENCE_DAYS, once per absence."""
+        cutoff = to_utc_iso(utc_now() - timedelta(days=self.ABSENCE_DAYS))
+        now = utc_now_iso()
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            cursor.execute("""
+                SELECT id, mac_address, last_seen FROM devices d
+                WHERE last_seen < ? AND NOT EXISTS (
+                    SELECT 1 FROM device_events e
+                    WHERE e.device_id = d.id AND e.event = 'absent' AND e.old_value = d.last_seen
+                )
+            """, (cutoff,))
+            absent = cursor.fetchall()
+            cursor.executemany("""
+                INSERT INTO device_events (timestamp, device_id, mac_address, event, old_value, new_value)
+                VALUES (?, ?, ?, 'absent', ?, NULL)
+            """, [(now, row["id"], row["mac_address"], row["last_seen"]) for row in absent])
+            conn.commit()
+        return [
+            {"timestamp": now, "device_id": row["id"], "mac_address": row["mac_address"], "event": "absent",
+             "old_value": row["last_seen"], "new_value": None}
+            for row in absent
+        ]
+    
     def get_device(self, device_id: str) -> Optional[Device]:
         """Get a device by ID."""
         with self._get_connection() as conn:
@@ -274,8 +729,28 @@ class DatabaseManager:
                 cursor.execute("SELECT * FROM devices WHERE is_monitored = 1 ORDER BY last_seen DESC")
             else:
                 cursor.execute("SELECT * FROM devices ORDER BY last_seen DESC")
+            devices = [self._row_to_device(row) for row in cursor.fetchall()]
             
-            return [self._row_to_device(row) for row in cursor.fetchall()]
+            bandwidth = self._device_bandwidth(cursor)
+            for device in devices:
+                device.bytes_in, device.bytes_out = bandwidth.get(device.id, (0, 0))
+            return devices
+    
+    def _device_bandwidth(self, cursor: sqlite3.Cursor) -> Dict[str, Tuple[int, int]]:
+        """Bytes downloaded and uploaded per device ID, over raw and rolled-up traffic and non-web flows."""
+        cursor.execute(f"""
+            SELECT device_id, SUM(bytes_in), SUM(bytes_out) FROM (
+                SELECT device_id, response_size AS bytes_in, request_size AS bytes_out FROM traffic
+                UNION ALL
+                SELECT device_id, bytes_in, bytes_out FROM traffic_aggregates
+                UNION ALL
+                SELECT device_id, bytes_in, bytes_out FROM flows
+                WHERE protocol NOT IN {self.PROXIED_FLOW_PROTOCOLS}
+            )
+            WHERE device_id IS NOT NULL
+            GROUP BY device_id
+        """)
+        return {row[0]: (row[1] or 0, row[2] or 0) for row in cursor.fetchall()}
     
     def update_device_stats(
         self,
@@ -292,7 +767,7 @@ class DatabaseManager:
                     total_bytes = total_bytes + ?,
                     last_seen = ?
                 WHERE id = ?
-            """, (requests, bytes_transferred, datetime.now().isoformat(), device_id))
+            """, (requests, bytes_transferred, utc_now_iso(), device_id))
             conn.commit()
     
     def _row_to_device(self, row: sqlite3.Row) -> Device:
@@ -307,6 +782,7 @@ class DatabaseManager:
             nickname=row["nickname"],
             is_monitored=bool(row["is_monitored"]),
             has_certificate=bool(row["has_certificate"]),
+            interception_mode=row["interception_mode"] or "full",
             first_seen=row["first_seen"],
             last_seen=row["last_seen"],
             total_requests=row["total_requests"] or 0,
@@ -326,7 +802,7 @@ class DatabaseManager:
                     response_ip, response_ttl, blocked, block_reason, category
                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             """, (
-                query.id, query.timestamp, query.device_id, query.device_ip,
+                query.id, to_utc_iso(query.timestamp), query.device_id, query.device_ip,
                 query.query_name, query.query_type, query.response_ip,
                 query.response_ttl, 1 if query.blocked else 0,
                 query.block_reason, query.category
@@ -360,7 +836,7 @@ class DatabaseManager:
             
             if since:
                 query += " AND timestamp > ?"
-                params.append(since.isoformat())
+                params.append(to_utc_iso(since))
             
             if blocked_only:
                 query += " AND blocked = 1"
@@ -400,24 +876,81 @@ class DatabaseManager:
                     protocol, request_headers, request_body, request_body_type,
                     request_size, status_code, status_message, response_headers,
                     response_body, response_body_type, response_size, duration_ms,
-                    category, sensitivity, blocked, block_reason, intercepted, alerts
-                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            """, (
-                entry.id, entry.timestamp, entry.device_id, entry.device_ip,
-                entry.method, entry.url, entry.host, entry.path,
-                entry.protocol.value, json.dumps(entry.request_headers),
-                entry.request_body, entry.request_body_type, entry.request_size,
-                entry.status_code, entry.status_message,
-                json.dumps(entry.response_headers), entry.response_body,
-                entry.response_body_type, entry.response_size, entry.duration_ms,
-                entry.category, entry.sensitivity, 1 if entry.blocked else 0,
-                entry.block_reason, 1 if entry.intercepted else 0,
-                json.dumps(entry.alerts)
-            ))
+                    category, sensitivity, blocked, block_reason, intercepted, alerts,
+                    tags, note
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            """, self._traffic_values(entry))
             
             conn.commit()
             return True
     
+    def add_traffic_entries(self, entries: List[TrafficEntry]) -> int:
+        """Add a batch of traffic entries in one transaction, skipping existing IDs and unknown devices."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            known = {row["id"] for row in cursor.execute("SELECT id FROM devices")}
+            entries = [e for e in entries if e.device_id in known]
+            
+            cursor.executemany("""
+                INSERT OR IGNORE INTO traffic (
+                    id, timestamp, device_id, device_ip, method, url, host, path,
+                    protocol, request_headers, request_body, request_body_type,
+                    request_size, status_code, status_message, response_headers,
+                    response_body, response_body_type, response_size, duration_ms,
+                    category, sensitivity, blocked, block_reason, intercepted, alerts,
+                    tags, note
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            """, [self._traffic_values(entry) for entry in entries])
+            
+            conn.commit()
+            return max(cursor.rowcount, 0)
+    
+    def import_traffic(self, path: Path, batch_size: int = 500) -> Dict[str, int]:
+        """Import traffic from an NDJSON file (one entry per line) in batches."""
+        imported = 0
+        skipped = 0
+        batch: List[TrafficEntry] = []
+        
+        with open(path, "r", encoding="utf-8") as f:
+            for line in f:
+                line = line.strip()
+                if not line:
+                    continue
+                try:
+                    batch.append(TrafficEntry.from_dict(json.loads(line)))
+                except (ValueError, KeyError, TypeError):
+                    skipped += 1
+                    continue
+                
+                if len(batch) >= batch_size:
+                    added = self.add_traffic_entries(batch)
+                    imported += added
+                    skipped += len(batch) - added
+                    batch = []
+        
+        if batch:
+            added = self.add_traffic_entries(batch)
+            imported += added
+            skipped += len(batch) - added
+        
+        return {"imported": imported, "skipped": skipped}
+    
+    @staticmethod
+    def _traffic_values(entry: TrafficEntry) -> tuple:
+        """Column values for inserting a traffic entry."""
+        return (
+            entry.id, to_utc_iso(entry.timestamp, timezone.utc), entry.device_id, entry.device_ip,
+            entry.method, entry.url, entry.host, entry.path,
+            entry.protocol.value, json.dumps(entry.request_headers),
+            entry.request_body, entry.request_body_type, entry.request_size,
+            entry.status_code, entry.status_message,
+            json.dumps(entry.response_headers), entry.response_body,
+            entry.response_body_type, entry.response_size, entry.duration_ms,
+            entry.category, entry.sensitivity, 1 if entry.blocked else 0,
+            entry.block_reason, 1 if entry.intercepted else 0,
+            json.dumps(entry.alerts), json.dumps(entry.tags), entry.note
+        )
+    
     def get_traffic_entry(self, entry_id: str) -> Optional[TrafficEntry]:
         """Get a traffic entry by ID."""
         with self._get_connection() as conn:
@@ -440,6 +973,28 @@ class DatabaseManager:
         limit: int = 1000
     ) -> List[TrafficEntry]:
         """Get traffic entries with optional filters."""
+        return list(self.iter_traffic(
+            device_id=device_id,
+            host=host,
+            category=category,
+            since=since,
+            blocked_only=blocked_only,
+            has_alerts=has_alerts,
+            limit=limit
+        ))
+    
+    def iter_traffic(
+        self,
+        device_id: Optional[str] = None,
+        host: Optional[str] = None,
+        category: Optional[str] = None,
+        since: Optional[datetime] = None,
+        blocked_only: bool = False,
+        has_alerts: bool = False,
+        limit: Optional[int] = None,
+        batch_size: int = 500
+    ) -> Generator[TrafficEntry, None, None]:
+        """Yield traffic entries a batch at a time, so large results use bounded memory."""
         with self._get_connection() as conn:
             cursor = conn.cursor()
             
@@ -460,7 +1015,7 @@ class DatabaseManager:
             
             if since:
                 query += " AND timestamp > ?"
-                params.append(since.isoformat())
+                params.append(to_utc_iso(since))
             
             if blocked_only:
                 query += " AND blocked = 1"
@@ -468,12 +1023,19 @@ class DatabaseManager:
             if has_alerts:
                 query += " AND alerts != '[]'"
             
-            query += " ORDER BY timestamp DESC LIMIT ?"
-            params.append(limit)
+            query += " ORDER BY timestamp DESC"
+            if limit is not None:
+                query += " LIMIT ?"
+                params.append(limit)
             
             cursor.execute(query, params)
             
-            return [self._row_to_traffic_entry(row) for row in cursor.fetchall()]
+            while True:
+                rows = cursor.fetchmany(batch_size)
+                if not rows:
+                    break
+                for row in rows:
+                    yield self._row_to_traffic_entry(row)
     
     def _row_to_traffic_entry(self, row: sqlite3.Row) -> TrafficEntry:
         """Convert database row to TrafficEntry."""
@@ -503,7 +1065,9 @@ class DatabaseManager:
             blocked=bool(row["blocked"]),
             block_reason=row["block_reason"],
             intercepted=bool(row["intercepted"]),
-            alerts=json.loads(row["alerts"] or "[]")
+            alerts=json.loads(row["alerts"] or "[]"),
+            tags=json.loads(row["tags"] or "[]"),
+            note=row["note"]
         )
     
     # Full-text search
@@ -549,19 +1113,24 @@ class DatabaseManager:
             params = []
             if since:
                 since_clause = " WHERE timestamp > ?"
-                params = [since.isoformat()]
-            
-            # Traffic stats
-            cursor.execute(f"SELECT COUNT(*) FROM traffic{since_clause}", params)
-            traffic_count = cursor.fetchone()[0]
+                params = [to_utc_iso(since)]
             
-            cursor.execute(f"SELECT SUM(request_size), SUM(response_size) FROM traffic{since_clause}", params)
+            # Traffic stats (raw rows plus rolled-up aggregates)
+            cursor.execute(f"SELECT SUM(requests), SUM(bytes_out), SUM(bytes_in), SUM(blocked) FROM {self.TRAFFIC_UNION}{since_clause}", params)
             row = cursor.fetchone()
-            bytes_out = row[0] or 0
-            bytes_in = row[1] or 0
+            traffic_count = row[0] or 0
+            bytes_out = row[1] or 0
+            bytes_in = row[2] or 0
+            blocked_count = row[3] or 0
             
-            cursor.execute(f"SELECT COUNT(*) FROM traffic WHERE blocked = 1{' AND timestamp > ?' if since else ''}", params)
-            blocked_count = cursor.fetchone()[0]
+            # Non-web traffic the proxy never sees, from passive flows
+            cursor.execute(f"""
+                SELECT SUM(bytes_out), SUM(bytes_in) FROM flows
+                WHERE protocol NOT IN {self.PROXIED_FLOW_PROTOCOLS}{' AND start_time > ?' if since else ''}
+            """, params)
+            row = cursor.fetchone()
+            bytes_out += row[0] or 0
+            bytes_in += row[1] or 0
             
             # DNS stats
             cursor.execute(f"SELECT COUNT(*) FROM dns_queries{since_clause}", params)
@@ -576,7 +1145,7 @@ class DatabaseManager:
             
             # Top domains
             cursor.execute(f"""
-                SELECT host, COUNT(*) as cnt FROM traffic
+                SELECT host, SUM(requests) as cnt FROM {self.TRAFFIC_UNION}
                 {since_clause}
                 GROUP BY host
                 ORDER BY cnt DESC
@@ -586,7 +1155,7 @@ class DatabaseManager:
             
             # Top categories
             cursor.execute(f"""
-                SELECT category, COUNT(*) as cnt FROM traffic
+                SELECT category, SUM(requests) as cnt FROM {self.TRAFFIC_UNION}
                 WHERE category IS NOT NULL{' AND timestamp > ?' if since else ''}
                 GROUP BY category
                 ORDER BY cnt DESC
@@ -603,16 +1172,386 @@ class DatabaseManager:
                 "dns_blocked": dns_blocked,
                 "device_count": device_count,
                 "top_domains": top_domains,
-                "top_categories": top_categories
+                "top_categories": top_categories,
+                "traffic_by_hour": self._traffic_by_hour(cursor)
             }
     
+    def _traffic_by_hour(self, cursor: sqlite3.Cursor) -> List[Dict[str, Any]]:
+        """
+        Requests and bytes in each direction per hour over the last 24
+        hours, oldest first.
+        
+        Rows are counted per UTC minute and then placed in hours of the
+        configured zone, so zones with half-hour offsets and days with a
+        DST change bucket correctly.
+        """
+        # Step back from the current local hour in UTC, where every hour is an hour
+        current = to_local(utc_now()).replace(minute=0, second=0, microsecond=0).astimezone(timezone.utc)
+        buckets: Dict[str, Dict[str, Any]] = {}
+        for hours_ago in range(23, -1, -1):
+            start = to_local(current - timedelta(hours=hours_ago))
+            buckets[start.isoformat()] = {
+                "hour": start.hour, "start": to_utc_iso(start), "requests": 0, "bytes_in": 0, "bytes_out": 0
+            }
+        
+        first = min(buckets.values(), key=lambda b: b["start"])["start"]
+        cursor.execute(f"""
+            SELECT substr(timestamp, 1, 16) AS minute, SUM(requests), SUM(bytes_in), SUM(bytes_out)
+            FROM {self.TRAFFIC_UNION}
+            WHERE timestamp >= ?
+            GROUP BY minute
+        """, (first[:16],))
+        for minute, requests, bytes_in, bytes_out in cursor.fetchall():
+            hour = to_local(minute).replace(minute=0, second=0, microsecond=0)
+            bucket = buckets.get(hour.isoformat())
+            if bucket:
+                bucket["requests"] += requests or 0
+                bucket["bytes_in"] += bytes_in or 0
+                bucket["bytes_out"] += bytes_out or 0
+        
+        return list(buckets.values())
+    
+    # Bypass detection operations
+    def add_bypass_detection(
+        self,
+        device_ip: str,
+        method: str,
+        protocol: str,
+        endpoint: str,
+        evidence: str,
+        detail: str = ""
+    ) -> str:
+        """Record a VPN/Tor/proxy detection and return its ID."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            
+            device = self.get_device_by_ip(device_ip)
+            detection_id = f"bypass_{datetime.now().strftime('%Y%m%d_%H%M%S_%f')}"
+            
+            cursor.execute("""
+                INSERT INTO bypass_detections (
+                    id, timestamp, device_id, device_ip, method, protocol,
+                    endpoint, evidence, detail
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            """, (
+                detection_id, utc_now_iso(),
+                device.id if device else None, device_ip, method, protocol,
+                endpoint, evidence, detail
+            ))
+            
+            conn.commit()
+            return detection_id
+    
+    # TLS session operations
+    def add_tls_session(
+        self,
+        device_ip: str,
+        host: str,
+        source: str,
+        client_tls_version: Optional[str] = None,
+        server_tls_version: Optional[str] = None,
+        cipher: Optional[str] = None,
+        cert_subject: Optional[str] = None,
+        cert_issuer: Optional[str] = None,
+        cert_not_before: Optional[str] = None,
+        cert_not_after: Optional[str] = None,
+        self_signed: bool = False
+    ) -> str:
+        """Record a TLS handshake ("proxy" or "passive") and return its ID."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            
+            device = self.get_device_by_ip(device_ip)
+            session_id = f"tls_{datetime.now().strftime('%Y%m%d_%H%M%S_%f')}"
+            
+            cursor.execute("""
+                INSERT INTO tls_sessions (
+                    id, timestamp, device_id, device_ip, host, source,
+                    client_tls_version, server_tls_version, cipher,
+                    cert_subject, cert_issuer, cert_not_before, cert_not_after, self_signed
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            """, (
+                session_id, utc_now_iso(),
+                device.id if device else None, device_ip, host, source,
+                client_tls_version, server_tls_version, cipher,
+                cert_subject, cert_issuer, cert_not_before, cert_not_after, int(self_signed)
+            ))
+            
+            conn.commit()
+            return session_id
+    
+    # Protocol usage operations
+    def add_protocol_usage(self, counters: Dict[Tuple[str, str, str], List[int]]):
+        """Add passive capture counters, keyed (minute, device IP, protocol) -> [packets, bytes in, bytes out]."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            cursor.executemany("""
+                INSERT INTO protocol_usage (minute, device_id, device_ip, protocol, packets, bytes_in, bytes_out)
+                VALUES (?, (SELECT id FROM devices WHERE ip_address = ? ORDER BY last_seen DESC LIMIT 1), ?, ?, ?, ?, ?)
+                ON CONFLICT (minute, device_ip, protocol) DO UPDATE SET
+                    packets = packets + excluded.packets,
+                    bytes_in = bytes_in + excluded.bytes_in,
+                    bytes_out = bytes_out + excluded.bytes_out
+            """, [
+                (minute, device_ip, device_ip, protocol, packets, bytes_in, bytes_out)
+                for (minute, device_ip, protocol), (packets, bytes_in, bytes_out) in counters.items()
+            ])
+            conn.commit()
+    
+    def add_flows(self, flows: List[Dict[str, Any]]):
+        """Add finished flows, as produced by the flow logger."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            cursor.executemany("""
+                INSERT INTO flows (
+                    start_time, end_time, device_id, device_ip, device_port, remote_ip, remote_port,
+                    transport, protocol, packets, bytes_in, bytes_out, duration_ms
+                )
+                VALUES (?, ?, (SELECT id FROM devices WHERE ip_address = ? ORDER BY last_seen DESC LIMIT 1),
+                        ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            """, [
+                (
+                    f["start_time"], f["end_time"], f["device_ip"], f["device_ip"], f["device_port"],
+                    f["remote_ip"], f["remote_port"], f["transport"], f["protocol"], f["packets"],
+                    f["bytes_in"], f["bytes_out"], f["duration_ms"]
+                )
+                for f in flows
+            ])
+            conn.commit()
+    
+    def add_quic_downgrades(self, counters: Dict[Tuple[str, str], List[int]]):
+        """Add QUIC blocker counters, keyed (minute, device IP) -> [packets, bytes, flows]."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            cursor.executemany("""
+                INSERT INTO quic_downgrades (minute, device_id, device_ip, packets, bytes, flows)
+                VALUES (?, (SELECT id FROM devices WHERE ip_address = ? ORDER BY last_seen DESC LIMIT 1), ?, ?, ?, ?)
+                ON CONFLICT (minute, device_ip) DO UPDATE SET
+                    packets = packets + excluded.packets,
+                    bytes = bytes + excluded.bytes,
+                    flows = flows + excluded.flows
+            """, [
+                (minute, device_ip, device_ip, packets, size, flows)
+                for (minute, device_ip), (packets, size, flows) in counters.items()
+            ])
+            conn.commit()
+    
+    # Overhead operations
+    def add_overhead_sample(self, host: str, direct_ms: Optional[float], proxied_ms: Optional[float]):
+        """Add one round of the overhead probe for a reference host."""
+        with self._get_connection() as conn:
+            conn.execute(
+                "INSERT INTO overhead_samples (timestamp, host, direct_ms, proxied_ms) VALUES (?, ?, ?, ?)",
+                (utc_now_iso(), host, direct_ms, proxied_ms)
+            )
+            conn.commit()
+    
+    # Watch list operations
+    def add_watch_matches(self, matches: List[Dict[str, Any]]):
+        """Add keyword watch-list matches (see alerts/watch_lists.py)."""
+        with self._get_connection() as conn:
+            conn.executemany("""
+                INSERT INTO watch_matches
+                    (timestamp, traffic_id, list_id, term, device_id, device_ip, host, url, location, snippet)
+                VALUES (:timestamp, :traffic_id, :list_id, :term, :device_id, :device_ip, :host, :url, :location, :snippet)
+            """, matches)
+            conn.commit()
+    
+    def get_bypass_detections(
+        self,
+        device_id: Optional[str] = None,
+        since: Optional[datetime] = None,
+        limit: int = 100
+    ) -> List[Dict[str, Any]]:
+        """Get bypass detections with optional filters."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            
+            query = "SELECT * FROM bypass_detections WHERE 1=1"
+            params: List[Any] = []
+            
+            if device_id:
+                query += " AND device_id = ?"
+                params.append(device_id)
+            
+            if since:
+                query += " AND timestamp > ?"
+                params.append(to_utc_iso(since))
+            
+            query += " ORDER BY timestamp DESC LIMIT ?"
+            params.append(limit)
+            
+            cursor.execute(query, params)
+            return [dict(row) for row in cursor.fetchall()]
+    
+    def tag_traffic(self, device_ip: str, since: datetime, tag: str) -> int:
+        """Add an alert tag to a device's traffic since a point in time."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            
+            cursor.execute(
+                "SELECT id, alerts FROM traffic WHERE device_ip = ? AND timestamp > ?",
+                (device_ip, to_utc_iso(since))
+            )
+            
+            tagged = 0
+            for row in cursor.fetchall():
+                alerts = json.loads(row["alerts"] or "[]")
+                if tag in alerts:
+                    continue
+                alerts.append(tag)
+                conn.execute(
+                    "UPDATE traffic SET alerts = ? WHERE id = ?",
+                    (json.dumps(alerts), row["id"])
+                )
+                tagged += 1
+            
+            conn.commit()
+            return tagged
+    
+    def annotate_traffic(
+        self,
+        entry_id: str,
+        tags: Optional[List[str]] = None,
+        note: Optional[str] = None
+    ) -> bool:
+        """Replace a traffic entry's tags and/or note (an empty note clears it)."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            
+            if tags is not None:
+                cursor.execute("UPDATE traffic SET tags = ? WHERE id = ?", (json.dumps(tags), entry_id))
+            if note is not None:
+                cursor.execute("UPDATE traffic SET note = ? WHERE id = ?", (note or None, entry_id))
+            
+            found = cursor.execute("SELECT 1 FROM traffic WHERE id = ?", (entry_id,)).fetchone()
+            conn.commit()
+            return found is not None
+    
+    # Aggregation
+    def aggregate_traffic(self, raw_retention_days: int = 7) -> Dict[str, int]:
+        """Roll raw traffic older than the retention window into per-minute aggregates."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            
+            cutoff = (utc_now() - timedelta(days=raw_retention_days)).isoformat()
+            
+            cursor.execute("""
+                INSERT INTO traffic_aggregates (
+                    minute, device_id, device_ip, host, category,
+                    requests, bytes_in, bytes_out, blocked
+                )
+                SELECT substr(timestamp, 1, 16), MAX(device_id), device_ip, host, MAX(category),
+                       COUNT(*), SUM(COALESCE(response_size, 0)), SUM(COALESCE(request_size, 0)),
+                       SUM(blocked)
+                FROM traffic
+                WHERE timestamp < ?
+                GROUP BY substr(timestamp, 1, 16), device_ip, host
+                ON CONFLICT (minute, device_ip, host) DO UPDATE SET
+                    requests = requests + excluded.requests,
+                    bytes_in = bytes_in + excluded.bytes_in,
+                    bytes_out = bytes_out + excluded.bytes_out,
+                    blocked = blocked + excluded.blocked
+            """, (cutoff,))
+            aggregated = cursor.rowcount
+            
+            cursor.execute("DELETE FROM traffic WHERE timestamp < ?", (cutoff,))
+            raw_deleted = cursor.rowcount
+            
+            conn.commit()
+            
+            return {
+                "aggregate_rows": aggregated,
+                "raw_deleted": raw_deleted
+            }
+    
+    # Retention
+    # Tables (and their timestamp column) removed with each retention class
+    RETENTION_TABLES = {
+        "traffic": [
+            ("traffic", "timestamp"),
+            ("traffic_aggregates", "minute"),
+            ("bypass_detections", "timestamp"),
+            ("tls_sessions", "timestamp"),
+            ("protocol_usage", "minute"),
+            ("flows", "start_time"),
+            ("quic_downgrades", "minute"),
+            ("overhead_samples", "timestamp"),
+            ("watch_matches", "timestamp"),
+        ],
+        "dns": [("dns_queries", "timestamp")],
+    }
+    
+    BODIES_CLAUSE = "(request_body IS NOT NULL OR response_body IS NOT NULL)"
+    
+    def _bodies_where(self, cutoffs: Dict[str, Optional[str]]) -> Tuple[str, list]:
+        """Traffic rows whose bodies are past their window but whose rows are not."""
+        where = f"timestamp < ? AND {self.BODIES_CLAUSE}"
+        params = [cutoffs["bodies"]]
+        if cutoffs.get("traffic"):
+            where += " AND timestamp >= ?"
+            params.append(cutoffs["traffic"])
+        return where, params
+    
+    def retention_counts(self, cutoffs: Dict[str, Optional[str]]) -> Dict[str, int]:
+        """Rows each retention class would lose at the given UTC cutoffs (None keeps a class)."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            counts: Dict[str, int] = {}
+            
+            for name, tables in self.RETENTION_TABLES.items():
+                cutoff = cutoffs.get(name)
+                counts[name] = 0
+                if not cutoff:
+                    continue
+                for table, column in tables:
+                    bound = cutoff[:16] if column == "minute" else cutoff
+                    cursor.execute(f"SELECT COUNT(*) FROM {table} WHERE {column} < ?", (bound,))
+                    counts[name] += cursor.fetchone()[0]
+            
+            counts["bodies"] = 0
+            if cutoffs.get("bodies"):
+                where, params = self._bodies_where(cutoffs)
+                cursor.execute(f"SELECT COUNT(*) FROM traffic WHERE {where}", params)
+                counts["bodies"] = cursor.fetchone()[0]
+            
+            return counts
+    
+    def apply_retention(self, cutoffs: Dict[str, Optional[str]]) -> Dict[str, int]:
+        """Delete rows and clear bodies past their retention window."""
+        with self._get_connection() as conn:
+            cursor = conn.cursor()
+            counts: Dict[str, int] = {}
+            
+            for name, tables in self.RETENTION_TABLES.items():
+                cutoff = cutoffs.get(name)
+                counts[name] = 0
+                if not cutoff:
+                    continue
+                for table, column in tables:
+                    bound = cutoff[:16] if column == "minute" else cutoff
+                    cursor.execute(f"DELETE FROM {table} WHERE {column} < ?", (bound,))
+                    counts[name] += cursor.rowcount
+            
+            counts["bodies"] = 0
+            if cutoffs.get("bodies"):
+                where, params = self._bodies_where(cutoffs)
+                cursor.execute(
+                    f"UPDATE traffic SET request_body = NULL, response_body = NULL WHERE {where}",
+                    params
+                )
+                counts["bodies"] = cursor.rowcount
+            
+            conn.commit()
+            return counts
+    
     # Cleanup
     def cleanup_old_data(self, days: int = 30):
         """Delete data older than specified days."""
         with self._get_connection() as conn:
             cursor = conn.cursor()
             
-            cutoff = (datetime.now() - timedelta(days=days)).isoformat()
+            cutoff = (utc_now() - timedelta(days=days)).isoformat()
             
             cursor.execute("DELETE FROM traffic WHERE timestamp < ?", (cutoff,))
             traffic_deleted = cursor.rowcount
@@ -620,6 +1559,14 @@ class DatabaseManager:
             cursor.execute("DELETE FROM dns_queries WHERE timestamp < ?", (cutoff,))
             dns_deleted = cursor.rowcount
             
+            cursor.execute("DELETE FROM bypass_detections WHERE timestamp < ?", (cutoff,))
+            cursor.execute("DELETE FROM traffic_aggregates WHERE minute < ?", (cutoff[:16],))
+            cursor.execute("DELETE FROM protocol_usage WHERE minute < ?", (cutoff[:16],))
+            cursor.execute("DELETE FROM flows WHERE start_time < ?", (cutoff,))
+            cursor.execute("DELETE FROM quic_downgrades WHERE minute < ?", (cutoff[:16],))
+            cursor.execute("DELETE FROM overhead_samples WHERE timestamp < ?", (cutoff,))
+            cursor.execute("DELETE FROM watch_matches WHERE timestamp < ?", (cutoff,))
+            
             conn.commit()
             
             # Vacuum to reclaim space
@@ -648,6 +1595,21 @@ def output_json(data: dict) -> None:
     print(json.dumps(data, default=str), flush=True)
 
 
+def output_record(data: dict) -> None:
+    """Output one record of an NDJSON stream (read line by line by Tauri)."""
+    print(json.dumps({"type": "record", "data": data}, default=str))
+
+
+def output_progress(**progress) -> None:
+    """Report how far a long action has got as a stream record, flushed so it arrives at once."""
+    print(json.dumps({"type": "record", "data": progress}, default=str), flush=True)
+
+
+def output_stream_end(**summary) -> None:
+    """Terminate an NDJSON stream with a summary line."""
+    print(json.dumps({"type": "end", **summary}, default=str), flush=True)
+
+
 def main():
     """CLI entry point for database operations."""
     import argparse
@@ -655,17 +1617,25 @@ def main():
     parser = argparse.ArgumentParser(description="Database management")
     parser.add_argument("--action", choices=[
         "stats", "search", "cleanup", "devices", "traffic", "dns",
-        "get-traffic", "update-device", "export"
+        "get-traffic", "update-device", "export", "bypass-detections",
+        "aggregate", "rebuild-search-index", "import", "annotate-traffic", "record-devices"
     ], default="stats", help="Action to perform")
     parser.add_argument("--query", help="Search query")
     parser.add_argument("--device", help="Device ID filter")
     parser.add_argument("--id", help="Entry ID for get operations")
     parser.add_argument("--monitored", help="Set monitored status (0 or 1)")
+    parser.add_argument("--interception-mode", choices=["full", "metadata_only", "none"],
+                        help="Set how a device's traffic is intercepted")
     parser.add_argument("--host", help="Host filter")
     parser.add_argument("--days", type=int, default=30, help="Cleanup days")
+    parser.add_argument("--hours", type=int, help="Only include the last N hours")
     parser.add_argument("--limit", type=int, default=100, help="Result limit")
-    parser.add_argument("--format", choices=["json", "csv"], default="json", help="Export format")
+    parser.add_argument("--format", choices=["json", "csv", "ndjson"], default="json", help="Export format")
     parser.add_argument("--output", help="Output file path for export")
+    parser.add_argument("--input", help="NDJSON file to import, or JSON list of scanned devices to record")
+    parser.add_argument("--stream", action="store_true", help="Stream results as NDJSON records")
+    parser.add_argument("--tags", help="JSON array of tags to set on a traffic entry")
+    parser.add_argument("--note", help="Note to set on a traffic entry (empty clears it)")
     
     args = parser.parse_args()
     
@@ -701,6 +1671,13 @@ def main():
                 "devices": [d.to_dict() for d in devices]
             })
         
+        elif args.action == "traffic" and args.stream:
+            count = 0
+            for entry in db.iter_traffic(device_id=args.device, host=args.host, limit=args.limit):
+                output_record(entry.to_dict())
+                count += 1
+            output_stream_end(success=True, count=count)
+        
         elif args.action == "traffic":
             entries = db.get_traffic(
                 device_id=args.device,
@@ -724,6 +1701,27 @@ def main():
                 "queries": [q.to_dict() for q in queries]
             })
         
+        elif args.action == "rebuild-search-index":
+            indexed = db.rebuild_search_index()
+            output_json({"success": True, "indexed": indexed or 0, "available": indexed is not None})
+        
+        elif args.action == "aggregate":
+            result = db.aggregate_traffic(raw_retention_days=args.days)
+            output_json({"success": True, "aggregate": result})
+        
+        elif args.action == "bypass-detections":
+            since = utc_now() - timedelta(hours=args.hours) if args.hours else None
+            detections = db.get_bypass_detections(
+                device_id=args.device,
+                since=since,
+                limit=args.limit
+            )
+            output_json({
+                "success": True,
+                "count": len(detections),
+                "detections": detections
+            })
+        
         elif args.action == "get-traffic":
             if not args.id:
                 output_json({"success": False, "error": "No entry ID specified"})
@@ -738,6 +1736,17 @@ def main():
             else:
                 output_json({"success": False, "error": f"Traffic entry not found: {args.id}"})
         
+        elif args.action == "annotate-traffic":
+            if not args.id:
+                output_json({"success": False, "error": "No entry ID specified"})
+                return
+            
+            tags = json.loads(args.tags) if args.tags is not None else None
+            if not db.annotate_traffic(args.id, tags=tags, note=args.note):
+                output_json({"success": False, "error": f"Traffic entry not found: {args.id}"})
+                return
+            output_json({"success": True, "action": "annotated", "id": args.id})
+        
         elif args.action == "update-device":
             if not args.device:
                 output_json({"success": False, "error": "No device ID specified"})
@@ -752,6 +1761,9 @@ def main():
             if args.monitored is not None:
                 device.is_monitored = args.monitored == "1"
             
+            if args.interception_mode is not None:
+                device.interception_mode = args.interception_mode
+            
             db.add_device(device)
             output_json({"success": True, "action": "updated", "device_id": args.device})
         
@@ -760,41 +1772,82 @@ def main():
                 output_json({"success": False, "error": "No output path specified"})
                 return
             
-            # Export based on format
-            stats = db.get_stats()
-            devices = db.list_devices()
-            traffic = db.get_traffic(limit=10000)
-            
-            export_data = {
-                "export_date": datetime.now().isoformat(),
-                "stats": stats,
-                "devices": [d.to_dict() for d in devices],
-                "traffic": [t.to_dict() for t in traffic]
-            }
-            
             output_path = Path(args.output)
             output_path.parent.mkdir(parents=True, exist_ok=True)
-            
-            if args.format == "json":
-                with open(output_path, "w") as f:
-                    json.dump(export_data, f, indent=2, default=str)
-            elif args.format == "csv":
-                import csv
-                # Export traffic as CSV
-                with open(output_path, "w", newline="") as f:
-                    if traffic:
-                        writer = csv.DictWriter(f, fieldnames=traffic[0].to_dict().keys())
-                        writer.writeheader()
-                        for entry in traffic:
-                            writer.writerow(entry.to_dict())
-            
-            output_json({
+            # Written alongside and renamed when done, so a cancelled export leaves no half file
+            partial_path = output_path.with_name(output_path.name + ".part")
+            with db._get_connection() as conn:
+                total = conn.execute("SELECT COUNT(*) FROM traffic").fetchone()[0]
+            
+            def exported():
+                """Traffic entries in order, reporting progress every EXPORT_PROGRESS_EVERY."""
+                for written, entry in enumerate(db.iter_traffic(), 1):
+                    yield entry
+                    if written % EXPORT_PROGRESS_EVERY == 0:
+                        output_progress(written=written, total=total)
+            
+            # Rows are written as they are read, so exports of any size use bounded memory
+            records = 0
+            with open(partial_path, "w", newline="", encoding="utf-8") as f:
+                if args.format == "ndjson":
+                    for entry in exported():
+                        f.write(json.dumps(entry.to_dict(), default=str) + "\n")
+                        records += 1
+                elif args.format == "csv":
+                    import csv
+                    writer = None
+                    for entry in exported():
+                        row = entry.to_dict()
+                        if writer is None:
+                            writer = csv.DictWriter(f, fieldnames=row.keys())
+                            writer.writeheader()
+                        writer.writerow(row)
+                        records += 1
+                else:
+                    header = {
+                        "export_date": utc_now_iso(),
+                        "stats": db.get_stats(),
+                        "devices": [d.to_dict() for d in db.list_devices()],
+                    }
+                    # Same document shape as before, with the traffic array written incrementally
+                    f.write(json.dumps(header, indent=2, default=str)[:-2])
+                    f.write(',\n  "traffic": [')
+                    for entry in exported():
+                        f.write(",\n    " if records else "\n    ")
+                        f.write(json.dumps(entry.to_dict(), default=str))
+                        records += 1
+                    f.write("\n  ]\n}\n")
+            partial_path.replace(output_path)
+            
+            output_stream_end(**{
                 "success": True,
                 "action": "exported",
                 "path": str(output_path),
                 "format": args.format,
-                "records": len(traffic)
+                "records": records
             })
+        
+        elif args.action == "record-devices":
+            if not args.input:
+                output_json({"success": False, "error": "No input path specified"})
+                return
+            
+            with open(args.input) as f:
+                scanned = json.load(f)
+            events = []
+            for device_data in scanned:
+                events.extend(db.upsert_device(device_data))
+            events.extend(db.record_ip_conflicts())
+            events.extend(db.record_absences())
+            output_json({"success": True, "action": "recorded", "devices": len(scanned), "events": events})
+        
+        elif args.action == "import":
+            if not args.input:
+                output_json({"success": False, "error": "No input path specified"})
+                return
+            
+            result = db.import_traffic(Path(args.input))
+            output_json({"success": True, "action": "imported", **result})
     
     except Exception as e:
         output_json({
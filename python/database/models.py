@@ -57,12 +57,15 @@ class Device:
     # Monitoring settings
     is_monitored: bool = True
     has_certificate: bool = False
+    interception_mode: str = "full"  # "full", "metadata_only" or "none"
     
     # Stats
     first_seen: Optional[str] = None
     last_seen: Optional[str] = None
     total_requests: int = 0
     total_bytes: int = 0
+    bytes_in: int = 0  # Downloaded, from recorded traffic (not stored)
+    bytes_out: int = 0  # Uploaded, from recorded traffic (not stored)
     
     # Metadata
     metadata: Dict[str, Any] = field(default_factory=dict)
@@ -79,10 +82,13 @@ class Device:
             "nickname": self.nickname,
             "is_monitored": self.is_monitored,
             "has_certificate": self.has_certificate,
+            "interception_mode": self.interception_mode,
             "first_seen": self.first_seen,
             "last_seen": self.last_seen,
             "total_requests": self.total_requests,
             "total_bytes": self.total_bytes,
+            "bytes_in": self.bytes_in,
+            "bytes_out": self.bytes_out,
             "metadata": self.metadata
         }
     
@@ -99,10 +105,13 @@ class Device:
             nickname=data.get("nickname"),
             is_monitored=data.get("is_monitored", True),
             has_certificate=data.get("has_certificate", False),
+            interception_mode=data.get("interception_mode", "full"),
             first_seen=data.get("first_seen"),
             last_seen=data.get("last_seen"),
             total_requests=data.get("total_requests", 0),
             total_bytes=data.get("total_bytes", 0),
+            bytes_in=data.get("bytes_in", 0),
+            bytes_out=data.get("bytes_out", 0),
             metadata=data.get("metadata", {})
         )
 
@@ -208,6 +217,10 @@ class TrafficEntry:
     # Alerts
     alerts: List[str] = field(default_factory=list)
     
+    # Investigation marks ("reviewed", "suspicious", ...) and a free-form note
+    tags: List[str] = field(default_factory=list)
+    note: Optional[str] = None
+    
     def to_dict(self) -> dict:
         """Convert to dictionary."""
         return {
@@ -236,7 +249,9 @@ class TrafficEntry:
             "blocked": self.blocked,
             "block_reason": self.block_reason,
             "intercepted": self.intercepted,
-            "alerts": self.alerts
+            "alerts": self.alerts,
+            "tags": self.tags,
+            "note": self.note
         }
     
     @classmethod
@@ -268,7 +283,9 @@ class TrafficEntry:
             blocked=data.get("blocked", False),
             block_reason=data.get("block_reason"),
             intercepted=data.get("intercepted", True),
-            alerts=data.get("alerts", [])
+            alerts=data.get("alerts", []),
+            tags=data.get("tags", []),
+            note=data.get("note")
         )
 
 
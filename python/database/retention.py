@@ -0,0 +1,128 @@
+"""
+Data Retention Policy.
+
+Each class of data is kept for its own window, from the `retention` setting:
+
+- traffic: raw rows, rolled-up aggregates, bypass detections, TLS sessions,
+  protocol and QUIC downgrade counters, and overhead probe samples
+- dns: DNS lookups
+- alerts: stored alerts and their incidents
+- bodies: captured request/response bodies, cleared from traffic rows long
+  before the rows themselves are deleted
+
+A window of 0 keeps that class forever. preview() counts what apply() would
+remove without changing anything.
+"""
+
+import json
+from datetime import datetime, timedelta
+from typing import Dict, List, Optional
+
+from ..utils.clock import parse_timestamp, to_iso, utc_now
+from ..utils.config import get_setting
+from .db_manager import DatabaseManager
+
+DEFAULT_POLICY = {
+    "traffic_days": 30,
+    "dns_days": 90,
+    "alerts_days": 365,
+    "bodies_hours": 48,
+}
+
+
+def policy() -> Dict[str, int]:
+    """The configured retention windows, with defaults for anything unset."""
+    configured = get_setting("retention", {}) or {}
+    return {key: int(configured.get(key, default) or 0) for key, default in DEFAULT_POLICY.items()}
+
+
+def cutoffs(now: Optional[datetime] = None) -> Dict[str, Optional[str]]:
+    """The UTC timestamp before which each class is removed (None to keep it forever)."""
+    now = now or utc_now()
+    windows = policy()
+    spans = {
+        "traffic": timedelta(days=windows["traffic_days"]),
+        "dns": timedelta(days=windows["dns_days"]),
+        "alerts": timedelta(days=windows["alerts_days"]),
+        "bodies": timedelta(hours=windows["bodies_hours"]),
+    }
+    return {name: to_iso(now - span) if span else None for name, span in spans.items()}
+
+
+def _alert_engine():
+    # Imported here: the alert engine reads its own window from this module
+    from ..alerts.alert_engine import AlertEngine
+    return AlertEngine()
+
+
+def _expired_alerts(engine, cutoff: Optional[str]) -> int:
+    if not cutoff:
+        return 0
+    limit = parse_timestamp(cutoff)
+    return sum(1 for a in engine.alerts if parse_timestamp(a.timestamp) <= limit)
+
+
+def _report(counts: Dict[str, int], limits: Dict[str, Optional[str]], dry_run: bool) -> dict:
+    windows = policy()
+    keep = {
+        "traffic": f"{windows['traffic_days']}d",
+        "dns": f"{windows['dns_days']}d",
+        "alerts": f"{windows['alerts_days']}d",
+        "bodies": f"{windows['bodies_hours']}h",
+    }
+    keep = {name: window if limits[name] else "forever" for name, window in keep.items()}
+    classes: List[dict] = [
+        {"class": name, "keep": keep[name], "cutoff": limits[name], "rows": counts.get(name, 0)}
+        for name in keep
+    ]
+    return {"dry_run": dry_run, "classes": classes, "total": sum(c["rows"] for c in classes)}
+
+
+def preview(db: Optional[DatabaseManager] = None) -> dict:
+    """What apply() would remove right now, per class."""
+    db = db or DatabaseManager()
+    limits = cutoffs()
+    counts = db.retention_counts(limits)
+    counts["alerts"] = _expired_alerts(_alert_engine(), limits["alerts"])
+    return _report(counts, limits, dry_run=True)
+
+
+def apply(db: Optional[DatabaseManager] = None) -> dict:
+    """Remove everything past its retention window."""
+    db = db or DatabaseManager()
+    limits = cutoffs()
+    counts = db.apply_retention(limits)
+
+    engine = _alert_engine()
+    counts["alerts"] = _expired_alerts(engine, limits["alerts"])
+    if counts["alerts"]:
+        # Saving drops alerts and incidents past the window
+        engine._save_alerts()
+        engine.incidents.save()
+    return _report(counts, limits, dry_run=False)
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Data retention")
+    parser.add_argument("--action", choices=["policy", "preview", "apply"], default="preview")
+
+    args = parser.parse_args()
+
+    try:
+        if args.action == "policy":
+            output_json({"success": True, "policy": policy()})
+        elif args.action == "preview":
+            output_json({"success": True, "retention": preview()})
+        elif args.action == "apply":
+            output_json({"success": True, "retention": apply()})
+
+    except Exception as e:
+        output_json({"success": False, "error": str(e)})
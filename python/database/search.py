@@ -16,6 +16,7 @@ from datetime import datetime, timedelta
 from pathlib import Path
 from typing import Any, Dict, List, Optional, Tuple
 
+from ..utils.clock import configured_zone, parse_timestamp, utc_now, utc_now_iso
 from .db_manager import DatabaseManager
 from .models import TrafficEntry
 
@@ -106,7 +107,7 @@ class SearchEngine:
                 continue
             
             # Apply time filter
-            if since and datetime.fromisoformat(entry.timestamp) < since:
+            if since and parse_timestamp(entry.timestamp) < since:
                 continue
             
             # Apply field filters
@@ -319,8 +320,8 @@ class SearchEngine:
         
         # Boost for recent entries
         try:
-            entry_time = datetime.fromisoformat(entry.timestamp)
-            hours_ago = (datetime.now() - entry_time).total_seconds() / 3600
+            entry_time = parse_timestamp(entry.timestamp)
+            hours_ago = (utc_now() - entry_time).total_seconds() / 3600
             if hours_ago < 24:
                 score += 1.0
             elif hours_ago < 168:  # Week
@@ -408,7 +409,7 @@ class SearchEngine:
         """Add search to history."""
         self._search_history.append({
             "query": query,
-            "timestamp": datetime.now().isoformat(),
+            "timestamp": utc_now_iso(),
             "result_count": result_count
         })
         
@@ -491,7 +492,8 @@ def main():
         elif args.query:
             since = None
             if args.since:
-                since = datetime.fromisoformat(args.since)
+                # Without an offset, --since is a wall-clock time in the configured zone
+                since = parse_timestamp(args.since, naive_tz=configured_zone())
             
             results = engine.search(
                 query=args.query,
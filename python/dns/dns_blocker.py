@@ -7,13 +7,15 @@ import json
 import threading
 from typing import List, Set, Dict, Optional
 from dataclasses import dataclass
-from datetime import datetime
 
 from scapy.all import (
     sniff, send, IP, UDP, DNS, DNSQR, DNSRR, Ether,
     conf, get_if_addr
 )
 
+from ..blocking.categories import is_doh_provider
+from ..utils.clock import utc_now_iso
+
 
 @dataclass
 class BlockedQuery:
@@ -35,7 +37,9 @@ class DNSBlocker:
         interface: str,
         blocked_domains: Optional[List[str]] = None,
         redirect_ip: str = "0.0.0.0",
-        block_mode: str = "nxdomain"  # 'nxdomain', 'redirect', 'drop'
+        block_mode: str = "nxdomain",  # 'nxdomain', 'redirect', 'drop'
+        block_doh: bool = False,
+        forced_devices: Optional[List[str]] = None
     ):
         """
         Initialize DNS blocker
@@ -45,11 +49,16 @@ class DNSBlocker:
             blocked_domains: List of domains to block
             redirect_ip: IP to redirect to (if mode is 'redirect')
             block_mode: How to block - 'nxdomain', 'redirect', or 'drop'
+            block_doh: Deny lookups of known DoH/DoT resolvers for everyone
+            forced_devices: Device IPs denied encrypted resolvers so they fall back to us
         """
         self.interface = interface
         self.blocked_domains: Set[str] = set(d.lower() for d in (blocked_domains or []))
         self.redirect_ip = redirect_ip
         self.block_mode = block_mode
+        self.block_doh = block_doh
+        self.forced_devices: Set[str] = set(forced_devices or [])
+        self.device_blocks: Dict[str, Set[str]] = {}
         self.running = False
         self.blocked_count = 0
         self.blocked_log: List[BlockedQuery] = []
@@ -74,6 +83,20 @@ class DNSBlocker:
         
         return False
     
+    def is_blocked_for_device(self, domain: str, device_ip: str) -> bool:
+        """Check per-device blocks (e.g. app time limits)"""
+        domain = domain.lower().rstrip('.')
+        return any(
+            domain == blocked or domain.endswith('.' + blocked)
+            for blocked in self.device_blocks.get(device_ip, set())
+        )
+    
+    def is_bypass_lookup(self, domain: str, device_ip: str) -> bool:
+        """Check if a lookup is for an encrypted resolver the device may not use"""
+        if not (self.block_doh or device_ip in self.forced_devices):
+            return False
+        return is_doh_provider(domain)
+    
     def _create_nxdomain_response(self, packet) -> Optional[bytes]:
         """Create NXDOMAIN response for blocked domain"""
         try:
@@ -133,13 +156,18 @@ class DNSBlocker:
             domain = domain.rstrip('.')
             
             # Check if blocked
-            if not self.is_blocked(domain):
+            device_ip = packet[IP].src
+            if not (
+                self.is_blocked(domain)
+                or self.is_blocked_for_device(domain, device_ip)
+                or self.is_bypass_lookup(domain, device_ip)
+            ):
                 return
             
             # Log blocked query
             self.blocked_count += 1
             blocked_query = BlockedQuery(
-                timestamp=datetime.now().isoformat(),
+                timestamp=utc_now_iso(),
                 device_ip=packet[IP].src,
                 domain=domain,
                 action=self.block_mode
@@ -219,6 +247,34 @@ class DNSBlocker:
             "domain": domain
         }), flush=True)
     
+    def set_doh_blocking(self, enabled: bool):
+        """Enable or disable DoH/DoT resolver blocking for all devices"""
+        self.block_doh = enabled
+        print(json.dumps({
+            "action": "doh_blocking",
+            "enabled": enabled
+        }), flush=True)
+    
+    def set_force_dns(self, device_ip: str, enabled: bool):
+        """Force a device's DNS through the monitor"""
+        if enabled:
+            self.forced_devices.add(device_ip)
+        else:
+            self.forced_devices.discard(device_ip)
+        print(json.dumps({
+            "action": "force_dns",
+            "device_ip": device_ip,
+            "enabled": enabled
+        }), flush=True)
+    
+    def block_device_domains(self, device_ip: str, domains: List[str]):
+        """Block domains for a single device"""
+        self.device_blocks.setdefault(device_ip, set()).update(d.lower() for d in domains)
+    
+    def unblock_device_domains(self, device_ip: str, domains: List[str]):
+        """Remove per-device domain blocks"""
+        self.device_blocks.get(device_ip, set()).difference_update(d.lower() for d in domains)
+    
     def get_blocked_log(self) -> List[Dict]:
         """Get log of blocked queries"""
         return [
@@ -242,6 +298,8 @@ if __name__ == "__main__":
     parser.add_argument("--domains", "-d", nargs="*", default=[], help="Domains to block")
     parser.add_argument("--mode", "-m", choices=["nxdomain", "redirect", "drop"], default="nxdomain")
     parser.add_argument("--redirect-ip", "-r", default="0.0.0.0", help="Redirect IP (for redirect mode)")
+    parser.add_argument("--block-doh", action="store_true", help="Block known DoH/DoT resolvers")
+    parser.add_argument("--force-dns", nargs="*", default=[], help="Device IPs forced through local DNS")
     
     args = parser.parse_args()
     
@@ -249,7 +307,9 @@ if __name__ == "__main__":
         interface=args.interface,
         blocked_domains=args.domains,
         redirect_ip=args.redirect_ip,
-        block_mode=args.mode
+        block_mode=args.mode,
+        block_doh=args.block_doh,
+        forced_devices=args.force_dns
     )
     
     try:
@@ -266,6 +326,14 @@ if __name__ == "__main__":
                         blocker.add_domain(cmd["domain"])
                     elif cmd.get("action") == "remove":
                         blocker.remove_domain(cmd["domain"])
+                    elif cmd.get("action") == "set_doh_blocking":
+                        blocker.set_doh_blocking(cmd.get("enabled", True))
+                    elif cmd.get("action") == "block_device_domains":
+                        blocker.block_device_domains(cmd["device_ip"], cmd.get("domains", []))
+                    elif cmd.get("action") == "unblock_device_domains":
+                        blocker.unblock_device_domains(cmd["device_ip"], cmd.get("domains", []))
+                    elif cmd.get("action") == "force_dns":
+                        blocker.set_force_dns(cmd["device_ip"], cmd.get("enabled", True))
             except (json.JSONDecodeError, EOFError):
                 pass
                 
@@ -7,12 +7,13 @@ Works without any installation on target devices
 import json
 import sys
 import threading
-from datetime import datetime
 from typing import Callable, Optional, Dict, List
 from dataclasses import dataclass, asdict
 
 from scapy.all import sniff, DNS, DNSQR, DNSRR, IP, UDP, Ether, conf
 
+from ..utils.clock import utc_now_iso
+
 
 @dataclass
 class DNSQuery:
@@ -126,7 +127,7 @@ class DNSCapture:
                 
                 query = DNSQuery(
                     id=self.query_count,
-                    timestamp=datetime.now().isoformat(),
+                    timestamp=utc_now_iso(),
                     device_ip=device_ip,
                     device_mac=device_mac.upper(),
                     query_name=query_name,
@@ -160,7 +161,7 @@ class DNSCapture:
                 
                 query = DNSQuery(
                     id=self.query_count,
-                    timestamp=datetime.now().isoformat(),
+                    timestamp=utc_now_iso(),
                     device_ip=device_ip,
                     device_mac=device_mac.upper(),
                     query_name=query_name,
@@ -11,6 +11,8 @@ from datetime import datetime
 from enum import IntEnum
 from typing import Optional
 
+from ..utils.clock import utc_now, utc_now_iso
+
 
 class DNSRecordType(IntEnum):
     """DNS record types."""
@@ -248,7 +250,7 @@ class DNSParser:
                 answers=answers,
                 authority=authority,
                 additional=additional,
-                timestamp=timestamp or datetime.now(),
+                timestamp=timestamp or utc_now(),
                 source_ip=source_ip,
                 source_mac=source_mac,
                 dest_ip=dest_ip
@@ -470,7 +472,7 @@ def parse_dns_from_scapy(packet) -> Optional[dict]:
             query_type = questions[0]["type"]
             
         return {
-            "timestamp": datetime.now().isoformat(),
+            "timestamp": utc_now_iso(),
             "transaction_id": dns.id,
             "is_response": bool(dns.qr),
             "opcode": dns.opcode,
@@ -6,8 +6,13 @@ Provides HTTPS traffic interception, decryption, and analysis:
 - Content decoding (JSON, forms, compression)
 - Traffic parsing and categorization
 - Transparent proxy with mitmproxy
+- Guest mode logging, rate limiting and notice page
+- Body capture policy (what bodies are stored)
+- Interception exclusions (hosts passed through untouched)
+- Thumbnails of captured images
 """
 
+from .body_capture import BodyCapturePolicy
 from .cert_generator import (
     CERT_PROFILES,
     CertificateGenerator,
@@ -18,6 +23,9 @@ from .content_decoder import (
     ContentType,
     DecodedContent,
 )
+from .guest_mode import GuestMonitor
+from .intercept_exclusions import InterceptExclusion, InterceptExclusions
+from .thumbnails import ThumbnailCache
 from .traffic_parser import (
     ParsedCookie,
     ParsedFlow,
@@ -62,4 +70,13 @@ __all__ = [
     "FlowEvent",
     "setup_windows_redirect",
     "cleanup_windows_redirect",
+    # Guest Mode
+    "GuestMonitor",
+    # Body Capture
+    "BodyCapturePolicy",
+    # Interception Exclusions
+    "InterceptExclusion",
+    "InterceptExclusions",
+    # Thumbnails
+    "ThumbnailCache",
 ]
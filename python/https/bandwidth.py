@@ -0,0 +1,159 @@
+"""
+Bandwidth Shaping.
+
+Caps the throughput of selected devices with a token bucket per device IP,
+filled at the device's limit and holding at most one second of traffic.
+Request and response bodies both draw from it; a flow that overdraws the
+bucket is held back until the debt is repaid, so a device stays at its limit
+on average however many connections it opens.
+
+Live usage is written to the workspace data directory (bandwidth_usage.json)
+at most every USAGE_INTERVAL seconds while shaped devices are active:
+
+    {"updated_at": ..., "devices": {ip: {"limit_kbps": ...,
+     "upload_bytes_per_sec": ..., "download_bytes_per_sec": ...,
+     "throttled_ms": ...}}}
+"""
+
+import asyncio
+import json
+import threading
+import time
+from collections import deque
+from pathlib import Path
+from typing import Deque, Dict, List, Optional, Tuple
+
+from ..utils.clock import utc_now_iso
+from ..utils.config import get_data_path
+
+# Seconds of traffic the usage rates are averaged over
+USAGE_WINDOW = 5.0
+
+# Least time between two usage file writes (seconds)
+USAGE_INTERVAL = 2.0
+
+# Rough size of the request or response line and headers, in bytes
+HEADER_BYTES = 400
+
+
+class BandwidthShaper:
+    """mitmproxy addon that holds back flows from devices over their limit."""
+
+    def __init__(self, limits: Optional[Dict[str, int]] = None, usage_file: Optional[Path] = None):
+        """
+        Initialize the shaper.
+
+        Args:
+            limits: Device IP -> limit in kilobits per second
+            usage_file: Path of the live usage file
+        """
+        self.limits: Dict[str, int] = dict(limits or {})
+        self.usage_file = Path(usage_file) if usage_file else get_data_path() / "bandwidth_usage.json"
+        self.lock = threading.Lock()
+
+        # Device IP -> [tokens in bytes, last refill time]
+        self._buckets: Dict[str, List[float]] = {}
+        # Device IP -> (time, upload bytes, download bytes) within the usage window
+        self._samples: Dict[str, Deque[Tuple[float, int, int]]] = {}
+        # Device IP -> milliseconds flows were held back within the usage window
+        self._throttled: Dict[str, Deque[Tuple[float, float]]] = {}
+        self._last_write = 0.0
+
+    def set_limits(self, limits: Dict[str, int]):
+        """Replace the per-device limits."""
+        with self.lock:
+            self.limits = {ip: kbps for ip, kbps in limits.items() if kbps > 0}
+            self._buckets = {ip: b for ip, b in self._buckets.items() if ip in self.limits}
+            self._samples = {ip: s for ip, s in self._samples.items() if ip in self.limits}
+            self._throttled = {ip: t for ip, t in self._throttled.items() if ip in self.limits}
+        self._write_usage(force=True)
+
+    def take(self, ip: str, upload: int = 0, download: int = 0) -> float:
+        """
+        Draw a flow's bytes from the device's bucket.
+
+        Returns:
+            Seconds the flow must wait to stay within the limit (0 for unshaped devices)
+        """
+        now = time.monotonic()
+        with self.lock:
+            kbps = self.limits.get(ip)
+            if not kbps:
+                return 0.0
+
+            rate = kbps * 1000 / 8.0
+            tokens, last = self._buckets.get(ip, [rate, now])
+            tokens = min(rate, tokens + (now - last) * rate) - (upload + download)
+            self._buckets[ip] = [tokens, now]
+            delay = -tokens / rate if tokens < 0 else 0.0
+
+            self._samples.setdefault(ip, deque()).append((now, upload, download))
+            if delay:
+                self._throttled.setdefault(ip, deque()).append((now, delay * 1000))
+        return delay
+
+    def usage(self) -> Dict[str, Dict[str, float]]:
+        """Per-device rates over the last USAGE_WINDOW seconds."""
+        cutoff = time.monotonic() - USAGE_WINDOW
+        devices = {}
+        with self.lock:
+            for ip, kbps in self.limits.items():
+                samples = self._samples.get(ip, deque())
+                while samples and samples[0][0] < cutoff:
+                    samples.popleft()
+                throttled = self._throttled.get(ip, deque())
+                while throttled and throttled[0][0] < cutoff:
+                    throttled.popleft()
+
+                devices[ip] = {
+                    "limit_kbps": kbps,
+                    "upload_bytes_per_sec": sum(s[1] for s in samples) / USAGE_WINDOW,
+                    "download_bytes_per_sec": sum(s[2] for s in samples) / USAGE_WINDOW,
+                    "throttled_ms": round(sum(t[1] for t in throttled)),
+                }
+        return devices
+
+    def _write_usage(self, force: bool = False):
+        now = time.monotonic()
+        if not force and now - self._last_write < USAGE_INTERVAL:
+            return
+        self._last_write = now
+
+        content = json.dumps({"updated_at": utc_now_iso(), "devices": self.usage()})
+        try:
+            self.usage_file.parent.mkdir(parents=True, exist_ok=True)
+            tmp = self.usage_file.with_name(self.usage_file.name + ".tmp")
+            tmp.write_text(content, encoding="utf-8")
+            tmp.replace(self.usage_file)
+        except IOError:
+            pass
+
+    async def _shape(self, flow, upload: int = 0, download: int = 0):
+        ip = flow.client_conn.peername[0] if flow.client_conn.peername else ""
+        if ip not in self.limits:
+            return
+        delay = self.take(ip, upload, download)
+        self._write_usage()
+        if delay:
+            await asyncio.sleep(delay)
+
+    async def request(self, flow):
+        """Hold back the request while its device is over its limit."""
+        # Answered by the proxy itself (blocked, rate-limited guest, ...)
+        if flow.response:
+            return
+        await self._shape(flow, upload=HEADER_BYTES + _body_size(flow.request))
+
+    async def response(self, flow):
+        """Hold back the response while its device is over its limit."""
+        await self._shape(flow, download=HEADER_BYTES + _body_size(flow.response))
+
+
+def _body_size(message) -> int:
+    """Body size, from the header when the body is streamed rather than read."""
+    if message.raw_content is not None:
+        return len(message.raw_content)
+    try:
+        return int(message.headers.get("content-length", 0))
+    except ValueError:
+        return 0
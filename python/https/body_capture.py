@@ -0,0 +1,81 @@
+"""
+Body Capture Policy.
+
+Decides which request and response bodies are kept with recorded traffic.
+Capture can be off altogether, skipped for content types (media by default),
+skipped above a size, and never done for sensitive hosts such as banking
+sites. A skipped body is dropped from the flow before it is emitted; the
+flow's metadata records why. Keyword checks run on the content in transit,
+so they are unaffected.
+"""
+
+from dataclasses import dataclass, field
+from typing import Any, Dict, List, Optional
+
+DEFAULT_SKIP_TYPES = ["image/*", "video/*", "audio/*", "font/*"]
+
+
+@dataclass
+class BodyCapturePolicy:
+    """Which bodies are stored; mirrors the `body_capture` setting."""
+    enabled: bool = True
+    max_bytes: int = 1024 * 1024
+    skip_content_types: List[str] = field(default_factory=lambda: list(DEFAULT_SKIP_TYPES))
+    excluded_hosts: List[str] = field(default_factory=list)
+
+    @classmethod
+    def from_dict(cls, data: Dict[str, Any]) -> "BodyCapturePolicy":
+        """Create from the setting's JSON, with defaults for anything missing."""
+        default = cls()
+        return cls(
+            enabled=bool(data.get("enabled", default.enabled)),
+            max_bytes=int(data.get("max_bytes", default.max_bytes)),
+            skip_content_types=[t.lower() for t in data.get("skip_content_types", default.skip_content_types)],
+            excluded_hosts=[h.lower() for h in data.get("excluded_hosts", default.excluded_hosts)],
+        )
+
+    def host_excluded(self, host: str) -> bool:
+        """Whether host is an excluded host or one of its subdomains."""
+        host = host.lower().rstrip(".")
+        return any(host == h or host.endswith("." + h) for h in self.excluded_hosts)
+
+    def type_skipped(self, mime_type: str) -> bool:
+        """Whether a MIME type matches a skipped type ("image/*" matches any image)."""
+        mime_type = mime_type.split(";", 1)[0].strip().lower()
+        for pattern in self.skip_content_types:
+            if pattern.endswith("/*"):
+                if mime_type.startswith(pattern[:-1]):
+                    return True
+            elif mime_type == pattern:
+                return True
+        return False
+
+    def skip_reason(self, host: str, mime_type: str, size: int) -> Optional[str]:
+        """Why a body would not be stored, or None if it would."""
+        if not self.enabled:
+            return "disabled"
+        if self.host_excluded(host):
+            return "excluded_host"
+        if self.type_skipped(mime_type):
+            return "content_type"
+        if size > self.max_bytes:
+            return "too_large"
+        return None
+
+    def apply(self, flow: Dict[str, Any]) -> Dict[str, Any]:
+        """Drop bodies the policy doesn't keep from a parsed flow dict, in place."""
+        host = (flow.get("request") or {}).get("host", "")
+        for part in ("request", "response"):
+            message = flow.get(part)
+            if not message or not message.get("body"):
+                continue
+            body = message["body"]
+            reason = self.skip_reason(
+                host,
+                body.get("mime_type") or message.get("content_type") or "",
+                body.get("raw_size") or message.get("content_length") or 0,
+            )
+            if reason:
+                message["body"] = None
+                message.setdefault("metadata", {})["body_skipped"] = reason
+        return flow
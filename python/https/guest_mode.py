@@ -0,0 +1,153 @@
+"""
+Guest Mode.
+
+Unknown devices are allowed internet access but every request they make is
+logged, they are rate-limited, and their first plain-HTTP request is answered
+with a one-time "this network is monitored" notice page.
+
+The log is JSON lines in the workspace data directory (guest_log.jsonl):
+
+    {"timestamp": ..., "event": "request" | "rate_limited" | "notice",
+     "device_id": ..., "device_ip": ..., "method": ..., "host": ..., "url": ...}
+"""
+
+import html
+import json
+import threading
+import time
+from pathlib import Path
+from typing import Dict, List, Optional, Set
+
+from ..utils.clock import utc_now_iso
+from ..utils.config import get_data_path
+
+# Rotate the log to guest_log.jsonl.1 past this size
+MAX_LOG_BYTES = 10 * 1024 * 1024
+
+NOTICE_PAGE = """<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>This network is monitored</title>
+<style>
+  body {{ font-family: -apple-system, "Segoe UI", sans-serif; background: #111827; color: #e5e7eb;
+         display: flex; align-items: center; justify-content: center; min-height: 100vh; margin: 0; }}
+  main {{ max-width: 32rem; padding: 2rem; background: #1f2937; border-radius: 0.75rem; }}
+  h1 {{ font-size: 1.4rem; margin-top: 0; }}
+  a {{ display: inline-block; margin-top: 1rem; padding: 0.6rem 1.2rem; background: #2563eb;
+       color: white; border-radius: 0.5rem; text-decoration: none; }}
+</style>
+</head>
+<body>
+<main>
+  <h1>This network is monitored</h1>
+  <p>You are connected as a guest. Internet access is provided, but web traffic
+  from this device is logged and may be rate-limited.</p>
+  <p>This notice is shown once.</p>
+  <a href="{url}">Continue to {host}</a>
+</main>
+</body>
+</html>
+"""
+
+
+class GuestMonitor:
+    """Tracks guest devices, their request budgets and notice state."""
+
+    def __init__(
+        self,
+        guests: Optional[Dict[str, str]] = None,
+        requests_per_minute: int = 30,
+        log_file: Optional[Path] = None
+    ):
+        """
+        Initialize the guest monitor.
+
+        Args:
+            guests: Guest device IP -> device ID
+            requests_per_minute: Request budget per guest device
+            log_file: Path of the guest request log
+        """
+        self.guests: Dict[str, str] = dict(guests or {})
+        self.requests_per_minute = requests_per_minute
+        self.log_file = Path(log_file) if log_file else get_data_path() / "guest_log.jsonl"
+        self.lock = threading.Lock()
+
+        # Device IP -> (tokens, last refill time)
+        self._buckets: Dict[str, List[float]] = {}
+        self.noticed: Set[str] = self._load_noticed()
+
+    def _load_noticed(self) -> Set[str]:
+        """Device IDs that have already seen the notice page."""
+        noticed = set()
+        for path in (self.log_file.with_name(self.log_file.name + ".1"), self.log_file):
+            if not path.exists():
+                continue
+            try:
+                with open(path, "r", encoding="utf-8") as f:
+                    for line in f:
+                        try:
+                            entry = json.loads(line)
+                        except json.JSONDecodeError:
+                            continue
+                        if entry.get("event") == "notice" and entry.get("device_id"):
+                            noticed.add(entry["device_id"])
+            except IOError:
+                pass
+        return noticed
+
+    def set_guests(self, guests: Dict[str, str], requests_per_minute: Optional[int] = None):
+        """Replace the guest device list."""
+        with self.lock:
+            self.guests = dict(guests)
+            if requests_per_minute:
+                self.requests_per_minute = requests_per_minute
+            self._buckets = {ip: b for ip, b in self._buckets.items() if ip in self.guests}
+
+    def is_guest(self, ip: str) -> bool:
+        return ip in self.guests
+
+    def allow(self, ip: str) -> bool:
+        """Take one request from the device's token bucket."""
+        now = time.monotonic()
+        rate = self.requests_per_minute / 60.0
+        with self.lock:
+            tokens, last = self._buckets.get(ip, [float(self.requests_per_minute), now])
+            tokens = min(float(self.requests_per_minute), tokens + (now - last) * rate)
+            allowed = tokens >= 1.0
+            if allowed:
+                tokens -= 1.0
+            self._buckets[ip] = [tokens, now]
+        return allowed
+
+    def needs_notice(self, ip: str) -> bool:
+        return self.guests.get(ip) not in self.noticed
+
+    def notice_page(self, ip: str, url: str, host: str) -> str:
+        """Render the notice page and record that it was shown."""
+        device_id = self.guests.get(ip, "")
+        with self.lock:
+            self.noticed.add(device_id)
+        return NOTICE_PAGE.format(url=html.escape(url, quote=True), host=html.escape(host))
+
+    def log(self, event: str, ip: str, method: str = "", host: str = "", url: str = ""):
+        """Append one entry to the guest log."""
+        entry = {
+            "timestamp": utc_now_iso(),
+            "event": event,
+            "device_id": self.guests.get(ip, ""),
+            "device_ip": ip,
+            "method": method,
+            "host": host,
+            "url": url,
+        }
+        with self.lock:
+            try:
+                self.log_file.parent.mkdir(parents=True, exist_ok=True)
+                if self.log_file.exists() and self.log_file.stat().st_size > MAX_LOG_BYTES:
+                    self.log_file.replace(self.log_file.with_name(self.log_file.name + ".1"))
+                with open(self.log_file, "a", encoding="utf-8") as f:
+                    f.write(json.dumps(entry) + "\n")
+            except IOError:
+                pass
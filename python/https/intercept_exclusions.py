@@ -0,0 +1,52 @@
+"""
+Interception Exclusions.
+
+Hosts whose TLS the proxy never decrypts, such as banking or medical sites.
+An exclusion names a domain (and its subdomains) for every device, or for a
+single device by IP. Matching connections are passed through untouched at
+the ClientHello; only their metadata (device, server name, address) is
+recorded.
+"""
+
+from dataclasses import dataclass, field
+from typing import Any, Dict, List, Optional
+
+
+@dataclass(frozen=True)
+class InterceptExclusion:
+    """A domain never intercepted, for one device or (device_ip None) all."""
+    domain: str
+    device_ip: Optional[str] = None
+
+    @classmethod
+    def parse(cls, value: str) -> "InterceptExclusion":
+        """Parse a CLI value: "domain" or "IP=domain"."""
+        if "=" in value:
+            device_ip, domain = value.split("=", 1)
+            return cls(domain.lower().rstrip("."), device_ip or None)
+        return cls(value.lower().rstrip("."))
+
+    def matches(self, host: str, client_ip: str) -> bool:
+        if self.device_ip and self.device_ip != client_ip:
+            return False
+        return host == self.domain or host.endswith("." + self.domain)
+
+
+@dataclass
+class InterceptExclusions:
+    """The exclusion list; mirrors what the app pushes with `set_intercept_exclusions`."""
+    entries: List[InterceptExclusion] = field(default_factory=list)
+
+    @classmethod
+    def from_list(cls, data: List[Dict[str, Any]]) -> "InterceptExclusions":
+        """Create from the pushed JSON list of {"domain", "device_ip"}."""
+        return cls([
+            InterceptExclusion(e["domain"].lower().rstrip("."), e.get("device_ip") or None)
+            for e in data
+            if e.get("domain")
+        ])
+
+    def excluded(self, host: str, client_ip: str) -> bool:
+        """Whether a connection from client_ip to host must not be intercepted."""
+        host = host.lower().rstrip(".")
+        return bool(host) and any(e.matches(host, client_ip) for e in self.entries)
@@ -0,0 +1,210 @@
+"""
+Monitoring Overhead Probe
+Measures how much latency the proxy adds.
+
+Every few minutes the same HTTPS request is made to a few reference hosts
+twice: directly, and through the proxy's localhost-only listener, which
+handles it exactly as it handles an intercepted device's request. The
+difference between the two round trips is the proxy's overhead. Samples are
+stored, and an alert is raised when the median overhead goes over the
+`overhead_alert_ms` setting.
+"""
+
+import http.client
+import json
+import ssl
+import statistics
+import sys
+import threading
+import time
+from typing import List, Optional, Tuple
+
+from ..alerts.alert_engine import AlertEngine
+from ..alerts.keywords import AlertCategory, AlertSeverity
+from ..database.db_manager import DatabaseManager
+from ..utils.config import get_setting
+from ..utils.i18n import t
+
+# Hosts measured each round
+REFERENCE_HOSTS = ["www.google.com", "www.cloudflare.com", "www.microsoft.com"]
+
+# Requests per host and path each round; the median is kept
+SAMPLES_PER_ROUND = 3
+
+# Seconds between rounds
+DEFAULT_INTERVAL = 300
+
+# Default for the overhead_alert_ms setting
+DEFAULT_ALERT_MS = 200
+
+# Alert at most this often (seconds)
+ALERT_INTERVAL = 3600
+
+REQUEST_TIMEOUT = 10
+
+
+def _round_trip(host: str, proxy_port: Optional[int]) -> Optional[float]:
+    """
+    Time one HEAD request, from connecting to the response headers, in ms
+
+    Through the proxy the request tunnels with CONNECT and is intercepted,
+    so the certificate it presents is ours and isn't verified.
+    """
+    context = ssl._create_unverified_context()
+    if proxy_port:
+        conn = http.client.HTTPSConnection("127.0.0.1", proxy_port, timeout=REQUEST_TIMEOUT, context=context)
+        conn.set_tunnel(host, 443)
+    else:
+        conn = http.client.HTTPSConnection(host, 443, timeout=REQUEST_TIMEOUT, context=context)
+
+    try:
+        start = time.perf_counter()
+        conn.request("HEAD", "/", headers={"User-Agent": "network-monitor-probe", "Cache-Control": "no-cache"})
+        conn.getresponse().read()
+        return (time.perf_counter() - start) * 1000
+    except Exception:
+        return None
+    finally:
+        conn.close()
+
+
+def measure(host: str, proxy_port: int) -> Tuple[Optional[float], Optional[float]]:
+    """Median direct and proxied round trips to a host, in ms (None if every attempt failed)"""
+    direct: List[float] = []
+    proxied: List[float] = []
+    # Alternate so both paths see the same network conditions
+    for _ in range(SAMPLES_PER_ROUND):
+        for samples, port in ((direct, None), (proxied, proxy_port)):
+            rtt = _round_trip(host, port)
+            if rtt is not None:
+                samples.append(rtt)
+
+    return (
+        statistics.median(direct) if direct else None,
+        statistics.median(proxied) if proxied else None,
+    )
+
+
+class OverheadProbe:
+    """
+    Overhead Probe
+    Periodically compares direct and proxied round trips
+    """
+
+    def __init__(self, proxy_port: int, interval: int = DEFAULT_INTERVAL, hosts: Optional[List[str]] = None):
+        """
+        Initialize overhead probe
+
+        Args:
+            proxy_port: Port of the proxy's localhost listener
+            interval: Seconds between rounds
+            hosts: Reference hosts (REFERENCE_HOSTS by default)
+        """
+        self.proxy_port = proxy_port
+        self.interval = interval
+        self.hosts = hosts or REFERENCE_HOSTS
+        self.running = False
+        self.db = DatabaseManager()
+        self.alert_engine = AlertEngine()
+        self._last_alert = 0.0
+        self._stopped = threading.Event()
+
+    def run_round(self) -> Optional[float]:
+        """Measure every host once, store the samples and check the threshold; returns the median overhead."""
+        overheads = []
+        for host in self.hosts:
+            direct_ms, proxied_ms = measure(host, self.proxy_port)
+            self.db.add_overhead_sample(host, direct_ms, proxied_ms)
+            if direct_ms is not None and proxied_ms is not None:
+                overheads.append((proxied_ms - direct_ms, direct_ms, proxied_ms))
+            print(json.dumps({
+                "type": "overhead_sample",
+                "host": host,
+                "direct_ms": direct_ms,
+                "proxied_ms": proxied_ms
+            }), flush=True)
+
+        if not overheads:
+            return None
+
+        overhead_ms = statistics.median(o[0] for o in overheads)
+        threshold = get_setting("overhead_alert_ms", DEFAULT_ALERT_MS)
+        if threshold and overhead_ms > threshold and time.time() - self._last_alert >= ALERT_INTERVAL:
+            self._last_alert = time.time()
+            self.alert_engine.create_alert(
+                severity=AlertSeverity.MEDIUM,
+                category=AlertCategory.NETWORK,
+                title=t("alert.overhead.title"),
+                description=t(
+                    "alert.overhead.description",
+                    overhead=round(overhead_ms),
+                    threshold=threshold,
+                    direct=round(statistics.median(o[1] for o in overheads)),
+                    proxied=round(statistics.median(o[2] for o in overheads)),
+                ),
+                metadata={"overhead_ms": overhead_ms, "threshold_ms": threshold}
+            )
+
+        return overhead_ms
+
+    def start(self):
+        """Start probing"""
+        self.running = True
+
+        def probe_loop():
+            # Let the proxy come up before the first round
+            self._stopped.wait(30)
+            while self.running:
+                try:
+                    self.run_round()
+                except Exception as e:
+                    print(json.dumps({"error": str(e), "type": "overhead_probe_error"}), file=sys.stderr, flush=True)
+                self._stopped.wait(self.interval)
+
+        threading.Thread(target=probe_loop, daemon=True).start()
+
+        print(json.dumps({
+            "status": "started",
+            "type": "overhead_probe",
+            "proxy_port": self.proxy_port,
+            "interval": self.interval,
+            "hosts": self.hosts
+        }), flush=True)
+
+    def stop(self):
+        """Stop probing"""
+        self.running = False
+        self._stopped.set()
+        print(json.dumps({"status": "stopped", "type": "overhead_probe"}), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Monitoring overhead probe")
+    parser.add_argument("--proxy-port", type=int, default=8081, help="Port of the proxy's localhost listener")
+    parser.add_argument("--interval", type=int, default=DEFAULT_INTERVAL, help="Seconds between rounds")
+    parser.add_argument("--host", action="append", help="Reference host (repeatable)")
+
+    args = parser.parse_args()
+
+    probe = OverheadProbe(proxy_port=args.proxy_port, interval=args.interval, hosts=args.host)
+
+    try:
+        probe.start()
+
+        while probe.running:
+            try:
+                line = sys.stdin.readline().strip()
+                if line:
+                    cmd = json.loads(line)
+                    if cmd.get("action") == "stop":
+                        break
+            except (json.JSONDecodeError, EOFError):
+                pass
+
+    except KeyboardInterrupt:
+        pass
+    finally:
+        probe.stop()
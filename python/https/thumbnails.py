@@ -0,0 +1,118 @@
+"""
+Media Thumbnail Cache.
+
+Image bodies are not stored with traffic, so the proxy shrinks each image
+response it sees to a small JPEG and keeps that on disk, for the media view.
+Thumbnails are keyed by a hash of the URL (FNV-1a, which the app computes
+the same way), made on a background thread so responses aren't held up,
+and the oldest are deleted once the cache outgrows its cap. Without Pillow
+installed no thumbnails are made.
+"""
+
+import io
+import json
+import queue
+import sys
+import threading
+from pathlib import Path
+from typing import Optional
+
+from ..utils.config import get_data_path
+
+try:
+    from PIL import Image
+except ImportError:  # pragma: no cover - optional dependency
+    Image = None
+
+# Longest side of a thumbnail, in pixels
+THUMBNAIL_SIZE = 160
+
+# Images larger than this aren't decoded
+MAX_SOURCE_BYTES = 10 * 1024 * 1024
+
+# Disk space the cache may use before the oldest thumbnails go
+MAX_CACHE_BYTES = 100 * 1024 * 1024
+
+# Pending images; more are dropped rather than queued
+QUEUE_SIZE = 64
+
+# Check the cache size after this many new thumbnails
+PRUNE_EVERY = 50
+
+
+def thumbnail_key(url: str) -> str:
+    """FNV-1a 64-bit hash of the URL, as 16 hex digits."""
+    value = 0xcbf29ce484222325
+    for byte in url.encode("utf-8"):
+        value ^= byte
+        value = (value * 0x100000001b3) & 0xFFFFFFFFFFFFFFFF
+    return f"{value:016x}"
+
+
+def thumbnail_dir() -> Path:
+    """Where thumbnails are cached."""
+    return get_data_path() / "thumbnails"
+
+
+class ThumbnailCache:
+    """Makes and stores thumbnails of image responses in the background."""
+
+    def __init__(self, directory: Optional[Path] = None, max_bytes: int = MAX_CACHE_BYTES):
+        self.directory = directory or thumbnail_dir()
+        self.max_bytes = max_bytes
+        self.enabled = Image is not None
+        self._queue: "queue.Queue" = queue.Queue(maxsize=QUEUE_SIZE)
+        self._made = 0
+        if self.enabled:
+            threading.Thread(target=self._work, daemon=True).start()
+
+    def submit(self, url: str, content: bytes) -> bool:
+        """Queue an image for a thumbnail; False if skipped."""
+        if not self.enabled or not content or len(content) > MAX_SOURCE_BYTES:
+            return False
+        if (self.directory / f"{thumbnail_key(url)}.jpg").exists():
+            return False
+        try:
+            self._queue.put_nowait((url, content))
+            return True
+        except queue.Full:
+            return False
+
+    def _work(self):
+        while True:
+            url, content = self._queue.get()
+            try:
+                self.make(url, content)
+            except Exception as e:
+                print(json.dumps({"error": str(e), "type": "thumbnail_error"}), file=sys.stderr, flush=True)
+
+    def make(self, url: str, content: bytes) -> Optional[Path]:
+        """Write the thumbnail for an image now; returns its path."""
+        if not self.enabled:
+            return None
+
+        with Image.open(io.BytesIO(content)) as image:
+            image.thumbnail((THUMBNAIL_SIZE, THUMBNAIL_SIZE))
+            if image.mode != "RGB":
+                image = image.convert("RGB")
+            self.directory.mkdir(parents=True, exist_ok=True)
+            path = self.directory / f"{thumbnail_key(url)}.jpg"
+            image.save(path, "JPEG", quality=70)
+
+        self._made += 1
+        if self._made % PRUNE_EVERY == 0:
+            self.prune()
+        return path
+
+    def prune(self) -> int:
+        """Delete the oldest thumbnails until the cache is under its cap; returns how many went."""
+        files = sorted(self.directory.glob("*.jpg"), key=lambda p: p.stat().st_mtime)
+        total = sum(p.stat().st_size for p in files)
+        removed = 0
+        for path in files:
+            if total <= self.max_bytes:
+                break
+            total -= path.stat().st_size
+            path.unlink(missing_ok=True)
+            removed += 1
+        return removed
@@ -14,11 +14,11 @@ Handles:
 import json
 import re
 from dataclasses import dataclass, field
-from datetime import datetime
 from enum import Enum
 from typing import Any, Dict, List, Optional, Tuple
 from urllib.parse import parse_qs, urlparse
 
+from ..utils.clock import utc_now_iso
 from .content_decoder import ContentDecoder, ContentType, DecodedContent
 
 
@@ -327,7 +327,7 @@ class TrafficParser:
         )
         
         return ParsedRequest(
-            timestamp=datetime.utcnow().isoformat(),
+            timestamp=utc_now_iso(),
             method=request.method,
             url=request.pretty_url if hasattr(request, 'pretty_url') else request.url,
             host=host,
@@ -386,7 +386,7 @@ class TrafficParser:
         )
         
         return ParsedResponse(
-            timestamp=datetime.utcnow().isoformat(),
+            timestamp=utc_now_iso(),
             status_code=response.status_code,
             status_message=response.reason if hasattr(response, 'reason') else '',
             headers=headers,
@@ -10,6 +10,7 @@ Supports:
 """
 
 import asyncio
+import html
 import json
 import os
 import queue
@@ -17,8 +18,8 @@ import signal
 import sys
 import threading
 import time
+import uuid
 from dataclasses import dataclass, field
-from datetime import datetime
 from pathlib import Path
 from typing import Any, Callable, Dict, List, Optional, Set
 
@@ -27,13 +28,30 @@ try:
     from mitmproxy import ctx, http, options
     from mitmproxy.addons import default_addons
     from mitmproxy.master import Master
+    from mitmproxy.net import server_spec
     from mitmproxy.proxy import config as proxy_config
     from mitmproxy.tools.dump import DumpMaster
     MITMPROXY_AVAILABLE = True
 except ImportError:
     MITMPROXY_AVAILABLE = False
 
+from .bandwidth import BandwidthShaper
+from .body_capture import DEFAULT_SKIP_TYPES, BodyCapturePolicy
+from .guest_mode import GuestMonitor
+from .intercept_exclusions import InterceptExclusion, InterceptExclusions
+from .thumbnails import ThumbnailCache
 from .traffic_parser import ParsedFlow, TrafficParser, TrafficCategory
+from ..blocking.categories import is_doh_provider
+from ..database.db_manager import DatabaseManager
+from ..utils.clock import utc_now_iso
+from ..utils.config import get_data_path
+from ..utils.secrets import get_secret
+
+# Path the block page's "request unblock" form posts to (on the blocked host)
+UNBLOCK_REQUEST_PATH = "/__network-monitor/unblock-request"
+
+# Record the same device/host/TLS parameters at most this often (seconds)
+TLS_RECORD_INTERVAL = 3600
 
 
 @dataclass
@@ -43,11 +61,23 @@ class ProxyConfig:
     listen_port: int = 8080
     transparent_mode: bool = True
     ssl_insecure: bool = True  # Accept invalid upstream certs
-    ca_cert_path: Optional[str] = None  # Custom CA cert
-    ca_key_path: Optional[str] = None  # Custom CA key
+    confdir: Optional[str] = None  # mitmproxy confdir holding the CA (mitmproxy-ca.pem)
+    upstream: Optional[Dict[str, Any]] = None  # Proxy to forward through: {"url", "username", "password"}
     block_list: Set[str] = field(default_factory=set)
     category_blocks: Set[TrafficCategory] = field(default_factory=set)
     keyword_alerts: List[str] = field(default_factory=list)
+    block_doh: bool = False  # Block DNS-over-HTTPS for every device
+    forced_dns_devices: Set[str] = field(default_factory=set)  # Block DoH per device
+    device_block_list: Dict[str, Set[str]] = field(default_factory=dict)  # Device IP -> domains
+    guest_devices: Dict[str, str] = field(default_factory=dict)  # Guest device IP -> device ID
+    guest_rate_limit: int = 30  # Requests per minute per guest device
+    rate_limits: Dict[str, int] = field(default_factory=dict)  # Device IP -> bandwidth limit in kbps
+    allow_list: Set[str] = field(default_factory=set)  # Domains exempt from blocking
+    block_page: Optional[str] = None  # Block page HTML; blocked flows are killed without one
+    body_capture: BodyCapturePolicy = field(default_factory=BodyCapturePolicy)  # Which bodies are stored
+    intercept_exclusions: InterceptExclusions = field(default_factory=InterceptExclusions)  # TLS passed through untouched
+    metadata_only_devices: Set[str] = field(default_factory=set)  # Device IPs whose TLS is never decrypted
+    probe_port: Optional[int] = 8081  # Localhost-only regular listener for the overhead probe
     max_body_size: int = 10 * 1024 * 1024  # 10MB
     stream_large_bodies: int = 5 * 1024 * 1024  # Stream bodies > 5MB
     anticache: bool = True
@@ -57,12 +87,30 @@ class ProxyConfig:
 @dataclass 
 class FlowEvent:
     """Event representing a traffic flow for IPC."""
-    event_type: str  # "request", "response", "error", "blocked"
+    event_type: str  # "request", "response", "error", "blocked", "passthrough"
     flow_id: str
     timestamp: str
     data: Dict[str, Any]
 
 
+def apply_config_file(config: ProxyConfig, path: str) -> None:
+    """
+    Apply the config file the app writes (port, CA, upstream proxy and
+    pass-through hosts) over the rest of the configuration.
+    """
+    data = json.loads(Path(path).read_text(encoding="utf-8"))
+    config.listen_port = data.get("listen_port", config.listen_port)
+    config.probe_port = data.get("probe_port", config.probe_port)
+    config.confdir = data.get("confdir")
+    config.upstream = data.get("upstream")
+    config.intercept_exclusions = InterceptExclusions.from_list(data.get("tls_passthrough", []))
+
+
+def _is_loopback(ip: str) -> bool:
+    """Whether an address is this machine (the probe listener only accepts loopback)."""
+    return ip.startswith("127.") or ip in ("::1", "::ffff:127.0.0.1")
+
+
 class TrafficInterceptor:
     """
     mitmproxy addon for intercepting and analyzing traffic.
@@ -75,7 +123,8 @@ class TrafficInterceptor:
         self,
         config: ProxyConfig,
         event_callback: Callable[[FlowEvent], None],
-        parser: Optional[TrafficParser] = None
+        parser: Optional[TrafficParser] = None,
+        guest: Optional[GuestMonitor] = None
     ):
         """
         Initialize the traffic interceptor.
@@ -84,11 +133,16 @@ class TrafficInterceptor:
             config: Proxy configuration
             event_callback: Callback for traffic events
             parser: Optional TrafficParser instance
+            guest: Optional guest mode tracker
         """
         self.config = config
         self.event_callback = event_callback
         self.parser = parser or TrafficParser()
+        self.guest = guest
         self.active_flows: Dict[str, Dict[str, Any]] = {}
+        self._db: Optional[DatabaseManager] = None
+        self._tls_seen: Dict[tuple, float] = {}
+        self.thumbnails = ThumbnailCache()
     
     def load(self, loader):
         """Called when addon is loaded."""
@@ -98,25 +152,89 @@ class TrafficInterceptor:
         """Called when configuration changes."""
         pass
     
+    def tls_clienthello(self, data):
+        """
+        Called when a client starts a TLS handshake.
+        
+        Excluded hosts, and every host for metadata-only devices, are passed
+        through without interception; only the connection's metadata is emitted.
+        """
+        client = data.context.client
+        client_ip = client.peername[0] if client.peername else ""
+        sni = data.client_hello.sni or ""
+        # The overhead probe is intercepted like a fully monitored device
+        if _is_loopback(client_ip):
+            return
+        if (
+            client_ip not in self.config.metadata_only_devices
+            and not self.config.intercept_exclusions.excluded(sni, client_ip)
+        ):
+            return
+        
+        data.ignore_connection = True
+        server = data.context.server.address
+        self._emit_event(FlowEvent(
+            event_type="passthrough",
+            flow_id=str(uuid.uuid4()),
+            timestamp=utc_now_iso(),
+            data={
+                "device_ip": client_ip,
+                "host": sni,
+                "server_ip": server[0] if server else None,
+                "server_port": server[1] if server else None
+            }
+        ))
+    
+    def requestheaders(self, flow: http.HTTPFlow):
+        """
+        Send the request through the upstream proxy, when one is configured,
+        unless its host is on the upstream's bypass list.
+        """
+        upstream = self.config.upstream
+        if not upstream or not upstream.get("url"):
+            return
+        host = flow.request.host.lower().rstrip(".")
+        if any(host == d or host.endswith("." + d) for d in upstream.get("bypass", [])):
+            return
+        flow.server_conn.via = server_spec.parse(upstream["url"], "http")
+    
     def request(self, flow: http.HTTPFlow):
         """
         Called when a request is received.
         
         This is where we can block, modify, or analyze requests.
         """
+        # Overhead probe requests are forwarded untouched and not recorded
+        if self._is_probe(flow):
+            return
+        
         flow_id = flow.id
         host = flow.request.host
         url = flow.request.pretty_url
         
+        # Guest devices are logged, rate-limited and shown a one-time notice
+        if self._handle_guest(flow):
+            return
+        
+        # Unblock requests submitted from the block page
+        if flow.request.path.startswith(UNBLOCK_REQUEST_PATH):
+            self._handle_unblock_request(flow)
+            return
+        
         # Check block list
         if self._should_block(flow):
             self._block_flow(flow, "Domain blocked by policy")
             return
         
+        # Check encrypted DNS bypass
+        if self._is_doh_bypass(flow):
+            self._block_flow(flow, "DNS-over-HTTPS blocked by policy")
+            return
+        
         # Check category blocks
         category = self.parser._categorize_domain(host)
-        if category in self.config.category_blocks:
-            self._block_flow(flow, f"Category blocked: {category.value}")
+        if category in self.config.category_blocks and not self._is_allowed(host):
+            self._block_flow(flow, f"Category blocked: {category.value}", category.value)
             return
         
         # Track flow
@@ -132,14 +250,14 @@ class TrafficInterceptor:
             self._emit_event(FlowEvent(
                 event_type="request",
                 flow_id=flow_id,
-                timestamp=datetime.utcnow().isoformat(),
-                data=self.parser.to_dict(parsed)
+                timestamp=utc_now_iso(),
+                data=self._stored_data(flow, parsed)
             ))
         except Exception as e:
             self._emit_event(FlowEvent(
                 event_type="error",
                 flow_id=flow_id,
-                timestamp=datetime.utcnow().isoformat(),
+                timestamp=utc_now_iso(),
                 data={"error": str(e), "phase": "request"}
             ))
     
@@ -149,6 +267,9 @@ class TrafficInterceptor:
         
         This is where we analyze response content and detect alerts.
         """
+        if self._is_probe(flow):
+            return
+        
         flow_id = flow.id
         
         # Remove from active flows
@@ -159,6 +280,11 @@ class TrafficInterceptor:
         if "start_time" in flow_info:
             duration_ms = int((time.time() - flow_info["start_time"]) * 1000)
         
+        try:
+            self._record_tls(flow)
+        except Exception as e:
+            print(json.dumps({"error": str(e), "type": "tls_record_error"}), file=sys.stderr, flush=True)
+        
         # Parse and emit response event
         try:
             parsed = self.parser.parse_mitmproxy_flow(flow)
@@ -169,19 +295,22 @@ class TrafficInterceptor:
             if alerts:
                 parsed.alerts.extend(alerts)
             
+            # Keyword checks above saw the full content; only storage follows the policy
             self._emit_event(FlowEvent(
                 event_type="response",
                 flow_id=flow_id,
-                timestamp=datetime.utcnow().isoformat(),
-                data=self.parser.to_dict(parsed)
+                timestamp=utc_now_iso(),
+                data=self._stored_data(flow, parsed)
             ))
             
+            self._queue_thumbnail(flow)
+            
             # Emit alert events separately for real-time notifications
             for alert in parsed.alerts:
                 self._emit_event(FlowEvent(
                     event_type="alert",
                     flow_id=flow_id,
-                    timestamp=datetime.utcnow().isoformat(),
+                    timestamp=utc_now_iso(),
                     data={
                         "alert_type": alert,
                         "host": flow.request.host,
@@ -193,44 +322,232 @@ class TrafficInterceptor:
             self._emit_event(FlowEvent(
                 event_type="error",
                 flow_id=flow_id,
-                timestamp=datetime.utcnow().isoformat(),
+                timestamp=utc_now_iso(),
                 data={"error": str(e), "phase": "response"}
             ))
     
     def error(self, flow: http.HTTPFlow):
         """Called when an error occurs."""
+        if self._is_probe(flow):
+            return
         self._emit_event(FlowEvent(
             event_type="error",
             flow_id=flow.id,
-            timestamp=datetime.utcnow().isoformat(),
+            timestamp=utc_now_iso(),
             data={
                 "error": str(flow.error) if flow.error else "Unknown error",
                 "host": flow.request.host if flow.request else "unknown"
             }
         ))
     
+    def _is_probe(self, flow: http.HTTPFlow) -> bool:
+        """Whether a flow came from the overhead probe on this machine."""
+        return bool(flow.client_conn.peername) and _is_loopback(flow.client_conn.peername[0])
+    
+    def _stored_data(self, flow: http.HTTPFlow, parsed: ParsedFlow) -> Dict[str, Any]:
+        """A parsed flow as it is emitted: bodies follow the capture policy, and
+        metadata-only devices' plain HTTP keeps none."""
+        data = self.config.body_capture.apply(self.parser.to_dict(parsed))
+        client_ip = flow.client_conn.peername[0] if flow.client_conn.peername else ""
+        if client_ip in self.config.metadata_only_devices:
+            for part in ("request", "response"):
+                message = data.get(part)
+                if message and message.get("body"):
+                    message["body"] = None
+                    message.setdefault("metadata", {})["body_skipped"] = "metadata_only"
+        return data
+    
+    def _queue_thumbnail(self, flow: http.HTTPFlow):
+        """Have a thumbnail made of an image response for the media view.
+        
+        Hosts excluded from body capture and metadata-only devices get none.
+        """
+        if not flow.response or not self.config.body_capture.enabled:
+            return
+        content_type = flow.response.headers.get("content-type", "").lower()
+        if not content_type.startswith("image/") or content_type.startswith("image/svg"):
+            return
+        client_ip = flow.client_conn.peername[0] if flow.client_conn.peername else ""
+        if client_ip in self.config.metadata_only_devices or self.config.body_capture.host_excluded(flow.request.host):
+            return
+        self.thumbnails.submit(flow.request.pretty_url, flow.response.content or b"")
+    
+    def _record_tls(self, flow: http.HTTPFlow):
+        """Record the TLS versions, cipher and server certificate of an HTTPS flow."""
+        server = flow.server_conn
+        if not server or not server.tls_version:
+            return
+        
+        client_ip = flow.client_conn.peername[0] if flow.client_conn.peername else ""
+        cert = server.certificate_list[0] if server.certificate_list else None
+        fingerprint = cert.fingerprint().hex() if cert else ""
+        
+        key = (client_ip, flow.request.host, flow.client_conn.tls_version, server.tls_version, server.cipher, fingerprint)
+        now = time.time()
+        if now - self._tls_seen.get(key, 0) < TLS_RECORD_INTERVAL:
+            return
+        self._tls_seen[key] = now
+        
+        def name(parts) -> str:
+            return ", ".join(f"{k}={v}" for k, v in parts)
+        
+        if self._db is None:
+            self._db = DatabaseManager()
+        self._db.add_tls_session(
+            device_ip=client_ip,
+            host=flow.request.host,
+            source="proxy",
+            client_tls_version=flow.client_conn.tls_version,
+            server_tls_version=server.tls_version,
+            cipher=server.cipher,
+            cert_subject=name(cert.subject) if cert else None,
+            cert_issuer=name(cert.issuer) if cert else None,
+            cert_not_before=cert.notbefore.isoformat() if cert else None,
+            cert_not_after=cert.notafter.isoformat() if cert else None,
+            self_signed=bool(cert) and cert.subject == cert.issuer
+        )
+    
+    def _handle_guest(self, flow: http.HTTPFlow) -> bool:
+        """Apply guest mode to a request. Returns True if the request was answered."""
+        client_ip = flow.client_conn.peername[0] if flow.client_conn.peername else ""
+        if not self.guest or not self.guest.is_guest(client_ip):
+            return False
+        
+        host = flow.request.host
+        url = flow.request.pretty_url
+        method = flow.request.method
+        
+        if not self.guest.allow(client_ip):
+            self.guest.log("rate_limited", client_ip, method, host, url)
+            flow.response = http.Response.make(
+                429, b"Too many requests", {"Content-Type": "text/plain", "Retry-After": "60"}
+            )
+            return True
+        
+        self.guest.log("request", client_ip, method, host, url)
+        
+        if flow.request.scheme == "http" and method == "GET" and self.guest.needs_notice(client_ip):
+            page = self.guest.notice_page(client_ip, url, host)
+            self.guest.log("notice", client_ip, method, host, url)
+            flow.response = http.Response.make(
+                200, page.encode("utf-8"), {"Content-Type": "text/html; charset=utf-8", "Cache-Control": "no-store"}
+            )
+            self._emit_event(FlowEvent(
+                event_type="guest_notice",
+                flow_id=flow.id,
+                timestamp=utc_now_iso(),
+                data={"device_ip": client_ip, "host": host, "url": url}
+            ))
+            return True
+        
+        return False
+    
+    def _handle_unblock_request(self, flow: http.HTTPFlow):
+        """Queue an unblock request from the block page for the app to pick up."""
+        client_ip = flow.client_conn.peername[0] if flow.client_conn.peername else ""
+        
+        if flow.request.method != "POST":
+            flow.response = http.Response.make(405, b"Method not allowed", {"Content-Type": "text/plain"})
+            return
+        
+        form = flow.request.urlencoded_form
+        request = {
+            "id": str(uuid.uuid4()),
+            "timestamp": utc_now_iso(),
+            "device_ip": client_ip,
+            "host": form.get("host", flow.request.host)[:253],
+            "url": form.get("url", "")[:2048],
+            "reason": form.get("reason", "")[:200],
+            "category": form.get("category", "")[:50],
+            "message": form.get("message", "")[:500],
+        }
+        
+        queue_file = get_data_path() / "unblock_requests.jsonl"
+        try:
+            queue_file.parent.mkdir(parents=True, exist_ok=True)
+            with open(queue_file, "a", encoding="utf-8") as f:
+                f.write(json.dumps(request) + "\n")
+        except IOError as e:
+            flow.response = http.Response.make(500, f"Could not send request: {e}".encode(), {"Content-Type": "text/plain"})
+            return
+        
+        self._emit_event(FlowEvent(
+            event_type="unblock_request",
+            flow_id=flow.id,
+            timestamp=utc_now_iso(),
+            data=request
+        ))
+        flow.response = http.Response.make(
+            200,
+            b"<!DOCTYPE html><html><body style=\"font-family: sans-serif; text-align: center; padding: 3rem\">"
+            b"<h1>Request sent</h1><p>You'll be able to open the site once your request is approved.</p>"
+            b"</body></html>",
+            {"Content-Type": "text/html; charset=utf-8", "Cache-Control": "no-store"}
+        )
+    
+    def _is_allowed(self, host: str) -> bool:
+        """Check if host is on the allow list (e.g. an approved unblock request)."""
+        host = host.lower()
+        return any(host == allowed or host.endswith('.' + allowed) for allowed in self.config.allow_list)
+    
     def _should_block(self, flow: http.HTTPFlow) -> bool:
         """Check if flow should be blocked."""
         host = flow.request.host.lower()
         url = flow.request.pretty_url.lower()
         
+        if self._is_allowed(host):
+            return False
+        
         for blocked in self.config.block_list:
             blocked = blocked.lower()
             if blocked in host or blocked in url:
                 return True
         
+        client_ip = flow.client_conn.peername[0] if flow.client_conn.peername else ""
+        for blocked in self.config.device_block_list.get(client_ip, set()):
+            if host == blocked or host.endswith('.' + blocked):
+                return True
+        
         return False
     
-    def _block_flow(self, flow: http.HTTPFlow, reason: str):
-        """Block a flow with a custom response."""
-        # Kill the flow
-        flow.kill()
+    def _is_doh_bypass(self, flow: http.HTTPFlow) -> bool:
+        """Check if flow is a DoH lookup from a device that must use local DNS."""
+        client_ip = flow.client_conn.peername[0] if flow.client_conn.peername else ""
+        if not (self.config.block_doh or client_ip in self.config.forced_dns_devices):
+            return False
+        
+        content_type = flow.request.headers.get("content-type", "")
+        accept = flow.request.headers.get("accept", "")
+        return (
+            is_doh_provider(flow.request.host)
+            or flow.request.path.startswith("/dns-query")
+            or "application/dns-message" in content_type
+            or "application/dns-message" in accept
+        )
+    
+    def _block_flow(self, flow: http.HTTPFlow, reason: str, category: str = ""):
+        """Block a flow, answering with the block page when one is configured."""
+        if self.config.block_page:
+            fields = {
+                "host": flow.request.host,
+                "url": flow.request.pretty_url,
+                "reason": reason,
+                "category": category,
+            }
+            page = self.config.block_page
+            for key, value in fields.items():
+                page = page.replace("{{" + key + "}}", html.escape(value, quote=True))
+            flow.response = http.Response.make(
+                403, page.encode("utf-8"), {"Content-Type": "text/html; charset=utf-8", "Cache-Control": "no-store"}
+            )
+        else:
+            flow.kill()
         
         # Emit blocked event
         self._emit_event(FlowEvent(
             event_type="blocked",
             flow_id=flow.id,
-            timestamp=datetime.utcnow().isoformat(),
+            timestamp=utc_now_iso(),
             data={
                 "reason": reason,
                 "host": flow.request.host,
@@ -298,6 +615,8 @@ class TransparentProxy:
             config: Proxy configuration
         """
         self.config = config or ProxyConfig()
+        self.guest = GuestMonitor(self.config.guest_devices, self.config.guest_rate_limit)
+        self.shaper = BandwidthShaper(self.config.rate_limits)
         self.master: Optional[Master] = None
         self.event_queue: queue.Queue = queue.Queue()
         self.running = False
@@ -322,31 +641,38 @@ class TransparentProxy:
             raise RuntimeError("mitmproxy is not installed")
         
         # Build mitmproxy options
+        modes = ["transparent"] if self.config.transparent_mode else ["regular"]
+        if self.config.probe_port:
+            modes.append(f"regular@127.0.0.1:{self.config.probe_port}")
         opts = options.Options(
             listen_host=self.config.listen_host,
             listen_port=self.config.listen_port,
-            mode=["transparent"] if self.config.transparent_mode else ["regular"],
+            mode=modes,
             ssl_insecure=self.config.ssl_insecure,
             anticache=self.config.anticache,
             anticomp=self.config.anticomp,
         )
         
-        # Set custom CA if provided
-        if self.config.ca_cert_path and self.config.ca_key_path:
-            # mitmproxy expects combined PEM file
-            combined_path = Path(self.config.ca_cert_path).with_suffix('.pem')
-            if combined_path.exists():
-                opts.certs = [str(combined_path)]
+        # Sign with the CA devices were given rather than mitmproxy's own
+        if self.config.confdir:
+            opts.update(confdir=self.config.confdir)
+        # Connect to servers only once the request is known, so it can go upstream
+        if self.config.upstream:
+            opts.update(connection_strategy="lazy")
         
         # Create master
         self.master = DumpMaster(opts)
+        self._apply_upstream_auth()
         
         # Add our interceptor addon
         interceptor = TrafficInterceptor(
             config=self.config,
-            event_callback=self._event_handler
+            event_callback=self._event_handler,
+            guest=self.guest
         )
         self.master.addons.add(interceptor)
+        # After the interceptor, so flows it answers itself aren't held back
+        self.master.addons.add(self.shaper)
         
         # Run
         self.running = True
@@ -388,6 +714,45 @@ class TransparentProxy:
             "mode": "transparent" if self.config.transparent_mode else "regular"
         })
     
+    def _apply_upstream_auth(self):
+        """Hand the upstream proxy's credentials to mitmproxy."""
+        if not self.master:
+            return
+        upstream = self.config.upstream or {}
+        auth = None
+        if upstream.get("username"):
+            # The config file leaves the password out; it is in the OS keychain
+            password = upstream.get("password") or get_secret("upstream_proxy_password") or ""
+            auth = f"{upstream['username']}:{password}"
+        self.master.options.update(upstream_auth=auth)
+    
+    def reload_config(self, path: str):
+        """
+        Re-read the config file. The upstream proxy and pass-through hosts
+        apply at once; a new port or CA waits for the next start.
+        """
+        port, confdir = self.config.listen_port, self.config.confdir
+        try:
+            apply_config_file(self.config, path)
+        except (OSError, ValueError) as e:
+            output_json({"type": "error", "error": f"Failed to reload config: {e}"})
+            return
+        self._apply_upstream_auth()
+        
+        restart_required = [
+            name for name, changed in (
+                ("listen_port", port != self.config.listen_port),
+                ("confdir", confdir != self.config.confdir),
+            ) if changed
+        ]
+        output_json({
+            "type": "config_update",
+            "action": "reload_config",
+            "upstream": bool(self.config.upstream),
+            "exclusions": len(self.config.intercept_exclusions.entries),
+            "restart_required": restart_required
+        })
+    
     def stop(self):
         """Stop the proxy."""
         if self.master:
@@ -446,6 +811,124 @@ class TransparentProxy:
         except ValueError:
             pass
     
+    def set_doh_blocking(self, enabled: bool):
+        """Enable or disable DoH blocking for all devices."""
+        self.config.block_doh = enabled
+        output_json({
+            "type": "config_update",
+            "action": "set_doh_blocking",
+            "enabled": enabled
+        })
+    
+    def set_force_dns(self, device_ip: str, enabled: bool):
+        """Force a device's DNS through the monitor."""
+        if enabled:
+            self.config.forced_dns_devices.add(device_ip)
+        else:
+            self.config.forced_dns_devices.discard(device_ip)
+        output_json({
+            "type": "config_update",
+            "action": "force_dns",
+            "device_ip": device_ip,
+            "enabled": enabled
+        })
+    
+    def block_device_domains(self, device_ip: str, domains: List[str]):
+        """Block domains for a single device."""
+        self.config.device_block_list.setdefault(device_ip, set()).update(d.lower() for d in domains)
+        output_json({
+            "type": "config_update",
+            "action": "block_device_domains",
+            "device_ip": device_ip,
+            "domains": domains
+        })
+    
+    def unblock_device_domains(self, device_ip: str, domains: List[str]):
+        """Remove per-device domain blocks."""
+        self.config.device_block_list.get(device_ip, set()).difference_update(d.lower() for d in domains)
+        output_json({
+            "type": "config_update",
+            "action": "unblock_device_domains",
+            "device_ip": device_ip,
+            "domains": domains
+        })
+    
+    def set_guests(self, guests: Dict[str, str], requests_per_minute: Optional[int] = None):
+        """Replace the guest device list (empty when guest mode is off)."""
+        self.guest.set_guests(guests, requests_per_minute)
+        output_json({
+            "type": "config_update",
+            "action": "set_guests",
+            "guests": len(guests)
+        })
+    
+    def set_rate_limits(self, limits: Dict[str, int]):
+        """Replace the per-device bandwidth limits (kbps)."""
+        self.shaper.set_limits(limits)
+        output_json({
+            "type": "config_update",
+            "action": "set_rate_limits",
+            "devices": len(limits)
+        })
+    
+    def set_block_page(self, page: Optional[str]):
+        """Replace the block page HTML (None kills blocked flows instead)."""
+        self.config.block_page = page or None
+        output_json({
+            "type": "config_update",
+            "action": "set_block_page",
+            "enabled": self.config.block_page is not None
+        })
+    
+    def set_body_capture(self, policy: Dict[str, Any]):
+        """Replace the body capture policy."""
+        self.config.body_capture = BodyCapturePolicy.from_dict(policy)
+        output_json({
+            "type": "config_update",
+            "action": "set_body_capture",
+            "enabled": self.config.body_capture.enabled
+        })
+    
+    def set_intercept_exclusions(self, exclusions: List[Dict[str, Any]]):
+        """Replace the list of hosts that are never intercepted."""
+        self.config.intercept_exclusions = InterceptExclusions.from_list(exclusions)
+        output_json({
+            "type": "config_update",
+            "action": "set_intercept_exclusions",
+            "exclusions": len(self.config.intercept_exclusions.entries)
+        })
+    
+    def set_interception_mode(self, device_ip: str, mode: str):
+        """Set whether a device's TLS is decrypted ("full") or only its metadata recorded."""
+        if mode == "full":
+            self.config.metadata_only_devices.discard(device_ip)
+        else:
+            self.config.metadata_only_devices.add(device_ip)
+        output_json({
+            "type": "config_update",
+            "action": "set_interception_mode",
+            "device_ip": device_ip,
+            "mode": mode
+        })
+    
+    def allow_domain(self, domain: str):
+        """Exempt a domain from blocking."""
+        self.config.allow_list.add(domain.lower())
+        output_json({
+            "type": "config_update",
+            "action": "allow_domain",
+            "domain": domain
+        })
+    
+    def disallow_domain(self, domain: str):
+        """Remove a domain's blocking exemption."""
+        self.config.allow_list.discard(domain.lower())
+        output_json({
+            "type": "config_update",
+            "action": "disallow_domain",
+            "domain": domain
+        })
+    
     def add_keyword_alert(self, keyword: str):
         """Add keyword for alert detection."""
         if keyword not in self.config.keyword_alerts:
@@ -574,19 +1057,49 @@ def main():
     parser.add_argument("--host", default="0.0.0.0", help="Proxy listen host")
     parser.add_argument("--transparent", action="store_true", default=True,
                        help="Run in transparent mode")
-    parser.add_argument("--ca-cert", help="Path to CA certificate")
-    parser.add_argument("--ca-key", help="Path to CA key")
+    parser.add_argument("--config", help="Config file written by the app (port, CA, upstream, pass-through hosts)")
     parser.add_argument("--block", action="append", default=[],
                        help="Domains to block")
     parser.add_argument("--block-category", action="append", default=[],
                        help="Categories to block")
     parser.add_argument("--keyword", action="append", default=[],
                        help="Keywords to alert on")
+    parser.add_argument("--block-doh", action="store_true",
+                       help="Block DNS-over-HTTPS for all devices")
+    parser.add_argument("--force-dns", action="append", default=[],
+                       help="Device IPs whose DNS must go through the monitor")
+    parser.add_argument("--guest", action="append", default=[],
+                       help="Guest device as IP=DEVICE_ID")
+    parser.add_argument("--guest-rate-limit", type=int, default=30,
+                       help="Requests per minute allowed per guest device")
+    parser.add_argument("--rate-limit", action="append", default=[],
+                       help="Device bandwidth limit as IP=KBPS")
+    parser.add_argument("--allow", action="append", default=[],
+                       help="Domains exempt from blocking")
+    parser.add_argument("--block-page", help="HTML file served for blocked requests")
+    parser.add_argument("--no-bodies", action="store_true",
+                       help="Don't store request/response bodies")
+    parser.add_argument("--max-body-size", type=int, default=BodyCapturePolicy.max_bytes,
+                       help="Largest body stored, in bytes")
+    parser.add_argument("--skip-body-type", action="append",
+                       help="MIME type (or type/*) whose bodies are never stored")
+    parser.add_argument("--no-body-host", action="append", default=[],
+                       help="Host whose bodies are never stored")
+    parser.add_argument("--no-intercept", action="append", default=[],
+                       help="Domain passed through without interception, as DOMAIN or IP=DOMAIN")
+    parser.add_argument("--metadata-only", action="append", default=[],
+                       help="Device IP whose TLS is passed through without interception")
+    parser.add_argument("--probe-port", type=int, default=8081,
+                       help="Localhost port the overhead probe connects through (0 disables)")
     
     args = parser.parse_args()
     
     if args.action == "setup-redirect":
-        success = setup_windows_redirect(args.port)
+        # Redirect to the port the app settled on, which may not be the default
+        port = args.port
+        if args.config:
+            port = json.loads(Path(args.config).read_text(encoding="utf-8")).get("listen_port", port)
+        success = setup_windows_redirect(port)
         output_json({
             "success": success,
             "action": "setup_redirect"
@@ -616,12 +1129,30 @@ def main():
         listen_host=args.host,
         listen_port=args.port,
         transparent_mode=args.transparent,
-        ca_cert_path=args.ca_cert,
-        ca_key_path=args.ca_key,
         block_list=set(args.block),
-        keyword_alerts=args.keyword
+        keyword_alerts=args.keyword,
+        block_doh=args.block_doh,
+        forced_dns_devices=set(args.force_dns),
+        guest_devices=dict(g.split("=", 1) for g in args.guest if "=" in g),
+        guest_rate_limit=args.guest_rate_limit,
+        rate_limits={ip: int(kbps) for ip, kbps in (r.split("=", 1) for r in args.rate_limit if "=" in r)},
+        allow_list={d.lower() for d in args.allow},
+        block_page=Path(args.block_page).read_text(encoding="utf-8") if args.block_page else None,
+        body_capture=BodyCapturePolicy.from_dict({
+            "enabled": not args.no_bodies,
+            "max_bytes": args.max_body_size,
+            "skip_content_types": args.skip_body_type if args.skip_body_type is not None else DEFAULT_SKIP_TYPES,
+            "excluded_hosts": args.no_body_host,
+        }),
+        intercept_exclusions=InterceptExclusions([InterceptExclusion.parse(e) for e in args.no_intercept]),
+        metadata_only_devices=set(args.metadata_only),
+        probe_port=args.probe_port or None
     )
     
+    # The app's config file wins over the flags it covers
+    if args.config:
+        apply_config_file(config, args.config)
+    
     # Add category blocks
     for cat in args.block_category:
         try:
@@ -670,6 +1201,37 @@ def main():
                         proxy.block_category(cmd.get("category", ""))
                     elif action == "unblock_category":
                         proxy.unblock_category(cmd.get("category", ""))
+                    elif action == "set_doh_blocking":
+                        proxy.set_doh_blocking(cmd.get("enabled", True))
+                    elif action == "force_dns":
+                        proxy.set_force_dns(cmd.get("device_ip", ""), cmd.get("enabled", True))
+                    elif action == "block_device_domains":
+                        proxy.block_device_domains(cmd.get("device_ip", ""), cmd.get("domains", []))
+                    elif action == "unblock_device_domains":
+                        proxy.unblock_device_domains(cmd.get("device_ip", ""), cmd.get("domains", []))
+                    elif action == "set_guests":
+                        proxy.set_guests(
+                            {g.get("ip", ""): g.get("device_id", "") for g in cmd.get("guests", [])},
+                            cmd.get("requests_per_minute")
+                        )
+                    elif action == "set_rate_limits":
+                        proxy.set_rate_limits(
+                            {d.get("ip", ""): int(d.get("kbps", 0)) for d in cmd.get("devices", [])}
+                        )
+                    elif action == "set_block_page":
+                        proxy.set_block_page(cmd.get("html"))
+                    elif action == "set_body_capture":
+                        proxy.set_body_capture(cmd.get("policy", {}))
+                    elif action == "set_intercept_exclusions":
+                        proxy.set_intercept_exclusions(cmd.get("exclusions", []))
+                    elif action == "reload_config":
+                        proxy.reload_config(cmd.get("path") or args.config)
+                    elif action == "set_interception_mode":
+                        proxy.set_interception_mode(cmd.get("device_ip", ""), cmd.get("mode", "full"))
+                    elif action == "allow_domain":
+                        proxy.allow_domain(cmd.get("domain", ""))
+                    elif action == "disallow_domain":
+                        proxy.disallow_domain(cmd.get("domain", ""))
                     elif action == "add_keyword":
                         proxy.add_keyword_alert(cmd.get("keyword", ""))
                     elif action == "remove_keyword":
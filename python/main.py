@@ -33,7 +33,9 @@ PROJECT_ROOT = Path(__file__).parent.parent
 sys.path.insert(0, str(PROJECT_ROOT))
 
 from python.utils.logger import setup_logger
-from python.utils.config import ConfigManager
+from python.utils.clock import utc_now_iso
+from python.utils.config import ConfigManager, get_config_path
+from python.utils.i18n import t
 from python.utils.network_utils import get_default_interface, get_gateway_ip
 from python.stealth.device_profiles import DeviceProfileManager
 from python.stealth.mac_changer import MACChanger
@@ -43,6 +45,8 @@ from python.dns.dns_blocker import DNSBlocker
 from python.arp.arp_gateway import ARPGateway
 from python.arp.device_scanner import DeviceScanner
 from python.arp.ip_forwarding import IPForwarding
+from python.arp.os_fingerprint import OSFingerprinter
+from python.arp.service_discovery import ServiceDiscovery
 from python.https.transparent_proxy import TransparentProxy
 from python.https.cert_generator import CertificateGenerator
 from python.blocking.blocker import UnifiedBlocker
@@ -61,7 +65,7 @@ class NetworkMonitor:
             config_path: Optional path to configuration directory
         """
         self.logger = setup_logger("network_monitor")
-        self.config_path = config_path or str(PROJECT_ROOT / "config")
+        self.config_path = config_path or str(get_config_path())
         
         # Load configuration
         self.config = ConfigManager(self.config_path)
@@ -76,6 +80,8 @@ class NetworkMonitor:
         self._arp_gateway: Optional[ARPGateway] = None
         self._device_scanner: Optional[DeviceScanner] = None
         self._ip_forwarding: Optional[IPForwarding] = None
+        self._os_fingerprinter: Optional[OSFingerprinter] = None
+        self._service_discovery: Optional[ServiceDiscovery] = None
         self._https_proxy: Optional[TransparentProxy] = None
         self._blocker: Optional[UnifiedBlocker] = None
         self._alert_engine: Optional[AlertEngine] = None
@@ -130,7 +136,7 @@ class NetworkMonitor:
         """
         event = {
             "type": event_type,
-            "timestamp": datetime.now().isoformat(),
+            "timestamp": utc_now_iso(),
             "data": data
         }
         print(json.dumps(event), flush=True)
@@ -347,7 +353,7 @@ class NetworkMonitor:
             # Check for alerts
             alert = self.alert_engine.check_dns(packet_data)
             if alert:
-                self.notifier.notify(alert)
+                self.notifier.notify_alert(alert)
                 self.emit("alert", alert)
                 
             # Emit to frontend
@@ -375,7 +381,7 @@ class NetworkMonitor:
             alert = self.alert_engine.check_traffic(flow_data)
             if alert:
                 flow_data["has_alert"] = True
-                self.notifier.notify(alert)
+                self.notifier.notify_alert(alert)
                 self.emit("alert", alert)
                 
             # Emit to frontend
@@ -391,19 +397,23 @@ class NetworkMonitor:
             device_data: Device information
         """
         try:
-            # Store/update in database
-            self.db.upsert_device(device_data)
+            # Store/update in database (gateway status updates carry no device)
+            if device_data.get("mac") and device_data.get("ip"):
+                self.db.upsert_device(device_data)
             
             # Check if new device
             if device_data.get("is_new"):
                 alert = {
                     "type": "new_device",
                     "severity": "low",
-                    "title": "New device connected",
-                    "description": f"Device {device_data.get('hostname', device_data.get('ip'))} joined the network",
+                    "title": t("alert.new_device.title"),
+                    "description": t(
+                        "alert.new_device.description",
+                        device=device_data.get("hostname", device_data.get("ip")),
+                    ),
                     "device": device_data
                 }
-                self.notifier.notify(alert)
+                self.notifier.notify_alert(alert)
                 self.emit("alert", alert)
                 
             # Emit to frontend
@@ -412,6 +422,38 @@ class NetworkMonitor:
         except Exception as e:
             self.logger.error(f"Device update handling error: {e}")
 
+    def start_os_fingerprinting(self) -> bool:
+        """Start passive OS fingerprinting.
+        
+        Returns:
+            True if successful, False otherwise
+        """
+        try:
+            self._os_fingerprinter = OSFingerprinter(interface=self.interface)
+            self._os_fingerprinter.start()
+            self.emit_status("OS fingerprinting started")
+            return True
+            
+        except Exception as e:
+            self.emit_error(f"OS fingerprinting failed: {e}", "os_fingerprint")
+            return False
+
+    def start_service_discovery(self) -> bool:
+        """Start collecting mDNS and SSDP service advertisements.
+        
+        Returns:
+            True if successful, False otherwise
+        """
+        try:
+            self._service_discovery = ServiceDiscovery(interface=self.interface)
+            self._service_discovery.start()
+            self.emit_status("Service discovery started")
+            return True
+            
+        except Exception as e:
+            self.emit_error(f"Service discovery failed: {e}", "service_discovery")
+            return False
+
     def start(self, mode: str = "full") -> bool:
         """Start the Network Monitor.
         
@@ -440,6 +482,9 @@ class NetworkMonitor:
             
         if mode in ("full", "arp-only"):
             success = success and self.start_arp_gateway()
+            # Not needed for monitoring, so their failure isn't fatal
+            self.start_os_fingerprinting()
+            self.start_service_discovery()
             
         if mode in ("full", "proxy-only"):
             success = success and self.start_https_proxy()
@@ -472,6 +517,12 @@ class NetworkMonitor:
         if self._arp_gateway:
             self._arp_gateway.stop()
             
+        if self._os_fingerprinter:
+            self._os_fingerprinter.stop()
+            
+        if self._service_discovery:
+            self._service_discovery.stop()
+            
         if self._https_proxy:
             self._https_proxy.stop()
             
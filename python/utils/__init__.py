@@ -1,5 +1,5 @@
 """Utility modules for Network Monitor"""
 
 from .logger import setup_logger, get_logger
-from .config import load_config, save_config, get_config_path
+from .config import load_config, save_config, get_config_path, get_data_path
 from .network_utils import get_local_ip, get_gateway_ip, get_mac_address
@@ -0,0 +1,144 @@
+"""
+Packet Capture Driver Check and Installer Download.
+
+Scapy needs the Npcap driver (or the older WinPcap) to capture packets on
+Windows; without it monitoring fails with an obscure error deep in scapy.
+This reports whether a driver is installed, which one and its version, and
+fetches the Npcap installer when no copy is bundled under `installers/`.
+Linux and macOS capture through the kernel and libpcap they ship with, so no
+driver is required there.
+"""
+
+import json
+import os
+import sys
+import urllib.request
+from pathlib import Path
+from typing import Dict, Optional
+
+from .config import get_data_path
+
+NPCAP_VERSION = "1.79"
+NPCAP_URL = f"https://npcap.com/dist/npcap-{NPCAP_VERSION}.exe"
+
+# Installers shipped with the app, next to the python/ directory
+BUNDLED_DIR = Path(__file__).parent.parent.parent / "installers"
+
+# Download read size, and how often progress is reported
+CHUNK_BYTES = 64 * 1024
+PROGRESS_EVERY_BYTES = 512 * 1024
+
+
+def _system32() -> Path:
+    return Path(os.environ.get("SystemRoot", r"C:\Windows")) / "System32"
+
+
+def _npcap_version() -> Optional[str]:
+    """Npcap's version from its uninstall entry in the registry."""
+    try:
+        import winreg
+    except ImportError:
+        return None
+
+    for key in (
+        r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall\NpcapInst",
+        r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\NpcapInst",
+    ):
+        try:
+            with winreg.OpenKey(winreg.HKEY_LOCAL_MACHINE, key) as handle:
+                return str(winreg.QueryValueEx(handle, "DisplayVersion")[0])
+        except OSError:
+            continue
+    return None
+
+
+def bundled_installer() -> Optional[Path]:
+    """The newest Npcap installer shipped with the app, if any."""
+    if not BUNDLED_DIR.is_dir():
+        return None
+    installers = sorted(BUNDLED_DIR.glob("npcap-*.exe"))
+    return installers[-1] if installers else None
+
+
+def driver_status() -> Dict[str, object]:
+    """Whether this platform needs a capture driver and which one is installed."""
+    status = {
+        "required": sys.platform == "win32",
+        "installed": sys.platform != "win32",
+        "driver": None if sys.platform == "win32" else "libpcap",
+        "version": None,
+        "winpcap_compatible": False,
+        "bundled_installer": None,
+        "download_url": NPCAP_URL,
+    }
+    if sys.platform != "win32":
+        return status
+
+    system32 = _system32()
+    program_files = Path(os.environ.get("ProgramFiles", r"C:\Program Files"))
+    npcap = (system32 / "Npcap" / "wpcap.dll").exists() or (program_files / "Npcap" / "NPFInstall.exe").exists()
+    # Present for WinPcap, and for Npcap installed in WinPcap API-compatible mode
+    compatible = (system32 / "wpcap.dll").exists()
+
+    status["installed"] = npcap or compatible
+    status["driver"] = "npcap" if npcap else ("winpcap" if compatible else None)
+    status["version"] = _npcap_version() if npcap else None
+    status["winpcap_compatible"] = compatible
+    installer = bundled_installer()
+    status["bundled_installer"] = str(installer) if installer else None
+    return status
+
+
+def download_installer(dest_dir: Optional[Path] = None) -> Path:
+    """Download the Npcap installer, streaming progress records; returns its path."""
+    dest_dir = dest_dir or get_data_path() / "installers"
+    dest_dir.mkdir(parents=True, exist_ok=True)
+    dest = dest_dir / f"npcap-{NPCAP_VERSION}.exe"
+    partial = dest.with_suffix(".part")
+
+    with urllib.request.urlopen(NPCAP_URL, timeout=30) as response, open(partial, "wb") as out:
+        total = int(response.headers.get("Content-Length") or 0) or None
+        downloaded = 0
+        reported = 0
+        while True:
+            chunk = response.read(CHUNK_BYTES)
+            if not chunk:
+                break
+            out.write(chunk)
+            downloaded += len(chunk)
+            if downloaded - reported >= PROGRESS_EVERY_BYTES:
+                reported = downloaded
+                output_record({"downloaded": downloaded, "total": total})
+
+    partial.replace(dest)
+    output_record({"downloaded": downloaded, "total": total or downloaded})
+    return dest
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+def output_record(data: dict) -> None:
+    """Output one record of an NDJSON stream (read line by line by Tauri)."""
+    print(json.dumps({"type": "record", "data": data}, default=str), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Packet capture driver check")
+    parser.add_argument("--action", choices=["status", "download"], default="status")
+
+    args = parser.parse_args()
+
+    try:
+        if args.action == "status":
+            output_json({"success": True, "driver": driver_status()})
+        elif args.action == "download":
+            path = download_installer()
+            output_json({"type": "end", "success": True, "path": str(path)})
+    except Exception as e:
+        output_json({"success": False, "error": str(e)})
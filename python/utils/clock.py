@@ -0,0 +1,78 @@
+"""
+Timestamps and Time Zones.
+
+Everything stored - database rows, alerts, incidents - is UTC, written as
+ISO 8601 with an explicit offset and always with microseconds so stored
+values compare correctly as strings. Wall-clock decisions (schedules, quiet
+hours, "today") use the `timezone` setting, an IANA name such as
+"Europe/Berlin"; without one, the system zone.
+"""
+
+from datetime import datetime, timezone, tzinfo
+from typing import Optional, Union
+from zoneinfo import ZoneInfo, ZoneInfoNotFoundError
+
+from .config import get_setting
+
+
+def utc_now() -> datetime:
+    """The current time as an aware UTC datetime."""
+    return datetime.now(timezone.utc)
+
+
+def to_iso(value: datetime) -> str:
+    """Format an aware datetime for storage (in UTC)."""
+    return value.astimezone(timezone.utc).isoformat(timespec="microseconds")
+
+
+def utc_now_iso() -> str:
+    """The current time, formatted for storage."""
+    return to_iso(utc_now())
+
+
+def parse_timestamp(value: str, naive_tz: Optional[tzinfo] = timezone.utc) -> datetime:
+    """
+    Parse a stored timestamp into an aware datetime.
+
+    Timestamps without an offset predate UTC storage; naive_tz says how to
+    read them (None for the system zone).
+    """
+    dt = datetime.fromisoformat(value.replace("Z", "+00:00"))
+    if dt.tzinfo is None:
+        dt = dt.replace(tzinfo=naive_tz) if naive_tz else dt.astimezone()
+    return dt
+
+
+def to_utc_iso(value: Union[str, datetime, None], naive_tz: Optional[tzinfo] = None) -> Optional[str]:
+    """Normalize a timestamp for storage; naive values are read as system local time by default."""
+    if value is None or value == "":
+        return value
+    if isinstance(value, datetime):
+        if value.tzinfo is None:
+            value = value.replace(tzinfo=naive_tz) if naive_tz else value.astimezone()
+        return to_iso(value)
+    return to_iso(parse_timestamp(value, naive_tz))
+
+
+def configured_zone() -> Optional[tzinfo]:
+    """The zone from the `timezone` setting, or None for the system zone."""
+    name = get_setting("timezone")
+    if not name:
+        return None
+    try:
+        return ZoneInfo(name)
+    except (ZoneInfoNotFoundError, ValueError):
+        return None
+
+
+def local_now() -> datetime:
+    """The current wall-clock time in the configured zone (aware)."""
+    zone = configured_zone()
+    return datetime.now(zone) if zone else datetime.now().astimezone()
+
+
+def to_local(value: Union[str, datetime]) -> datetime:
+    """A stored timestamp in the configured zone."""
+    dt = parse_timestamp(value) if isinstance(value, str) else value
+    zone = configured_zone()
+    return dt.astimezone(zone) if zone else dt.astimezone()
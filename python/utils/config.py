@@ -18,6 +18,14 @@ def get_config_path() -> Path:
     return Path(__file__).parent.parent.parent / "config"
 
 
+def get_data_path() -> Path:
+    """Get the data directory path (capture database, alert store)"""
+    if os.environ.get("NETWORK_MONITOR_DATA"):
+        return Path(os.environ["NETWORK_MONITOR_DATA"])
+    
+    return Path(__file__).parent.parent.parent / "database"
+
+
 def load_config(filename: str) -> Dict[str, Any]:
     """
     Load configuration from JSON file
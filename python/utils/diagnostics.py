@@ -0,0 +1,135 @@
+"""
+Diagnostics Bundle Writer.
+
+Packs what the app collected (component statuses, redacted settings,
+recent app logs, errors and crash reports) together with the Python side's
+view - interpreter and package versions, OS details and the engine's log
+files - into a single zip to attach to bug reports.
+"""
+
+import json
+import platform
+import sys
+import zipfile
+from datetime import datetime
+from importlib import metadata
+from pathlib import Path
+from typing import Dict, List, Optional
+
+# Project root (parent of python/), where the engine writes logs/
+PROJECT_ROOT = Path(__file__).resolve().parent.parent.parent
+
+# Newest engine log files included, and how much of the end of each
+MAX_LOG_FILES = 3
+MAX_LOG_BYTES = 1024 * 1024
+
+
+def package_versions() -> Dict[str, Optional[str]]:
+    """Installed versions of the packages in requirements.txt (None if missing)."""
+    versions: Dict[str, Optional[str]] = {}
+    requirements = PROJECT_ROOT / "python" / "requirements.txt"
+    if not requirements.exists():
+        return versions
+
+    for line in requirements.read_text().splitlines():
+        line = line.split("#", 1)[0].strip()
+        if not line:
+            continue
+        name = line.split(";", 1)[0]
+        for sep in (">=", "==", "<=", "~=", ">", "<"):
+            name = name.split(sep, 1)[0]
+        name = name.strip()
+        try:
+            versions[name] = metadata.version(name)
+        except metadata.PackageNotFoundError:
+            versions[name] = None
+    return versions
+
+
+def environment() -> dict:
+    """Interpreter and OS details."""
+    return {
+        "python": {
+            "version": sys.version,
+            "executable": sys.executable,
+            "packages": package_versions(),
+        },
+        "os": {
+            "system": platform.system(),
+            "release": platform.release(),
+            "version": platform.version(),
+            "machine": platform.machine(),
+            "platform": platform.platform(),
+        },
+    }
+
+
+def _tail(path: Path, max_bytes: int) -> str:
+    with open(path, "rb") as f:
+        f.seek(0, 2)
+        size = f.tell()
+        f.seek(max(0, size - max_bytes))
+        return f.read().decode("utf-8", errors="replace")
+
+
+def engine_logs() -> List[Path]:
+    """The newest engine log files."""
+    log_dir = PROJECT_ROOT / "logs"
+    if not log_dir.exists():
+        return []
+    files = sorted(log_dir.glob("*.log"), key=lambda p: p.stat().st_mtime, reverse=True)
+    return files[:MAX_LOG_FILES]
+
+
+def write_bundle(collected: dict, output: Path) -> dict:
+    """Write the zip and return a summary of what went in."""
+    output.parent.mkdir(parents=True, exist_ok=True)
+    env = environment()
+    files: List[str] = []
+
+    def add(name: str, content: str):
+        bundle.writestr(name, content)
+        files.append(name)
+
+    with zipfile.ZipFile(output, "w", compression=zipfile.ZIP_DEFLATED) as bundle:
+        add("summary.json", json.dumps({
+            "generated_at": datetime.now().isoformat(),
+            "app": collected.get("app", {}),
+            "python_version": platform.python_version(),
+            "os": env["os"]["platform"],
+        }, indent=2))
+        add("environment.json", json.dumps(env, indent=2))
+        add("components.json", json.dumps(collected.get("components", {}), indent=2, default=str))
+        add("settings.json", json.dumps(collected.get("settings", {}), indent=2))
+        add("logs/app.log", "\n".join(collected.get("logs", [])))
+        add("errors.txt", "\n".join(collected.get("errors", [])))
+
+        for crash in collected.get("crashes", []):
+            add(f"crashes/{crash['name']}", crash["content"])
+
+        for log_file in engine_logs():
+            add(f"logs/engine/{log_file.name}", _tail(log_file, MAX_LOG_BYTES))
+
+    return {"path": str(output), "size": output.stat().st_size, "files": files}
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Diagnostics bundle writer")
+    parser.add_argument("--input", required=True, help="JSON file with what the app collected")
+    parser.add_argument("--output", required=True, help="Zip file to write")
+
+    args = parser.parse_args()
+
+    try:
+        collected = json.loads(Path(args.input).read_text(encoding="utf-8"))
+        output_json({"success": True, **write_bundle(collected, Path(args.output))})
+    except Exception as e:
+        output_json({"success": False, "error": str(e)})
@@ -0,0 +1,184 @@
+"""
+Home Assistant REST integration for Network Monitor
+
+Pushes device presence as binary_sensor entities and alerts as events (and
+optionally to a webhook) through the Home Assistant REST API. The long-lived
+access token is kept in the OS keychain, never in the settings file.
+
+Inputs that carry the token or bulk data are read as JSON from stdin.
+"""
+
+import json
+import re
+import sys
+from typing import Any, Dict, List, Optional
+
+import requests
+
+KEYRING_SERVICE = "network-monitor"
+KEYRING_USER = "home_assistant"
+
+REQUEST_TIMEOUT = 10
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+def read_input() -> Dict[str, Any]:
+    """Read the JSON document passed on stdin."""
+    data = sys.stdin.read()
+    return json.loads(data) if data.strip() else {}
+
+
+def _keyring():
+    try:
+        import keyring
+        return keyring
+    except ImportError:
+        raise RuntimeError("OS keychain support is not installed (pip install keyring)")
+
+
+def store_token(token: str) -> None:
+    """Save (or with an empty token, remove) the access token in the OS keychain."""
+    keyring = _keyring()
+    if token:
+        keyring.set_password(KEYRING_SERVICE, KEYRING_USER, token)
+    else:
+        try:
+            keyring.delete_password(KEYRING_SERVICE, KEYRING_USER)
+        except keyring.errors.PasswordDeleteError:
+            pass
+
+
+def load_token() -> str:
+    """Get the access token from the OS keychain."""
+    token = _keyring().get_password(KEYRING_SERVICE, KEYRING_USER)
+    if not token:
+        raise RuntimeError("No Home Assistant token configured")
+    return token
+
+
+def entity_slug(value: str) -> str:
+    """Make a string safe for use in an entity ID."""
+    return re.sub(r"[^a-z0-9_]+", "_", value.lower()).strip("_")
+
+
+class HomeAssistantClient:
+    """Minimal client for the Home Assistant REST API."""
+
+    def __init__(self, base_url: str, token: Optional[str] = None):
+        self.base_url = base_url.rstrip("/")
+        self.session = requests.Session()
+        if token:
+            self.session.headers["Authorization"] = f"Bearer {token}"
+        self.session.headers["Content-Type"] = "application/json"
+
+    def _request(self, method: str, path: str, payload: Optional[dict] = None) -> requests.Response:
+        response = self.session.request(
+            method, f"{self.base_url}{path}", json=payload, timeout=REQUEST_TIMEOUT
+        )
+        if response.status_code == 401:
+            raise RuntimeError("Home Assistant rejected the access token")
+        response.raise_for_status()
+        return response
+
+    def test(self) -> Dict[str, Any]:
+        """Check the API is reachable and the token is valid."""
+        message = self._request("GET", "/api/").json().get("message", "")
+        config = self._request("GET", "/api/config").json()
+        return {
+            "message": message,
+            "version": config.get("version"),
+            "location_name": config.get("location_name"),
+        }
+
+    def set_device_state(self, device: Dict[str, Any]) -> None:
+        """Create or update the presence binary_sensor for a device."""
+        name = device.get("hostname") or device.get("ip") or device["id"]
+        entity_id = f"binary_sensor.network_monitor_{entity_slug(device['id'])}"
+        self._request("POST", f"/api/states/{entity_id}", {
+            "state": "on" if device.get("is_online") else "off",
+            "attributes": {
+                "friendly_name": name,
+                "device_class": "connectivity",
+                "ip": device.get("ip"),
+                "mac": device.get("mac"),
+                "vendor": device.get("vendor"),
+                "device_type": device.get("device_type"),
+                "last_seen": device.get("last_seen"),
+            },
+        })
+
+    def fire_event(self, event: str, data: Dict[str, Any]) -> None:
+        """Fire a network_monitor_<event> event on the Home Assistant bus."""
+        self._request("POST", f"/api/events/network_monitor_{event}", data)
+
+    def set_last_alert(self, alert: Dict[str, Any]) -> None:
+        """Expose the most recent alert as a sensor."""
+        self._request("POST", "/api/states/sensor.network_monitor_last_alert", {
+            "state": (alert.get("title") or "")[:255],
+            "attributes": {
+                "friendly_name": "Network Monitor last alert",
+                "icon": "mdi:shield-alert",
+                **{k: alert.get(k) for k in ("severity", "category", "description", "source_device", "timestamp")},
+            },
+        })
+
+    def post_webhook(self, webhook_id: str, payload: Dict[str, Any]) -> None:
+        """Trigger a webhook automation (webhooks don't need the token)."""
+        requests.post(
+            f"{self.base_url}/api/webhook/{webhook_id}", json=payload, timeout=REQUEST_TIMEOUT
+        ).raise_for_status()
+
+
+def push(client: HomeAssistantClient, states: List[dict], events: List[dict], webhook_id: Optional[str]) -> Dict[str, int]:
+    """Push device states and events; returns how many of each were sent."""
+    for device in states:
+        client.set_device_state(device)
+
+    for event in events:
+        name = event.get("event", "")
+        data = event.get("data", {})
+        client.fire_event(name, data)
+        if name == "alert":
+            client.set_last_alert(data)
+        if webhook_id:
+            client.post_webhook(webhook_id, event)
+
+    return {"states": len(states), "events": len(events)}
+
+
+def main():
+    """CLI entry point for the Home Assistant integration."""
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Home Assistant integration")
+    parser.add_argument("--action", choices=["store-token", "test", "push"], required=True)
+    parser.add_argument("--base-url", help="Home Assistant URL, e.g. http://homeassistant.local:8123")
+    parser.add_argument("--webhook-id", help="Webhook ID to also send events to")
+
+    args = parser.parse_args()
+
+    try:
+        if args.action == "store-token":
+            store_token(read_input().get("token", ""))
+            output_json({"success": True})
+
+        elif args.action == "test":
+            client = HomeAssistantClient(args.base_url, load_token())
+            output_json({"success": True, **client.test()})
+
+        elif args.action == "push":
+            data = read_input()
+            client = HomeAssistantClient(args.base_url, load_token())
+            result = push(client, data.get("states", []), data.get("events", []), args.webhook_id)
+            output_json({"success": True, **result})
+
+    except Exception as e:
+        output_json({"success": False, "error": str(e)})
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,41 @@
+"""
+Message Catalogs.
+
+Looks up backend text (alert titles and descriptions) by key in
+locales/<code>.json for the `locale` setting, falling back to English and
+then to the key. Placeholders are written {name}. The Rust side reads the
+same catalogs.
+"""
+
+import json
+from functools import lru_cache
+from pathlib import Path
+from typing import Dict, Optional
+
+from .config import get_setting
+
+LOCALES_DIR = Path(__file__).resolve().parent.parent.parent / "locales"
+
+DEFAULT_LOCALE = "en"
+
+
+@lru_cache(maxsize=None)
+def _catalog(code: str) -> Dict[str, str]:
+    path = LOCALES_DIR / f"{code}.json"
+    try:
+        return json.loads(path.read_text(encoding="utf-8")).get("messages", {})
+    except (OSError, ValueError):
+        return {}
+
+
+def _lookup(key: str) -> Optional[str]:
+    locale = get_setting("locale", DEFAULT_LOCALE) or DEFAULT_LOCALE
+    return _catalog(locale).get(key) or _catalog(DEFAULT_LOCALE).get(key)
+
+
+def t(key: str, default: Optional[str] = None, **args) -> str:
+    """Translate key into the configured locale, filling in {name} placeholders."""
+    text = _lookup(key) or default or key
+    for name, value in args.items():
+        text = text.replace("{" + name + "}", str(value))
+    return text
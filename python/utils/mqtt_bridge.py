@@ -0,0 +1,133 @@
+"""
+MQTT Bridge
+Keeps a connection to an MQTT broker open and publishes messages sent by the
+Tauri backend as JSON lines on stdin:
+
+    {"action": "connect", "host": "...", "port": 1883, "username": "...",
+     "password": "...", "client_id": "...", "status_topic": "..."}
+    {"action": "publish", "topic": "...", "payload": "...", "retain": false}
+    {"action": "stop"}
+
+Credentials arrive over stdin rather than the command line so they don't show
+up in process listings.
+"""
+
+import json
+import sys
+from typing import Optional
+
+import paho.mqtt.client as mqtt
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data), flush=True)
+
+
+class MqttBridge:
+    """Publishes backend events to an MQTT broker."""
+
+    def __init__(self):
+        self.client: Optional[mqtt.Client] = None
+        self.status_topic: Optional[str] = None
+
+    def connect(
+        self,
+        host: str,
+        port: int = 1883,
+        username: Optional[str] = None,
+        password: Optional[str] = None,
+        client_id: str = "network-monitor",
+        status_topic: Optional[str] = None
+    ):
+        """Connect (asynchronously, with automatic reconnects) to the broker."""
+        self.disconnect()
+
+        try:
+            # paho-mqtt 2.x requires choosing a callback API version
+            client = mqtt.Client(mqtt.CallbackAPIVersion.VERSION2, client_id=client_id)
+        except AttributeError:
+            client = mqtt.Client(client_id=client_id)
+
+        if username:
+            client.username_pw_set(username, password or None)
+
+        self.status_topic = status_topic
+        if status_topic:
+            # Broker marks us offline if the connection drops
+            client.will_set(status_topic, "offline", qos=1, retain=True)
+
+        client.on_connect = self._on_connect
+        client.reconnect_delay_set(min_delay=1, max_delay=60)
+        client.connect_async(host, port, keepalive=60)
+        client.loop_start()
+
+        self.client = client
+
+    def _on_connect(self, client, userdata, flags, reason_code, properties=None):
+        if reason_code == 0:
+            if self.status_topic:
+                client.publish(self.status_topic, "online", qos=1, retain=True)
+            output_json({"event": "connected"})
+        else:
+            output_json({"event": "connect_failed", "reason": str(reason_code)})
+
+    def publish(self, topic: str, payload, retain: bool = False):
+        """Publish a message; paho queues it while reconnecting."""
+        if not self.client:
+            return
+
+        if not isinstance(payload, str):
+            payload = json.dumps(payload, default=str)
+
+        self.client.publish(topic, payload, qos=1, retain=retain)
+
+    def disconnect(self):
+        """Publish offline status and close the connection."""
+        if not self.client:
+            return
+
+        if self.status_topic:
+            self.client.publish(self.status_topic, "offline", qos=1, retain=True).wait_for_publish(2)
+        self.client.disconnect()
+        self.client.loop_stop()
+        self.client = None
+
+
+def main():
+    bridge = MqttBridge()
+
+    try:
+        while True:
+            line = sys.stdin.readline()
+            if not line:
+                break
+
+            try:
+                cmd = json.loads(line)
+            except json.JSONDecodeError:
+                continue
+
+            action = cmd.get("action")
+            if action == "stop":
+                break
+            elif action == "connect":
+                bridge.connect(
+                    host=cmd["host"],
+                    port=int(cmd.get("port", 1883)),
+                    username=cmd.get("username"),
+                    password=cmd.get("password"),
+                    client_id=cmd.get("client_id", "network-monitor"),
+                    status_topic=cmd.get("status_topic")
+                )
+            elif action == "publish":
+                bridge.publish(cmd["topic"], cmd.get("payload", ""), cmd.get("retain", False))
+
+    except KeyboardInterrupt:
+        pass
+    finally:
+        bridge.disconnect()
+
+
+if __name__ == "__main__":
+    main()
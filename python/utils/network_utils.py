@@ -2,12 +2,23 @@
 Network utility functions for Network Monitor
 """
 
+import ipaddress
 import socket
 import subprocess
 import re
+import sys
 from typing import Optional, List, Dict, Tuple
 import psutil
 
+# Networks with more addresses than this are only scanned around the local address
+MAX_SCAN_HOSTS = 4096
+
+# Where traceroute aims when looking for a second router (double NAT)
+TRACE_TARGET = "8.8.8.8"
+
+# Carrier-grade NAT space; a hop here is the ISP's NAT, not a second home router
+CGNAT_NETWORK = ipaddress.IPv4Network("100.64.0.0/10")
+
 
 def get_interfaces() -> List[Dict[str, str]]:
     """
@@ -199,12 +210,265 @@ def get_network_range(interface: Optional[str] = None) -> str:
     if not ip:
         return "192.168.1.0/24"
     
-    # Simple /24 assumption for home networks
+    for network in detect_networks():
+        if network["address"] == ip:
+            return str(network["scan_cidr"])
+    
+    # No netmask known for the address: assume a /24, as most home networks are
     parts = ip.split('.')
     parts[3] = '0'
     return '.'.join(parts) + '/24'
 
 
+def _run(command: List[str], timeout: int = 10) -> str:
+    """Run a command and get its output, or "" when it fails"""
+    try:
+        result = subprocess.run(
+            command,
+            capture_output=True,
+            text=True,
+            timeout=timeout,
+            creationflags=subprocess.CREATE_NO_WINDOW if hasattr(subprocess, 'CREATE_NO_WINDOW') else 0
+        )
+        return result.stdout
+    except Exception:
+        return ""
+
+
+def _is_ipv4(value: str) -> bool:
+    try:
+        ipaddress.IPv4Address(value)
+        return True
+    except ValueError:
+        return False
+
+
+def get_gateways() -> List[str]:
+    """
+    Get every IPv4 gateway in the routing table, default gateways first
+    
+    Returns:
+        Gateway IPs; more than one with several interfaces, VLANs or static routes
+    """
+    defaults: List[str] = []
+    others: List[str] = []
+    
+    if sys.platform == "win32":
+        # Destination  Netmask  Gateway  Interface  Metric
+        for line in _run(['route', 'print', '-4']).split('\n'):
+            cols = line.split()
+            if len(cols) == 5 and _is_ipv4(cols[0]) and _is_ipv4(cols[2]):
+                (defaults if cols[0] == '0.0.0.0' else others).append(cols[2])
+    elif sys.platform == "darwin":
+        # Destination  Gateway  Flags  Netif
+        for line in _run(['netstat', '-rn', '-f', 'inet']).split('\n'):
+            cols = line.split()
+            if len(cols) >= 4 and _is_ipv4(cols[1]) and 'G' in cols[2]:
+                (defaults if cols[0] == 'default' else others).append(cols[1])
+    else:
+        for line in _run(['ip', '-4', 'route']).split('\n'):
+            match = re.search(r'\bvia (\d+\.\d+\.\d+\.\d+)', line)
+            if match:
+                (defaults if line.startswith('default') else others).append(match.group(1))
+    
+    gateways: List[str] = []
+    for gateway in defaults + others:
+        if gateway not in gateways:
+            gateways.append(gateway)
+    return gateways
+
+
+def _vlan_id(interface: str) -> Optional[int]:
+    """VLAN ID from an interface name like "eth0.20" or "vlan20" """
+    match = re.search(r'(?:\.|vlan)(\d{1,4})$', interface, re.IGNORECASE)
+    if match and 1 <= int(match.group(1)) <= 4094:
+        return int(match.group(1))
+    return None
+
+
+def detect_networks() -> List[Dict[str, object]]:
+    """
+    Find every IPv4 network this machine is directly connected to
+    
+    Each interface address counts once per network; loopback, link-local and
+    point-to-point (/31, /32) addresses are skipped. Networks too large to
+    sweep are scanned only in the /24 around the local address.
+    
+    Returns:
+        Network dictionaries, the default gateway's first
+    """
+    gateways = get_gateways()
+    stats = psutil.net_if_stats()
+    networks: List[Dict[str, object]] = []
+    seen = set()
+    
+    for name, addr_list in psutil.net_if_addrs().items():
+        if name not in stats or not stats[name].isup:
+            continue
+        for addr in addr_list:
+            if addr.family != socket.AF_INET or not addr.netmask:
+                continue
+            iface = ipaddress.IPv4Interface(f"{addr.address}/{addr.netmask}")
+            if iface.ip.is_loopback or iface.ip.is_link_local or iface.network.prefixlen >= 31:
+                continue
+            if iface.network in seen:
+                continue
+            seen.add(iface.network)
+            
+            scan = iface.network
+            truncated = scan.num_addresses > MAX_SCAN_HOSTS
+            if truncated:
+                scan = ipaddress.IPv4Interface(f"{addr.address}/24").network
+            gateway = next((g for g in gateways if ipaddress.IPv4Address(g) in iface.network), None)
+            
+            networks.append({
+                "cidr": str(iface.network),
+                "interface": name,
+                "address": addr.address,
+                "gateway": gateway,
+                "vlan_id": _vlan_id(name),
+                "scan_cidr": str(scan),
+                "truncated": truncated,
+                "routed": False,
+                "source": "detected",
+            })
+    
+    default = gateways[0] if gateways else None
+    networks.sort(key=lambda n: n["gateway"] is None or n["gateway"] != default)
+    return networks
+
+
+def trace_hops(max_hops: int = 3) -> List[Optional[str]]:
+    """First hops towards the internet, None for ones that didn't answer"""
+    if sys.platform == "win32":
+        command = ['tracert', '-d', '-h', str(max_hops), '-w', '1000', TRACE_TARGET]
+    else:
+        command = ['traceroute', '-n', '-m', str(max_hops), '-w', '1', '-q', '1', TRACE_TARGET]
+    
+    hops: List[Optional[str]] = []
+    for line in _run(command, timeout=max_hops * 5).split('\n'):
+        if not re.match(r'^\s*\d+\s', line):
+            continue
+        match = re.search(r'\b(\d+\.\d+\.\d+\.\d+)\b', line)
+        hops.append(match.group(1) if match else None)
+    return hops
+
+
+def detect_double_nat() -> Dict[str, object]:
+    """
+    Check whether the gateway sits behind a second router
+    
+    The hop after the local gateway having a private address means another
+    NAT router is upstream (a modem-router in front of the user's own).
+    
+    Returns:
+        "double_nat" (None when it couldn't be told) and the upstream router's IP
+    """
+    hops = trace_hops()
+    if len(hops) < 2 or not hops[1]:
+        return {"double_nat": None, "upstream_gateway": None}
+    
+    upstream = ipaddress.IPv4Address(hops[1])
+    double_nat = upstream.is_private and upstream not in CGNAT_NETWORK
+    return {"double_nat": double_nat, "upstream_gateway": hops[1] if double_nat else None}
+
+
+def scan_targets(ranges: Optional[List[str]] = None) -> Dict[str, object]:
+    """
+    Everything a device scan covers: the connected networks, the network of
+    an upstream router behind double NAT and manually added ranges
+    
+    Ranges inside a connected network are swept with ARP on its interface;
+    the others are routed and can only be pinged, so their devices have no MAC.
+    """
+    networks = detect_networks()
+    nat = detect_double_nat()
+    
+    if nat["upstream_gateway"]:
+        upstream = ipaddress.IPv4Interface(f"{nat['upstream_gateway']}/24").network
+        if not any(upstream.overlaps(ipaddress.IPv4Network(n["cidr"])) for n in networks):
+            networks.append({
+                "cidr": str(upstream),
+                "interface": None,
+                "address": None,
+                "gateway": nat["upstream_gateway"],
+                "vlan_id": None,
+                "scan_cidr": str(upstream),
+                "truncated": False,
+                "routed": True,
+                "source": "upstream",
+            })
+    
+    for cidr in ranges or []:
+        network = ipaddress.IPv4Network(cidr, strict=False)
+        owner = next((
+            n for n in networks
+            if not n["routed"] and network.subnet_of(ipaddress.IPv4Network(n["cidr"]))
+        ), None)
+        networks.append({
+            "cidr": str(network),
+            "interface": owner["interface"] if owner else None,
+            "address": owner["address"] if owner else None,
+            "gateway": owner["gateway"] if owner else None,
+            "vlan_id": owner["vlan_id"] if owner else None,
+            "scan_cidr": str(network),
+            "truncated": False,
+            "routed": owner is None,
+            "source": "manual",
+        })
+    
+    return {"networks": networks, **nat}
+
+
+def validate_interface(name: str) -> Dict[str, object]:
+    """
+    Check whether an interface can be used for capture
+    
+    Returns:
+        Dictionary of capability flags plus the list of known interface names
+    """
+    from .capture_driver import driver_status
+    
+    addrs = psutil.net_if_addrs()
+    stats = psutil.net_if_stats()
+    
+    result = {
+        "name": name,
+        "exists": name in addrs,
+        "is_up": bool(name in stats and stats[name].isup),
+        "has_ipv4": False,
+        "ipv4": None,
+        "supports_promiscuous": False,
+        "capture_driver_ok": False,
+        "capture_error": None,
+        "available": sorted(addrs.keys()),
+        "capture_driver": driver_status(),
+    }
+    
+    if not result["exists"]:
+        return result
+    
+    for addr in addrs[name]:
+        if addr.family == socket.AF_INET and not addr.address.startswith('127.'):
+            result["has_ipv4"] = True
+            result["ipv4"] = addr.address
+            break
+    
+    # Opening a promiscuous L2 socket exercises the Npcap/libpcap binding
+    try:
+        from scapy.all import conf
+        sock = conf.L2listen(iface=name, promisc=True)
+        sock.close()
+        result["supports_promiscuous"] = True
+        result["capture_driver_ok"] = True
+    except ImportError as e:
+        result["capture_error"] = f"scapy is not installed: {e}"
+    except Exception as e:
+        result["capture_error"] = str(e)
+    
+    return result
+
+
 def is_admin() -> bool:
     """Check if running with administrator privileges"""
     try:
@@ -239,9 +503,11 @@ def main():
     
     parser = argparse.ArgumentParser(description="Network utilities")
     parser.add_argument("--action", choices=[
-        "get-ip", "list-interfaces", "get-gateway", "get-mac", "get-range", "is-admin"
+        "get-ip", "list-interfaces", "get-gateway", "get-mac", "get-range", "is-admin",
+        "validate-interface", "ensure-forwarding", "detect-networks"
     ], default="list-interfaces", help="Action to perform")
     parser.add_argument("--interface", help="Network interface name")
+    parser.add_argument("--range", action="append", default=[], help="Extra CIDR range to scan (repeatable)")
     
     args = parser.parse_args()
     
@@ -291,6 +557,31 @@ def main():
                 "range": net_range
             })
         
+        elif args.action == "detect-networks":
+            output_json({
+                "success": True,
+                **scan_targets(args.range)
+            })
+        
+        elif args.action == "validate-interface":
+            if not args.interface:
+                output_json({"success": False, "error": "No interface specified"})
+                return
+            
+            output_json({
+                "success": True,
+                "capabilities": validate_interface(args.interface)
+            })
+        
+        elif args.action == "ensure-forwarding":
+            from ..arp.ip_forwarding import ensure_ip_forwarding
+            enabled, message = ensure_ip_forwarding(args.interface)
+            output_json({
+                "success": True,
+                "ip_forwarding_enabled": enabled,
+                "message": message
+            })
+        
         elif args.action == "is-admin":
             admin = is_admin()
             output_json({
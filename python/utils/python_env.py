@@ -0,0 +1,153 @@
+"""
+Python Environment Check and Repair.
+
+Compares the packages installed in the interpreter running this script (the
+app's venv) with the pins in `python/requirements.lock`, and reinstalls any
+that are missing or at another version with pip, streaming one record per
+package. Only the standard library is used here, so this still runs when the
+packages it repairs are broken or missing.
+"""
+
+import json
+import re
+import subprocess
+import sys
+from importlib import metadata
+from pathlib import Path
+from typing import Dict, List, Optional
+
+# Project root (parent of python/)
+PROJECT_ROOT = Path(__file__).resolve().parent.parent.parent
+LOCK_FILE = PROJECT_ROOT / "python" / "requirements.lock"
+
+# Longest a single package install may take
+INSTALL_TIMEOUT_SECONDS = 600
+
+# `name==version` with an optional `; sys_platform == "..."` marker
+LOCK_LINE = re.compile(
+    r'^(?P<name>[A-Za-z0-9._-]+)==(?P<version>[^\s;]+)'
+    r'\s*(?:;\s*sys_platform\s*(?P<op>==|!=)\s*["\'](?P<platform>[^"\']+)["\'])?\s*$'
+)
+
+
+def locked_packages(lock_file: Path = LOCK_FILE) -> List[Dict[str, str]]:
+    """The pins that apply to this platform. Raises ValueError on a line it can't read."""
+    packages = []
+    for number, line in enumerate(lock_file.read_text().splitlines(), 1):
+        line = line.split("#", 1)[0].strip()
+        if not line:
+            continue
+        match = LOCK_LINE.match(line)
+        if not match:
+            raise ValueError(f"Unreadable pin on line {number} of {lock_file.name}: {line}")
+        if match["op"] and (sys.platform == match["platform"]) != (match["op"] == "=="):
+            continue
+        packages.append({"name": match["name"], "version": match["version"]})
+    return packages
+
+
+def installed_version(name: str) -> Optional[str]:
+    try:
+        return metadata.version(name)
+    except metadata.PackageNotFoundError:
+        return None
+
+
+def verify() -> dict:
+    """Each pinned package with its installed version and "ok", "missing" or "mismatch"."""
+    packages = []
+    for package in locked_packages():
+        installed = installed_version(package["name"])
+        if installed is None:
+            status = "missing"
+        elif installed != package["version"]:
+            status = "mismatch"
+        else:
+            status = "ok"
+        packages.append({
+            "name": package["name"],
+            "required": package["version"],
+            "installed": installed,
+            "status": status,
+        })
+
+    return {
+        "python": sys.version.split()[0],
+        "executable": sys.executable,
+        "in_venv": sys.prefix != sys.base_prefix,
+        "healthy": all(p["status"] == "ok" for p in packages),
+        "packages": packages,
+    }
+
+
+def _pip(*args: str) -> subprocess.CompletedProcess:
+    return subprocess.run(
+        [sys.executable, "-m", "pip", "--disable-pip-version-check", *args],
+        capture_output=True, text=True, timeout=INSTALL_TIMEOUT_SECONDS,
+    )
+
+
+def _ensure_pip():
+    """Bootstrap pip into a venv created without it."""
+    if installed_version("pip") is None:
+        subprocess.run([sys.executable, "-m", "ensurepip", "--upgrade"], capture_output=True, check=True)
+
+
+def repair() -> dict:
+    """Reinstall every missing or mismatched package, one record per package."""
+    broken = [p for p in verify()["packages"] if p["status"] != "ok"]
+    if broken:
+        _ensure_pip()
+
+    repaired: List[str] = []
+    failed: Dict[str, str] = {}
+    for index, package in enumerate(broken, 1):
+        pin = f"{package['name']}=={package['required']}"
+        output_record({"package": package["name"], "index": index, "total": len(broken), "stage": "installing"})
+        # The lock constrains dependencies pulled in along the way as well
+        result = _pip("install", "--constraint", str(LOCK_FILE), pin)
+        if result.returncode == 0:
+            repaired.append(package["name"])
+            output_record({"package": package["name"], "index": index, "total": len(broken), "stage": "installed"})
+        else:
+            error = (result.stderr.strip().splitlines() or ["pip failed"])[-1]
+            failed[package["name"]] = error
+            output_record({
+                "package": package["name"], "index": index, "total": len(broken),
+                "stage": "failed", "error": error,
+            })
+
+    return {"repaired": repaired, "failed": failed}
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+def output_record(data: dict) -> None:
+    """Output one record of an NDJSON stream (read line by line by Tauri)."""
+    print(json.dumps({"type": "record", "data": data}, default=str), flush=True)
+
+
+# CLI interface
+if __name__ == "__main__":
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Python environment check and repair")
+    parser.add_argument("--action", choices=["verify", "repair"], default="verify")
+
+    args = parser.parse_args()
+
+    try:
+        if args.action == "verify":
+            output_json({"success": True, "environment": verify()})
+        elif args.action == "repair":
+            result = repair()
+            if result["failed"]:
+                failed = ", ".join(f"{name} ({error})" for name, error in result["failed"].items())
+                output_json({"type": "end", "success": False, "error": f"Failed to install {failed}", **result})
+            else:
+                output_json({"type": "end", "success": True, **result})
+    except Exception as e:
+        output_json({"success": False, "error": str(e)})
@@ -0,0 +1,69 @@
+"""
+Page screenshots for alert evidence
+
+Loads a URL in a headless Chromium and saves what it rendered as a PNG, so an
+alert about a page can show what the page looked like. The page is fetched
+from this machine, not the device that visited it. Without Playwright (and its
+Chromium, `playwright install chromium`) no screenshots are taken.
+"""
+
+import json
+from urllib.parse import urlparse
+
+try:
+    from playwright.sync_api import sync_playwright
+except ImportError:  # pragma: no cover - optional dependency
+    sync_playwright = None
+
+VIEWPORT = {"width": 1280, "height": 800}
+
+# Longest a page may take to load, in milliseconds
+LOAD_TIMEOUT_MS = 20_000
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+def capture(url: str, output: str) -> None:
+    """Render `url` and write the visible part of the page to `output`."""
+    if urlparse(url).scheme not in ("http", "https"):
+        raise ValueError(f"Not a web page: {url}")
+    if sync_playwright is None:
+        raise RuntimeError("Screenshots need Playwright (pip install playwright && playwright install chromium)")
+
+    with sync_playwright() as playwright:
+        browser = playwright.chromium.launch(headless=True)
+        try:
+            page = browser.new_page(viewport=VIEWPORT, ignore_https_errors=True)
+            page.goto(url, timeout=LOAD_TIMEOUT_MS, wait_until="load")
+            page.screenshot(path=output)
+        finally:
+            browser.close()
+
+
+def main():
+    """CLI entry point for page screenshots."""
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Page screenshots")
+    parser.add_argument("--url", required=True, help="Page to render")
+    parser.add_argument("--output", required=True, help="PNG file to write")
+
+    args = parser.parse_args()
+
+    try:
+        capture(args.url, args.output)
+        output_json({"success": True, "path": args.output})
+
+    except Exception as e:
+        output_json({
+            "success": False,
+            "error": str(e),
+            "type": type(e).__name__
+        })
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,20 @@
+"""
+Secrets kept in the OS keychain
+
+The app stores credentials (SMTP password, webhook URL, MQTT and upstream
+proxy passwords, integration tokens) under one keychain service rather than in
+the settings files; this reads them back for the Python side.
+"""
+
+from typing import Optional
+
+KEYRING_SERVICE = "network-monitor"
+
+
+def get_secret(name: str) -> Optional[str]:
+    """Get a secret, or None when it isn't stored or no keychain is available."""
+    try:
+        import keyring
+        return keyring.get_password(KEYRING_SERVICE, name) or None
+    except Exception:
+        return None
@@ -0,0 +1,102 @@
+"""
+Upstream proxy check for Network Monitor
+
+Sends one request through the configured upstream proxy, the way the HTTPS
+proxy will forward intercepted traffic, and reports whether it got through.
+The upstream settings (with the password) are read as JSON from stdin.
+"""
+
+import json
+import sys
+import time
+from typing import Any, Dict, Optional
+from urllib.parse import quote
+
+import requests
+
+# Answers 204 with no body; reachable from most networks
+DEFAULT_TEST_URL = "https://connectivitycheck.gstatic.com/generate_204"
+
+REQUEST_TIMEOUT = 10
+
+
+def output_json(data: dict) -> None:
+    """Output data as JSON to stdout for Tauri IPC."""
+    print(json.dumps(data, default=str), flush=True)
+
+
+def read_input() -> Dict[str, Any]:
+    """Read the JSON document passed on stdin."""
+    data = sys.stdin.read()
+    return json.loads(data) if data.strip() else {}
+
+
+def proxy_url(upstream: Dict[str, Any]) -> str:
+    """The upstream URL with its credentials, as requests expects it."""
+    url = upstream["url"].rstrip("/")
+    username = upstream.get("username")
+    if not username:
+        return url
+    scheme, address = url.split("://", 1)
+    credentials = quote(username, safe="")
+    if upstream.get("password"):
+        credentials += ":" + quote(upstream["password"], safe="")
+    return f"{scheme}://{credentials}@{address}"
+
+
+def test_upstream(upstream: Dict[str, Any], url: Optional[str] = None) -> Dict[str, Any]:
+    """Fetch `url` through the upstream proxy, timing the round trip."""
+    proxy = proxy_url(upstream)
+    started = time.monotonic()
+    try:
+        response = requests.get(
+            url or DEFAULT_TEST_URL,
+            proxies={"http": proxy, "https": proxy},
+            timeout=REQUEST_TIMEOUT,
+            allow_redirects=False
+        )
+    except requests.exceptions.ProxyError as e:
+        return {"ok": False, "status": None, "latency_ms": None, "error": f"Proxy error: {e}"}
+    except requests.exceptions.Timeout:
+        return {"ok": False, "status": None, "latency_ms": None,
+                "error": f"No answer within {REQUEST_TIMEOUT} seconds"}
+    except requests.RequestException as e:
+        return {"ok": False, "status": None, "latency_ms": None, "error": str(e)}
+
+    latency_ms = round((time.monotonic() - started) * 1000)
+    ok = response.status_code < 400
+    return {
+        "ok": ok,
+        "status": response.status_code,
+        "latency_ms": latency_ms,
+        "error": None if ok else f"HTTP {response.status_code} {response.reason}"
+    }
+
+
+def main():
+    """CLI entry point for the upstream proxy check."""
+    import argparse
+
+    parser = argparse.ArgumentParser(description="Upstream proxy check")
+    parser.add_argument("--action", choices=["test"], default="test", help="Action to perform")
+
+    args = parser.parse_args()
+
+    try:
+        if args.action == "test":
+            data = read_input()
+            output_json({
+                "success": True,
+                "test": test_upstream(data["upstream"], data.get("url"))
+            })
+
+    except Exception as e:
+        output_json({
+            "success": False,
+            "error": str(e),
+            "type": type(e).__name__
+        })
+
+
+if __name__ == "__main__":
+    main()
@@ -127,7 +127,15 @@ if ($Debug) {
     pnpm tauri build --debug
 } else {
     Write-Host "  Building in release mode..." -ForegroundColor Gray
-    pnpm tauri build
+    # Updater artifacts need the signing key and a public key to check them against
+    $pubkey = (Get-Content src-tauri/tauri.conf.json -Raw | ConvertFrom-Json).plugins.updater.pubkey
+    if ($env:TAURI_SIGNING_PRIVATE_KEY -and $pubkey) {
+        Write-Host "  Signing updater artifacts" -ForegroundColor Gray
+        pnpm tauri build --config src-tauri/tauri.updater.conf.json
+    } else {
+        Write-Host "  No signing key or public key configured, skipping updater artifacts" -ForegroundColor Gray
+        pnpm tauri build
+    }
 }
 
 if ($LASTEXITCODE -ne 0) {
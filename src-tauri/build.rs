@@ -1,3 +1,37 @@
 fn main() {
+    #[cfg(feature = "grpc")]
+    grpc_stubs();
+
     tauri_build::build()
 }
+
+/// Server stubs for `proto/control.proto`; the messages are written out in `src/grpc_server.rs`
+#[cfg(feature = "grpc")]
+fn grpc_stubs() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::grpc_server::{}", input))
+            .output_type(format!("crate::grpc_server::{}", output))
+            .codec_path("tonic::codec::ProstCodec")
+    };
+
+    let service = Service::builder()
+        .name("Control")
+        .package("netmon.v1")
+        .method(method("get_status", "GetStatus", "Empty", "MonitorStatus").build())
+        .method(method("watch_status", "WatchStatus", "Empty", "MonitorStatus").server_streaming().build())
+        .method(method("start_monitoring", "StartMonitoring", "Empty", "MonitorStatus").build())
+        .method(method("stop_monitoring", "StopMonitoring", "Empty", "MonitorStatus").build())
+        .method(method("list_devices", "ListDevices", "ListDevicesRequest", "DeviceList").build())
+        .method(method("list_rules", "ListRules", "Empty", "RuleList").build())
+        .method(method("add_rule", "AddRule", "Rule", "Empty").build())
+        .method(method("remove_rule", "RemoveRule", "Rule", "Empty").build())
+        .build();
+
+    println!("cargo:rerun-if-changed=build.rs");
+    Builder::new().build_client(false).compile(&[service]);
+}
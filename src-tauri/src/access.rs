@@ -0,0 +1,279 @@
+// Read-only observer sessions
+//
+// The owner can hand out observer tokens for sharing the dashboard. An
+// instance started with `--observer <token>` (or NM_OBSERVER_TOKEN set) runs as
+// an observer: every command goes through `authorize` before it runs, and only
+// the ones that read state are let through, so an observer can view
+// everything but can't change rules, settings or stop monitoring. Secrets are
+// redacted from the settings it sees. Revoking a token ends its sessions at
+// their next command. The daemon applies the same rule to requests that carry
+// an observer token: they may ask for status and nothing else. The owner's GUI
+// and CLI identify themselves to the daemon with an owner token, a secret in
+// the top-level config directory only the user who created it can read;
+// requests with no token are refused. Installing the service creates it as the
+// installing user, so the daemon running as root or SYSTEM and that user's
+// GUI share it.
+
+use crate::clock;
+use crate::i18n::t;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Environment variable an observer instance can take its token from
+pub const TOKEN_ENV: &str = "NM_OBSERVER_TOKEN";
+
+/// Commands that only read state, besides the ones named by READ_ONLY_PREFIXES
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "compare_device_activity",
+    "subscribe_traffic",
+    "unsubscribe_traffic",
+    "validate_interface",
+];
+
+const READ_ONLY_PREFIXES: &[&str] = &["get_", "list_", "search_", "preview_", "check_"];
+
+/// Daemon requests an observer token allows
+const READ_ONLY_DAEMON_REQUESTS: &[&str] = &["status"];
+
+const MAX_NAME_LEN: usize = 100;
+
+/// Serializes read-modify-write of the token file
+static TOKENS_LOCK: Mutex<()> = Mutex::new(());
+
+/// The observer token this instance runs with; None for the owner
+static SESSION: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ObserverToken {
+    pub id: String,
+    pub name: String,
+    pub token: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessInfo {
+    pub read_only: bool,
+    /// Name of the observer token this session runs with
+    pub observer: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct TokensFile {
+    #[serde(default)]
+    tokens: Vec<ObserverToken>,
+}
+
+fn get_tokens_path() -> PathBuf {
+    workspace::config_dir().join("observer_tokens.json")
+}
+
+/// Shared by every workspace, so the daemon and the GUI agree whichever is active
+pub fn get_owner_token_path() -> PathBuf {
+    workspace::root().join("config").join("daemon_token")
+}
+
+fn load_file() -> Result<TokensFile, String> {
+    let path = get_tokens_path();
+    if !path.exists() {
+        return Ok(TokensFile::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read observer tokens: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse observer tokens: {}", e))
+}
+
+fn save_file(file: &TokensFile) -> Result<(), String> {
+    fs::create_dir_all(workspace::config_dir())
+        .map_err(|e| format!("Failed to create config dir: {}", e))?;
+    let content = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize observer tokens: {}", e))?;
+    fs::write(get_tokens_path(), content).map_err(|e| format!("Failed to write observer tokens: {}", e))
+}
+
+/// Write a file only the current user can read
+fn write_private(path: &Path, content: &str) -> Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Compare without stopping at the first difference
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn find(token: &str) -> Result<Option<ObserverToken>, String> {
+    Ok(load_file()?.tokens.into_iter().find(|t| same_token(&t.token, token)))
+}
+
+fn random_hex(bytes: usize) -> Result<String, String> {
+    let mut buf = vec![0u8; bytes];
+    getrandom::getrandom(&mut buf).map_err(|e| format!("Failed to generate token: {}", e))?;
+    Ok(buf.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Whether a command only reads state
+pub fn is_read_only(command: &str) -> bool {
+    READ_ONLY_COMMANDS.contains(&command) || READ_ONLY_PREFIXES.iter().any(|p| command.starts_with(p))
+}
+
+/// The capability check run before every command
+pub fn authorize(command: &str) -> Result<(), String> {
+    let Some(token) = SESSION.lock().unwrap().clone() else {
+        return Ok(());
+    };
+    if find(&token)?.is_none() {
+        return Err(t("error.observer_token_revoked", &[]));
+    }
+    if is_read_only(command) {
+        Ok(())
+    } else {
+        log::warn!("Observer session tried to run {}", command);
+        Err(t("error.read_only_session", &[("command", &command)]))
+    }
+}
+
+/// The secret the owner presents to the daemon, created on first use
+pub fn owner_token() -> Result<String, String> {
+    let _guard = TOKENS_LOCK.lock().unwrap();
+    let path = get_owner_token_path();
+    if let Ok(content) = fs::read_to_string(&path) {
+        if !content.trim().is_empty() {
+            return Ok(content.trim().to_string());
+        }
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    let token = random_hex(32)?;
+    write_private(&path, &token)?;
+    log::info!("Created the daemon owner token");
+    Ok(token)
+}
+
+/// The check for a daemon request, given the owner or observer token it carried
+pub fn authorize_daemon(request: &str, token: Option<&str>) -> Result<(), String> {
+    let Some(token) = token else {
+        log::warn!("Refused daemon request {} without a token", request);
+        return Err(t("error.daemon_unauthorized", &[]));
+    };
+    if same_token(&owner_token()?, token) {
+        return Ok(());
+    }
+    if find(token)?.is_none() {
+        return Err(t("error.observer_token_revoked", &[]));
+    }
+    if READ_ONLY_DAEMON_REQUESTS.contains(&request) {
+        Ok(())
+    } else {
+        Err(t("error.read_only_session", &[("command", &request)]))
+    }
+}
+
+/// Run this instance as an observer with the given token
+pub fn enter_observer_session(token: &str) -> Result<(), String> {
+    let observer = find(token)?.ok_or_else(|| t("error.observer_token_revoked", &[]))?;
+    log::info!("Running as a read-only observer ({})", observer.name);
+    *SESSION.lock().unwrap() = Some(token.to_string());
+    Ok(())
+}
+
+/// Start an observer session if the command line or environment asks for one
+pub fn init_from_args() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().collect();
+    let token = args
+        .iter()
+        .position(|a| a == "--observer")
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| std::env::var(TOKEN_ENV).ok().filter(|t| !t.is_empty()));
+
+    match token {
+        Some(token) => enter_observer_session(&token),
+        None => Ok(()),
+    }
+}
+
+/// The token this session runs with, to pass on to the daemon
+pub fn session_token() -> Option<String> {
+    SESSION.lock().unwrap().clone()
+}
+
+pub fn is_observer() -> bool {
+    SESSION.lock().unwrap().is_some()
+}
+
+pub fn info() -> Result<AccessInfo, String> {
+    let observer = match session_token() {
+        Some(token) => find(&token)?.map(|t| t.name),
+        None => None,
+    };
+    Ok(AccessInfo {
+        read_only: is_observer(),
+        observer,
+    })
+}
+
+/// Tokens with all but their last characters masked
+pub fn list_tokens() -> Result<Vec<ObserverToken>, String> {
+    Ok(load_file()?
+        .tokens
+        .into_iter()
+        .map(|mut t| {
+            let shown = t.token.len().saturating_sub(4);
+            t.token = format!("…{}", &t.token[shown..]);
+            t
+        })
+        .collect())
+}
+
+/// Create a token; the only time it is returned in full
+pub fn create_token(name: &str) -> Result<ObserverToken, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Token name is empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Token names can be at most {} characters", MAX_NAME_LEN));
+    }
+
+    let token = ObserverToken {
+        id: format!("obs-{}", random_hex(4)?),
+        name: name.to_string(),
+        token: random_hex(24)?,
+        created_at: clock::now_utc(),
+    };
+
+    let _guard = TOKENS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    file.tokens.push(token.clone());
+    save_file(&file)?;
+
+    log::info!("Created observer token {} ({})", token.id, token.name);
+    Ok(token)
+}
+
+pub fn revoke_token(id: &str) -> Result<(), String> {
+    let _guard = TOKENS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    let before = file.tokens.len();
+    file.tokens.retain(|t| t.id != id);
+    if file.tokens.len() == before {
+        return Err(format!("Observer token not found: {}", id));
+    }
+    save_file(&file)?;
+
+    log::info!("Revoked observer token {}", id);
+    Ok(())
+}
@@ -0,0 +1,326 @@
+// Comparison of a device's activity between two periods
+//
+// Domains contacted, apps used and bandwidth are gathered for each period from
+// raw traffic, its rolled-up aggregates and DNS lookups, then diffed. Hosts are
+// grouped by site (e.g. "cdn.example.com" under "example.com") so a new CDN
+// subdomain of a site the device already used doesn't show up as new; a site
+// that is new in the compared period often means a newly installed app.
+
+use crate::apps::{self, AppUsage};
+use crate::clock::{self, Zone};
+use crate::database::open_readonly;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// How many sites each list in the comparison holds at most
+const MAX_SITES: usize = 50;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum NamedPeriod {
+    Today,
+    Yesterday,
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    LastMonth,
+}
+
+/// A period to compare: a named one, or explicit start/end timestamps (wall-clock
+/// times in the configured zone unless they carry an offset)
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Period {
+    Named(NamedPeriod),
+    Custom { start: String, end: String },
+}
+
+impl Period {
+    /// Start and end as UTC query bounds
+    pub fn bounds(&self) -> (String, String) {
+        let zone = Zone::configured();
+        match self {
+            Period::Custom { start, end } => (custom_bound(zone, start), custom_bound(zone, end)),
+            Period::Named(named) => {
+                let (start, end) = named_bounds(zone, *named);
+                zone.utc_bounds(start, end)
+            }
+        }
+    }
+}
+
+fn custom_bound(zone: Zone, bound: &str) -> String {
+    let wall = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(bound, fmt).ok())
+        .or_else(|| NaiveDate::parse_from_str(bound, "%Y-%m-%d").ok().map(|d| d.and_time(NaiveTime::MIN)));
+    match wall {
+        Some(wall) => zone.utc_bounds(wall, wall).0,
+        None => clock::parse_stored(bound)
+            .map(|at| at.format("%Y-%m-%dT%H:%M:%S").to_string())
+            .unwrap_or_else(|| bound.to_string()),
+    }
+}
+
+fn named_bounds(zone: Zone, period: NamedPeriod) -> (NaiveDateTime, NaiveDateTime) {
+    let now = zone.now();
+    let today = now.date();
+    let at_midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN);
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let month_start = today.with_day(1).unwrap_or(today);
+    let last_month_start = (month_start - Duration::days(1)).with_day(1).unwrap_or(month_start);
+
+    match period {
+        NamedPeriod::Today => (at_midnight(today), now),
+        NamedPeriod::Yesterday => (at_midnight(today - Duration::days(1)), at_midnight(today)),
+        NamedPeriod::ThisWeek => (at_midnight(week_start), now),
+        NamedPeriod::LastWeek => (at_midnight(week_start - Duration::days(7)), at_midnight(week_start)),
+        NamedPeriod::ThisMonth => (at_midnight(month_start), now),
+        NamedPeriod::LastMonth => (at_midnight(last_month_start), at_midnight(month_start)),
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PeriodSummary {
+    pub start: String,
+    pub end: String,
+    pub requests: u64,
+    pub bytes: u64,
+    pub sites: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SiteActivity {
+    pub site: String,
+    pub hosts: Vec<String>,
+    pub requests: u64,
+    pub bytes: u64,
+    pub first_seen: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SiteChange {
+    pub site: String,
+    pub bytes_a: u64,
+    pub bytes_b: u64,
+    pub requests_a: u64,
+    pub requests_b: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AppChange {
+    pub app_id: String,
+    pub name: String,
+    pub category: String,
+    pub active_minutes_a: u32,
+    pub active_minutes_b: u32,
+    pub bytes_a: u64,
+    pub bytes_b: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ActivityComparison {
+    pub device_id: String,
+    pub period_a: PeriodSummary,
+    pub period_b: PeriodSummary,
+    /// Change in bandwidth from period B to period A, as a percentage (None if B had none)
+    pub bytes_change_percent: Option<f64>,
+    /// Sites contacted in period A but not in period B
+    pub new_sites: Vec<SiteActivity>,
+    /// Sites contacted in period B but no longer in period A
+    pub dropped_sites: Vec<SiteActivity>,
+    /// Sites contacted in both, by largest change in bandwidth
+    pub changed_sites: Vec<SiteChange>,
+    pub apps: Vec<AppChange>,
+}
+
+#[derive(Default)]
+struct SiteAccumulator {
+    hosts: BTreeSet<String>,
+    requests: u64,
+    bytes: u64,
+    first_seen: Option<String>,
+}
+
+/// Group a host under its site: the last two labels, or three for
+/// country-code second-level domains like "example.co.uk"
+pub fn site_of(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+
+    let labels: Vec<&str> = host.split('.').collect();
+    let keep = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && second.len() <= 3 && labels.len() > 2 => 3,
+        _ => 2,
+    };
+    labels[labels.len().saturating_sub(keep)..].join(".")
+}
+
+fn site_activity(device_id: &str, start: &str, end: &str) -> Result<HashMap<String, SiteAccumulator>, String> {
+    let conn = open_readonly()?;
+    let mut sites: HashMap<String, SiteAccumulator> = HashMap::new();
+
+    // Proxy traffic (raw and rolled up) carries sizes; DNS lookups add what the proxy can't see
+    let queries = [
+        "SELECT host, MIN(timestamp), COUNT(*),
+                SUM(COALESCE(request_size, 0) + COALESCE(response_size, 0))
+         FROM traffic
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND device_id = ?3 AND host IS NOT NULL
+         GROUP BY host",
+        "SELECT host, MIN(minute), SUM(requests), SUM(bytes_in + bytes_out)
+         FROM traffic_aggregates
+         WHERE minute >= substr(?1, 1, 16) AND minute < ?2 AND device_id = ?3 AND host IS NOT NULL
+         GROUP BY host",
+        "SELECT query_name, MIN(timestamp), COUNT(*), 0
+         FROM dns_queries
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND device_id = ?3 AND query_name IS NOT NULL
+         GROUP BY query_name",
+    ];
+
+    for sql in queries {
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map(params![start, end, device_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                ))
+            })
+            .map_err(|e| format!("Failed to query activity: {}", e))?;
+
+        for row in rows {
+            let (host, first_seen, requests, bytes) =
+                row.map_err(|e| format!("Failed to read activity: {}", e))?;
+            if host.is_empty() {
+                continue;
+            }
+
+            let acc = sites.entry(site_of(&host)).or_default();
+            acc.hosts.insert(host.trim_end_matches('.').to_lowercase());
+            acc.requests += requests as u64;
+            acc.bytes += bytes as u64;
+            if first_seen.is_some() && (acc.first_seen.is_none() || first_seen < acc.first_seen) {
+                acc.first_seen = first_seen;
+            }
+        }
+    }
+
+    Ok(sites)
+}
+
+fn summarize(start: String, end: String, sites: &HashMap<String, SiteAccumulator>) -> PeriodSummary {
+    PeriodSummary {
+        start,
+        end,
+        requests: sites.values().map(|s| s.requests).sum(),
+        bytes: sites.values().map(|s| s.bytes).sum(),
+        sites: sites.len(),
+    }
+}
+
+/// Sites present in `from` but not in `other`, busiest first
+fn sites_missing_from(
+    from: &HashMap<String, SiteAccumulator>,
+    other: &HashMap<String, SiteAccumulator>,
+) -> Vec<SiteActivity> {
+    let mut sites: Vec<SiteActivity> = from
+        .iter()
+        .filter(|(site, _)| !other.contains_key(*site))
+        .map(|(site, acc)| SiteActivity {
+            site: site.clone(),
+            hosts: acc.hosts.iter().cloned().collect(),
+            requests: acc.requests,
+            bytes: acc.bytes,
+            first_seen: acc.first_seen.clone(),
+        })
+        .collect();
+
+    sites.sort_by(|a, b| b.requests.cmp(&a.requests).then(b.bytes.cmp(&a.bytes)));
+    sites.truncate(MAX_SITES);
+    sites
+}
+
+fn compare_apps(a: Vec<AppUsage>, b: Vec<AppUsage>) -> Vec<AppChange> {
+    let mut changes: HashMap<String, AppChange> = HashMap::new();
+
+    for (usage, in_a) in a.into_iter().map(|u| (u, true)).chain(b.into_iter().map(|u| (u, false))) {
+        let change = changes.entry(usage.app_id.clone()).or_insert_with(|| AppChange {
+            app_id: usage.app_id.clone(),
+            name: usage.name.clone(),
+            category: usage.category.clone(),
+            active_minutes_a: 0,
+            active_minutes_b: 0,
+            bytes_a: 0,
+            bytes_b: 0,
+        });
+        if in_a {
+            change.active_minutes_a = usage.active_minutes;
+            change.bytes_a = usage.bytes;
+        } else {
+            change.active_minutes_b = usage.active_minutes;
+            change.bytes_b = usage.bytes;
+        }
+    }
+
+    let mut changes: Vec<AppChange> = changes.into_values().collect();
+    changes.sort_by_key(|c| std::cmp::Reverse(c.active_minutes_a.abs_diff(c.active_minutes_b)));
+    changes
+}
+
+/// Diff a device's activity in period A against period B (the baseline)
+pub fn compare(device_id: &str, period_a: &Period, period_b: &Period) -> Result<ActivityComparison, String> {
+    let (start_a, end_a) = period_a.bounds();
+    let (start_b, end_b) = period_b.bounds();
+    if start_a >= end_a || start_b >= end_b {
+        return Err("Period start must be before its end".to_string());
+    }
+
+    let sites_a = site_activity(device_id, &start_a, &end_a)?;
+    let sites_b = site_activity(device_id, &start_b, &end_b)?;
+
+    let mut changed_sites: Vec<SiteChange> = sites_a
+        .iter()
+        .filter_map(|(site, a)| {
+            let b = sites_b.get(site)?;
+            Some(SiteChange {
+                site: site.clone(),
+                bytes_a: a.bytes,
+                bytes_b: b.bytes,
+                requests_a: a.requests,
+                requests_b: b.requests,
+            })
+        })
+        .filter(|c| c.bytes_a != c.bytes_b || c.requests_a != c.requests_b)
+        .collect();
+    changed_sites.sort_by_key(|c| std::cmp::Reverse(c.bytes_a.abs_diff(c.bytes_b)));
+    changed_sites.truncate(MAX_SITES);
+
+    let apps = compare_apps(
+        apps::app_usage_between(Some(device_id), &start_a, &end_a)?,
+        apps::app_usage_between(Some(device_id), &start_b, &end_b)?,
+    );
+
+    let period_a = summarize(start_a, end_a, &sites_a);
+    let period_b = summarize(start_b, end_b, &sites_b);
+    let bytes_change_percent = (period_b.bytes > 0)
+        .then(|| (period_a.bytes as f64 - period_b.bytes as f64) / period_b.bytes as f64 * 100.0);
+
+    Ok(ActivityComparison {
+        device_id: device_id.to_string(),
+        period_a,
+        period_b,
+        bytes_change_percent,
+        new_sites: sites_missing_from(&sites_a, &sites_b),
+        dropped_sites: sites_missing_from(&sites_b, &sites_a),
+        changed_sites,
+        apps,
+    })
+}
@@ -0,0 +1,226 @@
+// Firmware vulnerability hints
+//
+// Devices say what they run in what they advertise: UPnP servers put their
+// stack and version in the SSDP `SERVER` header, description documents give
+// the model and often the firmware, and admin pages name the model in their
+// login realm or title. `python/arp/service_discovery.py` collects those
+// banners, and they're matched against the CVE hints bundled in
+// `config/firmware_advisories.json`. A hint names the products it applies to
+// and, when known, the affected firmware versions; without a readable
+// version, a model match is still shown, as unconfirmed. These are hints to
+// go and check the vendor's advisories, not a vulnerability scan.
+
+use crate::commands::Device;
+use crate::discovery;
+use crate::python::get_project_root;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+
+/// Service details that describe the device rather than name it
+const BANNER_KEYS: &[&str] = &[
+    "server",
+    "http_server",
+    "http_realm",
+    "http_title",
+    "manufacturer",
+    "model",
+    "model_number",
+    "md",
+    "ty",
+    "product",
+    "am",
+    "firmware",
+];
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AdvisoryHint {
+    pub id: String,
+    pub title: String,
+    pub severity: String,
+    pub summary: String,
+    pub reference: Option<String>,
+    /// Lowercase text any of which in a banner means the product
+    #[serde(rename = "match")]
+    pub patterns: Vec<String>,
+    /// Lowercase text the device's vendor or manufacturer must contain
+    pub vendor: Option<String>,
+    /// The version follows this text in the banner, rather than coming from the firmware field
+    pub version_after: Option<String>,
+    /// First affected version (inclusive)
+    pub affected_from: Option<String>,
+    /// First fixed version
+    pub fixed_in: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct HintFile {
+    #[serde(default)]
+    advisories: Vec<AdvisoryHint>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceAdvisory {
+    pub id: String,
+    pub title: String,
+    /// "critical", "high", "medium" or "low"
+    pub severity: String,
+    pub summary: String,
+    pub reference: Option<String>,
+    /// The banner the product was recognized in
+    pub matched: String,
+    /// Firmware version read from the banners, if any
+    pub firmware: Option<String>,
+    pub fixed_in: Option<String>,
+    /// The firmware is known to be in the affected range; otherwise only the product matched
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceAdvisories {
+    pub device_id: String,
+    /// What the device was matched on
+    pub banners: Vec<String>,
+    pub advisories: Vec<DeviceAdvisory>,
+}
+
+fn load_hints() -> Result<Vec<AdvisoryHint>, String> {
+    let path = get_project_root().join("config").join("firmware_advisories.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read firmware advisories: {}", e))?;
+    parse_hints(&content)
+}
+
+pub fn parse_hints(content: &str) -> Result<Vec<AdvisoryHint>, String> {
+    let file: HintFile =
+        serde_json::from_str(content).map_err(|e| format!("Failed to parse firmware advisories: {}", e))?;
+    Ok(file.advisories)
+}
+
+/// The first version number in text: "V5.3.0 build 150513" -> "5.3.0"
+pub fn version_in(text: &str) -> Option<String> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let version: String = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let version = version.trim_end_matches('.');
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> { v.split('.').map(|p| p.parse().unwrap_or(0)).collect() };
+    let (a, b) = (parts(a), parts(b));
+    for i in 0..a.len().max(b.len()) {
+        let order = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    Ordering::Equal
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "critical" => 0,
+        "high" => 1,
+        "medium" => 2,
+        _ => 3,
+    }
+}
+
+/// Hints that apply to a device with these banners, vendor and firmware, most severe first
+pub fn matching(
+    hints: &[AdvisoryHint],
+    banners: &[String],
+    vendor: Option<&str>,
+    firmware: Option<&str>,
+) -> Vec<DeviceAdvisory> {
+    let lowered: Vec<String> = banners.iter().map(|b| b.to_lowercase()).collect();
+    let vendors = format!("{} {}", vendor.unwrap_or_default(), lowered.join(" ")).to_lowercase();
+
+    let mut advisories: Vec<DeviceAdvisory> = hints
+        .iter()
+        .filter_map(|hint| {
+            if hint.vendor.as_deref().is_some_and(|v| !vendors.contains(v)) {
+                return None;
+            }
+            let index = lowered.iter().position(|b| hint.patterns.iter().any(|p| b.contains(p.as_str())))?;
+            let version = match &hint.version_after {
+                Some(marker) => lowered
+                    .iter()
+                    .find_map(|b| b.split_once(marker.as_str()).and_then(|(_, rest)| version_in(rest))),
+                None => firmware.and_then(version_in),
+            };
+
+            let ranged = hint.affected_from.is_some() || hint.fixed_in.is_some();
+            if let Some(version) = version.as_deref().filter(|_| ranged) {
+                let after_start = hint
+                    .affected_from
+                    .as_deref()
+                    .is_none_or(|from| compare_versions(version, from) != Ordering::Less);
+                let before_fix = hint
+                    .fixed_in
+                    .as_deref()
+                    .is_none_or(|fixed| compare_versions(version, fixed) == Ordering::Less);
+                if !(after_start && before_fix) {
+                    return None;
+                }
+            }
+
+            Some(DeviceAdvisory {
+                id: hint.id.clone(),
+                title: hint.title.clone(),
+                severity: hint.severity.clone(),
+                summary: hint.summary.clone(),
+                reference: hint.reference.clone(),
+                matched: banners[index].clone(),
+                confirmed: ranged && version.is_some(),
+                firmware: version,
+                fixed_in: hint.fixed_in.clone(),
+            })
+        })
+        .collect();
+    advisories.sort_by(|a, b| {
+        severity_rank(&a.severity)
+            .cmp(&severity_rank(&b.severity))
+            .then(b.confirmed.cmp(&a.confirmed))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    advisories
+}
+
+/// Advisories for what a device advertises about itself
+pub fn device_advisories(device: &Device) -> Result<DeviceAdvisories, String> {
+    let hints = load_hints()?;
+    let services = discovery::device_services(device).services;
+
+    let mut banners: Vec<String> = Vec::new();
+    let mut firmware = None;
+    for service in &services {
+        for key in BANNER_KEYS {
+            let Some(value) = service.details.get(*key).map(|v| v.trim()).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            if *key == "firmware" && firmware.is_none() {
+                firmware = Some(value.to_string());
+            }
+            if !banners.iter().any(|b| b == value) {
+                banners.push(value.to_string());
+            }
+        }
+    }
+
+    let advisories = matching(&hints, &banners, device.vendor.as_deref(), firmware.as_deref());
+    if !advisories.is_empty() {
+        log::info!("{} firmware advisories for device {}", advisories.len(), device.id);
+    }
+    Ok(DeviceAdvisories {
+        device_id: device.id.clone(),
+        banners,
+        advisories,
+    })
+}
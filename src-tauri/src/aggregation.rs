@@ -0,0 +1,96 @@
+// Background rollup of raw traffic into per-minute aggregates
+//
+// Raw entries older than `raw_retention_days` are folded into
+// `traffic_aggregates` (minute/device/host) and deleted, keeping the traffic
+// table small on busy networks. Stats queries read both tables. The same
+// maintenance pass then enforces the per-class retention policy, and builds
+// the traffic search index if the database doesn't have one yet.
+
+use crate::commands::load_settings;
+use crate::database;
+use crate::db_writer::write_database;
+use crate::retention;
+use crate::stats_cache;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// How often the rollup and retention run
+const AGGREGATE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Set once SQLite turned out to have no trigram tokenizer, so the build isn't retried every pass
+static SEARCH_INDEX_UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Roll up raw traffic past the configured retention window
+pub fn run_aggregation() -> Result<Value, String> {
+    let days = load_settings()?.raw_retention_days.to_string();
+    let result = write_database("aggregate", &[("--days", &days)])?;
+    // Raw rows moved into aggregates would otherwise be counted again
+    stats_cache::invalidate();
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(result.get("aggregate").cloned().unwrap_or(Value::Null))
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Build the traffic search index from scratch; the rows indexed, or None without trigram support
+pub fn rebuild_search_index() -> Result<Option<u64>, String> {
+    let result = write_database("rebuild-search-index", &[])?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        if !result.get("available").and_then(|a| a.as_bool()).unwrap_or(true) {
+            SEARCH_INDEX_UNAVAILABLE.store(true, Ordering::Relaxed);
+            return Ok(None);
+        }
+        Ok(Some(result.get("indexed").and_then(|n| n.as_u64()).unwrap_or(0)))
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Build the search index if the database has none yet
+fn ensure_search_index() -> Result<Option<u64>, String> {
+    if SEARCH_INDEX_UNAVAILABLE.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    let conn = database::open_readonly()?;
+    if database::has_search_index(&conn) {
+        return Ok(None);
+    }
+    drop(conn);
+
+    log::info!("Building traffic search index");
+    rebuild_search_index()
+}
+
+/// Spawn the background task that keeps raw traffic and stored data within their windows
+pub fn spawn_aggregator() {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match tauri::async_runtime::spawn_blocking(run_aggregation).await {
+                Ok(Ok(summary)) => log::info!("Traffic aggregation finished: {}", summary),
+                Ok(Err(e)) => log::debug!("Traffic aggregation failed: {}", e),
+                Err(e) => log::warn!("Traffic aggregation task panicked: {}", e),
+            }
+
+            match tauri::async_runtime::spawn_blocking(retention::apply).await {
+                Ok(Ok(report)) => log::info!("Retention removed {} rows", report.total),
+                Ok(Err(e)) => log::debug!("Retention failed: {}", e),
+                Err(e) => log::warn!("Retention task panicked: {}", e),
+            }
+
+            match tauri::async_runtime::spawn_blocking(ensure_search_index).await {
+                Ok(Ok(Some(indexed))) => log::info!("Traffic search index built with {} rows", indexed),
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => log::debug!("Search index build failed: {}", e),
+                Err(e) => log::warn!("Search index task panicked: {}", e),
+            }
+
+            tokio::time::sleep(AGGREGATE_INTERVAL).await;
+        }
+    });
+}
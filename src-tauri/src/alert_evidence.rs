@@ -0,0 +1,273 @@
+// Alert evidence
+//
+// When a new alert is stored, what it was about is captured while it is still
+// in the database: the alerting device's traffic (to the alert's domain, when
+// it names one) and its DNS queries from a few minutes either side of the
+// alert, and with `screenshots` on, a rendering of the alert's URL fetched by
+// a headless browser (`python/utils/screenshot.py`). Evidence is kept in the
+// data directory's `alert_evidence/` next to the alert store, one JSON file
+// (and PNG) per alert. Alerts from before evidence was captured get theirs the
+// first time it is asked for, without a screenshot.
+
+use crate::clock;
+use crate::commands::{DnsQuery, TrafficEntry};
+use crate::database::{self, open_readonly, row_to_dns, row_to_traffic};
+use crate::python::run_python_script;
+use crate::workspace;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+const SCRIPT: &str = "python/utils/screenshot.py";
+
+/// Traffic and DNS this long before and after the alert are captured
+const WINDOW_MINUTES: i64 = 2;
+
+const MAX_TRAFFIC: u32 = 100;
+const MAX_DNS: u32 = 200;
+
+/// Evidence kept on disk; the oldest is deleted past this
+const MAX_STORED: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct AlertEvidenceSettings {
+    /// Capture traffic and DNS evidence for new alerts
+    pub enabled: bool,
+    /// Also render the alert's URL in a headless browser
+    pub screenshots: bool,
+}
+
+impl Default for AlertEvidenceSettings {
+    fn default() -> Self {
+        AlertEvidenceSettings { enabled: true, screenshots: false }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AlertEvidence {
+    pub alert_id: String,
+    pub captured_at: String,
+    /// UTC bounds of the traffic and DNS captured
+    pub window_start: String,
+    pub window_end: String,
+    /// Newest first
+    pub traffic: Vec<TrafficEntry>,
+    /// Newest first
+    pub dns: Vec<DnsQuery>,
+    /// Page rendered, fetched with `get_alert_screenshot`
+    pub screenshot_url: Option<String>,
+    /// Why the page couldn't be rendered
+    pub screenshot_error: Option<String>,
+}
+
+fn evidence_dir() -> PathBuf {
+    workspace::data_dir().join("alert_evidence")
+}
+
+fn validate_id(alert_id: &str) -> Result<(), String> {
+    let valid = !alert_id.is_empty()
+        && alert_id.len() <= 128
+        && alert_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid alert ID: {}", alert_id))
+    }
+}
+
+fn evidence_path(alert_id: &str) -> PathBuf {
+    evidence_dir().join(format!("{}.json", alert_id))
+}
+
+fn screenshot_path(alert_id: &str) -> PathBuf {
+    evidence_dir().join(format!("{}.png", alert_id))
+}
+
+fn text<'a>(alert: &'a Value, key: &str) -> Option<&'a str> {
+    alert.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty())
+}
+
+/// The domain an alert is about: its own, or its URL's host
+fn alert_domain(alert: &Value) -> Option<String> {
+    if let Some(domain) = text(alert, "domain") {
+        return Some(domain.trim_end_matches('.').to_lowercase());
+    }
+    let url = text(alert, "url")?;
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next()?.rsplit('@').next()?;
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host);
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+fn matching_traffic(
+    conn: &Connection,
+    bounds: (&str, &str),
+    device: (Option<&str>, Option<&str>),
+    domain: Option<&str>,
+) -> Result<Vec<TrafficEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT * FROM traffic
+             WHERE timestamp >= ?1 AND timestamp < ?2
+               AND (?3 IS NULL AND ?4 IS NULL OR device_ip = ?3 OR device_id = ?4)
+               AND (?5 IS NULL OR host = ?5 OR host LIKE '%.' || ?5)
+             ORDER BY timestamp DESC LIMIT ?6",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map(params![bounds.0, bounds.1, device.0, device.1, domain, MAX_TRAFFIC], row_to_traffic)
+        .map_err(|e| format!("Failed to query traffic: {}", e))?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read traffic: {}", e))
+}
+
+/// The device's DNS queries, and anyone's for the alert's domain
+fn matching_dns(
+    conn: &Connection,
+    bounds: (&str, &str),
+    device: (Option<&str>, Option<&str>),
+    domain: Option<&str>,
+) -> Result<Vec<DnsQuery>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT * FROM dns_queries
+             WHERE timestamp >= ?1 AND timestamp < ?2
+               AND (device_ip = ?3 OR device_id = ?4 OR query_name = ?5 OR query_name LIKE '%.' || ?5)
+             ORDER BY timestamp DESC LIMIT ?6",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map(params![bounds.0, bounds.1, device.0, device.1, domain, MAX_DNS], row_to_dns)
+        .map_err(|e| format!("Failed to query DNS queries: {}", e))?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read DNS queries: {}", e))
+}
+
+fn take_screenshot(alert_id: &str, url: &str) -> Result<(), String> {
+    let output = screenshot_path(alert_id).to_string_lossy().to_string();
+    let result = run_python_script(SCRIPT, &["--url", url, "--output", &output])?;
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(())
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+fn save(evidence: &AlertEvidence) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(evidence)
+        .map_err(|e| format!("Failed to serialize alert evidence: {}", e))?;
+    fs::write(evidence_path(&evidence.alert_id), content)
+        .map_err(|e| format!("Failed to write alert evidence: {}", e))
+}
+
+/// Delete the oldest evidence beyond MAX_STORED
+fn prune() {
+    let Ok(entries) = fs::read_dir(evidence_dir()) else { return };
+    let mut stored: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    if stored.len() <= MAX_STORED {
+        return;
+    }
+    stored.sort();
+    for (_, path) in &stored[..stored.len() - MAX_STORED] {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(path.with_extension("png"));
+    }
+}
+
+/// Capture and store the evidence for an alert
+pub fn capture(alert: &Value, screenshot: bool) -> Result<AlertEvidence, String> {
+    let alert_id = text(alert, "id").ok_or_else(|| "Alert has no ID".to_string())?;
+    validate_id(alert_id)?;
+
+    let at = text(alert, "timestamp").and_then(clock::parse_stored).unwrap_or_else(chrono::Utc::now);
+    let window = chrono::Duration::minutes(WINDOW_MINUTES);
+    let (start, end) = (clock::utc_bound(at - window), clock::utc_bound(at + window));
+
+    let device = (text(alert, "source_ip"), text(alert, "source_device"));
+    let domain = alert_domain(alert);
+    let (traffic, dns) = if device.0.is_none() && device.1.is_none() && domain.is_none() {
+        (vec![], vec![])
+    } else {
+        let conn = open_readonly()?;
+        (
+            matching_traffic(&conn, (&start, &end), device, domain.as_deref())?,
+            matching_dns(&conn, (&start, &end), device, domain.as_deref())?,
+        )
+    };
+
+    fs::create_dir_all(evidence_dir()).map_err(|e| format!("Failed to create evidence directory: {}", e))?;
+
+    let mut evidence = AlertEvidence {
+        alert_id: alert_id.to_string(),
+        captured_at: clock::now_utc(),
+        window_start: start,
+        window_end: end,
+        traffic,
+        dns,
+        screenshot_url: None,
+        screenshot_error: None,
+    };
+    if let Some(url) = text(alert, "url").filter(|_| screenshot) {
+        match take_screenshot(alert_id, url) {
+            Ok(()) => evidence.screenshot_url = Some(url.to_string()),
+            Err(e) => {
+                log::debug!("No screenshot for alert {}: {}", alert_id, e);
+                evidence.screenshot_error = Some(e);
+            }
+        }
+    }
+
+    save(&evidence)?;
+    prune();
+    log::info!(
+        "Captured evidence for alert {}: {} traffic entries, {} DNS queries",
+        alert_id,
+        evidence.traffic.len(),
+        evidence.dns.len()
+    );
+    Ok(evidence)
+}
+
+/// An alert's evidence, captured now if it never was
+pub fn get(alert_id: &str) -> Result<AlertEvidence, String> {
+    validate_id(alert_id)?;
+
+    let path = evidence_path(alert_id);
+    if path.exists() {
+        let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read alert evidence: {}", e))?;
+        return serde_json::from_str(&content).map_err(|e| format!("Failed to parse alert evidence: {}", e));
+    }
+
+    let alert = database::stored_alerts()
+        .into_iter()
+        .find(|a| a.get("id").and_then(|i| i.as_str()) == Some(alert_id))
+        .ok_or_else(|| format!("Alert not found: {}", alert_id))?;
+    capture(&alert, false)
+}
+
+pub fn screenshot(alert_id: &str) -> Result<Vec<u8>, String> {
+    validate_id(alert_id)?;
+    fs::read(screenshot_path(alert_id)).map_err(|e| format!("Failed to read alert screenshot: {}", e))
+}
+
+/// Remove an alert's evidence along with the alert
+pub fn delete(alert_id: &str) -> Result<(), String> {
+    validate_id(alert_id)?;
+    for path in [evidence_path(alert_id), screenshot_path(alert_id)] {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete alert evidence: {}", e)),
+        }
+    }
+    Ok(())
+}
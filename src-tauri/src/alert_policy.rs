@@ -0,0 +1,81 @@
+// Alert severity mapping and escalation
+//
+// Two settings let the user decide what counts as critical. The severity map
+// overrides the severity an alert is raised with, keyed by rule (keyword) or
+// category, as the rate limits are. Escalation rules turn a burst of serious
+// alerts from one device into a critical incident with a notification. The
+// alert engine in `python/alerts/alert_engine.py` applies both as alerts come
+// in; this side only checks them before they are saved.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const SEVERITIES: &[&str] = &["low", "medium", "high", "critical"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EscalationRule {
+    pub name: String,
+    /// Alerts of at least this severity count towards the rule
+    #[serde(default = "default_min_severity")]
+    pub min_severity: String,
+    /// Only alerts of this category count (None for any)
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Alerts from one device that trigger the rule...
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// ...within this many minutes
+    #[serde(default = "default_window_minutes")]
+    pub window_minutes: u32,
+    /// Send a notification when the rule fires
+    #[serde(default = "default_notify")]
+    pub notify: bool,
+}
+
+fn default_min_severity() -> String {
+    "high".to_string()
+}
+
+fn default_count() -> u32 {
+    3
+}
+
+fn default_window_minutes() -> u32 {
+    60
+}
+
+fn default_notify() -> bool {
+    true
+}
+
+fn check_severity(severity: &str) -> Result<(), String> {
+    if SEVERITIES.contains(&severity) {
+        Ok(())
+    } else {
+        Err(format!("Unknown severity: {} (expected one of {})", severity, SEVERITIES.join(", ")))
+    }
+}
+
+/// Reject unknown severities, unnamed or duplicate rules and empty thresholds
+pub fn validate(severity_map: &BTreeMap<String, String>, escalations: &[EscalationRule]) -> Result<(), String> {
+    for severity in severity_map.values() {
+        check_severity(severity)?;
+    }
+
+    let mut names = Vec::new();
+    for rule in escalations {
+        let name = rule.name.trim();
+        if name.is_empty() {
+            return Err("Escalation rules need a name".to_string());
+        }
+        if names.contains(&name) {
+            return Err(format!("Duplicate escalation rule: {}", name));
+        }
+        names.push(name);
+        check_severity(&rule.min_severity)?;
+        if rule.count == 0 || rule.window_minutes == 0 {
+            return Err(format!("Escalation rule {} needs a count and window of at least 1", name));
+        }
+    }
+    Ok(())
+}
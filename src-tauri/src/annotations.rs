@@ -0,0 +1,85 @@
+// Traffic tags and notes
+//
+// During an investigation entries get marked ("reviewed", "suspicious",
+// "false positive") and annotated with a free-form note. Both are stored on
+// the traffic row itself, so they come back with every read of the entry, are
+// included in exports, and can be searched for with `tag:<name>` terms.
+
+use crate::db_writer::write_database;
+use crate::demo;
+
+/// Search term prefix that matches entries carrying a tag
+pub const TAG_PREFIX: &str = "tag:";
+
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LEN: usize = 64;
+const MAX_NOTE_LEN: usize = 4000;
+
+/// Lowercased, de-duplicated tags in the order given, with inner spaces as
+/// dashes so each is one search term ("False positive" -> "false-positive")
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.split_whitespace().collect::<Vec<_>>().join("-").to_lowercase();
+        if tag.is_empty() || normalized.contains(&tag) {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(format!("Tags can be at most {} characters", MAX_TAG_LEN));
+        }
+        normalized.push(tag);
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(format!("An entry can have at most {} tags", MAX_TAGS));
+    }
+    Ok(normalized)
+}
+
+fn annotate(entry_id: &str, args: &[(&str, &str)]) -> Result<(), String> {
+    let mut all_args = vec![("--id", entry_id)];
+    all_args.extend_from_slice(args);
+
+    let result = write_database("annotate-traffic", &all_args)?;
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(())
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Replace an entry's tags
+pub fn set_tags(entry_id: &str, tags: &[String]) -> Result<Vec<String>, String> {
+    let tags = normalize_tags(tags)?;
+    if let Some(result) = demo::annotate(entry_id, Some(&tags), None) {
+        return result.map(|_| tags);
+    }
+
+    let encoded = serde_json::to_string(&tags).map_err(|e| format!("Failed to serialize tags: {}", e))?;
+    annotate(entry_id, &[("--tags", &encoded)])?;
+    log::info!("Tagged traffic entry {} with {:?}", entry_id, tags);
+    Ok(tags)
+}
+
+/// Set an entry's note; an empty note removes it
+pub fn set_note(entry_id: &str, note: &str) -> Result<(), String> {
+    let note = note.trim();
+    if note.chars().count() > MAX_NOTE_LEN {
+        return Err(format!("Notes can be at most {} characters", MAX_NOTE_LEN));
+    }
+    if let Some(result) = demo::annotate(entry_id, None, Some(note)) {
+        return result;
+    }
+
+    annotate(entry_id, &[("--note", note)])?;
+    log::info!("Annotated traffic entry {}", entry_id);
+    Ok(())
+}
+
+/// Split a search query into its `tag:` terms (normalized) and the rest
+pub fn split_query(query: &str) -> (Vec<String>, String) {
+    let (tags, rest): (Vec<&str>, Vec<&str>) =
+        query.split_whitespace().partition(|t| t.len() > TAG_PREFIX.len() && t.to_lowercase().starts_with(TAG_PREFIX));
+    let tags = tags.iter().map(|t| t[TAG_PREFIX.len()..].to_lowercase()).collect();
+    (tags, rest.join(" "))
+}
@@ -0,0 +1,269 @@
+// API keys for remote clients
+//
+// Programs talking to the app over a network interface authenticate with an
+// API key rather than an observer token. Each key has scopes: `read_only` for
+// commands that only read state, `alert_management` for acting on alerts and
+// incidents, and `admin` for everything. A key can expire, is limited to its
+// own number of requests per minute, and records when it was last used. Keys
+// are kept in the config directory like observer tokens, and only shown in
+// full when created. The daemon's localhost protocol takes a key in a
+// request's `api_key` field.
+
+use crate::access;
+use crate::clock;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const MAX_NAME_LEN: usize = 100;
+
+const DEFAULT_RATE_LIMIT: u32 = 60;
+const MAX_RATE_LIMIT: u32 = 10_000;
+
+/// Daemon requests that only read state
+const READ_ONLY_DAEMON_REQUESTS: &[&str] = &["status"];
+
+/// Commands an `alert_management` key may run besides the read-only ones
+const ALERT_MANAGEMENT_COMMANDS: &[&str] = &[
+    "mark_alert_read",
+    "mark_all_alerts_read",
+    "resolve_alert",
+    "delete_alert",
+    "resolve_incident",
+];
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Last use is written to disk at most this often per key
+const LAST_USED_PRECISION: chrono::Duration = chrono::Duration::minutes(1);
+
+/// Serializes read-modify-write of the key file
+static KEYS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Recent requests per key ID, for rate limiting
+static RECENT_REQUESTS: Mutex<BTreeMap<String, VecDeque<Instant>>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiScope {
+    ReadOnly,
+    AlertManagement,
+    Admin,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub key: String,
+    pub scopes: Vec<ApiScope>,
+    /// Requests allowed per minute
+    pub rate_limit: u32,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct KeysFile {
+    #[serde(default)]
+    keys: Vec<ApiKey>,
+}
+
+fn get_keys_path() -> PathBuf {
+    workspace::config_dir().join("api_keys.json")
+}
+
+fn load_file() -> Result<KeysFile, String> {
+    let path = get_keys_path();
+    if !path.exists() {
+        return Ok(KeysFile::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read API keys: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse API keys: {}", e))
+}
+
+fn save_file(file: &KeysFile) -> Result<(), String> {
+    fs::create_dir_all(workspace::config_dir())
+        .map_err(|e| format!("Failed to create config dir: {}", e))?;
+    let content = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize API keys: {}", e))?;
+    fs::write(get_keys_path(), content).map_err(|e| format!("Failed to write API keys: {}", e))
+}
+
+fn random_hex(bytes: usize) -> Result<String, String> {
+    let mut buf = vec![0u8; bytes];
+    getrandom::getrandom(&mut buf).map_err(|e| format!("Failed to generate API key: {}", e))?;
+    Ok(buf.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Compare without stopping at the first difference
+fn same_key(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The scope a command or daemon request needs
+pub fn required_scope(command: &str) -> ApiScope {
+    if access::is_read_only(command) || READ_ONLY_DAEMON_REQUESTS.contains(&command) {
+        ApiScope::ReadOnly
+    } else if ALERT_MANAGEMENT_COMMANDS.contains(&command) {
+        ApiScope::AlertManagement
+    } else {
+        ApiScope::Admin
+    }
+}
+
+/// Whether scopes cover what a request needs; admin covers everything
+pub fn allows(scopes: &[ApiScope], needed: ApiScope) -> bool {
+    scopes.contains(&ApiScope::Admin) || scopes.contains(&needed)
+}
+
+fn is_expired(key: &ApiKey) -> bool {
+    key.expires_at
+        .as_deref()
+        .and_then(clock::parse_stored)
+        .is_some_and(|at| at <= chrono::Utc::now())
+}
+
+/// Count a request against a key's limit; fails once the limit is reached
+fn check_rate(key: &ApiKey) -> Result<(), String> {
+    let mut recent = RECENT_REQUESTS.lock().unwrap();
+    let requests = recent.entry(key.id.clone()).or_default();
+    let now = Instant::now();
+    while requests.front().is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW) {
+        requests.pop_front();
+    }
+    if requests.len() >= key.rate_limit as usize {
+        return Err(format!("Rate limit of {} requests per minute reached for API key {}", key.rate_limit, key.name));
+    }
+    requests.push_back(now);
+    Ok(())
+}
+
+fn record_use(id: &str) -> Result<(), String> {
+    let _guard = KEYS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    let Some(key) = file.keys.iter_mut().find(|k| k.id == id) else { return Ok(()) };
+    let recent = key
+        .last_used_at
+        .as_deref()
+        .and_then(clock::parse_stored)
+        .is_some_and(|at| chrono::Utc::now() - at < LAST_USED_PRECISION);
+    if !recent {
+        key.last_used_at = Some(clock::now_utc());
+        save_file(&file)?;
+    }
+    Ok(())
+}
+
+/// Check a request made with `key` that needs `scope`, returning the key's name
+pub fn authenticate(key: &str, scope: ApiScope) -> Result<String, String> {
+    let api_key = load_file()?
+        .keys
+        .into_iter()
+        .find(|k| same_key(&k.key, key))
+        .ok_or_else(|| "Invalid API key".to_string())?;
+    if is_expired(&api_key) {
+        return Err(format!("API key {} has expired", api_key.name));
+    }
+    if !allows(&api_key.scopes, scope) {
+        log::warn!("API key {} lacks the {:?} scope", api_key.name, scope);
+        return Err(format!("API key {} is not allowed to do this", api_key.name));
+    }
+    check_rate(&api_key)?;
+    if let Err(e) = record_use(&api_key.id) {
+        log::debug!("Failed to record use of API key {}: {}", api_key.id, e);
+    }
+    Ok(api_key.name)
+}
+
+/// Keys with all but their last characters masked
+pub fn list_keys() -> Result<Vec<ApiKey>, String> {
+    Ok(load_file()?
+        .keys
+        .into_iter()
+        .map(|mut k| {
+            let shown = k.key.len().saturating_sub(4);
+            k.key = format!("…{}", &k.key[shown..]);
+            k
+        })
+        .collect())
+}
+
+/// Create a key; the only time it is returned in full
+pub fn create_key(
+    name: &str,
+    scopes: Vec<ApiScope>,
+    expires_at: Option<&str>,
+    rate_limit: Option<u32>,
+) -> Result<ApiKey, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("API key name is empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("API key names can be at most {} characters", MAX_NAME_LEN));
+    }
+    if scopes.is_empty() {
+        return Err("An API key needs at least one scope".to_string());
+    }
+    let expires_at = match expires_at.filter(|e| !e.is_empty()) {
+        Some(expiry) => {
+            let at = clock::parse_stored(expiry).ok_or_else(|| format!("Invalid expiry: {}", expiry))?;
+            if at <= chrono::Utc::now() {
+                return Err("API key expiry is in the past".to_string());
+            }
+            Some(clock::utc_bound(at))
+        }
+        None => None,
+    };
+    let rate_limit = rate_limit.unwrap_or(DEFAULT_RATE_LIMIT);
+    if !(1..=MAX_RATE_LIMIT).contains(&rate_limit) {
+        return Err(format!("Rate limit must be between 1 and {} requests per minute", MAX_RATE_LIMIT));
+    }
+
+    let mut unique = Vec::new();
+    for scope in scopes {
+        if !unique.contains(&scope) {
+            unique.push(scope);
+        }
+    }
+    let key = ApiKey {
+        id: format!("key-{}", random_hex(4)?),
+        name: name.to_string(),
+        key: format!("nm_{}", random_hex(24)?),
+        scopes: unique,
+        rate_limit,
+        created_at: clock::now_utc(),
+        expires_at,
+        last_used_at: None,
+    };
+
+    let _guard = KEYS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    file.keys.push(key.clone());
+    save_file(&file)?;
+
+    log::info!("Created API key {} ({})", key.id, key.name);
+    Ok(key)
+}
+
+pub fn revoke_key(id: &str) -> Result<(), String> {
+    let _guard = KEYS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    let before = file.keys.len();
+    file.keys.retain(|k| k.id != id);
+    if file.keys.len() == before {
+        return Err(format!("API key not found: {}", id));
+    }
+    save_file(&file)?;
+
+    RECENT_REQUESTS.lock().unwrap().remove(id);
+    log::info!("Revoked API key {}", id);
+    Ok(())
+}
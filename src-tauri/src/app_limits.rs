@@ -0,0 +1,301 @@
+// Per-device daily usage limits for apps and app categories
+//
+// Limits are checked against today's recognized usage once a minute. When a
+// device goes over, the app's domains are blocked for that device through the
+// blocking engine; the block is lifted once usage is back under the limit
+// (i.e. at midnight, or when the limit is raised or removed).
+
+use crate::apps::{self, AppUsage, UsageRange, APPS};
+use crate::commands::broadcast_to_processes;
+use crate::database;
+use crate::i18n::t;
+use crate::event_bus;
+use crate::python::run_blocking_command;
+use crate::state::AppState;
+use crate::workspace;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often limits are re-evaluated
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Serializes read-modify-write of the limits file
+static LIMITS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppLimit {
+    pub device_id: String,
+    /// An app ID ("youtube") or an app category ("gaming")
+    pub target: String,
+    pub daily_minutes: u32,
+}
+
+/// A limit currently enforced through the blocking engine
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct EnforcedLimit {
+    device_id: String,
+    target: String,
+    device_ip: String,
+    domains: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct LimitsFile {
+    #[serde(default)]
+    limits: Vec<AppLimit>,
+    #[serde(default)]
+    enforced: Vec<EnforcedLimit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppLimitStatus {
+    pub device_id: String,
+    pub target: String,
+    pub target_name: String,
+    pub daily_minutes: u32,
+    pub used_minutes: u32,
+    pub remaining_minutes: u32,
+    pub exceeded: bool,
+    pub enforced: bool,
+}
+
+fn get_limits_path() -> PathBuf {
+    workspace::config_dir().join("app_limits.json")
+}
+
+fn load_file() -> Result<LimitsFile, String> {
+    let path = get_limits_path();
+    if !path.exists() {
+        return Ok(LimitsFile::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read app limits: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse app limits: {}", e))
+}
+
+fn save_file(file: &LimitsFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize app limits: {}", e))?;
+    fs::write(get_limits_path(), content).map_err(|e| format!("Failed to write app limits: {}", e))
+}
+
+/// Check that a target names a known app or category
+fn is_valid_target(target: &str) -> bool {
+    APPS.iter().any(|a| a.id == target || a.category == target)
+}
+
+fn target_name(target: &str) -> String {
+    apps::find_app(target)
+        .map(|a| a.name.to_string())
+        .unwrap_or_else(|| target.replace('_', " "))
+}
+
+fn target_domains(target: &str) -> Vec<String> {
+    APPS.iter()
+        .filter(|a| a.id == target || a.category == target)
+        .flat_map(|a| a.domains.iter().map(|d| d.to_string()))
+        .collect()
+}
+
+/// Minutes used today for a target (category minutes are summed per app)
+fn used_minutes(usage: &[AppUsage], target: &str) -> u32 {
+    usage
+        .iter()
+        .filter(|u| u.app_id == target || u.category == target)
+        .map(|u| u.active_minutes)
+        .sum()
+}
+
+/// Add, update or (with `daily_minutes` = None) remove a limit
+pub fn set_limit(device_id: &str, target: &str, daily_minutes: Option<u32>) -> Result<(), String> {
+    if !is_valid_target(target) {
+        return Err(format!("Unknown app or category: {}", target));
+    }
+
+    let _guard = LIMITS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    file.limits.retain(|l| !(l.device_id == device_id && l.target == target));
+
+    if let Some(minutes) = daily_minutes {
+        file.limits.push(AppLimit {
+            device_id: device_id.to_string(),
+            target: target.to_string(),
+            daily_minutes: minutes,
+        });
+    }
+
+    save_file(&file)
+}
+
+fn status_for(limit: &AppLimit, enforced: &[EnforcedLimit]) -> Result<AppLimitStatus, String> {
+    let usage = apps::app_usage(Some(&limit.device_id), UsageRange::Today)?;
+    let used = used_minutes(&usage, &limit.target);
+
+    Ok(AppLimitStatus {
+        device_id: limit.device_id.clone(),
+        target: limit.target.clone(),
+        target_name: target_name(&limit.target),
+        daily_minutes: limit.daily_minutes,
+        used_minutes: used,
+        remaining_minutes: limit.daily_minutes.saturating_sub(used),
+        exceeded: used >= limit.daily_minutes,
+        enforced: enforced
+            .iter()
+            .any(|e| e.device_id == limit.device_id && e.target == limit.target),
+    })
+}
+
+/// Get today's usage against every configured limit
+pub fn limits_status(device_id: Option<&str>) -> Result<Vec<AppLimitStatus>, String> {
+    let file = load_file()?;
+    file.limits
+        .iter()
+        .filter(|l| device_id.is_none_or(|d| l.device_id == d))
+        .map(|l| status_for(l, &file.enforced))
+        .collect()
+}
+
+fn block_command(action: &str, device_ip: &str, domains: &[String]) -> Result<(), String> {
+    let joined = domains.join(",");
+    let result = run_blocking_command(action, &[("--device", device_ip), ("--domains", &joined)])?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(())
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Block a limit's target if it was just exceeded; the block it is under afterwards, if any
+fn enforce_limit(
+    conn: &Connection,
+    limit: &AppLimit,
+    enforced: &[EnforcedLimit],
+) -> Result<Option<EnforcedLimit>, String> {
+    let status = status_for(limit, enforced)?;
+    let existing = enforced
+        .iter()
+        .find(|e| e.device_id == limit.device_id && e.target == limit.target);
+
+    match (status.exceeded, existing) {
+        (true, Some(existing)) => Ok(Some(existing.clone())),
+        (true, None) => {
+            let Some(device_ip) = database::device_ip(conn, &limit.device_id)? else {
+                return Ok(None);
+            };
+            let domains = target_domains(&limit.target);
+            block_command("block-device", &device_ip, &domains)?;
+
+            log::info!("App limit reached: {} on {}", status.target_name, limit.device_id);
+            let description = t(
+                "alert.app_limit.description",
+                &[
+                    ("target", &status.target_name),
+                    ("used", &status.used_minutes),
+                    ("limit", &limit.daily_minutes),
+                ],
+            );
+            event_bus::raise_alert(
+                "low",
+                "custom",
+                &t("alert.app_limit.title", &[("target", &status.target_name)]),
+                &description,
+                Some(&limit.device_id),
+                Some(&json!({ "limit": limit, "used_minutes": status.used_minutes })),
+            );
+
+            Ok(Some(EnforcedLimit {
+                device_id: limit.device_id.clone(),
+                target: limit.target.clone(),
+                device_ip,
+                domains,
+            }))
+        }
+        (false, _) => Ok(None),
+    }
+}
+
+/// Block newly exceeded limits and lift blocks that no longer apply
+///
+/// A limit that fails is logged and skipped, keeping whatever block it had, so
+/// one bad device doesn't stop the others or lose track of blocks in place.
+fn enforce(state: &AppState) -> Result<(), String> {
+    let _guard = LIMITS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    let conn = database::open_readonly()?;
+
+    let mut still_enforced = Vec::new();
+
+    for limit in &file.limits {
+        match enforce_limit(&conn, limit, &file.enforced) {
+            Ok(enforced) => still_enforced.extend(enforced),
+            Err(e) => {
+                log::warn!("Failed to enforce app limit on {}: {}", limit.device_id, e);
+                still_enforced.extend(
+                    file.enforced
+                        .iter()
+                        .find(|e| e.device_id == limit.device_id && e.target == limit.target)
+                        .cloned(),
+                );
+            }
+        }
+    }
+
+    // Lift blocks for limits that are back under (new day) or were removed/raised
+    for enforced in &file.enforced {
+        let kept = still_enforced
+            .iter()
+            .any(|e| e.device_id == enforced.device_id && e.target == enforced.target);
+        if kept {
+            continue;
+        }
+        if let Err(e) = block_command("unblock-device", &enforced.device_ip, &enforced.domains) {
+            // Still blocked, so try again next pass
+            log::warn!("Failed to lift app limit on {}: {}", enforced.device_id, e);
+            still_enforced.push(enforced.clone());
+            continue;
+        }
+        broadcast_to_processes(state, &json!({
+            "action": "unblock_device_domains",
+            "device_ip": enforced.device_ip,
+            "domains": enforced.domains,
+        }));
+    }
+
+    // Re-push active blocks every pass so restarted capture processes pick them up
+    for enforced in &still_enforced {
+        broadcast_to_processes(state, &json!({
+            "action": "block_device_domains",
+            "device_ip": enforced.device_ip,
+            "domains": enforced.domains,
+        }));
+    }
+
+    file.enforced = still_enforced;
+    save_file(&file)
+}
+
+/// Spawn the background task that enforces app limits
+pub fn spawn_limit_enforcer(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = enforce(&handle.state::<AppState>()) {
+                    log::debug!("App limit check failed: {}", e);
+                }
+            })
+            .await;
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,306 @@
+// Application recognition and per-app usage from captured traffic
+//
+// Hosts (HTTP Host / TLS SNI from the proxy, names from DNS capture) and raw
+// IP destinations are mapped to known applications. Active time is counted in
+// one-minute buckets: a minute with any traffic to an app is a minute of use.
+// Host matches go through the enrichment cache as category lookups.
+
+use crate::clock::Zone;
+use crate::database::open_readonly;
+use crate::enrichment::{self, Kind};
+use chrono::{Duration, NaiveTime};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+
+pub struct App {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub category: &'static str,
+    pub domains: &'static [&'static str],
+    /// IPv4 ranges for clients that connect by address (CIDR notation)
+    pub cidrs: &'static [&'static str],
+}
+
+pub const APPS: &[App] = &[
+    App {
+        id: "youtube",
+        name: "YouTube",
+        category: "streaming",
+        domains: &["youtube.com", "googlevideo.com", "ytimg.com", "youtu.be", "youtubei.googleapis.com"],
+        cidrs: &[],
+    },
+    App {
+        id: "netflix",
+        name: "Netflix",
+        category: "streaming",
+        domains: &["netflix.com", "nflxvideo.net", "nflximg.net", "nflxext.com", "nflxso.net"],
+        cidrs: &["45.57.0.0/17", "198.38.96.0/19"],
+    },
+    App {
+        id: "twitch",
+        name: "Twitch",
+        category: "streaming",
+        domains: &["twitch.tv", "ttvnw.net", "jtvnw.net"],
+        cidrs: &[],
+    },
+    App {
+        id: "disney_plus",
+        name: "Disney+",
+        category: "streaming",
+        domains: &["disneyplus.com", "disney-plus.net", "dssott.com", "bamgrid.com"],
+        cidrs: &[],
+    },
+    App {
+        id: "spotify",
+        name: "Spotify",
+        category: "music",
+        domains: &["spotify.com", "scdn.co", "spotifycdn.com"],
+        cidrs: &[],
+    },
+    App {
+        id: "tiktok",
+        name: "TikTok",
+        category: "social_media",
+        domains: &["tiktok.com", "tiktokv.com", "tiktokcdn.com", "byteoversea.com", "ibytedtos.com", "musical.ly"],
+        cidrs: &[],
+    },
+    App {
+        id: "instagram",
+        name: "Instagram",
+        category: "social_media",
+        domains: &["instagram.com", "cdninstagram.com"],
+        cidrs: &[],
+    },
+    App {
+        id: "snapchat",
+        name: "Snapchat",
+        category: "social_media",
+        domains: &["snapchat.com", "sc-cdn.net", "snapkit.com", "sc-static.net"],
+        cidrs: &[],
+    },
+    App {
+        id: "facebook",
+        name: "Facebook",
+        category: "social_media",
+        domains: &["facebook.com", "fbcdn.net", "fb.com", "facebook.net"],
+        cidrs: &[],
+    },
+    App {
+        id: "reddit",
+        name: "Reddit",
+        category: "social_media",
+        domains: &["reddit.com", "redd.it", "redditmedia.com", "redditstatic.com"],
+        cidrs: &[],
+    },
+    App {
+        id: "whatsapp",
+        name: "WhatsApp",
+        category: "messaging",
+        domains: &["whatsapp.com", "whatsapp.net", "wa.me"],
+        cidrs: &[],
+    },
+    App {
+        id: "discord",
+        name: "Discord",
+        category: "messaging",
+        domains: &["discord.com", "discord.gg", "discordapp.com", "discordapp.net", "discord.media"],
+        cidrs: &[],
+    },
+    App {
+        id: "fortnite",
+        name: "Fortnite",
+        category: "gaming",
+        domains: &["fortnite.com", "epicgames.com", "epicgames.dev", "unrealengine.com", "ol.epicgames.com"],
+        cidrs: &[],
+    },
+    App {
+        id: "roblox",
+        name: "Roblox",
+        category: "gaming",
+        domains: &["roblox.com", "rbxcdn.com", "rbx.com", "robloxlabs.com"],
+        cidrs: &["128.116.0.0/17"],
+    },
+    App {
+        id: "minecraft",
+        name: "Minecraft",
+        category: "gaming",
+        domains: &["minecraft.net", "mojang.com", "minecraftservices.com"],
+        cidrs: &[],
+    },
+    App {
+        id: "steam",
+        name: "Steam",
+        category: "gaming",
+        domains: &["steampowered.com", "steamcommunity.com", "steamstatic.com", "steamcontent.com"],
+        cidrs: &[],
+    },
+];
+
+/// Time window for usage queries
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub enum UsageRange {
+    #[default]
+    #[serde(rename = "today")]
+    Today,
+    #[serde(rename = "yesterday")]
+    Yesterday,
+    #[serde(rename = "24h")]
+    Last24Hours,
+    #[serde(rename = "7d")]
+    Last7Days,
+    #[serde(rename = "30d")]
+    Last30Days,
+}
+
+impl UsageRange {
+    /// Start and end as UTC query bounds; days start at midnight in the configured zone
+    pub fn bounds(&self) -> (String, String) {
+        let zone = Zone::configured();
+        let now = zone.now();
+        let midnight = now.date().and_time(NaiveTime::MIN);
+        let (start, end) = match self {
+            UsageRange::Today => (midnight, now),
+            UsageRange::Yesterday => (midnight - Duration::days(1), midnight),
+            UsageRange::Last24Hours => (now - Duration::hours(24), now),
+            UsageRange::Last7Days => (now - Duration::days(7), now),
+            UsageRange::Last30Days => (now - Duration::days(30), now),
+        };
+        zone.utc_bounds(start, end)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AppUsage {
+    pub app_id: String,
+    pub name: String,
+    pub category: String,
+    pub active_minutes: u32,
+    pub bytes: u64,
+    pub requests: u64,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+}
+
+fn parse_cidr(cidr: &str) -> Option<(u32, u32)> {
+    let (addr, bits) = cidr.split_once('/')?;
+    let addr: Ipv4Addr = addr.parse().ok()?;
+    let bits: u32 = bits.parse().ok()?;
+    let mask = if bits == 0 { 0 } else { u32::MAX << (32 - bits) };
+    Some((u32::from(addr) & mask, mask))
+}
+
+/// Map a host name or IP address to a known application
+pub fn recognize(host: &str) -> Option<&'static App> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    enrichment::lookup(Kind::Category, &host, |host| match_app(host).map(|app| app.id.to_string()))
+        .and_then(|id| find_app(&id))
+}
+
+fn match_app(host: &str) -> Option<&'static App> {
+    if let Ok(addr) = host.parse::<Ipv4Addr>() {
+        let addr = u32::from(addr);
+        return APPS.iter().find(|app| {
+            app.cidrs
+                .iter()
+                .filter_map(|c| parse_cidr(c))
+                .any(|(net, mask)| addr & mask == net)
+        });
+    }
+
+    APPS.iter().find(|app| {
+        app.domains
+            .iter()
+            .any(|d| host == *d || host.ends_with(&format!(".{}", d)))
+    })
+}
+
+/// Get an application by its ID
+pub fn find_app(app_id: &str) -> Option<&'static App> {
+    APPS.iter().find(|app| app.id == app_id)
+}
+
+#[derive(Default)]
+struct Accumulator {
+    minutes: HashSet<String>,
+    bytes: u64,
+    requests: u64,
+}
+
+/// Compute per-app active time and bandwidth for a device (or all devices)
+pub fn app_usage(device_id: Option<&str>, range: UsageRange) -> Result<Vec<AppUsage>, String> {
+    let (start, end) = range.bounds();
+    app_usage_between(device_id, &start, &end)
+}
+
+/// Per-app usage between two naive local timestamps
+pub fn app_usage_between(device_id: Option<&str>, start: &str, end: &str) -> Result<Vec<AppUsage>, String> {
+    let conn = open_readonly()?;
+    let mut usage: HashMap<&'static str, Accumulator> = HashMap::new();
+
+    // Proxy traffic (raw and rolled up) carries sizes; DNS lookups fill in apps the proxy can't see (pinned TLS, UDP)
+    let queries = [
+        "SELECT host, substr(timestamp, 1, 16), COUNT(*),
+                SUM(COALESCE(request_size, 0) + COALESCE(response_size, 0))
+         FROM traffic
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_id = ?3)
+         GROUP BY host, substr(timestamp, 1, 16)",
+        "SELECT host, minute, SUM(requests), SUM(bytes_in + bytes_out)
+         FROM traffic_aggregates
+         WHERE minute >= substr(?1, 1, 16) AND minute < ?2 AND (?3 IS NULL OR device_id = ?3)
+         GROUP BY host, minute",
+        "SELECT query_name, substr(timestamp, 1, 16), 0, 0
+         FROM dns_queries
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_id = ?3)
+         GROUP BY query_name, substr(timestamp, 1, 16)",
+    ];
+
+    for sql in queries {
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map(params![start, end, device_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                ))
+            })
+            .map_err(|e| format!("Failed to query usage: {}", e))?;
+
+        for row in rows {
+            let (host, minute, requests, bytes) =
+                row.map_err(|e| format!("Failed to read usage: {}", e))?;
+            let Some(app) = recognize(&host) else {
+                continue;
+            };
+            let acc = usage.entry(app.id).or_default();
+            acc.minutes.insert(minute);
+            acc.requests += requests as u64;
+            acc.bytes += bytes as u64;
+        }
+    }
+
+    let mut result: Vec<AppUsage> = usage
+        .into_iter()
+        .filter_map(|(id, acc)| {
+            let app = find_app(id)?;
+            Some(AppUsage {
+                app_id: app.id.to_string(),
+                name: app.name.to_string(),
+                category: app.category.to_string(),
+                active_minutes: acc.minutes.len() as u32,
+                bytes: acc.bytes,
+                requests: acc.requests,
+                first_seen: acc.minutes.iter().min().cloned(),
+                last_seen: acc.minutes.iter().max().cloned(),
+            })
+        })
+        .collect();
+
+    result.sort_by(|a, b| b.active_minutes.cmp(&a.active_minutes).then(b.bytes.cmp(&a.bytes)));
+    Ok(result)
+}
@@ -0,0 +1,89 @@
+// Append-only audit log of actions taken on the user's behalf
+//
+// Entries are JSON lines in `audit.log` in the workspace data directory. The file is trimmed to the
+// most recent entries once it grows past a size limit.
+
+use crate::clock;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Trim the log once it grows past this size
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Entries kept when trimming
+const KEEP_ENTRIES: usize = 5000;
+
+static AUDIT_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    /// What kind of action this was, e.g. "hook"
+    pub kind: String,
+    pub summary: String,
+    #[serde(default)]
+    pub detail: Value,
+}
+
+fn get_audit_path() -> PathBuf {
+    workspace::data_dir().join("audit.log")
+}
+
+/// Append an entry to the audit log
+pub fn record(kind: &str, summary: &str, detail: Value) -> Result<(), String> {
+    let entry = AuditEntry {
+        timestamp: clock::now_utc(),
+        kind: kind.to_string(),
+        summary: summary.to_string(),
+        detail,
+    };
+    let line = serde_json::to_string(&entry)
+        .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+
+    let _guard = AUDIT_LOCK.lock().unwrap();
+    let path = get_audit_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create audit dir: {}", e))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open audit log: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))?;
+
+    if file.metadata().map(|m| m.len()).unwrap_or(0) > MAX_LOG_BYTES {
+        trim(&path)?;
+    }
+    Ok(())
+}
+
+fn trim(path: &PathBuf) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read audit log: {}", e))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let kept = &lines[lines.len().saturating_sub(KEEP_ENTRIES)..];
+    fs::write(path, kept.join("\n") + "\n").map_err(|e| format!("Failed to trim audit log: {}", e))
+}
+
+/// Get the most recent entries, newest first, optionally of one kind
+pub fn entries(kind: Option<&str>, limit: usize) -> Result<Vec<AuditEntry>, String> {
+    let path = get_audit_path();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read audit log: {}", e))?;
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .filter(|e| kind.is_none_or(|k| e.kind == k))
+        .take(limit)
+        .collect())
+}
@@ -0,0 +1,178 @@
+// Launch-at-login registration
+//
+// A launch at login resumes monitoring only if it was running when the last
+// session ended. Whether it is running is recorded on every start and stop,
+// so a session cut short by a shutdown or logout still counts as running.
+
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+#[cfg(any(windows, target_os = "macos"))]
+use std::process::Command;
+
+/// Argument passed when launched by the OS at login
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+/// Argument requesting the window start hidden in the tray
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+const APP_NAME: &str = "NetworkMonitor";
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct ResumeState {
+    was_monitoring: bool,
+}
+
+fn resume_state_path() -> PathBuf {
+    workspace::config_dir().join("resume_state.json")
+}
+
+fn launch_args(minimized: bool) -> Vec<&'static str> {
+    let mut args = vec![AUTOSTART_ARG];
+    if minimized {
+        args.push(MINIMIZED_ARG);
+    }
+    args
+}
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))
+}
+
+/// Check whether the process was launched by the OS login hook
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+/// Check whether the window should start hidden
+pub fn launched_minimized() -> bool {
+    std::env::args().any(|arg| arg == MINIMIZED_ARG)
+}
+
+/// Record whether monitoring is running, for the next launch at login
+pub fn remember_monitoring(running: bool) {
+    let content = serde_json::to_string_pretty(&ResumeState { was_monitoring: running })
+        .map_err(|e| format!("Failed to serialize resume state: {}", e))
+        .and_then(|content| {
+            std::fs::write(resume_state_path(), content).map_err(|e| format!("Failed to write resume state: {}", e))
+        });
+    if let Err(e) = content {
+        log::warn!("{}", e);
+    }
+}
+
+/// Whether monitoring was running when the last session ended
+pub fn was_monitoring() -> bool {
+    std::fs::read_to_string(resume_state_path())
+        .ok()
+        .and_then(|content| serde_json::from_str::<ResumeState>(&content).ok())
+        .unwrap_or_default()
+        .was_monitoring
+}
+
+/// Register or unregister the app with the OS login items
+pub fn set_login_item(enabled: bool, minimized: bool) -> Result<(), String> {
+    let exe = current_exe()?;
+    let command_line = format!("\"{}\" {}", exe.display(), launch_args(minimized).join(" "));
+
+    #[cfg(windows)]
+    {
+        let key = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+        let output = if enabled {
+            Command::new("reg")
+                .args(["add", key, "/v", APP_NAME, "/t", "REG_SZ", "/d", &command_line, "/f"])
+                .output()
+        } else {
+            Command::new("reg")
+                .args(["delete", key, "/v", APP_NAME, "/f"])
+                .output()
+        }
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+
+        // Deleting a value that isn't there is not an error for our purposes
+        if !output.status.success() && enabled {
+            return Err(format!(
+                "Failed to update Run key: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let _ = command_line;
+        let home = std::env::var("HOME").map_err(|_| "HOME is not set".to_string())?;
+        let plist_path = PathBuf::from(home)
+            .join("Library/LaunchAgents")
+            .join(format!("com.networkmonitor.{}.plist", APP_NAME));
+
+        if !enabled {
+            let _ = Command::new("launchctl").arg("unload").arg(&plist_path).output();
+            if plist_path.exists() {
+                std::fs::remove_file(&plist_path)
+                    .map_err(|e| format!("Failed to remove launch agent: {}", e))?;
+            }
+            return Ok(());
+        }
+
+        let args: String = launch_args(minimized)
+            .iter()
+            .map(|a| format!("<string>{}</string>", a))
+            .collect();
+        let plist = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key><string>com.networkmonitor.{}</string>
+    <key>ProgramArguments</key><array><string>{}</string>{}</array>
+    <key>RunAtLoad</key><true/>
+</dict>
+</plist>
+"#,
+            APP_NAME,
+            exe.display(),
+            args
+        );
+
+        if let Some(parent) = plist_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create LaunchAgents dir: {}", e))?;
+        }
+        std::fs::write(&plist_path, plist)
+            .map_err(|e| format!("Failed to write launch agent: {}", e))
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let config_home = std::env::var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".config")))
+            .map_err(|_| "Neither XDG_CONFIG_HOME nor HOME is set".to_string())?;
+        let desktop_path = config_home
+            .join("autostart")
+            .join(format!("{}.desktop", APP_NAME.to_lowercase()));
+
+        if !enabled {
+            if desktop_path.exists() {
+                std::fs::remove_file(&desktop_path)
+                    .map_err(|e| format!("Failed to remove autostart entry: {}", e))?;
+            }
+            return Ok(());
+        }
+
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName=Network Monitor\nExec={}\n\
+             X-GNOME-Autostart-enabled=true\nHidden=false\n",
+            command_line
+        );
+
+        if let Some(parent) = desktop_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create autostart dir: {}", e))?;
+        }
+        std::fs::write(&desktop_path, entry)
+            .map_err(|e| format!("Failed to write autostart entry: {}", e))
+    }
+}
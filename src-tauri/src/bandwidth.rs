@@ -0,0 +1,230 @@
+// Device bandwidth shaping
+//
+// Beyond pausing or blocking a device, its throughput can be capped ("the
+// smart TV gets 5 Mbps"). Limits are stored per device ID and handed to the
+// proxy by IP, at start and whenever a limit or a shaped device's IP changes;
+// the proxy holds back flows from devices over their limit with a token bucket
+// per device and writes their live usage to a file, read back here.
+
+use crate::clock;
+use crate::commands::{broadcast_to_processes, Device};
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the limits pushed to the proxy are re-evaluated
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Usage older than this means the proxy saw no shaped traffic since
+const USAGE_STALE: Duration = Duration::from_secs(15);
+
+/// Bounds of a device limit, in kbps
+const MIN_KBPS: u32 = 64;
+const MAX_KBPS: u32 = 10_000_000;
+
+/// Serializes read-modify-write of the bandwidth config
+static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+/// Limits last pushed to the proxy, as (IP, kbps)
+static PUSHED: Mutex<Option<Vec<(String, u32)>>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct BandwidthConfig {
+    /// Device ID -> limit in kbps
+    pub limits: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct DeviceBandwidth {
+    pub device_id: String,
+    pub device_ip: String,
+    pub limit_kbps: u32,
+    pub upload_kbps: f64,
+    pub download_kbps: f64,
+    /// Share of the limit in use, from 0 to 1
+    pub utilization: f64,
+    /// Time flows were held back over the last few seconds
+    pub throttled_ms: u64,
+}
+
+fn get_config_path() -> PathBuf {
+    workspace::config_dir().join("bandwidth.json")
+}
+
+fn get_usage_path() -> PathBuf {
+    workspace::data_dir().join("bandwidth_usage.json")
+}
+
+pub fn load_config() -> Result<BandwidthConfig, String> {
+    let path = get_config_path();
+    if !path.exists() {
+        return Ok(BandwidthConfig::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read bandwidth config: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse bandwidth config: {}", e))
+}
+
+fn save_config(config: &BandwidthConfig) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize bandwidth config: {}", e))?;
+    fs::write(get_config_path(), content).map_err(|e| format!("Failed to write bandwidth config: {}", e))
+}
+
+/// Limits of the shaped devices that have an IP, as (IP, kbps)
+fn shaped(config: &BandwidthConfig, devices: &[Device]) -> Vec<(String, u32)> {
+    let mut shaped: Vec<(String, u32)> = devices
+        .iter()
+        .filter(|d| !d.ip.is_empty())
+        .filter_map(|d| config.limits.get(&d.id).map(|kbps| (d.ip.clone(), *kbps)))
+        .collect();
+    shaped.sort();
+    shaped
+}
+
+/// Extra proxy arguments that apply the limits at startup
+pub fn proxy_args(devices: &[Device]) -> Vec<String> {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            log::warn!("Failed to load bandwidth config, starting without it: {}", e);
+            return vec![];
+        }
+    };
+
+    let shaped = shaped(&config, devices);
+    let args = shaped
+        .iter()
+        .flat_map(|(ip, kbps)| ["--rate-limit".to_string(), format!("{}={}", ip, kbps)])
+        .collect();
+    *PUSHED.lock().unwrap() = Some(shaped);
+    args
+}
+
+/// Push the current limits to the proxy if they changed
+fn push_limits(state: &AppState, force: bool) -> Result<(), String> {
+    let config = load_config()?;
+    let devices = state.snapshot.devices(false)?.data;
+    let shaped = shaped(&config, &devices);
+
+    let mut pushed = PUSHED.lock().unwrap();
+    if !force && pushed.as_ref() == Some(&shaped) {
+        return Ok(());
+    }
+
+    broadcast_to_processes(state, &json!({
+        "action": "set_rate_limits",
+        "devices": shaped
+            .iter()
+            .map(|(ip, kbps)| json!({ "ip": ip, "kbps": kbps }))
+            .collect::<Vec<_>>(),
+    }));
+    *pushed = Some(shaped);
+    Ok(())
+}
+
+pub fn validate_limit(kbps: u32) -> Result<(), String> {
+    if !(MIN_KBPS..=MAX_KBPS).contains(&kbps) {
+        return Err(format!("Bandwidth limit must be between {} and {} kbps", MIN_KBPS, MAX_KBPS));
+    }
+    Ok(())
+}
+
+/// Set or clear (`None`) a device's limit and apply it to the running proxy
+pub fn set_limit(state: &AppState, device_id: &str, kbps: Option<u32>) -> Result<(), String> {
+    if let Some(kbps) = kbps {
+        validate_limit(kbps)?;
+    }
+
+    {
+        let _guard = CONFIG_LOCK.lock().unwrap();
+        let mut config = load_config()?;
+        match kbps {
+            Some(kbps) => config.limits.insert(device_id.to_string(), kbps),
+            None => config.limits.remove(device_id),
+        };
+        save_config(&config)?;
+    }
+
+    match kbps {
+        Some(kbps) => log::info!("Bandwidth limit for {} set to {} kbps", device_id, kbps),
+        None => log::info!("Bandwidth limit for {} removed", device_id),
+    }
+    push_limits(state, true)
+}
+
+/// Limits and live usage of every shaped device
+pub fn utilization(state: &AppState) -> Result<Vec<DeviceBandwidth>, String> {
+    let config = load_config()?;
+    let devices = state.snapshot.devices(false)?.data;
+
+    let usage: Value = fs::read_to_string(get_usage_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let fresh = usage
+        .get("updated_at")
+        .and_then(|t| t.as_str())
+        .and_then(clock::parse_stored)
+        .is_some_and(|at| {
+            chrono::Utc::now() - at < chrono::Duration::from_std(USAGE_STALE).unwrap_or_default()
+        });
+
+    let mut report: Vec<DeviceBandwidth> = config
+        .limits
+        .iter()
+        .map(|(device_id, &limit_kbps)| {
+            let device_ip = devices
+                .iter()
+                .find(|d| &d.id == device_id)
+                .map(|d| d.ip.clone())
+                .unwrap_or_default();
+            let mut entry = DeviceBandwidth {
+                device_id: device_id.clone(),
+                device_ip,
+                limit_kbps,
+                ..Default::default()
+            };
+
+            let current = usage.get("devices").and_then(|d| d.get(&entry.device_ip));
+            if let Some(current) = current.filter(|_| fresh) {
+                let kbps = |key: &str| current.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0) * 8.0 / 1000.0;
+                entry.upload_kbps = kbps("upload_bytes_per_sec");
+                entry.download_kbps = kbps("download_bytes_per_sec");
+                entry.utilization = ((entry.upload_kbps + entry.download_kbps) / limit_kbps as f64).min(1.0);
+                entry.throttled_ms = current.get("throttled_ms").and_then(|v| v.as_u64()).unwrap_or(0);
+            }
+            entry
+        })
+        .collect();
+    report.sort_by(|a, b| b.utilization.total_cmp(&a.utilization));
+    Ok(report)
+}
+
+/// Spawn the background task that keeps the proxy's limits on the devices' current IPs
+pub fn spawn_bandwidth_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                let state = handle.state::<AppState>();
+                if state.lifecycle.current().is_running() {
+                    if let Err(e) = push_limits(&state, false) {
+                        log::debug!("Bandwidth limit update failed: {}", e);
+                    }
+                }
+            })
+            .await;
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
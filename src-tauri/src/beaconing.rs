@@ -0,0 +1,329 @@
+// Malware beaconing detection
+//
+// Command-and-control implants tend to check in with their server on a fixed
+// timer with small, near-identical requests. For each device/host pair in the
+// stored traffic, requests are grouped into bursts (one check-in may be
+// several requests) and the gaps between bursts are tested for a regular
+// period. Regularity, payload constancy and the number of check-ins make up
+// the confidence; confident candidates are raised as "possible C2 beaconing"
+// alerts, at most once a day per device and host.
+//
+// DNS lookups aren't used: resolver caching makes them periodic (by TTL) for
+// perfectly ordinary hosts.
+
+use crate::clock;
+use crate::database::{self, open_readonly};
+use crate::event_bus;
+use crate::i18n::t;
+use crate::state::AppState;
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the background analysis runs
+const ANALYSIS_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// Traffic window the background analysis looks at
+const ANALYSIS_HOURS: u32 = 24;
+
+/// Requests closer together than this belong to the same check-in
+const BURST_GAP_SECS: f64 = 5.0;
+
+/// Fewest check-ins a pattern needs before it is considered
+const MIN_BEACONS: usize = 6;
+
+/// Shortest and longest period considered (faster is polling/streaming)
+const MIN_PERIOD_SECS: f64 = 10.0;
+const MAX_PERIOD_SECS: f64 = 6.0 * 3600.0;
+
+/// Candidates below this confidence aren't reported
+const MIN_REPORT_CONFIDENCE: f64 = 0.5;
+
+/// Candidates at or above this confidence raise an alert
+const ALERT_CONFIDENCE: f64 = 0.8;
+
+/// Services that check in on a timer by design
+const IGNORED_SUFFIXES: &[&str] = &[
+    "push.apple.com",
+    "icloud.com",
+    "time.apple.com",
+    "mtalk.google.com",
+    "clients4.google.com",
+    "connectivitycheck.gstatic.com",
+    "msftconnecttest.com",
+    "windowsupdate.com",
+    "events.data.microsoft.com",
+    "ntp.org",
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BeaconCandidate {
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub host: String,
+    /// Detected check-in period in seconds
+    pub period_seconds: f64,
+    /// Median deviation from the period, as a percentage of it
+    pub jitter_percent: f64,
+    pub beacons: usize,
+    pub avg_payload_bytes: u64,
+    /// Coefficient of variation of per-check-in payload size (None without size data)
+    pub payload_variation: Option<f64>,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// 0.0–1.0
+    pub confidence: f64,
+}
+
+struct Burst {
+    start: f64,
+    bytes: u64,
+}
+
+/// Requests from one device to one host
+struct Series {
+    device_id: Option<String>,
+    /// (seconds, bytes) in time order
+    events: Vec<(f64, u64)>,
+    first_seen: String,
+    last_seen: String,
+}
+
+/// A regular pattern found in a series
+struct Pattern {
+    period: f64,
+    jitter: f64,
+    beacons: usize,
+    avg_payload: u64,
+    variation: Option<f64>,
+    confidence: f64,
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+fn is_ignored(host: &str) -> bool {
+    IGNORED_SUFFIXES
+        .iter()
+        .any(|s| host == *s || host.ends_with(&format!(".{}", s)))
+}
+
+/// Score one device/host pair's request times (seconds) and sizes
+fn analyze(events: &[(f64, u64)]) -> Option<Pattern> {
+    let mut bursts: Vec<Burst> = Vec::new();
+    let mut last = f64::NEG_INFINITY;
+    for &(at, bytes) in events {
+        match bursts.last_mut() {
+            Some(burst) if at - last <= BURST_GAP_SECS => burst.bytes += bytes,
+            _ => bursts.push(Burst { start: at, bytes }),
+        }
+        last = at;
+    }
+    if bursts.len() < MIN_BEACONS {
+        return None;
+    }
+
+    let intervals: Vec<f64> = bursts.windows(2).map(|w| w[1].start - w[0].start).collect();
+    let period = median(&mut intervals.clone());
+    if !(MIN_PERIOD_SECS..=MAX_PERIOD_SECS).contains(&period) {
+        return None;
+    }
+
+    // A missed check-in shows up as a gap of two or three periods, which still fits
+    let tolerance = (period * 0.1).max(2.0);
+    let multiple = |gap: f64| {
+        let k = (gap / period).round();
+        (1.0..=3.0).contains(&k) && (gap - k * period).abs() <= tolerance * k
+    };
+    let consistency = intervals.iter().filter(|&&gap| multiple(gap)).count() as f64 / intervals.len() as f64;
+
+    let mut deviations: Vec<f64> = intervals
+        .iter()
+        .filter(|&&gap| (gap - period).abs() <= tolerance)
+        .map(|gap| (gap - period).abs())
+        .collect();
+    if deviations.len() * 2 < intervals.len() {
+        return None;
+    }
+    let jitter = median(&mut deviations) / period;
+
+    let sizes: Vec<f64> = bursts.iter().map(|b| b.bytes as f64).collect();
+    let mean = sizes.iter().sum::<f64>() / sizes.len() as f64;
+    let variation = (mean > 0.0).then(|| {
+        let variance = sizes.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / sizes.len() as f64;
+        variance.sqrt() / mean
+    });
+
+    let regularity = consistency * (1.0 - (jitter / 0.1).min(1.0) * 0.5);
+    let payload_score = variation.map_or(0.5, |v| 1.0 - v.min(1.0));
+    let volume = (bursts.len() as f64 / 24.0).min(1.0);
+    let confidence = 0.6 * regularity + 0.25 * payload_score + 0.15 * volume;
+
+    Some(Pattern {
+        period,
+        jitter,
+        beacons: bursts.len(),
+        avg_payload: mean as u64,
+        variation,
+        confidence,
+    })
+}
+
+/// Find beacon-like traffic in the last `hours`, most confident first
+pub fn detect(device_id: Option<&str>, hours: u32) -> Result<Vec<BeaconCandidate>, String> {
+    let conn = open_readonly()?;
+    let since = clock::utc_cutoff(chrono::Duration::hours(hours as i64));
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, device_ip, host, timestamp,
+                    COALESCE(request_size, 0) + COALESCE(response_size, 0)
+             FROM traffic
+             WHERE timestamp >= ?1 AND (?2 IS NULL OR device_id = ?2)
+             ORDER BY timestamp",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![since, device_id], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query traffic: {}", e))?;
+
+    let mut pairs: HashMap<(String, String), Series> = HashMap::new();
+    for row in rows {
+        let (device_id, device_ip, host, timestamp, bytes) =
+            row.map_err(|e| format!("Failed to read traffic: {}", e))?;
+        let host = host.trim_end_matches('.').to_lowercase();
+        if host.is_empty() || is_ignored(&host) {
+            continue;
+        }
+        let Some(at) = clock::parse_stored(&timestamp) else {
+            continue;
+        };
+
+        let series = pairs.entry((device_ip, host)).or_insert_with(|| Series {
+            device_id,
+            events: Vec::new(),
+            first_seen: timestamp.clone(),
+            last_seen: timestamp.clone(),
+        });
+        series.events.push((at.timestamp_millis() as f64 / 1000.0, bytes.max(0) as u64));
+        series.last_seen = timestamp;
+    }
+
+    let mut candidates: Vec<BeaconCandidate> = pairs
+        .into_iter()
+        .filter_map(|((device_ip, host), series)| {
+            let pattern = analyze(&series.events)?;
+            (pattern.confidence >= MIN_REPORT_CONFIDENCE).then(|| BeaconCandidate {
+                device_id: series.device_id,
+                device_ip,
+                host,
+                period_seconds: (pattern.period * 10.0).round() / 10.0,
+                jitter_percent: (pattern.jitter * 1000.0).round() / 10.0,
+                beacons: pattern.beacons,
+                avg_payload_bytes: pattern.avg_payload,
+                payload_variation: pattern.variation.map(|v| (v * 100.0).round() / 100.0),
+                first_seen: series.first_seen,
+                last_seen: series.last_seen,
+                confidence: (pattern.confidence * 100.0).round() / 100.0,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    Ok(candidates)
+}
+
+fn describe_period(seconds: f64) -> String {
+    if seconds >= 3600.0 {
+        t("period.hours", &[("value", &format!("{:.1}", seconds / 3600.0))])
+    } else if seconds >= 120.0 {
+        t("period.minutes", &[("value", &format!("{:.1}", seconds / 60.0))])
+    } else {
+        t("period.seconds", &[("value", &format!("{:.0}", seconds))])
+    }
+}
+
+/// Alert on confident candidates not already alerted in the last day
+fn raise_alerts(candidates: &[BeaconCandidate]) {
+    let since = clock::utc_cutoff(chrono::Duration::hours(24));
+    let recent: Vec<(String, String)> = database::stored_alerts()
+        .iter()
+        .filter(|a| a.pointer("/metadata/beaconing").and_then(|b| b.as_bool()) == Some(true))
+        .filter(|a| a.get("timestamp").and_then(|t| t.as_str()).is_some_and(|t| t >= since.as_str()))
+        .filter_map(|a| {
+            let text = |p: &str| a.pointer(p).and_then(|v| v.as_str()).map(|v| v.to_string());
+            Some((text("/metadata/device_ip")?, text("/metadata/host")?))
+        })
+        .collect();
+
+    for candidate in candidates.iter().filter(|c| c.confidence >= ALERT_CONFIDENCE) {
+        if recent.contains(&(candidate.device_ip.clone(), candidate.host.clone())) {
+            continue;
+        }
+
+        let description = t(
+            "alert.beaconing.description",
+            &[
+                ("device", &candidate.device_id.as_deref().unwrap_or(&candidate.device_ip)),
+                ("host", &candidate.host),
+                ("count", &candidate.beacons),
+                ("period", &describe_period(candidate.period_seconds)),
+                ("jitter", &candidate.jitter_percent),
+                ("bytes", &candidate.avg_payload_bytes),
+                ("confidence", &format!("{:.0}", candidate.confidence * 100.0)),
+            ],
+        );
+        let mut metadata = json!(candidate);
+        metadata["beaconing"] = json!(true);
+
+        log::warn!("Possible beaconing from {} to {}", candidate.device_ip, candidate.host);
+        event_bus::raise_alert(
+            "high",
+            "security",
+            &t("alert.beaconing.title", &[("host", &candidate.host)]),
+            &description,
+            candidate.device_id.as_deref(),
+            Some(&metadata),
+        );
+    }
+}
+
+/// Spawn the background task that looks for beaconing while monitoring runs
+pub fn spawn_beacon_detector(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(ANALYSIS_INTERVAL).await;
+
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if !handle.state::<AppState>().lifecycle.current().is_running() {
+                    return;
+                }
+                match detect(None, ANALYSIS_HOURS) {
+                    Ok(candidates) => raise_alerts(&candidates),
+                    Err(e) => log::debug!("Beaconing analysis failed: {}", e),
+                }
+            })
+            .await;
+        }
+    });
+}
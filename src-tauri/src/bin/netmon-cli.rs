@@ -0,0 +1,6 @@
+// Network Monitor - command-line companion
+// Drives the monitor headlessly over the same code paths as the desktop app
+
+fn main() {
+    std::process::exit(network_monitor_lib::cli::main())
+}
@@ -0,0 +1,172 @@
+// Block page served by the proxy
+//
+// The page is rendered here from a template (the built-in one, or
+// `block_page.html` in the workspace config) with the configured title, message
+// and unblock form filled in. The per-request fields ({{host}}, {{url}},
+// {{reason}}, {{category}}) are left for the proxy to fill in, escaped, for each
+// blocked request.
+//
+// The unblock form posts back through the proxy; see `unblock` for what
+// happens to the request from there.
+
+use crate::commands::broadcast_to_processes;
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+  body { font-family: -apple-system, "Segoe UI", sans-serif; background: #111827; color: #e5e7eb;
+         display: flex; align-items: center; justify-content: center; min-height: 100vh; margin: 0; }
+  main { max-width: 32rem; padding: 2rem; background: #1f2937; border-radius: 0.75rem; }
+  h1 { font-size: 1.4rem; margin-top: 0; }
+  dt { color: #9ca3af; font-size: 0.85rem; }
+  dd { margin: 0 0 0.75rem 0; word-break: break-all; }
+  textarea { width: 100%; box-sizing: border-box; min-height: 4rem; margin: 0.5rem 0; }
+  button { padding: 0.6rem 1.2rem; background: #2563eb; color: white; border: 0; border-radius: 0.5rem; }
+</style>
+</head>
+<body>
+<main>
+  <h1>{{title}}</h1>
+  <p>{{message}}</p>
+  <dl>
+    <dt>Site</dt><dd>{{host}}</dd>
+    <dt>Reason</dt><dd>{{reason}}</dd>
+  </dl>
+  {{unblock_form}}
+</main>
+</body>
+</html>
+"#;
+
+const UNBLOCK_FORM: &str = r#"<form method="post" action="/__network-monitor/unblock-request">
+    <input type="hidden" name="host" value="{{host}}">
+    <input type="hidden" name="url" value="{{url}}">
+    <input type="hidden" name="reason" value="{{reason}}">
+    <input type="hidden" name="category" value="{{category}}">
+    <label for="message">Need this site? Tell us why:</label>
+    <textarea id="message" name="message" maxlength="500"></textarea>
+    <button type="submit">Request unblock</button>
+  </form>"#;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BlockPageConfig {
+    pub enabled: bool,
+    pub title: String,
+    pub message: String,
+    pub allow_unblock_requests: bool,
+}
+
+impl Default for BlockPageConfig {
+    fn default() -> Self {
+        BlockPageConfig {
+            enabled: true,
+            title: "This site is blocked".to_string(),
+            message: "This website is not available on this network.".to_string(),
+            allow_unblock_requests: true,
+        }
+    }
+}
+
+fn get_config_path() -> PathBuf {
+    workspace::config_dir().join("block_page.json")
+}
+
+/// Optional user template overriding the built-in one
+fn get_template_path() -> PathBuf {
+    workspace::config_dir().join("block_page.html")
+}
+
+/// Rendered page handed to the proxy at startup
+fn get_rendered_path() -> PathBuf {
+    workspace::data_dir().join("block_page.html")
+}
+
+pub fn load_config() -> Result<BlockPageConfig, String> {
+    let path = get_config_path();
+    if !path.exists() {
+        return Ok(BlockPageConfig::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read block page config: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse block page config: {}", e))
+}
+
+fn save_config(config: &BlockPageConfig) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize block page config: {}", e))?;
+    fs::write(get_config_path(), content).map_err(|e| format!("Failed to write block page config: {}", e))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
+
+/// Render the page, leaving the per-request placeholders for the proxy
+pub fn render(config: &BlockPageConfig) -> String {
+    let template = fs::read_to_string(get_template_path()).unwrap_or_else(|_| DEFAULT_TEMPLATE.to_string());
+    let form = if config.allow_unblock_requests { UNBLOCK_FORM } else { "" };
+
+    template
+        .replace("{{title}}", &escape_html(&config.title))
+        .replace("{{message}}", &escape_html(&config.message))
+        .replace("{{unblock_form}}", form)
+}
+
+/// Render the page with sample request details, for previewing in the app
+pub fn preview(config: &BlockPageConfig) -> String {
+    render(config)
+        .replace("{{host}}", "example.com")
+        .replace("{{url}}", "https://example.com/")
+        .replace("{{reason}}", "Category blocked: gaming")
+        .replace("{{category}}", "gaming")
+}
+
+/// Extra proxy arguments that apply the block page at startup
+pub fn proxy_args() -> Vec<String> {
+    let mut args = Vec::new();
+
+    match load_config() {
+        Ok(config) if config.enabled => {
+            let path = get_rendered_path();
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, render(&config)));
+            match written {
+                Ok(()) => {
+                    args.push("--block-page".to_string());
+                    args.push(path.to_string_lossy().to_string());
+                }
+                Err(e) => log::warn!("Failed to write block page: {}", e),
+            }
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Failed to load block page config, starting without it: {}", e),
+    }
+
+    args
+}
+
+/// Save the block page settings and push the re-rendered page to the proxy
+pub fn set_config(state: &AppState, config: &BlockPageConfig) -> Result<(), String> {
+    save_config(config)?;
+
+    let page = config.enabled.then(|| render(config));
+    broadcast_to_processes(state, &json!({ "action": "set_block_page", "html": page }));
+    Ok(())
+}
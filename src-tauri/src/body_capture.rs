@@ -0,0 +1,101 @@
+// Request/response body capture
+//
+// Which bodies the proxy keeps with the traffic it records: capture can be
+// turned off altogether, skipped by content type (media by default), skipped
+// above a size, and never done for sensitive hosts such as banking sites. A
+// skipped body is dropped before the flow is recorded; its URL, status and
+// sizes still are, and keyword alerts still see the content in transit. The
+// policy is passed to the proxy at start and pushed again when settings change.
+
+use crate::commands::broadcast_to_processes;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Largest body that can be configured for storage
+const MAX_BODY_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BodyCaptureSettings {
+    /// Store request and response bodies at all
+    pub enabled: bool,
+    /// Bodies larger than this are not stored
+    pub max_bytes: u64,
+    /// MIME types never stored, e.g. "application/pdf" or "image/*"
+    pub skip_content_types: Vec<String>,
+    /// Hosts (and their subdomains) whose bodies are never stored
+    pub excluded_hosts: Vec<String>,
+}
+
+impl Default for BodyCaptureSettings {
+    fn default() -> Self {
+        BodyCaptureSettings {
+            enabled: true,
+            max_bytes: 1024 * 1024,
+            skip_content_types: ["image/*", "video/*", "audio/*", "font/*"]
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            excluded_hosts: vec![],
+        }
+    }
+}
+
+fn valid_content_type(pattern: &str) -> bool {
+    let token = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "!#$&^_.+-".contains(c));
+    match pattern.split_once('/') {
+        Some((kind, "*")) => token(kind),
+        Some((kind, subtype)) => token(kind) && token(subtype),
+        None => false,
+    }
+}
+
+fn valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && host
+            .split('.')
+            .all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+/// Check the settings before they are saved
+pub fn validate(settings: &BodyCaptureSettings) -> Result<(), String> {
+    if settings.max_bytes == 0 || settings.max_bytes > MAX_BODY_BYTES {
+        return Err(format!(
+            "Maximum body size must be between 1 byte and {} MB",
+            MAX_BODY_BYTES / (1024 * 1024)
+        ));
+    }
+    if let Some(pattern) = settings.skip_content_types.iter().find(|t| !valid_content_type(t)) {
+        return Err(format!("Invalid content type: {} (expected e.g. \"image/png\" or \"image/*\")", pattern));
+    }
+    if let Some(host) = settings.excluded_hosts.iter().find(|h| !valid_host(h)) {
+        return Err(format!("Invalid host: {} (expected a domain such as \"bank.example\")", host));
+    }
+    Ok(())
+}
+
+/// Extra proxy arguments that apply the policy at startup
+pub fn proxy_args(settings: &BodyCaptureSettings) -> Vec<String> {
+    let mut args = Vec::new();
+    if !settings.enabled {
+        args.push("--no-bodies".to_string());
+    }
+    args.push("--max-body-size".to_string());
+    args.push(settings.max_bytes.to_string());
+    for pattern in &settings.skip_content_types {
+        args.push("--skip-body-type".to_string());
+        args.push(pattern.to_lowercase());
+    }
+    for host in &settings.excluded_hosts {
+        args.push("--no-body-host".to_string());
+        args.push(host.to_lowercase());
+    }
+    args
+}
+
+/// Push the policy to a running proxy
+pub fn push(state: &AppState, settings: &BodyCaptureSettings) {
+    broadcast_to_processes(state, &json!({ "action": "set_body_capture", "policy": settings }));
+}
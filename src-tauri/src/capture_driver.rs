@@ -0,0 +1,112 @@
+// Packet capture driver (Npcap) detection and installation
+//
+// Capture on Windows needs Npcap, or the older WinPcap. The Python side
+// reports which driver is installed; monitoring refuses to start without one
+// and points at the guided install instead. Installing uses the Npcap
+// installer bundled under `installers/` when present, otherwise downloads it,
+// then runs it elevated with WinPcap API compatibility turned on. Progress is
+// emitted as INSTALL_PROGRESS_EVENT. Other platforms need no driver.
+
+use crate::i18n::t;
+use crate::platform;
+use crate::python::{run_python_script, stream_python_script};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+const SCRIPT: &str = "python/utils/capture_driver.py";
+
+/// Event emitted at each step of an install
+pub const INSTALL_PROGRESS_EVENT: &str = "capture-driver-install-progress";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureDriverStatus {
+    /// Whether capture on this platform needs a driver at all
+    pub required: bool,
+    pub installed: bool,
+    /// "npcap", "winpcap" or "libpcap"
+    pub driver: Option<String>,
+    pub version: Option<String>,
+    /// wpcap.dll is in System32, as scapy expects
+    pub winpcap_compatible: bool,
+    /// Installer shipped with the app, used instead of downloading
+    pub bundled_installer: Option<String>,
+    pub download_url: String,
+}
+
+impl CaptureDriverStatus {
+    /// The actionable error for a missing driver, if it is missing
+    pub fn ensure_installed(&self) -> Result<(), String> {
+        if self.required && !self.installed {
+            return Err(t("error.capture_driver_missing", &[("url", &self.download_url)]));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct InstallProgress {
+    /// "downloading", "installing", "verifying" or "done"
+    stage: &'static str,
+    downloaded: Option<u64>,
+    total: Option<u64>,
+}
+
+fn emit(app: &AppHandle, stage: &'static str, downloaded: Option<u64>, total: Option<u64>) {
+    let _ = app.emit(INSTALL_PROGRESS_EVENT, InstallProgress { stage, downloaded, total });
+}
+
+pub fn status() -> Result<CaptureDriverStatus, String> {
+    let result = run_python_script(SCRIPT, &["--action", "status"])?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        serde_json::from_value(result.get("driver").cloned().unwrap_or(Value::Null))
+            .map_err(|e| format!("Failed to parse capture driver status: {}", e))
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Download (unless bundled) and run the Npcap installer, then check it took
+pub fn install(app: &AppHandle) -> Result<CaptureDriverStatus, String> {
+    let current = status()?;
+    if !current.required || current.installed {
+        emit(app, "done", None, None);
+        return Ok(current);
+    }
+
+    let installer = match current.bundled_installer {
+        Some(path) => PathBuf::from(path),
+        None => {
+            emit(app, "downloading", Some(0), None);
+            let summary = stream_python_script(SCRIPT, &["--action", "download"], |progress| {
+                let count = |key: &str| progress.get(key).and_then(|v| v.as_u64());
+                emit(app, "downloading", count("downloaded"), count("total"));
+                Ok(())
+            })
+            .map_err(|e| format!("Failed to download the Npcap installer: {}", e))?;
+            summary
+                .get("path")
+                .and_then(|p| p.as_str())
+                .map(PathBuf::from)
+                .ok_or_else(|| "Npcap download finished without a file".to_string())?
+        }
+    };
+
+    log::info!("Running Npcap installer: {:?}", installer);
+    emit(app, "installing", None, None);
+    // The installer is interactive; this waits for the user to finish it
+    platform::elevated_command(&installer, &["/winpcap_mode=yes"])
+        .status()
+        .map_err(|e| format!("Failed to run the Npcap installer: {}", e))?;
+
+    emit(app, "verifying", None, None);
+    let installed = status()?;
+    if !installed.installed {
+        return Err(t("error.capture_driver_not_installed", &[]));
+    }
+    emit(app, "done", None, None);
+    Ok(installed)
+}
@@ -0,0 +1,221 @@
+// Device-specific certificate install guides
+//
+// Picks the install steps for a device's platform: iOS has to trust the
+// profile after installing it, Android puts the CA in the user store, Windows
+// needs the trusted root store, and so on. The platform comes from the
+// User-Agent of the device's recent traffic when there is any, then from its
+// passive OS fingerprint (`os_detection`), otherwise from its hostname and
+// vendor. The steps themselves live in
+// `cert-installer/guides.json`, next to the installer pages they mirror.
+
+use crate::commands::Device;
+use crate::database::open_readonly;
+use crate::python::get_project_root;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+
+/// Recent requests whose User-Agent is looked at
+const USER_AGENT_SAMPLE: u32 = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Ios,
+    Android,
+    Windows,
+    Macos,
+    Linux,
+    Chromeos,
+    Generic,
+}
+
+impl Platform {
+    fn key(self) -> &'static str {
+        match self {
+            Platform::Ios => "ios",
+            Platform::Android => "android",
+            Platform::Windows => "windows",
+            Platform::Macos => "macos",
+            Platform::Linux => "linux",
+            Platform::Chromeos => "chromeos",
+            Platform::Generic => "generic",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GuideStep {
+    pub title: String,
+    pub detail: String,
+    #[serde(default)]
+    pub warning: Option<String>,
+    /// Shell commands to run, for platforms installed from a terminal
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct GuideContent {
+    name: String,
+    cert_format: String,
+    steps: Vec<GuideStep>,
+    #[serde(default)]
+    notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CertInstallGuide {
+    pub device_id: String,
+    pub platform: Platform,
+    /// "user_agent", "fingerprint", "hostname", "vendor", "unknown" or "requested"
+    pub detected_from: String,
+    pub name: String,
+    /// Installer path serving the certificate in the format this platform takes
+    pub download_path: String,
+    pub steps: Vec<GuideStep>,
+    pub notes: Vec<String>,
+}
+
+/// The platform a User-Agent names, checked in the same order as the installer
+pub fn platform_from_user_agent(user_agent: &str) -> Option<Platform> {
+    let ua = user_agent.to_lowercase();
+    if ua.contains("iphone") || ua.contains("ipad") {
+        Some(Platform::Ios)
+    } else if ua.contains("android") {
+        Some(Platform::Android)
+    } else if ua.contains(" cros ") || ua.contains("chromeos") {
+        Some(Platform::Chromeos)
+    } else if ua.contains("windows") {
+        Some(Platform::Windows)
+    } else if ua.contains("macintosh") || ua.contains("mac os") {
+        Some(Platform::Macos)
+    } else if ua.contains("linux") {
+        Some(Platform::Linux)
+    } else {
+        None
+    }
+}
+
+fn platform_from_hostname(hostname: &str) -> Option<Platform> {
+    let name = hostname.to_lowercase();
+    let has = |hints: &[&str]| hints.iter().any(|h| name.contains(h));
+    if has(&["iphone", "ipad"]) {
+        Some(Platform::Ios)
+    } else if has(&["android", "galaxy", "pixel", "oneplus", "redmi", "xiaomi"]) {
+        Some(Platform::Android)
+    } else if has(&["macbook", "imac", "mac-mini", "macmini"]) {
+        Some(Platform::Macos)
+    } else if has(&["chromebook"]) {
+        Some(Platform::Chromeos)
+    } else if has(&["desktop-", "laptop-", "windows"]) {
+        Some(Platform::Windows)
+    } else {
+        None
+    }
+}
+
+fn platform_from_vendor(vendor: &str, device_type: &str) -> Option<Platform> {
+    let vendor = vendor.to_lowercase();
+    let mobile = matches!(device_type, "phone" | "tablet");
+    if vendor.contains("apple") {
+        Some(if mobile { Platform::Ios } else { Platform::Macos })
+    } else if mobile && ["samsung", "google", "xiaomi", "oneplus", "motorola", "huawei", "oppo"]
+        .iter()
+        .any(|v| vendor.contains(v))
+    {
+        Some(Platform::Android)
+    } else {
+        None
+    }
+}
+
+/// The platform named by the User-Agent in a request's stored headers (JSON)
+pub fn platform_from_headers(headers: &str) -> Option<Platform> {
+    let headers = serde_json::from_str::<Value>(headers).ok()?;
+    let user_agent = headers
+        .as_object()?
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))?
+        .1
+        .as_str()?;
+    platform_from_user_agent(user_agent)
+}
+
+/// The platform most of a device's recent requests name in their User-Agent
+fn platform_from_traffic(device_id: &str) -> Result<Option<Platform>, String> {
+    // No capture database yet means no traffic to go on
+    let Ok(conn) = open_readonly() else { return Ok(None) };
+    let mut stmt = conn
+        .prepare(
+            "SELECT request_headers FROM traffic
+             WHERE device_id = ?1
+             ORDER BY timestamp DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| format!("Failed to prepare user agent query: {}", e))?;
+    let rows = stmt
+        .query_map(params![device_id, USER_AGENT_SAMPLE], |row| row.get::<_, Option<String>>(0))
+        .map_err(|e| format!("Failed to query user agents: {}", e))?;
+
+    let mut votes: HashMap<Platform, u32> = HashMap::new();
+    for headers in rows {
+        let headers = headers.map_err(|e| format!("Failed to read user agents: {}", e))?;
+        if let Some(platform) = headers.as_deref().and_then(platform_from_headers) {
+            *votes.entry(platform).or_default() += 1;
+        }
+    }
+    Ok(votes.into_iter().max_by_key(|(_, count)| *count).map(|(platform, _)| platform))
+}
+
+/// The device's platform and what it was told from
+pub fn detect(device: &Device) -> Result<(Platform, &'static str), String> {
+    if let Some(platform) = platform_from_traffic(&device.id)? {
+        return Ok((platform, "user_agent"));
+    }
+    if let Some(os) = &device.os {
+        return Ok((os.platform, "fingerprint"));
+    }
+    if let Some(platform) = device.hostname.as_deref().and_then(platform_from_hostname) {
+        return Ok((platform, "hostname"));
+    }
+    if let Some(platform) = device
+        .vendor
+        .as_deref()
+        .and_then(|vendor| platform_from_vendor(vendor, &device.device_type))
+    {
+        return Ok((platform, "vendor"));
+    }
+    Ok((Platform::Generic, "unknown"))
+}
+
+fn load_guides() -> Result<HashMap<String, GuideContent>, String> {
+    let path = get_project_root().join("cert-installer").join("guides.json");
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read install guides: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse install guides: {}", e))
+}
+
+/// Install steps for a device, for `platform` if given or else the detected one
+pub fn guide(device: &Device, platform: Option<Platform>) -> Result<CertInstallGuide, String> {
+    let (platform, detected_from) = match platform {
+        Some(platform) => (platform, "requested"),
+        None => detect(device)?,
+    };
+    let mut guides = load_guides()?;
+    let content = guides
+        .remove(platform.key())
+        .or_else(|| guides.remove(Platform::Generic.key()))
+        .ok_or_else(|| format!("No install guide for {}", platform.key()))?;
+
+    Ok(CertInstallGuide {
+        device_id: device.id.clone(),
+        platform,
+        detected_from: detected_from.to_string(),
+        name: content.name,
+        download_path: format!("/download/cert.{}", content.cert_format),
+        steps: content.steps,
+        notes: content.notes,
+    })
+}
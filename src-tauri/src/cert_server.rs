@@ -0,0 +1,289 @@
+// Certificate installer server lifecycle
+//
+// The cert installer (`cert-installer/server.py`) is the page phones visit to
+// fetch the CA certificate. At most one runs, tracked here apart from the
+// capture processes so stopping monitoring leaves it up. The server prints a
+// JSON line for every request it serves; a reader thread counts requests and
+// certificate downloads from those and notes the last activity, and the idle
+// watcher stops the server once nobody has used it for
+// `cert_server_idle_minutes`.
+//
+// Install links handed out as QR codes carry a one-time token naming the
+// device they were made for. The first visitor to open a link claims its
+// token; downloads by that visitor are then counted against the device, and
+// anyone else reusing the link is not.
+
+use crate::clock;
+use crate::commands::load_settings;
+use crate::python::start_python_script;
+use crate::state::AppState;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Read};
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+
+const SCRIPT: &str = "cert-installer/server.py";
+
+/// How often the idle watcher looks at the server
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long an install link can be claimed after it is made
+const TOKEN_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct InstallToken {
+    device_id: Option<String>,
+    issued: Instant,
+    /// Session of the visitor who opened the link first
+    visitor: Option<String>,
+    downloads: u64,
+}
+
+type Tokens = Arc<Mutex<HashMap<String, InstallToken>>>;
+
+#[derive(Default)]
+struct Activity {
+    requests: u64,
+    downloads: u64,
+    last: Option<Instant>,
+    last_at: Option<String>,
+}
+
+struct Running {
+    child: Child,
+    port: u16,
+    started: Instant,
+    started_at: String,
+    activity: Arc<Mutex<Activity>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CertServerStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub started_at: Option<String>,
+    /// Pages and files served since it started
+    pub requests: u64,
+    /// Certificate downloads served since it started
+    pub downloads: u64,
+    pub last_activity: Option<String>,
+    /// Downloads through install links, by the device each link was made for
+    pub downloads_by_device: BTreeMap<String, u64>,
+}
+
+/// The running cert installer server, if any, and the install links handed out
+#[derive(Default)]
+pub struct CertServer {
+    running: Mutex<Option<Running>>,
+    tokens: Tokens,
+}
+
+/// 128 random bits as hex; enough to tell links apart, not a secret
+fn new_token() -> String {
+    let half = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos());
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", half(), half())
+}
+
+/// Give a token to the first visitor using it, and count that visitor's downloads
+fn claim(tokens: &Tokens, token: &str, visitor: &str, download: bool) {
+    let mut tokens = tokens.lock().unwrap();
+    let Some(entry) = tokens.get_mut(token) else { return };
+    match entry.visitor.as_deref() {
+        Some(holder) if holder != visitor => return,
+        Some(_) => {}
+        None if entry.issued.elapsed() < TOKEN_TTL => entry.visitor = Some(visitor.to_string()),
+        None => return,
+    }
+    if download {
+        entry.downloads += 1;
+    }
+}
+
+/// Count requests from the server's output until it exits
+fn read_activity(stdout: impl Read, activity: Arc<Mutex<Activity>>, tokens: Tokens) {
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else { break };
+        let Ok(event) = serde_json::from_str::<Value>(&line) else { continue };
+        let kind = event.get("event").and_then(|e| e.as_str());
+        if !matches!(kind, Some("request" | "download")) {
+            continue;
+        }
+
+        let download = kind == Some("download");
+        let text = |key: &str| event.get(key).and_then(|v| v.as_str());
+        if let (Some(token), Some(visitor)) = (text("token"), text("visitor")) {
+            claim(&tokens, token, visitor, download);
+        }
+
+        let mut activity = activity.lock().unwrap();
+        activity.requests += 1;
+        if download {
+            activity.downloads += 1;
+        }
+        activity.last = Some(Instant::now());
+        activity.last_at = Some(clock::now_utc());
+    }
+}
+
+impl CertServer {
+    /// Drop the server if it has exited on its own
+    fn reap(running: &mut Option<Running>) {
+        if let Some(server) = running {
+            if !matches!(server.child.try_wait(), Ok(None)) {
+                log::warn!("Certificate server on port {} exited", server.port);
+                *running = None;
+            }
+        }
+    }
+
+    fn describe(&self, running: &Option<Running>) -> CertServerStatus {
+        let mut downloads_by_device = BTreeMap::new();
+        for token in self.tokens.lock().unwrap().values() {
+            if let (Some(device_id), true) = (&token.device_id, token.downloads > 0) {
+                *downloads_by_device.entry(device_id.clone()).or_default() += token.downloads;
+            }
+        }
+
+        match running {
+            Some(server) => {
+                let activity = server.activity.lock().unwrap();
+                CertServerStatus {
+                    running: true,
+                    port: Some(server.port),
+                    started_at: Some(server.started_at.clone()),
+                    requests: activity.requests,
+                    downloads: activity.downloads,
+                    last_activity: activity.last_at.clone(),
+                    downloads_by_device,
+                }
+            }
+            None => CertServerStatus {
+                running: false,
+                port: None,
+                started_at: None,
+                requests: 0,
+                downloads: 0,
+                last_activity: None,
+                downloads_by_device,
+            },
+        }
+    }
+
+    pub fn status(&self) -> CertServerStatus {
+        let mut running = self.running.lock().unwrap();
+        Self::reap(&mut running);
+        self.describe(&running)
+    }
+
+    /// Start the server on `port`; if one is already running, report it instead
+    pub fn start(&self, port: u16) -> Result<CertServerStatus, String> {
+        let mut running = self.running.lock().unwrap();
+        Self::reap(&mut running);
+        if let Some(server) = running.as_ref() {
+            log::info!("Certificate server already running on port {}", server.port);
+            return Ok(self.describe(&running));
+        }
+
+        let mut child = start_python_script(SCRIPT, &["--port", &port.to_string()])
+            .map_err(|e| format!("Failed to start cert server: {}", e))?;
+
+        let activity = Arc::new(Mutex::new(Activity::default()));
+        if let Some(stdout) = child.stdout.take() {
+            let (activity, tokens) = (activity.clone(), self.tokens.clone());
+            thread::spawn(move || read_activity(stdout, activity, tokens));
+        }
+        // Flask logs each request to stderr; drain it so the server never blocks on a full pipe
+        if let Some(mut stderr) = child.stderr.take() {
+            thread::spawn(move || {
+                let _ = std::io::copy(&mut stderr, &mut std::io::sink());
+            });
+        }
+
+        log::info!("Certificate server started on port {}", port);
+        *running = Some(Running {
+            child,
+            port,
+            started: Instant::now(),
+            started_at: clock::now_utc(),
+            activity,
+        });
+        Ok(self.describe(&running))
+    }
+
+    pub fn stop(&self) -> Result<(), String> {
+        let mut server = self
+            .running
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| "Certificate server is not running".to_string())?;
+
+        let _ = server.child.kill();
+        let _ = server.child.wait();
+        log::info!("Certificate server on port {} stopped", server.port);
+        Ok(())
+    }
+
+    /// A one-time token for an install link made for `device_id`
+    pub fn issue_token(&self, device_id: Option<&str>) -> String {
+        let mut tokens = self.tokens.lock().unwrap();
+        // Drop links nobody opened in time; claimed ones keep their counts
+        tokens.retain(|_, t| t.visitor.is_some() || t.issued.elapsed() < TOKEN_TTL);
+
+        let token = new_token();
+        tokens.insert(
+            token.clone(),
+            InstallToken {
+                device_id: device_id.map(str::to_string),
+                issued: Instant::now(),
+                visitor: None,
+                downloads: 0,
+            },
+        );
+        token
+    }
+
+    /// Stop the server if nothing has been served for `idle` (counting from its start)
+    pub fn stop_if_idle(&self, idle: Duration) -> bool {
+        let idle_for = {
+            let mut running = self.running.lock().unwrap();
+            Self::reap(&mut running);
+            match running.as_ref() {
+                Some(server) => server.activity.lock().unwrap().last.unwrap_or(server.started).elapsed(),
+                None => return false,
+            }
+        };
+        idle_for >= idle && self.stop().is_ok()
+    }
+}
+
+/// Spawn the background task that stops the cert server once it sits idle
+pub fn spawn_idle_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+
+            let minutes = match load_settings() {
+                Ok(settings) => settings.cert_server_idle_minutes,
+                Err(_) => continue,
+            };
+            if minutes == 0 {
+                continue;
+            }
+            let idle = Duration::from_secs(u64::from(minutes) * 60);
+            if app.state::<AppState>().cert_server.stop_if_idle(idle) {
+                log::info!("Stopped certificate server after {} idle minutes", minutes);
+            }
+        }
+    });
+}
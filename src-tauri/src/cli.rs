@@ -0,0 +1,239 @@
+// Command-line companion
+//
+// `netmon-cli` drives the monitor from terminals and scripts without the
+// window. Each subcommand calls what the matching GUI command calls, so a
+// scan records inventory changes and an export writes the same files, and
+// prints its result as JSON on stdout; errors go to stderr with a non-zero
+// exit code. Monitoring started from the GUI lives in that process, so
+// `status` reports the headless daemon's state alongside the captured data.
+
+use crate::commands::{self, load_settings};
+use crate::daemon;
+use crate::demo;
+use crate::diagnostics;
+use crate::state::AppState;
+use crate::stats_cache;
+use serde_json::{json, Value};
+use tauri::async_runtime::block_on;
+
+pub const USAGE: &str = "Usage: netmon-cli <command> [options]
+
+Commands:
+  scan                                 Scan the network and record the devices found
+  status                               Daemon state, devices and dashboard totals
+  traffic [--limit N] [--device ID] [--search QUERY]
+                                       Recent traffic, or full-text search results
+  block VALUE [--type TYPE] [--minutes N]
+                                       Add a block rule (TYPE: domain, category, keyword, allow)
+  unblock VALUE [--type TYPE]          Remove a block rule
+  export PATH [--format FORMAT]        Export captured data (FORMAT: json, csv, ndjson, parquet)
+";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Scan,
+    Status,
+    Traffic {
+        limit: Option<u32>,
+        device_id: Option<String>,
+        search: Option<String>,
+    },
+    Block {
+        rule_type: String,
+        value: String,
+        minutes: Option<u32>,
+    },
+    Unblock {
+        rule_type: String,
+        value: String,
+    },
+    Export {
+        format: String,
+        path: String,
+    },
+    Help,
+}
+
+/// Positional arguments, and `--name value` options
+type SplitArgs<'a> = (Vec<&'a str>, Vec<(&'a str, &'a str)>);
+
+/// Split arguments, rejecting options not in `allowed`
+fn split_args<'a>(args: &'a [String], allowed: &[&str]) -> Result<SplitArgs<'a>, String> {
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.strip_prefix("--") {
+            Some(name) if allowed.contains(&name) => {
+                let value = iter.next().ok_or_else(|| format!("Missing value for --{}", name))?;
+                options.push((name, value.as_str()));
+            }
+            Some(name) => return Err(format!("Unknown option: --{}", name)),
+            None => positional.push(arg.as_str()),
+        }
+    }
+    Ok((positional, options))
+}
+
+fn option<'a>(options: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    options.iter().rev().find(|(n, _)| *n == name).map(|(_, v)| *v)
+}
+
+fn number_option(options: &[(&str, &str)], name: &str) -> Result<Option<u32>, String> {
+    option(options, name)
+        .map(|v| v.parse().map_err(|_| format!("--{} must be a number, got {}", name, v)))
+        .transpose()
+}
+
+fn single_value<'a>(positional: &[&'a str], what: &str) -> Result<&'a str, String> {
+    match positional {
+        [value] => Ok(value),
+        [] => Err(format!("Missing {}", what)),
+        _ => Err(format!("Expected one {}, got {}", what, positional.join(" "))),
+    }
+}
+
+/// Parse the arguments after the program name
+pub fn parse_args(args: &[String]) -> Result<CliCommand, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(CliCommand::Help);
+    };
+
+    match command.as_str() {
+        "scan" | "status" => {
+            let (positional, _) = split_args(rest, &[])?;
+            if let Some(extra) = positional.first() {
+                return Err(format!("Unexpected argument: {}", extra));
+            }
+            Ok(if command == "scan" { CliCommand::Scan } else { CliCommand::Status })
+        }
+        "traffic" => {
+            let (positional, options) = split_args(rest, &["limit", "device", "search"])?;
+            if let Some(extra) = positional.first() {
+                return Err(format!("Unexpected argument: {}", extra));
+            }
+            if option(&options, "search").is_some() && option(&options, "device").is_some() {
+                return Err("--search applies to all devices and can't be combined with --device".to_string());
+            }
+            Ok(CliCommand::Traffic {
+                limit: number_option(&options, "limit")?,
+                device_id: option(&options, "device").map(str::to_string),
+                search: option(&options, "search").map(str::to_string),
+            })
+        }
+        "block" => {
+            let (positional, options) = split_args(rest, &["type", "minutes"])?;
+            Ok(CliCommand::Block {
+                rule_type: option(&options, "type").unwrap_or("domain").to_string(),
+                value: single_value(&positional, "value to block")?.to_string(),
+                minutes: number_option(&options, "minutes")?,
+            })
+        }
+        "unblock" => {
+            let (positional, options) = split_args(rest, &["type"])?;
+            Ok(CliCommand::Unblock {
+                rule_type: option(&options, "type").unwrap_or("domain").to_string(),
+                value: single_value(&positional, "value to unblock")?.to_string(),
+            })
+        }
+        "export" => {
+            let (positional, options) = split_args(rest, &["format"])?;
+            Ok(CliCommand::Export {
+                format: option(&options, "format").unwrap_or("json").to_string(),
+                path: single_value(&positional, "export path")?.to_string(),
+            })
+        }
+        "help" | "--help" | "-h" => Ok(CliCommand::Help),
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
+
+fn status() -> Result<Value, String> {
+    let devices = commands::fetch_devices()?;
+    let stats = stats_cache::current(&devices)?;
+    let daemon_running = daemon::is_daemon_running();
+    let daemon = if daemon_running { daemon::daemon_request("status")? } else { json!({}) };
+    Ok(json!({
+        "daemon_running": daemon_running,
+        "is_running": daemon.get("is_running").cloned().unwrap_or(json!(false)),
+        "uptime": daemon.get("uptime").cloned().unwrap_or(json!(0)),
+        "stats": stats,
+    }))
+}
+
+/// Run a parsed command, returning what to print
+pub fn execute(command: CliCommand) -> Result<Value, String> {
+    match command {
+        CliCommand::Scan => Ok(json!(commands::refresh_devices(&AppState::default())?)),
+        CliCommand::Status => status(),
+        CliCommand::Traffic {
+            limit,
+            device_id,
+            search,
+        } => {
+            let entries = match search {
+                Some(query) => block_on(commands::search_traffic(query, limit))?,
+                None => block_on(commands::get_traffic(limit, None, device_id))?,
+            };
+            Ok(json!(entries))
+        }
+        CliCommand::Block {
+            rule_type,
+            value,
+            minutes,
+        } => {
+            block_on(commands::add_block_rule(rule_type, value, minutes))?;
+            Ok(json!({ "success": true }))
+        }
+        CliCommand::Unblock { rule_type, value } => {
+            block_on(commands::remove_block_rule(rule_type, value))?;
+            Ok(json!({ "success": true }))
+        }
+        CliCommand::Export { format, path } => commands::export_to(&format, &path, |written, total| {
+            if total > 0 {
+                eprint!("\rExported {} of {} rows", written, total);
+            }
+            Ok(())
+        }),
+        CliCommand::Help => Ok(json!(USAGE)),
+    }
+}
+
+/// Entry point of `netmon-cli`, returning the exit code
+pub fn main() -> i32 {
+    diagnostics::init_logging();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(CliCommand::Help) => {
+            print!("{}", USAGE);
+            return 0;
+        }
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    // Same data as the window would show
+    if let Some(seed) = load_settings().ok().and_then(|s| s.demo_seed) {
+        demo::enable(Some(seed));
+    }
+
+    let export = matches!(command, CliCommand::Export { .. });
+    let result = execute(command);
+    if export {
+        eprintln!();
+    }
+    match result {
+        Ok(value) => {
+            println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default());
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// Timestamps and time zones
+//
+// Everything stored is UTC: the capture layer writes ISO 8601 with a "+00:00"
+// offset, so range queries compare UTC strings against UTC bounds. Anything
+// shown to the user, and anything that depends on wall-clock time ("today",
+// hour-of-day buckets), goes through the `timezone` setting (an IANA name such
+// as "Europe/Berlin"; empty for the system zone). Naive timestamps left over
+// from before UTC storage are read as UTC; the database migration converts the
+// ones that were written in local time.
+
+use crate::commands::load_settings;
+use chrono::{DateTime, FixedOffset, Local, LocalResult, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Format used for range bounds in queries, comparable with stored timestamps
+const BOUND_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Where wall-clock times are read and shown
+#[derive(Debug, Clone, Copy)]
+pub enum Zone {
+    System,
+    Named(Tz),
+}
+
+impl Zone {
+    /// The zone from the `timezone` setting
+    pub fn configured() -> Zone {
+        let name = load_settings().ok().and_then(|s| s.timezone).unwrap_or_default();
+        if name.is_empty() {
+            return Zone::System;
+        }
+        match name.parse() {
+            Ok(zone) => Zone::Named(zone),
+            Err(_) => {
+                log::warn!("Unknown timezone {}, using the system zone", name);
+                Zone::System
+            }
+        }
+    }
+
+    /// A UTC time in this zone
+    pub fn at(self, at: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Zone::Named(zone) => at.with_timezone(&zone).fixed_offset(),
+            Zone::System => at.with_timezone(&Local).fixed_offset(),
+        }
+    }
+
+    /// A stored timestamp as RFC 3339 in this zone (unchanged if it doesn't parse)
+    pub fn display(self, timestamp: &str) -> String {
+        match parse_stored(timestamp) {
+            Some(at) => self.at(at).to_rfc3339_opts(SecondsFormat::Secs, false),
+            None => timestamp.to_string(),
+        }
+    }
+
+    /// The current wall-clock time
+    pub fn now(self) -> NaiveDateTime {
+        self.at(Utc::now()).naive_local()
+    }
+
+    /// A wall-clock time in this zone as UTC
+    pub fn to_utc(self, wall: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            Zone::Named(zone) => resolve(&zone, wall),
+            Zone::System => resolve(&Local, wall),
+        }
+    }
+
+    /// Wall-clock bounds in this zone as UTC query bounds
+    pub fn utc_bounds(self, start: NaiveDateTime, end: NaiveDateTime) -> (String, String) {
+        (
+            self.to_utc(start).format(BOUND_FORMAT).to_string(),
+            self.to_utc(end).format(BOUND_FORMAT).to_string(),
+        )
+    }
+}
+
+/// Check a zone name before it is saved (empty means the system zone)
+pub fn validate_zone(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.parse::<Tz>().is_ok() {
+        Ok(())
+    } else {
+        Err(format!("Unknown timezone: {}", name))
+    }
+}
+
+/// The current time, formatted for storage
+pub fn now_utc() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+/// A query bound `ago` before now
+pub fn utc_cutoff(ago: chrono::Duration) -> String {
+    utc_bound(Utc::now() - ago)
+}
+
+/// A query bound at the given time
+pub fn utc_bound(at: DateTime<Utc>) -> String {
+    at.format(BOUND_FORMAT).to_string()
+}
+
+/// Parse a stored timestamp; naive ones are UTC
+pub fn parse_stored(timestamp: &str) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(timestamp) {
+        return Some(at.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(timestamp, fmt).ok())
+        .map(|naive| naive.and_utc())
+}
+
+fn resolve<Z: TimeZone>(zone: &Z, wall: NaiveDateTime) -> DateTime<Utc> {
+    match zone.from_local_datetime(&wall) {
+        LocalResult::Single(at) => at.with_timezone(&Utc),
+        // Repeated hour when clocks go back: take the first
+        LocalResult::Ambiguous(first, _) => first.with_timezone(&Utc),
+        // Skipped hour when clocks go forward: the instant the gap ends
+        LocalResult::None => resolve(zone, wall + chrono::Duration::hours(1)),
+    }
+}
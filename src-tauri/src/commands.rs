@@ -1,15 +1,106 @@
 // Tauri command handlers
 
 use crate::python::{
-    kill_python_processes, start_python_script, run_python_script,
-    query_database, run_blocking_command, run_stealth_command, run_alert_command
+    kill_python_processes, start_python_script, run_python_script, stream_python_script,
+    query_database, query_database_stream, run_blocking_command, run_stealth_command, run_alert_command,
+    send_command_to_process
 };
+use crate::access::{self, AccessInfo, ObserverToken};
+use crate::advisories::{self, DeviceAdvisories};
+use crate::alert_evidence::{self, AlertEvidence, AlertEvidenceSettings};
+use crate::alert_policy::{self, EscalationRule};
+use crate::annotations;
+use crate::api_keys::{self, ApiKey, ApiScope};
+use crate::app_limits::{self, AppLimitStatus};
+use crate::audit::{self, AuditEntry};
+use crate::autostart;
+use crate::bandwidth::{self, DeviceBandwidth};
+use crate::beaconing::{self, BeaconCandidate};
+use crate::block_page::{self, BlockPageConfig};
+use crate::body_capture::{self, BodyCaptureSettings};
+use crate::capture_driver::{self, CaptureDriverStatus};
+use crate::cert_guide::{self, CertInstallGuide, Platform};
+use crate::cert_server::CertServerStatus;
+use crate::clock::{self, Zone};
+use crate::activity::{self, ActivityComparison, Period};
+use crate::apps::{self, AppUsage, UsageRange};
+use crate::aggregation;
+use crate::credential_exposure::{self, CredentialExposure, CredentialExposureConfig};
+use crate::daemon;
+use crate::database;
+use crate::db_writer;
+use crate::diagnostics::{self, DiagnosticsBundle};
+use crate::demo::{self, DemoStatus};
+use crate::dhcp::{DhcpScanResult, DhcpServer};
+use crate::discovery::{self, DeviceServices};
+use crate::dns_bypass::{self, DnsBypassAttempt, DnsPolicy};
+use crate::dns_tunneling::{self, DnsAnomalyReport};
+use crate::domains::{self, DomainDetails};
+use crate::elevation::{self, ElevationHandoff};
+use crate::enrichment::{self, EnrichmentCacheStats, Kind as EnrichmentKind};
+use crate::flows::{self, FlowLog};
+use crate::forwarder::{self, ForwarderSettings, ForwarderStatus};
+use crate::grpc::{self, GrpcSettings};
+use crate::guest::{self, GuestStats};
+use crate::heatmap::{self, ActivityHeatmap};
+use crate::home_assistant::{self, HomeAssistantSettings};
+use crate::hooks::{self, Hook, HookRun};
+use crate::hotkeys::{self, HotkeyConflict, HotkeySettings};
+use crate::i18n::{self, t, LocaleInfo};
+use crate::intercept_exclusions::{self, InterceptExclusion};
+use crate::investigations::{self, EvidenceKind, Investigation, InvestigationStatus};
+use crate::ip_conflicts::{self, IpConflictReport};
+use crate::interception::{self, InterceptionMode};
+use crate::inventory::{self, DeviceChangelog, InventoryChanges};
+use crate::lifecycle::{LifecycleError, MonitoringState};
+use crate::media::{self, MediaEntry, MediaType};
+use crate::metrics;
+use crate::mqtt::MqttSettings;
+use crate::notifications::{self, AlertSound, NotificationSettings, SoundPreview};
+use crate::operations::{self, OperationStatus};
+use crate::os_detection::{self, DeviceOs};
+use crate::platform;
+use crate::overhead::{self, OverheadMetrics};
+use crate::p2p::{self, P2pActivityReport};
+use crate::parquet_export;
+use crate::performance::{self, PerformanceMetrics};
+use crate::phone_home::{self, PhoneHomeReport};
+use crate::protocols::{self, ProtocolBreakdown};
+use crate::proxy_config::{self, ProxyConfig, ProxyPorts, ProxySettings, ProxySyncStatus, UpstreamProxy, UpstreamProxyTest};
+use crate::python_env::{self, PythonEnvironment};
+use crate::qr::{self, QrFormat};
+use crate::quic::{self, QuicDowngradeReport, QuicPolicy};
+use crate::quick_actions::{self, QuickAction};
+use crate::retention::{self, RetentionReport, RetentionSettings};
+use crate::secrets;
+use crate::sessions::{self, DeviceSessions};
+use crate::snapshot::Snapshot;
+use crate::spoofing::{self, SpoofTargets};
+use crate::subnets::{self, NetworkDetection};
 use crate::state::AppState;
+use crate::state_snapshot;
+use crate::stats_cache;
+use crate::threat_intel;
+use crate::tls_report::{self, TlsReport};
+use crate::topology::{self, NetworkTopology};
+use crate::trackers::{self, TrackerReport};
+use crate::unblock::{self, UnblockRequest, UnblockStatus};
+use crate::updater::{self, UpdateInfo};
+use crate::usage_cost::{self, CostModel, UsageCost};
+use crate::users::{self, DeviceMetadata, SavedSearch, SettingsSchema};
+use crate::watch_lists::{self, WatchListConfig, WatchMatch, WatchRollup};
+use crate::widgets::{self, Widget};
+use crate::wifi::{self, WifiEnvironment};
+use crate::workspace::{self, WorkspaceInfo};
+use crate::subscriptions::{self, spawn_traffic_subscription, PushSettings};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use tauri::State;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::atomic::Ordering;
 
 // ============================================
 // Data Types
@@ -28,9 +119,31 @@ pub struct Device {
     pub is_online: bool,
     pub is_monitored: bool,
     pub has_certificate: bool,
+    #[serde(default)]
+    pub interception_mode: InterceptionMode,
     pub total_bytes: u64,
+    /// Bytes downloaded by the device in recorded traffic
+    pub bytes_in: u64,
+    /// Bytes uploaded by the device in recorded traffic
+    pub bytes_out: u64,
     pub blocked_requests: u32,
     pub alerts: u32,
+    /// Operating system told from passive fingerprints and User-Agents
+    #[serde(default)]
+    pub os: Option<DeviceOs>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BypassDetection {
+    pub id: String,
+    pub timestamp: String,
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub method: String,
+    pub protocol: Option<String>,
+    pub endpoint: Option<String>,
+    pub evidence: String,
+    pub detail: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -45,12 +158,33 @@ pub struct TrafficEntry {
     pub path: Option<String>,
     pub status_code: Option<u16>,
     pub content_type: Option<String>,
+    /// Bytes sent by the device (its upload)
     pub request_size: u64,
+    /// Bytes received by the device (its download)
     pub response_size: u64,
     pub duration: u32,
     pub is_blocked: bool,
     pub has_alert: bool,
     pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DnsQuery {
+    pub id: String,
+    pub timestamp: String,
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub query_name: String,
+    pub query_type: String,
+    pub response_ip: Option<String>,
+    pub response_ttl: Option<u32>,
+    pub blocked: bool,
+    pub block_reason: Option<String>,
+    pub category: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -64,13 +198,98 @@ pub struct Alert {
     pub description: String,
     pub url: Option<String>,
     pub matched_keywords: Option<Vec<String>>,
+    /// Times this alert fired within the dedup window (1 if never repeated)
+    pub occurrences: u32,
+    pub last_seen: String,
     pub is_read: bool,
     pub is_resolved: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Incident {
+    pub id: String,
+    /// Device ID, or IP for devices without one
+    pub device: Option<String>,
+    pub category: String,
+    /// Highest severity among its alerts
+    pub severity: String,
+    pub title: String,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub alert_count: u32,
+    pub status: String,
+    pub resolved_at: Option<String>,
+    /// Escalation rule that made it critical
+    #[serde(default)]
+    pub escalated_by: Option<String>,
+    #[serde(default)]
+    pub escalated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct IncidentDetails {
+    #[serde(flatten)]
+    pub incident: Incident,
+    /// The incident's alerts, oldest first
+    pub timeline: Vec<Alert>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TrafficFilter {
+    pub device_id: Option<String>,
+    pub host: Option<String>,
+    pub method: Option<String>,
+    pub category: Option<String>,
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub blocked_only: bool,
+}
+
+impl TrafficFilter {
+    /// Check whether a traffic entry passes this filter
+    pub fn matches(&self, entry: &TrafficEntry) -> bool {
+        if let Some(ref device_id) = self.device_id {
+            if entry.device_id.as_deref() != Some(device_id.as_str()) {
+                return false;
+            }
+        }
+        if let Some(ref host) = self.host {
+            if !entry.host.to_lowercase().contains(&host.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(ref method) = self.method {
+            if !entry.method.eq_ignore_ascii_case(method) {
+                return false;
+            }
+        }
+        if let Some(ref category) = self.category {
+            if entry.category.as_deref() != Some(category.as_str()) {
+                return false;
+            }
+        }
+        if let Some(ref tag) = self.tag {
+            if !entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                return false;
+            }
+        }
+        !self.blocked_only || entry.is_blocked
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TrafficStreamEvent {
+    pub subscription_id: String,
+    pub entries: Vec<TrafficEntry>,
+    /// Matching entries left out since the previous event to keep up with a
+    /// traffic storm (rows skipped unread count too)
+    pub dropped: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
 pub struct MonitoringStatus {
     pub is_running: bool,
+    pub state: MonitoringState,
     pub arp_spoofing: bool,
     pub https_proxy: bool,
     pub dns_capture: bool,
@@ -78,9 +297,11 @@ pub struct MonitoringStatus {
     pub current_profile: String,
     pub uptime: u64,
     pub errors: Vec<String>,
+    /// Ports the HTTPS proxy is bound to while it runs
+    pub proxy_ports: Option<ProxyPorts>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DashboardStats {
     pub total_devices: u32,
     pub online_devices: u32,
@@ -89,20 +310,95 @@ pub struct DashboardStats {
     pub total_alerts: u32,
     pub unresolved_alerts: u32,
     pub total_bandwidth: u64,
+    /// Downloaded part of `total_bandwidth`
+    pub bytes_in: u64,
+    /// Uploaded part of `total_bandwidth`
+    pub bytes_out: u64,
     pub top_domains: Vec<TopDomain>,
     pub traffic_by_hour: Vec<HourlyTraffic>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TopDomain {
     pub domain: String,
     pub count: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct HourlyTraffic {
+    /// Hour of the day in the configured timezone
     pub hour: u32,
+    /// Start of the hour, in the configured timezone
+    pub start: String,
     pub requests: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InterfaceCapabilities {
+    pub name: String,
+    pub exists: bool,
+    pub is_up: bool,
+    pub has_ipv4: bool,
+    pub ipv4: Option<String>,
+    pub supports_promiscuous: bool,
+    pub capture_driver_ok: bool,
+    pub capture_error: Option<String>,
+    #[serde(default)]
+    pub available: Vec<String>,
+    /// Npcap/WinPcap status (None from older scripts)
+    #[serde(default)]
+    pub capture_driver: Option<CaptureDriverStatus>,
+}
+
+impl InterfaceCapabilities {
+    /// Turn missing capabilities into an actionable error message
+    pub fn ensure_usable(&self) -> Result<(), String> {
+        if let Some(driver) = &self.capture_driver {
+            driver.ensure_installed()?;
+        }
+        if !self.exists {
+            return Err(format!(
+                "Network interface '{}' does not exist. Available interfaces: {}. Select one in Settings.",
+                self.name,
+                self.available.join(", ")
+            ));
+        }
+        if !self.is_up {
+            return Err(format!(
+                "Network interface '{}' is down. Connect it to the network and try again.",
+                self.name
+            ));
+        }
+        if !self.has_ipv4 {
+            return Err(format!(
+                "Network interface '{}' has no IPv4 address. Make sure it is connected to your LAN.",
+                self.name
+            ));
+        }
+        if !self.capture_driver_ok {
+            return Err(format!(
+                "Packet capture on '{}' is not available ({}). Install Npcap/libpcap and run as administrator.",
+                self.name,
+                self.capture_error.as_deref().unwrap_or("unknown error")
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostics {
+    pub platform: String,
+    pub is_elevated: bool,
+    pub elevation_method: String,
+    pub elevation_hint: Option<String>,
+    pub python_path: String,
+    pub python_version: Option<String>,
+    pub default_interface: String,
+    pub monitoring_state: MonitoringState,
+    pub daemon_running: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -113,6 +409,190 @@ pub struct Settings {
     pub blocking_enabled: bool,
     pub notifications_enabled: bool,
     pub network_interface: Option<String>,
+    #[serde(default)]
+    pub trusted_dhcp_servers: Vec<String>,
+    /// CIDR ranges scanned besides the detected networks
+    #[serde(default)]
+    pub scan_ranges: Vec<String>,
+    #[serde(default)]
+    pub autostart: bool,
+    #[serde(default)]
+    pub start_minimized: bool,
+    #[serde(default = "default_raw_retention_days")]
+    pub raw_retention_days: u32,
+    /// How long each class of stored data is kept
+    #[serde(default)]
+    pub retention: RetentionSettings,
+    /// Serve Prometheus metrics on localhost
+    #[serde(default)]
+    pub metrics_enabled: bool,
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
+    /// gRPC control server for automation (builds with the `grpc` feature)
+    #[serde(default)]
+    pub grpc: GrpcSettings,
+    #[serde(default)]
+    pub mqtt: MqttSettings,
+    #[serde(default)]
+    pub home_assistant: HomeAssistantSettings,
+    #[serde(default)]
+    pub forwarder: ForwarderSettings,
+    /// Repeats of an unread alert within this many seconds bump its count instead (0 disables)
+    #[serde(default = "default_alert_dedup_window_seconds")]
+    pub alert_dedup_window_seconds: u32,
+    /// Alerts each rule (keyword or category) may raise per hour (0 for no limit)
+    #[serde(default = "default_alert_max_per_hour")]
+    pub alert_max_per_hour: u32,
+    /// Per-rule overrides of `alert_max_per_hour`, keyed by keyword or category
+    #[serde(default)]
+    pub alert_rule_limits: BTreeMap<String, u32>,
+    /// Severity alerts are raised with, keyed by keyword or category
+    #[serde(default)]
+    pub alert_severity_map: BTreeMap<String, String>,
+    /// Bursts of alerts from one device that make its incident critical
+    #[serde(default)]
+    pub alert_escalations: Vec<EscalationRule>,
+    /// Traffic, DNS and screenshots captured when an alert fires
+    #[serde(default)]
+    pub alert_evidence: AlertEvidenceSettings,
+    /// "stable" or "beta"
+    #[serde(default = "default_update_channel")]
+    pub update_channel: String,
+    /// Bundle diagnostics automatically on the next start after a crash
+    #[serde(default)]
+    pub crash_reports_enabled: bool,
+    /// System-wide shortcuts for pausing, panic stop and stealth
+    #[serde(default)]
+    pub hotkeys: HotkeySettings,
+    /// Language of alerts, reports and errors the backend generates
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// IANA timezone for displayed times and day boundaries (None for the system zone)
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Which request/response bodies the proxy stores
+    #[serde(default)]
+    pub body_capture: BodyCaptureSettings,
+    /// Seed of the synthetic data shown while demo mode is on (None when off)
+    #[serde(default)]
+    pub demo_seed: Option<u64>,
+    /// Alert when the proxy adds more than this many milliseconds per request (0 disables)
+    #[serde(default = "default_overhead_alert_ms")]
+    pub overhead_alert_ms: u32,
+    /// Every OS user reads and writes the same capture database (off: one per user)
+    #[serde(default = "default_shared_capture_database")]
+    pub shared_capture_database: bool,
+    /// Port the certificate installer page is served on
+    #[serde(default = "default_cert_server_port")]
+    pub cert_server_port: u16,
+    /// Stop the certificate installer after this many minutes without a visit (0 keeps it up)
+    #[serde(default = "default_cert_server_idle_minutes")]
+    pub cert_server_idle_minutes: u32,
+    /// HTTPS proxy port and upstream proxy
+    #[serde(default)]
+    pub proxy: ProxySettings,
+    /// How often and how much live traffic is pushed to the window
+    #[serde(default)]
+    pub ui_push: PushSettings,
+    /// Price, data cap and billing cycle of a metered connection
+    #[serde(default)]
+    pub usage_cost: CostModel,
+}
+
+fn default_raw_retention_days() -> u32 {
+    7
+}
+
+fn default_metrics_port() -> u16 {
+    9464
+}
+
+fn default_alert_dedup_window_seconds() -> u32 {
+    300
+}
+
+fn default_alert_max_per_hour() -> u32 {
+    100
+}
+
+fn default_update_channel() -> String {
+    "stable".to_string()
+}
+
+fn default_overhead_alert_ms() -> u32 {
+    200
+}
+
+fn default_shared_capture_database() -> bool {
+    true
+}
+
+fn default_cert_server_port() -> u16 {
+    8888
+}
+
+fn default_cert_server_idle_minutes() -> u32 {
+    30
+}
+
+fn default_locale() -> String {
+    i18n::DEFAULT_LOCALE.to_string()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: "dark".to_string(),
+            stealth_enabled: true,
+            device_profile: "hp_printer".to_string(),
+            blocking_enabled: true,
+            notifications_enabled: true,
+            network_interface: None,
+            trusted_dhcp_servers: vec![],
+            scan_ranges: vec![],
+            autostart: false,
+            start_minimized: false,
+            raw_retention_days: default_raw_retention_days(),
+            retention: RetentionSettings::default(),
+            metrics_enabled: false,
+            metrics_port: default_metrics_port(),
+            grpc: GrpcSettings::default(),
+            mqtt: MqttSettings::default(),
+            home_assistant: HomeAssistantSettings::default(),
+            forwarder: ForwarderSettings::default(),
+            alert_dedup_window_seconds: default_alert_dedup_window_seconds(),
+            alert_max_per_hour: default_alert_max_per_hour(),
+            alert_rule_limits: BTreeMap::new(),
+            alert_severity_map: BTreeMap::new(),
+            alert_escalations: vec![],
+            alert_evidence: AlertEvidenceSettings::default(),
+            update_channel: default_update_channel(),
+            crash_reports_enabled: false,
+            hotkeys: HotkeySettings::default(),
+            locale: default_locale(),
+            timezone: None,
+            body_capture: BodyCaptureSettings::default(),
+            demo_seed: None,
+            overhead_alert_ms: default_overhead_alert_ms(),
+            shared_capture_database: default_shared_capture_database(),
+            cert_server_port: default_cert_server_port(),
+            cert_server_idle_minutes: default_cert_server_idle_minutes(),
+            proxy: ProxySettings::default(),
+            ui_push: PushSettings::default(),
+            usage_cost: CostModel::default(),
+        }
+    }
+}
+
+/// A QR code of a certificate install link
+#[derive(Debug, Serialize)]
+pub struct CertQr {
+    pub url: String,
+    pub token: String,
+    pub device_id: Option<String>,
+    /// "image/png" or "image/svg+xml"
+    pub mime_type: String,
+    pub data: Vec<u8>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -137,21 +617,15 @@ pub struct BlockCategory {
 // ============================================
 
 fn get_config_path() -> PathBuf {
-    crate::python::get_project_root().join("config")
+    crate::workspace::config_dir()
 }
 
-fn load_settings() -> Result<Settings, String> {
+/// Read the workspace's shared settings file as a JSON object
+fn load_shared_settings() -> Result<serde_json::Map<String, Value>, String> {
     let path = get_config_path().join("settings.json");
     
     if !path.exists() {
-        return Ok(Settings {
-            theme: "dark".to_string(),
-            stealth_enabled: true,
-            device_profile: "hp_printer".to_string(),
-            blocking_enabled: true,
-            notifications_enabled: true,
-            network_interface: None,
-        });
+        return Ok(serde_json::Map::new());
     }
     
     let content = fs::read_to_string(&path)
@@ -161,38 +635,77 @@ fn load_settings() -> Result<Settings, String> {
         .map_err(|e| format!("Failed to parse settings: {}", e))
 }
 
-fn save_settings(settings: &Settings) -> Result<(), String> {
+/// Shared settings with the current OS user's own settings on top
+pub fn load_settings() -> Result<Settings, String> {
+    let mut merged = match serde_json::to_value(Settings::default()) {
+        Ok(Value::Object(defaults)) => defaults,
+        _ => serde_json::Map::new(),
+    };
+    merged.extend(load_shared_settings()?);
+    users::overlay_settings(&mut merged)?;
+    
+    serde_json::from_value(Value::Object(merged))
+        .map_err(|e| format!("Failed to parse settings: {}", e))
+}
+
+/// Save user-scoped settings for the current OS user and the rest to the shared file
+///
+/// User-scoped values already in the shared file are left as they are, as
+/// the defaults for users who haven't saved their own.
+pub fn save_settings(settings: &Settings) -> Result<(), String> {
     let path = get_config_path().join("settings.json");
     
     fs::create_dir_all(get_config_path())
         .map_err(|e| format!("Failed to create config dir: {}", e))?;
     
-    let content = serde_json::to_string_pretty(settings)
+    let values = match serde_json::to_value(settings) {
+        Ok(Value::Object(values)) => values,
+        Ok(_) => return Err("Failed to serialize settings: not an object".to_string()),
+        Err(e) => return Err(format!("Failed to serialize settings: {}", e)),
+    };
+    users::save_settings(&values)?;
+    
+    let mut shared = load_shared_settings().unwrap_or_default();
+    shared.extend(values.into_iter().filter(|(key, _)| users::scope(key) == "shared"));
+    let content = serde_json::to_string_pretty(&shared)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
     
     fs::write(&path, content)
-        .map_err(|e| format!("Failed to write settings: {}", e))
+        .map_err(|e| format!("Failed to write settings: {}", e))?;
+    users::reload();
+    Ok(())
 }
 
 fn parse_devices(json: Value) -> Vec<Device> {
+    let zone = Zone::configured();
     if let Some(devices) = json.get("devices").and_then(|d| d.as_array()) {
         devices.iter().filter_map(|d| {
-            Some(Device {
+            let mut device = Device {
                 id: d.get("id")?.as_str()?.to_string(),
                 mac: d.get("mac_address").or(d.get("mac"))?.as_str()?.to_string(),
                 ip: d.get("ip_address").or(d.get("ip"))?.as_str()?.to_string(),
                 hostname: d.get("hostname").and_then(|h| h.as_str()).map(|s| s.to_string()),
                 vendor: d.get("manufacturer").or(d.get("vendor")).and_then(|v| v.as_str()).map(|s| s.to_string()),
                 device_type: d.get("device_type").and_then(|t| t.as_str()).unwrap_or("unknown").to_string(),
-                first_seen: d.get("first_seen").and_then(|t| t.as_str()).unwrap_or("").to_string(),
-                last_seen: d.get("last_seen").and_then(|t| t.as_str()).unwrap_or("").to_string(),
+                first_seen: zone.display(d.get("first_seen").and_then(|t| t.as_str()).unwrap_or("")),
+                last_seen: zone.display(d.get("last_seen").and_then(|t| t.as_str()).unwrap_or("")),
                 is_online: d.get("is_online").and_then(|b| b.as_bool()).unwrap_or(false),
                 is_monitored: d.get("is_monitored").and_then(|b| b.as_bool()).unwrap_or(true),
                 has_certificate: d.get("has_certificate").and_then(|b| b.as_bool()).unwrap_or(false),
+                interception_mode: d
+                    .get("interception_mode")
+                    .and_then(|m| serde_json::from_value(m.clone()).ok())
+                    .unwrap_or_default(),
                 total_bytes: d.get("total_bytes").and_then(|n| n.as_u64()).unwrap_or(0),
+                bytes_in: d.get("bytes_in").and_then(|n| n.as_u64()).unwrap_or(0),
+                bytes_out: d.get("bytes_out").and_then(|n| n.as_u64()).unwrap_or(0),
                 blocked_requests: d.get("blocked_requests").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
                 alerts: d.get("alerts").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
-            })
+                os: None,
+            };
+            discovery::apply(&mut device);
+            device.os = os_detection::for_device(&device.id, &device.mac, &device.device_type);
+            Some(device)
         }).collect()
     } else {
         vec![]
@@ -201,24 +714,56 @@ fn parse_devices(json: Value) -> Vec<Device> {
 
 fn parse_traffic(json: Value) -> Vec<TrafficEntry> {
     if let Some(traffic) = json.get("traffic").and_then(|t| t.as_array()) {
-        traffic.iter().filter_map(|t| {
-            Some(TrafficEntry {
-                id: t.get("id")?.as_str()?.to_string(),
-                timestamp: t.get("timestamp")?.as_str()?.to_string(),
-                device_id: t.get("device_id").and_then(|d| d.as_str()).map(|s| s.to_string()),
-                device_ip: t.get("device_ip").and_then(|d| d.as_str()).unwrap_or("").to_string(),
-                method: t.get("method").and_then(|m| m.as_str()).unwrap_or("GET").to_string(),
-                url: t.get("url").and_then(|u| u.as_str()).unwrap_or("").to_string(),
-                host: t.get("host").and_then(|h| h.as_str()).unwrap_or("").to_string(),
-                path: t.get("path").and_then(|p| p.as_str()).map(|s| s.to_string()),
-                status_code: t.get("status_code").and_then(|s| s.as_u64()).map(|n| n as u16),
-                content_type: t.get("content_type").or(t.get("response_body_type")).and_then(|c| c.as_str()).map(|s| s.to_string()),
-                request_size: t.get("request_size").and_then(|n| n.as_u64()).unwrap_or(0),
-                response_size: t.get("response_size").and_then(|n| n.as_u64()).unwrap_or(0),
-                duration: t.get("duration_ms").or(t.get("duration")).and_then(|n| n.as_u64()).unwrap_or(0) as u32,
-                is_blocked: t.get("blocked").and_then(|b| b.as_bool()).unwrap_or(false),
-                has_alert: t.get("alerts").and_then(|a| a.as_array()).map(|a| !a.is_empty()).unwrap_or(false),
-                category: t.get("category").and_then(|c| c.as_str()).map(|s| s.to_string()),
+        let zone = Zone::configured();
+        traffic.iter().filter_map(|t| traffic_from_json(t, zone)).collect()
+    } else {
+        vec![]
+    }
+}
+
+fn traffic_from_json(t: &Value, zone: Zone) -> Option<TrafficEntry> {
+    Some(TrafficEntry {
+        id: t.get("id")?.as_str()?.to_string(),
+        timestamp: zone.display(t.get("timestamp")?.as_str()?),
+        device_id: t.get("device_id").and_then(|d| d.as_str()).map(|s| s.to_string()),
+        device_ip: t.get("device_ip").and_then(|d| d.as_str()).unwrap_or("").to_string(),
+        method: t.get("method").and_then(|m| m.as_str()).unwrap_or("GET").to_string(),
+        url: t.get("url").and_then(|u| u.as_str()).unwrap_or("").to_string(),
+        host: t.get("host").and_then(|h| h.as_str()).unwrap_or("").to_string(),
+        path: t.get("path").and_then(|p| p.as_str()).map(|s| s.to_string()),
+        status_code: t.get("status_code").and_then(|s| s.as_u64()).map(|n| n as u16),
+        content_type: t.get("content_type").or(t.get("response_body_type")).and_then(|c| c.as_str()).map(|s| s.to_string()),
+        request_size: t.get("request_size").and_then(|n| n.as_u64()).unwrap_or(0),
+        response_size: t.get("response_size").and_then(|n| n.as_u64()).unwrap_or(0),
+        duration: t.get("duration_ms").or(t.get("duration")).and_then(|n| n.as_u64()).unwrap_or(0) as u32,
+        is_blocked: t.get("blocked").and_then(|b| b.as_bool()).unwrap_or(false),
+        has_alert: t.get("alerts").and_then(|a| a.as_array()).map(|a| !a.is_empty()).unwrap_or(false),
+        category: t.get("category").and_then(|c| c.as_str()).map(|s| s.to_string()),
+        tags: t
+            .get("tags")
+            .and_then(|a| a.as_array())
+            .map(|a| a.iter().filter_map(|t| t.as_str()).map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+        note: t.get("note").and_then(|n| n.as_str()).map(|s| s.to_string()),
+    })
+}
+
+fn parse_dns_queries(json: Value) -> Vec<DnsQuery> {
+    let zone = Zone::configured();
+    if let Some(queries) = json.get("queries").and_then(|q| q.as_array()) {
+        queries.iter().filter_map(|q| {
+            Some(DnsQuery {
+                id: q.get("id")?.as_str()?.to_string(),
+                timestamp: zone.display(q.get("timestamp")?.as_str()?),
+                device_id: q.get("device_id").and_then(|d| d.as_str()).map(|s| s.to_string()),
+                device_ip: q.get("device_ip").and_then(|d| d.as_str()).unwrap_or("").to_string(),
+                query_name: q.get("query_name")?.as_str()?.to_string(),
+                query_type: q.get("query_type").and_then(|t| t.as_str()).unwrap_or("A").to_string(),
+                response_ip: q.get("response_ip").and_then(|r| r.as_str()).map(|s| s.to_string()),
+                response_ttl: q.get("response_ttl").and_then(|n| n.as_u64()).map(|n| n as u32),
+                blocked: q.get("blocked").and_then(|b| b.as_bool()).unwrap_or(false),
+                block_reason: q.get("block_reason").and_then(|r| r.as_str()).map(|s| s.to_string()),
+                category: q.get("category").and_then(|c| c.as_str()).map(|s| s.to_string()),
             })
         }).collect()
     } else {
@@ -227,11 +772,12 @@ fn parse_traffic(json: Value) -> Vec<TrafficEntry> {
 }
 
 fn parse_alerts(json: Value) -> Vec<Alert> {
+    let zone = Zone::configured();
     if let Some(alerts) = json.get("alerts").and_then(|a| a.as_array()) {
         alerts.iter().filter_map(|a| {
             Some(Alert {
                 id: a.get("id")?.as_str()?.to_string(),
-                timestamp: a.get("timestamp")?.as_str()?.to_string(),
+                timestamp: zone.display(a.get("timestamp")?.as_str()?),
                 device_id: a.get("device_id").or(a.get("source_device")).and_then(|d| d.as_str()).map(|s| s.to_string()),
                 severity: a.get("severity")?.as_str()?.to_string(),
                 category: a.get("category")?.as_str()?.to_string(),
@@ -239,6 +785,8 @@ fn parse_alerts(json: Value) -> Vec<Alert> {
                 description: a.get("description").and_then(|d| d.as_str()).unwrap_or("").to_string(),
                 url: a.get("url").and_then(|u| u.as_str()).map(|s| s.to_string()),
                 matched_keywords: a.get("matched_keyword").and_then(|k| k.as_str()).map(|s| vec![s.to_string()]),
+                occurrences: a.get("occurrences").and_then(|n| n.as_u64()).unwrap_or(1) as u32,
+                last_seen: zone.display(a.get("last_seen").or(a.get("timestamp")).and_then(|t| t.as_str()).unwrap_or("")),
                 is_read: a.get("acknowledged").and_then(|b| b.as_bool()).unwrap_or(false),
                 is_resolved: a.get("acknowledged").and_then(|b| b.as_bool()).unwrap_or(false),
             })
@@ -253,25 +801,113 @@ fn parse_alerts(json: Value) -> Vec<Alert> {
 // ============================================
 
 #[tauri::command]
-pub async fn start_monitoring(state: State<'_, AppState>) -> Result<(), String> {
-    let mut is_monitoring = state.is_monitoring.lock().unwrap();
-    
-    if *is_monitoring {
-        return Err("Monitoring is already running".to_string());
+pub async fn start_monitoring(state: State<'_, AppState>) -> Result<(), LifecycleError> {
+    performance::typed_command("start_monitoring", async move {
+        begin_monitoring(&state)
+    })
+    .await
+}
+
+/// Start the capture processes (or have the daemon start them), stepping through the lifecycle
+pub fn begin_monitoring(state: &AppState) -> Result<(), LifecycleError> {
+    state.lifecycle.transition(MonitoringState::Starting)?;
+
+    // A headless daemon owns the capture processes when one is running
+    if daemon::is_daemon_running() {
+        let response = match daemon::daemon_request("start") {
+            Ok(response) => response,
+            Err(e) => {
+                let _ = state.lifecycle.transition(MonitoringState::Stopped);
+                return Err(e.into());
+            }
+        };
+
+        *state.start_time.lock().unwrap() = Some(daemon_start_time(&response));
+        log::info!("Monitoring started via headless daemon");
+
+        state.lifecycle.transition(MonitoringState::Running)?;
+        autostart::remember_monitoring(true);
+        return Ok(());
     }
 
+    let children = match load_settings().and_then(|settings| {
+        let interface = settings.network_interface.unwrap_or_else(platform::default_interface);
+        check_interface(&interface)?.ensure_usable()?;
+        spawn_monitoring_processes(&interface)
+    }) {
+        Ok(children) => children,
+        Err(e) => {
+            proxy_config::stopped();
+            let _ = state.lifecycle.transition(MonitoringState::Stopped);
+            return Err(e.into());
+        }
+    };
+
     let mut processes = state.python_processes.lock().unwrap();
-    let settings = load_settings()?;
-    let interface = settings.network_interface.unwrap_or_else(|| "Wi-Fi".to_string());
+    processes.extend(children);
+    
+    // Update start time
+    let mut start_time = state.start_time.lock().unwrap();
+    *start_time = Some(std::time::Instant::now());
+    
+    log::info!("Monitoring started with {} processes", processes.len());
+
+    state.lifecycle.transition(MonitoringState::Running)?;
+    autostart::remember_monitoring(true);
+
+    Ok(())
+}
 
-    // Start ARP gateway with interface
-    match start_python_script("python/arp/arp_gateway.py", &["--interface", &interface]) {
+/// Spawn the capture processes, killing any already started if one fails
+pub fn spawn_monitoring_processes(interface: &str) -> Result<Vec<Child>, String> {
+    let mut processes = Vec::new();
+
+    let dns_policy = dns_bypass::load_policy().unwrap_or_else(|e| {
+        log::warn!("Failed to load DNS policy, starting without it: {}", e);
+        DnsPolicy::default()
+    });
+
+    // Start ARP gateway with interface, redirecting the selected devices that are intercepted,
+    // but only once forwarding is on so they aren't cut off
+    spoofing::ensure_forwarding(interface)?;
+    let devices = fetch_devices().unwrap_or_default();
+    let target_args = spoofing::arp_args(&devices);
+    let mut arp_args = vec!["--interface", interface];
+    arp_args.extend(target_args.iter().map(|a| a.as_str()));
+    match start_python_script("python/arp/arp_gateway.py", &arp_args) {
         Ok(child) => processes.push(child),
         Err(e) => return Err(format!("Failed to start ARP gateway: {}", e)),
     }
 
     // Start HTTPS proxy
-    match start_python_script("python/https/transparent_proxy.py", &["--action", "start"]) {
+    let proxy_policy = dns_bypass::proxy_args(&dns_policy);
+    let guest_policy = guest::proxy_args(&devices);
+    let bandwidth_policy = bandwidth::proxy_args(&devices);
+    let block_page_policy = block_page::proxy_args();
+    let allow_policy = unblock::proxy_args();
+    let body_policy = body_capture::proxy_args(&load_settings().map(|s| s.body_capture).unwrap_or_default());
+    let metadata_policy = interception::proxy_args(&devices);
+    // Port, CA, upstream proxy and pass-through hosts come from the config file
+    let (proxy_config_path, proxy_ports) = match proxy_config::prepare_start(&devices) {
+        Ok(started) => started,
+        Err(e) => {
+            kill_python_processes(&mut processes);
+            return Err(e);
+        }
+    };
+    let mut proxy_args = vec!["--action", "start", "--config", &proxy_config_path];
+    proxy_args.extend(
+        proxy_policy
+            .iter()
+            .chain(&guest_policy)
+            .chain(&bandwidth_policy)
+            .chain(&block_page_policy)
+            .chain(&allow_policy)
+            .chain(&body_policy)
+            .chain(&metadata_policy)
+            .map(|a| a.as_str()),
+    );
+    match start_python_script("python/https/transparent_proxy.py", &proxy_args) {
         Ok(child) => processes.push(child),
         Err(e) => {
             kill_python_processes(&mut processes);
@@ -280,7 +916,7 @@ pub async fn start_monitoring(state: State<'_, AppState>) -> Result<(), String>
     }
 
     // Start DNS capture with interface
-    match start_python_script("python/dns/dns_capture.py", &["--interface", &interface]) {
+    match start_python_script("python/dns/dns_capture.py", &["--interface", interface]) {
         Ok(child) => processes.push(child),
         Err(e) => {
             kill_python_processes(&mut processes);
@@ -288,53 +924,155 @@ pub async fn start_monitoring(state: State<'_, AppState>) -> Result<(), String>
         }
     }
 
-    *is_monitoring = true;
-    
-    // Update start time
-    let mut start_time = state.start_time.lock().unwrap();
-    *start_time = Some(std::time::Instant::now());
-    
-    log::info!("Monitoring started with {} processes", processes.len());
+    // Start VPN/Tor/proxy bypass detection; capture goes on without it
+    match start_python_script("python/alerts/bypass_detector.py", &["--interface", interface]) {
+        Ok(child) => processes.push(child),
+        Err(e) => log::warn!("Failed to start bypass detector: {}", e),
+    }
 
-    Ok(())
+    // Watch for DHCP servers answering clients; rogue DHCP detection works without it
+    match start_python_script("python/arp/dhcp_watch.py", &["--interface", interface]) {
+        Ok(child) => processes.push(child),
+        Err(e) => log::warn!("Failed to start DHCP watch: {}", e),
+    }
+
+    // Start DNS blocker so DoH/DoT resolver lookups can be denied
+    let blocker_policy = dns_bypass::dns_blocker_args(&dns_policy);
+    let mut blocker_args = vec!["--interface", interface];
+    blocker_args.extend(blocker_policy.iter().map(|a| a.as_str()));
+    match start_python_script("python/dns/dns_blocker.py", &blocker_args) {
+        Ok(child) => processes.push(child),
+        Err(e) => {
+            kill_python_processes(&mut processes);
+            return Err(format!("Failed to start DNS blocker: {}", e));
+        }
+    }
+
+    // Start QUIC blocker so blocked devices fall back to TCP/TLS; capture goes on without it
+    let quic_policy = quic::load_policy().unwrap_or_else(|e| {
+        log::warn!("Failed to load QUIC policy, starting without it: {}", e);
+        QuicPolicy::default()
+    });
+    let quic_policy = quic::blocker_args(&quic_policy);
+    let mut quic_args = vec!["--interface", interface];
+    quic_args.extend(quic_policy.iter().map(|a| a.as_str()));
+    match start_python_script("python/blocking/quic_blocker.py", &quic_args) {
+        Ok(child) => processes.push(child),
+        Err(e) => log::warn!("Failed to start QUIC blocker: {}", e),
+    }
+
+    // Start overhead probe, which measures the proxy through its localhost listener;
+    // it's diagnostic only, so capture goes on without it
+    let probe_port = proxy_ports.probe.to_string();
+    match start_python_script("python/https/overhead_probe.py", &["--proxy-port", &probe_port]) {
+        Ok(child) => processes.push(child),
+        Err(e) => log::warn!("Failed to start overhead probe: {}", e),
+    }
+
+    Ok(processes)
 }
 
 #[tauri::command]
-pub async fn stop_monitoring(state: State<'_, AppState>) -> Result<(), String> {
-    let mut is_monitoring = state.is_monitoring.lock().unwrap();
-    let mut processes = state.python_processes.lock().unwrap();
+pub async fn stop_monitoring(state: State<'_, AppState>) -> Result<(), LifecycleError> {
+    performance::typed_command("stop_monitoring", async move {
+        end_monitoring(&state)
+    })
+    .await
+}
+
+/// When the daemon started monitoring, from the uptime in its response
+fn daemon_start_time(response: &serde_json::Value) -> std::time::Instant {
+    let now = std::time::Instant::now();
+    let uptime = response.get("uptime").and_then(|u| u.as_u64()).unwrap_or(0);
+    now.checked_sub(std::time::Duration::from_secs(uptime)).unwrap_or(now)
+}
+
+/// Show monitoring as running when a daemon launched before the GUI is already monitoring
+pub fn sync_with_daemon(state: &AppState) {
+    let Ok(status) = daemon::daemon_request("status") else {
+        return;
+    };
+    if !status.get("is_running").and_then(|r| r.as_bool()).unwrap_or(false) {
+        return;
+    }
+
+    let adopted = state.lifecycle
+        .transition(MonitoringState::Starting)
+        .and_then(|_| state.lifecycle.transition(MonitoringState::Running));
+    if adopted.is_ok() {
+        *state.start_time.lock().unwrap() = Some(daemon_start_time(&status));
+        autostart::remember_monitoring(true);
+        log::info!("Headless daemon is already monitoring");
+    }
+}
+
+/// Kill the capture processes (and stop the daemon's), stepping through the lifecycle
+pub fn end_monitoring(state: &AppState) -> Result<(), LifecycleError> {
+    state.lifecycle.transition(MonitoringState::Stopping)?;
+
+    {
+        let mut processes = state.python_processes.lock().unwrap();
+        kill_python_processes(&mut processes);
+    }
+    proxy_config::stopped();
 
-    kill_python_processes(&mut processes);
-    *is_monitoring = false;
+    if daemon::is_daemon_running() {
+        if let Err(e) = daemon::daemon_request("stop") {
+            log::warn!("Failed to stop daemon monitoring: {}", e);
+        }
+    }
     
     // Clear start time
     let mut start_time = state.start_time.lock().unwrap();
     *start_time = None;
 
+    state.lifecycle.transition(MonitoringState::Stopped)?;
+    autostart::remember_monitoring(false);
+
     log::info!("Monitoring stopped");
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_status(state: State<'_, AppState>) -> Result<MonitoringStatus, String> {
-    let is_monitoring = state.is_monitoring.lock().unwrap();
+pub async fn get_status(
+    force_refresh: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Snapshot<MonitoringStatus>, String> {
+    performance::command("get_status", async move {
+        state.snapshot.serve_status(app, force_refresh.unwrap_or(false)).await
+    })
+    .await
+}
+
+/// Work out the monitoring status (bypassing the snapshot cache)
+pub fn fetch_status(state: &AppState) -> MonitoringStatus {
+    let lifecycle = state.lifecycle.current();
+    let is_running = lifecycle.is_running();
     let profile = state.current_profile.lock().unwrap();
     let start_time = state.start_time.lock().unwrap();
-    
+
     let uptime = start_time.as_ref()
         .map(|t| t.elapsed().as_secs())
         .unwrap_or(0);
 
-    Ok(MonitoringStatus {
-        is_running: *is_monitoring,
-        arp_spoofing: *is_monitoring,
-        https_proxy: *is_monitoring,
-        dns_capture: *is_monitoring,
+    let mut errors = vec![];
+    if is_running && !platform::is_elevated() {
+        errors.push("Not running with administrator rights; ARP spoofing will fail".to_string());
+    }
+
+    MonitoringStatus {
+        is_running,
+        state: lifecycle,
+        arp_spoofing: is_running,
+        https_proxy: is_running,
+        dns_capture: is_running,
         stealth_mode: true,
         current_profile: profile.clone(),
         uptime,
-        errors: vec![],
-    })
+        errors,
+        proxy_ports: if is_running { proxy_config::bound_ports() } else { None },
+    }
 }
 
 // ============================================
@@ -342,7 +1080,22 @@ pub async fn get_status(state: State<'_, AppState>) -> Result<MonitoringStatus,
 // ============================================
 
 #[tauri::command]
-pub async fn get_devices() -> Result<Vec<Device>, String> {
+pub async fn get_devices(
+    force_refresh: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Snapshot<Vec<Device>>, String> {
+    performance::command("get_devices", async move {
+        state.snapshot.serve_devices(force_refresh.unwrap_or(false)).await
+    })
+    .await
+}
+
+/// Query the device list from the database (bypassing the snapshot cache)
+pub fn fetch_devices() -> Result<Vec<Device>, String> {
+    if let Some(devices) = demo::devices() {
+        return Ok(devices);
+    }
+
     let result = query_database("devices", &[])?;
     
     if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
@@ -353,483 +1106,2662 @@ pub async fn get_devices() -> Result<Vec<Device>, String> {
     }
 }
 
+/// Scan the network for devices as an operation, returning its id
 #[tauri::command]
-pub async fn scan_devices() -> Result<Vec<Device>, String> {
-    let result = run_python_script("python/arp/device_scanner.py", &["--scan"])?;
+pub async fn scan_devices(app: AppHandle) -> Result<String, String> {
+    performance::command("scan_devices", async move {
+        let handle = app.clone();
+        Ok(operations::start(&app, "device_scan", move |_| {
+            refresh_devices(&handle.state::<AppState>()).map(|devices| json!(devices))
+        }))
+    })
+    .await
+}
+
+/// Scan the network for devices and refresh the snapshot with them
+pub fn refresh_devices(state: &AppState) -> Result<Vec<Device>, String> {
+    let mut args = vec!["--scan".to_string()];
+    args.extend(subnets::range_args());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = run_python_script("python/arp/device_scanner.py", &args)?;
     
     if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(parse_devices(result))
+        // Scanned devices carry no id until recorded; the snapshot is rebuilt from the database
+        let scanned = result.get("devices").cloned().unwrap_or_else(|| json!([]));
+        let recorded = inventory::record_scan(&scanned).and_then(|_| fetch_devices());
+        let devices = match recorded {
+            Ok(devices) => devices,
+            Err(e) => {
+                log::warn!("Failed to record scanned devices: {}", e);
+                parse_devices(result)
+            }
+        };
+        state.snapshot.set_devices(devices.clone());
+        Ok(devices)
     } else {
         let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
         Err(error.to_string())
     }
 }
 
+/// Local networks and gateways a device scan covers, with the manual ranges
 #[tauri::command]
-pub async fn set_device_monitoring(device_id: String, enabled: bool) -> Result<(), String> {
-    log::info!("Set device {} monitoring to {}", device_id, enabled);
-    
-    let enabled_str = if enabled { "1" } else { "0" };
-    let result = run_python_script(
-        "python/database/db_manager.py",
-        &["--action", "update-device", "--device", &device_id, "--monitored", enabled_str]
-    )?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn get_detected_networks() -> Result<NetworkDetection, String> {
+    performance::command("get_detected_networks", async move {
+        subnets::detect()
+    })
+    .await
 }
 
-// ============================================
-// Traffic Commands
-// ============================================
+/// Scan a CIDR range besides the detected networks; returns the manual ranges
+#[tauri::command]
+pub async fn add_scan_range(range: String) -> Result<Vec<String>, String> {
+    performance::command("add_scan_range", async move {
+        subnets::add_range(&range)
+    })
+    .await
+}
 
 #[tauri::command]
-pub async fn get_traffic(
-    limit: Option<u32>,
-    offset: Option<u32>,
-    device_id: Option<String>,
-) -> Result<Vec<TrafficEntry>, String> {
-    let mut args: Vec<(&str, String)> = vec![
-        ("--limit", limit.unwrap_or(100).to_string()),
-    ];
-    
-    if let Some(ref did) = device_id {
-        args.push(("--device", did.clone()));
-    }
-    
-    let args_refs: Vec<(&str, &str)> = args.iter().map(|(k, v)| (*k, v.as_str())).collect();
-    let result = query_database("traffic", &args_refs)?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(parse_traffic(result))
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn remove_scan_range(range: String) -> Result<Vec<String>, String> {
+    performance::command("remove_scan_range", async move {
+        subnets::remove_range(&range)
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn search_traffic(query: String) -> Result<Vec<TrafficEntry>, String> {
-    log::info!("Searching traffic for: {}", query);
-    
-    let result = query_database("search", &[("--query", &query)])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        // Search results are in "results" not "traffic"
-        if let Some(results) = result.get("results").and_then(|r| r.as_array()) {
-            let traffic: Vec<TrafficEntry> = results.iter().filter_map(|t| {
-                Some(TrafficEntry {
-                    id: t.get("id")?.as_str()?.to_string(),
-                    timestamp: t.get("timestamp")?.as_str()?.to_string(),
-                    device_id: t.get("device_id").and_then(|d| d.as_str()).map(|s| s.to_string()),
-                    device_ip: t.get("device_ip").and_then(|d| d.as_str()).unwrap_or("").to_string(),
-                    method: t.get("method").and_then(|m| m.as_str()).unwrap_or("GET").to_string(),
-                    url: t.get("url").and_then(|u| u.as_str()).unwrap_or("").to_string(),
-                    host: t.get("host").and_then(|h| h.as_str()).unwrap_or("").to_string(),
-                    path: t.get("path").and_then(|p| p.as_str()).map(|s| s.to_string()),
-                    status_code: t.get("status_code").and_then(|s| s.as_u64()).map(|n| n as u16),
-                    content_type: t.get("content_type").and_then(|c| c.as_str()).map(|s| s.to_string()),
-                    request_size: t.get("request_size").and_then(|n| n.as_u64()).unwrap_or(0),
-                    response_size: t.get("response_size").and_then(|n| n.as_u64()).unwrap_or(0),
-                    duration: t.get("duration_ms").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
-                    is_blocked: t.get("blocked").and_then(|b| b.as_bool()).unwrap_or(false),
-                    has_alert: t.get("alerts").and_then(|a| a.as_array()).map(|a| !a.is_empty()).unwrap_or(false),
-                    category: t.get("category").and_then(|c| c.as_str()).map(|s| s.to_string()),
-                })
-            }).collect();
-            Ok(traffic)
-        } else {
-            Ok(vec![])
-        }
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn get_network_topology(state: State<'_, AppState>) -> Result<NetworkTopology, String> {
+    performance::command("get_network_topology", async move {
+        let devices = state.snapshot.serve_devices(false).await?.data;
+        tauri::async_runtime::spawn_blocking(move || topology::build_topology(&devices))
+            .await
+            .map_err(|e| format!("Failed to build network topology: {}", e))
+    })
+    .await
 }
 
+/// Nearby Wi-Fi networks with channel congestion and recommendations
 #[tauri::command]
-pub async fn get_traffic_details(entry_id: String) -> Result<TrafficEntry, String> {
-    let result = run_python_script(
-        "python/database/db_manager.py",
-        &["--action", "get-traffic", "--id", &entry_id]
-    )?;
+pub async fn get_wifi_environment() -> Result<WifiEnvironment, String> {
+    performance::command("get_wifi_environment", async move {
+        log::info!("Scanning Wi-Fi environment");
+        wifi::scan()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_app_usage(
+    device_id: Option<String>,
+    range: Option<UsageRange>,
+) -> Result<Vec<AppUsage>, String> {
+    performance::command("get_app_usage", async move {
+        apps::app_usage(device_id.as_deref(), range.unwrap_or_default())
+    })
+    .await
+}
+
+/// Diff a device's domains, apps and bandwidth in period A against period B
+#[tauri::command]
+pub async fn compare_device_activity(
+    device_id: String,
+    period_a: Period,
+    period_b: Period,
+) -> Result<ActivityComparison, String> {
+    performance::command("compare_device_activity", async move {
+        activity::compare(&device_id, &period_a, &period_b)
+    })
+    .await
+}
+
+/// Requests and bandwidth by day and hour, for a device's (or every device's) activity heatmap
+#[tauri::command]
+pub async fn get_activity_heatmap(
+    device_id: Option<String>,
+    range: Option<UsageRange>,
+) -> Result<ActivityHeatmap, String> {
+    performance::command("get_activity_heatmap", async move {
+        heatmap::heatmap(device_id.as_deref(), range.unwrap_or(UsageRange::Last7Days))
+    })
+    .await
+}
+
+/// A device's requests grouped into browsing sessions, newest first
+#[tauri::command]
+pub async fn get_device_sessions(
+    device_id: String,
+    range: Option<UsageRange>,
+) -> Result<DeviceSessions, String> {
+    performance::command("get_device_sessions", async move {
+        sessions::sessions(&device_id, range.unwrap_or_default())
+    })
+    .await
+}
+
+/// The services a device advertises over mDNS and SSDP
+#[tauri::command]
+pub async fn get_device_services(device_id: String, state: State<'_, AppState>) -> Result<DeviceServices, String> {
+    performance::command("get_device_services", async move {
+        let device = state
+            .snapshot
+            .devices(false)?
+            .data
+            .into_iter()
+            .find(|d| d.id == device_id)
+            .ok_or_else(|| format!("Device not found: {}", device_id))?;
+        Ok(discovery::device_services(&device))
+    })
+    .await
+}
+
+/// Known vulnerabilities hinted at by the model and firmware a device advertises
+#[tauri::command]
+pub async fn get_device_advisories(device_id: String, state: State<'_, AppState>) -> Result<DeviceAdvisories, String> {
+    performance::command("get_device_advisories", async move {
+        let device = state
+            .snapshot
+            .devices(false)?
+            .data
+            .into_iter()
+            .find(|d| d.id == device_id)
+            .ok_or_else(|| format!("Device not found: {}", device_id))?;
+        advisories::device_advisories(&device)
+    })
+    .await
+}
+
+/// New devices, address, name and vendor changes and long absences of a device, newest first
+#[tauri::command]
+pub async fn get_device_changelog(device_id: String) -> Result<DeviceChangelog, String> {
+    performance::command("get_device_changelog", async move {
+        inventory::changelog(&device_id)
+    })
+    .await
+}
+
+/// Device inventory changes across the network in a range
+#[tauri::command]
+pub async fn get_inventory_changes(range: Option<UsageRange>) -> Result<InventoryChanges, String> {
+    performance::command("get_inventory_changes", async move {
+        inventory::changes(range.unwrap_or_default())
+    })
+    .await
+}
+
+/// IP conflicts and flapping leases in the last `hours` (default a week), with suggested DHCP reservations
+#[tauri::command]
+pub async fn get_ip_conflicts(hours: Option<u32>, state: State<'_, AppState>) -> Result<IpConflictReport, String> {
+    performance::command("get_ip_conflicts", async move {
+        let devices = state.snapshot.devices(false)?.data;
+        ip_conflicts::report(&devices, hours.unwrap_or(168))
+    })
+    .await
+}
+
+/// Captured image, video and audio responses, newest first
+#[tauri::command]
+pub async fn get_media_entries(
+    device_id: Option<String>,
+    range: Option<UsageRange>,
+    media_type: Option<MediaType>,
+) -> Result<Vec<MediaEntry>, String> {
+    performance::command("get_media_entries", async move {
+        media::entries(device_id.as_deref(), range.unwrap_or_default(), media_type)
+    })
+    .await
+}
+
+/// JPEG bytes of a media entry's thumbnail
+#[tauri::command]
+pub async fn get_media_thumbnail(key: String) -> Result<tauri::ipc::Response, String> {
+    performance::bytes_command("get_media_thumbnail", async move { media::thumbnail(&key) })
+        .await
+        .map(tauri::ipc::Response::new)
+}
+
+/// Tracker companies a device (or every device) contacted in a range, by request count
+#[tauri::command]
+pub async fn get_tracker_report(
+    device_id: Option<String>,
+    range: Option<UsageRange>,
+) -> Result<TrackerReport, String> {
+    performance::command("get_tracker_report", async move {
+        trackers::report(device_id.as_deref(), range.unwrap_or_default())
+    })
+    .await
+}
+
+/// What each smart TV and IoT device contacted in a range, and how much of it while idle
+#[tauri::command]
+pub async fn get_iot_phone_home_report(
+    range: Option<UsageRange>,
+    state: State<'_, AppState>,
+) -> Result<PhoneHomeReport, String> {
+    performance::command("get_iot_phone_home_report", async move {
+        let devices = state.snapshot.devices(false)?.data;
+        phone_home::report(&devices, range.unwrap_or_default())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_app_limit(
+    device_id: String,
+    target: String,
+    daily_minutes: Option<u32>,
+) -> Result<(), String> {
+    performance::command("set_app_limit", async move {
+        log::info!("Set app limit for {} on {}: {:?} minutes", target, device_id, daily_minutes);
+        app_limits::set_limit(&device_id, &target, daily_minutes)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_app_limits_status(device_id: Option<String>) -> Result<Vec<AppLimitStatus>, String> {
+    performance::command("get_app_limits_status", async move {
+        app_limits::limits_status(device_id.as_deref())
+    })
+    .await
+}
+
+/// Choose whether a device is fully intercepted, recorded as metadata only, or left alone
+#[tauri::command]
+pub async fn set_device_interception_mode(
+    device_id: String,
+    mode: InterceptionMode,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("set_device_interception_mode", async move {
+        interception::set_mode(&state, &device_id, mode)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_spoof_targets(state: State<'_, AppState>) -> Result<SpoofTargets, String> {
+    performance::command("get_spoof_targets", async move {
+        spoofing::status(&state)
+    })
+    .await
+}
+
+/// Redirect only these devices (every intercepted device when None); the gateway and routers never are
+#[tauri::command]
+pub async fn set_spoof_targets(
+    device_ids: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<SpoofTargets, String> {
+    performance::command("set_spoof_targets", async move {
+        spoofing::set_targets(&state, device_ids)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_device_monitoring(device_id: String, enabled: bool) -> Result<(), String> {
+    performance::command("set_device_monitoring", async move {
+        log::info!("Set device {} monitoring to {}", device_id, enabled);
+    
+        let enabled_str = if enabled { "1" } else { "0" };
+        let result = db_writer::write_async(
+            "python/database/db_manager.py",
+            &["--action", "update-device", "--device", &device_id, "--monitored", enabled_str]
+        ).await?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+/// The current OS user's names, notes and tags for devices, by device ID
+#[tauri::command]
+pub async fn get_device_metadata() -> Result<BTreeMap<String, DeviceMetadata>, String> {
+    performance::command("get_device_metadata", async move {
+        users::load_device_metadata()
+    })
+    .await
+}
+
+/// Set the current OS user's metadata for a device (None clears it)
+#[tauri::command]
+pub async fn set_device_metadata(device_id: String, metadata: Option<DeviceMetadata>) -> Result<(), String> {
+    performance::command("set_device_metadata", async move {
+        log::info!("Set metadata of device {}", device_id);
+        users::set_device_metadata(&device_id, metadata)
+    })
+    .await
+}
+
+// ============================================
+// Traffic Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_traffic(
+    limit: Option<u32>,
+    offset: Option<u32>,
+    device_id: Option<String>,
+) -> Result<Vec<TrafficEntry>, String> {
+    performance::command("get_traffic", async move {
+        if let Some(entries) = demo::traffic(limit.unwrap_or(100) as usize, offset.unwrap_or(0) as usize, device_id.as_deref()) {
+            return Ok(entries);
+        }
+
+        let mut args: Vec<(&str, String)> = vec![
+            ("--limit", limit.unwrap_or(100).to_string()),
+        ];
+    
+        if let Some(ref did) = device_id {
+            args.push(("--device", did.clone()));
+        }
+    
+        let args_refs: Vec<(&str, &str)> = args.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+        // Rows are parsed as they stream in rather than from one large JSON document
+        let zone = Zone::configured();
+        let mut entries = Vec::new();
+        query_database_stream("traffic", &args_refs, |record| {
+            entries.extend(traffic_from_json(&record, zone));
+            Ok(())
+        })?;
+
+        Ok(entries)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn search_traffic(query: String, limit: Option<u32>) -> Result<Vec<TrafficEntry>, String> {
+    performance::command("search_traffic", async move {
+        log::info!("Searching traffic for: {}", query);
+
+        if let Some(entries) = demo::search_traffic(&query, limit.unwrap_or(100) as usize) {
+            return Ok(entries);
+        }
+
+        let conn = database::open_readonly()?;
+        let zone = Zone::configured();
+        let mut entries = database::search_traffic(&conn, &query, limit.unwrap_or(100))?;
+        for entry in &mut entries {
+            entry.timestamp = zone.display(&entry.timestamp);
+        }
+        Ok(entries)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rebuild_search_index() -> Result<u64, String> {
+    performance::command("rebuild_search_index", async move {
+        log::info!("Rebuilding traffic search index");
+
+        match aggregation::rebuild_search_index()? {
+            Some(indexed) => Ok(indexed),
+            None => {
+                log::info!("SQLite has no trigram tokenizer, search uses LIKE");
+                Ok(0)
+            }
+        }
+    })
+    .await
+}
+
+/// The current OS user's saved searches
+#[tauri::command]
+pub async fn get_saved_searches() -> Result<Vec<SavedSearch>, String> {
+    performance::command("get_saved_searches", async move {
+        users::load_saved_searches()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn save_search(name: String, query: String) -> Result<SavedSearch, String> {
+    performance::command("save_search", async move {
+        log::info!("Saving search '{}'", name);
+        users::save_search(&name, &query)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_saved_search(name: String) -> Result<(), String> {
+    performance::command("delete_saved_search", async move {
+        log::info!("Deleting saved search '{}'", name);
+        users::delete_search(&name)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_traffic_details(entry_id: String) -> Result<TrafficEntry, String> {
+    performance::command("get_traffic_details", async move {
+        if let Some(entry) = demo::traffic_entry(&entry_id) {
+            return entry.ok_or_else(|| "Traffic entry not found".to_string());
+        }
+
+        let result = run_python_script(
+            "python/database/db_manager.py",
+            &["--action", "get-traffic", "--id", &entry_id]
+        )?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            let entries = parse_traffic(result);
+            entries.into_iter().next().ok_or_else(|| "Traffic entry not found".to_string())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+/// Replace a traffic entry's tags, returning them as stored
+#[tauri::command]
+pub async fn tag_traffic_entry(id: String, tags: Vec<String>) -> Result<Vec<String>, String> {
+    performance::command("tag_traffic_entry", async move {
+        annotations::set_tags(&id, &tags)
+    })
+    .await
+}
+
+/// Set a traffic entry's note; an empty note removes it
+#[tauri::command]
+pub async fn annotate_entry(id: String, note: String) -> Result<(), String> {
+    performance::command("annotate_entry", async move {
+        annotations::set_note(&id, &note)
+    })
+    .await
+}
+
+/// Devices, timeline, categories, threat hits and resolved IPs for a domain and its subdomains
+#[tauri::command]
+pub async fn get_domain_details(domain: String) -> Result<DomainDetails, String> {
+    performance::command("get_domain_details", async move {
+        domains::details(&domain)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_dns_queries(limit: Option<u32>, device_id: Option<String>) -> Result<Vec<DnsQuery>, String> {
+    performance::command("get_dns_queries", async move {
+        let limit = limit.unwrap_or(100);
+        if let Some(queries) = demo::dns_queries(limit as usize, device_id.as_deref()) {
+            return Ok(queries);
+        }
+
+        let limit = limit.to_string();
+        let mut args = vec![("--limit", limit.as_str())];
+        if let Some(ref did) = device_id {
+            args.push(("--device", did.as_str()));
+        }
+
+        let result = query_database("dns", &args)?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(parse_dns_queries(result))
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn subscribe_traffic(
+    filter: Option<TrafficFilter>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    performance::command("subscribe_traffic", async move {
+        let filter = filter.unwrap_or_default();
+        let id = format!("sub-{}", state.next_subscription_id.fetch_add(1, Ordering::SeqCst) + 1);
+
+        log::info!("Starting traffic subscription {} with filter: {:?}", id, filter);
+
+        let task = spawn_traffic_subscription(app, id.clone(), filter);
+        let mut subscriptions = state.traffic_subscriptions.lock().unwrap();
+        subscriptions.insert(id.clone(), task);
+
+        Ok(id)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn unsubscribe_traffic(
+    subscription_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("unsubscribe_traffic", async move {
+        let mut subscriptions = state.traffic_subscriptions.lock().unwrap();
+
+        match subscriptions.remove(&subscription_id) {
+            Some(task) => {
+                task.abort();
+                log::info!("Stopped traffic subscription {}", subscription_id);
+                Ok(())
+            }
+            None => Err(format!("Unknown subscription: {}", subscription_id)),
+        }
+    })
+    .await
+}
+
+// ============================================
+// Alert Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_alerts(unread_only: Option<bool>) -> Result<Vec<Alert>, String> {
+    performance::command("get_alerts", async move {
+        if let Some(mut alerts) = demo::alerts() {
+            if unread_only.unwrap_or(false) {
+                alerts.retain(|a| !a.is_read);
+            }
+            return Ok(alerts);
+        }
+
+        let result = run_alert_command("list", &[])?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            let mut alerts = parse_alerts(result);
+        
+            // Filter unread if requested
+            if unread_only.unwrap_or(false) {
+                alerts.retain(|a| !a.is_read);
+            }
+        
+            Ok(alerts)
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn mark_alert_read(alert_id: String) -> Result<(), String> {
+    performance::command("mark_alert_read", async move {
+        log::info!("Marking alert as read: {}", alert_id);
+    
+        if let Some(result) = demo::mark_read(Some(&alert_id)) {
+            return result;
+        }
+    
+        let result = run_alert_command("acknowledge", &[("--id", &alert_id)])?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn resolve_alert(alert_id: String) -> Result<(), String> {
+    performance::command("resolve_alert", async move {
+        log::info!("Resolving alert: {}", alert_id);
+    
+        let result = run_alert_command("acknowledge", &[("--id", &alert_id)])?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_alert(alert_id: String) -> Result<(), String> {
+    performance::command("delete_alert", async move {
+        log::info!("Deleting alert: {}", alert_id);
+    
+        let result = run_alert_command("delete", &[("--id", &alert_id)])?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            alert_evidence::delete(&alert_id)
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+/// Traffic, DNS queries and screenshot captured when an alert fired
+#[tauri::command]
+pub async fn get_alert_evidence(alert_id: String) -> Result<AlertEvidence, String> {
+    performance::command("get_alert_evidence", async move {
+        alert_evidence::get(&alert_id)
+    })
+    .await
+}
+
+/// PNG rendering of the page an alert was about
+#[tauri::command]
+pub async fn get_alert_screenshot(alert_id: String) -> Result<tauri::ipc::Response, String> {
+    performance::bytes_command("get_alert_screenshot", async move { alert_evidence::screenshot(&alert_id) })
+        .await
+        .map(tauri::ipc::Response::new)
+}
+
+/// Correlated alert groups, most recently active first
+#[tauri::command]
+pub async fn get_incidents(status: Option<String>, device_id: Option<String>) -> Result<Vec<Incident>, String> {
+    performance::command("get_incidents", async move {
+        let mut args = vec![];
+        if let Some(status) = status.as_deref() {
+            args.push(("--status", status));
+        }
+        if let Some(device) = device_id.as_deref() {
+            args.push(("--device", device));
+        }
+
+        let result = run_alert_command("incidents", &args)?;
+
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            let incidents = result.get("incidents").cloned().unwrap_or(Value::Array(vec![]));
+            serde_json::from_value(incidents).map_err(|e| format!("Failed to parse incidents: {}", e))
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_incident_details(incident_id: String) -> Result<IncidentDetails, String> {
+    performance::command("get_incident_details", async move {
+        let result = run_alert_command("incident", &[("--id", &incident_id)])?;
+
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            let details = result.get("incident").cloned().unwrap_or(Value::Null);
+            let incident: Incident = serde_json::from_value(details.clone())
+                .map_err(|e| format!("Failed to parse incident: {}", e))?;
+            Ok(IncidentDetails {
+                incident,
+                timeline: parse_alerts(details),
+            })
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+/// Resolve an incident, acknowledging all of its alerts
+#[tauri::command]
+pub async fn resolve_incident(incident_id: String) -> Result<(), String> {
+    performance::command("resolve_incident", async move {
+        log::info!("Resolving incident: {}", incident_id);
+
+        let result = run_alert_command("resolve-incident", &[("--id", &incident_id)])?;
+
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn mark_all_alerts_read() -> Result<(), String> {
+    performance::command("mark_all_alerts_read", async move {
+        if let Some(result) = demo::mark_read(None) {
+            return result;
+        }
+    
+        let result = run_alert_command("acknowledge-all", &[])?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+/// Per-severity alert sounds and quiet hours, as the notifier applies them
+#[tauri::command]
+pub async fn get_notification_settings() -> Result<NotificationSettings, String> {
+    performance::command("get_notification_settings", async move {
+        notifications::load()
+    })
+    .await
+}
+
+/// Only notify critical alerts between `start` and `end` (11pm to 7am by default)
+#[tauri::command]
+pub async fn set_quiet_hours(
+    enabled: bool,
+    start: Option<u32>,
+    end: Option<u32>,
+) -> Result<NotificationSettings, String> {
+    performance::command("set_quiet_hours", async move {
+        log::info!("Set quiet hours to {} ({:?}-{:?})", enabled, start, end);
+        notifications::set_quiet_hours(enabled, start, end)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_alert_sound(severity: String, sound: AlertSound) -> Result<NotificationSettings, String> {
+    performance::command("set_alert_sound", async move {
+        log::info!("Set {} alert sound (enabled: {})", severity, sound.enabled);
+        notifications::set_alert_sound(&severity, &sound)
+    })
+    .await
+}
+
+/// Play the sound a severity's alerts make, even during quiet hours
+#[tauri::command]
+pub async fn preview_alert_sound(severity: String) -> Result<SoundPreview, String> {
+    performance::command("preview_alert_sound", async move {
+        notifications::preview(&severity)
+    })
+    .await
+}
+
+/// Keyword watch lists and the device groups they apply to
+#[tauri::command]
+pub async fn get_watch_lists() -> Result<WatchListConfig, String> {
+    performance::command("get_watch_lists", async move {
+        watch_lists::load_config()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_watch_lists(config: WatchListConfig) -> Result<WatchListConfig, String> {
+    performance::command("set_watch_lists", async move {
+        log::info!("Updating watch lists: {} lists, {} groups", config.lists.len(), config.groups.len());
+        watch_lists::set_config(&config)
+    })
+    .await
+}
+
+/// Watch-list matches with their context, newest first
+#[tauri::command]
+pub async fn get_watch_matches(
+    list_id: Option<String>,
+    device_id: Option<String>,
+    range: Option<UsageRange>,
+) -> Result<Vec<WatchMatch>, String> {
+    performance::command("get_watch_matches", async move {
+        watch_lists::matches(list_id.as_deref(), device_id.as_deref(), range.unwrap_or(UsageRange::Last7Days))
+    })
+    .await
+}
+
+/// Watch-list matches per list, device, term and day (the last week by default)
+#[tauri::command]
+pub async fn get_watch_list_rollup(range: Option<UsageRange>) -> Result<WatchRollup, String> {
+    performance::command("get_watch_list_rollup", async move {
+        watch_lists::rollup(range.unwrap_or(UsageRange::Last7Days))
+    })
+    .await
+}
+
+// ============================================
+// Network Security Commands
+// ============================================
+
+#[tauri::command]
+pub async fn detect_dhcp_servers(
+    timeout_secs: Option<u64>,
+    app: AppHandle,
+) -> Result<DhcpScanResult, String> {
+    performance::command("detect_dhcp_servers", async move {
+        let settings = load_settings()?;
+        let timeout = std::time::Duration::from_secs(timeout_secs.unwrap_or(5).clamp(1, 30));
+
+        log::info!("Probing for DHCP servers ({}s)", timeout.as_secs());
+        // The probe listens for the whole timeout
+        tauri::async_runtime::spawn_blocking(move || {
+            app.state::<AppState>().dhcp.scan(timeout, &settings.trusted_dhcp_servers)
+        })
+        .await
+        .map_err(|e| format!("DHCP probe failed: {}", e))?
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_bypass_detections(
+    device_id: Option<String>,
+    hours: Option<u32>,
+    limit: Option<u32>,
+) -> Result<Vec<BypassDetection>, String> {
+    performance::command("get_bypass_detections", async move {
+        let mut args: Vec<(&str, String)> = vec![
+            ("--limit", limit.unwrap_or(100).to_string()),
+            ("--hours", hours.unwrap_or(24).to_string()),
+        ];
+
+        if let Some(ref did) = device_id {
+            args.push(("--device", did.clone()));
+        }
+
+        let args_refs: Vec<(&str, &str)> = args.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        let result = query_database("bypass-detections", &args_refs)?;
+
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            let detections = result.get("detections").cloned().unwrap_or(Value::Array(vec![]));
+            serde_json::from_value(detections)
+                .map_err(|e| format!("Failed to parse bypass detections: {}", e))
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn update_tor_relay_list() -> Result<u64, String> {
+    performance::command("update_tor_relay_list", async move {
+        let result = run_python_script("python/alerts/bypass_detector.py", &["--action", "update-tor-list"])?;
+
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(result.get("relays").and_then(|r| r.as_u64()).unwrap_or(0))
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+/// Beacon-like (regular, small, repeated) traffic per device and host, most confident first
+#[tauri::command]
+pub async fn detect_beaconing(device_id: Option<String>, hours: Option<u32>) -> Result<Vec<BeaconCandidate>, String> {
+    performance::command("detect_beaconing", async move {
+        beaconing::detect(device_id.as_deref(), hours.unwrap_or(24))
+    })
+    .await
+}
+
+/// Per-domain DNS tunneling scores for lookups in the last `hours` (default 24)
+#[tauri::command]
+pub async fn get_dns_anomaly_report(
+    device_id: Option<String>,
+    hours: Option<u32>,
+) -> Result<DnsAnomalyReport, String> {
+    performance::command("get_dns_anomaly_report", async move {
+        dns_tunneling::report(device_id.as_deref(), hours.unwrap_or(24))
+    })
+    .await
+}
+
+/// Likely BitTorrent/P2P file sharing per device in the last `hours` (default 24)
+#[tauri::command]
+pub async fn get_p2p_activity(
+    device_id: Option<String>,
+    hours: Option<u32>,
+) -> Result<P2pActivityReport, String> {
+    performance::command("get_p2p_activity", async move {
+        p2p::report(device_id.as_deref(), hours.unwrap_or(24))
+    })
+    .await
+}
+
+/// TLS versions, weak ciphers, bad certificates and legacy-TLS devices seen in a range
+#[tauri::command]
+pub async fn get_tls_report(range: Option<UsageRange>) -> Result<TlsReport, String> {
+    performance::command("get_tls_report", async move {
+        tls_report::report(range.unwrap_or_default())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_credential_exposures(
+    device_id: Option<String>,
+    hours: Option<u32>,
+) -> Result<Vec<CredentialExposure>, String> {
+    performance::command("get_credential_exposures", async move {
+        credential_exposure::report(device_id.as_deref(), hours.unwrap_or(24))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_credential_exposure_config() -> Result<CredentialExposureConfig, String> {
+    performance::command("get_credential_exposure_config", async move {
+        credential_exposure::load_config()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_credential_exposure_config(
+    config: CredentialExposureConfig,
+) -> Result<CredentialExposureConfig, String> {
+    performance::command("set_credential_exposure_config", async move {
+        log::info!("Updating credential exposure config: {:?}", config);
+        credential_exposure::set_config(&config)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_threat_feeds() -> Result<Value, String> {
+    performance::command("get_threat_feeds", async move {
+        threat_intel::feeds()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn update_threat_feeds(force: Option<bool>) -> Result<Value, String> {
+    performance::command("update_threat_feeds", async move {
+        threat_intel::update_feeds(force.unwrap_or(true))
+    })
+    .await
+}
+
+/// Match stored history (default: last 30 days) against the threat feeds, alerting on hits
+#[tauri::command]
+pub async fn rescan_history_against_feeds(days: Option<u32>) -> Result<Value, String> {
+    performance::command("rescan_history_against_feeds", async move {
+        log::info!("Rescanning history against threat feeds");
+        threat_intel::rescan_history(days.unwrap_or(30))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_dhcp_servers(state: State<'_, AppState>) -> Result<Vec<DhcpServer>, String> {
+    performance::command("get_dhcp_servers", async move {
+        Ok(state.dhcp.servers())
+    })
+    .await
+}
+
+// ============================================
+// Stats Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_stats(
+    force_refresh: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Snapshot<DashboardStats>, String> {
+    performance::command("get_stats", async move {
+        state.snapshot.serve_stats(app, force_refresh.unwrap_or(false)).await
+    })
+    .await
+}
+
+/// Recount the dashboard stats from the database, repairing a view that drifted
+#[tauri::command]
+pub async fn rebuild_stats(state: State<'_, AppState>) -> Result<DashboardStats, String> {
+    performance::command("rebuild_stats", async move {
+        let devices = state.snapshot.devices(false)?.data;
+        let stats = stats_cache::rebuild(&devices)?;
+        state.snapshot.set_stats(stats.clone());
+        Ok(stats)
+    })
+    .await
+}
+
+/// Traffic volume by protocol from passive capture, including what the proxy never saw
+#[tauri::command]
+pub async fn get_protocol_breakdown(
+    range: Option<UsageRange>,
+    device_id: Option<String>,
+) -> Result<ProtocolBreakdown, String> {
+    performance::command("get_protocol_breakdown", async move {
+        protocols::breakdown(device_id.as_deref(), range.unwrap_or_default())
+    })
+    .await
+}
+
+/// Estimated cost of a range on a metered connection (the billing cycle so far by default)
+/// and the usage projected for the end of the cycle
+#[tauri::command]
+pub async fn get_usage_cost(range: Option<UsageRange>) -> Result<UsageCost, String> {
+    performance::command("get_usage_cost", async move {
+        usage_cost::usage_cost(range)
+    })
+    .await
+}
+
+/// Connection-level flows from passive capture, including non-web traffic
+#[tauri::command]
+pub async fn get_flows(device_id: Option<String>, range: Option<UsageRange>) -> Result<FlowLog, String> {
+    performance::command("get_flows", async move {
+        flows::flows(device_id.as_deref(), range.unwrap_or_default())
+    })
+    .await
+}
+
+/// Latency the proxy adds to requests, from the overhead probe's last `hours` of samples
+#[tauri::command]
+pub async fn get_overhead_metrics(hours: Option<u32>) -> Result<OverheadMetrics, String> {
+    performance::command("get_overhead_metrics", async move {
+        overhead::metrics(hours.unwrap_or(24))
+    })
+    .await
+}
+
+/// Recompute dashboard stats in Python over the whole database (bypassing the snapshot and the stats view)
+pub fn fetch_stats() -> Result<DashboardStats, String> {
+    if let Some(stats) = demo::stats() {
+        return Ok(stats);
+    }
+
+    let result = query_database("stats", &[])?;
+    
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let stats = result.get("stats").unwrap_or(&result);
+        
+        // Parse top domains
+        let top_domains: Vec<TopDomain> = if let Some(domains) = stats.get("top_domains").and_then(|d| d.as_object()) {
+            domains.iter().map(|(k, v)| TopDomain {
+                domain: k.clone(),
+                count: v.as_u64().unwrap_or(0),
+            }).collect()
+        } else {
+            vec![]
+        };
+        
+        let zone = Zone::configured();
+        let traffic_by_hour: Vec<HourlyTraffic> = stats
+            .get("traffic_by_hour")
+            .and_then(|h| h.as_array())
+            .map(|hours| {
+                hours
+                    .iter()
+                    .filter_map(|h| {
+                        Some(HourlyTraffic {
+                            hour: h.get("hour")?.as_u64()? as u32,
+                            start: zone.display(h.get("start")?.as_str()?),
+                            requests: h.get("requests").and_then(|n| n.as_u64()).unwrap_or(0),
+                            bytes_in: h.get("bytes_in").and_then(|n| n.as_u64()).unwrap_or(0),
+                            bytes_out: h.get("bytes_out").and_then(|n| n.as_u64()).unwrap_or(0),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let bytes_in = stats.get("bytes_in").and_then(|n| n.as_u64()).unwrap_or(0);
+        let bytes_out = stats.get("bytes_out").and_then(|n| n.as_u64()).unwrap_or(0);
+        Ok(DashboardStats {
+            total_devices: stats.get("device_count").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
+            online_devices: stats.get("online_devices").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
+            total_requests: stats.get("traffic_count").and_then(|n| n.as_u64()).unwrap_or(0),
+            blocked_requests: stats.get("blocked_count").and_then(|n| n.as_u64()).unwrap_or(0),
+            total_alerts: stats.get("alert_count").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
+            unresolved_alerts: stats.get("unresolved_alerts").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
+            total_bandwidth: bytes_in + bytes_out,
+            bytes_in,
+            bytes_out,
+            top_domains,
+            traffic_by_hour,
+        })
+    } else {
+        // Return empty stats on error (database might not exist yet)
+        Ok(DashboardStats {
+            total_devices: 0,
+            online_devices: 0,
+            total_requests: 0,
+            blocked_requests: 0,
+            total_alerts: 0,
+            unresolved_alerts: 0,
+            total_bandwidth: 0,
+            bytes_in: 0,
+            bytes_out: 0,
+            top_domains: vec![],
+            traffic_by_hour: vec![],
+        })
+    }
+}
+
+// ============================================
+// Blocking Commands
+// ============================================
+
+/// Add a block (or "allow") rule, expiring after `duration` minutes when given
+#[tauri::command]
+pub async fn add_block_rule(rule_type: String, value: String, duration: Option<u32>) -> Result<(), String> {
+    performance::command("add_block_rule", async move {
+        log::info!("Adding block rule: {} - {} (duration: {:?} min)", rule_type, value, duration);
+    
+        let action = match rule_type.as_str() {
+            "domain" => "block",
+            "category" => "block-category",
+            "keyword" => "add-keyword",
+            "allow" => "whitelist",
+            _ => return Err(t("error.unknown_rule_type", &[("rule_type", &rule_type)])),
+        };
+    
+        let arg_name = match rule_type.as_str() {
+            "domain" => "--domain",
+            "category" => "--category",
+            "keyword" => "--keyword",
+            _ => "--domain",
+        };
+    
+        let duration = duration.map(|d| d.to_string());
+        let mut args = vec![(arg_name, value.as_str())];
+        if let Some(ref minutes) = duration {
+            args.push(("--duration", minutes));
+        }
+    
+        let result = run_blocking_command(action, &args)?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_block_rule(rule_type: String, value: String) -> Result<(), String> {
+    performance::command("remove_block_rule", async move {
+        log::info!("Removing block rule: {} - {}", rule_type, value);
+    
+        let action = match rule_type.as_str() {
+            "domain" => "unblock",
+            "category" => "unblock-category",
+            "keyword" => "remove-keyword",
+            "allow" => "remove-whitelist",
+            _ => return Err(t("error.unknown_rule_type", &[("rule_type", &rule_type)])),
+        };
+    
+        let arg_name = match rule_type.as_str() {
+            "domain" => "--domain",
+            "category" => "--category",
+            "keyword" => "--keyword",
+            _ => "--domain",
+        };
+    
+        let result = run_blocking_command(action, &[(arg_name, &value)])?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn toggle_category(category_id: String, enabled: bool) -> Result<(), String> {
+    performance::command("toggle_category", async move {
+        log::info!("Toggle category {} to {}", category_id, enabled);
+    
+        let action = if enabled { "block-category" } else { "unblock-category" };
+        let result = run_blocking_command(action, &[("--category", &category_id)])?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(())
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+/// Push a live policy change to every running capture process
+///
+/// Processes ignore actions they don't handle. A headless daemon's processes
+/// pick the persisted policy up on their next start.
+pub fn broadcast_to_processes(state: &AppState, command: &Value) {
+    for process in state.python_processes.lock().unwrap().iter_mut() {
+        if let Err(e) = send_command_to_process(process, command) {
+            log::warn!("Failed to push policy update: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_dns_policy() -> Result<DnsPolicy, String> {
+    performance::command("get_dns_policy", async move {
+        dns_bypass::load_policy()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_doh_blocking(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    performance::command("set_doh_blocking", async move {
+        log::info!("Set DoH blocking to {}", enabled);
+
+        let flag = if enabled { "true" } else { "false" };
+        let result = run_blocking_command("set-doh-blocking", &[("--enabled", flag)])?;
+
+        if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            return Err(error.to_string());
+        }
+
+        broadcast_to_processes(&state, &json!({
+            "action": "set_doh_blocking",
+            "enabled": enabled
+        }));
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_force_dns(
+    device_ip: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("set_force_dns", async move {
+        log::info!("Set force DNS for {} to {}", device_ip, enabled);
+
+        let flag = if enabled { "true" } else { "false" };
+        let result = run_blocking_command("force-dns", &[("--device", &device_ip), ("--enabled", flag)])?;
+
+        if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            return Err(error.to_string());
+        }
+
+        broadcast_to_processes(&state, &json!({
+            "action": "force_dns",
+            "device_ip": device_ip,
+            "enabled": enabled
+        }));
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_dns_bypass_attempts(
+    device_id: Option<String>,
+    hours: Option<u32>,
+) -> Result<Vec<DnsBypassAttempt>, String> {
+    performance::command("get_dns_bypass_attempts", async move {
+        let policy = dns_bypass::load_policy()?;
+        dns_bypass::find_bypass_attempts(&policy, device_id.as_deref(), hours.unwrap_or(24))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_quic_policy() -> Result<QuicPolicy, String> {
+    performance::command("get_quic_policy", async move {
+        quic::load_policy()
+    })
+    .await
+}
+
+/// Block QUIC (UDP/443) so devices fall back to TCP/TLS the proxy can intercept
+#[tauri::command]
+pub async fn set_quic_blocking(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    performance::command("set_quic_blocking", async move {
+        quic::set_blocking(&state, enabled)
+    })
+    .await
+}
+
+/// Override one device's QUIC blocking; `blocked` of None follows the global setting
+#[tauri::command]
+pub async fn set_device_quic_blocking(
+    device_id: String,
+    blocked: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("set_device_quic_blocking", async move {
+        quic::set_device(&state, &device_id, blocked)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_quic_downgrades(
+    range: Option<UsageRange>,
+    device_id: Option<String>,
+) -> Result<QuicDowngradeReport, String> {
+    performance::command("get_quic_downgrades", async move {
+        quic::report(device_id.as_deref(), range.unwrap_or_default())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_guest_mode(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    performance::command("set_guest_mode", async move {
+        guest::set_enabled(&state, enabled)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_guest_stats(hours: Option<u32>, state: State<'_, AppState>) -> Result<GuestStats, String> {
+    performance::command("get_guest_stats", async move {
+        guest::stats(&state, hours.unwrap_or(24))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_device_rate_limit(
+    device_id: String,
+    kbps: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("set_device_rate_limit", async move {
+        bandwidth::set_limit(&state, &device_id, kbps)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_bandwidth_usage(state: State<'_, AppState>) -> Result<Vec<DeviceBandwidth>, String> {
+    performance::command("get_bandwidth_usage", async move {
+        bandwidth::utilization(&state)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_block_page_config() -> Result<BlockPageConfig, String> {
+    performance::command("get_block_page_config", async move {
+        block_page::load_config()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_block_page_config(config: BlockPageConfig, state: State<'_, AppState>) -> Result<(), String> {
+    performance::command("set_block_page_config", async move {
+        log::info!("Updating block page: {:?}", config);
+        block_page::set_config(&state, &config)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn preview_block_page(config: Option<BlockPageConfig>) -> Result<String, String> {
+    performance::command("preview_block_page", async move {
+        let config = match config {
+            Some(config) => config,
+            None => block_page::load_config()?,
+        };
+        Ok(block_page::preview(&config))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_unblock_requests(status: Option<UnblockStatus>) -> Result<Vec<UnblockRequest>, String> {
+    performance::command("get_unblock_requests", async move {
+        unblock::requests(status)
+    })
+    .await
+}
+
+/// Approve an unblock request for `duration` minutes, or for good when omitted
+#[tauri::command]
+pub async fn approve_unblock_request(
+    id: String,
+    duration: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("approve_unblock_request", async move {
+        unblock::approve(&state, &id, duration)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn deny_unblock_request(id: String) -> Result<(), String> {
+    performance::command("deny_unblock_request", async move {
+        unblock::deny(&id)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_block_config() -> Result<Value, String> {
+    performance::command("get_block_config", async move {
+        run_blocking_command("config", &[])
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn check_domain(domain: String) -> Result<Value, String> {
+    performance::command("check_domain", async move {
+        run_blocking_command("check", &[("--domain", &domain)])
+    })
+    .await
+}
+
+/// Write all rules to a file as "hosts", "adblock" or "json", as an operation
+#[tauri::command]
+pub async fn export_rules(path: String, format: String, app: AppHandle) -> Result<String, String> {
+    performance::command("export_rules", async move {
+        log::info!("Exporting rules to {} ({})", path, format);
+
+        Ok(operations::start(&app, "rules_export", move |_| {
+            let result = run_blocking_command("export-rules", &[("--path", &path), ("--format", &format)])?;
+
+            if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+                Ok(result)
+            } else {
+                let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+                Err(error.to_string())
+            }
+        }))
+    })
+    .await
+}
+
+/// Merge rules from a file. With dry_run, only reports what would change and what conflicts.
+#[tauri::command]
+pub async fn import_rules(path: String, format: String, dry_run: Option<bool>) -> Result<Value, String> {
+    performance::command("import_rules", async move {
+        let dry_run = dry_run.unwrap_or(false);
+        log::info!("Importing rules from {} ({}, dry run: {})", path, format, dry_run);
+    
+        let mut args = vec!["--action", "import-rules", "--path", &path, "--format", &format];
+        if dry_run {
+            args.push("--dry-run");
+        }
+    
+        let result = run_python_script("python/blocking/blocker.py", &args)?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(result)
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+// ============================================
+// Settings Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_settings() -> Result<Settings, String> {
+    performance::command("get_settings", async move {
+        let settings = load_settings()?;
+        if !access::is_observer() {
+            return Ok(settings);
+        }
+
+        let mut value = serde_json::to_value(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        diagnostics::redact(&mut value);
+        serde_json::from_value(value).map_err(|e| format!("Failed to redact settings: {}", e))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn update_settings(mut settings: Settings, app: AppHandle) -> Result<(), String> {
+    performance::command("update_settings", async move {
+        log::info!("Updating settings: {}", diagnostics::redacted(&settings));
+        i18n::validate_locale(&settings.locale)?;
+        clock::validate_zone(settings.timezone.as_deref().unwrap_or(""))?;
+        body_capture::validate(&settings.body_capture)?;
+        alert_policy::validate(&settings.alert_severity_map, &settings.alert_escalations)?;
+        proxy_config::validate(&settings.proxy)?;
+        grpc::validate(&settings.grpc)?;
+        subscriptions::validate(&settings.ui_push)?;
+        usage_cost::validate(&settings.usage_cost)?;
+        settings.scan_ranges = subnets::validate_ranges(&settings.scan_ranges)?;
+        let previous = load_settings()?;
+        // Running capture processes hold the current database open
+        if settings.shared_capture_database != previous.shared_capture_database
+            && app.state::<AppState>().lifecycle.current().is_running()
+        {
+            return Err(t("error.stop_monitoring_to_change_database", &[]));
+        }
+        // Passwords go to the OS keychain, not the settings file
+        let stored = secrets::take_from_settings(&mut settings)?;
+        save_settings(&settings)?;
+        body_capture::push(&app.state::<AppState>(), &settings.body_capture);
+        if settings.proxy != previous.proxy {
+            proxy_config::sync(&app.state::<AppState>())?;
+        }
+        for name in stored {
+            secrets::apply(&app.state::<AppState>(), name)?;
+        }
+        grpc::apply_settings(&app, &settings.grpc)?;
+        metrics::apply_settings(&app, &settings)
+    })
+    .await
+}
+
+/// Each setting with whether it is stored per OS user or shared
+#[tauri::command]
+pub async fn get_settings_schema() -> Result<SettingsSchema, String> {
+    performance::command("get_settings_schema", async move {
+        let settings = serde_json::to_value(load_settings()?)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        Ok(users::schema(&settings))
+    })
+    .await
+}
+
+/// Locales backend text can be generated in
+#[tauri::command]
+pub async fn get_available_locales() -> Result<Vec<LocaleInfo>, String> {
+    performance::command("get_available_locales", async move {
+        i18n::available_locales()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_autostart(enabled: bool, minimized: bool) -> Result<(), String> {
+    performance::command("set_autostart", async move {
+        log::info!("Set autostart to {} (minimized: {})", enabled, minimized);
+
+        autostart::set_login_item(enabled, minimized)?;
+
+        let mut settings = load_settings()?;
+        settings.autostart = enabled;
+        settings.start_minimized = minimized;
+        save_settings(&settings)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_workspaces() -> Result<Vec<WorkspaceInfo>, String> {
+    performance::command("list_workspaces", async move {
+        workspace::list()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn switch_workspace(
+    name: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("switch_workspace", async move {
+        // Running capture processes hold the old workspace's files open
+        if state.lifecycle.current().is_running() {
+            return Err(t("error.stop_monitoring_first", &[]));
+        }
+
+        workspace::switch(&name)?;
+        state.snapshot.clear();
+        metrics::apply_settings(&app, &load_settings()?)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn change_stealth_profile(
+    profile_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("change_stealth_profile", async move {
+        let settings = load_settings()?;
+        let interface = settings.network_interface.unwrap_or_else(platform::default_interface);
+    
+        // Apply the profile
+        let result = run_stealth_command("apply", &interface, Some(&profile_id))?;
+    
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            // Update state
+            let mut profile = state.current_profile.lock().unwrap();
+            *profile = profile_id.clone();
+        
+            // Save to settings
+            let mut settings = load_settings()?;
+            settings.device_profile = profile_id;
+            save_settings(&settings)?;
+        
+            log::info!("Changed stealth profile successfully");
+            Ok(())
+        } else {
+            let error = result.get("message").or(result.get("error"))
+                .and_then(|e| e.as_str())
+                .unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_stealth_profiles() -> Result<Value, String> {
+    performance::command("get_stealth_profiles", async move {
+        run_python_script("python/stealth/mac_changer.py", &["--list-profiles"])
+    })
+    .await
+}
+
+// ============================================
+// Observer Access Commands
+// ============================================
+
+/// Whether this session is a read-only observer
+#[tauri::command]
+pub async fn get_access_info() -> Result<AccessInfo, String> {
+    performance::command("get_access_info", async move {
+        access::info()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_observer_tokens() -> Result<Vec<ObserverToken>, String> {
+    performance::command("get_observer_tokens", async move {
+        access::list_tokens()
+    })
+    .await
+}
+
+/// Create a read-only token; it is shown in full only in this response
+#[tauri::command]
+pub async fn create_observer_token(name: String) -> Result<ObserverToken, String> {
+    performance::command("create_observer_token", async move {
+        access::create_token(&name)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn revoke_observer_token(id: String) -> Result<(), String> {
+    performance::command("revoke_observer_token", async move {
+        access::revoke_token(&id)
+    })
+    .await
+}
+
+// ============================================
+// API Key Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_api_keys() -> Result<Vec<ApiKey>, String> {
+    performance::command("get_api_keys", async move {
+        api_keys::list_keys()
+    })
+    .await
+}
+
+/// Create an API key; it is shown in full only in this response
+#[tauri::command]
+pub async fn create_api_key(
+    name: String,
+    scopes: Vec<ApiScope>,
+    expiry: Option<String>,
+    rate_limit: Option<u32>,
+) -> Result<ApiKey, String> {
+    performance::command("create_api_key", async move {
+        api_keys::create_key(&name, scopes, expiry.as_deref(), rate_limit)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn revoke_api_key(id: String) -> Result<(), String> {
+    performance::command("revoke_api_key", async move {
+        api_keys::revoke_key(&id)
+    })
+    .await
+}
+
+// ============================================
+// Secrets Commands
+// ============================================
+
+/// Store a credential in the OS keychain; an empty value removes it
+#[tauri::command]
+pub async fn set_secret(name: String, value: String, state: State<'_, AppState>) -> Result<(), String> {
+    performance::command("set_secret", async move {
+        secrets::set_secret(&name, &value)?;
+        secrets::apply(&state, &name)
+    })
+    .await
+}
+
+/// Which credentials are stored, without their values
+#[tauri::command]
+pub async fn get_secret_names() -> Result<Vec<String>, String> {
+    performance::command("get_secret_names", async move {
+        secrets::stored_names()
+    })
+    .await
+}
+
+// ============================================
+// Certificate Commands
+// ============================================
+
+/// Generate the CA certificate as an operation; its result holds `cert_path`
+#[tauri::command]
+pub async fn generate_certificate(profile: String, app: AppHandle) -> Result<String, String> {
+    performance::command("generate_certificate", async move {
+        log::info!("Generating certificate with profile: {}", profile);
+
+        let handle = app.clone();
+        Ok(operations::start(&app, "certificate", move |_| {
+            let result = run_python_script(
+                "python/https/cert_generator.py",
+                &["--action", "generate", "--profile", &profile],
+            )?;
+
+            if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+                // The proxy signs with the new CA from its next start
+                if let Err(e) = proxy_config::sync(&handle.state::<AppState>()) {
+                    log::warn!("Failed to update proxy config with the new certificate: {}", e);
+                }
+                let cert_path = result.get("cert_path")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or("certs/ca.crt");
+                Ok(json!({ "cert_path": cert_path }))
+            } else {
+                let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+                Err(error.to_string())
+            }
+        }))
+    })
+    .await
+}
+
+/// Start the certificate installer page, or report the one already running
+#[tauri::command]
+pub async fn start_cert_server(state: State<'_, AppState>) -> Result<CertServerStatus, String> {
+    performance::command("start_cert_server", async move {
+        state.cert_server.start(load_settings()?.cert_server_port)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn stop_cert_server(state: State<'_, AppState>) -> Result<(), String> {
+    performance::command("stop_cert_server", async move {
+        state.cert_server.stop()
+    })
+    .await
+}
+
+/// Whether the certificate installer is up, its port and what it has served
+#[tauri::command]
+pub async fn get_cert_server_status(state: State<'_, AppState>) -> Result<CertServerStatus, String> {
+    performance::command("get_cert_server_status", async move {
+        Ok(state.cert_server.status())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_cert_url(state: State<'_, AppState>) -> Result<String, String> {
+    performance::command("get_cert_url", async move {
+        cert_url(&state)
+    })
+    .await
+}
+
+/// The certificate installer's address on this machine's LAN IP
+fn cert_url(state: &AppState) -> Result<String, String> {
+    // Get local IP
+    let result = run_python_script("python/utils/network_utils.py", &["--action", "get-ip"])?;
+    
+    let ip = result.get("ip")
+        .and_then(|i| i.as_str())
+        .unwrap_or("192.168.1.1");
+    let port = match state.cert_server.status().port {
+        Some(port) => port,
+        None => load_settings()?.cert_server_port,
+    };
     
+    Ok(format!("http://{}:{}", ip, port))
+}
+
+/// Step-by-step certificate install instructions for a device's platform (detected unless given)
+#[tauri::command]
+pub async fn get_cert_install_guide(
+    device_id: String,
+    platform: Option<Platform>,
+    state: State<'_, AppState>,
+) -> Result<CertInstallGuide, String> {
+    performance::command("get_cert_install_guide", async move {
+        let device = state
+            .snapshot
+            .devices(false)?
+            .data
+            .into_iter()
+            .find(|d| d.id == device_id)
+            .ok_or_else(|| format!("Device not found: {}", device_id))?;
+        cert_guide::guide(&device, platform)
+    })
+    .await
+}
+
+/// The certificate installer link as a QR code, with a one-time token naming the device it is for
+#[tauri::command]
+pub async fn get_cert_qr(
+    device_id: Option<String>,
+    format: Option<QrFormat>,
+    state: State<'_, AppState>,
+) -> Result<CertQr, String> {
+    performance::command("get_cert_qr", async move {
+        let format = format.unwrap_or_default();
+        let token = state.cert_server.issue_token(device_id.as_deref());
+        let url = format!("{}/?t={}", cert_url(&state)?, token);
+
+        Ok(CertQr {
+            data: qr::render(&url, format)?,
+            mime_type: format.mime_type().to_string(),
+            url,
+            token,
+            device_id,
+        })
+    })
+    .await
+}
+
+/// Never intercept TLS to `domain` (and its subdomains), for one device or all
+#[tauri::command]
+pub async fn add_intercept_exclusion(
+    domain: String,
+    device_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<InterceptExclusion, String> {
+    performance::command("add_intercept_exclusion", async move {
+        intercept_exclusions::add(&state, &domain, device_id.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_intercept_exclusion(
+    domain: String,
+    device_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    performance::command("remove_intercept_exclusion", async move {
+        intercept_exclusions::remove(&state, &domain, device_id.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_intercept_exclusions() -> Result<Vec<InterceptExclusion>, String> {
+    performance::command("get_intercept_exclusions", async move {
+        intercept_exclusions::load_exclusions()
+    })
+    .await
+}
+
+/// What the proxy is configured with: port, CA, upstream proxy and pass-through hosts
+#[tauri::command]
+pub async fn get_proxy_config(state: State<'_, AppState>) -> Result<ProxyConfig, String> {
+    performance::command("get_proxy_config", async move {
+        let devices = state.snapshot.devices(false)?.data;
+        proxy_config::build(&load_settings()?, &devices)
+    })
+    .await
+}
+
+/// Rewrite the proxy config file and have a running proxy reload it
+#[tauri::command]
+pub async fn sync_proxy_config(state: State<'_, AppState>) -> Result<ProxySyncStatus, String> {
+    performance::command("sync_proxy_config", async move {
+        proxy_config::sync(&state)
+    })
+    .await
+}
+
+/// Check an upstream proxy (the configured one when none is given) by sending a request through it
+#[tauri::command]
+pub async fn test_upstream_proxy(upstream: Option<UpstreamProxy>, url: Option<String>) -> Result<UpstreamProxyTest, String> {
+    performance::command("test_upstream_proxy", async move {
+        let upstream = match upstream {
+            Some(upstream) => upstream,
+            None => load_settings()?.proxy.upstream.ok_or_else(|| "No upstream proxy configured".to_string())?,
+        };
+        log::info!("Testing upstream proxy {}", upstream.url);
+        proxy_config::test_upstream(&upstream, url.as_deref())
+    })
+    .await
+}
+
+// ============================================
+// Export Commands
+// ============================================
+
+/// Export captured data to `path`, reporting (written, total) rows as they go.
+/// "parquet" writes a directory partitioned by date and device instead of one file
+pub fn export_to(
+    format: &str,
+    path: &str,
+    mut on_progress: impl FnMut(u64, u64) -> Result<(), String>,
+) -> Result<Value, String> {
+    if format == "parquet" {
+        return parquet_export::export(Path::new(path), on_progress);
+    }
+
+    // Progress records arrive every few thousand rows; an error stops the script at the next
+    stream_python_script(
+        "python/database/db_manager.py",
+        &["--action", "export", "--format", format, "--output", path],
+        |record| {
+            let count = |key: &str| record.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+            on_progress(count("written"), count("total"))
+        },
+    )
+}
+
+/// Export captured data to a file as an operation, returning its id
+#[tauri::command]
+pub async fn export_data(format: String, path: String, app: AppHandle) -> Result<String, String> {
+    performance::command("export_data", async move {
+        log::info!("Exporting data as {} to {}", format, path);
+
+        Ok(operations::start(&app, "export", move |progress| {
+            export_to(&format, &path, |written, total| {
+                progress.check()?;
+                if total > 0 {
+                    progress.report(Some(written as f64 / total as f64), None);
+                }
+                Ok(())
+            })
+        }))
+    })
+    .await
+}
+
+/// Import traffic from an NDJSON file (as written by an "ndjson" export)
+#[tauri::command]
+pub async fn import_data(path: String) -> Result<Value, String> {
+    performance::command("import_data", async move {
+        log::info!("Importing data from {}", path);
+
+        let result = db_writer::write_database_async("import", &[("--input", &path)]).await?;
+
+        if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(result)
+        } else {
+            let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    })
+    .await
+}
+
+// ============================================
+// Investigation Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_investigations() -> Result<Vec<Investigation>, String> {
+    performance::command("get_investigations", async move {
+        investigations::list()
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn create_investigation(name: String) -> Result<Investigation, String> {
+    performance::command("create_investigation", async move {
+        investigations::create(&name)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_investigation(id: String, name: String) -> Result<Investigation, String> {
+    performance::command("rename_investigation", async move {
+        investigations::rename(&id, &name)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn set_investigation_status(id: String, status: InvestigationStatus) -> Result<Investigation, String> {
+    performance::command("set_investigation_status", async move {
+        investigations::set_status(&id, status)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_investigation(id: String) -> Result<(), String> {
+    performance::command("delete_investigation", async move {
+        log::info!("Deleting investigation {}", id);
+        investigations::delete(&id)
+    })
+    .await
+}
+
+/// Attach a device, traffic entry or alert to an investigation
+#[tauri::command]
+pub async fn attach_to_investigation(
+    id: String,
+    kind: EvidenceKind,
+    item_id: String,
+) -> Result<Investigation, String> {
+    performance::command("attach_to_investigation", async move {
+        investigations::attach(&id, kind, &item_id)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn detach_from_investigation(
+    id: String,
+    kind: EvidenceKind,
+    item_id: String,
+) -> Result<Investigation, String> {
+    performance::command("detach_from_investigation", async move {
+        investigations::detach(&id, kind, &item_id)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn add_investigation_note(id: String, text: String) -> Result<Investigation, String> {
+    performance::command("add_investigation_note", async move {
+        investigations::add_note(&id, &text)
+    })
+    .await
+}
+
+/// Write an investigation with its evidence to a file as "json" or "markdown"
+#[tauri::command]
+pub async fn export_investigation(id: String, path: String, format: Option<String>) -> Result<String, String> {
+    performance::command("export_investigation", async move {
+        investigations::export(&id, &path, format.as_deref().unwrap_or("markdown"))
+    })
+    .await
+}
+
+// ============================================
+// Operation Commands
+// ============================================
+
+/// Latest status of an operation started by a long-running command
+#[tauri::command]
+pub async fn get_operation_status(
+    operation_id: String,
+    state: State<'_, AppState>,
+) -> Result<OperationStatus, String> {
+    performance::command("get_operation_status", async move {
+        state.operations.status(&operation_id)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn cancel_operation(operation_id: String, app: AppHandle) -> Result<OperationStatus, String> {
+    performance::command("cancel_operation", async move {
+        operations::cancel(&app, &operation_id)
+    })
+    .await
+}
+
+// ============================================
+// Utility Commands
+// ============================================
+
+#[tauri::command]
+pub async fn get_network_interfaces() -> Result<Value, String> {
+    performance::command("get_network_interfaces", async move {
+        run_python_script("python/utils/network_utils.py", &["--action", "list-interfaces"])
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn validate_interface(name: String) -> Result<InterfaceCapabilities, String> {
+    performance::command("validate_interface", async move {
+        check_interface(&name)
+    })
+    .await
+}
+
+/// Probe an interface's capture capabilities via the Python network utilities
+pub fn check_interface(name: &str) -> Result<InterfaceCapabilities, String> {
+    let result = run_python_script(
+        "python/utils/network_utils.py",
+        &["--action", "validate-interface", "--interface", name],
+    )?;
+
     if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        let entries = parse_traffic(result);
-        entries.into_iter().next().ok_or_else(|| "Traffic entry not found".to_string())
+        let capabilities = result.get("capabilities").cloned().unwrap_or(Value::Null);
+        serde_json::from_value(capabilities)
+            .map_err(|e| format!("Failed to parse interface capabilities: {}", e))
     } else {
         let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
         Err(error.to_string())
     }
 }
 
-// ============================================
-// Alert Commands
-// ============================================
+/// Whether the packet capture driver (Npcap on Windows) is installed
+#[tauri::command]
+pub async fn get_capture_driver_status() -> Result<CaptureDriverStatus, String> {
+    performance::command("get_capture_driver_status", async move {
+        capture_driver::status()
+    })
+    .await
+}
+
+/// Install Npcap with the bundled or downloaded installer, reporting progress as events
+#[tauri::command]
+pub async fn install_capture_driver(app: AppHandle) -> Result<CaptureDriverStatus, String> {
+    performance::command("install_capture_driver", async move {
+        log::info!("Installing packet capture driver");
+        capture_driver::install(&app)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn check_admin() -> Result<bool, String> {
+    performance::command("check_admin", async move {
+        Ok(platform::is_elevated())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn request_elevation(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    performance::command("request_elevation", async move {
+        if platform::is_elevated() {
+            return Err("Already running with administrator rights".to_string());
+        }
+
+        let handoff = ElevationHandoff {
+            was_monitoring: state.lifecycle.current().is_running(),
+            current_profile: state.current_profile.lock().unwrap().clone(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        elevation::write_handoff(&handoff)?;
+
+        log::info!("Requesting elevation (monitoring: {})", handoff.was_monitoring);
+
+        let launcher = match platform::relaunch_elevated(&[elevation::ELEVATED_ARG]) {
+            Ok(launcher) => launcher,
+            Err(e) => {
+                elevation::discard_handoff();
+                return Err(e);
+            }
+        };
+        tauri::async_runtime::spawn_blocking(move || elevation::wait_for_pickup(launcher))
+            .await
+            .map_err(|e| format!("Elevation task failed: {}", e))??;
+
+        // Release the interface now that the elevated instance has taken over
+        kill_python_processes(&mut state.python_processes.lock().unwrap());
+        app.exit(0);
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_diagnostics(state: State<'_, AppState>) -> Result<Diagnostics, String> {
+    performance::command("get_diagnostics", async move {
+        Ok(collect_diagnostics(&state))
+    })
+    .await
+}
+
+/// Platform, privilege and Python details for troubleshooting
+pub fn collect_diagnostics(state: &AppState) -> Diagnostics {
+    let is_elevated = platform::is_elevated();
+
+    Diagnostics {
+        platform: std::env::consts::OS.to_string(),
+        is_elevated,
+        elevation_method: platform::elevation_method().to_string(),
+        elevation_hint: if is_elevated {
+            None
+        } else {
+            Some("ARP spoofing and MAC changes need administrator rights; use request_elevation".to_string())
+        },
+        python_path: crate::python::get_python_path().display().to_string(),
+        python_version: crate::python::check_python().ok(),
+        default_interface: platform::default_interface(),
+        monitoring_state: state.lifecycle.current(),
+        daemon_running: daemon::is_daemon_running(),
+    }
+}
+
+/// Zip logs, component statuses, redacted settings, versions and recent errors for a bug report
+#[tauri::command]
+pub async fn generate_diagnostics_bundle(app: AppHandle) -> Result<DiagnosticsBundle, String> {
+    performance::command("generate_diagnostics_bundle", async move {
+        log::info!("Generating diagnostics bundle");
+        diagnostics::generate_bundle(&app)
+    })
+    .await
+}
+
+/// Write an anonymized snapshot of what the window shows, for reproducing a reported UI issue
+#[tauri::command]
+pub async fn export_state_snapshot(
+    path: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<state_snapshot::SnapshotExport, String> {
+    performance::command("export_state_snapshot", async move {
+        let version = app.package_info().version.to_string();
+        state_snapshot::export(&state, &version, path.as_deref()).await
+    })
+    .await
+}
+
+/// Show a state snapshot through demo mode (debug builds only)
+#[tauri::command]
+pub async fn import_state_snapshot(path: String, state: State<'_, AppState>) -> Result<DemoStatus, String> {
+    performance::command("import_state_snapshot", async move {
+        state_snapshot::import(&state, &path)
+    })
+    .await
+}
+
+/// Installed Python packages checked against the pinned versions
+#[tauri::command]
+pub async fn get_python_environment() -> Result<PythonEnvironment, String> {
+    performance::command("get_python_environment", async move {
+        python_env::verify()
+    })
+    .await
+}
+
+/// Reinstall missing or mismatched Python packages, optionally recreating the venv first
+#[tauri::command]
+pub async fn repair_python_environment(
+    recreate: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PythonEnvironment, String> {
+    performance::command("repair_python_environment", async move {
+        // Capture processes run from the venv being repaired
+        if state.lifecycle.current().is_running() {
+            return Err(t("error.stop_monitoring_to_repair", &[]));
+        }
+
+        log::info!("Repairing Python environment");
+        python_env::repair(&app, recreate.unwrap_or(false))
+    })
+    .await
+}
+
+/// Hit rates and sizes of the vendor, geolocation and category lookup cache
+#[tauri::command]
+pub async fn get_enrichment_cache_stats() -> Result<EnrichmentCacheStats, String> {
+    performance::command("get_enrichment_cache_stats", async move {
+        Ok(enrichment::stats())
+    })
+    .await
+}
+
+/// Forget cached lookups of one kind, or of every kind when none is given
+#[tauri::command]
+pub async fn clear_enrichment_cache(kind: Option<EnrichmentKind>) -> Result<(), String> {
+    performance::command("clear_enrichment_cache", async move {
+        enrichment::clear(kind)
+    })
+    .await
+}
 
+/// Per-command and per-script timings, the newest calls (100 unless `limit` is given) and slowdown warnings
 #[tauri::command]
-pub async fn get_alerts(unread_only: Option<bool>) -> Result<Vec<Alert>, String> {
-    let result = run_alert_command("list", &[])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        let mut alerts = parse_alerts(result);
-        
-        // Filter unread if requested
-        if unread_only.unwrap_or(false) {
-            alerts.retain(|a| !a.is_read);
-        }
-        
-        Ok(alerts)
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn get_performance_metrics(limit: Option<usize>) -> Result<PerformanceMetrics, String> {
+    performance::command("get_performance_metrics", async move {
+        Ok(performance::metrics(limit.unwrap_or(100)))
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn mark_alert_read(alert_id: String) -> Result<(), String> {
-    log::info!("Marking alert as read: {}", alert_id);
-    
-    let result = run_alert_command("acknowledge", &[("--id", &alert_id)])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn aggregate_traffic() -> Result<Value, String> {
+    performance::command("aggregate_traffic", async move {
+        aggregation::run_aggregation()
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn resolve_alert(alert_id: String) -> Result<(), String> {
-    log::info!("Resolving alert: {}", alert_id);
-    
-    let result = run_alert_command("acknowledge", &[("--id", &alert_id)])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn cleanup_database(days: u32) -> Result<Value, String> {
+    performance::command("cleanup_database", async move {
+        let result = db_writer::write_async(
+            "python/database/db_manager.py",
+            &["--action", "cleanup", "--days", &days.to_string()]
+        ).await;
+        stats_cache::invalidate();
+        result
+    })
+    .await
 }
 
+/// Count what the retention policy would delete, without deleting it
 #[tauri::command]
-pub async fn delete_alert(alert_id: String) -> Result<(), String> {
-    log::info!("Deleting alert: {}", alert_id);
-    
-    let result = run_alert_command("delete", &[("--id", &alert_id)])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn preview_retention() -> Result<RetentionReport, String> {
+    performance::command("preview_retention", async move {
+        retention::preview()
+    })
+    .await
 }
 
+/// Delete everything past its retention window now instead of at the next maintenance run
 #[tauri::command]
-pub async fn mark_all_alerts_read() -> Result<(), String> {
-    let result = run_alert_command("acknowledge-all", &[])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn apply_retention() -> Result<RetentionReport, String> {
+    performance::command("apply_retention", async move {
+        log::info!("Applying retention policy");
+        let report = retention::apply()?;
+        let summary = format!("Retention policy removed {} rows", report.total);
+        if let Err(e) = audit::record("retention", &summary, serde_json::json!(report)) {
+            log::warn!("Failed to audit retention: {}", e);
+        }
+        Ok(report)
+    })
+    .await
 }
 
 // ============================================
-// Stats Commands
+// Integration Commands
 // ============================================
 
+/// Point the Home Assistant integration at an instance; the token goes to the OS keychain
 #[tauri::command]
-pub async fn get_stats() -> Result<DashboardStats, String> {
-    let result = query_database("stats", &[])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        let stats = result.get("stats").unwrap_or(&result);
-        
-        // Parse top domains
-        let top_domains: Vec<TopDomain> = if let Some(domains) = stats.get("top_domains").and_then(|d| d.as_object()) {
-            domains.iter().map(|(k, v)| TopDomain {
-                domain: k.clone(),
-                count: v.as_u64().unwrap_or(0),
-            }).collect()
-        } else {
-            vec![]
+pub async fn configure_home_assistant(
+    base_url: String,
+    token: String,
+    webhook_id: Option<String>,
+) -> Result<(), String> {
+    performance::command("configure_home_assistant", async move {
+        log::info!("Configuring Home Assistant at {}", base_url);
+
+        home_assistant::store_token(&token)?;
+
+        let mut settings = load_settings()?;
+        settings.home_assistant = HomeAssistantSettings {
+            enabled: !base_url.is_empty(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            webhook_id: webhook_id.filter(|w| !w.is_empty()),
         };
-        
-        Ok(DashboardStats {
-            total_devices: stats.get("device_count").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
-            online_devices: stats.get("online_devices").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
-            total_requests: stats.get("traffic_count").and_then(|n| n.as_u64()).unwrap_or(0),
-            blocked_requests: stats.get("blocked_count").and_then(|n| n.as_u64()).unwrap_or(0),
-            total_alerts: stats.get("alert_count").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
-            unresolved_alerts: stats.get("unresolved_alerts").and_then(|n| n.as_u64()).unwrap_or(0) as u32,
-            total_bandwidth: stats.get("bytes_in").and_then(|n| n.as_u64()).unwrap_or(0)
-                + stats.get("bytes_out").and_then(|n| n.as_u64()).unwrap_or(0),
-            top_domains,
-            traffic_by_hour: vec![], // TODO: Implement hourly aggregation
-        })
-    } else {
-        // Return empty stats on error (database might not exist yet)
-        Ok(DashboardStats {
-            total_devices: 0,
-            online_devices: 0,
-            total_requests: 0,
-            blocked_requests: 0,
-            total_alerts: 0,
-            unresolved_alerts: 0,
-            total_bandwidth: 0,
-            top_domains: vec![],
-            traffic_by_hour: vec![],
-        })
-    }
+        save_settings(&settings)
+    })
+    .await
 }
 
-// ============================================
-// Blocking Commands
-// ============================================
+#[tauri::command]
+pub async fn test_home_assistant_connection() -> Result<Value, String> {
+    performance::command("test_home_assistant_connection", async move {
+        let settings = load_settings()?.home_assistant;
+        if settings.base_url.is_empty() {
+            return Err("Home Assistant is not configured".to_string());
+        }
+        home_assistant::test_connection(&settings.base_url)
+    })
+    .await
+}
 
 #[tauri::command]
-pub async fn add_block_rule(rule_type: String, value: String) -> Result<(), String> {
-    log::info!("Adding block rule: {} - {}", rule_type, value);
-    
-    let action = match rule_type.as_str() {
-        "domain" => "block",
-        "category" => "block-category",
-        "keyword" => "add-keyword",
-        _ => return Err(format!("Unknown rule type: {}", rule_type)),
-    };
-    
-    let arg_name = match rule_type.as_str() {
-        "domain" => "--domain",
-        "category" => "--category",
-        "keyword" => "--keyword",
-        _ => "--domain",
-    };
-    
-    let result = run_blocking_command(action, &[(arg_name, &value)])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn get_forwarder_status() -> Result<ForwarderStatus, String> {
+    performance::command("get_forwarder_status", async move {
+        forwarder::status()
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn remove_block_rule(rule_type: String, value: String) -> Result<(), String> {
-    log::info!("Removing block rule: {} - {}", rule_type, value);
-    
-    let action = match rule_type.as_str() {
-        "domain" => "unblock",
-        "category" => "unblock-category",
-        "keyword" => "remove-keyword",
-        _ => return Err(format!("Unknown rule type: {}", rule_type)),
-    };
-    
-    let arg_name = match rule_type.as_str() {
-        "domain" => "--domain",
-        "category" => "--category",
-        "keyword" => "--keyword",
-        _ => "--domain",
-    };
-    
-    let result = run_blocking_command(action, &[(arg_name, &value)])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn get_hooks() -> Result<Vec<Hook>, String> {
+    performance::command("get_hooks", async move {
+        hooks::list_hooks()
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn toggle_category(category_id: String, enabled: bool) -> Result<(), String> {
-    log::info!("Toggle category {} to {}", category_id, enabled);
-    
-    let action = if enabled { "block-category" } else { "unblock-category" };
-    let result = run_blocking_command(action, &[("--category", &category_id)])?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn save_hook(hook: Hook) -> Result<(), String> {
+    performance::command("save_hook", async move {
+        log::info!("Saving hook {} ({} -> {})", hook.name, hook.event, hook.command);
+        hooks::save_hook(hook)
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn get_block_config() -> Result<Value, String> {
-    run_blocking_command("config", &[])
+pub async fn delete_hook(hook_id: String) -> Result<(), String> {
+    performance::command("delete_hook", async move {
+        log::info!("Deleting hook {}", hook_id);
+        hooks::delete_hook(&hook_id)
+    })
+    .await
 }
 
+/// Run a hook once with sample event data
 #[tauri::command]
-pub async fn check_domain(domain: String) -> Result<Value, String> {
-    run_blocking_command("check", &[("--domain", &domain)])
+pub async fn test_hook(hook_id: String) -> Result<HookRun, String> {
+    performance::command("test_hook", async move {
+        log::info!("Testing hook {}", hook_id);
+        hooks::test_hook(&hook_id)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_audit_log(kind: Option<String>, limit: Option<u32>) -> Result<Vec<AuditEntry>, String> {
+    performance::command("get_audit_log", async move {
+        audit::entries(kind.as_deref(), limit.unwrap_or(200) as usize)
+    })
+    .await
 }
 
 // ============================================
-// Settings Commands
+// Service Commands
 // ============================================
 
 #[tauri::command]
-pub async fn get_settings() -> Result<Settings, String> {
-    load_settings()
+pub async fn install_service() -> Result<String, String> {
+    performance::command("install_service", async move {
+        log::info!("Installing headless monitoring service");
+        daemon::install_service()
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn update_settings(settings: Settings) -> Result<(), String> {
-    log::info!("Updating settings: {:?}", settings);
-    save_settings(&settings)
+pub async fn uninstall_service() -> Result<String, String> {
+    performance::command("uninstall_service", async move {
+        log::info!("Uninstalling headless monitoring service");
+        daemon::uninstall_service()
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn change_stealth_profile(
-    profile_id: String,
-    state: State<'_, AppState>,
-) -> Result<(), String> {
-    let settings = load_settings()?;
-    let interface = settings.network_interface.unwrap_or_else(|| "Wi-Fi".to_string());
-    
-    // Apply the profile
-    let result = run_stealth_command("apply", &interface, Some(&profile_id))?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        // Update state
-        let mut profile = state.current_profile.lock().unwrap();
-        *profile = profile_id.clone();
-        
-        // Save to settings
-        let mut settings = load_settings()?;
-        settings.device_profile = profile_id;
-        save_settings(&settings)?;
-        
-        log::info!("Changed stealth profile successfully");
-        Ok(())
-    } else {
-        let error = result.get("message").or(result.get("error"))
-            .and_then(|e| e.as_str())
-            .unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn get_daemon_status() -> Result<Value, String> {
+    performance::command("get_daemon_status", async move {
+        if daemon::is_daemon_running() {
+            let mut status = daemon::daemon_request("status")?;
+            status["daemon_running"] = Value::Bool(true);
+            Ok(status)
+        } else {
+            Ok(serde_json::json!({ "success": true, "daemon_running": false }))
+        }
+    })
+    .await
 }
 
+// ============================================
+// Hotkey Commands
+// ============================================
+
 #[tauri::command]
-pub async fn get_stealth_profiles() -> Result<Value, String> {
-    run_python_script("python/stealth/mac_changer.py", &["--list-profiles"])
+pub async fn get_hotkeys() -> Result<HotkeySettings, String> {
+    performance::command("get_hotkeys", async move {
+        Ok(load_settings()?.hotkeys)
+    })
+    .await
+}
+
+/// Save and register new hotkeys, returning bindings that conflict
+///
+/// Invalid or duplicate bindings leave the current hotkeys unchanged.
+#[tauri::command]
+pub async fn set_hotkeys(app: AppHandle, hotkeys: HotkeySettings) -> Result<Vec<HotkeyConflict>, String> {
+    performance::command("set_hotkeys", async move {
+        log::info!("Setting hotkeys: {:?}", hotkeys);
+        hotkeys::set(&app, hotkeys)
+    })
+    .await
 }
 
 // ============================================
-// Certificate Commands
+// Update Commands
 // ============================================
 
+/// Check the configured channel for a newer build and its changelog
 #[tauri::command]
-pub async fn generate_certificate(profile: String) -> Result<String, String> {
-    log::info!("Generating certificate with profile: {}", profile);
-    
-    let result = run_python_script(
-        "python/https/cert_generator.py",
-        &["--action", "generate", "--profile", &profile],
-    )?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        let cert_path = result.get("cert_path")
-            .and_then(|p| p.as_str())
-            .unwrap_or("certs/ca.crt");
-        Ok(format!("Certificate generated: {}", cert_path))
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn check_for_updates(app: AppHandle) -> Result<UpdateInfo, String> {
+    performance::command("check_for_updates", async move {
+        updater::check(&app).await
+    })
+    .await
 }
 
+/// Download and verify the available update, emitting progress events
 #[tauri::command]
-pub async fn start_cert_server(state: State<'_, AppState>) -> Result<String, String> {
-    let mut processes = state.python_processes.lock().unwrap();
-    
-    match start_python_script("cert-installer/server.py", &[]) {
-        Ok(child) => {
-            processes.push(child);
-            Ok("Certificate server started on port 8888".to_string())
-        }
-        Err(e) => Err(format!("Failed to start cert server: {}", e)),
-    }
+pub async fn download_update(app: AppHandle) -> Result<UpdateInfo, String> {
+    performance::command("download_update", async move {
+        updater::download(&app).await
+    })
+    .await
 }
 
+/// Install the downloaded update and restart
 #[tauri::command]
-pub async fn get_cert_url() -> Result<String, String> {
-    // Get local IP
-    let result = run_python_script("python/utils/network_utils.py", &["--action", "get-ip"])?;
-    
-    let ip = result.get("ip")
-        .and_then(|i| i.as_str())
-        .unwrap_or("192.168.1.1");
-    
-    Ok(format!("http://{}:8888", ip))
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    performance::command("install_update", async move {
+        updater::install(&app)
+    })
+    .await
 }
 
 // ============================================
-// Export Commands
+// Quick Action Commands
 // ============================================
 
+/// Actions a command palette can offer, with the arguments each takes
 #[tauri::command]
-pub async fn export_data(format: String, path: String) -> Result<(), String> {
-    log::info!("Exporting data as {} to {}", format, path);
-    
-    let result = run_python_script(
-        "python/database/db_manager.py",
-        &["--action", "export", "--format", &format, "--output", &path]
-    )?;
-    
-    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
-        Ok(())
-    } else {
-        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
-        Err(error.to_string())
-    }
+pub async fn list_quick_actions() -> Result<Vec<QuickAction>, String> {
+    performance::command("list_quick_actions", async move {
+        Ok(quick_actions::list())
+    })
+    .await
+}
+
+/// Run a quick action by id with its arguments as a JSON object
+#[tauri::command]
+pub async fn run_quick_action(app: AppHandle, id: String, args: Option<Value>) -> Result<Value, String> {
+    performance::command("run_quick_action", async move {
+        log::info!("Running quick action {}", id);
+        quick_actions::run(&app, &id, &args.unwrap_or(Value::Null)).await
+    })
+    .await
 }
 
 // ============================================
-// Utility Commands
+// Dashboard Widget Commands
 // ============================================
 
+/// Widgets a dashboard can show, with the parameters each takes
 #[tauri::command]
-pub async fn get_network_interfaces() -> Result<Value, String> {
-    run_python_script("python/utils/network_utils.py", &["--action", "list-interfaces"])
+pub async fn list_widgets() -> Result<Vec<Widget>, String> {
+    performance::command("list_widgets", async move {
+        Ok(widgets::list())
+    })
+    .await
 }
 
+/// Data for one dashboard widget, with its parameters as a JSON object
 #[tauri::command]
-pub async fn check_admin() -> Result<bool, String> {
-    #[cfg(windows)]
-    {
-        use std::process::Command;
-        let output = Command::new("net")
-            .args(["session"])
-            .output()
-            .map_err(|e| e.to_string())?;
-        Ok(output.status.success())
-    }
-    
-    #[cfg(not(windows))]
-    {
-        Ok(false)
-    }
+pub async fn get_widget_data(
+    widget_id: String,
+    params: Option<Value>,
+    state: State<'_, AppState>,
+) -> Result<Value, String> {
+    performance::command("get_widget_data", async move {
+        let devices = state.snapshot.devices(false)?.data;
+        widgets::data(&widget_id, &params.unwrap_or(Value::Null), &devices)
+    })
+    .await
+}
+
+// ============================================
+// Demo Commands
+// ============================================
+
+/// Show synthetic devices, traffic, DNS and alerts instead of the database, or go back
+#[tauri::command]
+pub async fn enable_demo_mode(
+    enabled: bool,
+    seed: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<DemoStatus, String> {
+    performance::command("enable_demo_mode", async move {
+        let status = if enabled {
+            demo::enable(seed)
+        } else {
+            demo::disable();
+            demo::status()
+        };
+
+        let mut settings = load_settings()?;
+        settings.demo_seed = status.seed;
+        save_settings(&settings)?;
+
+        // Cached snapshots are from the other data set
+        state.snapshot.clear();
+        Ok(status)
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn cleanup_database(days: u32) -> Result<Value, String> {
-    run_python_script(
-        "python/database/db_manager.py",
-        &["--action", "cleanup", "--days", &days.to_string()]
-    )
+pub async fn get_demo_status() -> Result<DemoStatus, String> {
+    performance::command("get_demo_status", async move {
+        Ok(demo::status())
+    })
+    .await
 }
@@ -0,0 +1,106 @@
+// Plaintext credential exposure alerts
+//
+// The Python detector finds password/token fields (by configurable name
+// patterns) in URL query strings and plain-HTTP requests and raises privacy
+// alerts naming the device and host, never the value. This task has it check
+// new captures while monitoring runs.
+
+use crate::python::run_python_script;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const SCRIPT: &str = "python/alerts/credential_exposure.py";
+
+/// How often new captures are checked
+const SCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CredentialExposureConfig {
+    pub enabled: bool,
+    /// Case-insensitive regexes matched against query, form and JSON field names
+    pub field_patterns: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CredentialExposure {
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub host: String,
+    /// "query_string", "plain_http_body" and/or "plain_http_header"
+    pub kinds: Vec<String>,
+    pub fields: Vec<String>,
+    pub requests: u64,
+    pub plain_http: bool,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+fn run_credential_command(args: &[&str]) -> Result<Value, String> {
+    let result = run_python_script(SCRIPT, args)?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(result)
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+fn parse_config(result: Value) -> Result<CredentialExposureConfig, String> {
+    let config = result.get("config").cloned().unwrap_or(Value::Null);
+    serde_json::from_value(config).map_err(|e| format!("Failed to parse credential exposure config: {}", e))
+}
+
+pub fn load_config() -> Result<CredentialExposureConfig, String> {
+    parse_config(run_credential_command(&["--action", "config"])?)
+}
+
+/// Save the config; fails on an invalid pattern
+pub fn set_config(config: &CredentialExposureConfig) -> Result<CredentialExposureConfig, String> {
+    let json = serde_json::to_string(config)
+        .map_err(|e| format!("Failed to serialize credential exposure config: {}", e))?;
+    parse_config(run_credential_command(&["--action", "set-config", "--config", &json])?)
+}
+
+/// Devices and hosts that leaked credentials in the last `hours`, plain HTTP first
+pub fn report(device_id: Option<&str>, hours: u32) -> Result<Vec<CredentialExposure>, String> {
+    let hours = hours.to_string();
+    let mut args = vec!["--action", "report", "--hours", &hours];
+    if let Some(device) = device_id {
+        args.push("--device");
+        args.push(device);
+    }
+
+    let exposures = run_credential_command(&args)?
+        .get("exposures")
+        .cloned()
+        .unwrap_or(Value::Array(vec![]));
+    serde_json::from_value(exposures).map_err(|e| format!("Failed to parse credential exposures: {}", e))
+}
+
+/// Spawn the background task that checks new captures for exposed credentials
+pub fn spawn_credential_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if !handle.state::<AppState>().lifecycle.current().is_running() {
+                    return;
+                }
+                match run_credential_command(&["--action", "scan"]) {
+                    Ok(result) if result.get("alerts").and_then(|a| a.as_u64()).unwrap_or(0) > 0 => {
+                        log::warn!("Credential exposures in new traffic: {}", result)
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::debug!("Credential exposure scan failed: {}", e),
+                }
+            })
+            .await;
+
+            tokio::time::sleep(SCAN_INTERVAL).await;
+        }
+    });
+}
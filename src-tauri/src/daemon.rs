@@ -0,0 +1,343 @@
+// Headless daemon mode: the monitoring engine without a window, driven over
+// line-delimited JSON on localhost by the GUI, the CLI and API key holders
+
+use crate::access;
+use crate::api_keys;
+use crate::commands::{check_interface, load_settings, spawn_monitoring_processes};
+use crate::i18n::t;
+use crate::platform;
+use crate::python::kill_python_processes;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Localhost port the daemon listens on
+pub const DAEMON_PORT: u16 = 47816;
+
+/// Name used when registering the daemon with the OS
+pub const SERVICE_NAME: &str = "NetworkMonitorService";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a client gets to send its request line
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct DaemonState {
+    processes: Mutex<Vec<Child>>,
+    start_time: Mutex<Option<Instant>>,
+}
+
+fn daemon_addr() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], DAEMON_PORT))
+}
+
+/// Run the monitoring engine headlessly until a shutdown request arrives
+pub fn run_headless() -> Result<(), String> {
+    let listener = TcpListener::bind(daemon_addr())
+        .map_err(|e| format!("Failed to bind daemon port {}: {}", DAEMON_PORT, e))?;
+
+    log::info!("Headless daemon listening on {}", daemon_addr());
+
+    // Created up front so clients started later find it; an installed service
+    // finds the one the installing user created
+    access::owner_token()?;
+
+    let state = DaemonState {
+        processes: Mutex::new(Vec::new()),
+        start_time: Mutex::new(None),
+    };
+
+    // Monitoring starts immediately; that's the point of running as a service
+    if let Err(e) = handle_request(&state, "start") {
+        log::error!("Failed to start monitoring: {}", e);
+    }
+
+    let shutdown = AtomicBool::new(false);
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            if shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+            let stream = match stream {
+                Ok(s) => s,
+                Err(e) => {
+                    log::warn!("Daemon accept failed: {}", e);
+                    continue;
+                }
+            };
+
+            // Each connection gets its own thread, so a silent client holds up nobody else
+            let (state, shutdown) = (&state, &shutdown);
+            scope.spawn(move || match serve_connection(state, stream) {
+                Ok(true) => {
+                    shutdown.store(true, Ordering::SeqCst);
+                    // Wake the accept loop so it sees the flag
+                    let _ = TcpStream::connect_timeout(&daemon_addr(), CONNECT_TIMEOUT);
+                }
+                Ok(false) => {}
+                Err(e) => log::warn!("Daemon request failed: {}", e),
+            });
+        }
+    });
+
+    let _ = handle_request(&state, "stop");
+    log::info!("Headless daemon stopped");
+    Ok(())
+}
+
+/// Serve one request; returns true if the daemon should shut down
+fn serve_connection(state: &DaemonState, stream: TcpStream) -> Result<bool, String> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT)).map_err(|e| e.to_string())?;
+
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| e.to_string())?;
+
+    let request: Value = serde_json::from_str(line.trim())
+        .map_err(|e| format!("Invalid request: {}", e))?;
+    let command = request.get("command").and_then(|c| c.as_str()).unwrap_or("");
+    let token = request.get("token").and_then(|t| t.as_str());
+
+    let allowed = match request.get("api_key").and_then(|k| k.as_str()) {
+        Some(key) => api_keys::authenticate(key, api_keys::required_scope(command)).map(|_| ()),
+        None => access::authorize_daemon(command, token),
+    };
+
+    let response = match allowed.clone().and_then(|_| handle_request(state, command)) {
+        Ok(value) => value,
+        Err(e) => json!({ "success": false, "error": e }),
+    };
+
+    let mut stream = stream;
+    writeln!(stream, "{}", response).map_err(|e| e.to_string())?;
+
+    Ok(command == "shutdown" && allowed.is_ok())
+}
+
+fn handle_request(state: &DaemonState, command: &str) -> Result<Value, String> {
+    let uptime = || state.start_time.lock().unwrap().map(|t| t.elapsed().as_secs()).unwrap_or(0);
+
+    match command {
+        "status" | "shutdown" => {
+            let running = !state.processes.lock().unwrap().is_empty();
+            Ok(json!({ "success": true, "is_running": running, "uptime": uptime() }))
+        }
+        "start" => {
+            let mut processes = state.processes.lock().unwrap();
+            if !processes.is_empty() {
+                return Ok(json!({ "success": true, "already_running": true, "uptime": uptime() }));
+            }
+
+            let settings = load_settings()?;
+            let interface = settings.network_interface.unwrap_or_else(platform::default_interface);
+            check_interface(&interface)?.ensure_usable()?;
+            processes.extend(spawn_monitoring_processes(&interface)?);
+            *state.start_time.lock().unwrap() = Some(Instant::now());
+
+            log::info!("Daemon started monitoring with {} processes", processes.len());
+            Ok(json!({ "success": true, "uptime": 0 }))
+        }
+        "stop" => {
+            kill_python_processes(&mut state.processes.lock().unwrap());
+            *state.start_time.lock().unwrap() = None;
+            Ok(json!({ "success": true }))
+        }
+        _ => Err(format!("Unknown daemon command: {}", command)),
+    }
+}
+
+// ============================================
+// Client
+// ============================================
+
+/// Check whether a daemon is listening locally, whether or not we may talk to it
+pub fn is_daemon_running() -> bool {
+    TcpStream::connect_timeout(&daemon_addr(), CONNECT_TIMEOUT).is_ok()
+}
+
+/// Send a command to the running daemon and return its JSON response
+pub fn daemon_request(command: &str) -> Result<Value, String> {
+    let response = exchange(command)?;
+    if response.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(response)
+    } else {
+        let error = response.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// One request and its response, whether it succeeded or not
+fn exchange(command: &str) -> Result<Value, String> {
+    let mut stream = TcpStream::connect_timeout(&daemon_addr(), CONNECT_TIMEOUT)
+        .map_err(|e| format!("Daemon is not reachable: {}", e))?;
+    stream.set_read_timeout(Some(IO_TIMEOUT)).map_err(|e| e.to_string())?;
+
+    // Observer sessions identify themselves so the daemon only answers status requests
+    let token = match access::session_token() {
+        Some(token) => token,
+        None => access::owner_token().map_err(|e| t("error.daemon_token_unreadable", &[("error", &e)]))?,
+    };
+    let request = json!({ "command": command, "token": token });
+    writeln!(stream, "{}", request)
+        .map_err(|e| format!("Failed to send daemon request: {}", e))?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)
+        .map_err(|e| format!("Failed to read daemon response: {}", e))?;
+
+    serde_json::from_str(line.trim()).map_err(|e| format!("Invalid daemon response: {}", e))
+}
+
+// ============================================
+// Service registration
+// ============================================
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))
+}
+
+/// Run a system tool, prompting for elevation if we aren't already privileged
+fn run_checked(program: &str, args: &[&str]) -> Result<(), String> {
+    let mut command = if platform::is_elevated() {
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        cmd
+    } else {
+        platform::elevated_command(Path::new(program), args)
+    };
+
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    // An elevated child's stderr doesn't come back through the prompt, only its exit code
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    match (output.status.success(), stderr.is_empty()) {
+        (true, _) => Ok(()),
+        (false, true) => Err(format!("{} failed: {}", program, output.status)),
+        (false, false) => Err(format!("{} failed: {}", program, stderr)),
+    }
+}
+
+/// Write a root-owned file by staging it in the temp dir and copying it into place
+#[cfg(unix)]
+fn install_file(path: &str, content: &str) -> Result<(), String> {
+    let staged = std::env::temp_dir().join(format!("{}.staged", SERVICE_NAME));
+    std::fs::write(&staged, content)
+        .map_err(|e| format!("Failed to stage {}: {}", path, e))?;
+
+    let result = run_checked("cp", &[&staged.to_string_lossy(), path]);
+    let _ = std::fs::remove_file(&staged);
+    result
+}
+
+/// Create the owner token as the installing user before the daemon, running
+/// as root or SYSTEM, would create one only it can read
+fn hand_over_owner_token() -> Result<(), String> {
+    if access::owner_token().is_ok() {
+        return Ok(());
+    }
+
+    // Left behind by a daemon that created it for itself
+    let path = access::get_owner_token_path();
+    let path = path.to_string_lossy();
+    #[cfg(windows)]
+    run_checked("cmd", &["/C", "del", "/F", "/Q", &path])?;
+    #[cfg(unix)]
+    run_checked("rm", &["-f", &path])?;
+
+    access::owner_token().map(|_| ())
+}
+
+/// Register the daemon to start at boot, independent of any user session
+pub fn install_service() -> Result<String, String> {
+    let exe = current_exe()?;
+    let exe = exe.to_string_lossy();
+    hand_over_owner_token()?;
+
+    #[cfg(windows)]
+    {
+        // A SYSTEM boot task keeps running across logout without needing an SCM handler
+        let task = format!("\"{}\" --headless", exe);
+        run_checked("schtasks", &[
+            "/Create", "/F", "/TN", SERVICE_NAME, "/SC", "ONSTART",
+            "/RU", "SYSTEM", "/RL", "HIGHEST", "/TR", &task,
+        ])?;
+        run_checked("schtasks", &["/Run", "/TN", SERVICE_NAME])?;
+        Ok(format!("Registered scheduled task {}", SERVICE_NAME))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let plist_path = format!("/Library/LaunchDaemons/com.networkmonitor.{}.plist", SERVICE_NAME);
+        let plist = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key><string>com.networkmonitor.{}</string>
+    <key>ProgramArguments</key><array><string>{}</string><string>--headless</string></array>
+    <key>RunAtLoad</key><true/>
+    <key>KeepAlive</key><true/>
+</dict>
+</plist>
+"#,
+            SERVICE_NAME, exe
+        );
+        install_file(&plist_path, &plist)?;
+        run_checked("launchctl", &["load", "-w", &plist_path])?;
+        Ok(format!("Installed launch daemon {}", plist_path))
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let unit_path = format!("/etc/systemd/system/{}.service", SERVICE_NAME);
+        let unit = format!(
+            "[Unit]\nDescription=Network Monitor headless engine\nAfter=network-online.target\n\n\
+             [Service]\nExecStart=\"{}\" --headless\nRestart=on-failure\n\n\
+             [Install]\nWantedBy=multi-user.target\n",
+            exe
+        );
+        install_file(&unit_path, &unit)?;
+        run_checked("systemctl", &["daemon-reload"])?;
+        run_checked("systemctl", &["enable", "--now", SERVICE_NAME])?;
+        Ok(format!("Installed systemd unit {}", unit_path))
+    }
+}
+
+/// Stop the daemon and remove its boot registration
+pub fn uninstall_service() -> Result<String, String> {
+    if is_daemon_running() {
+        let _ = daemon_request("shutdown");
+    }
+
+    #[cfg(windows)]
+    {
+        run_checked("schtasks", &["/Delete", "/F", "/TN", SERVICE_NAME])?;
+        Ok(format!("Removed scheduled task {}", SERVICE_NAME))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let plist_path = format!("/Library/LaunchDaemons/com.networkmonitor.{}.plist", SERVICE_NAME);
+        let _ = run_checked("launchctl", &["unload", "-w", &plist_path]);
+        run_checked("rm", &["-f", &plist_path])?;
+        Ok(format!("Removed launch daemon {}", plist_path))
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let unit_path = format!("/etc/systemd/system/{}.service", SERVICE_NAME);
+        let _ = run_checked("systemctl", &["disable", "--now", SERVICE_NAME]);
+        run_checked("rm", &["-f", &unit_path])?;
+        let _ = run_checked("systemctl", &["daemon-reload"]);
+        Ok(format!("Removed systemd unit {}", unit_path))
+    }
+}
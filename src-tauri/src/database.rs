@@ -0,0 +1,201 @@
+// Direct SQLite access for read paths that are too hot for a Python round-trip
+
+use crate::annotations;
+use crate::commands::{DnsQuery, TrafficEntry};
+use crate::performance;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Get the path of the capture database written by the Python layer
+pub fn get_database_path() -> PathBuf {
+    crate::workspace::data_dir().join("network_monitor.db")
+}
+
+/// Read the alert engine's stored alerts (it keeps them as JSON next to the database)
+pub fn stored_alerts() -> Vec<Value> {
+    let path = crate::workspace::data_dir().join("alerts.json");
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|data| data.get("alerts").and_then(|a| a.as_array()).cloned())
+        .unwrap_or_default()
+}
+
+/// Open a read-only connection to the capture database
+pub fn open_readonly() -> Result<Connection, String> {
+    let path = get_database_path();
+
+    if !path.exists() {
+        return Err("Database has not been created yet".to_string());
+    }
+
+    let mut conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+    conn.profile(Some(performance::record_sqlite));
+    // Wait out a checkpoint or writer instead of failing with "database is locked"
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(|e| format!("Failed to set busy timeout: {}", e))?;
+    Ok(conn)
+}
+
+/// Convert a `traffic` row into a TrafficEntry
+pub fn row_to_traffic(row: &Row) -> rusqlite::Result<TrafficEntry> {
+    let alerts: Option<String> = row.get("alerts")?;
+
+    Ok(TrafficEntry {
+        id: row.get("id")?,
+        timestamp: row.get("timestamp")?,
+        device_id: row.get("device_id")?,
+        device_ip: row.get("device_ip")?,
+        method: row.get("method")?,
+        url: row.get("url")?,
+        host: row.get("host")?,
+        path: row.get("path")?,
+        status_code: row.get::<_, Option<i64>>("status_code")?.map(|n| n as u16),
+        content_type: row.get("response_body_type")?,
+        request_size: row.get::<_, Option<i64>>("request_size")?.unwrap_or(0) as u64,
+        response_size: row.get::<_, Option<i64>>("response_size")?.unwrap_or(0) as u64,
+        duration: row.get::<_, Option<i64>>("duration_ms")?.unwrap_or(0) as u32,
+        is_blocked: row.get::<_, Option<i64>>("blocked")?.unwrap_or(0) != 0,
+        has_alert: alerts.map(|a| a.trim() != "[]" && !a.is_empty()).unwrap_or(false),
+        category: row.get("category")?,
+        tags: row
+            .get::<_, Option<String>>("tags")?
+            .and_then(|t| serde_json::from_str(&t).ok())
+            .unwrap_or_default(),
+        note: row.get("note")?,
+    })
+}
+
+/// Convert a `dns_queries` row into a DnsQuery
+pub fn row_to_dns(row: &Row) -> rusqlite::Result<DnsQuery> {
+    Ok(DnsQuery {
+        id: row.get("id")?,
+        timestamp: row.get("timestamp")?,
+        device_id: row.get("device_id")?,
+        device_ip: row.get("device_ip")?,
+        query_name: row.get("query_name")?,
+        query_type: row.get::<_, Option<String>>("query_type")?.unwrap_or_else(|| "A".to_string()),
+        response_ip: row.get("response_ip")?,
+        response_ttl: row.get::<_, Option<i64>>("response_ttl")?.map(|n| n as u32),
+        blocked: row.get::<_, Option<i64>>("blocked")?.unwrap_or(0) != 0,
+        block_reason: row.get("block_reason")?,
+        category: row.get("category")?,
+    })
+}
+
+/// Get the highest traffic rowid currently stored (0 if empty)
+pub fn latest_traffic_rowid(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("SELECT COALESCE(MAX(rowid), 0) FROM traffic", [], |r| r.get(0))
+        .map_err(|e| format!("Failed to query traffic: {}", e))
+}
+
+/// Get traffic entries inserted after the given rowid, oldest first
+pub fn traffic_after_rowid(
+    conn: &Connection,
+    rowid: i64,
+    limit: u32,
+) -> Result<Vec<(i64, TrafficEntry)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT rowid, * FROM traffic WHERE rowid > ?1 ORDER BY rowid ASC LIMIT ?2")
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![rowid, limit], |row| {
+            Ok((row.get::<_, i64>(0)?, row_to_traffic(row)?))
+        })
+        .map_err(|e| format!("Failed to query traffic: {}", e))?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read traffic: {}", e))
+}
+
+/// Look up a device's current IP address
+pub fn device_ip(conn: &Connection, device_id: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT ip_address FROM devices WHERE id = ?1",
+        params![device_id],
+        |r| r.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("Failed to query device: {}", e))
+}
+
+/// Whether the trigram `traffic_search` index has been built
+pub fn has_search_index(conn: &Connection) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE name = 'traffic_search'",
+        [],
+        |_| Ok(()),
+    )
+    .optional()
+    .ok()
+    .flatten()
+    .is_some()
+}
+
+/// A LIKE pattern matching `term` anywhere, with its wildcards taken literally
+pub fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Search traffic URLs, hosts and user agents through the `traffic_search` index
+///
+/// Each whitespace-separated term must match as a substring. Terms shorter
+/// than three characters can't use the trigram index and fall back to LIKE on
+/// the URL and host, as every term does while the index hasn't been built.
+/// `tag:<name>` terms match entries carrying that tag instead.
+pub fn search_traffic(conn: &Connection, query: &str, limit: u32) -> Result<Vec<TrafficEntry>, String> {
+    let (tags, query) = annotations::split_query(query);
+    let min_indexed = if has_search_index(conn) { 3 } else { usize::MAX };
+    let (indexed, short): (Vec<&str>, Vec<&str>) =
+        query.split_whitespace().partition(|t| t.chars().count() >= min_indexed);
+
+    let mut sql = String::from("SELECT traffic.* FROM traffic");
+    let mut values: Vec<String> = Vec::new();
+
+    if !indexed.is_empty() {
+        sql.push_str(
+            " JOIN traffic_search ON traffic_search.rowid = traffic.rowid WHERE traffic_search MATCH ?",
+        );
+        values.push(
+            indexed
+                .iter()
+                .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" AND "),
+        );
+    } else {
+        sql.push_str(" WHERE 1=1");
+    }
+
+    for term in short {
+        sql.push_str(" AND (traffic.url LIKE ? ESCAPE '\\' OR traffic.host LIKE ? ESCAPE '\\')");
+        let pattern = like_pattern(term);
+        values.push(pattern.clone());
+        values.push(pattern);
+    }
+
+    for tag in tags {
+        sql.push_str(" AND EXISTS (SELECT 1 FROM json_each(traffic.tags) WHERE json_each.value = ?)");
+        values.push(tag);
+    }
+
+    sql.push_str(" ORDER BY traffic.rowid DESC LIMIT ?");
+    values.push(limit.to_string());
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to prepare search: {}", e))?;
+
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), row_to_traffic)
+        .map_err(|e| format!("Failed to search traffic: {}", e))?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read search results: {}", e))
+}
@@ -0,0 +1,181 @@
+// Serialized database writes
+//
+// Every write the app itself makes (device updates, imports, rollups,
+// cleanup, retention) goes through one writer thread fed by a bounded queue,
+// so they never contend with each other for SQLite's write lock. Commands
+// await their write (`write_async`) rather than block the async executor;
+// background work already off the executor can use the blocking `write`.
+// Each write runs with the script runner of the thread that queued it. They can
+// still collide with the capture processes, which write on their own; the
+// database runs in WAL mode with a busy timeout, and a write that still fails
+// with "database is locked" is retried with backoff. Queue and retry counts
+// are exported on the metrics endpoint.
+
+use crate::python::{self, run_python_script_with, ScriptRunner};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::sync::oneshot;
+
+/// Writes waiting beyond this block their callers until the writer catches up
+const QUEUE_CAPACITY: usize = 32;
+
+/// Attempts per write when the database stays locked
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry; doubled for each one after
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+struct Job {
+    script: String,
+    args: Vec<String>,
+    runner: Arc<dyn ScriptRunner>,
+    reply: oneshot::Sender<Result<Value, String>>,
+}
+
+static QUEUE: OnceLock<mpsc::Sender<Job>> = OnceLock::new();
+
+static WRITES: AtomicU64 = AtomicU64::new(0);
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+static LOCK_FAILURES: AtomicU64 = AtomicU64::new(0);
+static QUEUE_FULL: AtomicU64 = AtomicU64::new(0);
+static QUEUED: AtomicU64 = AtomicU64::new(0);
+
+/// Counters for the metrics endpoint
+#[derive(Debug, Clone, Copy)]
+pub struct WriterStats {
+    /// Writes run, whatever their outcome
+    pub writes: u64,
+    /// Attempts repeated because the database was locked
+    pub retries: u64,
+    /// Writes that were still locked out after the last attempt
+    pub lock_failures: u64,
+    /// Writes that had to wait for room in the queue
+    pub queue_full: u64,
+    /// Writes waiting right now
+    pub queued: u64,
+}
+
+pub fn stats() -> WriterStats {
+    WriterStats {
+        writes: WRITES.load(Ordering::Relaxed),
+        retries: RETRIES.load(Ordering::Relaxed),
+        lock_failures: LOCK_FAILURES.load(Ordering::Relaxed),
+        queue_full: QUEUE_FULL.load(Ordering::Relaxed),
+        queued: QUEUED.load(Ordering::Relaxed),
+    }
+}
+
+/// Whether a script call failed because another connection held the lock
+fn is_locked(result: &Result<Value, String>) -> bool {
+    let error = match result {
+        Ok(value) => value.get("error").and_then(|e| e.as_str()).unwrap_or(""),
+        Err(e) => e.as_str(),
+    };
+    error.contains("database is locked") || error.contains("database is busy")
+}
+
+/// Run one write, retrying while the database is locked
+fn run_with_retry(runner: &dyn ScriptRunner, script: &str, args: &[&str]) -> Result<Value, String> {
+    WRITES.fetch_add(1, Ordering::Relaxed);
+
+    let mut backoff = RETRY_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = run_python_script_with(runner, script, args);
+        if !is_locked(&result) {
+            return result;
+        }
+        if attempt == MAX_ATTEMPTS {
+            LOCK_FAILURES.fetch_add(1, Ordering::Relaxed);
+            log::warn!("Database still locked after {} attempts: {} {:?}", MAX_ATTEMPTS, script, args);
+            return result;
+        }
+
+        RETRIES.fetch_add(1, Ordering::Relaxed);
+        log::debug!("Database locked, retrying {} {:?} in {:?}", script, args, backoff);
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+    unreachable!("the last attempt always returns")
+}
+
+fn queue() -> &'static mpsc::Sender<Job> {
+    QUEUE.get_or_init(|| {
+        let (sender, mut receiver) = mpsc::channel::<Job>(QUEUE_CAPACITY);
+        thread::spawn(move || {
+            while let Some(job) = receiver.blocking_recv() {
+                QUEUED.fetch_sub(1, Ordering::Relaxed);
+                let args: Vec<&str> = job.args.iter().map(String::as_str).collect();
+                let _ = job.reply.send(run_with_retry(job.runner.as_ref(), &job.script, &args));
+            }
+        });
+        sender
+    })
+}
+
+/// Queue a writing script and wait for its JSON output without blocking the executor
+pub async fn write_async(script: &str, args: &[&str]) -> Result<Value, String> {
+    let (reply, response) = oneshot::channel();
+    let job = Job {
+        script: script.to_string(),
+        args: args.iter().map(|a| a.to_string()).collect(),
+        runner: python::runner(),
+        reply,
+    };
+
+    QUEUED.fetch_add(1, Ordering::Relaxed);
+    let sent = match queue().try_send(job) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Full(job)) => {
+            QUEUE_FULL.fetch_add(1, Ordering::Relaxed);
+            queue().send(job).await.map_err(|_| ())
+        }
+        Err(TrySendError::Closed(_)) => Err(()),
+    };
+    if sent.is_err() {
+        QUEUED.fetch_sub(1, Ordering::Relaxed);
+        return Err("Database writer has stopped".to_string());
+    }
+
+    response
+        .await
+        .map_err(|_| "Database writer dropped the write".to_string())?
+}
+
+/// Run a writing script on the writer thread and wait for its JSON output
+///
+/// For code off the async executor; called on an executor thread, it lets the
+/// runtime move that thread's other tasks elsewhere while it waits.
+pub fn write(script: &str, args: &[&str]) -> Result<Value, String> {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(write_async(script, args))),
+        Err(_) => tauri::async_runtime::block_on(write_async(script, args)),
+    }
+}
+
+/// Run a writing db_manager action, like `query_database`
+pub fn write_database(action: &str, args: &[(&str, &str)]) -> Result<Value, String> {
+    let mut script_args = vec!["--action", action];
+
+    for (key, value) in args {
+        script_args.push(key);
+        script_args.push(value);
+    }
+
+    write("python/database/db_manager.py", &script_args)
+}
+
+/// `write_database` for async commands
+pub async fn write_database_async(action: &str, args: &[(&str, &str)]) -> Result<Value, String> {
+    let mut script_args = vec!["--action", action];
+
+    for (key, value) in args {
+        script_args.push(key);
+        script_args.push(value);
+    }
+
+    write_async("python/database/db_manager.py", &script_args).await
+}
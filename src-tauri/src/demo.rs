@@ -0,0 +1,705 @@
+// Demo mode with synthetic data
+//
+// While demo mode is on, the dashboard, device, traffic, DNS and alert
+// commands are answered from a generated household instead of the database:
+// a handful of devices with believable vendors and habits, a day of traffic
+// and DNS lookups, and the alerts that traffic would have raised. Nothing is
+// captured and no Python runs, so the UI can be evaluated (and developed)
+// without root or a network to monitor. The generator is seeded, so a given
+// seed always produces the same household; timestamps are laid out relative
+// to when demo mode was turned on. A state snapshot exported by a user (see
+// `state_snapshot`) can be replayed the same way in place of the generated
+// household.
+
+use crate::annotations;
+use crate::cert_guide::Platform;
+use crate::clock::{self, Zone};
+use crate::commands::{Alert, DashboardStats, Device, DnsQuery, HourlyTraffic, TopDomain, TrafficEntry};
+use crate::interception::InterceptionMode;
+use crate::os_detection::{self, Source};
+use chrono::{DateTime, Duration, SecondsFormat, Timelike, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Seed used when none is given
+const DEFAULT_SEED: u64 = 0x5eed_2024;
+
+/// Hours of history generated
+const HISTORY_HOURS: i64 = 24;
+
+static DEMO: Mutex<Option<DemoData>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DemoStatus {
+    pub enabled: bool,
+    pub seed: Option<u64>,
+    /// When the replayed state snapshot was taken, if one is shown
+    pub snapshot: Option<String>,
+    pub devices: usize,
+    pub traffic: usize,
+    pub dns_queries: usize,
+    pub alerts: usize,
+}
+
+/// Generated or replayed data, with timestamps in UTC
+struct DemoData {
+    seed: Option<u64>,
+    snapshot: Option<String>,
+    devices: Vec<Device>,
+    traffic: Vec<TrafficEntry>,
+    dns: Vec<DnsQuery>,
+    alerts: Vec<Alert>,
+    /// Dashboard stats as they were, instead of ones computed from the traffic
+    stats: Option<DashboardStats>,
+}
+
+/// Data of a state snapshot to show, with timestamps in UTC
+pub struct Replay {
+    pub taken_at: String,
+    pub devices: Vec<Device>,
+    pub traffic: Vec<TrafficEntry>,
+    pub dns: Vec<DnsQuery>,
+    pub alerts: Vec<Alert>,
+    pub stats: Option<DashboardStats>,
+}
+
+/// Hosts the generated devices visit, with their category
+const SITES: &[(&str, &str)] = &[
+    ("www.youtube.com", "video"),
+    ("i.ytimg.com", "video"),
+    ("www.netflix.com", "video"),
+    ("www.tiktok.com", "social"),
+    ("i.instagram.com", "social"),
+    ("www.reddit.com", "social"),
+    ("discord.com", "chat"),
+    ("www.roblox.com", "gaming"),
+    ("accounts.nintendo.com", "gaming"),
+    ("store.steampowered.com", "gaming"),
+    ("www.google.com", "search"),
+    ("api.spotify.com", "music"),
+    ("en.wikipedia.org", "education"),
+    ("www.khanacademy.org", "education"),
+    ("docs.google.com", "productivity"),
+    ("github.com", "development"),
+    ("avs-alexa-na.amazon.com", "smart_home"),
+    ("updates.cdn-apple.com", "system"),
+    ("connectivitycheck.gstatic.com", "system"),
+    ("hpeprint.com", "system"),
+    // Blocked by the default categories
+    ("ad.doubleclick.net", "ads"),
+    ("www.bet365.com", "gambling"),
+];
+
+const BLOCKED_CATEGORIES: &[&str] = &["ads", "gambling"];
+
+/// A generated device and its habits
+struct Profile {
+    hostname: &'static str,
+    vendor: &'static str,
+    device_type: &'static str,
+    oui: &'static str,
+    /// Requests over the day
+    requests: u32,
+    online: bool,
+    sites: &'static [&'static str],
+    /// What passive OS detection would have seen of it
+    os: &'static [(Source, Platform)],
+}
+
+const PROFILES: &[Profile] = &[
+    Profile {
+        hostname: "Emmas-iPhone",
+        vendor: "Apple",
+        device_type: "phone",
+        oui: "a4:83:e7",
+        requests: 180,
+        online: true,
+        sites: &["www.tiktok.com", "i.instagram.com", "www.youtube.com", "api.spotify.com", "discord.com", "www.google.com", "ad.doubleclick.net", "www.bet365.com"],
+        os: &[(Source::UserAgent, Platform::Ios), (Source::Dhcp, Platform::Ios), (Source::Tcp, Platform::Ios)],
+    },
+    Profile {
+        hostname: "Jacobs-iPad",
+        vendor: "Apple",
+        device_type: "tablet",
+        oui: "f0:18:98",
+        requests: 140,
+        online: true,
+        sites: &["www.roblox.com", "www.youtube.com", "i.ytimg.com", "www.khanacademy.org", "ad.doubleclick.net", "updates.cdn-apple.com"],
+        os: &[(Source::UserAgent, Platform::Ios), (Source::Tcp, Platform::Ios)],
+    },
+    Profile {
+        hostname: "DESKTOP-7QK2M1",
+        vendor: "Dell",
+        device_type: "computer",
+        oui: "f8:bc:12",
+        requests: 160,
+        online: true,
+        sites: &["github.com", "docs.google.com", "www.google.com", "en.wikipedia.org", "www.reddit.com", "store.steampowered.com", "ad.doubleclick.net"],
+        os: &[(Source::UserAgent, Platform::Windows), (Source::Dhcp, Platform::Windows), (Source::Tcp, Platform::Windows)],
+    },
+    Profile {
+        hostname: "Pixel-8",
+        vendor: "Google",
+        device_type: "phone",
+        oui: "3c:28:6d",
+        requests: 110,
+        online: true,
+        sites: &["www.google.com", "connectivitycheck.gstatic.com", "www.reddit.com", "api.spotify.com", "www.youtube.com"],
+        os: &[(Source::UserAgent, Platform::Android), (Source::Dhcp, Platform::Android)],
+    },
+    Profile {
+        hostname: "Living-Room-TV",
+        vendor: "Samsung",
+        device_type: "tv",
+        oui: "8c:79:f5",
+        requests: 90,
+        online: true,
+        sites: &["www.netflix.com", "www.youtube.com", "i.ytimg.com", "ad.doubleclick.net"],
+        os: &[(Source::Dhcp, Platform::Linux), (Source::Tcp, Platform::Linux)],
+    },
+    Profile {
+        hostname: "Nintendo-Switch",
+        vendor: "Nintendo",
+        device_type: "console",
+        oui: "98:b6:e9",
+        requests: 45,
+        online: true,
+        sites: &["accounts.nintendo.com", "www.youtube.com"],
+        os: &[],
+    },
+    Profile {
+        hostname: "echo-dot",
+        vendor: "Amazon",
+        device_type: "iot",
+        oui: "44:65:0d",
+        requests: 60,
+        online: true,
+        sites: &["avs-alexa-na.amazon.com", "api.spotify.com"],
+        os: &[(Source::Tcp, Platform::Linux)],
+    },
+    Profile {
+        hostname: "HP-LaserJet",
+        vendor: "HP",
+        device_type: "printer",
+        oui: "3c:52:82",
+        requests: 8,
+        online: false,
+        sites: &["hpeprint.com"],
+        os: &[],
+    },
+];
+
+/// Paths requested, with their content type and typical response size
+const RESOURCES: &[(&str, &str, u64)] = &[
+    ("/", "text/html", 48_000),
+    ("/api/v1/feed", "application/json", 12_000),
+    ("/search", "text/html", 64_000),
+    ("/static/js/app.js", "application/javascript", 220_000),
+    ("/images/thumb.jpg", "image/jpeg", 35_000),
+    ("/media/segment.mp4", "video/mp4", 1_800_000),
+];
+
+/// Small deterministic generator (SplitMix64)
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A value in 0..n
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+fn category_of(host: &str) -> &'static str {
+    SITES.iter().find(|(h, _)| *h == host).map(|(_, c)| *c).unwrap_or("other")
+}
+
+fn stamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+fn generate(seed: u64) -> DemoData {
+    let mut rng = Rng(seed);
+    let now = Utc::now();
+    let mut devices = Vec::new();
+    let mut traffic = Vec::new();
+    let mut dns = Vec::new();
+
+    for (index, profile) in PROFILES.iter().enumerate() {
+        let mac = format!(
+            "{}:{:02x}:{:02x}:{:02x}",
+            profile.oui,
+            rng.below(256),
+            rng.below(256),
+            rng.below(256)
+        );
+        let device_id = format!("dev_{}", mac.replace(':', ""));
+        let ip = format!("192.168.1.{}", 20 + index);
+        // Offline devices were last heard from a few hours ago
+        let last_active = if profile.online {
+            now - Duration::seconds(rng.below(300) as i64)
+        } else {
+            now - Duration::minutes(120 + rng.below(600) as i64)
+        };
+
+        let mut bytes_in = 0;
+        let mut bytes_out = 0;
+        let mut blocked_requests = 0;
+        for _ in 0..profile.requests {
+            let span = (last_active - (now - Duration::hours(HISTORY_HOURS))).num_seconds().max(1);
+            let at = last_active - Duration::seconds(rng.below(span as u64) as i64);
+            let host = *rng.pick(profile.sites);
+            let category = category_of(host);
+            let is_blocked = BLOCKED_CATEGORIES.contains(&category);
+            let (path, content_type, typical_size) = *rng.pick(RESOURCES);
+            let method = if rng.chance(15) { "POST" } else { "GET" };
+            let response_size = if is_blocked { 0 } else { typical_size / 2 + rng.below(typical_size) };
+            let request_size = 300 + rng.below(if method == "POST" { 4000 } else { 900 });
+            let status_code = if is_blocked {
+                None
+            } else if rng.chance(4) {
+                Some(404)
+            } else if rng.chance(10) {
+                Some(304)
+            } else {
+                Some(200)
+            };
+
+            bytes_in += response_size;
+            bytes_out += request_size;
+            if is_blocked {
+                blocked_requests += 1;
+            }
+
+            // Most requests follow a lookup; blocked ones are stopped at the lookup
+            if is_blocked || rng.chance(60) {
+                let lookup_at = at - Duration::milliseconds(50 + rng.below(3000) as i64);
+                dns.push(DnsQuery {
+                    id: format!("demo-dns-{}", dns.len() + 1),
+                    timestamp: stamp(lookup_at),
+                    device_id: Some(device_id.clone()),
+                    device_ip: ip.clone(),
+                    query_name: host.to_string(),
+                    query_type: if rng.chance(20) { "AAAA" } else { "A" }.to_string(),
+                    response_ip: Some(if is_blocked {
+                        "0.0.0.0".to_string()
+                    } else {
+                        format!("{}.{}.{}.{}", *rng.pick(&[104, 142, 151, 172, 185]), rng.below(256), rng.below(256), 1 + rng.below(254))
+                    }),
+                    response_ttl: Some(if is_blocked { 0 } else { 60 + rng.below(3540) as u32 }),
+                    blocked: is_blocked,
+                    block_reason: is_blocked.then(|| format!("Category: {}", category)),
+                    category: Some(category.to_string()),
+                });
+            }
+
+            traffic.push(TrafficEntry {
+                id: String::new(),
+                timestamp: stamp(at),
+                device_id: Some(device_id.clone()),
+                device_ip: ip.clone(),
+                method: method.to_string(),
+                url: format!("https://{}{}", host, path),
+                host: host.to_string(),
+                path: Some(path.to_string()),
+                status_code,
+                content_type: Some(content_type.to_string()),
+                request_size,
+                response_size,
+                duration: if is_blocked { 0 } else { 20 + rng.below(780) as u32 },
+                is_blocked,
+                has_alert: false,
+                category: Some(category.to_string()),
+                tags: vec![],
+                note: None,
+            });
+        }
+
+        devices.push(Device {
+            id: device_id,
+            mac,
+            ip,
+            hostname: Some(profile.hostname.to_string()),
+            vendor: Some(profile.vendor.to_string()),
+            device_type: profile.device_type.to_string(),
+            first_seen: stamp(now - Duration::days(3 + rng.below(90) as i64)),
+            last_seen: stamp(last_active),
+            is_online: profile.online,
+            is_monitored: true,
+            has_certificate: matches!(profile.device_type, "phone" | "tablet" | "computer"),
+            interception_mode: InterceptionMode::Full,
+            total_bytes: bytes_in + bytes_out,
+            bytes_in,
+            bytes_out,
+            blocked_requests,
+            alerts: 0,
+            os: os_detection::combine(profile.os),
+        });
+    }
+
+    // Newest first, as the database returns them
+    traffic.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    dns.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    for (index, entry) in traffic.iter_mut().enumerate() {
+        entry.id = format!("demo-{}", index + 1);
+    }
+
+    let alerts = generate_alerts(&mut rng, now, &mut devices, &mut traffic);
+    DemoData {
+        seed: Some(seed),
+        snapshot: None,
+        devices,
+        traffic,
+        dns,
+        alerts,
+        stats: None,
+    }
+}
+
+/// Alerts the generated traffic would have raised, marking the entries behind them
+fn generate_alerts(rng: &mut Rng, now: DateTime<Utc>, devices: &mut [Device], traffic: &mut [TrafficEntry]) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    let push = |alerts: &mut Vec<Alert>, entry: Option<&mut TrafficEntry>, device: &Device, severity: &str, category: &str, title: String, description: String, keyword: Option<&str>| {
+        let timestamp = entry
+            .as_ref()
+            .map(|e| e.timestamp.clone())
+            .unwrap_or_else(|| device.first_seen.clone());
+        let url = entry.as_ref().map(|e| e.url.clone());
+        if let Some(entry) = entry {
+            entry.has_alert = true;
+        }
+        alerts.push(Alert {
+            id: format!("alert_demo_{}", alerts.len() + 1),
+            timestamp: timestamp.clone(),
+            device_id: Some(device.id.clone()),
+            severity: severity.to_string(),
+            category: category.to_string(),
+            title,
+            description,
+            url,
+            matched_keywords: keyword.map(|k| vec![k.to_string()]),
+            occurrences: 1,
+            last_seen: timestamp,
+            is_read: false,
+            is_resolved: false,
+        });
+    };
+
+    // A blocked gambling visit per device that made one
+    for device in devices.iter() {
+        let name = device.hostname.clone().unwrap_or_default();
+        if let Some(entry) = traffic
+            .iter_mut()
+            .find(|t| t.device_id.as_deref() == Some(&device.id) && t.category.as_deref() == Some("gambling"))
+        {
+            let description = format!("{} tried to open {}", name, entry.host);
+            push(&mut alerts, Some(entry), device, "medium", "network", "Blocked gambling site".to_string(), description, None);
+        }
+    }
+
+    // Keyword matches in searches and chat on the first phone
+    let keywords: &[(&str, &str, &str)] = &[("vape", "drugs", "high"), ("kill myself", "self_harm", "critical"), ("home address", "personal_info", "medium")];
+    let phone = devices[0].clone();
+    for &(keyword, category, severity) in keywords {
+        let candidates: Vec<usize> = traffic
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.device_id.as_deref() == Some(&phone.id) && !t.is_blocked && !t.has_alert)
+            .map(|(i, _)| i)
+            .collect();
+        if candidates.is_empty() {
+            break;
+        }
+        let entry = &mut traffic[*rng.pick(&candidates)];
+        let title = format!("Keyword detected: \"{}\"", keyword);
+        let description = format!("\"{}\" appeared in a request to {}", keyword, entry.host);
+        push(&mut alerts, Some(entry), &phone, severity, category, title, description, Some(keyword));
+    }
+
+    // The console joined the network recently
+    if let Some(console) = devices.iter_mut().find(|d| d.device_type == "console") {
+        console.first_seen = stamp(now - Duration::hours(6 + rng.below(12) as i64));
+        let description = format!("{} ({}) joined the network at {}", console.hostname.clone().unwrap_or_default(), console.mac, console.ip);
+        push(&mut alerts, None, console, "low", "network", "New device connected".to_string(), description, None);
+    }
+
+    // Some older alerts have already been looked at
+    alerts.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    let read_from = alerts.len() / 2;
+    for alert in alerts.iter_mut().skip(read_from) {
+        alert.is_read = true;
+    }
+    for device in devices.iter_mut() {
+        device.alerts = alerts.iter().filter(|a| a.device_id.as_deref() == Some(&device.id)).count() as u32;
+    }
+    alerts
+}
+
+/// Turn demo mode on, generating data from the seed
+pub fn enable(seed: Option<u64>) -> DemoStatus {
+    let seed = seed.unwrap_or(DEFAULT_SEED);
+    log::info!("Demo mode enabled (seed {})", seed);
+    *DEMO.lock().unwrap() = Some(generate(seed));
+    status()
+}
+
+/// Show a state snapshot's data in place of the generated household
+pub fn replay(replay: Replay) -> DemoStatus {
+    log::info!("Replaying state snapshot taken at {}", replay.taken_at);
+    *DEMO.lock().unwrap() = Some(DemoData {
+        seed: None,
+        snapshot: Some(replay.taken_at),
+        devices: replay.devices,
+        traffic: replay.traffic,
+        dns: replay.dns,
+        alerts: replay.alerts,
+        stats: replay.stats,
+    });
+    status()
+}
+
+/// Turn demo mode off, going back to the database
+pub fn disable() {
+    if DEMO.lock().unwrap().take().is_some() {
+        log::info!("Demo mode disabled");
+    }
+}
+
+pub fn status() -> DemoStatus {
+    match DEMO.lock().unwrap().as_ref() {
+        Some(data) => DemoStatus {
+            enabled: true,
+            seed: data.seed,
+            snapshot: data.snapshot.clone(),
+            devices: data.devices.len(),
+            traffic: data.traffic.len(),
+            dns_queries: data.dns.len(),
+            alerts: data.alerts.len(),
+        },
+        None => DemoStatus {
+            enabled: false,
+            seed: None,
+            snapshot: None,
+            devices: 0,
+            traffic: 0,
+            dns_queries: 0,
+            alerts: 0,
+        },
+    }
+}
+
+fn with_data<T>(f: impl FnOnce(&mut DemoData, Zone) -> T) -> Option<T> {
+    let mut demo = DEMO.lock().unwrap();
+    let data = demo.as_mut()?;
+    Some(f(data, Zone::configured()))
+}
+
+fn display_traffic(entry: &TrafficEntry, zone: Zone) -> TrafficEntry {
+    TrafficEntry {
+        timestamp: zone.display(&entry.timestamp),
+        ..entry.clone()
+    }
+}
+
+/// The demo devices, or None when demo mode is off
+pub fn devices() -> Option<Vec<Device>> {
+    with_data(|data, zone| {
+        data.devices
+            .iter()
+            .map(|d| Device {
+                first_seen: zone.display(&d.first_seen),
+                last_seen: zone.display(&d.last_seen),
+                ..d.clone()
+            })
+            .collect()
+    })
+}
+
+/// Demo traffic, newest first
+pub fn traffic(limit: usize, offset: usize, device_id: Option<&str>) -> Option<Vec<TrafficEntry>> {
+    with_data(|data, zone| {
+        data.traffic
+            .iter()
+            .filter(|t| device_id.is_none() || t.device_id.as_deref() == device_id)
+            .skip(offset)
+            .take(limit)
+            .map(|t| display_traffic(t, zone))
+            .collect()
+    })
+}
+
+pub fn traffic_entry(entry_id: &str) -> Option<Option<TrafficEntry>> {
+    with_data(|data, zone| data.traffic.iter().find(|t| t.id == entry_id).map(|t| display_traffic(t, zone)))
+}
+
+/// Demo traffic whose URL, host or category contains every word of the query
+/// and that carries every `tag:` term's tag
+pub fn search_traffic(query: &str, limit: usize) -> Option<Vec<TrafficEntry>> {
+    let (tags, query) = annotations::split_query(query);
+    let words: Vec<String> = query.split_whitespace().map(|w| w.to_lowercase()).collect();
+    with_data(|data, zone| {
+        data.traffic
+            .iter()
+            .filter(|t| {
+                let text = format!("{} {} {}", t.url, t.host, t.category.as_deref().unwrap_or("")).to_lowercase();
+                words.iter().all(|w| text.contains(w.as_str())) && tags.iter().all(|tag| t.tags.contains(tag))
+            })
+            .take(limit)
+            .map(|t| display_traffic(t, zone))
+            .collect()
+    })
+}
+
+/// Set a demo entry's tags and/or note (empty clears it); None when demo mode is off
+pub fn annotate(entry_id: &str, tags: Option<&[String]>, note: Option<&str>) -> Option<Result<(), String>> {
+    with_data(|data, _| {
+        let entry = data
+            .traffic
+            .iter_mut()
+            .find(|t| t.id == entry_id)
+            .ok_or_else(|| format!("Traffic entry not found: {}", entry_id))?;
+        if let Some(tags) = tags {
+            entry.tags = tags.to_vec();
+        }
+        if let Some(note) = note {
+            entry.note = (!note.is_empty()).then(|| note.to_string());
+        }
+        Ok(())
+    })
+}
+
+pub fn dns_queries(limit: usize, device_id: Option<&str>) -> Option<Vec<DnsQuery>> {
+    with_data(|data, zone| {
+        data.dns
+            .iter()
+            .filter(|q| device_id.is_none() || q.device_id.as_deref() == device_id)
+            .take(limit)
+            .map(|q| DnsQuery {
+                timestamp: zone.display(&q.timestamp),
+                ..q.clone()
+            })
+            .collect()
+    })
+}
+
+pub fn alerts() -> Option<Vec<Alert>> {
+    with_data(|data, zone| {
+        data.alerts
+            .iter()
+            .map(|a| Alert {
+                timestamp: zone.display(&a.timestamp),
+                last_seen: zone.display(&a.last_seen),
+                ..a.clone()
+            })
+            .collect()
+    })
+}
+
+/// Mark one demo alert (or all of them, with None) as read; None when demo mode is off
+pub fn mark_read(alert_id: Option<&str>) -> Option<Result<(), String>> {
+    with_data(|data, _| {
+        let mut found = false;
+        for alert in data.alerts.iter_mut().filter(|a| alert_id.is_none() || alert_id == Some(a.id.as_str())) {
+            alert.is_read = true;
+            alert.is_resolved = true;
+            found = true;
+        }
+        if found || alert_id.is_none() {
+            Ok(())
+        } else {
+            Err("Alert not found".to_string())
+        }
+    })
+}
+
+/// Dashboard stats computed from the demo data (or replayed as they were)
+pub fn stats() -> Option<DashboardStats> {
+    with_data(|data, zone| {
+        if let Some(stats) = &data.stats {
+            let mut stats = stats.clone();
+            for hour in &mut stats.traffic_by_hour {
+                if let Some(at) = clock::parse_stored(&hour.start) {
+                    hour.hour = zone.at(at).hour();
+                }
+                hour.start = zone.display(&hour.start);
+            }
+            return stats;
+        }
+
+        let mut domains: HashMap<&str, u64> = HashMap::new();
+        for entry in &data.traffic {
+            *domains.entry(entry.host.as_str()).or_default() += 1;
+        }
+        let mut top_domains: Vec<TopDomain> = domains
+            .into_iter()
+            .map(|(domain, count)| TopDomain { domain: domain.to_string(), count })
+            .collect();
+        top_domains.sort_by(|a, b| b.count.cmp(&a.count).then(a.domain.cmp(&b.domain)));
+        top_domains.truncate(10);
+
+        // The last 24 hours of the configured zone, oldest first
+        let local = zone.at(Utc::now());
+        let current = local
+            .with_minute(0)
+            .and_then(|t| t.with_second(0))
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(local)
+            .with_timezone(&Utc);
+        let first = current - Duration::hours(23);
+        let mut traffic_by_hour: Vec<HourlyTraffic> = (0..24)
+            .map(|i| {
+                let start = first + Duration::hours(i);
+                HourlyTraffic {
+                    hour: zone.at(start).hour(),
+                    start: zone.display(&stamp(start)),
+                    requests: 0,
+                    bytes_in: 0,
+                    bytes_out: 0,
+                }
+            })
+            .collect();
+        for entry in &data.traffic {
+            if let Some(at) = clock::parse_stored(&entry.timestamp) {
+                let index = (at - first).num_hours();
+                if at >= first && index < 24 {
+                    let hour = &mut traffic_by_hour[index as usize];
+                    hour.requests += 1;
+                    hour.bytes_in += entry.response_size;
+                    hour.bytes_out += entry.request_size;
+                }
+            }
+        }
+
+        let bytes_in = data.traffic.iter().map(|t| t.response_size).sum::<u64>();
+        let bytes_out = data.traffic.iter().map(|t| t.request_size).sum::<u64>();
+        DashboardStats {
+            total_devices: data.devices.len() as u32,
+            online_devices: data.devices.iter().filter(|d| d.is_online).count() as u32,
+            total_requests: data.traffic.len() as u64,
+            blocked_requests: data.traffic.iter().filter(|t| t.is_blocked).count() as u64,
+            total_alerts: data.alerts.len() as u32,
+            unresolved_alerts: data.alerts.iter().filter(|a| !a.is_resolved).count() as u32,
+            total_bandwidth: bytes_in + bytes_out,
+            bytes_in,
+            bytes_out,
+            top_domains,
+            traffic_by_hour,
+        }
+    })
+}
@@ -0,0 +1,367 @@
+// DHCP server detection and rogue DHCP alerts
+//
+// Broadcasts a DHCPDISCOVER and records every server that answers. While
+// monitoring, `python/arp/dhcp_watch.py` also records the servers it sees
+// answering other clients in the data directory's `dhcp_servers.json`, which
+// is merged in the background, so a rogue server shows up between probes and
+// probes still work when the OS DHCP client holds the client port. More than
+// one responding server, or one outside the trusted list, is a strong sign of
+// a rogue device handing out its own gateway/DNS. Servers that stop answering
+// probes, or aren't seen for a day, are forgotten, so replacing the router
+// doesn't leave the old one flagged.
+
+use crate::clock;
+use crate::commands::load_settings;
+use crate::event_bus;
+use crate::i18n::t;
+use crate::state::AppState;
+use crate::workspace;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const DHCP_SERVER_PORT: u16 = 67;
+const DHCP_CLIENT_PORT: u16 = 68;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+
+const OPT_MESSAGE_TYPE: u8 = 53;
+const OPT_SERVER_ID: u8 = 54;
+const OPT_PARAM_REQUEST: u8 = 55;
+const OPT_END: u8 = 255;
+
+const MSG_DISCOVER: u8 = 1;
+const MSG_OFFER: u8 = 2;
+const MSG_ACK: u8 = 5;
+
+/// Probes in a row a server can miss before it is forgotten
+const MISSED_PROBES: u32 = 2;
+
+/// How long a server is kept after it was last seen
+const SERVER_TTL: chrono::Duration = chrono::Duration::hours(24);
+
+/// How often the passive watch's sightings are merged in
+const WATCH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DhcpServer {
+    pub server_ip: String,
+    pub offered_ip: Option<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub offers: u32,
+    pub is_trusted: bool,
+    #[serde(skip)]
+    missed_probes: u32,
+}
+
+/// A server as the passive watch recorded it
+#[derive(Debug, Deserialize)]
+struct ObservedServer {
+    offered_ip: Option<String>,
+    seen_at: String,
+    #[serde(default)]
+    offers: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DhcpScanResult {
+    pub servers: Vec<DhcpServer>,
+    pub rogue_detected: bool,
+}
+
+#[derive(Default)]
+pub struct DhcpMonitor {
+    servers: Mutex<HashMap<String, DhcpServer>>,
+    alerted: Mutex<HashSet<String>>,
+}
+
+/// A DHCP reply parsed from the wire
+struct DhcpReply {
+    server_ip: Ipv4Addr,
+    offered_ip: Ipv4Addr,
+}
+
+fn new_xid() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() ^ (d.as_secs() as u32))
+        .unwrap_or(0x4e4d_4f4e)
+}
+
+/// Build a broadcast DHCPDISCOVER with a locally administered client MAC
+fn build_discover(xid: u32) -> Vec<u8> {
+    let mut packet = vec![0u8; 236];
+    packet[0] = 1; // BOOTREQUEST
+    packet[1] = 1; // Ethernet
+    packet[2] = 6; // MAC length
+    packet[4..8].copy_from_slice(&xid.to_be_bytes());
+    packet[10] = 0x80; // Broadcast flag so replies reach us without an address
+
+    let xid_bytes = xid.to_be_bytes();
+    let chaddr = [0x02, 0x4e, xid_bytes[0], xid_bytes[1], xid_bytes[2], xid_bytes[3]];
+    packet[28..34].copy_from_slice(&chaddr);
+
+    packet.extend_from_slice(&MAGIC_COOKIE);
+    packet.extend_from_slice(&[OPT_MESSAGE_TYPE, 1, MSG_DISCOVER]);
+    packet.extend_from_slice(&[OPT_PARAM_REQUEST, 4, 1, 3, 6, OPT_SERVER_ID]);
+    packet.push(OPT_END);
+    packet
+}
+
+/// Parse an OFFER/ACK, returning None for anything else
+fn parse_reply(packet: &[u8]) -> Option<DhcpReply> {
+    if packet.len() < 240 || packet[0] != 2 || packet[236..240] != MAGIC_COOKIE {
+        return None;
+    }
+
+    let yiaddr = Ipv4Addr::new(packet[16], packet[17], packet[18], packet[19]);
+    let siaddr = Ipv4Addr::new(packet[20], packet[21], packet[22], packet[23]);
+
+    let mut message_type = None;
+    let mut server_id = None;
+    let mut i = 240;
+
+    while i < packet.len() {
+        let code = packet[i];
+        if code == OPT_END {
+            break;
+        }
+        if code == 0 {
+            i += 1;
+            continue;
+        }
+        let len = *packet.get(i + 1)? as usize;
+        let value = packet.get(i + 2..i + 2 + len)?;
+
+        match code {
+            OPT_MESSAGE_TYPE if len == 1 => message_type = Some(value[0]),
+            OPT_SERVER_ID if len == 4 => {
+                server_id = Some(Ipv4Addr::new(value[0], value[1], value[2], value[3]))
+            }
+            _ => {}
+        }
+        i += 2 + len;
+    }
+
+    match message_type {
+        Some(MSG_OFFER) | Some(MSG_ACK) => Some(DhcpReply {
+            server_ip: server_id.unwrap_or(siaddr),
+            offered_ip: yiaddr,
+        }),
+        _ => None,
+    }
+}
+
+fn observed_path() -> PathBuf {
+    workspace::data_dir().join("dhcp_servers.json")
+}
+
+/// Servers the passive watch has seen, by address
+fn load_observed() -> HashMap<String, ObservedServer> {
+    fs::read_to_string(observed_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Broadcast a DISCOVER and collect replies until the timeout expires
+///
+/// When the client port is taken (the OS DHCP client holds it) the DISCOVER
+/// goes out from another port and None is returned: the replies still go to
+/// the client port, where the passive watch picks them up.
+fn probe(timeout: Duration) -> Result<Option<Vec<DhcpReply>>, String> {
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DHCP_CLIENT_PORT)) {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::info!("DHCP client port {} is in use ({}), leaving replies to the passive watch", DHCP_CLIENT_PORT, e);
+            let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+                .map_err(|e| format!("Failed to open DHCP probe socket: {}", e))?;
+            socket.set_broadcast(true).map_err(|e| e.to_string())?;
+            socket
+                .send_to(&build_discover(new_xid()), (Ipv4Addr::BROADCAST, DHCP_SERVER_PORT))
+                .map_err(|e| format!("Failed to send DHCP discover: {}", e))?;
+            std::thread::sleep(timeout);
+            return Ok(None);
+        }
+    };
+    socket.set_broadcast(true).map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(Duration::from_millis(250)))
+        .map_err(|e| e.to_string())?;
+
+    let xid = new_xid();
+    socket
+        .send_to(&build_discover(xid), (Ipv4Addr::BROADCAST, DHCP_SERVER_PORT))
+        .map_err(|e| format!("Failed to send DHCP discover: {}", e))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut replies = Vec::new();
+    let mut buf = [0u8; 1500];
+
+    // Replies for other clients' transactions are recorded too (passive watch)
+    while Instant::now() < deadline {
+        match socket.recv_from(&mut buf) {
+            Ok((len, _)) => {
+                if let Some(reply) = parse_reply(&buf[..len]) {
+                    replies.push(reply);
+                }
+            }
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(format!("Failed to read DHCP replies: {}", e)),
+        }
+    }
+
+    Ok(Some(replies))
+}
+
+impl DhcpMonitor {
+    pub fn servers(&self) -> Vec<DhcpServer> {
+        let mut servers: Vec<_> = self.servers.lock().unwrap().values().cloned().collect();
+        servers.sort_by(|a, b| a.server_ip.cmp(&b.server_ip));
+        servers
+    }
+
+    /// Probe the network and raise an alert for unexpected servers
+    ///
+    /// Blocks for the whole timeout.
+    pub fn scan(&self, timeout: Duration, trusted: &[String]) -> Result<DhcpScanResult, String> {
+        let replies = probe(timeout)?;
+        let now = Utc::now().to_rfc3339();
+
+        {
+            let mut servers = self.servers.lock().unwrap();
+            if let Some(replies) = replies {
+                let answered: HashSet<String> = replies.iter().map(|r| r.server_ip.to_string()).collect();
+                for server in servers.values_mut() {
+                    if answered.contains(&server.server_ip) {
+                        server.missed_probes = 0;
+                    } else {
+                        server.missed_probes += 1;
+                    }
+                }
+                for reply in replies {
+                    record(&mut servers, &reply.server_ip.to_string(), Some(reply.offered_ip.to_string()), &now, 1);
+                }
+            }
+        }
+
+        Ok(self.refresh(trusted))
+    }
+
+    /// Merge in what the passive watch saw, forget stale servers and alert on rogue ones
+    pub fn refresh(&self, trusted: &[String]) -> DhcpScanResult {
+        {
+            let mut servers = self.servers.lock().unwrap();
+            for (server_ip, observed) in load_observed() {
+                let newer = servers
+                    .get(&server_ip)
+                    .and_then(|s| clock::parse_stored(&s.last_seen))
+                    .is_none_or(|last| clock::parse_stored(&observed.seen_at).is_some_and(|seen| seen > last));
+                if newer {
+                    // The watch counts offers itself
+                    let server = record(&mut servers, &server_ip, observed.offered_ip, &observed.seen_at, 0);
+                    server.offers = server.offers.max(observed.offers);
+                    server.missed_probes = 0;
+                }
+            }
+
+            let cutoff = Utc::now() - SERVER_TTL;
+            servers.retain(|ip, server| {
+                let fresh = server.missed_probes < MISSED_PROBES
+                    && clock::parse_stored(&server.last_seen).is_some_and(|seen| seen > cutoff);
+                if !fresh {
+                    log::info!("Forgetting DHCP server {}, not seen recently", ip);
+                }
+                fresh
+            });
+
+            for server in servers.values_mut() {
+                server.is_trusted = trusted.is_empty() || trusted.contains(&server.server_ip);
+            }
+        }
+
+        let servers = self.servers();
+        let untrusted: Vec<_> = servers.iter().filter(|s| !s.is_trusted).collect();
+        let rogue_detected = servers.len() > 1 || !untrusted.is_empty();
+
+        if rogue_detected {
+            self.raise_rogue_alert(&servers);
+        }
+
+        DhcpScanResult { servers, rogue_detected }
+    }
+
+    /// Alert once per distinct set of responding servers
+    fn raise_rogue_alert(&self, servers: &[DhcpServer]) {
+        let mut ips: Vec<_> = servers.iter().map(|s| s.server_ip.clone()).collect();
+        ips.sort();
+        let key = ips.join(",");
+
+        if !self.alerted.lock().unwrap().insert(key.clone()) {
+            return;
+        }
+
+        let description = t(
+            "alert.rogue_dhcp.description",
+            &[("count", &servers.len()), ("servers", &key)],
+        );
+        let metadata = json!({ "servers": servers });
+
+        log::warn!("Rogue DHCP suspected: {}", key);
+
+        event_bus::raise_alert(
+            "critical",
+            "network",
+            &t("alert.rogue_dhcp.title", &[]),
+            &description,
+            None,
+            Some(&metadata),
+        );
+    }
+}
+
+/// Note a server answering at `seen_at`
+fn record<'a>(
+    servers: &'a mut HashMap<String, DhcpServer>,
+    server_ip: &str,
+    offered_ip: Option<String>,
+    seen_at: &str,
+    offers: u32,
+) -> &'a mut DhcpServer {
+    let server = servers.entry(server_ip.to_string()).or_insert_with(|| DhcpServer {
+        server_ip: server_ip.to_string(),
+        offered_ip: None,
+        first_seen: seen_at.to_string(),
+        last_seen: seen_at.to_string(),
+        offers: 0,
+        is_trusted: false,
+        missed_probes: 0,
+    });
+    server.offered_ip = offered_ip.or(server.offered_ip.take());
+    server.last_seen = seen_at.to_string();
+    server.offers += offers;
+    server
+}
+
+/// Spawn the background task that merges the passive watch's sightings and alerts on rogue servers
+pub fn spawn_dhcp_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                let trusted = load_settings().map(|s| s.trusted_dhcp_servers).unwrap_or_default();
+                handle.state::<AppState>().dhcp.refresh(&trusted);
+            })
+            .await;
+        }
+    });
+}
@@ -0,0 +1,337 @@
+// Diagnostics bundles and crash reports
+//
+// Log records are teed into an in-memory ring (errors into a second, longer
+// lived one) so a bundle can include what happened before the user went
+// looking. Panics are written to `crashes/` in the data directory. A bundle
+// gathers component statuses, settings with secrets redacted, recent logs,
+// errors and crash reports (log lines have the values of secret-looking keys
+// replaced as they are captured), and hands them to `python/utils/diagnostics.py`,
+// which adds interpreter, package and OS details and writes the zip.
+//
+// With `crash_reports_enabled` set, a bundle is generated automatically on the
+// next start after a crash.
+
+use crate::commands::{collect_diagnostics, load_settings};
+use crate::forwarder;
+use crate::python::run_python_script;
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Log lines kept for bundles
+const MAX_LOG_LINES: usize = 2000;
+
+/// Error lines kept for bundles
+const MAX_ERRORS: usize = 200;
+
+/// Crash reports included in a bundle, newest first
+const MAX_CRASHES: usize = 5;
+
+/// Setting keys whose values are replaced before settings or log lines go into a bundle
+const SECRET_MARKERS: &[&str] = &["password", "token", "secret", "api_key", "private_key"];
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static RECENT_ERRORS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiagnosticsBundle {
+    pub path: String,
+    pub size: u64,
+    pub files: Vec<String>,
+}
+
+fn push_bounded(buffer: &Mutex<VecDeque<String>>, line: String, max: usize) {
+    if let Ok(mut buffer) = buffer.lock() {
+        if buffer.len() >= max {
+            buffer.pop_front();
+        }
+        buffer.push_back(line);
+    }
+}
+
+/// Passes records to env_logger and keeps info-and-above for bundles,
+/// whatever RUST_LOG says
+struct TeeLogger {
+    inner: env_logger::Logger,
+}
+
+impl log::Log for TeeLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.level() <= log::Level::Info {
+            let line = redact_line(&format!(
+                "{} {:<5} {} - {}",
+                chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+            if record.level() == log::Level::Error {
+                push_bounded(&RECENT_ERRORS, line.clone(), MAX_ERRORS);
+            }
+            push_bounded(&RECENT_LOGS, line, MAX_LOG_LINES);
+        }
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Set up logging (in place of `env_logger::init`)
+pub fn init_logging() {
+    let inner = env_logger::Builder::from_default_env().build();
+    let max_level = inner.filter().max(log::LevelFilter::Info);
+    if log::set_boxed_logger(Box::new(TeeLogger { inner })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+fn crash_dir() -> PathBuf {
+    workspace::data_dir().join("crashes")
+}
+
+/// Write a crash report for every panic, then run the default hook
+pub fn install_crash_handler() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let now = chrono::Local::now();
+        let thread = std::thread::current();
+        let recent: Vec<String> = RECENT_LOGS
+            .lock()
+            .map(|logs| logs.iter().rev().take(50).rev().cloned().collect())
+            .unwrap_or_default();
+
+        let report = format!(
+            "Network Monitor {} crashed at {}\nThread: {}\n{}\n\nBacktrace:\n{}\n\nRecent log:\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            now.to_rfc3339(),
+            thread.name().unwrap_or("unnamed"),
+            info,
+            std::backtrace::Backtrace::force_capture(),
+            recent.join("\n"),
+        );
+        push_bounded(&RECENT_ERRORS, format!("{} PANIC {}", now.format("%Y-%m-%dT%H:%M:%S"), info), MAX_ERRORS);
+
+        let dir = crash_dir();
+        if fs::create_dir_all(&dir).is_ok() {
+            let _ = fs::write(dir.join(format!("crash_{}.txt", now.format("%Y%m%d_%H%M%S"))), report);
+        }
+
+        default_hook(info);
+    }));
+}
+
+/// Crash reports, newest first
+fn crash_reports() -> Vec<(PathBuf, String)> {
+    let Ok(entries) = fs::read_dir(crash_dir()) else {
+        return vec![];
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+    paths.reverse();
+
+    paths
+        .into_iter()
+        .take(MAX_CRASHES)
+        .filter_map(|p| fs::read_to_string(&p).ok().map(|content| (p, content)))
+        .collect()
+}
+
+/// Info-and-above log lines of this run, oldest first
+pub fn recent_logs() -> Vec<String> {
+    RECENT_LOGS.lock().map(|l| l.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// Replace the values of secret-looking keys (passwords, tokens, keys) in place
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SECRET_MARKERS.iter().any(|m| key.contains(m)) && !item.is_null() {
+                    *item = json!("[redacted]");
+                } else {
+                    redact(item);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// A serializable value as JSON with secrets redacted, for logging
+pub fn redacted<T: Serialize>(value: &T) -> Value {
+    let mut value = json!(value);
+    redact(&mut value);
+    value
+}
+
+/// Skip ASCII spaces from `i`
+fn skip_spaces(bytes: &[u8], mut i: usize) -> usize {
+    while bytes.get(i) == Some(&b' ') {
+        i += 1;
+    }
+    i
+}
+
+/// A log line with the values after secret-looking keys (`password: ...`,
+/// `"token":"..."`, `api_key=...`) replaced
+pub fn redact_line(line: &str) -> String {
+    // ASCII lowercasing keeps byte offsets the same as in `line`
+    let lower = line.to_ascii_lowercase();
+    let bytes = line.as_bytes();
+    let mut redacted = String::with_capacity(line.len());
+    let mut copied = 0;
+    let mut search = 0;
+
+    while let Some((at, marker)) = SECRET_MARKERS
+        .iter()
+        .filter_map(|m| lower[search..].find(m).map(|i| (search + i, *m)))
+        .min_by_key(|(i, _)| *i)
+    {
+        search = at + marker.len();
+        // The rest of the key ("password_hash"), a closing quote, then ':' or '='
+        let mut i = search;
+        while bytes.get(i).is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'_') {
+            i += 1;
+        }
+        if bytes.get(i) == Some(&b'"') {
+            i += 1;
+        }
+        i = skip_spaces(bytes, i);
+        if !matches!(bytes.get(i), Some(b':') | Some(b'=')) {
+            continue;
+        }
+        i = skip_spaces(bytes, i + 1);
+        if line[i..].starts_with("Some(") {
+            i += "Some(".len();
+        }
+
+        let end = if bytes.get(i) == Some(&b'"') {
+            let mut j = i + 1;
+            while j < bytes.len() && bytes[j] != b'"' {
+                j += if bytes[j] == b'\\' { 2 } else { 1 };
+            }
+            (j + 1).min(bytes.len())
+        } else {
+            line[i..]
+                .find([',', ' ', '}', ')', '&', ';'])
+                .map_or(line.len(), |n| i + n)
+        };
+        let value = &line[i..end];
+        if !value.is_empty() && value != "None" && value != "null" {
+            redacted.push_str(&line[copied..i]);
+            redacted.push_str("[redacted]");
+            copied = end;
+        }
+        search = search.max(end);
+    }
+
+    redacted.push_str(&line[copied..]);
+    redacted
+}
+
+fn component_statuses(app: &AppHandle) -> Value {
+    let state = app.state::<AppState>();
+    json!({
+        "system": collect_diagnostics(&state),
+        "python_processes": state.python_processes.lock().map(|p| p.len()).unwrap_or(0),
+        "forwarder": forwarder::status().map(|s| json!(s)).unwrap_or_else(|e| json!({ "error": e })),
+        "workspace": workspace::active(),
+    })
+}
+
+/// Collect diagnostics into a zip in the data directory's `diagnostics/` folder
+pub fn generate_bundle(app: &AppHandle) -> Result<DiagnosticsBundle, String> {
+    let mut settings = load_settings().map(|s| json!(s)).unwrap_or_else(|e| json!({ "error": e }));
+    redact(&mut settings);
+
+    let crashes: Vec<Value> = crash_reports()
+        .into_iter()
+        .map(|(path, content)| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            // Reports written before log lines were redacted
+            let content: Vec<String> = content.lines().map(redact_line).collect();
+            json!({ "name": name, "content": content.join("\n") })
+        })
+        .collect();
+
+    let collected = json!({
+        "app": {
+            "version": app.package_info().version.to_string(),
+            "workspace": workspace::active(),
+        },
+        "components": component_statuses(app),
+        "settings": settings,
+        "logs": recent_logs(),
+        "errors": RECENT_ERRORS.lock().map(|l| l.iter().cloned().collect::<Vec<_>>()).unwrap_or_default(),
+        "crashes": crashes,
+    });
+
+    let dir = workspace::data_dir().join("diagnostics");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create diagnostics directory: {}", e))?;
+    let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let input = dir.join(format!("collected_{}.json", stamp));
+    let output = dir.join(format!("diagnostics_{}.zip", stamp));
+
+    fs::write(&input, collected.to_string()).map_err(|e| format!("Failed to write diagnostics: {}", e))?;
+    let result = run_python_script(
+        "python/utils/diagnostics.py",
+        &["--input", &input.to_string_lossy(), "--output", &output.to_string_lossy()],
+    );
+    let _ = fs::remove_file(&input);
+    let result = result?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        serde_json::from_value(result).map_err(|e| format!("Failed to parse diagnostics result: {}", e))
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// If opted in, bundle crash reports left by a previous run and mark them reported
+pub fn report_previous_crashes(app: &AppHandle) {
+    if !load_settings().map(|s| s.crash_reports_enabled).unwrap_or(false) {
+        return;
+    }
+
+    let crashes = crash_reports();
+    if crashes.is_empty() {
+        return;
+    }
+
+    match generate_bundle(app) {
+        Ok(bundle) => {
+            log::warn!("Previous run crashed; diagnostics saved to {}", bundle.path);
+            let reported = crash_dir().join("reported");
+            if fs::create_dir_all(&reported).is_ok() {
+                for (path, _) in crashes {
+                    if let Some(name) = path.file_name() {
+                        let _ = fs::rename(&path, reported.join(name));
+                    }
+                }
+            }
+        }
+        Err(e) => log::warn!("Failed to bundle crash reports: {}", e),
+    }
+}
@@ -0,0 +1,165 @@
+// Discovered network services
+//
+// `python/arp/service_discovery.py` collects what devices advertise over mDNS
+// (AirPlay, Chromecast, printers, SMB shares, HomeKit…) and SSDP (media
+// renderers, DIAL, gateways) and keeps it per MAC address in the data
+// directory's `device_services.json`. This reads it back for a device's
+// service list, and uses the names devices give themselves there ("Living
+// Room TV") for ones that don't have a hostname, and the kinds of service they
+// offer to type ones that are still unknown. The file is reloaded whenever it
+// changes.
+
+use crate::commands::Device;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Kinds whose advertised name best names the device, best first
+const NAMING_KINDS: &[&str] = &[
+    "chromecast",
+    "airplay",
+    "media_renderer",
+    "dial",
+    "sonos",
+    "printer",
+    "scanner",
+    "homekit",
+    "smb",
+    "file_sharing",
+    "spotify_connect",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeviceService {
+    /// "airplay", "chromecast", "printer", "smb", "media_renderer", …
+    pub kind: String,
+    /// mDNS service type ("_googlecast._tcp") or SSDP notification type
+    #[serde(rename(deserialize = "type"))]
+    pub service_type: String,
+    pub name: Option<String>,
+    pub port: Option<u16>,
+    pub host: Option<String>,
+    /// "mdns" or "ssdp"
+    pub source: String,
+    /// Friendly name ("fn"), model and the like, as advertised
+    #[serde(default)]
+    pub details: BTreeMap<String, String>,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceServices {
+    pub device_id: String,
+    pub services: Vec<DeviceService>,
+    /// Name the device goes by in its advertisements
+    pub advertised_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct StoredDevice {
+    #[serde(default)]
+    services: BTreeMap<String, DeviceService>,
+}
+
+struct Cache {
+    modified: SystemTime,
+    /// Services by lowercase MAC
+    by_mac: HashMap<String, Vec<DeviceService>>,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+fn services_path() -> PathBuf {
+    workspace::data_dir().join("device_services.json")
+}
+
+fn load(modified: SystemTime) -> Result<Cache, String> {
+    let content = fs::read_to_string(services_path()).map_err(|e| format!("Failed to read device services: {}", e))?;
+    let stored: HashMap<String, StoredDevice> =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse device services: {}", e))?;
+    let by_mac = stored
+        .into_iter()
+        .map(|(mac, device)| (mac.to_lowercase(), device.services.into_values().collect()))
+        .collect();
+    Ok(Cache { modified, by_mac })
+}
+
+/// Services a MAC address advertises, most recently seen first
+fn services_for_mac(mac: &str) -> Vec<DeviceService> {
+    let Ok(modified) = fs::metadata(services_path()).and_then(|m| m.modified()) else { return vec![] };
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.as_ref().is_none_or(|c| c.modified != modified) {
+        match load(modified) {
+            Ok(loaded) => *cache = Some(loaded),
+            Err(e) => {
+                log::debug!("{}", e);
+                return vec![];
+            }
+        }
+    }
+    let mut services = cache
+        .as_ref()
+        .and_then(|c| c.by_mac.get(&mac.to_lowercase()).cloned())
+        .unwrap_or_default();
+    services.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    services
+}
+
+/// The name a device gives itself, from the service that names it best
+pub fn advertised_name(services: &[DeviceService]) -> Option<String> {
+    NAMING_KINDS.iter().find_map(|kind| {
+        services
+            .iter()
+            .filter(|s| s.kind == *kind)
+            .find_map(|s| s.details.get("fn").or(s.name.as_ref()))
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+    })
+}
+
+/// The device type its services suggest
+pub fn advertised_type(services: &[DeviceService]) -> Option<&'static str> {
+    let offers = |kinds: &[&str]| services.iter().any(|s| kinds.contains(&s.kind.as_str()));
+    if offers(&["chromecast", "dial", "media_renderer"]) {
+        Some("smart_tv")
+    } else if offers(&["gateway"]) {
+        Some("router")
+    } else if offers(&["printer", "scanner", "homekit", "sonos", "spotify_connect"]) {
+        Some("iot")
+    } else {
+        None
+    }
+}
+
+/// Fill in a device's missing hostname and unknown type from what it advertises
+pub fn apply(device: &mut Device) {
+    if device.hostname.as_deref().is_some_and(|h| !h.is_empty()) && device.device_type != "unknown" {
+        return;
+    }
+    let services = services_for_mac(&device.mac);
+    if services.is_empty() {
+        return;
+    }
+    if device.hostname.as_deref().is_none_or(str::is_empty) {
+        device.hostname = advertised_name(&services);
+    }
+    if device.device_type == "unknown" {
+        if let Some(device_type) = advertised_type(&services) {
+            device.device_type = device_type.to_string();
+        }
+    }
+}
+
+pub fn device_services(device: &Device) -> DeviceServices {
+    let services = services_for_mac(&device.mac);
+    DeviceServices {
+        device_id: device.id.clone(),
+        advertised_name: advertised_name(&services),
+        services,
+    }
+}
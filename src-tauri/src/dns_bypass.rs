@@ -0,0 +1,165 @@
+// Encrypted DNS (DoH/DoT) bypass detection and policy
+//
+// Devices that switch to a public DoH/DoT resolver skip our DNS capture and
+// blocking entirely. The blocking engine owns the provider list and policy;
+// this module reads it back and scans captured traffic and DNS lookups for
+// devices talking to those resolvers.
+
+use crate::clock;
+use crate::database::open_readonly;
+use crate::python::run_blocking_command;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Paths and media types that identify a DoH request regardless of host (RFC 8484)
+const DOH_PATH: &str = "/dns-query";
+const DOH_CONTENT_TYPE: &str = "application/dns-message";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DnsPolicy {
+    pub block_doh: bool,
+    pub forced_dns_devices: Vec<String>,
+    pub doh_providers: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DnsBypassAttempt {
+    pub timestamp: String,
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    /// "doh" for an HTTPS resolver request, "resolver_lookup" for a plain DNS
+    /// lookup of a DoH/DoT resolver hostname (the bootstrap step for both)
+    pub kind: String,
+    pub host: String,
+    pub blocked: bool,
+    pub forced_device: bool,
+}
+
+/// Load the DNS bypass policy from the blocking engine
+pub fn load_policy() -> Result<DnsPolicy, String> {
+    let result = run_blocking_command("dns-policy", &[])?;
+    let policy = result
+        .get("policy")
+        .cloned()
+        .ok_or_else(|| "Blocking engine returned no DNS policy".to_string())?;
+
+    serde_json::from_value(policy).map_err(|e| format!("Failed to parse DNS policy: {}", e))
+}
+
+/// Extra proxy arguments that apply the policy at startup
+pub fn proxy_args(policy: &DnsPolicy) -> Vec<String> {
+    let mut args = Vec::new();
+    if policy.block_doh {
+        args.push("--block-doh".to_string());
+    }
+    for ip in &policy.forced_dns_devices {
+        args.push("--force-dns".to_string());
+        args.push(ip.clone());
+    }
+    args
+}
+
+/// Extra DNS blocker arguments that apply the policy at startup
+pub fn dns_blocker_args(policy: &DnsPolicy) -> Vec<String> {
+    let mut args = Vec::new();
+    if policy.block_doh {
+        args.push("--block-doh".to_string());
+    }
+    if !policy.forced_dns_devices.is_empty() {
+        args.push("--force-dns".to_string());
+        args.extend(policy.forced_dns_devices.iter().cloned());
+    }
+    args
+}
+
+fn is_provider(host: &str, providers: &[String]) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    providers
+        .iter()
+        .any(|p| host == *p || host.ends_with(&format!(".{}", p)))
+}
+
+/// Collect traffic and DNS rows since a timestamp, keeping those that match `is_bypass`
+fn query_attempts(
+    conn: &Connection,
+    sql: &str,
+    kind: &str,
+    since: &str,
+    device_id: Option<&str>,
+    is_bypass: impl Fn(&str, Option<&str>) -> bool,
+) -> Result<Vec<DnsBypassAttempt>, String> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![since, device_id], |row| {
+            let host: String = row.get(3)?;
+            let hint: Option<String> = row.get(4)?;
+            let attempt = DnsBypassAttempt {
+                timestamp: row.get(0)?,
+                device_id: row.get(1)?,
+                device_ip: row.get(2)?,
+                kind: kind.to_string(),
+                host,
+                blocked: row.get::<_, Option<i64>>(5)?.unwrap_or(0) != 0,
+                forced_device: false,
+            };
+            Ok((attempt, hint))
+        })
+        .map_err(|e| format!("Failed to query {}: {}", kind, e))?;
+
+    let mut attempts = Vec::new();
+    for row in rows {
+        let (attempt, hint) = row.map_err(|e| format!("Failed to read {}: {}", kind, e))?;
+        if is_bypass(&attempt.host, hint.as_deref()) {
+            attempts.push(attempt);
+        }
+    }
+    Ok(attempts)
+}
+
+/// Find DoH requests and encrypted-resolver lookups in the last `hours`
+pub fn find_bypass_attempts(
+    policy: &DnsPolicy,
+    device_id: Option<&str>,
+    hours: u32,
+) -> Result<Vec<DnsBypassAttempt>, String> {
+    let conn = open_readonly()?;
+    let since = clock::utc_cutoff(chrono::Duration::hours(hours as i64));
+
+    // `hint` is the path || content type for traffic, unused for DNS lookups
+    let mut attempts = query_attempts(
+        &conn,
+        "SELECT timestamp, device_id, device_ip, host,
+                COALESCE(path, '') || ' ' || COALESCE(request_body_type, ''), blocked
+         FROM traffic
+         WHERE timestamp >= ?1 AND (?2 IS NULL OR device_id = ?2)",
+        "doh",
+        &since,
+        device_id,
+        |host, hint| {
+            let hint = hint.unwrap_or("");
+            is_provider(host, &policy.doh_providers)
+                || hint.starts_with(DOH_PATH)
+                || hint.contains(DOH_CONTENT_TYPE)
+        },
+    )?;
+
+    attempts.extend(query_attempts(
+        &conn,
+        "SELECT timestamp, device_id, device_ip, query_name, NULL, blocked
+         FROM dns_queries
+         WHERE timestamp >= ?1 AND (?2 IS NULL OR device_id = ?2)",
+        "resolver_lookup",
+        &since,
+        device_id,
+        |host, _| is_provider(host, &policy.doh_providers),
+    )?);
+
+    for attempt in &mut attempts {
+        attempt.forced_device = policy.forced_dns_devices.contains(&attempt.device_ip);
+    }
+    attempts.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    Ok(attempts)
+}
@@ -0,0 +1,308 @@
+// DNS tunneling and exfiltration detection
+//
+// Tunnels (iodine, dnscat2, DNS exfiltration by malware) encode data in the
+// query names under a domain the attacker controls, so lookups under that
+// domain have long, random-looking, mostly unique subdomains, come in volume,
+// and often ask for TXT or NULL records to carry the reply. Captured lookups
+// are grouped by domain and scored 0–100 on those signals; domains at or above
+// the alert score raise an alert, at most once a day per domain.
+
+use crate::activity::site_of;
+use crate::clock;
+use crate::database::{self, open_readonly};
+use crate::event_bus;
+use crate::i18n::t;
+use crate::state::AppState;
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the background analysis runs
+const ANALYSIS_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Lookup window the background analysis looks at
+const ANALYSIS_HOURS: u32 = 24;
+
+/// Domains with fewer lookups aren't scored
+const MIN_QUERIES: u64 = 20;
+
+/// Domains below this score are left out of the report
+const MIN_REPORT_SCORE: u32 = 30;
+
+/// Domains at or above this score raise an alert
+const ALERT_SCORE: u32 = 70;
+
+/// Services whose hostnames are long and random by design
+const IGNORED_DOMAINS: &[&str] = &[
+    "akamaiedge.net",
+    "akamaihd.net",
+    "cloudfront.net",
+    "amazonaws.com",
+    "googlevideo.com",
+    "gvt1.com",
+    "azureedge.net",
+    "trafficmanager.net",
+    "in-addr.arpa",
+    "ip6.arpa",
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DomainAnomaly {
+    pub domain: String,
+    pub queries: u64,
+    pub unique_subdomains: usize,
+    pub avg_subdomain_length: f64,
+    pub max_subdomain_length: usize,
+    /// Mean Shannon entropy of subdomains, in bits per character
+    pub avg_entropy: f64,
+    /// Share of lookups asking for TXT or NULL records
+    pub txt_ratio: f64,
+    pub devices: Vec<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// 0–100
+    pub score: u32,
+    /// Which signals contributed, for display
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DnsAnomalyReport {
+    pub hours: u32,
+    pub queries_analyzed: u64,
+    pub domains_analyzed: usize,
+    pub domains: Vec<DomainAnomaly>,
+}
+
+#[derive(Default)]
+struct DomainStats {
+    queries: u64,
+    subdomains: HashSet<String>,
+    length_total: usize,
+    max_length: usize,
+    entropy_total: f64,
+    with_subdomain: u64,
+    txt: u64,
+    devices: HashSet<String>,
+    first_seen: String,
+    last_seen: String,
+}
+
+fn entropy(text: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    let mut total = 0;
+    for c in text.chars().filter(|c| *c != '.') {
+        *counts.entry(c).or_default() += 1;
+        total += 1;
+    }
+    if total == 0 {
+        return 0.0;
+    }
+
+    counts
+        .values()
+        .map(|&n| {
+            let p = n as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn is_ignored(domain: &str) -> bool {
+    IGNORED_DOMAINS
+        .iter()
+        .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)))
+}
+
+fn score(domain: String, stats: DomainStats) -> DomainAnomaly {
+    let with_subdomain = stats.with_subdomain.max(1) as f64;
+    let avg_length = stats.length_total as f64 / with_subdomain;
+    let avg_entropy = stats.entropy_total / with_subdomain;
+    let txt_ratio = stats.txt as f64 / stats.queries as f64;
+
+    let length_score = ((avg_length - 20.0) / 30.0).clamp(0.0, 1.0);
+    let entropy_score = ((avg_entropy - 3.0) / 1.5).clamp(0.0, 1.0);
+    let volume_score = (stats.subdomains.len() as f64 / 200.0).clamp(0.0, 1.0);
+    let txt_score = txt_ratio.clamp(0.0, 1.0);
+
+    let mut reasons = Vec::new();
+    if length_score > 0.5 {
+        reasons.push(t("report.dns_tunneling.long_names", &[("length", &format!("{:.0}", avg_length))]));
+    }
+    if entropy_score > 0.5 {
+        reasons.push(t("report.dns_tunneling.high_entropy", &[("entropy", &format!("{:.1}", avg_entropy))]));
+    }
+    if volume_score > 0.5 {
+        reasons.push(t("report.dns_tunneling.many_subdomains", &[("count", &stats.subdomains.len())]));
+    }
+    if txt_score > 0.5 {
+        reasons.push(t("report.dns_tunneling.txt_lookups", &[("percent", &format!("{:.0}", txt_ratio * 100.0))]));
+    }
+
+    let mut devices: Vec<String> = stats.devices.into_iter().collect();
+    devices.sort();
+
+    DomainAnomaly {
+        domain,
+        queries: stats.queries,
+        unique_subdomains: stats.subdomains.len(),
+        avg_subdomain_length: (avg_length * 10.0).round() / 10.0,
+        max_subdomain_length: stats.max_length,
+        avg_entropy: (avg_entropy * 100.0).round() / 100.0,
+        txt_ratio: (txt_ratio * 100.0).round() / 100.0,
+        devices,
+        first_seen: stats.first_seen,
+        last_seen: stats.last_seen,
+        score: (100.0 * (0.25 * length_score + 0.25 * entropy_score + 0.3 * volume_score + 0.2 * txt_score))
+            .round() as u32,
+        reasons,
+    }
+}
+
+/// Score DNS lookups in the last `hours` per domain, most suspicious first
+pub fn report(device_id: Option<&str>, hours: u32) -> Result<DnsAnomalyReport, String> {
+    let conn = open_readonly()?;
+    let since = clock::utc_cutoff(chrono::Duration::hours(hours as i64));
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT query_name, COALESCE(query_type, 'A'), device_id, device_ip, timestamp
+             FROM dns_queries
+             WHERE timestamp >= ?1 AND (?2 IS NULL OR device_id = ?2)
+             ORDER BY timestamp",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![since, device_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query DNS lookups: {}", e))?;
+
+    let mut domains: HashMap<String, DomainStats> = HashMap::new();
+    let mut total = 0;
+    for row in rows {
+        let (name, query_type, device_id, device_ip, timestamp) =
+            row.map_err(|e| format!("Failed to read DNS lookup: {}", e))?;
+        let name = name.trim_end_matches('.').to_lowercase();
+        let domain = site_of(&name);
+        total += 1;
+        if domain.is_empty() || is_ignored(&domain) {
+            continue;
+        }
+
+        let stats = domains.entry(domain.clone()).or_default();
+        stats.queries += 1;
+        if stats.first_seen.is_empty() {
+            stats.first_seen = timestamp.clone();
+        }
+        stats.last_seen = timestamp;
+        stats.devices.insert(device_id.unwrap_or(device_ip));
+        if matches!(query_type.to_uppercase().as_str(), "TXT" | "NULL") {
+            stats.txt += 1;
+        }
+
+        let subdomain = name
+            .strip_suffix(&domain)
+            .map(|s| s.trim_end_matches('.'))
+            .unwrap_or("");
+        if !subdomain.is_empty() {
+            stats.with_subdomain += 1;
+            stats.length_total += subdomain.len();
+            stats.max_length = stats.max_length.max(subdomain.len());
+            stats.entropy_total += entropy(subdomain);
+            stats.subdomains.insert(subdomain.to_string());
+        }
+    }
+
+    let domains_analyzed = domains.len();
+    let mut anomalies: Vec<DomainAnomaly> = domains
+        .into_iter()
+        .filter(|(_, stats)| stats.queries >= MIN_QUERIES)
+        .map(|(domain, stats)| score(domain, stats))
+        .filter(|a| a.score >= MIN_REPORT_SCORE)
+        .collect();
+    anomalies.sort_by(|a, b| b.score.cmp(&a.score).then(b.queries.cmp(&a.queries)));
+
+    Ok(DnsAnomalyReport {
+        hours,
+        queries_analyzed: total,
+        domains_analyzed,
+        domains: anomalies,
+    })
+}
+
+/// Alert on high-scoring domains not already alerted in the last day
+fn raise_alerts(report: &DnsAnomalyReport) {
+    let since = clock::utc_cutoff(chrono::Duration::hours(24));
+    let recent: HashSet<String> = database::stored_alerts()
+        .iter()
+        .filter(|a| a.pointer("/metadata/dns_tunneling").and_then(|b| b.as_bool()) == Some(true))
+        .filter(|a| a.get("timestamp").and_then(|t| t.as_str()).is_some_and(|t| t >= since.as_str()))
+        .filter_map(|a| a.pointer("/metadata/domain").and_then(|d| d.as_str()).map(|d| d.to_string()))
+        .collect();
+
+    for anomaly in report.domains.iter().filter(|a| a.score >= ALERT_SCORE) {
+        if recent.contains(&anomaly.domain) {
+            continue;
+        }
+
+        let description = t(
+            "alert.dns_tunneling.description",
+            &[
+                ("queries", &anomaly.queries),
+                ("domain", &anomaly.domain),
+                ("devices", &anomaly.devices.join(", ")),
+                ("reasons", &anomaly.reasons.join("; ")),
+                ("score", &anomaly.score),
+            ],
+        );
+        let mut metadata = json!(anomaly);
+        metadata["dns_tunneling"] = json!(true);
+        // Devices without a known ID are listed by IP, which can't be attached to the alert
+        let device = match anomaly.devices.as_slice() {
+            [only] if only.parse::<std::net::IpAddr>().is_err() => Some(only.as_str()),
+            _ => None,
+        };
+
+        log::warn!("Possible DNS tunneling via {} (score {})", anomaly.domain, anomaly.score);
+        event_bus::raise_alert(
+            "high",
+            "security",
+            &t("alert.dns_tunneling.title", &[("domain", &anomaly.domain)]),
+            &description,
+            device,
+            Some(&metadata),
+        );
+    }
+}
+
+/// Spawn the background task that looks for DNS tunneling while monitoring runs
+pub fn spawn_tunnel_detector(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(ANALYSIS_INTERVAL).await;
+
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if !handle.state::<AppState>().lifecycle.current().is_running() {
+                    return;
+                }
+                match report(None, ANALYSIS_HOURS) {
+                    Ok(report) => raise_alerts(&report),
+                    Err(e) => log::debug!("DNS tunneling analysis failed: {}", e),
+                }
+            })
+            .await;
+        }
+    });
+}
@@ -0,0 +1,322 @@
+// Drill-down into one domain
+//
+// Everything stored about a domain and its subdomains in one place: which
+// devices contacted it and when, request counts per day, the categories it was
+// filed under, threat intelligence hits and the addresses it resolved to.
+// Requests come from raw traffic and its rolled-up aggregates, addresses from
+// DNS lookups and hits from the alert store.
+
+use crate::apps;
+use crate::clock::{self, Zone};
+use crate::database::{self, open_readonly};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DomainDevice {
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub hostname: Option<String>,
+    pub requests: u64,
+    pub bytes: u64,
+    pub blocked: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DomainHost {
+    pub host: String,
+    pub requests: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DomainDay {
+    /// Local date, "YYYY-MM-DD"
+    pub date: String,
+    pub requests: u64,
+    pub bytes: u64,
+    pub blocked: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DomainCategory {
+    pub category: String,
+    pub requests: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ThreatHit {
+    pub alert_id: String,
+    pub timestamp: String,
+    pub severity: String,
+    pub title: String,
+    pub device_id: Option<String>,
+    /// The listed domain or IP that matched
+    pub indicator: Option<String>,
+    pub feeds: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolvedIp {
+    pub ip: String,
+    pub lookups: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DomainDetails {
+    pub domain: String,
+    /// Known application the domain belongs to
+    pub app: Option<String>,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+    pub total_requests: u64,
+    pub total_bytes: u64,
+    pub blocked_requests: u64,
+    pub dns_lookups: u64,
+    /// The domain and subdomains seen, busiest first
+    pub hosts: Vec<DomainHost>,
+    /// Devices that contacted it, busiest first
+    pub devices: Vec<DomainDevice>,
+    /// Requests per local day, oldest first
+    pub timeline: Vec<DomainDay>,
+    pub categories: Vec<DomainCategory>,
+    /// Alerts of any kind about the domain
+    pub alert_count: usize,
+    pub threat_hits: Vec<ThreatHit>,
+    pub resolved_ips: Vec<ResolvedIp>,
+}
+
+/// Whether a host is the domain or one of its subdomains
+fn in_domain(host: &str, domain: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+fn threat_hit(alert: &Value, zone: Zone) -> ThreatHit {
+    let text = |key: &str| alert.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let metadata = alert.get("metadata");
+    ThreatHit {
+        alert_id: text("id").unwrap_or_default(),
+        timestamp: zone.display(&text("timestamp").unwrap_or_default()),
+        severity: text("severity").unwrap_or_default(),
+        title: text("title").unwrap_or_default(),
+        device_id: text("source_device"),
+        indicator: metadata
+            .and_then(|m| m.get("indicator"))
+            .and_then(|i| i.as_str())
+            .map(str::to_string),
+        feeds: metadata
+            .and_then(|m| m.get("feeds"))
+            .and_then(|f| f.as_array())
+            .map(|feeds| feeds.iter().filter_map(|f| f.as_str().map(str::to_string)).collect())
+            .unwrap_or_default(),
+    }
+}
+
+#[derive(Default)]
+struct DeviceAccumulator {
+    requests: u64,
+    bytes: u64,
+    blocked: u64,
+    first_seen: String,
+    last_seen: String,
+}
+
+/// Devices, timeline, categories, threat hits and resolved addresses for a domain
+pub fn details(domain: &str) -> Result<DomainDetails, String> {
+    let conn = open_readonly()?;
+    details_from(&conn, domain, &database::stored_alerts(), Zone::configured())
+}
+
+/// `details` against an open database and a set of stored alerts
+pub fn details_from(conn: &Connection, domain: &str, stored_alerts: &[Value], zone: Zone) -> Result<DomainDetails, String> {
+    let domain = domain.trim().trim_end_matches('.').to_lowercase();
+    if domain.is_empty() {
+        return Err("No domain given".to_string());
+    }
+
+    // One row per device, host, category and UTC hour
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, device_ip, host, COALESCE(category, ''), substr(timestamp, 1, 13),
+                    SUM(requests), SUM(bytes), SUM(blocked), MIN(timestamp), MAX(timestamp)
+             FROM (
+                 SELECT device_id, device_ip, host, category, timestamp, 1 AS requests,
+                        COALESCE(request_size, 0) + COALESCE(response_size, 0) AS bytes,
+                        COALESCE(blocked, 0) AS blocked
+                 FROM traffic
+                 WHERE lower(host) = ?1 OR substr(lower(host), -length(?1) - 1) = '.' || ?1
+                 UNION ALL
+                 SELECT device_id, device_ip, host, category, minute, requests, bytes_in + bytes_out, blocked
+                 FROM traffic_aggregates
+                 WHERE lower(host) = ?1 OR substr(lower(host), -length(?1) - 1) = '.' || ?1
+             )
+             GROUP BY device_id, device_ip, host, category, substr(timestamp, 1, 13)",
+        )
+        .map_err(|e| format!("Failed to prepare domain query: {}", e))?;
+    let rows = stmt
+        .query_map(params![domain], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<i64>>(5)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(6)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(7)?.unwrap_or(0) as u64,
+                row.get::<_, String>(8)?,
+                row.get::<_, String>(9)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query domain traffic: {}", e))?;
+
+    let mut devices: HashMap<(Option<String>, String), DeviceAccumulator> = HashMap::new();
+    let mut hosts: HashMap<String, u64> = HashMap::new();
+    let mut categories: HashMap<String, u64> = HashMap::new();
+    let mut days: BTreeMap<String, DomainDay> = BTreeMap::new();
+
+    for row in rows {
+        let (device_id, device_ip, host, category, hour, requests, bytes, blocked, first, last) =
+            row.map_err(|e| format!("Failed to read domain traffic: {}", e))?;
+
+        let device = devices.entry((device_id, device_ip)).or_default();
+        device.requests += requests;
+        device.bytes += bytes;
+        device.blocked += blocked;
+        if device.first_seen.is_empty() || first < device.first_seen {
+            device.first_seen = first;
+        }
+        if last > device.last_seen {
+            device.last_seen = last;
+        }
+
+        *hosts.entry(host.to_lowercase()).or_default() += requests;
+        if !category.is_empty() {
+            *categories.entry(category).or_default() += requests;
+        }
+
+        if let Some(at) = clock::parse_stored(&format!("{}:00", hour)) {
+            let date = zone.at(at).date_naive().format("%Y-%m-%d").to_string();
+            let day = days.entry(date.clone()).or_insert_with(|| DomainDay {
+                date,
+                requests: 0,
+                bytes: 0,
+                blocked: 0,
+            });
+            day.requests += requests;
+            day.bytes += bytes;
+            day.blocked += blocked;
+        }
+    }
+
+    let hostnames: HashMap<String, String> = conn
+        .prepare("SELECT id, hostname FROM devices WHERE hostname IS NOT NULL AND hostname != ''")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(|e| format!("Failed to query device names: {}", e))?;
+
+    let first_seen = devices.values().map(|d| d.first_seen.clone()).min();
+    let last_seen = devices.values().map(|d| d.last_seen.clone()).max();
+
+    let mut devices: Vec<DomainDevice> = devices
+        .into_iter()
+        .map(|((device_id, device_ip), d)| DomainDevice {
+            hostname: device_id.as_ref().and_then(|id| hostnames.get(id).cloned()),
+            device_id,
+            device_ip,
+            requests: d.requests,
+            bytes: d.bytes,
+            blocked: d.blocked,
+            first_seen: zone.display(&d.first_seen),
+            last_seen: zone.display(&d.last_seen),
+        })
+        .collect();
+    devices.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.device_ip.cmp(&b.device_ip)));
+
+    let mut hosts: Vec<DomainHost> = hosts
+        .into_iter()
+        .map(|(host, requests)| DomainHost { host, requests })
+        .collect();
+    hosts.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.host.cmp(&b.host)));
+
+    let mut categories: Vec<DomainCategory> = categories
+        .into_iter()
+        .map(|(category, requests)| DomainCategory { category, requests })
+        .collect();
+    categories.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.category.cmp(&b.category)));
+
+    // Addresses the domain and its subdomains resolved to
+    let mut stmt = conn
+        .prepare(
+            "SELECT response_ip, COUNT(*), MIN(timestamp), MAX(timestamp)
+             FROM dns_queries
+             WHERE (rtrim(lower(query_name), '.') = ?1 OR substr(rtrim(lower(query_name), '.'), -length(?1) - 1) = '.' || ?1)
+               AND response_ip IS NOT NULL AND response_ip != ''
+             GROUP BY response_ip
+             ORDER BY COUNT(*) DESC",
+        )
+        .map_err(|e| format!("Failed to prepare DNS query: {}", e))?;
+    let resolved_ips = stmt
+        .query_map(params![domain], |row| {
+            Ok(ResolvedIp {
+                ip: row.get(0)?,
+                lookups: row.get::<_, i64>(1)? as u64,
+                first_seen: zone.display(&row.get::<_, String>(2)?),
+                last_seen: zone.display(&row.get::<_, String>(3)?),
+            })
+        })
+        .map_err(|e| format!("Failed to query resolved addresses: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read resolved addresses: {}", e))?;
+
+    let dns_lookups: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM dns_queries
+             WHERE rtrim(lower(query_name), '.') = ?1 OR substr(rtrim(lower(query_name), '.'), -length(?1) - 1) = '.' || ?1",
+            params![domain],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count DNS lookups: {}", e))?;
+
+    let alerts: Vec<&Value> = stored_alerts
+        .iter()
+        .filter(|a| a.get("domain").and_then(|d| d.as_str()).is_some_and(|d| in_domain(d, &domain)))
+        .collect();
+    let mut threat_hits: Vec<ThreatHit> = alerts
+        .iter()
+        .filter(|a| {
+            a.get("metadata")
+                .and_then(|m| m.get("threat_intel"))
+                .and_then(|t| t.as_bool())
+                .unwrap_or(false)
+        })
+        .map(|a| threat_hit(a, zone))
+        .collect();
+    threat_hits.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+    Ok(DomainDetails {
+        app: apps::recognize(&domain).map(|app| app.name.to_string()),
+        first_seen: first_seen.map(|t| zone.display(&t)),
+        last_seen: last_seen.map(|t| zone.display(&t)),
+        total_requests: devices.iter().map(|d| d.requests).sum(),
+        total_bytes: devices.iter().map(|d| d.bytes).sum(),
+        blocked_requests: devices.iter().map(|d| d.blocked).sum(),
+        dns_lookups: dns_lookups as u64,
+        hosts,
+        devices,
+        timeline: days.into_values().collect(),
+        categories,
+        alert_count: alerts.len(),
+        threat_hits,
+        resolved_ips,
+        domain,
+    })
+}
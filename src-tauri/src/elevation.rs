@@ -0,0 +1,93 @@
+// Elevation hand-off between the unprivileged and relaunched elevated instance
+//
+// The unprivileged instance writes its state to a hand-off file and launches
+// the elevated copy, which removes the file when it picks the state up. Only
+// then does the unprivileged instance stop its capture and exit; if the prompt
+// is cancelled or the copy never starts, it discards the file and keeps running.
+
+use crate::i18n::t;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Child;
+use std::time::{Duration, Instant};
+
+/// Argument marking an instance started by `request_elevation`
+pub const ELEVATED_ARG: &str = "--elevated";
+
+/// How long the user has to answer the prompt and the copy to start
+const PICKUP_TIMEOUT: Duration = Duration::from_secs(120);
+
+const PICKUP_POLL: Duration = Duration::from_millis(200);
+
+/// State carried over to the elevated instance
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ElevationHandoff {
+    pub was_monitoring: bool,
+    pub current_profile: String,
+    pub created_at: String,
+}
+
+fn handoff_path() -> PathBuf {
+    crate::workspace::root()
+        .join("config")
+        .join("elevation_handoff.json")
+}
+
+/// Check whether this process was relaunched for elevation
+pub fn launched_elevated() -> bool {
+    std::env::args().any(|arg| arg == ELEVATED_ARG)
+}
+
+/// Persist state for the elevated instance to pick up
+pub fn write_handoff(handoff: &ElevationHandoff) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(handoff)
+        .map_err(|e| format!("Failed to serialize handoff: {}", e))?;
+    fs::write(handoff_path(), content)
+        .map_err(|e| format!("Failed to write handoff: {}", e))
+}
+
+/// Remove a hand-off no elevated instance is going to take
+pub fn discard_handoff() {
+    let _ = fs::remove_file(handoff_path());
+}
+
+/// Read and remove the hand-off file, if one was left for us
+pub fn take_handoff() -> Option<ElevationHandoff> {
+    let path = handoff_path();
+    let content = fs::read_to_string(&path).ok()?;
+    let _ = fs::remove_file(&path);
+
+    serde_json::from_str(&content)
+        .map_err(|e| log::warn!("Ignoring invalid elevation handoff: {}", e))
+        .ok()
+}
+
+/// Wait for the elevated instance to take the hand-off, given the launcher that started it
+pub fn wait_for_pickup(mut launcher: Child) -> Result<(), String> {
+    let path = handoff_path();
+    let deadline = Instant::now() + PICKUP_TIMEOUT;
+
+    let result = loop {
+        if !path.exists() {
+            break Ok(());
+        }
+        match launcher.try_wait() {
+            Ok(Some(status)) if !status.success() => {
+                log::info!("Elevation prompt was cancelled ({})", status);
+                break Err(t("error.elevation_cancelled", &[]));
+            }
+            Ok(_) => {}
+            Err(e) => break Err(format!("Failed to check elevation launcher: {}", e)),
+        }
+        if Instant::now() >= deadline {
+            break Err(t("error.elevation_timed_out", &[]));
+        }
+        std::thread::sleep(PICKUP_POLL);
+    };
+
+    if result.is_err() {
+        discard_handoff();
+    }
+    result
+}
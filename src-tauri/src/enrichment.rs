@@ -0,0 +1,329 @@
+// Enrichment lookup cache
+//
+// Vendor (MAC OUI), geolocation and category lookups are repeated for the same
+// few keys while thousands of entries are parsed. Results are kept in two
+// tiers: an in-memory LRU per kind, and `enrichment_cache.db` next to the
+// capture database so they survive restarts. "Nothing found" is cached too.
+// New results reach the disk in batches, flushed once FLUSH_BATCH are pending
+// and every FLUSH_INTERVAL. Hits per tier and misses are counted for the stats
+// command and the metrics endpoint.
+//
+// Category lookups come from tables built into the app, so persisted ones are
+// dropped when the app version changes.
+
+use crate::clock;
+use crate::workspace;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Entries kept in memory per kind
+const MEMORY_CAPACITY: usize = 10_000;
+
+/// Pending disk writes that trigger a flush
+const FLUSH_BATCH: usize = 256;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Vendor,
+    Geo,
+    Category,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 3] = [Kind::Vendor, Kind::Geo, Kind::Category];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Vendor => "vendor",
+            Kind::Geo => "geo",
+            Kind::Category => "category",
+        }
+    }
+}
+
+/// Least recently used entries go first once `MEMORY_CAPACITY` is reached
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, (Option<String>, u64)>,
+    /// Keys by last use
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Lru {
+    fn get(&mut self, key: &str) -> Option<Option<String>> {
+        let (value, used) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        self.tick += 1;
+        *used = self.tick;
+        self.order.insert(self.tick, key.to_string());
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: String, value: Option<String>) {
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(self.tick, key);
+        while self.entries.len() > MEMORY_CAPACITY {
+            let Some((_, oldest)) = self.order.pop_first() else { break };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Counters {
+    memory_hits: u64,
+    disk_hits: u64,
+    misses: u64,
+}
+
+struct Disk {
+    path: PathBuf,
+    conn: Connection,
+}
+
+#[derive(Default)]
+struct Cache {
+    memory: HashMap<Kind, Lru>,
+    counters: HashMap<Kind, Counters>,
+    disk: Option<Disk>,
+    /// Results not yet written to disk
+    pending: Vec<(Kind, String, Option<String>)>,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KindStats {
+    pub kind: Kind,
+    pub entries_in_memory: usize,
+    pub memory_hits: u64,
+    pub disk_hits: u64,
+    pub misses: u64,
+    /// Share of lookups answered from either tier, 0.0–1.0
+    pub hit_rate: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EnrichmentCacheStats {
+    pub kinds: Vec<KindStats>,
+    pub entries_on_disk: u64,
+    pub hit_rate: f64,
+}
+
+fn hit_rate(counters: &Counters) -> f64 {
+    let hits = counters.memory_hits + counters.disk_hits;
+    let total = hits + counters.misses;
+    if total == 0 {
+        0.0
+    } else {
+        hits as f64 / total as f64
+    }
+}
+
+fn open_disk(path: PathBuf) -> Result<Disk, String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    let conn = Connection::open(&path).map_err(|e| format!("Failed to open enrichment cache: {}", e))?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(|e| format!("Failed to set busy timeout: {}", e))?;
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;
+         CREATE TABLE IF NOT EXISTS entries (
+             kind TEXT NOT NULL,
+             key TEXT NOT NULL,
+             value TEXT,
+             version TEXT NOT NULL,
+             updated_at TEXT NOT NULL,
+             PRIMARY KEY (kind, key)
+         );",
+    )
+    .map_err(|e| format!("Failed to create enrichment cache: {}", e))?;
+    conn.execute(
+        "DELETE FROM entries WHERE kind = ?1 AND version != ?2",
+        params![Kind::Category.as_str(), APP_VERSION],
+    )
+    .map_err(|e| format!("Failed to drop stale enrichment entries: {}", e))?;
+    Ok(Disk { path, conn })
+}
+
+impl Cache {
+    /// The disk tier of the active workspace, reopened after a workspace switch
+    fn disk(&mut self) -> Option<&Connection> {
+        let path = workspace::data_dir().join("enrichment_cache.db");
+        if self.disk.as_ref().is_none_or(|disk| disk.path != path) {
+            self.flush();
+            self.disk = match open_disk(path) {
+                Ok(disk) => Some(disk),
+                Err(e) => {
+                    log::warn!("Enrichment cache is memory only: {}", e);
+                    None
+                }
+            };
+        }
+        self.disk.as_ref().map(|disk| &disk.conn)
+    }
+
+    fn read_disk(&mut self, kind: Kind, key: &str) -> Option<Option<String>> {
+        let conn = self.disk()?;
+        let mut stmt = conn
+            .prepare_cached("SELECT value FROM entries WHERE kind = ?1 AND key = ?2")
+            .ok()?;
+        stmt.query_row(params![kind.as_str(), key], |row| row.get::<_, Option<String>>(0))
+            .optional()
+            .ok()
+            .flatten()
+    }
+
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let Some(disk) = self.disk.as_mut() else {
+            self.pending.clear();
+            return;
+        };
+
+        let now = clock::now_utc();
+        let written = (|| -> rusqlite::Result<()> {
+            let tx = disk.conn.transaction()?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT OR REPLACE INTO entries (kind, key, value, version, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                )?;
+                for (kind, key, value) in &self.pending {
+                    stmt.execute(params![kind.as_str(), key, value, APP_VERSION, now])?;
+                }
+            }
+            tx.commit()
+        })();
+        if let Err(e) = written {
+            log::warn!("Failed to write enrichment cache: {}", e);
+        }
+        self.pending.clear();
+    }
+}
+
+/// Run `f` on the cache, creating it on first use
+fn with_cache<T>(f: impl FnOnce(&mut Cache) -> T) -> T {
+    let mut cache = CACHE.lock().unwrap();
+    f(cache.get_or_insert_with(Cache::default))
+}
+
+/// The cached result of looking `key` up, or `compute`'s result (cached for
+/// next time). The cache isn't locked while `compute` runs.
+pub fn lookup(kind: Kind, key: &str, compute: impl FnOnce(&str) -> Option<String>) -> Option<String> {
+    let cached = with_cache(|cache| {
+        if let Some(value) = cache.memory.entry(kind).or_default().get(key) {
+            cache.counters.entry(kind).or_default().memory_hits += 1;
+            return Some(value);
+        }
+        let value = cache.read_disk(kind, key)?;
+        cache.counters.entry(kind).or_default().disk_hits += 1;
+        cache.memory.entry(kind).or_default().insert(key.to_string(), value.clone());
+        Some(value)
+    });
+    if let Some(value) = cached {
+        return value;
+    }
+
+    let value = compute(key);
+    with_cache(|cache| {
+        cache.counters.entry(kind).or_default().misses += 1;
+        cache.memory.entry(kind).or_default().insert(key.to_string(), value.clone());
+        cache.pending.push((kind, key.to_string(), value.clone()));
+        if cache.pending.len() >= FLUSH_BATCH {
+            cache.flush();
+        }
+    });
+    value
+}
+
+pub fn stats() -> EnrichmentCacheStats {
+    with_cache(|cache| {
+        let entries_on_disk = cache
+            .disk()
+            .and_then(|conn| conn.query_row("SELECT COUNT(*) FROM entries", [], |row| row.get::<_, i64>(0)).ok())
+            .unwrap_or(0) as u64;
+
+        let mut total = Counters::default();
+        let kinds = Kind::ALL
+            .iter()
+            .map(|&kind| {
+                let counters = cache.counters.get(&kind).copied().unwrap_or_default();
+                total.memory_hits += counters.memory_hits;
+                total.disk_hits += counters.disk_hits;
+                total.misses += counters.misses;
+                KindStats {
+                    kind,
+                    entries_in_memory: cache.memory.get(&kind).map_or(0, |lru| lru.entries.len()),
+                    memory_hits: counters.memory_hits,
+                    disk_hits: counters.disk_hits,
+                    misses: counters.misses,
+                    hit_rate: hit_rate(&counters),
+                }
+            })
+            .collect();
+
+        EnrichmentCacheStats { kinds, entries_on_disk: entries_on_disk + cache.pending.len() as u64, hit_rate: hit_rate(&total) }
+    })
+}
+
+/// Drop cached results of one kind (or every kind) from both tiers, and reset their counts
+pub fn clear(kind: Option<Kind>) -> Result<(), String> {
+    with_cache(|cache| {
+        let kinds: Vec<Kind> = kind.map_or(Kind::ALL.to_vec(), |kind| vec![kind]);
+        cache.pending.retain(|(pending, _, _)| !kinds.contains(pending));
+        for kind in &kinds {
+            cache.memory.remove(kind);
+            cache.counters.remove(kind);
+        }
+
+        if let Some(conn) = cache.disk() {
+            for kind in &kinds {
+                conn.execute("DELETE FROM entries WHERE kind = ?1", params![kind.as_str()])
+                    .map_err(|e| format!("Failed to clear enrichment cache: {}", e))?;
+            }
+        }
+        log::info!("Cleared enrichment cache ({})", kind.map_or("all kinds", Kind::as_str));
+        Ok(())
+    })
+}
+
+/// Spawn the background task that writes pending results to disk
+pub fn spawn_flusher() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            with_cache(Cache::flush);
+        }
+    });
+}
+
+/// Lookups per kind and outcome ("memory_hit", "disk_hit", "miss"), for metrics
+pub fn lookup_counts() -> Vec<(Kind, &'static str, u64)> {
+    with_cache(|cache| {
+        Kind::ALL
+            .iter()
+            .flat_map(|&kind| {
+                let c = cache.counters.get(&kind).copied().unwrap_or_default();
+                [(kind, "memory_hit", c.memory_hits), (kind, "disk_hit", c.disk_hits), (kind, "miss", c.misses)]
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,210 @@
+// Internal event bus
+//
+// Supervisors, analyzers and the change watcher publish what happens to one
+// broadcast channel instead of each talking to the window, the integrations or
+// the alert store. Four bridge tasks take it from there:
+//
+// - the window bridge emits events to the frontend as Tauri events,
+// - the notification bridge hands device and alert changes to the notification
+//   channels (hooks, MQTT, Home Assistant, the SIEM forwarder),
+// - the persistence bridge stores alerts raised by Rust-side detectors through
+//   the alert engine,
+// - the evidence bridge captures the traffic and DNS behind each new alert.
+//
+// Each bridge handles events in order on its own. One that falls more than
+// CAPACITY events behind skips the ones it missed and logs how many. Device and
+// alert changes come from the change watcher, which diffs the device snapshot
+// and the alert store every WATCH_INTERVAL; a stored alert reaches the
+// notification channels from there, whoever raised it.
+
+use crate::alert_evidence;
+use crate::commands::load_settings;
+use crate::database;
+use crate::events::{EventTracker, NetworkEvent};
+use crate::forwarder;
+use crate::home_assistant;
+use crate::hooks;
+use crate::lifecycle::{LifecycleTransition, LIFECYCLE_EVENT};
+use crate::mqtt;
+use crate::python::create_alert;
+use crate::state::AppState;
+use serde_json::{json, Value};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+
+/// Events a bridge may fall behind by before it starts skipping
+const CAPACITY: usize = 1024;
+
+/// How often the change watcher diffs devices and alerts
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Event emitted to the window for device and alert changes
+pub const NETWORK_EVENT: &str = "network-event";
+
+#[derive(Debug, Clone)]
+pub struct RaisedAlert {
+    pub severity: String,
+    pub category: String,
+    pub title: String,
+    pub description: String,
+    pub device_id: Option<String>,
+    pub metadata: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BusEvent {
+    /// A monitoring lifecycle transition
+    Lifecycle(LifecycleTransition),
+    /// A new device, presence change or newly stored alert
+    Network(NetworkEvent),
+    /// An alert from a Rust-side detector, not stored yet
+    AlertRaised(RaisedAlert),
+}
+
+static BUS: OnceLock<Sender<BusEvent>> = OnceLock::new();
+
+static TRACKER: Mutex<Option<EventTracker>> = Mutex::new(None);
+
+fn sender() -> &'static Sender<BusEvent> {
+    BUS.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+/// A receiver for every event published from now on
+pub fn subscribe() -> Receiver<BusEvent> {
+    sender().subscribe()
+}
+
+/// Publish an event to every bridge; it is dropped if none is running yet
+pub fn publish(event: BusEvent) {
+    let _ = sender().send(event);
+}
+
+/// Publish an alert for the persistence bridge to store
+pub fn raise_alert(
+    severity: &str,
+    category: &str,
+    title: &str,
+    description: &str,
+    device_id: Option<&str>,
+    metadata: Option<&Value>,
+) {
+    publish(BusEvent::AlertRaised(RaisedAlert {
+        severity: severity.to_string(),
+        category: category.to_string(),
+        title: title.to_string(),
+        description: description.to_string(),
+        device_id: device_id.map(str::to_string),
+        metadata: metadata.cloned(),
+    }));
+}
+
+/// Spawn a bridge task that runs `handle` on every event, off the async runtime
+fn spawn_bridge(app: AppHandle, name: &'static str, handle: fn(&AppHandle, BusEvent)) {
+    let mut events = subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let app = app.clone();
+                    let _ = tauri::async_runtime::spawn_blocking(move || handle(&app, event)).await;
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Event bus: {} bridge fell behind and skipped {} events", name, skipped)
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+fn to_window(app: &AppHandle, event: BusEvent) {
+    let emitted = match event {
+        BusEvent::Lifecycle(transition) => app.emit(LIFECYCLE_EVENT, transition),
+        BusEvent::Network(event) => app.emit(NETWORK_EVENT, json!({ "event": event.name(), "data": event.data() })),
+        // Reaches the window as a network event once it is stored
+        BusEvent::AlertRaised(_) => return,
+    };
+    if let Err(e) = emitted {
+        log::warn!("Failed to emit event: {}", e);
+    }
+}
+
+fn to_channels(_app: &AppHandle, event: BusEvent) {
+    let BusEvent::Network(event) = event else { return };
+    for (channel, delivered) in [
+        ("Hook", hooks::notify(&event)),
+        ("MQTT", mqtt::notify(&event)),
+        ("Home Assistant", home_assistant::notify(&event)),
+        ("SIEM", forwarder::notify(&event)),
+    ] {
+        if let Err(e) = delivered {
+            log::debug!("{} notification failed: {}", channel, e);
+        }
+    }
+}
+
+fn to_store(_app: &AppHandle, event: BusEvent) {
+    let BusEvent::AlertRaised(alert) = event else { return };
+    if let Err(e) = create_alert(
+        &alert.severity,
+        &alert.category,
+        &alert.title,
+        &alert.description,
+        alert.device_id.as_deref(),
+        alert.metadata.as_ref(),
+    ) {
+        log::warn!("Failed to store alert \"{}\": {}", alert.title, e);
+    }
+}
+
+fn to_evidence(_app: &AppHandle, event: BusEvent) {
+    let BusEvent::Network(NetworkEvent::Alert(alert)) = event else { return };
+    let settings = match load_settings() {
+        Ok(settings) => settings.alert_evidence,
+        Err(e) => {
+            log::warn!("No evidence captured for a new alert: {}", e);
+            return;
+        }
+    };
+    if !settings.enabled {
+        return;
+    }
+    if let Err(e) = alert_evidence::capture(&alert, settings.screenshots) {
+        log::warn!("Failed to capture alert evidence: {}", e);
+    }
+}
+
+/// Publish device and alert changes since the last pass
+fn watch_changes(state: &AppState) -> Result<(), String> {
+    let devices = state.snapshot.devices(false)?.data;
+    let mut tracker = TRACKER.lock().unwrap();
+    let tracker = tracker.get_or_insert_with(EventTracker::default);
+    for event in tracker.update(&devices, &database::stored_alerts()) {
+        publish(BusEvent::Network(event));
+    }
+    Ok(())
+}
+
+/// Spawn the bridges, then the change watcher feeding them
+pub fn spawn(app: AppHandle) {
+    spawn_bridge(app.clone(), "window", to_window);
+    spawn_bridge(app.clone(), "notification", to_channels);
+    spawn_bridge(app.clone(), "persistence", to_store);
+    spawn_bridge(app.clone(), "evidence", to_evidence);
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = watch_changes(&handle.state::<AppState>()) {
+                    log::debug!("Change watch failed: {}", e);
+                }
+            })
+            .await;
+
+            tokio::time::sleep(WATCH_INTERVAL).await;
+        }
+    });
+}
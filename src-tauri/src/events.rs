@@ -0,0 +1,89 @@
+// Device and alert change tracking
+//
+// The event bus's change watcher diffs the device snapshot and the alert store
+// against its last pass and publishes what changed; the notification channels
+// (hooks, MQTT, Home Assistant, ...) get those events from the bus.
+
+use crate::commands::Device;
+use crate::workspace;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub enum NetworkEvent {
+    NewDevice(Device),
+    DeviceOnline(Device),
+    DeviceOffline(Device),
+    Alert(Value),
+}
+
+impl NetworkEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NetworkEvent::NewDevice(_) => "new_device",
+            NetworkEvent::DeviceOnline(_) => "device_online",
+            NetworkEvent::DeviceOffline(_) => "device_offline",
+            NetworkEvent::Alert(_) => "alert",
+        }
+    }
+
+    /// The device or alert the event is about
+    pub fn data(&self) -> Value {
+        match self {
+            NetworkEvent::NewDevice(device)
+            | NetworkEvent::DeviceOnline(device)
+            | NetworkEvent::DeviceOffline(device) => json!(device),
+            NetworkEvent::Alert(alert) => alert.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct EventTracker {
+    /// Device ID -> online state at the last pass
+    devices: HashMap<String, bool>,
+    alerts: HashSet<String>,
+    /// Workspace the baseline was recorded in
+    workspace: String,
+    synced: bool,
+}
+
+impl EventTracker {
+    /// Whether a first pass has recorded the baseline state
+    pub fn is_synced(&self) -> bool {
+        self.synced && self.workspace == workspace::active()
+    }
+
+    /// Diff against the previous pass. The first pass (and the first after a
+    /// workspace switch) only records the baseline, so existing devices and
+    /// alerts aren't reported as new.
+    pub fn update(&mut self, devices: &[Device], alerts: &[Value]) -> Vec<NetworkEvent> {
+        if !self.is_synced() {
+            *self = EventTracker::default();
+        }
+        self.workspace = workspace::active();
+
+        let mut events = Vec::new();
+
+        for device in devices {
+            match self.devices.insert(device.id.clone(), device.is_online) {
+                None if self.synced => events.push(NetworkEvent::NewDevice(device.clone())),
+                Some(false) if device.is_online => events.push(NetworkEvent::DeviceOnline(device.clone())),
+                Some(true) if !device.is_online => events.push(NetworkEvent::DeviceOffline(device.clone())),
+                _ => {}
+            }
+        }
+
+        for alert in alerts {
+            let Some(id) = alert.get("id").and_then(|i| i.as_str()) else {
+                continue;
+            };
+            if self.alerts.insert(id.to_string()) && self.synced {
+                events.push(NetworkEvent::Alert(alert.clone()));
+            }
+        }
+
+        self.synced = true;
+        events
+    }
+}
@@ -0,0 +1,104 @@
+// Connection-level flows
+//
+// The bypass detector groups every packet between a LAN device and the
+// internet into flows by 5-tuple (see `python/alerts/flow_logger.py`), with
+// packets, bytes each way and duration, so traffic the proxy never records —
+// SSH, games, P2P, VPNs — shows up next to HTTP entries. A long flow is
+// written out every few minutes while it lasts, so it can appear as several
+// consecutive records. Non-web flows also count towards device and dashboard
+// bandwidth.
+
+use crate::apps::UsageRange;
+use crate::database::open_readonly;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+/// Most flows returned for one query, newest first
+const MAX_FLOWS: u32 = 2000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Flow {
+    pub id: i64,
+    pub start_time: String,
+    pub end_time: String,
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub device_port: u16,
+    pub remote_ip: String,
+    pub remote_port: u16,
+    /// "tcp" or "udp"
+    pub transport: String,
+    /// "ssh", "https", "gaming", "bittorrent", … or "other"
+    pub protocol: String,
+    pub packets: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FlowLog {
+    pub start: String,
+    pub end: String,
+    pub device_id: Option<String>,
+    pub flows: Vec<Flow>,
+    /// More flows matched than were returned
+    pub truncated: bool,
+}
+
+/// Flows that overlap `start`..`end`, for a device (or all devices), newest first
+pub fn flows_from(
+    conn: &Connection,
+    device_id: Option<&str>,
+    start: &str,
+    end: &str,
+    limit: u32,
+) -> Result<Vec<Flow>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, start_time, end_time, device_id, device_ip, device_port, remote_ip, remote_port,
+                    transport, protocol, packets, bytes_in, bytes_out, duration_ms
+             FROM flows
+             WHERE start_time < ?2 AND end_time >= ?1 AND (?3 IS NULL OR device_id = ?3)
+             ORDER BY start_time DESC
+             LIMIT ?4",
+        )
+        .map_err(|e| format!("Failed to prepare flow query: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, device_id, limit], |row| {
+            Ok(Flow {
+                id: row.get(0)?,
+                start_time: row.get(1)?,
+                end_time: row.get(2)?,
+                device_id: row.get(3)?,
+                device_ip: row.get(4)?,
+                device_port: row.get(5)?,
+                remote_ip: row.get(6)?,
+                remote_port: row.get(7)?,
+                transport: row.get(8)?,
+                protocol: row.get(9)?,
+                packets: row.get::<_, Option<i64>>(10)?.unwrap_or(0) as u64,
+                bytes_in: row.get::<_, Option<i64>>(11)?.unwrap_or(0) as u64,
+                bytes_out: row.get::<_, Option<i64>>(12)?.unwrap_or(0) as u64,
+                duration_ms: row.get::<_, Option<i64>>(13)?.unwrap_or(0) as u64,
+            })
+        })
+        .map_err(|e| format!("Failed to query flows: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read flows: {}", e))
+}
+
+pub fn flows(device_id: Option<&str>, range: UsageRange) -> Result<FlowLog, String> {
+    let (start, end) = range.bounds();
+    let conn = open_readonly()?;
+    let mut flows = flows_from(&conn, device_id, &start, &end, MAX_FLOWS + 1)?;
+    let truncated = flows.len() > MAX_FLOWS as usize;
+    flows.truncate(MAX_FLOWS as usize);
+    Ok(FlowLog {
+        start,
+        end,
+        device_id: device_id.map(str::to_string),
+        flows,
+        truncated,
+    })
+}
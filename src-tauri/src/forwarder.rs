@@ -0,0 +1,253 @@
+// Syslog/CEF forwarding of alerts and traffic summaries to a SIEM
+//
+// New alerts from the event bus (and, optionally, periodic per-device traffic
+// summaries) are handed to a long-running Python forwarder that formats them
+// as RFC 5424 or CEF and ships them over UDP, TCP or TLS, buffering while the
+// collector is unreachable.
+
+use crate::clock;
+use crate::commands::load_settings;
+use crate::database;
+use crate::events::NetworkEvent;
+use crate::python::{read_process_output, send_command_to_process, start_python_script};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::Child;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often the settings and traffic summaries are checked
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ForwarderSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// "udp", "tcp" or "tls"
+    pub transport: String,
+    /// "rfc5424" or "cef"
+    pub format: String,
+    pub verify_tls: bool,
+    pub forward_traffic_summaries: bool,
+    pub summary_interval_minutes: u32,
+}
+
+impl Default for ForwarderSettings {
+    fn default() -> Self {
+        ForwarderSettings {
+            enabled: false,
+            host: String::new(),
+            port: 514,
+            transport: "udp".to_string(),
+            format: "rfc5424".to_string(),
+            verify_tls: true,
+            forward_traffic_summaries: false,
+            summary_interval_minutes: 5,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForwarderStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub connected: bool,
+    pub destination: Option<String>,
+    pub buffered: u64,
+    pub sent: u64,
+    pub dropped: u64,
+    pub last_error: Option<String>,
+    pub last_sent_at: Option<String>,
+}
+
+struct Forwarder {
+    process: Child,
+    settings: ForwarderSettings,
+    /// Start of the traffic window not yet summarized (UTC timestamp)
+    summary_since: String,
+    last_summary: Instant,
+}
+
+static FORWARDER: Mutex<Option<Forwarder>> = Mutex::new(None);
+
+/// Per-device request, block and byte counts for traffic in [since, until)
+fn traffic_summaries(since: &str, until: &str) -> Result<Vec<Value>, String> {
+    let conn = database::open_readonly()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(device_id, ''), device_ip, COUNT(*), COALESCE(SUM(blocked), 0),
+                    SUM(COALESCE(response_size, 0)), SUM(COALESCE(request_size, 0))
+             FROM traffic
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             GROUP BY device_id, device_ip",
+        )
+        .map_err(|e| format!("Failed to prepare summary query: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![since, until], |r| {
+            Ok(json!({
+                "device_id": r.get::<_, String>(0)?,
+                "device_ip": r.get::<_, String>(1)?,
+                "requests": r.get::<_, i64>(2)?,
+                "blocked": r.get::<_, i64>(3)?,
+                "bytes_in": r.get::<_, i64>(4)?,
+                "bytes_out": r.get::<_, i64>(5)?,
+                "start": since,
+                "end": until,
+            }))
+        })
+        .map_err(|e| format!("Failed to query traffic summary: {}", e))?;
+
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read traffic summary: {}", e))
+}
+
+impl Forwarder {
+    fn start(settings: &ForwarderSettings) -> Result<Self, String> {
+        let port = settings.port.to_string();
+        let mut args = vec![
+            "--host", settings.host.as_str(),
+            "--port", port.as_str(),
+            "--transport", settings.transport.as_str(),
+            "--format", settings.format.as_str(),
+        ];
+        if !settings.verify_tls {
+            args.push("--no-verify");
+        }
+
+        let process = start_python_script("python/alerts/siem_forwarder.py", &args)
+            .map_err(|e| format!("Failed to start SIEM forwarder: {}", e))?;
+
+        log::info!(
+            "Forwarding alerts to {}:{} ({} over {})",
+            settings.host, settings.port, settings.format, settings.transport
+        );
+
+        Ok(Forwarder {
+            process,
+            settings: settings.clone(),
+            summary_since: clock::now_utc(),
+            last_summary: Instant::now(),
+        })
+    }
+
+    fn stop(mut self) {
+        let _ = send_command_to_process(&mut self.process, &json!({ "action": "stop" }));
+        let _ = self.process.wait();
+    }
+
+    fn is_alive(&mut self) -> bool {
+        matches!(self.process.try_wait(), Ok(None))
+    }
+
+    fn forward_summaries(&mut self) -> Result<(), String> {
+        let interval = Duration::from_secs(self.settings.summary_interval_minutes.max(1) as u64 * 60);
+        if self.settings.forward_traffic_summaries && self.last_summary.elapsed() >= interval {
+            let until = clock::now_utc();
+            for summary in traffic_summaries(&self.summary_since, &until)? {
+                send_command_to_process(&mut self.process, &json!({
+                    "action": "traffic_summary",
+                    "summary": summary,
+                }))?;
+            }
+            self.summary_since = until;
+            self.last_summary = Instant::now();
+        }
+
+        Ok(())
+    }
+}
+
+/// Start, restart or stop the forwarder to match settings, then forward any due traffic summary
+fn forward_pending() -> Result<(), String> {
+    let settings = load_settings()?.forwarder;
+    let mut forwarder = FORWARDER.lock().unwrap();
+
+    // Restart when the destination changes or the process died
+    if let Some(mut running) = forwarder.take() {
+        if running.settings == settings && running.is_alive() {
+            *forwarder = Some(running);
+        } else {
+            running.stop();
+        }
+    }
+
+    if !settings.enabled || settings.host.is_empty() {
+        return Ok(());
+    }
+
+    let forwarder = match forwarder.as_mut() {
+        Some(f) => f,
+        None => forwarder.insert(Forwarder::start(&settings)?),
+    };
+    forwarder.forward_summaries()
+}
+
+/// Forward a newly stored alert from the event bus, if the forwarder is running
+pub fn notify(event: &NetworkEvent) -> Result<(), String> {
+    let NetworkEvent::Alert(alert) = event else { return Ok(()) };
+    match FORWARDER.lock().unwrap().as_mut() {
+        Some(running) => send_command_to_process(&mut running.process, &json!({ "action": "alert", "alert": alert })),
+        None => Ok(()),
+    }
+}
+
+/// Get the forwarder's connection and buffer state
+pub fn status() -> Result<ForwarderStatus, String> {
+    let settings = load_settings()?.forwarder;
+    let mut forwarder = FORWARDER.lock().unwrap();
+
+    let alive = forwarder.as_mut().is_some_and(|f| f.is_alive());
+    let Some(running) = forwarder.as_mut().filter(|_| alive) else {
+        return Ok(ForwarderStatus {
+            enabled: settings.enabled,
+            running: false,
+            connected: false,
+            destination: None,
+            buffered: 0,
+            sent: 0,
+            dropped: 0,
+            last_error: None,
+            last_sent_at: None,
+        });
+    };
+
+    send_command_to_process(&mut running.process, &json!({ "action": "status" }))?;
+    let status = read_process_output(&mut running.process)?;
+    let count = |key: &str| status.get(key).and_then(|n| n.as_u64()).unwrap_or(0);
+    let text = |key: &str| status.get(key).and_then(|s| s.as_str()).map(|s| s.to_string());
+
+    Ok(ForwarderStatus {
+        enabled: settings.enabled,
+        running: true,
+        connected: status.get("connected").and_then(|c| c.as_bool()).unwrap_or(false),
+        destination: Some(format!(
+            "{}://{}:{}",
+            running.settings.transport, running.settings.host, running.settings.port
+        )),
+        buffered: count("buffered"),
+        sent: count("sent"),
+        dropped: count("dropped"),
+        last_error: text("last_error"),
+        last_sent_at: text("last_sent_at"),
+    })
+}
+
+/// Spawn the background task that forwards alerts and traffic summaries
+pub fn spawn_forwarder() {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = forward_pending() {
+                    log::debug!("SIEM forwarding failed: {}", e);
+                }
+            })
+            .await;
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
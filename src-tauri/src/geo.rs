@@ -0,0 +1,98 @@
+// IP geolocation (country level)
+//
+// Countries come from an IP-to-country range list in
+// `config/ip_countries.csv`, one `first,last,country` row per range, IPv4 and
+// IPv6 mixed — the layout of the free DB-IP "IP to Country Lite" download, so
+// that file can be dropped in as is. Without it nothing is geolocated.
+// Ranges are reloaded when the file changes, and lookups go through the
+// enrichment cache.
+
+use crate::enrichment::{self, Kind};
+use crate::python::get_project_root;
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Inclusive address range (IPv4 as mapped IPv6) and its ISO country code
+pub type CountryRange = (u128, u128, String);
+
+struct Ranges {
+    modified: SystemTime,
+    ranges: Arc<Vec<CountryRange>>,
+}
+
+static RANGES: Mutex<Option<Ranges>> = Mutex::new(None);
+
+fn ranges_path() -> PathBuf {
+    get_project_root().join("config").join("ip_countries.csv")
+}
+
+fn to_bits(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(v4) => u128::from(v4.to_ipv6_mapped()),
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+/// Parse `first,last,country` rows, skipping ones that aren't ranges, sorted by start
+pub fn parse_ranges(csv: &str) -> Vec<CountryRange> {
+    let mut ranges: Vec<CountryRange> = csv
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(|f| f.trim().trim_matches('"'));
+            let first = fields.next()?.parse::<IpAddr>().ok()?;
+            let last = fields.next()?.parse::<IpAddr>().ok()?;
+            let country = fields.next().filter(|c| c.len() == 2)?.to_uppercase();
+            Some((to_bits(first), to_bits(last), country))
+        })
+        .collect();
+    ranges.sort_by_key(|r| r.0);
+    ranges
+}
+
+/// The country of an address in sorted ranges
+pub fn country_in(ranges: &[CountryRange], ip: IpAddr) -> Option<&str> {
+    let bits = to_bits(ip);
+    let index = ranges.partition_point(|r| r.0 <= bits).checked_sub(1)?;
+    let (_, last, country) = &ranges[index];
+    (bits <= *last).then_some(country.as_str())
+}
+
+/// The current ranges, reloaded if the file changed; empty without one
+fn ranges() -> Arc<Vec<CountryRange>> {
+    let Ok(modified) = fs::metadata(ranges_path()).and_then(|m| m.modified()) else { return Arc::default() };
+    let mut guard = RANGES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(loaded) = guard.as_ref().filter(|r| r.modified == modified) {
+        return loaded.ranges.clone();
+    }
+    let ranges = match fs::read_to_string(ranges_path()) {
+        Ok(content) => Arc::new(parse_ranges(&content)),
+        Err(e) => {
+            log::debug!("Failed to read IP country ranges: {}", e);
+            return Arc::default();
+        }
+    };
+    log::info!("Loaded {} IP country ranges", ranges.len());
+    *guard = Some(Ranges { modified, ranges: ranges.clone() });
+    ranges
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_multicast()),
+        IpAddr::V6(v6) => !(v6.is_loopback() || v6.is_multicast() || (v6.segments()[0] & 0xfe00) == 0xfc00),
+    }
+}
+
+/// ISO country code of a public address, if the ranges list it
+pub fn country(ip: &str) -> Option<String> {
+    let addr = ip.parse::<IpAddr>().ok().filter(|a| is_public(*a))?;
+    let ranges = ranges();
+    // Nothing is cached until there is something to look up in
+    if ranges.is_empty() {
+        return None;
+    }
+    enrichment::lookup(Kind::Geo, ip, |_| country_in(&ranges, addr).map(str::to_string))
+}
@@ -0,0 +1,83 @@
+// gRPC control interface settings
+//
+// Builds with the `grpc` feature serve the control service described in
+// `proto/control.proto` (see `grpc_server`): start and stop monitoring, list
+// devices, manage rules and stream status changes. Callers authenticate with
+// an API key. The server listens on loopback by default; any other address
+// must be served over TLS, with a certificate and key the user provides.
+// Builds without the feature keep the settings and log that they're ignored.
+
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
+use tauri::AppHandle;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct GrpcSettings {
+    pub enabled: bool,
+    /// Address to listen on; anything but loopback needs TLS
+    pub bind_address: String,
+    pub port: u16,
+    /// PEM certificate chain and private key to serve TLS with
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+}
+
+impl Default for GrpcSettings {
+    fn default() -> Self {
+        GrpcSettings {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 50051,
+            tls_cert_path: None,
+            tls_key_path: None,
+        }
+    }
+}
+
+impl GrpcSettings {
+    /// Certificate and key paths when TLS is configured
+    pub fn tls_paths(&self) -> Option<(&str, &str)> {
+        Some((self.tls_cert_path.as_deref()?, self.tls_key_path.as_deref()?))
+    }
+}
+
+pub fn validate(settings: &GrpcSettings) -> Result<(), String> {
+    let address: IpAddr = settings
+        .bind_address
+        .parse()
+        .map_err(|_| format!("Invalid gRPC bind address: {}", settings.bind_address))?;
+    if settings.port == 0 {
+        return Err("Invalid gRPC port: 0".to_string());
+    }
+    if settings.tls_cert_path.is_some() != settings.tls_key_path.is_some() {
+        return Err("gRPC TLS needs both a certificate and a private key".to_string());
+    }
+    if let Some((cert, key)) = settings.tls_paths() {
+        if let Some(missing) = [cert, key].into_iter().find(|p| !Path::new(p).is_file()) {
+            return Err(format!("gRPC TLS file not found: {}", missing));
+        }
+    } else if settings.enabled && !address.is_loopback() {
+        return Err(format!(
+            "Serving gRPC on {} needs a TLS certificate and key (only loopback may be plaintext)",
+            address
+        ));
+    }
+    Ok(())
+}
+
+/// Start, stop or move the gRPC server to match the settings
+#[cfg(feature = "grpc")]
+pub fn apply_settings(app: &AppHandle, settings: &GrpcSettings) -> Result<(), String> {
+    crate::grpc_server::apply_settings(app, settings)
+}
+
+/// Start, stop or move the gRPC server to match the settings
+#[cfg(not(feature = "grpc"))]
+pub fn apply_settings(_app: &AppHandle, settings: &GrpcSettings) -> Result<(), String> {
+    if settings.enabled {
+        log::warn!("gRPC control is enabled in settings, but this build was made without the grpc feature");
+    }
+    Ok(())
+}
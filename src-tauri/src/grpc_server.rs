@@ -0,0 +1,375 @@
+// gRPC control server
+//
+// The `netmon.v1.Control` service of `proto/control.proto`, served from the
+// app process so it drives the same lifecycle, snapshot and rule commands as
+// the window. Messages are written out here to match the proto, and build.rs
+// generates the service stubs from the same method list, so no protoc is
+// needed to build. Each call checks its API key (read-only scope for reads,
+// admin otherwise); WatchStatus pushes a new status on every lifecycle
+// transition and device change from the event bus.
+
+use crate::api_keys::{self, ApiScope};
+use crate::commands::{self, Device};
+use crate::event_bus::{self, BusEvent};
+use crate::grpc::GrpcSettings;
+use crate::state::AppState;
+use serde_json::Value;
+use std::fs;
+use std::net::{SocketAddr, TcpListener};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tokio_stream::Stream;
+use tonic::transport::{Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+
+mod stubs {
+    include!(concat!(env!("OUT_DIR"), "/netmon.v1.Control.rs"));
+}
+
+use stubs::control_server::{Control, ControlServer};
+
+/// Status updates a slow WatchStatus client may fall behind by
+const WATCH_BUFFER: usize = 16;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Empty {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MonitorStatus {
+    #[prost(bool, tag = "1")]
+    pub is_running: bool,
+    #[prost(string, tag = "2")]
+    pub state: String,
+    #[prost(uint64, tag = "3")]
+    pub uptime_seconds: u64,
+    #[prost(string, tag = "4")]
+    pub profile: String,
+    #[prost(uint32, tag = "5")]
+    pub total_devices: u32,
+    #[prost(uint32, tag = "6")]
+    pub online_devices: u32,
+    #[prost(string, repeated, tag = "7")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ListDevicesRequest {
+    #[prost(bool, tag = "1")]
+    pub online_only: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DeviceInfo {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(string, tag = "2")]
+    pub mac: String,
+    #[prost(string, tag = "3")]
+    pub ip: String,
+    #[prost(string, optional, tag = "4")]
+    pub hostname: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub vendor: Option<String>,
+    #[prost(string, tag = "6")]
+    pub device_type: String,
+    #[prost(bool, tag = "7")]
+    pub is_online: bool,
+    #[prost(string, tag = "8")]
+    pub last_seen: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DeviceList {
+    #[prost(message, repeated, tag = "1")]
+    pub devices: Vec<DeviceInfo>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Rule {
+    #[prost(string, tag = "1")]
+    pub rule_type: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+    #[prost(uint32, optional, tag = "3")]
+    pub duration_minutes: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RuleList {
+    #[prost(message, repeated, tag = "1")]
+    pub rules: Vec<Rule>,
+}
+
+impl From<&Device> for DeviceInfo {
+    fn from(device: &Device) -> Self {
+        DeviceInfo {
+            id: device.id.clone(),
+            mac: device.mac.clone(),
+            ip: device.ip.clone(),
+            hostname: device.hostname.clone(),
+            vendor: device.vendor.clone(),
+            device_type: device.device_type.clone(),
+            is_online: device.is_online,
+            last_seen: device.last_seen.clone(),
+        }
+    }
+}
+
+/// Rules in the blocking engine's `config` output, as the types `add_block_rule` takes
+pub fn rules_from_config(config: &Value) -> Vec<Rule> {
+    let strings = |key: &str| -> Vec<String> {
+        config
+            .get(key)
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().or_else(|| item.get("id").and_then(|id| id.as_str())))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    [
+        ("domain", "blocked_domains"),
+        ("category", "blocked_categories"),
+        ("keyword", "blocked_keywords"),
+        ("allow", "whitelisted_domains"),
+    ]
+    .into_iter()
+    .flat_map(|(rule_type, key)| {
+        strings(key).into_iter().map(move |value| Rule {
+            rule_type: rule_type.to_string(),
+            value,
+            duration_minutes: None,
+        })
+    })
+    .collect()
+}
+
+/// Why a call was refused
+enum AuthError {
+    Missing,
+    Denied(String),
+}
+
+impl From<AuthError> for Status {
+    fn from(error: AuthError) -> Self {
+        match error {
+            AuthError::Missing => Status::unauthenticated("Missing API key"),
+            AuthError::Denied(reason) => Status::permission_denied(reason),
+        }
+    }
+}
+
+/// Check the call's "authorization: Bearer <key>" against the scope it needs
+fn authorize<T>(request: &Request<T>, scope: ApiScope) -> Result<(), AuthError> {
+    let key = request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or(AuthError::Missing)?;
+    api_keys::authenticate(key.trim(), scope)
+        .map(|_| ())
+        .map_err(AuthError::Denied)
+}
+
+struct ControlService {
+    app: AppHandle,
+}
+
+async fn current_status(app: &AppHandle) -> Result<MonitorStatus, Status> {
+    let state = app.state::<AppState>();
+    let devices = state.snapshot.serve_devices(false).await.map(|s| s.data).unwrap_or_default();
+    let status = state.snapshot.serve_status(app.clone(), false).await.map_err(Status::internal)?.data;
+    Ok(MonitorStatus {
+        is_running: status.is_running,
+        state: status.state.to_string(),
+        uptime_seconds: status.uptime,
+        profile: status.current_profile,
+        total_devices: devices.len() as u32,
+        online_devices: devices.iter().filter(|d| d.is_online).count() as u32,
+        errors: status.errors,
+    })
+}
+
+#[tonic::async_trait]
+impl Control for ControlService {
+    type WatchStatusStream = Pin<Box<dyn Stream<Item = Result<MonitorStatus, Status>> + Send>>;
+
+    async fn get_status(&self, request: Request<Empty>) -> Result<Response<MonitorStatus>, Status> {
+        authorize(&request, ApiScope::ReadOnly)?;
+        Ok(Response::new(current_status(&self.app).await?))
+    }
+
+    async fn watch_status(&self, request: Request<Empty>) -> Result<Response<Self::WatchStatusStream>, Status> {
+        authorize(&request, ApiScope::ReadOnly)?;
+
+        let (tx, rx) = mpsc::channel(WATCH_BUFFER);
+        let mut events = event_bus::subscribe();
+        let app = self.app.clone();
+        tauri::async_runtime::spawn(async move {
+            if tx.send(current_status(&app).await).await.is_err() {
+                return;
+            }
+            loop {
+                match events.recv().await {
+                    Ok(BusEvent::Lifecycle(_)) | Ok(BusEvent::Network(_)) | Err(RecvError::Lagged(_)) => {
+                        if tx.send(current_status(&app).await).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(BusEvent::AlertRaised(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn start_monitoring(&self, request: Request<Empty>) -> Result<Response<MonitorStatus>, Status> {
+        authorize(&request, ApiScope::Admin)?;
+        log::info!("Starting monitoring over gRPC");
+        commands::start_monitoring(self.app.state::<AppState>())
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+        Ok(Response::new(current_status(&self.app).await?))
+    }
+
+    async fn stop_monitoring(&self, request: Request<Empty>) -> Result<Response<MonitorStatus>, Status> {
+        authorize(&request, ApiScope::Admin)?;
+        log::info!("Stopping monitoring over gRPC");
+        commands::stop_monitoring(self.app.state::<AppState>())
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+        Ok(Response::new(current_status(&self.app).await?))
+    }
+
+    async fn list_devices(&self, request: Request<ListDevicesRequest>) -> Result<Response<DeviceList>, Status> {
+        authorize(&request, ApiScope::ReadOnly)?;
+        let online_only = request.get_ref().online_only;
+        let devices = self
+            .app
+            .state::<AppState>()
+            .snapshot
+            .devices(false)
+            .map_err(Status::internal)?
+            .data;
+        Ok(Response::new(DeviceList {
+            devices: devices.iter().filter(|d| d.is_online || !online_only).map(DeviceInfo::from).collect(),
+        }))
+    }
+
+    async fn list_rules(&self, request: Request<Empty>) -> Result<Response<RuleList>, Status> {
+        authorize(&request, ApiScope::ReadOnly)?;
+        let result = commands::get_block_config().await.map_err(Status::internal)?;
+        let config = result.get("config").unwrap_or(&result);
+        Ok(Response::new(RuleList {
+            rules: rules_from_config(config),
+        }))
+    }
+
+    async fn add_rule(&self, request: Request<Rule>) -> Result<Response<Empty>, Status> {
+        authorize(&request, ApiScope::Admin)?;
+        let rule = request.into_inner();
+        commands::add_block_rule(rule.rule_type, rule.value, rule.duration_minutes)
+            .await
+            .map_err(Status::invalid_argument)?;
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn remove_rule(&self, request: Request<Rule>) -> Result<Response<Empty>, Status> {
+        authorize(&request, ApiScope::Admin)?;
+        let rule = request.into_inner();
+        commands::remove_block_rule(rule.rule_type, rule.value)
+            .await
+            .map_err(Status::invalid_argument)?;
+        Ok(Response::new(Empty {}))
+    }
+}
+
+struct Running {
+    settings: GrpcSettings,
+    shutdown: oneshot::Sender<()>,
+}
+
+static SERVER: Mutex<Option<Running>> = Mutex::new(None);
+
+fn start(app: AppHandle, settings: &GrpcSettings) -> Result<Running, String> {
+    let mut server = Server::builder();
+    if let Some((cert, key)) = settings.tls_paths() {
+        let cert = fs::read(cert).map_err(|e| format!("Failed to read gRPC TLS certificate: {}", e))?;
+        let key = fs::read(key).map_err(|e| format!("Failed to read gRPC TLS key: {}", e))?;
+        server = server
+            .tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))
+            .map_err(|e| format!("Invalid gRPC TLS configuration: {}", e))?;
+    }
+
+    let ip = settings
+        .bind_address
+        .parse()
+        .map_err(|_| format!("Invalid gRPC bind address: {}", settings.bind_address))?;
+    let addr = SocketAddr::new(ip, settings.port);
+    // Bound here so a taken port is reported to whoever changed the settings
+    let listener = TcpListener::bind(addr).map_err(|e| format!("Failed to bind gRPC port {}: {}", settings.port, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure gRPC listener: {}", e))?;
+
+    let (shutdown, stopped) = oneshot::channel::<()>();
+    let tls = settings.tls_paths().is_some();
+    tauri::async_runtime::spawn(async move {
+        let incoming = match tokio::net::TcpListener::from_std(listener) {
+            Ok(listener) => TcpListenerStream::new(listener),
+            Err(e) => {
+                log::error!("Failed to start gRPC listener: {}", e);
+                return;
+            }
+        };
+        let result = server
+            .add_service(ControlServer::new(ControlService { app }))
+            .serve_with_incoming_shutdown(incoming, async {
+                let _ = stopped.await;
+            })
+            .await;
+        match result {
+            Ok(()) => log::info!("gRPC control server on {} stopped", addr),
+            Err(e) => log::error!("gRPC control server failed: {}", e),
+        }
+    });
+
+    log::info!("Serving gRPC control on {} ({})", addr, if tls { "TLS" } else { "plaintext" });
+    Ok(Running {
+        settings: settings.clone(),
+        shutdown,
+    })
+}
+
+/// Start, stop or move the server to match the settings
+pub fn apply_settings(app: &AppHandle, settings: &GrpcSettings) -> Result<(), String> {
+    let mut server = SERVER.lock().unwrap();
+
+    if let Some(running) = server.take() {
+        if settings.enabled && running.settings == *settings {
+            *server = Some(running);
+            return Ok(());
+        }
+        let _ = running.shutdown.send(());
+        // Give the old server a chance to release its port
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    if settings.enabled {
+        *server = Some(start(app.clone(), settings)?);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,251 @@
+// Guest network mode
+//
+// When enabled, every device that wasn't known at the time (or marked known
+// since) is treated as a guest: the proxy lets its traffic through but logs
+// every request, rate-limits it and answers its first plain-HTTP request with a
+// one-time "this network is monitored" page. The proxy writes the guest log;
+// statistics are read back from it here.
+
+use crate::clock;
+use crate::commands::{broadcast_to_processes, Device};
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the guest list pushed to the proxy is re-evaluated
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Serializes read-modify-write of the guest config
+static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+/// Guest list last pushed to the proxy
+static PUSHED: Mutex<Option<Vec<(String, String)>>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct GuestConfig {
+    pub enabled: bool,
+    pub requests_per_minute: u32,
+    /// Device IDs that are never treated as guests
+    pub known_devices: Vec<String>,
+}
+
+impl Default for GuestConfig {
+    fn default() -> Self {
+        GuestConfig {
+            enabled: false,
+            requests_per_minute: 30,
+            known_devices: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct GuestDeviceStats {
+    pub device_id: String,
+    pub device_ip: String,
+    pub requests: u64,
+    pub rate_limited: u64,
+    pub distinct_hosts: usize,
+    pub notice_shown: bool,
+    pub first_request: Option<String>,
+    pub last_request: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuestStats {
+    pub enabled: bool,
+    pub requests_per_minute: u32,
+    pub online_guests: usize,
+    pub total_requests: u64,
+    pub total_rate_limited: u64,
+    pub devices: Vec<GuestDeviceStats>,
+}
+
+fn get_config_path() -> PathBuf {
+    workspace::config_dir().join("guest.json")
+}
+
+fn get_log_path() -> PathBuf {
+    workspace::data_dir().join("guest_log.jsonl")
+}
+
+pub fn load_config() -> Result<GuestConfig, String> {
+    let path = get_config_path();
+    if !path.exists() {
+        return Ok(GuestConfig::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read guest config: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse guest config: {}", e))
+}
+
+fn save_config(config: &GuestConfig) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize guest config: {}", e))?;
+    fs::write(get_config_path(), content).map_err(|e| format!("Failed to write guest config: {}", e))
+}
+
+/// Online guest devices as (IP, device ID)
+fn guests(config: &GuestConfig, devices: &[Device]) -> Vec<(String, String)> {
+    if !config.enabled {
+        return vec![];
+    }
+
+    let mut guests: Vec<(String, String)> = devices
+        .iter()
+        .filter(|d| d.is_online && !config.known_devices.contains(&d.id))
+        .map(|d| (d.ip.clone(), d.id.clone()))
+        .collect();
+    guests.sort();
+    guests
+}
+
+/// Extra proxy arguments that apply guest mode at startup
+pub fn proxy_args(devices: &[Device]) -> Vec<String> {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            log::warn!("Failed to load guest config, starting without it: {}", e);
+            return vec![];
+        }
+    };
+
+    let guests = guests(&config, devices);
+    let mut args = vec!["--guest-rate-limit".to_string(), config.requests_per_minute.to_string()];
+    for (ip, id) in &guests {
+        args.push("--guest".to_string());
+        args.push(format!("{}={}", ip, id));
+    }
+    *PUSHED.lock().unwrap() = Some(guests);
+    args
+}
+
+/// Push the current guest list to the proxy if it changed
+fn push_guests(state: &AppState, force: bool) -> Result<(), String> {
+    let config = load_config()?;
+    let devices = state.snapshot.devices(false)?.data;
+    let guests = guests(&config, &devices);
+
+    let mut pushed = PUSHED.lock().unwrap();
+    if !force && pushed.as_ref() == Some(&guests) {
+        return Ok(());
+    }
+
+    broadcast_to_processes(state, &json!({
+        "action": "set_guests",
+        "requests_per_minute": config.requests_per_minute,
+        "guests": guests
+            .iter()
+            .map(|(ip, id)| json!({ "ip": ip, "device_id": id }))
+            .collect::<Vec<_>>(),
+    }));
+    *pushed = Some(guests);
+    Ok(())
+}
+
+/// Turn guest mode on or off. Turning it on marks every device seen so far as known.
+pub fn set_enabled(state: &AppState, enabled: bool) -> Result<(), String> {
+    {
+        let _guard = CONFIG_LOCK.lock().unwrap();
+        let mut config = load_config()?;
+        config.enabled = enabled;
+
+        if enabled {
+            let known: HashSet<String> = config.known_devices.iter().cloned().collect();
+            let devices = state.snapshot.devices(true)?.data;
+            config.known_devices.extend(devices.into_iter().map(|d| d.id).filter(|id| !known.contains(id)));
+        }
+        save_config(&config)?;
+    }
+
+    log::info!("Guest mode {}", if enabled { "enabled" } else { "disabled" });
+    push_guests(state, true)
+}
+
+/// Per-guest request statistics from the proxy's guest log
+pub fn stats(state: &AppState, hours: u32) -> Result<GuestStats, String> {
+    let config = load_config()?;
+    let devices = state.snapshot.devices(false)?.data;
+    let since = clock::utc_cutoff(chrono::Duration::hours(hours as i64));
+
+    let mut per_device: HashMap<String, (GuestDeviceStats, HashSet<String>)> = HashMap::new();
+    let content = fs::read_to_string(get_log_path()).unwrap_or_default();
+
+    for entry in content.lines().filter_map(|l| serde_json::from_str::<Value>(l).ok()) {
+        let text = |key: &str| entry.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let timestamp = text("timestamp");
+        let event = text("event");
+
+        // The notice is one-time, so it counts regardless of the window
+        if timestamp < since && event != "notice" {
+            continue;
+        }
+
+        let (stats, hosts) = per_device.entry(text("device_id")).or_default();
+        stats.device_id = text("device_id");
+        stats.device_ip = text("device_ip");
+
+        match event.as_str() {
+            "notice" => {
+                stats.notice_shown = true;
+                continue;
+            }
+            "rate_limited" => stats.rate_limited += 1,
+            _ => {
+                stats.requests += 1;
+                hosts.insert(text("host"));
+            }
+        }
+
+        if stats.first_request.is_none() {
+            stats.first_request = Some(timestamp.clone());
+        }
+        stats.last_request = Some(timestamp);
+    }
+
+    let mut devices_stats: Vec<GuestDeviceStats> = per_device
+        .into_values()
+        .map(|(mut stats, hosts)| {
+            stats.distinct_hosts = hosts.len();
+            stats
+        })
+        .collect();
+    devices_stats.sort_by_key(|d| std::cmp::Reverse(d.requests));
+
+    Ok(GuestStats {
+        enabled: config.enabled,
+        requests_per_minute: config.requests_per_minute,
+        online_guests: guests(&config, &devices).len(),
+        total_requests: devices_stats.iter().map(|d| d.requests).sum(),
+        total_rate_limited: devices_stats.iter().map(|d| d.rate_limited).sum(),
+        devices: devices_stats,
+    })
+}
+
+/// Spawn the background task that keeps the proxy's guest list current
+pub fn spawn_guest_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                let state = handle.state::<AppState>();
+                if state.lifecycle.current().is_running() {
+                    if let Err(e) = push_guests(&state, false) {
+                        log::debug!("Guest list update failed: {}", e);
+                    }
+                }
+            })
+            .await;
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,127 @@
+// When devices are active, by day and hour
+//
+// Requests and bytes from raw traffic and its rolled-up aggregates are
+// bucketed by wall-clock day and hour in the configured zone, giving a
+// day x hour matrix for a GitHub-style heatmap. Counts are gathered per UTC
+// minute first so zones with half-hour offsets land in the right hour. Every
+// day in the range has a row, including days with no traffic.
+
+use crate::apps::UsageRange;
+use crate::clock::{self, Zone};
+use crate::database::open_readonly;
+use chrono::{Duration, NaiveDate, Timelike};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HeatmapDay {
+    /// Local date, "YYYY-MM-DD"
+    pub date: String,
+    /// Requests in each local hour, 0-23
+    pub requests: Vec<u64>,
+    /// Bytes in and out in each local hour, 0-23
+    pub bytes: Vec<u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ActivityHeatmap {
+    pub start: String,
+    pub end: String,
+    pub device_id: Option<String>,
+    pub days: Vec<HeatmapDay>,
+    /// Busiest cells, for scaling the colors
+    pub max_requests: u64,
+    pub max_bytes: u64,
+    pub total_requests: u64,
+    pub total_bytes: u64,
+}
+
+fn empty_day(date: NaiveDate) -> HeatmapDay {
+    HeatmapDay {
+        date: date.format("%Y-%m-%d").to_string(),
+        requests: vec![0; 24],
+        bytes: vec![0; 24],
+    }
+}
+
+/// Requests and bandwidth of a device (or all devices) by local day and hour
+pub fn heatmap(device_id: Option<&str>, range: UsageRange) -> Result<ActivityHeatmap, String> {
+    let (start, end) = range.bounds();
+    let conn = open_readonly()?;
+    heatmap_from(&conn, device_id, &start, &end, Zone::configured())
+}
+
+/// `heatmap` against an open database, between two UTC query bounds
+pub fn heatmap_from(
+    conn: &Connection,
+    device_id: Option<&str>,
+    start: &str,
+    end: &str,
+    zone: Zone,
+) -> Result<ActivityHeatmap, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT minute, SUM(requests), SUM(bytes) FROM (
+                 SELECT substr(timestamp, 1, 16) AS minute, 1 AS requests,
+                        COALESCE(request_size, 0) + COALESCE(response_size, 0) AS bytes
+                 FROM traffic
+                 WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_id = ?3)
+                 UNION ALL
+                 SELECT minute, requests, bytes_in + bytes_out
+                 FROM traffic_aggregates
+                 WHERE minute >= substr(?1, 1, 16) AND minute < ?2 AND (?3 IS NULL OR device_id = ?3)
+             )
+             GROUP BY minute",
+        )
+        .map_err(|e| format!("Failed to prepare heatmap query: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, device_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(2)?.unwrap_or(0) as u64,
+            ))
+        })
+        .map_err(|e| format!("Failed to query heatmap: {}", e))?;
+
+    // A row for every local day in the range
+    let mut days: BTreeMap<NaiveDate, HeatmapDay> = BTreeMap::new();
+    if let (Some(first), Some(last)) = (clock::parse_stored(start), clock::parse_stored(end)) {
+        let mut date = zone.at(first).date_naive();
+        // The end is exclusive, so a range ending at midnight doesn't add the next day
+        let last = zone.at(last - Duration::seconds(1)).date_naive();
+        while date <= last {
+            days.insert(date, empty_day(date));
+            date += Duration::days(1);
+        }
+    }
+
+    for row in rows {
+        let (minute, requests, bytes) = row.map_err(|e| format!("Failed to read heatmap: {}", e))?;
+        let Some(at) = clock::parse_stored(&minute) else {
+            continue;
+        };
+        let local = zone.at(at);
+        let day = days
+            .entry(local.date_naive())
+            .or_insert_with(|| empty_day(local.date_naive()));
+        let hour = local.hour() as usize;
+        day.requests[hour] += requests;
+        day.bytes[hour] += bytes;
+    }
+
+    let days: Vec<HeatmapDay> = days.into_values().collect();
+    let cells = || days.iter().flat_map(|d| d.requests.iter().zip(&d.bytes));
+
+    Ok(ActivityHeatmap {
+        start: start.to_string(),
+        end: end.to_string(),
+        device_id: device_id.map(str::to_string),
+        max_requests: cells().map(|(r, _)| *r).max().unwrap_or(0),
+        max_bytes: cells().map(|(_, b)| *b).max().unwrap_or(0),
+        total_requests: cells().map(|(r, _)| *r).sum(),
+        total_bytes: cells().map(|(_, b)| *b).sum(),
+        days,
+    })
+}
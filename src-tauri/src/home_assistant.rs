@@ -0,0 +1,119 @@
+// Home Assistant REST integration
+//
+// Device presence is pushed as binary_sensor entities and new devices, presence
+// changes and alerts are fired as `network_monitor_*` events (and optionally
+// sent to a webhook). The access token lives in the OS keychain, managed by the
+// Python side; only the URL and webhook ID are kept in settings.
+
+use crate::commands::load_settings;
+use crate::events::NetworkEvent;
+use crate::python::run_python_script_with_input;
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the settings are checked for a change that needs a full push
+const PUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+const SCRIPT: &str = "python/utils/home_assistant.py";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct HomeAssistantSettings {
+    pub enabled: bool,
+    pub base_url: String,
+    pub webhook_id: Option<String>,
+}
+
+/// Settings and workspace of the last full push; events are only pushed after one
+static SYNCED: Mutex<Option<(HomeAssistantSettings, String)>> = Mutex::new(None);
+
+fn check_success(result: Value) -> Result<Value, String> {
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(result)
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Save the long-lived access token in the OS keychain
+pub fn store_token(token: &str) -> Result<(), String> {
+    let result = run_python_script_with_input(SCRIPT, &["--action", "store-token"], &json!({ "token": token }))?;
+    check_success(result).map(|_| ())
+}
+
+/// Check that Home Assistant is reachable and accepts the stored token
+pub fn test_connection(base_url: &str) -> Result<Value, String> {
+    let result = run_python_script_with_input(SCRIPT, &["--action", "test", "--base-url", base_url], &json!({}))?;
+    check_success(result)
+}
+
+fn push(settings: &HomeAssistantSettings, states: Vec<Value>, events: Vec<Value>) -> Result<(), String> {
+    if states.is_empty() && events.is_empty() {
+        return Ok(());
+    }
+
+    let mut args = vec!["--action", "push", "--base-url", settings.base_url.as_str()];
+    if let Some(ref webhook) = settings.webhook_id {
+        args.push("--webhook-id");
+        args.push(webhook);
+    }
+
+    let result = run_python_script_with_input(SCRIPT, &args, &json!({ "states": states, "events": events }))?;
+    check_success(result).map(|_| ())
+}
+
+/// Push every device's presence when the settings or the workspace changed, so the entities exist
+fn push_pending(state: &AppState) -> Result<(), String> {
+    let settings = load_settings()?.home_assistant;
+    let mut synced = SYNCED.lock().unwrap();
+
+    if !settings.enabled || settings.base_url.is_empty() {
+        *synced = None;
+        return Ok(());
+    }
+    let workspace = workspace::active();
+    if synced.as_ref().is_some_and(|(s, w)| *s == settings && *w == workspace) {
+        return Ok(());
+    }
+
+    let devices = state.snapshot.devices(false)?.data;
+    push(&settings, devices.iter().map(|d| json!(d)).collect(), vec![])?;
+    *synced = Some((settings, workspace));
+    Ok(())
+}
+
+/// Fire an event from the event bus, updating presence for device events
+pub fn notify(event: &NetworkEvent) -> Result<(), String> {
+    let Some((settings, _)) = SYNCED.lock().unwrap().clone() else {
+        return Ok(());
+    };
+    let data = event.data();
+    let states = match event {
+        NetworkEvent::Alert(_) => vec![],
+        _ => vec![data.clone()],
+    };
+    push(&settings, states, vec![json!({ "event": event.name(), "data": data })])
+}
+
+/// Spawn the background task that pushes full presence to Home Assistant when needed
+pub fn spawn_home_assistant_sync(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = push_pending(&handle.state::<AppState>()) {
+                    log::debug!("Home Assistant push failed: {}", e);
+                }
+            })
+            .await;
+
+            tokio::time::sleep(PUSH_INTERVAL).await;
+        }
+    });
+}
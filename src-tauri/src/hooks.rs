@@ -0,0 +1,415 @@
+// User-configured hooks: run a command when an event happens
+//
+// Hooks fire on new devices, presence changes and alerts (optionally only at
+// or above a severity). The command line is split into arguments before
+// template variables like {{device_ip}} are filled in, and it runs without a
+// shell, so event data can't inject extra commands. The same variables are
+// also passed as NM_* environment variables. Every run (or rate-limited skip)
+// is recorded in the audit log with its captured output.
+
+use crate::audit;
+use crate::clock;
+use crate::commands::Device;
+use crate::events::NetworkEvent;
+use crate::interception::InterceptionMode;
+use crate::python::get_project_root;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Captured output kept per stream in the audit log
+const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+const EVENTS: &[&str] = &["alert", "new_device", "device_online", "device_offline"];
+const SEVERITIES: &[&str] = &["low", "medium", "high", "critical"];
+
+static HOOKS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Recent run times per hook ID, for rate limiting
+static RECENT_RUNS: Mutex<BTreeMap<String, VecDeque<Instant>>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Hook {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// "alert", "new_device", "device_online" or "device_offline"
+    pub event: String,
+    /// For alert hooks, only fire at or above this severity
+    #[serde(default)]
+    pub min_severity: Option<String>,
+    /// Program and arguments, with {{variable}} placeholders
+    pub command: String,
+    #[serde(default = "default_max_runs_per_hour")]
+    pub max_runs_per_hour: u32,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_runs_per_hour() -> u32 {
+    10
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct HooksFile {
+    #[serde(default)]
+    hooks: Vec<Hook>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HookRun {
+    pub hook_id: String,
+    pub command: Vec<String>,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+fn get_hooks_path() -> PathBuf {
+    workspace::config_dir().join("hooks.json")
+}
+
+fn load_file() -> Result<HooksFile, String> {
+    let path = get_hooks_path();
+    if !path.exists() {
+        return Ok(HooksFile::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read hooks: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse hooks: {}", e))
+}
+
+fn save_file(file: &HooksFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize hooks: {}", e))?;
+    fs::create_dir_all(workspace::config_dir()).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    fs::write(get_hooks_path(), content).map_err(|e| format!("Failed to write hooks: {}", e))
+}
+
+pub fn list_hooks() -> Result<Vec<Hook>, String> {
+    Ok(load_file()?.hooks)
+}
+
+/// Add a hook, or replace the one with the same ID
+pub fn save_hook(hook: Hook) -> Result<(), String> {
+    if !EVENTS.contains(&hook.event.as_str()) {
+        return Err(format!("Unknown hook event: {}", hook.event));
+    }
+    if let Some(ref severity) = hook.min_severity {
+        if !SEVERITIES.contains(&severity.as_str()) {
+            return Err(format!("Unknown severity: {}", severity));
+        }
+    }
+    if split_command(&hook.command).is_empty() {
+        return Err("Hook command is empty".to_string());
+    }
+
+    let _guard = HOOKS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    file.hooks.retain(|h| h.id != hook.id);
+    file.hooks.push(hook);
+    save_file(&file)
+}
+
+pub fn delete_hook(hook_id: &str) -> Result<(), String> {
+    let _guard = HOOKS_LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    file.hooks.retain(|h| h.id != hook_id);
+    save_file(&file)
+}
+
+/// Split a command line into arguments, honouring single and double quotes
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_arg = false;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+fn device_variables(vars: &mut HashMap<&'static str, String>, device: &Device) {
+    vars.insert("device_id", device.id.clone());
+    vars.insert("device_ip", device.ip.clone());
+    vars.insert("device_mac", device.mac.clone());
+    vars.insert("device_name", device.hostname.clone().unwrap_or_else(|| device.ip.clone()));
+    vars.insert("device_vendor", device.vendor.clone().unwrap_or_default());
+    vars.insert("device_type", device.device_type.clone());
+}
+
+/// Template variables for an event
+fn event_variables(event: &NetworkEvent) -> HashMap<&'static str, String> {
+    let mut vars = HashMap::new();
+    vars.insert("event", event.name().to_string());
+    vars.insert("timestamp", clock::now_utc());
+
+    match event {
+        NetworkEvent::NewDevice(device)
+        | NetworkEvent::DeviceOnline(device)
+        | NetworkEvent::DeviceOffline(device) => device_variables(&mut vars, device),
+        NetworkEvent::Alert(alert) => {
+            let text = |key: &str| alert.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+            vars.insert("alert_id", text("id"));
+            vars.insert("severity", text("severity"));
+            vars.insert("category", text("category"));
+            vars.insert("title", text("title"));
+            vars.insert("description", text("description"));
+            vars.insert("device_id", text("source_device"));
+            vars.insert("device_ip", text("source_ip"));
+            vars.insert("domain", text("domain"));
+            vars.insert("url", text("url"));
+            vars.insert("timestamp", text("timestamp"));
+        }
+    }
+    vars
+}
+
+/// Fill in {{variable}} placeholders in one pass (values are never re-expanded)
+fn render(template: &str, vars: &HashMap<&'static str, String>) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let key = rest[start + 2..start + 2 + len].trim();
+        out.push_str(&rest[..start]);
+        match vars.get(key) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + len + 4]),
+        }
+        rest = &rest[start + len + 4..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn severity_rank(severity: &str) -> usize {
+    SEVERITIES.iter().position(|s| *s == severity).unwrap_or(0)
+}
+
+fn matches(hook: &Hook, event: &NetworkEvent) -> bool {
+    if !hook.enabled || hook.event != event.name() {
+        return false;
+    }
+    match (event, &hook.min_severity) {
+        (NetworkEvent::Alert(alert), Some(min)) => {
+            let severity = alert.get("severity").and_then(|s| s.as_str()).unwrap_or("low");
+            severity_rank(severity) >= severity_rank(min)
+        }
+        _ => true,
+    }
+}
+
+/// Record a run against the hook's hourly budget; false if it is used up
+fn take_rate_limit(hook: &Hook) -> bool {
+    let mut recent = RECENT_RUNS.lock().unwrap();
+    let runs = recent.entry(hook.id.clone()).or_default();
+    while runs.front().is_some_and(|t| t.elapsed() > Duration::from_secs(3600)) {
+        runs.pop_front();
+    }
+    if runs.len() >= hook.max_runs_per_hour as usize {
+        return false;
+    }
+    runs.push_back(Instant::now());
+    true
+}
+
+fn capture(stream: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut stream) = stream {
+            let _ = stream.by_ref().take(MAX_OUTPUT_BYTES as u64).read_to_end(&mut output);
+            // Keep draining so a chatty command doesn't block on a full pipe
+            let _ = io::copy(&mut stream, &mut io::sink());
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    })
+}
+
+/// Run a hook's command for an event, killing it after the hook's timeout
+fn execute(hook: &Hook, vars: &HashMap<&'static str, String>) -> Result<HookRun, String> {
+    let args: Vec<String> = split_command(&hook.command)
+        .iter()
+        .map(|arg| render(arg, vars))
+        .collect();
+    let (program, rest) = args.split_first().ok_or_else(|| "Hook command is empty".to_string())?;
+
+    let mut command = Command::new(program);
+    command
+        .args(rest)
+        .current_dir(get_project_root())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    for (key, value) in vars {
+        command.env(format!("NM_{}", key.to_uppercase()), value);
+    }
+
+    let started = Instant::now();
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run hook command: {}", e))?;
+    let stdout = capture(child.stdout.take());
+    let stderr = capture(child.stderr.take());
+
+    let timeout = Duration::from_secs(hook.timeout_secs.max(1));
+    let mut timed_out = false;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() >= timeout => {
+                timed_out = true;
+                let _ = child.kill();
+                break child.wait().ok();
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(format!("Failed to wait for hook command: {}", e)),
+        }
+    };
+
+    Ok(HookRun {
+        hook_id: hook.id.clone(),
+        command: args,
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        duration_ms: started.elapsed().as_millis() as u64,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Run a hook and record the outcome in the audit log
+fn run_and_audit(hook: &Hook, vars: &HashMap<&'static str, String>, test: bool) -> Result<HookRun, String> {
+    let result = execute(hook, vars);
+    let summary = match &result {
+        Ok(run) if run.timed_out => format!("Hook \"{}\" timed out", hook.name),
+        Ok(run) => format!("Hook \"{}\" exited with {:?}", hook.name, run.exit_code),
+        Err(e) => format!("Hook \"{}\" failed: {}", hook.name, e),
+    };
+    let detail = json!({
+        "hook_id": hook.id,
+        "event": vars.get("event"),
+        "test": test,
+        "run": result.as_ref().ok(),
+        "error": result.as_ref().err(),
+    });
+    if let Err(e) = audit::record("hook", &summary, detail) {
+        log::warn!("Failed to audit hook run: {}", e);
+    }
+    result
+}
+
+/// Run a hook once against sample data, bypassing its rate limit
+pub fn test_hook(hook_id: &str) -> Result<HookRun, String> {
+    let hook = load_file()?
+        .hooks
+        .into_iter()
+        .find(|h| h.id == hook_id)
+        .ok_or_else(|| format!("Hook not found: {}", hook_id))?;
+
+    let sample = Device {
+        id: "test-device".to_string(),
+        mac: "00:11:22:33:44:55".to_string(),
+        ip: "192.168.1.123".to_string(),
+        hostname: Some("test-device".to_string()),
+        vendor: Some("Example Vendor".to_string()),
+        device_type: "unknown".to_string(),
+        first_seen: String::new(),
+        last_seen: String::new(),
+        is_online: true,
+        is_monitored: true,
+        has_certificate: false,
+        interception_mode: InterceptionMode::Full,
+        total_bytes: 0,
+        bytes_in: 0,
+        bytes_out: 0,
+        blocked_requests: 0,
+        alerts: 0,
+        os: None,
+    };
+    let event = match hook.event.as_str() {
+        "alert" => NetworkEvent::Alert(json!({
+            "id": "test-alert",
+            "severity": hook.min_severity.as_deref().unwrap_or("high"),
+            "category": "custom",
+            "title": "Test alert",
+            "description": "Sample alert sent by test_hook",
+            "source_device": sample.id,
+            "source_ip": sample.ip,
+            "timestamp": clock::now_utc(),
+        })),
+        "device_online" => NetworkEvent::DeviceOnline(sample),
+        "device_offline" => NetworkEvent::DeviceOffline(sample),
+        _ => NetworkEvent::NewDevice(sample),
+    };
+
+    run_and_audit(&hook, &event_variables(&event), true)
+}
+
+/// Run the hooks matching an event from the event bus
+pub fn notify(event: &NetworkEvent) -> Result<(), String> {
+    let hooks = load_file()?.hooks;
+    for hook in hooks.iter().filter(|h| matches(h, event)) {
+        let vars = event_variables(event);
+        if !take_rate_limit(hook) {
+            log::warn!("Hook {} rate limited", hook.name);
+            let _ = audit::record(
+                "hook",
+                &format!("Hook \"{}\" skipped: over {} runs per hour", hook.name, hook.max_runs_per_hour),
+                json!({ "hook_id": hook.id, "event": event.name(), "rate_limited": true }),
+            );
+            continue;
+        }
+
+        let hook = hook.clone();
+        // Each run gets its own thread so a slow hook doesn't hold up the others
+        thread::spawn(move || {
+            let _ = run_and_audit(&hook, &vars, false);
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,269 @@
+// System-wide hotkeys
+//
+// Three actions can be bound to global shortcuts that work while the window is
+// hidden: pausing (or resuming) monitoring, a panic stop that tears everything
+// down and puts the network and MAC address back, and toggling stealth mode.
+// Bindings are checked before they are saved: a shortcut that doesn't parse, is
+// bound twice, or is already taken by another application is reported as a
+// conflict rather than silently not firing.
+
+use crate::commands::{self, load_settings, save_settings};
+use crate::platform;
+use crate::python::{kill_python_processes, run_stealth_command, send_command_to_process};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+/// Event emitted when a hotkey fires, with the action's name
+pub const HOTKEY_EVENT: &str = "hotkey-triggered";
+
+/// How long a panic stop waits for the ARP gateway to restore targets before killing it
+const PANIC_RESTORE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shortcuts in the accelerator format, e.g. "CommandOrControl+Shift+P"; None leaves an action unbound
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct HotkeySettings {
+    pub pause_monitoring: Option<String>,
+    pub panic_stop: Option<String>,
+    pub toggle_stealth: Option<String>,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        HotkeySettings {
+            pause_monitoring: Some("CommandOrControl+Alt+Shift+P".to_string()),
+            panic_stop: Some("CommandOrControl+Alt+Shift+X".to_string()),
+            toggle_stealth: Some("CommandOrControl+Alt+Shift+S".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    PauseMonitoring,
+    PanicStop,
+    ToggleStealth,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HotkeyConflict {
+    pub action: HotkeyAction,
+    pub shortcut: String,
+    pub reason: String,
+}
+
+/// Shortcuts currently registered, and the action each runs
+static BOUND: Mutex<Vec<(Shortcut, HotkeyAction)>> = Mutex::new(Vec::new());
+
+impl HotkeySettings {
+    fn bindings(&self) -> Vec<(HotkeyAction, &str)> {
+        [
+            (HotkeyAction::PauseMonitoring, &self.pause_monitoring),
+            (HotkeyAction::PanicStop, &self.panic_stop),
+            (HotkeyAction::ToggleStealth, &self.toggle_stealth),
+        ]
+        .into_iter()
+        .filter_map(|(action, shortcut)| {
+            shortcut.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(|s| (action, s))
+        })
+        .collect()
+    }
+}
+
+/// Bindings that can't parse or share a shortcut with an earlier one
+fn check(settings: &HotkeySettings) -> (Vec<(Shortcut, HotkeyAction)>, Vec<HotkeyConflict>) {
+    let mut parsed: Vec<(Shortcut, HotkeyAction)> = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for (action, text) in settings.bindings() {
+        let shortcut = match text.parse::<Shortcut>() {
+            Ok(shortcut) => shortcut,
+            Err(e) => {
+                conflicts.push(HotkeyConflict {
+                    action,
+                    shortcut: text.to_string(),
+                    reason: format!("Invalid shortcut: {}", e),
+                });
+                continue;
+            }
+        };
+
+        if let Some((_, other)) = parsed.iter().find(|(s, _)| *s == shortcut) {
+            conflicts.push(HotkeyConflict {
+                action,
+                shortcut: text.to_string(),
+                reason: format!("Already bound to {:?}", other),
+            });
+            continue;
+        }
+        parsed.push((shortcut, action));
+    }
+
+    (parsed, conflicts)
+}
+
+/// Replace the registered shortcuts, returning any the system refused
+pub fn apply(app: &AppHandle, settings: &HotkeySettings) -> Vec<HotkeyConflict> {
+    let (parsed, mut conflicts) = check(settings);
+    let texts = settings.bindings();
+
+    let shortcuts = app.global_shortcut();
+    if let Err(e) = shortcuts.unregister_all() {
+        log::warn!("Failed to unregister hotkeys: {}", e);
+    }
+
+    let mut bound = Vec::new();
+    for (shortcut, action) in parsed {
+        match shortcuts.register(shortcut) {
+            Ok(()) => bound.push((shortcut, action)),
+            Err(e) => {
+                let text = texts.iter().find(|(a, _)| *a == action).map(|(_, t)| t.to_string()).unwrap_or_default();
+                conflicts.push(HotkeyConflict {
+                    action,
+                    shortcut: text,
+                    reason: format!("In use by another application: {}", e),
+                });
+            }
+        }
+    }
+
+    log::info!("Registered {} hotkeys", bound.len());
+    if let Ok(mut current) = BOUND.lock() {
+        *current = bound;
+    }
+    conflicts
+}
+
+/// Validate, register and save new bindings
+///
+/// Bindings that don't parse or are duplicated are rejected without changing
+/// anything. Ones another application holds are still saved (they work once it
+/// lets go after a restart) and returned as conflicts.
+pub fn set(app: &AppHandle, hotkeys: HotkeySettings) -> Result<Vec<HotkeyConflict>, String> {
+    let (_, conflicts) = check(&hotkeys);
+    if !conflicts.is_empty() {
+        return Ok(conflicts);
+    }
+
+    let conflicts = apply(app, &hotkeys);
+    let mut settings = load_settings()?;
+    settings.hotkeys = hotkeys;
+    save_settings(&settings)?;
+    Ok(conflicts)
+}
+
+/// Register the saved bindings at startup
+pub fn init(app: &AppHandle) {
+    let settings = load_settings().map(|s| s.hotkeys).unwrap_or_default();
+    for conflict in apply(app, &settings) {
+        log::warn!("Hotkey {} for {:?} not registered: {}", conflict.shortcut, conflict.action, conflict.reason);
+    }
+}
+
+/// Global shortcut plugin handler
+pub fn handle_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+
+    let action = BOUND
+        .lock()
+        .ok()
+        .and_then(|bound| bound.iter().find(|(s, _)| s == shortcut).map(|(_, a)| *a));
+    let Some(action) = action else {
+        return;
+    };
+
+    log::info!("Hotkey triggered: {:?}", action);
+    let _ = app.emit(HOTKEY_EVENT, action);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match action {
+            HotkeyAction::PauseMonitoring => toggle_monitoring(&app).await,
+            HotkeyAction::PanicStop => panic_stop(&app).await,
+            HotkeyAction::ToggleStealth => toggle_stealth(),
+        };
+        if let Err(e) = result {
+            log::error!("Hotkey {:?} failed: {}", action, e);
+        }
+    });
+}
+
+async fn toggle_monitoring(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let result = if state.lifecycle.current().is_running() {
+        commands::stop_monitoring(state).await
+    } else {
+        commands::start_monitoring(state).await
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// Stop everything and undo what monitoring changed on the network
+///
+/// The ARP gateway is asked to stop first so it re-announces the real gateway
+/// to every target; only then are the processes killed. The original MAC
+/// address is restored even if monitoring wasn't running.
+pub async fn panic_stop(app: &AppHandle) -> Result<(), String> {
+    log::warn!("Panic stop");
+    let state = app.state::<AppState>();
+
+    {
+        let mut processes = state.python_processes.lock().unwrap();
+        for process in processes.iter_mut() {
+            let _ = send_command_to_process(process, &json!({ "action": "stop" }));
+        }
+
+        let deadline = Instant::now() + PANIC_RESTORE_TIMEOUT;
+        while Instant::now() < deadline
+            && processes.iter_mut().any(|p| matches!(p.try_wait(), Ok(None)))
+        {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        kill_python_processes(&mut processes);
+    }
+
+    if state.lifecycle.current().is_running() {
+        if let Err(e) = commands::stop_monitoring(state).await {
+            log::warn!("Failed to stop monitoring: {}", e);
+        }
+    }
+
+    let interface = load_settings()?.network_interface.unwrap_or_else(platform::default_interface);
+    let result = run_stealth_command("restore", &interface, None)?;
+    // Fails harmlessly when the MAC was never changed
+    if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let message = result.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown error");
+        log::warn!("MAC address not restored: {}", message);
+    }
+    Ok(())
+}
+
+/// Turn stealth mode off (restoring the real MAC) or back on (applying the profile)
+fn toggle_stealth() -> Result<(), String> {
+    let mut settings = load_settings()?;
+    let interface = settings.network_interface.clone().unwrap_or_else(platform::default_interface);
+    let enable = !settings.stealth_enabled;
+
+    let result = if enable {
+        run_stealth_command("apply", &interface, Some(&settings.device_profile))?
+    } else {
+        run_stealth_command("restore", &interface, None)?
+    };
+    if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let error = result.get("message").or(result.get("error")).and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        return Err(error.to_string());
+    }
+
+    settings.stealth_enabled = enable;
+    save_settings(&settings)?;
+    log::info!("Stealth mode {}", if enable { "enabled" } else { "disabled" });
+    Ok(())
+}
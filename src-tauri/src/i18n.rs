@@ -0,0 +1,102 @@
+// Message catalogs for backend text
+//
+// Alert titles and descriptions, report text and command errors are looked up
+// by key in `locales/<code>.json` for the `locale` setting, falling back to
+// English and then to the key itself. Placeholders are written `{name}`. The
+// Python side reads the same catalogs (`python/utils/i18n.py`).
+
+use crate::commands::load_settings;
+use crate::python::get_project_root;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+#[derive(Debug, Deserialize, Clone)]
+struct Catalog {
+    name: String,
+    messages: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LocaleInfo {
+    pub code: String,
+    pub name: String,
+}
+
+/// Catalogs loaded so far, by locale code (None if missing or unreadable)
+static CATALOGS: Mutex<Option<HashMap<String, Option<Catalog>>>> = Mutex::new(None);
+
+fn locales_dir() -> PathBuf {
+    get_project_root().join("locales")
+}
+
+fn load_catalog(code: &str) -> Option<Catalog> {
+    let content = fs::read_to_string(locales_dir().join(format!("{}.json", code))).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(catalog) => Some(catalog),
+        Err(e) => {
+            log::warn!("Failed to parse {} catalog: {}", code, e);
+            None
+        }
+    }
+}
+
+fn lookup(code: &str, key: &str) -> Option<String> {
+    let mut catalogs = CATALOGS.lock().ok()?;
+    catalogs
+        .get_or_insert_with(HashMap::new)
+        .entry(code.to_string())
+        .or_insert_with(|| load_catalog(code))
+        .as_ref()
+        .and_then(|c| c.messages.get(key).cloned())
+}
+
+/// The configured locale
+pub fn locale() -> String {
+    load_settings().map(|s| s.locale).unwrap_or_else(|_| DEFAULT_LOCALE.to_string())
+}
+
+/// Translate `key` into the configured locale, filling in `{name}` placeholders
+pub fn t(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let locale = locale();
+    let template = lookup(&locale, key)
+        .or_else(|| lookup(DEFAULT_LOCALE, key))
+        .unwrap_or_else(|| key.to_string());
+
+    args.iter().fold(template, |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), &value.to_string())
+    })
+}
+
+/// Locales with a catalog, English first
+pub fn available_locales() -> Result<Vec<LocaleInfo>, String> {
+    let entries = fs::read_dir(locales_dir()).map_err(|e| format!("Failed to read locales: {}", e))?;
+
+    let mut locales: Vec<LocaleInfo> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|p| {
+            let code = p.file_stem()?.to_string_lossy().to_string();
+            let catalog = load_catalog(&code)?;
+            Some(LocaleInfo { code, name: catalog.name })
+        })
+        .collect();
+    locales.sort_by(|a, b| (a.code != DEFAULT_LOCALE, &a.code).cmp(&(b.code != DEFAULT_LOCALE, &b.code)));
+    Ok(locales)
+}
+
+/// Check a locale has a catalog before it is saved
+pub fn validate_locale(code: &str) -> Result<(), String> {
+    // English is built in: keys fall back to it even without catalogs
+    if code == DEFAULT_LOCALE || available_locales()?.iter().any(|l| l.code == code) {
+        Ok(())
+    } else {
+        Err(t("error.unknown_locale", &[("locale", &code)]))
+    }
+}
@@ -0,0 +1,141 @@
+// Hosts that are never intercepted
+//
+// Some traffic shouldn't be decrypted at all, such as banking or medical
+// sites. An exclusion names a domain (and its subdomains), either for every
+// device or for one device. The proxy passes matching TLS connections through
+// at the ClientHello without a certificate swap, and records only who
+// connected to which server. Device exclusions reach the proxy by the device's
+// current IP, as the pass-through list of its config file (see
+// `proxy_config`), which is rewritten whenever the list changes.
+
+use crate::clock;
+use crate::commands::Device;
+use crate::proxy_config;
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Serializes read-modify-write of the exclusions file
+static EXCLUSIONS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InterceptExclusion {
+    pub domain: String,
+    /// Only this device's traffic is excluded; every device's when None
+    #[serde(default)]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub created_at: String,
+}
+
+fn get_exclusions_path() -> PathBuf {
+    workspace::config_dir().join("intercept_exclusions.json")
+}
+
+pub fn load_exclusions() -> Result<Vec<InterceptExclusion>, String> {
+    let path = get_exclusions_path();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read intercept exclusions: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse intercept exclusions: {}", e))
+}
+
+fn save_exclusions(exclusions: &[InterceptExclusion]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(exclusions)
+        .map_err(|e| format!("Failed to serialize intercept exclusions: {}", e))?;
+    fs::write(get_exclusions_path(), content)
+        .map_err(|e| format!("Failed to write intercept exclusions: {}", e))
+}
+
+/// Lowercase a domain, dropping a pasted URL's scheme, port and path and a
+/// trailing dot, and check its shape
+pub fn normalize_domain(domain: &str) -> Result<String, String> {
+    let domain = domain.trim();
+    let domain = domain.split_once("://").map_or(domain, |(_, rest)| rest);
+    let host = domain.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = host.split(':').next().unwrap_or_default();
+    let domain = host.trim_end_matches('.').to_lowercase();
+    let valid = !domain.is_empty()
+        && domain.len() <= 253
+        && domain.contains('.')
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+
+    if valid {
+        Ok(domain)
+    } else {
+        Err(format!("Invalid domain: {} (expected e.g. \"mybank.com\")", domain))
+    }
+}
+
+/// Exclusions as (device IP, domain) for the proxy; device exclusions whose
+/// device has no known IP are left out
+pub fn resolve(exclusions: &[InterceptExclusion], devices: &[Device]) -> Vec<(Option<String>, String)> {
+    exclusions
+        .iter()
+        .filter_map(|e| match &e.device_id {
+            None => Some((None, e.domain.clone())),
+            Some(id) => devices
+                .iter()
+                .find(|d| &d.id == id && !d.ip.is_empty())
+                .map(|d| (Some(d.ip.clone()), e.domain.clone())),
+        })
+        .collect()
+}
+
+/// Rewrite the proxy config with the new list; a running proxy reloads it
+fn push(state: &AppState) -> Result<(), String> {
+    proxy_config::sync(state).map(|_| ())
+}
+
+/// Add an exclusion (a no-op if it already exists) and push the new list
+pub fn add(state: &AppState, domain: &str, device_id: Option<&str>) -> Result<InterceptExclusion, String> {
+    let exclusion = InterceptExclusion {
+        domain: normalize_domain(domain)?,
+        device_id: device_id.map(str::to_string),
+        created_at: clock::now_utc(),
+    };
+
+    {
+        let _guard = EXCLUSIONS_LOCK.lock().unwrap();
+        let mut exclusions = load_exclusions()?;
+        if let Some(existing) = exclusions
+            .iter()
+            .find(|e| e.domain == exclusion.domain && e.device_id == exclusion.device_id)
+        {
+            return Ok(existing.clone());
+        }
+        exclusions.push(exclusion.clone());
+        save_exclusions(&exclusions)?;
+    }
+
+    log::info!("Excluded {} from interception ({})", exclusion.domain, device_id.unwrap_or("all devices"));
+    push(state)?;
+    Ok(exclusion)
+}
+
+/// Remove an exclusion and push the new list
+pub fn remove(state: &AppState, domain: &str, device_id: Option<&str>) -> Result<(), String> {
+    let domain = normalize_domain(domain)?;
+
+    {
+        let _guard = EXCLUSIONS_LOCK.lock().unwrap();
+        let mut exclusions = load_exclusions()?;
+        let before = exclusions.len();
+        exclusions.retain(|e| !(e.domain == domain && e.device_id.as_deref() == device_id));
+        if exclusions.len() == before {
+            return Err(format!("No intercept exclusion for {}", domain));
+        }
+        save_exclusions(&exclusions)?;
+    }
+
+    log::info!("Removed interception exclusion for {} ({})", domain, device_id.unwrap_or("all devices"));
+    push(state)
+}
@@ -0,0 +1,84 @@
+// Per-device interception mode
+//
+// A device is either fully intercepted (redirected through this machine and
+// its TLS decrypted), metadata only (redirected, but TLS passed through so
+// only who talked to which server is recorded), or not intercepted at all
+// (never redirected). The mode is stored with the device. The ARP gateway is
+// told which devices to redirect (narrowed by the spoof target selection) and
+// the proxy which ones to pass through, at start and whenever a mode changes.
+
+use crate::commands::{broadcast_to_processes, fetch_devices, Device};
+use crate::db_writer::write_database;
+use crate::spoofing;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InterceptionMode {
+    #[default]
+    Full,
+    MetadataOnly,
+    None,
+}
+
+impl InterceptionMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InterceptionMode::Full => "full",
+            InterceptionMode::MetadataOnly => "metadata_only",
+            InterceptionMode::None => "none",
+        }
+    }
+}
+
+/// Whether a device's mode has it redirected through this machine
+pub fn redirected(device: &Device) -> bool {
+    device.is_monitored && device.interception_mode != InterceptionMode::None && !device.ip.is_empty()
+}
+
+/// Extra proxy arguments naming the devices whose TLS is passed through
+pub fn proxy_args(devices: &[Device]) -> Vec<String> {
+    devices
+        .iter()
+        .filter(|d| d.interception_mode == InterceptionMode::MetadataOnly && !d.ip.is_empty())
+        .flat_map(|d| ["--metadata-only".to_string(), d.ip.clone()])
+        .collect()
+}
+
+/// Store a device's mode and apply it to the running gateway and proxy
+pub fn set_mode(state: &AppState, device_id: &str, mode: InterceptionMode) -> Result<(), String> {
+    let result = write_database(
+        "update-device",
+        &[("--device", device_id), ("--interception-mode", mode.as_str())],
+    )?;
+    if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        return Err(error.to_string());
+    }
+
+    let device = fetch_devices()?
+        .into_iter()
+        .find(|d| d.id == device_id)
+        .ok_or_else(|| format!("Device not found: {}", device_id))?;
+    state.snapshot.clear();
+
+    if spoofing::should_spoof(&device) {
+        broadcast_to_processes(state, &json!({
+            "action": "add_target",
+            "ip": device.ip,
+            "hostname": device.hostname,
+        }));
+    } else {
+        broadcast_to_processes(state, &json!({ "action": "remove_target", "ip": device.ip }));
+    }
+    broadcast_to_processes(state, &json!({
+        "action": "set_interception_mode",
+        "device_ip": device.ip,
+        "mode": mode,
+    }));
+
+    log::info!("Interception mode for {} ({}) set to {}", device_id, device.ip, mode.as_str());
+    Ok(())
+}
@@ -0,0 +1,140 @@
+// Device inventory history
+//
+// Every device scan (and every device the ARP gateway learns about) is
+// recorded by `upsert_device` in `python/database/db_manager.py`, which logs
+// what changed since the device was last seen to the `device_events` table:
+// a new device, a new IP address, hostname or vendor, another device on the
+// same address, a device gone for a week ("absent") and its return. The log
+// is never pruned, so it answers "when did this show up" and "what was on the
+// network last month".
+
+use crate::apps::UsageRange;
+use crate::database::open_readonly;
+use crate::db_writer;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Most events returned for one query, newest first
+const MAX_EVENTS: u32 = 1000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceEvent {
+    pub id: i64,
+    pub timestamp: String,
+    pub device_id: String,
+    pub mac: String,
+    /// "new_device", "ip_changed", "hostname_changed", "vendor_changed", "ip_conflict", "absent" or "returned"
+    pub event: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceChangelog {
+    pub device_id: String,
+    pub events: Vec<DeviceEvent>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InventoryChanges {
+    pub start: String,
+    pub end: String,
+    pub events: Vec<DeviceEvent>,
+    /// Events of each kind in the range
+    pub counts: BTreeMap<String, u64>,
+    /// More events matched than were returned
+    pub truncated: bool,
+}
+
+/// Record the devices a scan found, returning the events it caused
+pub fn record_scan(devices: &Value) -> Result<Vec<Value>, String> {
+    let path = std::env::temp_dir().join(format!("device-scan-{}.json", std::process::id()));
+    fs::write(&path, devices.to_string()).map_err(|e| format!("Failed to write scanned devices: {}", e))?;
+    let result = db_writer::write_database("record-devices", &[("--input", &path.to_string_lossy())]);
+    let _ = fs::remove_file(&path);
+    let result = result?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let events = result.get("events").and_then(|e| e.as_array()).cloned().unwrap_or_default();
+        if !events.is_empty() {
+            log::info!("Device scan recorded {} inventory changes", events.len());
+        }
+        Ok(events)
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Inventory events for a device (or all devices) in an optional range, newest first
+pub fn events_from(
+    conn: &Connection,
+    device_id: Option<&str>,
+    start: Option<&str>,
+    end: Option<&str>,
+    limit: u32,
+) -> Result<Vec<DeviceEvent>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp, device_id, mac_address, event, old_value, new_value
+             FROM device_events
+             WHERE (?1 IS NULL OR device_id = ?1) AND (?2 IS NULL OR timestamp >= ?2)
+                   AND (?3 IS NULL OR timestamp < ?3)
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?4",
+        )
+        .map_err(|e| format!("Failed to prepare device event query: {}", e))?;
+    let rows = stmt
+        .query_map(params![device_id, start, end, limit], |row| {
+            Ok(DeviceEvent {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                device_id: row.get(2)?,
+                mac: row.get(3)?,
+                event: row.get(4)?,
+                old_value: row.get(5)?,
+                new_value: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query device events: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read device events: {}", e))
+}
+
+/// Everything recorded about a device, newest first
+pub fn changelog(device_id: &str) -> Result<DeviceChangelog, String> {
+    let conn = open_readonly()?;
+    Ok(DeviceChangelog {
+        device_id: device_id.to_string(),
+        events: events_from(&conn, Some(device_id), None, None, MAX_EVENTS)?,
+    })
+}
+
+/// Inventory changes across the network in a range
+pub fn changes(range: UsageRange) -> Result<InventoryChanges, String> {
+    let (start, end) = range.bounds();
+    let conn = open_readonly()?;
+    let mut events = events_from(&conn, None, Some(&start), Some(&end), MAX_EVENTS + 1)?;
+    let truncated = events.len() > MAX_EVENTS as usize;
+    events.truncate(MAX_EVENTS as usize);
+
+    let mut stmt = conn
+        .prepare("SELECT event, COUNT(*) FROM device_events WHERE timestamp >= ?1 AND timestamp < ?2 GROUP BY event")
+        .map_err(|e| format!("Failed to prepare device event query: {}", e))?;
+    let counts = stmt
+        .query_map(params![start, end], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64)))
+        .map_err(|e| format!("Failed to count device events: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read device event counts: {}", e))?;
+
+    Ok(InventoryChanges {
+        start,
+        end,
+        events,
+        counts,
+        truncated,
+    })
+}
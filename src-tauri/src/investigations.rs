@@ -0,0 +1,400 @@
+// Investigations
+//
+// An investigation groups the devices, traffic entries and alerts looked at
+// while following something up, with dated notes and a status, so ad-hoc
+// digging ends up as one case. Cases are kept in the workspace data directory
+// and only reference the evidence; exporting one resolves the references and
+// writes everything still on record as a JSON bundle or a Markdown report.
+
+use crate::clock::{self, Zone};
+use crate::commands::{fetch_devices, TrafficEntry};
+use crate::database::{self, open_readonly};
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const MAX_NAME_LEN: usize = 200;
+
+/// Serializes read-modify-write of the investigations file
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InvestigationStatus {
+    #[default]
+    Open,
+    OnHold,
+    Closed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceKind {
+    Device,
+    Traffic,
+    Alert,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InvestigationNote {
+    pub timestamp: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Investigation {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub status: InvestigationStatus,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub devices: Vec<String>,
+    #[serde(default)]
+    pub traffic: Vec<String>,
+    #[serde(default)]
+    pub alerts: Vec<String>,
+    #[serde(default)]
+    pub notes: Vec<InvestigationNote>,
+}
+
+impl Investigation {
+    fn evidence(&mut self, kind: EvidenceKind) -> &mut Vec<String> {
+        match kind {
+            EvidenceKind::Device => &mut self.devices,
+            EvidenceKind::Traffic => &mut self.traffic,
+            EvidenceKind::Alert => &mut self.alerts,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct InvestigationsFile {
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    investigations: Vec<Investigation>,
+}
+
+fn get_path() -> PathBuf {
+    workspace::data_dir().join("investigations.json")
+}
+
+fn load_file() -> Result<InvestigationsFile, String> {
+    let path = get_path();
+    if !path.exists() {
+        return Ok(InvestigationsFile::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read investigations: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse investigations: {}", e))
+}
+
+fn save_file(file: &InvestigationsFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize investigations: {}", e))?;
+    fs::write(get_path(), content).map_err(|e| format!("Failed to write investigations: {}", e))
+}
+
+/// Apply `change` to one investigation and store it, returning the result
+fn update(
+    id: &str,
+    change: impl FnOnce(&mut Investigation) -> Result<(), String>,
+) -> Result<Investigation, String> {
+    let _guard = LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    let investigation = file
+        .investigations
+        .iter_mut()
+        .find(|i| i.id == id)
+        .ok_or_else(|| format!("Investigation not found: {}", id))?;
+
+    change(investigation)?;
+    investigation.updated_at = clock::now_utc();
+    let updated = investigation.clone();
+    save_file(&file)?;
+    Ok(updated)
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Investigation name is empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Investigation names can be at most {} characters", MAX_NAME_LEN));
+    }
+    Ok(name.to_string())
+}
+
+/// Every investigation, most recently updated first
+pub fn list() -> Result<Vec<Investigation>, String> {
+    let mut investigations = load_file()?.investigations;
+    investigations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(investigations)
+}
+
+pub fn get(id: &str) -> Result<Investigation, String> {
+    load_file()?
+        .investigations
+        .into_iter()
+        .find(|i| i.id == id)
+        .ok_or_else(|| format!("Investigation not found: {}", id))
+}
+
+pub fn create(name: &str) -> Result<Investigation, String> {
+    let name = validate_name(name)?;
+
+    let _guard = LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    file.next_id += 1;
+    let now = clock::now_utc();
+    let investigation = Investigation {
+        id: format!("inv-{}", file.next_id),
+        name,
+        status: InvestigationStatus::Open,
+        created_at: now.clone(),
+        updated_at: now,
+        devices: vec![],
+        traffic: vec![],
+        alerts: vec![],
+        notes: vec![],
+    };
+    file.investigations.push(investigation.clone());
+    save_file(&file)?;
+
+    log::info!("Created investigation {} \"{}\"", investigation.id, investigation.name);
+    Ok(investigation)
+}
+
+pub fn rename(id: &str, name: &str) -> Result<Investigation, String> {
+    let name = validate_name(name)?;
+    update(id, |i| {
+        i.name = name;
+        Ok(())
+    })
+}
+
+pub fn set_status(id: &str, status: InvestigationStatus) -> Result<Investigation, String> {
+    update(id, |i| {
+        i.status = status;
+        Ok(())
+    })
+}
+
+pub fn delete(id: &str) -> Result<(), String> {
+    let _guard = LOCK.lock().unwrap();
+    let mut file = load_file()?;
+    let before = file.investigations.len();
+    file.investigations.retain(|i| i.id != id);
+    if file.investigations.len() == before {
+        return Err(format!("Investigation not found: {}", id));
+    }
+    save_file(&file)
+}
+
+/// Check that the evidence exists before it is attached
+fn check_evidence(kind: EvidenceKind, item_id: &str) -> Result<(), String> {
+    let found = match kind {
+        EvidenceKind::Device => fetch_devices()?.iter().any(|d| d.id == item_id),
+        EvidenceKind::Traffic => !traffic_entries(&[item_id.to_string()])?.is_empty(),
+        EvidenceKind::Alert => database::stored_alerts()
+            .iter()
+            .any(|a| a.get("id").and_then(|v| v.as_str()) == Some(item_id)),
+    };
+    if found {
+        Ok(())
+    } else {
+        Err(format!("Evidence not found: {}", item_id))
+    }
+}
+
+pub fn attach(id: &str, kind: EvidenceKind, item_id: &str) -> Result<Investigation, String> {
+    check_evidence(kind, item_id)?;
+    update(id, |i| {
+        let evidence = i.evidence(kind);
+        if !evidence.iter().any(|e| e == item_id) {
+            evidence.push(item_id.to_string());
+        }
+        Ok(())
+    })
+}
+
+pub fn detach(id: &str, kind: EvidenceKind, item_id: &str) -> Result<Investigation, String> {
+    update(id, |i| {
+        i.evidence(kind).retain(|e| e != item_id);
+        Ok(())
+    })
+}
+
+pub fn add_note(id: &str, text: &str) -> Result<Investigation, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Note is empty".to_string());
+    }
+    update(id, |i| {
+        i.notes.push(InvestigationNote {
+            timestamp: clock::now_utc(),
+            text: text.to_string(),
+        });
+        Ok(())
+    })
+}
+
+/// Traffic entries still on record among the given IDs
+fn traffic_entries(ids: &[String]) -> Result<Vec<TrafficEntry>, String> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let conn = open_readonly()?;
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let mut stmt = conn
+        .prepare(&format!("SELECT * FROM traffic WHERE id IN ({}) ORDER BY timestamp", placeholders))
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(ids.iter()), database::row_to_traffic)
+        .map_err(|e| format!("Failed to query traffic: {}", e))?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read traffic: {}", e))
+}
+
+/// The investigation with its evidence resolved; `missing` lists references
+/// no longer on record (pruned by retention, forgotten devices)
+pub fn bundle(id: &str) -> Result<Value, String> {
+    let investigation = get(id)?;
+    let zone = Zone::configured();
+
+    let devices: Vec<Value> = fetch_devices()?
+        .into_iter()
+        .filter(|d| investigation.devices.contains(&d.id))
+        .map(|d| json!(d))
+        .collect();
+
+    let mut traffic = traffic_entries(&investigation.traffic)?;
+    for entry in &mut traffic {
+        entry.timestamp = zone.display(&entry.timestamp);
+    }
+
+    let alerts: Vec<Value> = database::stored_alerts()
+        .into_iter()
+        .filter(|a| {
+            a.get("id")
+                .and_then(|v| v.as_str())
+                .is_some_and(|id| investigation.alerts.iter().any(|a| a == id))
+        })
+        .collect();
+
+    let found = |items: &[Value], id: &str| items.iter().any(|v| v.get("id").and_then(|v| v.as_str()) == Some(id));
+    let missing: Vec<&String> = investigation
+        .devices
+        .iter()
+        .filter(|id| !found(&devices, id))
+        .chain(investigation.traffic.iter().filter(|id| !traffic.iter().any(|t| &t.id == *id)))
+        .chain(investigation.alerts.iter().filter(|id| !found(&alerts, id)))
+        .collect();
+
+    Ok(json!({
+        "exported_at": zone.display(&clock::now_utc()),
+        "investigation": investigation,
+        "devices": devices,
+        "traffic": traffic,
+        "alerts": alerts,
+        "missing": missing,
+    }))
+}
+
+fn markdown(bundle: &Value) -> String {
+    let text = |v: &Value, key: &str| v.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let items = |key: &str| bundle.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    let investigation = &bundle["investigation"];
+    let zone = Zone::configured();
+
+    let mut report = String::new();
+    let _ = writeln!(report, "# {}\n", text(investigation, "name"));
+    let _ = writeln!(report, "- Status: {}", text(investigation, "status").replace('_', " "));
+    let _ = writeln!(report, "- Opened: {}", zone.display(&text(investigation, "created_at")));
+    let _ = writeln!(report, "- Last updated: {}", zone.display(&text(investigation, "updated_at")));
+    let _ = writeln!(report, "- Exported: {}", text(bundle, "exported_at"));
+
+    let _ = writeln!(report, "\n## Notes\n");
+    for note in investigation["notes"].as_array().cloned().unwrap_or_default() {
+        let _ = writeln!(report, "- **{}** {}", zone.display(&text(&note, "timestamp")), text(&note, "text"));
+    }
+
+    let _ = writeln!(report, "\n## Devices\n");
+    for device in items("devices") {
+        let name = device
+            .get("hostname")
+            .and_then(|v| v.as_str())
+            .unwrap_or_else(|| device.get("ip").and_then(|v| v.as_str()).unwrap_or(""));
+        let _ = writeln!(report, "- {} ({}, {})", name, text(&device, "ip"), text(&device, "mac"));
+    }
+
+    let _ = writeln!(report, "\n## Alerts\n");
+    for alert in items("alerts") {
+        let _ = writeln!(
+            report,
+            "- {} [{}] {}: {}",
+            zone.display(&text(&alert, "timestamp")),
+            text(&alert, "severity"),
+            text(&alert, "title"),
+            text(&alert, "description")
+        );
+    }
+
+    let _ = writeln!(report, "\n## Traffic\n");
+    let _ = writeln!(report, "| Time | Device | Method | URL | Status | Tags | Note |");
+    let _ = writeln!(report, "|---|---|---|---|---|---|---|");
+    for entry in items("traffic") {
+        let tags: Vec<String> = entry["tags"]
+            .as_array()
+            .map(|t| t.iter().filter_map(|t| t.as_str()).map(str::to_string).collect())
+            .unwrap_or_default();
+        let cell = |s: String| s.replace('|', "\\|").replace('\n', " ");
+        let _ = writeln!(
+            report,
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            text(&entry, "timestamp"),
+            text(&entry, "device_ip"),
+            text(&entry, "method"),
+            cell(text(&entry, "url")),
+            entry.get("status_code").and_then(|s| s.as_u64()).map(|s| s.to_string()).unwrap_or_default(),
+            tags.join(", "),
+            cell(text(&entry, "note"))
+        );
+    }
+
+    let missing = items("missing");
+    if !missing.is_empty() {
+        let _ = writeln!(report, "\n## No longer on record\n");
+        for id in missing {
+            let _ = writeln!(report, "- {}", id.as_str().unwrap_or(""));
+        }
+    }
+    report
+}
+
+/// Write the investigation to `path` as "json" or "markdown", returning the path
+pub fn export(id: &str, path: &str, format: &str) -> Result<String, String> {
+    let bundle = bundle(id)?;
+    let content = match format {
+        "json" => serde_json::to_string_pretty(&bundle)
+            .map_err(|e| format!("Failed to serialize investigation: {}", e))?,
+        "markdown" => markdown(&bundle),
+        other => return Err(format!("Unknown export format: {}", other)),
+    };
+
+    if let Some(parent) = PathBuf::from(path).parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create export directory: {}", e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write investigation export: {}", e))?;
+    log::info!("Exported investigation {} to {} ({})", id, path, format);
+    Ok(path.to_string())
+}
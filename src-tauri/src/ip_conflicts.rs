@@ -0,0 +1,322 @@
+// IP conflicts, flapping leases and reservation suggestions
+//
+// Device scans log two kinds of trouble to the inventory (see `inventory`):
+// an `ip_conflict` when two MACs answer for the same address, or are both
+// seen on it within a few minutes, and an `ip_changed` each time a device
+// comes back on a new address. A device whose address keeps changing has a
+// flapping lease. Either one is how "the printer disappeared" happens, and a
+// DHCP reservation fixes both, so the report suggests reservations for the
+// devices involved and for the ones others connect to by address.
+
+use crate::clock;
+use crate::commands::Device;
+use crate::database::{self, open_readonly};
+use crate::event_bus;
+use crate::i18n::t;
+use crate::phone_home;
+use crate::state::AppState;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often conflicts and flapping are checked while monitoring runs
+const ANALYSIS_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Hours the background check looks back
+const ANALYSIS_HOURS: u32 = 24;
+
+/// Address changes in the window that make a lease flapping
+const FLAP_CHANGES: u32 = 3;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct IpConflict {
+    pub ip: String,
+    pub macs: Vec<String>,
+    /// Known devices among the MACs
+    pub device_ids: Vec<String>,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// Days the conflict was seen on
+    pub days: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FlappingLease {
+    pub device_id: String,
+    pub mac: String,
+    pub changes: u32,
+    /// Addresses the device had, oldest first
+    pub addresses: Vec<String>,
+    pub last_change: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReservationSuggestion {
+    pub device_id: String,
+    pub name: String,
+    pub mac: String,
+    /// Address to reserve; None when another suggestion already takes the current one
+    pub ip: Option<String>,
+    /// "conflict", "flapping" or "fixed_role"
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct IpConflictReport {
+    pub since: String,
+    pub conflicts: Vec<IpConflict>,
+    pub flapping: Vec<FlappingLease>,
+    pub reservations: Vec<ReservationSuggestion>,
+}
+
+#[derive(Default)]
+struct ConflictAccumulator {
+    macs: BTreeSet<String>,
+    device_ids: BTreeSet<String>,
+    days: BTreeSet<String>,
+    first_seen: String,
+    last_seen: String,
+}
+
+/// Conflicts logged since `since`, most recent first
+pub fn conflicts_from(conn: &Connection, since: &str) -> Result<Vec<IpConflict>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT new_value, device_id, mac_address, old_value, timestamp FROM device_events
+             WHERE event = 'ip_conflict' AND timestamp >= ?1 AND new_value IS NOT NULL
+             ORDER BY timestamp",
+        )
+        .map_err(|e| format!("Failed to prepare IP conflict query: {}", e))?;
+    let rows = stmt
+        .query_map(params![since], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query IP conflicts: {}", e))?;
+
+    let mut by_ip: BTreeMap<String, ConflictAccumulator> = BTreeMap::new();
+    for row in rows {
+        let (ip, device_id, mac, other_mac, timestamp) = row.map_err(|e| format!("Failed to read IP conflict: {}", e))?;
+        let conflict = by_ip.entry(ip).or_default();
+        conflict.macs.insert(mac);
+        conflict.macs.extend(other_mac);
+        conflict.device_ids.insert(device_id);
+        conflict.days.insert(timestamp.chars().take(10).collect());
+        if conflict.first_seen.is_empty() {
+            conflict.first_seen = timestamp.clone();
+        }
+        conflict.last_seen = timestamp;
+    }
+
+    let mut conflicts: Vec<IpConflict> = by_ip
+        .into_iter()
+        .map(|(ip, c)| IpConflict {
+            ip,
+            macs: c.macs.into_iter().collect(),
+            device_ids: c.device_ids.into_iter().collect(),
+            first_seen: c.first_seen,
+            last_seen: c.last_seen,
+            days: c.days.len() as u32,
+        })
+        .collect();
+    conflicts.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    Ok(conflicts)
+}
+
+/// Devices whose address changed at least FLAP_CHANGES times since `since`, most changes first
+pub fn flapping_from(conn: &Connection, since: &str) -> Result<Vec<FlappingLease>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, mac_address, old_value, new_value, timestamp FROM device_events
+             WHERE event = 'ip_changed' AND timestamp >= ?1
+             ORDER BY timestamp, id",
+        )
+        .map_err(|e| format!("Failed to prepare address change query: {}", e))?;
+    let rows = stmt
+        .query_map(params![since], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query address changes: {}", e))?;
+
+    let mut by_device: BTreeMap<String, FlappingLease> = BTreeMap::new();
+    for row in rows {
+        let (device_id, mac, old_ip, new_ip, timestamp) =
+            row.map_err(|e| format!("Failed to read address change: {}", e))?;
+        let lease = by_device.entry(device_id.clone()).or_insert_with(|| FlappingLease {
+            device_id,
+            mac,
+            changes: 0,
+            addresses: old_ip.into_iter().collect(),
+            last_change: String::new(),
+        });
+        lease.changes += 1;
+        if let Some(ip) = new_ip.filter(|ip| !lease.addresses.contains(ip)) {
+            lease.addresses.push(ip);
+        }
+        lease.last_change = timestamp;
+    }
+
+    let mut flapping: Vec<FlappingLease> = by_device.into_values().filter(|l| l.changes >= FLAP_CHANGES).collect();
+    flapping.sort_by(|a, b| b.changes.cmp(&a.changes).then_with(|| a.device_id.cmp(&b.device_id)));
+    Ok(flapping)
+}
+
+/// Reservations for devices in conflicts, with flapping leases, or that others connect to
+pub fn suggest_reservations(
+    devices: &[Device],
+    conflicts: &[IpConflict],
+    flapping: &[FlappingLease],
+) -> Vec<ReservationSuggestion> {
+    let conflicted: HashSet<&str> = conflicts.iter().flat_map(|c| c.device_ids.iter().map(String::as_str)).collect();
+    let flaps: HashSet<&str> = flapping.iter().map(|l| l.device_id.as_str()).collect();
+
+    let mut candidates: Vec<(u8, &Device)> = devices
+        .iter()
+        .filter(|d| !d.mac.is_empty() && d.device_type != "router")
+        .filter_map(|d| {
+            let rank = if conflicted.contains(d.id.as_str()) {
+                0
+            } else if flaps.contains(d.id.as_str()) {
+                1
+            } else if phone_home::is_iot(d) {
+                2
+            } else {
+                return None;
+            };
+            Some((rank, d))
+        })
+        .collect();
+    // Devices others connect to keep their address when two want the same one
+    candidates.sort_by(|(a_rank, a), (b_rank, b)| {
+        phone_home::is_iot(b)
+            .cmp(&phone_home::is_iot(a))
+            .then(a_rank.cmp(b_rank))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    let mut taken: HashSet<&str> = HashSet::new();
+    let mut suggestions: Vec<(u8, ReservationSuggestion)> = candidates
+        .into_iter()
+        .map(|(rank, device)| {
+            let reason = ["conflict", "flapping", "fixed_role"][rank as usize];
+            let ip = taken.insert(device.ip.as_str()).then(|| device.ip.clone());
+            (
+                rank,
+                ReservationSuggestion {
+                    device_id: device.id.clone(),
+                    name: device.hostname.clone().unwrap_or_else(|| device.ip.clone()),
+                    mac: device.mac.clone(),
+                    ip,
+                    reason: reason.to_string(),
+                },
+            )
+        })
+        .collect();
+    suggestions.sort_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then_with(|| a.name.cmp(&b.name)));
+    suggestions.into_iter().map(|(_, s)| s).collect()
+}
+
+/// Conflicts and flapping leases in the last `hours`, with suggested reservations
+pub fn report(devices: &[Device], hours: u32) -> Result<IpConflictReport, String> {
+    let since = clock::utc_cutoff(chrono::Duration::hours(hours as i64));
+    let conn = open_readonly()?;
+    let conflicts = conflicts_from(&conn, &since)?;
+    let flapping = flapping_from(&conn, &since)?;
+    let reservations = suggest_reservations(devices, &conflicts, &flapping);
+    Ok(IpConflictReport {
+        since,
+        conflicts,
+        flapping,
+        reservations,
+    })
+}
+
+/// Values of a metadata key on alerts raised today that carry `flag`
+fn alerted_today(flag: &str, key: &str) -> HashSet<String> {
+    let today = clock::utc_cutoff(chrono::Duration::hours(24));
+    database::stored_alerts()
+        .iter()
+        .filter(|a| a.pointer(&format!("/metadata/{}", flag)).and_then(|b| b.as_bool()) == Some(true))
+        .filter(|a| a.get("timestamp").and_then(|t| t.as_str()).is_some_and(|t| t >= today.as_str()))
+        .filter_map(|a| a.pointer(&format!("/metadata/{}", key)).and_then(|v| v.as_str()).map(str::to_string))
+        .collect()
+}
+
+fn raise_alerts(report: &IpConflictReport) {
+    let conflicts_alerted = alerted_today("ip_conflict", "ip");
+    for conflict in report.conflicts.iter().filter(|c| !conflicts_alerted.contains(&c.ip)) {
+        let mut metadata = json!(conflict);
+        metadata["ip_conflict"] = json!(true);
+
+        log::warn!("IP conflict on {} between {}", conflict.ip, conflict.macs.join(", "));
+        event_bus::raise_alert(
+            "medium",
+            "network",
+            &t("alert.ip_conflict.title", &[("ip", &conflict.ip)]),
+            &t(
+                "alert.ip_conflict.description",
+                &[("ip", &conflict.ip), ("macs", &conflict.macs.join(", "))],
+            ),
+            conflict.device_ids.first().map(String::as_str),
+            Some(&metadata),
+        );
+    }
+
+    let flapping_alerted = alerted_today("lease_flapping", "device_id");
+    for lease in report.flapping.iter().filter(|l| !flapping_alerted.contains(&l.device_id)) {
+        let mut metadata = json!(lease);
+        metadata["lease_flapping"] = json!(true);
+
+        log::warn!("Device {} changed address {} times", lease.device_id, lease.changes);
+        event_bus::raise_alert(
+            "low",
+            "network",
+            &t("alert.lease_flapping.title", &[("device", &lease.device_id)]),
+            &t(
+                "alert.lease_flapping.description",
+                &[
+                    ("device", &lease.device_id),
+                    ("changes", &lease.changes),
+                    ("addresses", &lease.addresses.join(", ")),
+                ],
+            ),
+            Some(&lease.device_id),
+            Some(&metadata),
+        );
+    }
+}
+
+/// Spawn the background task that alerts on IP conflicts and flapping leases while monitoring runs
+pub fn spawn_ip_conflict_detector(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(ANALYSIS_INTERVAL).await;
+
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if !handle.state::<AppState>().lifecycle.current().is_running() {
+                    return;
+                }
+                match report(&[], ANALYSIS_HOURS) {
+                    Ok(report) => raise_alerts(&report),
+                    Err(e) => log::debug!("IP conflict analysis failed: {}", e),
+                }
+            })
+            .await;
+        }
+    });
+}
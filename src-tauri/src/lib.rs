@@ -0,0 +1,482 @@
+// Network Monitor - Tauri Backend
+// Library shared by the desktop app and `netmon-cli`
+
+mod access;
+mod activity;
+mod advisories;
+mod aggregation;
+mod alert_evidence;
+mod alert_policy;
+mod annotations;
+mod api_keys;
+mod app_limits;
+mod apps;
+mod audit;
+mod autostart;
+mod bandwidth;
+mod beaconing;
+mod block_page;
+mod body_capture;
+mod capture_driver;
+mod cert_guide;
+mod cert_server;
+pub mod cli;
+mod clock;
+mod commands;
+mod credential_exposure;
+mod daemon;
+mod database;
+mod db_writer;
+mod demo;
+mod diagnostics;
+mod dhcp;
+mod discovery;
+mod dns_bypass;
+mod dns_tunneling;
+mod domains;
+mod elevation;
+mod enrichment;
+mod event_bus;
+mod events;
+mod flows;
+mod forwarder;
+mod geo;
+mod grpc;
+#[cfg(feature = "grpc")]
+mod grpc_server;
+mod guest;
+mod heatmap;
+mod home_assistant;
+mod hooks;
+mod hotkeys;
+mod i18n;
+mod intercept_exclusions;
+mod interception;
+mod inventory;
+mod investigations;
+mod ip_conflicts;
+mod lifecycle;
+mod media;
+mod metrics;
+mod mqtt;
+mod notifications;
+mod operations;
+mod os_detection;
+mod overhead;
+mod p2p;
+mod parquet_export;
+mod performance;
+mod phone_home;
+mod platform;
+mod protocols;
+mod proxy_config;
+mod python;
+mod python_env;
+mod qr;
+mod quic;
+mod quick_actions;
+mod retention;
+mod secrets;
+mod sessions;
+mod snapshot;
+mod spoofing;
+mod state;
+mod state_snapshot;
+mod stats_cache;
+mod subnets;
+mod subscriptions;
+mod temporary_rules;
+#[cfg(test)]
+mod testing;
+#[cfg(test)]
+mod tests;
+mod threat_intel;
+mod tls_report;
+mod topology;
+mod trackers;
+mod unblock;
+mod updater;
+mod usage_cost;
+mod users;
+mod watch_lists;
+mod widgets;
+mod wifi;
+mod workspace;
+
+use lifecycle::MonitoringState;
+use state::AppState;
+use tauri::Manager;
+
+/// Run the desktop app (or, with `--headless`, the daemon)
+pub fn run() {
+    diagnostics::init_logging();
+    diagnostics::install_crash_handler();
+
+    // Credentials older versions kept in plaintext settings go to the OS keychain
+    secrets::migrate_plaintext();
+
+    // Run the monitoring engine without a window (service / background mode)
+    if std::env::args().any(|arg| arg == "--headless") {
+        if let Err(e) = daemon::run_headless() {
+            log::error!("Headless daemon failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // A second instance sharing the dashboard runs read-only with an observer token
+    if let Err(e) = access::init_from_args() {
+        log::error!("Cannot start observer session: {}", e);
+        std::process::exit(1);
+    }
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(hotkeys::handle_shortcut)
+                .build(),
+        )
+        .manage(AppState::default())
+        .invoke_handler(tauri::generate_handler![
+            // Monitoring
+            commands::start_monitoring,
+            commands::stop_monitoring,
+            commands::get_status,
+            // Devices
+            commands::get_devices,
+            commands::scan_devices,
+            commands::get_detected_networks,
+            commands::add_scan_range,
+            commands::remove_scan_range,
+            commands::set_device_monitoring,
+            commands::get_device_metadata,
+            commands::set_device_metadata,
+            commands::set_device_interception_mode,
+            commands::get_spoof_targets,
+            commands::set_spoof_targets,
+            commands::get_network_topology,
+            commands::get_wifi_environment,
+            commands::get_app_usage,
+            commands::compare_device_activity,
+            commands::get_activity_heatmap,
+            commands::get_device_sessions,
+            commands::get_device_services,
+            commands::get_device_advisories,
+            commands::get_device_changelog,
+            commands::get_inventory_changes,
+            commands::get_ip_conflicts,
+            commands::get_media_entries,
+            commands::get_media_thumbnail,
+            commands::get_tracker_report,
+            commands::get_iot_phone_home_report,
+            commands::set_app_limit,
+            commands::get_app_limits_status,
+            // Traffic
+            commands::get_traffic,
+            commands::search_traffic,
+            commands::rebuild_search_index,
+            commands::get_saved_searches,
+            commands::save_search,
+            commands::delete_saved_search,
+            commands::get_traffic_details,
+            commands::tag_traffic_entry,
+            commands::annotate_entry,
+            commands::get_domain_details,
+            commands::get_dns_queries,
+            commands::subscribe_traffic,
+            commands::unsubscribe_traffic,
+            // Alerts
+            commands::get_alerts,
+            commands::mark_alert_read,
+            commands::resolve_alert,
+            commands::delete_alert,
+            commands::get_alert_evidence,
+            commands::get_alert_screenshot,
+            commands::mark_all_alerts_read,
+            commands::get_notification_settings,
+            commands::set_quiet_hours,
+            commands::set_alert_sound,
+            commands::preview_alert_sound,
+            commands::get_incidents,
+            commands::get_incident_details,
+            commands::resolve_incident,
+            commands::get_watch_lists,
+            commands::set_watch_lists,
+            commands::get_watch_matches,
+            commands::get_watch_list_rollup,
+            // Network security
+            commands::detect_dhcp_servers,
+            commands::get_dhcp_servers,
+            commands::get_bypass_detections,
+            commands::update_tor_relay_list,
+            commands::detect_beaconing,
+            commands::get_dns_anomaly_report,
+            commands::get_p2p_activity,
+            commands::get_tls_report,
+            commands::get_credential_exposures,
+            commands::get_credential_exposure_config,
+            commands::set_credential_exposure_config,
+            commands::get_threat_feeds,
+            commands::update_threat_feeds,
+            commands::rescan_history_against_feeds,
+            // Stats
+            commands::get_stats,
+            commands::rebuild_stats,
+            commands::get_protocol_breakdown,
+            commands::get_usage_cost,
+            commands::get_flows,
+            commands::get_overhead_metrics,
+            // Blocking
+            commands::add_block_rule,
+            commands::remove_block_rule,
+            commands::toggle_category,
+            commands::get_block_config,
+            commands::check_domain,
+            commands::export_rules,
+            commands::import_rules,
+            commands::get_dns_policy,
+            commands::set_doh_blocking,
+            commands::set_force_dns,
+            commands::get_dns_bypass_attempts,
+            commands::get_quic_policy,
+            commands::set_quic_blocking,
+            commands::set_device_quic_blocking,
+            commands::get_quic_downgrades,
+            commands::set_guest_mode,
+            commands::get_guest_stats,
+            commands::set_device_rate_limit,
+            commands::get_bandwidth_usage,
+            commands::get_block_page_config,
+            commands::set_block_page_config,
+            commands::preview_block_page,
+            commands::get_unblock_requests,
+            commands::approve_unblock_request,
+            commands::deny_unblock_request,
+            // Settings
+            commands::get_settings,
+            commands::update_settings,
+            commands::get_settings_schema,
+            commands::get_available_locales,
+            commands::set_autostart,
+            commands::list_workspaces,
+            commands::switch_workspace,
+            commands::get_access_info,
+            commands::get_observer_tokens,
+            commands::create_observer_token,
+            commands::revoke_observer_token,
+            // API keys
+            commands::get_api_keys,
+            commands::create_api_key,
+            commands::revoke_api_key,
+            // Secrets
+            commands::set_secret,
+            commands::get_secret_names,
+            // Stealth
+            commands::change_stealth_profile,
+            commands::get_stealth_profiles,
+            // Certificates
+            commands::generate_certificate,
+            commands::start_cert_server,
+            commands::stop_cert_server,
+            commands::get_cert_server_status,
+            commands::get_cert_url,
+            commands::get_cert_qr,
+            commands::get_cert_install_guide,
+            commands::add_intercept_exclusion,
+            commands::remove_intercept_exclusion,
+            commands::get_intercept_exclusions,
+            commands::get_proxy_config,
+            commands::sync_proxy_config,
+            commands::test_upstream_proxy,
+            // Export
+            commands::export_data,
+            commands::import_data,
+            commands::get_investigations,
+            commands::create_investigation,
+            commands::rename_investigation,
+            commands::set_investigation_status,
+            commands::delete_investigation,
+            commands::attach_to_investigation,
+            commands::detach_from_investigation,
+            commands::add_investigation_note,
+            commands::export_investigation,
+            // Operations
+            commands::get_operation_status,
+            commands::cancel_operation,
+            // Utilities
+            commands::get_network_interfaces,
+            commands::validate_interface,
+            commands::get_capture_driver_status,
+            commands::install_capture_driver,
+            commands::check_admin,
+            commands::request_elevation,
+            commands::get_diagnostics,
+            commands::generate_diagnostics_bundle,
+            commands::export_state_snapshot,
+            commands::import_state_snapshot,
+            commands::get_python_environment,
+            commands::repair_python_environment,
+            commands::get_enrichment_cache_stats,
+            commands::clear_enrichment_cache,
+            commands::get_performance_metrics,
+            commands::cleanup_database,
+            commands::aggregate_traffic,
+            commands::preview_retention,
+            commands::apply_retention,
+            // Integrations
+            commands::configure_home_assistant,
+            commands::test_home_assistant_connection,
+            commands::get_forwarder_status,
+            commands::get_hooks,
+            commands::save_hook,
+            commands::delete_hook,
+            commands::test_hook,
+            commands::get_audit_log,
+            // Service
+            commands::install_service,
+            commands::uninstall_service,
+            commands::get_daemon_status,
+            // Hotkeys
+            commands::get_hotkeys,
+            commands::set_hotkeys,
+            // Updates
+            commands::check_for_updates,
+            commands::download_update,
+            commands::install_update,
+            // Quick actions
+            commands::list_quick_actions,
+            commands::run_quick_action,
+            commands::list_widgets,
+            commands::get_widget_data,
+            // Demo
+            commands::enable_demo_mode,
+            commands::get_demo_status,
+        ])
+        .setup(|app| {
+            let window = app.get_webview_window("main").unwrap();
+            
+            // Set window title
+            window.set_title("Network Monitor")?;
+            
+            // A daemon started before the window may already be monitoring
+            commands::sync_with_daemon(&app.state::<AppState>());
+
+            // Relaunched with admin rights: pick up where the unprivileged instance left off
+            if elevation::launched_elevated() {
+                if let Some(handoff) = elevation::take_handoff() {
+                    let handle = app.handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        let state = handle.state::<AppState>();
+                        *state.current_profile.lock().unwrap() = handoff.current_profile;
+
+                        if handoff.was_monitoring {
+                            if let Err(e) = commands::start_monitoring(state).await {
+                                log::error!("Failed to resume monitoring after elevation: {}", e);
+                            }
+                        }
+                    });
+                }
+            }
+
+            // Launched at login: optionally stay in the tray, and resume monitoring
+            // if it was running when the last session ended and the daemon isn't already
+            if autostart::launched_at_login() {
+                if autostart::launched_minimized() {
+                    window.hide()?;
+                }
+
+                let state = app.state::<AppState>();
+                if autostart::was_monitoring() && state.lifecycle.current() == MonitoringState::Stopped {
+                    let handle = app.handle().clone();
+                    tauri::async_runtime::spawn(async move {
+                        let state = handle.state::<AppState>();
+                        if let Err(e) = commands::start_monitoring(state).await {
+                            log::error!("Failed to resume monitoring at login: {}", e);
+                        }
+                    });
+                }
+            }
+            
+            // Come back up in demo mode if it was on
+            if let Some(seed) = commands::load_settings().ok().and_then(|s| s.demo_seed) {
+                demo::enable(Some(seed));
+            }
+
+            // Keep stats/device snapshots warm so UI polling stays cheap
+            snapshot::spawn_snapshot_refresher(app.handle().clone());
+
+            // Roll old raw traffic into aggregates
+            aggregation::spawn_aggregator();
+
+            // Merge passive DHCP sightings and alert on rogue servers
+            dhcp::spawn_dhcp_watcher(app.handle().clone());
+
+            // Enforce per-device app time limits
+            app_limits::spawn_limit_enforcer(app.handle().clone());
+
+            // Alert when a metered connection heads over its data cap
+            usage_cost::spawn_cap_watcher();
+
+            // Route lifecycle, device and alert events to the window, hooks and integrations
+            event_bus::spawn(app.handle().clone());
+
+            // Publish device and alert events to MQTT if enabled
+            mqtt::spawn_mqtt_publisher(app.handle().clone());
+
+            // Push presence and alerts to Home Assistant if configured
+            home_assistant::spawn_home_assistant_sync(app.handle().clone());
+
+            // Forward alerts to a SIEM if configured
+            forwarder::spawn_forwarder();
+            guest::spawn_guest_monitor(app.handle().clone());
+            bandwidth::spawn_bandwidth_monitor(app.handle().clone());
+            unblock::spawn_unblock_monitor(app.handle().clone());
+            temporary_rules::spawn_rule_expiry(app.handle().clone());
+            threat_intel::spawn_threat_intel(app.handle().clone());
+            beaconing::spawn_beacon_detector(app.handle().clone());
+            dns_tunneling::spawn_tunnel_detector(app.handle().clone());
+            p2p::spawn_p2p_detector(app.handle().clone());
+            ip_conflicts::spawn_ip_conflict_detector(app.handle().clone());
+            credential_exposure::spawn_credential_monitor(app.handle().clone());
+            watch_lists::spawn_watch_list_monitor(app.handle().clone());
+            tls_report::spawn_tls_monitor(app.handle().clone());
+            cert_server::spawn_idle_watcher(app.handle().clone());
+            enrichment::spawn_flusher();
+            os_detection::spawn_os_detector();
+
+            // Serve Prometheus metrics and gRPC control if enabled
+            match commands::load_settings() {
+                Ok(settings) => {
+                    if let Err(e) = metrics::apply_settings(app.handle(), &settings) {
+                        log::error!("Failed to start metrics endpoint: {}", e);
+                    }
+                    if let Err(e) = grpc::apply_settings(app.handle(), &settings.grpc) {
+                        log::error!("Failed to start gRPC control server: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("Failed to load settings for metrics: {}", e),
+            }
+            
+            // Register system-wide hotkeys
+            hotkeys::init(app.handle());
+
+            // Bundle crash reports from the previous run if the user opted in
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || diagnostics::report_previous_crashes(&handle));
+
+            log::info!("Network Monitor started");
+            
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // Hide to tray instead of closing
+                window.hide().unwrap();
+                api.prevent_close();
+            }
+        })
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
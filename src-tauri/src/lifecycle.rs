@@ -0,0 +1,160 @@
+// Monitoring lifecycle state machine
+
+use crate::event_bus::{self, BusEvent};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::sync::Mutex;
+
+/// Event the window gets on every lifecycle transition
+pub const LIFECYCLE_EVENT: &str = "monitoring-state";
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitoringState {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+}
+
+/// Why monitoring couldn't start or stop; the frontend gets `{kind, message}`
+/// plus the states involved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleError {
+    AlreadyRunning,
+    AlreadyStopped,
+    Busy { state: MonitoringState },
+    InvalidTransition { from: MonitoringState, to: MonitoringState },
+    /// The transition was allowed but starting or stopping went wrong
+    Failed { message: String },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LifecycleTransition {
+    pub from: MonitoringState,
+    pub to: MonitoringState,
+}
+
+impl fmt::Display for MonitoringState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MonitoringState::Stopped => "stopped",
+            MonitoringState::Starting => "starting",
+            MonitoringState::Running => "running",
+            MonitoringState::Stopping => "stopping",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for LifecycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LifecycleError::AlreadyRunning => write!(f, "Monitoring is already running"),
+            LifecycleError::AlreadyStopped => write!(f, "Monitoring is not running"),
+            LifecycleError::Busy { state } => {
+                write!(f, "Monitoring is currently {}, try again shortly", state)
+            }
+            LifecycleError::InvalidTransition { from, to } => {
+                write!(f, "Invalid monitoring transition: {} -> {}", from, to)
+            }
+            LifecycleError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for LifecycleError {}
+
+impl From<String> for LifecycleError {
+    fn from(message: String) -> Self {
+        LifecycleError::Failed { message }
+    }
+}
+
+impl LifecycleError {
+    fn kind(&self) -> &'static str {
+        match self {
+            LifecycleError::AlreadyRunning => "already_running",
+            LifecycleError::AlreadyStopped => "already_stopped",
+            LifecycleError::Busy { .. } => "busy",
+            LifecycleError::InvalidTransition { .. } => "invalid_transition",
+            LifecycleError::Failed { .. } => "failed",
+        }
+    }
+}
+
+impl Serialize for LifecycleError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            LifecycleError::Busy { state } => map.serialize_entry("state", state)?,
+            LifecycleError::InvalidTransition { from, to } => {
+                map.serialize_entry("from", from)?;
+                map.serialize_entry("to", to)?;
+            }
+            _ => {}
+        }
+        map.end()
+    }
+}
+
+impl MonitoringState {
+    /// Whether monitoring processes are (or should be) up
+    pub fn is_running(self) -> bool {
+        self == MonitoringState::Running
+    }
+
+    fn check_transition(self, to: MonitoringState) -> Result<(), LifecycleError> {
+        use MonitoringState::*;
+
+        match (self, to) {
+            (Stopped, Starting)
+            | (Starting, Running)
+            | (Starting, Stopped)
+            | (Running, Stopping)
+            | (Stopping, Stopped) => Ok(()),
+            (Running, Starting) => Err(LifecycleError::AlreadyRunning),
+            (Stopped, Stopping) => Err(LifecycleError::AlreadyStopped),
+            (Starting, _) | (Stopping, _) => Err(LifecycleError::Busy { state: self }),
+            (from, to) => Err(LifecycleError::InvalidTransition { from, to }),
+        }
+    }
+}
+
+pub struct Lifecycle {
+    state: Mutex<MonitoringState>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle {
+            state: Mutex::new(MonitoringState::Stopped),
+        }
+    }
+}
+
+impl Lifecycle {
+    pub fn current(&self) -> MonitoringState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Atomically move to a new state, publishing a transition event on success
+    pub fn transition(&self, to: MonitoringState) -> Result<MonitoringState, LifecycleError> {
+        let from = {
+            let mut state = self.state.lock().unwrap();
+            let from = *state;
+            from.check_transition(to)?;
+            *state = to;
+            from
+        };
+
+        log::info!("Monitoring state: {} -> {}", from, to);
+
+        event_bus::publish(BusEvent::Lifecycle(LifecycleTransition { from, to }));
+
+        Ok(from)
+    }
+}
@@ -0,0 +1,143 @@
+// Captured images, video and audio
+//
+// Lists the image, video and audio responses a device received, by their
+// content type, so visual content can be reviewed without going through raw
+// traffic. Media bodies aren't stored; instead the proxy shrinks each image
+// it passes to a small JPEG in the data directory's `thumbnails/` cache
+// (size-capped, oldest deleted first), keyed by an FNV-1a hash of the URL.
+// Video and audio are listed without thumbnails.
+
+use crate::apps::UsageRange;
+use crate::clock::Zone;
+use crate::database::open_readonly;
+use crate::workspace;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Most entries returned by one listing
+const MAX_ENTRIES: u32 = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    Image,
+    Video,
+    Audio,
+}
+
+impl MediaType {
+    fn prefix(self) -> &'static str {
+        match self {
+            MediaType::Image => "image/",
+            MediaType::Video => "video/",
+            MediaType::Audio => "audio/",
+        }
+    }
+
+    fn of(content_type: &str) -> Option<MediaType> {
+        let content_type = content_type.trim().to_lowercase();
+        [MediaType::Image, MediaType::Video, MediaType::Audio]
+            .into_iter()
+            .find(|t| content_type.starts_with(t.prefix()))
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MediaEntry {
+    pub id: String,
+    pub timestamp: String,
+    pub device_id: Option<String>,
+    pub url: String,
+    pub host: String,
+    pub content_type: String,
+    pub media_type: MediaType,
+    pub size: u64,
+    pub status_code: Option<u16>,
+    /// Key for `get_media_thumbnail`, when a thumbnail was made
+    pub thumbnail: Option<String>,
+}
+
+/// FNV-1a 64-bit hash of a URL as 16 hex digits, as the proxy names thumbnails
+pub fn thumbnail_key(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn thumbnail_path(key: &str) -> PathBuf {
+    workspace::data_dir().join("thumbnails").join(format!("{}.jpg", key))
+}
+
+/// Image, video and audio responses of a device (or every device), newest first
+pub fn entries(
+    device_id: Option<&str>,
+    range: UsageRange,
+    media_type: Option<MediaType>,
+) -> Result<Vec<MediaEntry>, String> {
+    let (start, end) = range.bounds();
+    let zone = Zone::configured();
+    let conn = open_readonly()?;
+    let prefix = media_type.map(|t| format!("{}%", t.prefix()));
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp, device_id, url, host, response_body_type, response_size, status_code
+             FROM traffic
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_id = ?3)
+               AND (lower(response_body_type) LIKE 'image/%'
+                    OR lower(response_body_type) LIKE 'video/%'
+                    OR lower(response_body_type) LIKE 'audio/%')
+               AND (?4 IS NULL OR lower(response_body_type) LIKE ?4)
+             ORDER BY timestamp DESC
+             LIMIT ?5",
+        )
+        .map_err(|e| format!("Failed to prepare media query: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, device_id, prefix, MAX_ENTRIES], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                row.get::<_, String>(5)?,
+                row.get::<_, Option<i64>>(6)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(7)?.map(|n| n as u16),
+            ))
+        })
+        .map_err(|e| format!("Failed to query media: {}", e))?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (id, timestamp, device_id, url, host, content_type, size, status_code) =
+            row.map_err(|e| format!("Failed to read media: {}", e))?;
+        let Some(media_type) = MediaType::of(&content_type) else {
+            continue;
+        };
+        let key = thumbnail_key(&url);
+        entries.push(MediaEntry {
+            id,
+            timestamp: zone.display(&timestamp),
+            device_id,
+            thumbnail: thumbnail_path(&key).exists().then_some(key),
+            url,
+            host,
+            content_type,
+            media_type,
+            size,
+            status_code,
+        });
+    }
+    Ok(entries)
+}
+
+/// A cached thumbnail's JPEG bytes
+pub fn thumbnail(key: &str) -> Result<Vec<u8>, String> {
+    if key.len() != 16 || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid thumbnail key: {}", key));
+    }
+    fs::read(thumbnail_path(key)).map_err(|e| format!("Failed to read thumbnail: {}", e))
+}
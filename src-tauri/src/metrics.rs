@@ -0,0 +1,369 @@
+// Prometheus/OpenMetrics exporter
+//
+// When enabled in settings, a plain-text `/metrics` endpoint is served on
+// localhost for scraping into Prometheus/Grafana. Traffic rates, bandwidth and
+// alert counts are read on each scrape; Python call latency is recorded
+// in-process by the script runner, write retries by the database writer and
+// enrichment cache hits by the cache.
+
+use crate::clock;
+use crate::commands::Settings;
+use crate::database;
+use crate::db_writer;
+use crate::enrichment;
+use crate::state::AppState;
+use rusqlite::params;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Window the per-second rates are averaged over
+const RATE_WINDOW_SECS: i64 = 60;
+
+/// Upper bounds of the Python call latency histogram buckets
+const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// How often the accept loop checks whether it should stop
+const ACCEPT_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Default)]
+struct CallStats {
+    count: u64,
+    errors: u64,
+    seconds: f64,
+    buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+/// Python call stats keyed by (script, action)
+static PYTHON_CALLS: Mutex<BTreeMap<(String, String), CallStats>> = Mutex::new(BTreeMap::new());
+
+struct Server {
+    port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+static SERVER: Mutex<Option<Server>> = Mutex::new(None);
+
+/// The script name and `--action` of a Python script call
+pub fn python_call_labels(script_path: &str, args: &[&str]) -> (String, String) {
+    let script = script_path
+        .rsplit('/')
+        .next()
+        .unwrap_or(script_path)
+        .trim_end_matches(".py")
+        .to_string();
+    let action = match args {
+        ["--action", action, ..] => action.to_string(),
+        _ => String::new(),
+    };
+    (script, action)
+}
+
+/// Record the duration and outcome of a Python script call
+pub fn record_python_call(script_path: &str, args: &[&str], elapsed: Duration, ok: bool) {
+    let (script, action) = python_call_labels(script_path, args);
+
+    let seconds = elapsed.as_secs_f64();
+    let mut calls = PYTHON_CALLS.lock().unwrap();
+    let stats = calls.entry((script, action)).or_default();
+    stats.count += 1;
+    stats.seconds += seconds;
+    if !ok {
+        stats.errors += 1;
+    }
+    for (bucket, bound) in stats.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+        if seconds <= bound {
+            *bucket += 1;
+        }
+    }
+}
+
+/// Escape a label value for the text exposition format
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Traffic rates and per-device bandwidth over the last window
+fn write_traffic_metrics(out: &mut String) -> Result<(), String> {
+    let conn = database::open_readonly()?;
+    let since = clock::utc_cutoff(chrono::Duration::seconds(RATE_WINDOW_SECS));
+    let window = RATE_WINDOW_SECS as f64;
+
+    let (requests, blocked): (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(blocked), 0) FROM traffic WHERE timestamp >= ?1",
+            params![since],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .map_err(|e| format!("Failed to query traffic rates: {}", e))?;
+
+    write_header(out, "nms_requests_per_second", "gauge", "Captured requests per second over the last minute");
+    let _ = writeln!(out, "nms_requests_per_second {}", requests as f64 / window);
+    write_header(out, "nms_blocked_per_second", "gauge", "Blocked requests per second over the last minute");
+    let _ = writeln!(out, "nms_blocked_per_second {}", blocked as f64 / window);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(device_id, ''), device_ip,
+                    SUM(COALESCE(request_size, 0)), SUM(COALESCE(response_size, 0))
+             FROM traffic
+             WHERE timestamp >= ?1
+             GROUP BY device_id, device_ip",
+        )
+        .map_err(|e| format!("Failed to prepare bandwidth query: {}", e))?;
+    let rows = stmt
+        .query_map(params![since], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, i64>(2)?,
+                r.get::<_, i64>(3)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query bandwidth: {}", e))?;
+
+    write_header(out, "nms_device_bandwidth_bytes_per_second", "gauge", "Per-device bandwidth over the last minute");
+    for row in rows {
+        let (device_id, device_ip, sent, received) =
+            row.map_err(|e| format!("Failed to read bandwidth: {}", e))?;
+        for (direction, bytes) in [("out", sent), ("in", received)] {
+            let _ = writeln!(
+                out,
+                "nms_device_bandwidth_bytes_per_second{{device_id=\"{}\",device_ip=\"{}\",direction=\"{}\"}} {}",
+                label(&device_id),
+                label(&device_ip),
+                direction,
+                bytes as f64 / window
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Stored and unacknowledged alerts by severity, read from the alert engine's store
+fn write_alert_metrics(out: &mut String) {
+    let alerts = database::stored_alerts();
+
+    let mut stored: BTreeMap<&str, u64> = ["low", "medium", "high", "critical"]
+        .into_iter()
+        .map(|s| (s, 0))
+        .collect();
+    let mut unacknowledged = stored.clone();
+
+    for alert in &alerts {
+        let Some(severity) = alert.get("severity").and_then(|s| s.as_str()) else {
+            continue;
+        };
+        let Some(count) = stored.get_mut(severity) else {
+            continue;
+        };
+        *count += 1;
+        if !alert.get("acknowledged").and_then(|a| a.as_bool()).unwrap_or(false) {
+            *unacknowledged.get_mut(severity).unwrap() += 1;
+        }
+    }
+
+    write_header(out, "nms_alerts", "gauge", "Stored alerts (last 7 days) by severity");
+    for (severity, count) in &stored {
+        let _ = writeln!(out, "nms_alerts{{severity=\"{}\"}} {}", severity, count);
+    }
+    write_header(out, "nms_alerts_unacknowledged", "gauge", "Unacknowledged alerts by severity");
+    for (severity, count) in &unacknowledged {
+        let _ = writeln!(out, "nms_alerts_unacknowledged{{severity=\"{}\"}} {}", severity, count);
+    }
+}
+
+/// Health of the monitoring engine, capture processes and database
+fn write_component_metrics(out: &mut String, state: &AppState) {
+    let monitoring = state.lifecycle.current().is_running();
+    let (alive, exited) = {
+        let mut processes = state.python_processes.lock().unwrap();
+        let alive = processes
+            .iter_mut()
+            .filter_map(|p| p.try_wait().ok())
+            .filter(|status| status.is_none())
+            .count();
+        (alive, processes.len() - alive)
+    };
+    let database_up = database::open_readonly().is_ok();
+
+    write_header(out, "nms_component_up", "gauge", "Whether a component is healthy (1) or not (0)");
+    for (component, up) in [
+        ("monitoring", monitoring),
+        ("capture_processes", !monitoring || (alive > 0 && exited == 0)),
+        ("database", database_up),
+    ] {
+        let _ = writeln!(out, "nms_component_up{{component=\"{}\"}} {}", component, up as u8);
+    }
+
+    write_header(out, "nms_capture_processes", "gauge", "Capture processes by state");
+    let _ = writeln!(out, "nms_capture_processes{{state=\"running\"}} {}", alive);
+    let _ = writeln!(out, "nms_capture_processes{{state=\"exited\"}} {}", exited);
+}
+
+fn write_python_metrics(out: &mut String) {
+    let calls = PYTHON_CALLS.lock().unwrap();
+
+    write_header(out, "nms_python_call_duration_seconds", "histogram", "Latency of Python script calls");
+    for ((script, action), stats) in calls.iter() {
+        let labels = format!("script=\"{}\",action=\"{}\"", label(script), label(action));
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+            let _ = writeln!(out, "nms_python_call_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, count);
+        }
+        let _ = writeln!(out, "nms_python_call_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, stats.count);
+        let _ = writeln!(out, "nms_python_call_duration_seconds_sum{{{}}} {}", labels, stats.seconds);
+        let _ = writeln!(out, "nms_python_call_duration_seconds_count{{{}}} {}", labels, stats.count);
+    }
+
+    write_header(out, "nms_python_call_errors_total", "counter", "Python script calls that failed");
+    for ((script, action), stats) in calls.iter() {
+        let _ = writeln!(
+            out,
+            "nms_python_call_errors_total{{script=\"{}\",action=\"{}\"}} {}",
+            label(script),
+            label(action),
+            stats.errors
+        );
+    }
+}
+
+/// Queue depth and lock retries of the database writer
+fn write_db_writer_metrics(out: &mut String) {
+    let stats = db_writer::stats();
+
+    write_header(out, "nms_db_writes_total", "counter", "Database writes run by the writer queue");
+    let _ = writeln!(out, "nms_db_writes_total {}", stats.writes);
+    write_header(out, "nms_db_write_retries_total", "counter", "Write attempts repeated because the database was locked");
+    let _ = writeln!(out, "nms_db_write_retries_total {}", stats.retries);
+    write_header(out, "nms_db_write_lock_failures_total", "counter", "Writes that stayed locked out after every retry");
+    let _ = writeln!(out, "nms_db_write_lock_failures_total {}", stats.lock_failures);
+    write_header(out, "nms_db_write_queue_full_total", "counter", "Writes that waited for room in the writer queue");
+    let _ = writeln!(out, "nms_db_write_queue_full_total {}", stats.queue_full);
+    write_header(out, "nms_db_write_queue_depth", "gauge", "Writes waiting in the writer queue");
+    let _ = writeln!(out, "nms_db_write_queue_depth {}", stats.queued);
+}
+
+/// Enrichment lookups by kind and which tier answered them
+fn write_enrichment_metrics(out: &mut String) {
+    write_header(out, "nms_enrichment_lookups_total", "counter", "Enrichment lookups by kind and outcome");
+    for (kind, outcome, count) in enrichment::lookup_counts() {
+        let _ = writeln!(
+            out,
+            "nms_enrichment_lookups_total{{kind=\"{}\",outcome=\"{}\"}} {}",
+            kind.as_str(),
+            outcome,
+            count
+        );
+    }
+}
+
+/// Render all metrics in the Prometheus text format
+pub fn render(state: &AppState) -> String {
+    let mut out = String::new();
+
+    write_header(&mut out, "nms_up", "gauge", "Network Monitor is running");
+    out.push_str("nms_up 1\n");
+
+    if let Err(e) = write_traffic_metrics(&mut out) {
+        log::debug!("Skipping traffic metrics: {}", e);
+    }
+    write_alert_metrics(&mut out);
+    write_component_metrics(&mut out, state);
+    write_python_metrics(&mut out);
+    write_db_writer_metrics(&mut out);
+    write_enrichment_metrics(&mut out);
+
+    out
+}
+
+fn serve_connection(app: &AppHandle, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the request headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if request_line.starts_with("GET ") && path.split('?').next() == Some("/metrics") {
+        ("200 OK", render(&app.state::<AppState>()))
+    } else {
+        ("404 Not Found", "Not found\n".to_string())
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+fn start(app: AppHandle, port: u16) -> Result<Server, String> {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))
+        .map_err(|e| format!("Failed to bind metrics port {}: {}", port, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure metrics listener: {}", e))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+
+    thread::spawn(move || {
+        while !stopped.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    if let Err(e) = serve_connection(&app, stream) {
+                        log::debug!("Metrics request failed: {}", e);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                Err(e) => log::warn!("Metrics listener error: {}", e),
+            }
+        }
+        log::info!("Metrics endpoint on port {} stopped", port);
+    });
+
+    log::info!("Serving metrics on http://127.0.0.1:{}/metrics", port);
+    Ok(Server { port, stop })
+}
+
+/// Start, stop or move the metrics endpoint to match the settings
+pub fn apply_settings(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let mut server = SERVER.lock().unwrap();
+
+    if let Some(running) = server.take() {
+        if settings.metrics_enabled && running.port == settings.metrics_port {
+            *server = Some(running);
+            return Ok(());
+        }
+        running.stop.store(true, Ordering::SeqCst);
+        // Give the old listener a chance to release its port
+        thread::sleep(ACCEPT_POLL * 2);
+    }
+
+    if settings.metrics_enabled {
+        *server = Some(start(app.clone(), settings.metrics_port)?);
+    }
+
+    Ok(())
+}
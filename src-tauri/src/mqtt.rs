@@ -0,0 +1,269 @@
+// MQTT publishing for home automation
+//
+// Device online/offline changes, newly seen devices and new alerts are
+// published to a broker through a long-running Python bridge (paho-mqtt).
+// Events come off the event bus; the full device state is published when the
+// bridge starts and after a workspace switch. With Home Assistant discovery
+// on, every device also appears as a connectivity binary_sensor and the latest
+// alert as a sensor.
+
+use crate::commands::{load_settings, Device};
+use crate::events::NetworkEvent;
+use crate::python::{send_command_to_process, start_python_script};
+use crate::secrets;
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::Child;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the bridge is checked against the settings
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Prefix for every published topic, e.g. "network_monitor/device/<id>/state"
+    pub topic_prefix: String,
+    pub home_assistant_discovery: bool,
+    pub discovery_prefix: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        MqttSettings {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            username: None,
+            password: None,
+            topic_prefix: "network_monitor".to_string(),
+            home_assistant_discovery: true,
+            discovery_prefix: "homeassistant".to_string(),
+        }
+    }
+}
+
+/// Bridge process and what has been published so far
+struct Publisher {
+    bridge: Child,
+    settings: MqttSettings,
+    /// Workspace whose devices were last published in full
+    synced_workspace: Option<String>,
+}
+
+static PUBLISHER: Mutex<Option<Publisher>> = Mutex::new(None);
+
+fn device_name(device: &Device) -> String {
+    device.hostname.clone().unwrap_or_else(|| device.ip.clone())
+}
+
+fn presence(online: bool) -> &'static str {
+    if online { "online" } else { "offline" }
+}
+
+impl Publisher {
+    fn start(settings: &MqttSettings) -> Result<Self, String> {
+        // Passwords from older versions stay in the settings until migrated
+        let password = match settings.password.clone() {
+            Some(password) => Some(password),
+            None => secrets::get_secret(secrets::MQTT_PASSWORD)?,
+        };
+        let mut bridge = start_python_script("python/utils/mqtt_bridge.py", &[])
+            .map_err(|e| format!("Failed to start MQTT bridge: {}", e))?;
+
+        send_command_to_process(&mut bridge, &json!({
+            "action": "connect",
+            "host": settings.host,
+            "port": settings.port,
+            "username": settings.username,
+            "password": password,
+            "client_id": "network-monitor",
+            "status_topic": format!("{}/status", settings.topic_prefix),
+        }))?;
+
+        log::info!("MQTT publishing to {}:{}", settings.host, settings.port);
+
+        Ok(Publisher {
+            bridge,
+            settings: settings.clone(),
+            synced_workspace: None,
+        })
+    }
+
+    fn stop(mut self) {
+        let _ = send_command_to_process(&mut self.bridge, &json!({ "action": "stop" }));
+        let _ = self.bridge.wait();
+    }
+
+    fn is_alive(&mut self) -> bool {
+        matches!(self.bridge.try_wait(), Ok(None))
+    }
+
+    fn publish_raw(&mut self, topic: &str, payload: Value, retain: bool) -> Result<(), String> {
+        send_command_to_process(&mut self.bridge, &json!({
+            "action": "publish",
+            "topic": topic,
+            "payload": payload,
+            "retain": retain,
+        }))
+    }
+
+    /// Publish under the configured topic prefix
+    fn publish(&mut self, topic: &str, payload: Value, retain: bool) -> Result<(), String> {
+        let topic = format!("{}/{}", self.settings.topic_prefix, topic);
+        self.publish_raw(&topic, payload, retain)
+    }
+
+    /// Publish retained Home Assistant discovery config for a device's presence sensor
+    fn publish_device_discovery(&mut self, device: &Device) -> Result<(), String> {
+        let prefix = &self.settings.topic_prefix;
+        let object_id = format!("network_monitor_{}", device.id.replace([':', '-', '.'], "_"));
+        let config = json!({
+            "name": device_name(device),
+            "unique_id": object_id,
+            "device_class": "connectivity",
+            "state_topic": format!("{}/device/{}/state", prefix, device.id),
+            "payload_on": "online",
+            "payload_off": "offline",
+            "json_attributes_topic": format!("{}/device/{}/attributes", prefix, device.id),
+            "availability_topic": format!("{}/status", prefix),
+            "device": {
+                "identifiers": [object_id],
+                "name": device_name(device),
+                "manufacturer": device.vendor,
+                "connections": [["mac", device.mac]],
+            },
+        });
+
+        let topic = format!("{}/binary_sensor/{}/config", self.settings.discovery_prefix, object_id);
+        self.publish_raw(&topic, config, true)
+    }
+
+    /// Publish retained Home Assistant discovery config for the latest-alert sensor
+    fn publish_alert_discovery(&mut self) -> Result<(), String> {
+        let prefix = &self.settings.topic_prefix;
+        let config = json!({
+            "name": "Network Monitor last alert",
+            "unique_id": "network_monitor_last_alert",
+            "icon": "mdi:shield-alert",
+            "state_topic": format!("{}/event/alert", prefix),
+            "value_template": "{{ value_json.title }}",
+            "json_attributes_topic": format!("{}/event/alert", prefix),
+            "availability_topic": format!("{}/status", prefix),
+        });
+
+        let topic = format!("{}/sensor/network_monitor_last_alert/config", self.settings.discovery_prefix);
+        self.publish_raw(&topic, config, true)
+    }
+
+    fn publish_device_state(&mut self, device: &Device) -> Result<(), String> {
+        self.publish(&format!("device/{}/state", device.id), json!(presence(device.is_online)), true)?;
+        self.publish(&format!("device/{}/attributes", device.id), json!(device), true)
+    }
+
+    /// Publish every device's current state, and discovery config if enabled
+    fn sync(&mut self, devices: &[Device]) -> Result<(), String> {
+        let discovery = self.settings.home_assistant_discovery;
+        if discovery {
+            self.publish_alert_discovery()?;
+        }
+        for device in devices {
+            if discovery {
+                self.publish_device_discovery(device)?;
+            }
+            self.publish_device_state(device)?;
+        }
+        self.synced_workspace = Some(workspace::active());
+        Ok(())
+    }
+
+    fn publish_event(&mut self, event: &NetworkEvent) -> Result<(), String> {
+        let topic = format!("event/{}", event.name());
+        match event {
+            NetworkEvent::NewDevice(device) => {
+                log::info!("MQTT: new device {}", device_name(device));
+                if self.settings.home_assistant_discovery {
+                    self.publish_device_discovery(device)?;
+                }
+                self.publish(&topic, json!(device), false)?;
+                self.publish_device_state(device)
+            }
+            NetworkEvent::DeviceOnline(device) | NetworkEvent::DeviceOffline(device) => {
+                self.publish(&topic, json!(device), false)?;
+                self.publish_device_state(device)
+            }
+            NetworkEvent::Alert(alert) => self.publish(&topic, alert.clone(), false),
+        }
+    }
+}
+
+/// Stop the bridge so the next check reconnects it, e.g. with a new password
+pub fn reconnect() {
+    if let Some(running) = PUBLISHER.lock().unwrap().take() {
+        running.stop();
+    }
+}
+
+/// Start, restart or stop the bridge to match settings, publishing the full
+/// state when it (re)starts or the workspace changed
+fn publish_pending(state: &AppState) -> Result<(), String> {
+    let settings = load_settings()?.mqtt;
+    let mut publisher = PUBLISHER.lock().unwrap();
+
+    // Restart when the connection settings change or the bridge died
+    if let Some(mut running) = publisher.take() {
+        if running.settings == settings && running.is_alive() {
+            *publisher = Some(running);
+        } else {
+            running.stop();
+        }
+    }
+
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let publisher = match publisher.as_mut() {
+        Some(p) => p,
+        None => publisher.insert(Publisher::start(&settings)?),
+    };
+
+    if publisher.synced_workspace.as_deref() != Some(workspace::active().as_str()) {
+        publisher.sync(&state.snapshot.devices(false)?.data)?;
+    }
+    Ok(())
+}
+
+/// Publish an event from the event bus, if the bridge is up
+pub fn notify(event: &NetworkEvent) -> Result<(), String> {
+    match PUBLISHER.lock().unwrap().as_mut() {
+        Some(publisher) if publisher.synced_workspace.is_some() => publisher.publish_event(event),
+        _ => Ok(()),
+    }
+}
+
+/// Spawn the background task that keeps the MQTT bridge up and in sync
+pub fn spawn_mqtt_publisher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = publish_pending(&handle.state::<AppState>()) {
+                    log::debug!("MQTT publish failed: {}", e);
+                }
+            })
+            .await;
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,129 @@
+// Alert sounds and quiet hours
+//
+// Desktop notifications go out through the Python notifier, which every alert
+// source calls: the capture engine, escalation rules and unblock requests. It
+// owns the notification settings and applies them to each notification, so
+// they are set through it here: a sound per severity (on or off, a sound file
+// or the system sound, a volume) and quiet hours, during which only critical
+// alerts are notified. Capture processes reload the settings when they change.
+
+use crate::python::run_python_script;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+const NOTIFIER_SCRIPT: &str = "python/alerts/notifier.py";
+
+pub const SEVERITIES: &[&str] = &["low", "medium", "high", "critical"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AlertSound {
+    pub enabled: bool,
+    /// Sound file to play; the system sound for the severity when unset
+    pub sound: Option<String>,
+    /// 0.0 to 1.0
+    pub volume: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuietHours {
+    /// Local hour they start, 0-23; unset when quiet hours are off
+    pub start: Option<u32>,
+    pub end: Option<u32>,
+    /// Whether it is quiet right now
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub sound_enabled: bool,
+    pub min_severity: String,
+    pub sounds: BTreeMap<String, AlertSound>,
+    pub quiet_hours: QuietHours,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SoundPreview {
+    pub severity: String,
+    #[serde(flatten)]
+    pub sound: AlertSound,
+    /// False when no player was found for this platform
+    pub played: bool,
+}
+
+fn run_notifier(action: &str, args: &[(&str, &str)]) -> Result<Value, String> {
+    let mut script_args = vec!["--action", action];
+    for (key, value) in args {
+        script_args.push(key);
+        script_args.push(value);
+    }
+
+    let result = run_python_script(NOTIFIER_SCRIPT, &script_args)?;
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(result)
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+fn parse_settings(result: &Value) -> Result<NotificationSettings, String> {
+    let status = result.get("status").cloned().unwrap_or_default();
+    serde_json::from_value(status).map_err(|e| format!("Failed to parse notification settings: {}", e))
+}
+
+fn check_severity(severity: &str) -> Result<(), String> {
+    if SEVERITIES.contains(&severity) {
+        Ok(())
+    } else {
+        Err(format!("Unknown severity: {}", severity))
+    }
+}
+
+pub fn validate_quiet_hours(start: Option<u32>, end: Option<u32>) -> Result<(), String> {
+    if let Some(hour) = [start, end].into_iter().flatten().find(|h| *h > 23) {
+        return Err(format!("Quiet hours must be between 0 and 23, got {}", hour));
+    }
+    if start.is_some() && start == end {
+        return Err("Quiet hours can't start and end at the same hour".to_string());
+    }
+    Ok(())
+}
+
+pub fn load() -> Result<NotificationSettings, String> {
+    parse_settings(&run_notifier("status", &[])?)
+}
+
+/// Turn quiet hours on (from 11pm to 7am unless given) or off
+pub fn set_quiet_hours(enabled: bool, start: Option<u32>, end: Option<u32>) -> Result<NotificationSettings, String> {
+    validate_quiet_hours(start, end)?;
+    let start = start.map(|h| h.to_string());
+    let end = end.map(|h| h.to_string());
+    let mut args = vec![("--enabled", if enabled { "true" } else { "false" })];
+    if let Some(start) = start.as_deref() {
+        args.push(("--start", start));
+    }
+    if let Some(end) = end.as_deref() {
+        args.push(("--end", end));
+    }
+    parse_settings(&run_notifier("quiet-hours", &args)?)
+}
+
+pub fn set_alert_sound(severity: &str, sound: &AlertSound) -> Result<NotificationSettings, String> {
+    check_severity(severity)?;
+    if !(0.0..=1.0).contains(&sound.volume) {
+        return Err(format!("Volume must be between 0 and 1, got {}", sound.volume));
+    }
+
+    let value = serde_json::to_string(sound).map_err(|e| format!("Failed to serialize alert sound: {}", e))?;
+    parse_settings(&run_notifier("sound", &[("--severity", severity), ("--value", &value)])?)
+}
+
+/// Play a severity's sound now, even when it is off or it's quiet
+pub fn preview(severity: &str) -> Result<SoundPreview, String> {
+    check_severity(severity)?;
+    let result = run_notifier("preview-sound", &[("--severity", severity)])?;
+    let preview = result.get("preview").cloned().unwrap_or_default();
+    serde_json::from_value(preview).map_err(|e| format!("Failed to parse sound preview: {}", e))
+}
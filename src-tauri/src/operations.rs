@@ -0,0 +1,203 @@
+// Long-running operations
+//
+// Commands that take a while (device scans, exports, certificate generation)
+// start an operation and return its id straight away. The work runs on a
+// blocking thread, and every change to the operation - progress, completion,
+// failure or cancellation - is emitted as an `op://{id}` event carrying its
+// whole OperationStatus, which `get_operation_status` also returns.
+//
+// Cancelling marks the operation cancelled at once. Work that streams records
+// stops at the next one (see `Progress::check`); anything else runs to the end
+// and has its result discarded. Finished operations are kept for FINISHED_TTL
+// so a late poll still finds them.
+
+use crate::clock;
+use crate::state::AppState;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+/// How long a finished operation's status stays available
+const FINISHED_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OperationStatus {
+    pub id: String,
+    /// What is running, e.g. "device_scan" or "export"
+    pub kind: String,
+    pub state: OperationState,
+    /// From 0.0 to 1.0, when the work knows how far along it is
+    pub progress: Option<f64>,
+    pub message: Option<String>,
+    /// What the command would have returned, once completed
+    pub result: Option<Value>,
+    pub error: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+struct Entry {
+    status: OperationStatus,
+    cancelled: Arc<AtomicBool>,
+    finished: Option<Instant>,
+}
+
+/// Operations started this session, by id
+#[derive(Default)]
+pub struct Operations {
+    entries: Mutex<HashMap<String, Entry>>,
+    next_id: AtomicU64,
+}
+
+impl Operations {
+    pub fn status(&self, id: &str) -> Result<OperationStatus, String> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|entry| entry.status.clone())
+            .ok_or_else(|| format!("Unknown operation: {}", id))
+    }
+
+    /// Apply a change to a running operation; finished ones are left as they are
+    fn update(&self, id: &str, change: impl FnOnce(&mut OperationStatus)) -> Option<OperationStatus> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(id)?;
+        if entry.status.state != OperationState::Running {
+            return None;
+        }
+        change(&mut entry.status);
+        if entry.status.state != OperationState::Running {
+            entry.status.finished_at = Some(clock::now_utc());
+            entry.finished = Some(Instant::now());
+        }
+        Some(entry.status.clone())
+    }
+
+    fn prune(&self) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.finished.is_none_or(|at| at.elapsed() < FINISHED_TTL));
+    }
+}
+
+/// Event name for an operation's status changes
+pub fn event_name(id: &str) -> String {
+    format!("op://{}", id)
+}
+
+fn emit<R: Runtime>(app: &AppHandle<R>, status: Option<OperationStatus>) {
+    if let Some(status) = status {
+        let _ = app.emit(&event_name(&status.id), status);
+    }
+}
+
+/// Handed to the work so it can report progress and notice cancellation
+pub struct Progress<R: Runtime> {
+    app: AppHandle<R>,
+    id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<R: Runtime> Progress<R> {
+    /// Report how far along the work is, with an optional note for the user
+    pub fn report(&self, progress: Option<f64>, message: Option<String>) {
+        let status = self.app.state::<AppState>().operations.update(&self.id, |status| {
+            status.progress = progress.map(|p| p.clamp(0.0, 1.0));
+            status.message = message;
+        });
+        emit(&self.app, status);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fails once the operation is cancelled; returning this from a stream's
+    /// record callback stops the script
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(format!("Operation {} was cancelled", self.id))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Run `work` in the background as an operation of `kind`, returning its id
+pub fn start<R, F>(app: &AppHandle<R>, kind: &str, work: F) -> String
+where
+    R: Runtime,
+    F: FnOnce(&Progress<R>) -> Result<Value, String> + Send + 'static,
+{
+    let operations = &app.state::<AppState>().operations;
+    operations.prune();
+
+    let id = format!("op-{}", operations.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let status = OperationStatus {
+        id: id.clone(),
+        kind: kind.to_string(),
+        state: OperationState::Running,
+        progress: None,
+        message: None,
+        result: None,
+        error: None,
+        started_at: clock::now_utc(),
+        finished_at: None,
+    };
+    operations.entries.lock().unwrap().insert(
+        id.clone(),
+        Entry { status: status.clone(), cancelled: cancelled.clone(), finished: None },
+    );
+    emit(app, Some(status));
+    log::info!("Started operation {} ({})", id, kind);
+
+    let progress = Progress { app: app.clone(), id: id.clone(), cancelled };
+    tauri::async_runtime::spawn_blocking(move || {
+        let outcome = work(&progress);
+        let status = progress.app.state::<AppState>().operations.update(&progress.id, |status| match outcome {
+            Ok(result) => {
+                status.state = OperationState::Completed;
+                status.progress = Some(1.0);
+                status.result = Some(result);
+            }
+            Err(e) => {
+                log::warn!("Operation {} failed: {}", status.id, e);
+                status.state = OperationState::Failed;
+                status.error = Some(e);
+            }
+        });
+        emit(&progress.app, status);
+    });
+
+    id
+}
+
+/// Cancel a running operation; finished ones are returned unchanged
+pub fn cancel<R: Runtime>(app: &AppHandle<R>, id: &str) -> Result<OperationStatus, String> {
+    let operations = &app.state::<AppState>().operations;
+    if let Some(entry) = operations.entries.lock().unwrap().get(id) {
+        entry.cancelled.store(true, Ordering::SeqCst);
+    }
+    let status = operations.update(id, |status| status.state = OperationState::Cancelled);
+    if status.is_some() {
+        log::info!("Cancelled operation {}", id);
+    }
+    emit(app, status);
+    operations.status(id)
+}
@@ -0,0 +1,228 @@
+// Passive OS detection
+//
+// Each device's operating system is worked out from what it sends anyway:
+// the User-Agents in its recent traffic, and the TCP SYN and DHCP request
+// fingerprints `python/arp/os_fingerprint.py` records per MAC address in the
+// data directory's `os_fingerprints.json`. Every source votes for a platform
+// with its own weight (a User-Agent names the OS outright, a TTL only narrows
+// it to a family), and the confidence is the share of the possible weight
+// behind the winner. The evidence is reloaded in the background as it arrives
+// and fills the `os` field of every device, which the certificate install
+// guides pick their platform from.
+
+use crate::cert_guide::{platform_from_headers, Platform};
+use crate::clock;
+use crate::database::open_readonly;
+use crate::workspace;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How far back User-Agents are looked at
+const USER_AGENT_HOURS: i64 = 24;
+
+/// Recent requests whose User-Agent is looked at, across all devices
+const USER_AGENT_SAMPLE: u32 = 5000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    UserAgent,
+    Dhcp,
+    Tcp,
+}
+
+impl Source {
+    const ALL: [Source; 3] = [Source::UserAgent, Source::Dhcp, Source::Tcp];
+
+    fn weight(self) -> f64 {
+        match self {
+            Source::UserAgent => 0.6,
+            Source::Dhcp => 0.3,
+            Source::Tcp => 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DeviceOs {
+    pub platform: Platform,
+    /// 0.0–1.0
+    pub confidence: f64,
+    /// Sources that agree on the platform
+    pub sources: Vec<Source>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(default)]
+struct Observation {
+    /// "windows", "apple", "linux", "android", "ios", "macos" or none
+    family: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(default)]
+struct Fingerprint {
+    tcp: Option<Observation>,
+    dhcp: Option<Observation>,
+}
+
+/// Passive fingerprints by lowercase MAC
+type Fingerprints = HashMap<String, Fingerprint>;
+
+#[derive(Default)]
+struct Evidence {
+    fingerprints: Fingerprints,
+    fingerprints_modified: Option<SystemTime>,
+    /// Platform most of a device's recent User-Agents name, by device ID
+    user_agents: HashMap<String, Platform>,
+}
+
+static EVIDENCE: Mutex<Option<Evidence>> = Mutex::new(None);
+
+fn fingerprints_path() -> PathBuf {
+    workspace::data_dir().join("os_fingerprints.json")
+}
+
+/// The platform a fingerprint's family means for a device of this type; TCP
+/// stacks are shared by a vendor's phones and computers
+pub fn resolve_family(family: &str, device_type: &str) -> Option<Platform> {
+    let mobile = matches!(device_type, "phone" | "tablet");
+    match family {
+        "windows" => Some(Platform::Windows),
+        "ios" => Some(Platform::Ios),
+        "macos" => Some(Platform::Macos),
+        "android" => Some(Platform::Android),
+        "chromeos" => Some(Platform::Chromeos),
+        "apple" if mobile => Some(Platform::Ios),
+        "apple" => Some(Platform::Macos),
+        "linux" if mobile => Some(Platform::Android),
+        "linux" => Some(Platform::Linux),
+        _ => None,
+    }
+}
+
+/// Weigh what each source says; None without any evidence
+pub fn combine(votes: &[(Source, Platform)]) -> Option<DeviceOs> {
+    let mut scores: HashMap<Platform, f64> = HashMap::new();
+    for (source, platform) in votes {
+        *scores.entry(*platform).or_default() += source.weight();
+    }
+    let (platform, score) = scores.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    let possible: f64 = Source::ALL.iter().map(|s| s.weight()).sum();
+    let sources: Vec<Source> = votes.iter().filter(|(_, p)| *p == platform).map(|(s, _)| *s).collect();
+    Some(DeviceOs {
+        platform,
+        confidence: ((score / possible).min(1.0) * 100.0).round() / 100.0,
+        sources,
+    })
+}
+
+/// The OS of a device from the evidence loaded so far
+pub fn for_device(device_id: &str, mac: &str, device_type: &str) -> Option<DeviceOs> {
+    let guard = EVIDENCE.lock().unwrap_or_else(|e| e.into_inner());
+    let evidence = guard.as_ref()?;
+
+    let mut votes = Vec::new();
+    if let Some(platform) = evidence.user_agents.get(device_id) {
+        votes.push((Source::UserAgent, *platform));
+    }
+    if let Some(fingerprint) = evidence.fingerprints.get(&mac.to_lowercase()) {
+        for (source, observation) in [(Source::Dhcp, &fingerprint.dhcp), (Source::Tcp, &fingerprint.tcp)] {
+            let family = observation.as_ref().and_then(|o| o.family.as_deref());
+            if let Some(platform) = family.and_then(|f| resolve_family(f, device_type)) {
+                votes.push((source, platform));
+            }
+        }
+    }
+    combine(&votes)
+}
+
+/// The fingerprints file, if it changed since `since`
+fn load_fingerprints(since: Option<SystemTime>) -> Result<Option<(Fingerprints, SystemTime)>, String> {
+    let path = fingerprints_path();
+    let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) else { return Ok(None) };
+    if since == Some(modified) {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read OS fingerprints: {}", e))?;
+    let fingerprints: Fingerprints =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse OS fingerprints: {}", e))?;
+    let fingerprints = fingerprints.into_iter().map(|(mac, f)| (mac.to_lowercase(), f)).collect();
+    Ok(Some((fingerprints, modified)))
+}
+
+/// The platform most of each device's recent User-Agents name
+fn user_agent_platforms() -> Result<HashMap<String, Platform>, String> {
+    // No capture database yet means no traffic to go on
+    let Ok(conn) = open_readonly() else { return Ok(HashMap::new()) };
+    let since = clock::utc_bound(chrono::Utc::now() - chrono::Duration::hours(USER_AGENT_HOURS));
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, request_headers FROM traffic
+             WHERE timestamp >= ?1 AND device_id IS NOT NULL AND request_headers IS NOT NULL
+             ORDER BY timestamp DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| format!("Failed to prepare user agent query: {}", e))?;
+    let rows = stmt
+        .query_map(params![since, USER_AGENT_SAMPLE], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Failed to query user agents: {}", e))?;
+
+    let mut votes: HashMap<String, HashMap<Platform, u32>> = HashMap::new();
+    for row in rows {
+        let (device_id, headers) = row.map_err(|e| format!("Failed to read user agents: {}", e))?;
+        if let Some(platform) = platform_from_headers(&headers) {
+            *votes.entry(device_id).or_default().entry(platform).or_default() += 1;
+        }
+    }
+    Ok(votes
+        .into_iter()
+        .filter_map(|(device_id, counts)| {
+            let (platform, _) = counts.into_iter().max_by_key(|(_, count)| *count)?;
+            Some((device_id, platform))
+        })
+        .collect())
+}
+
+/// Reload whatever evidence changed
+pub fn refresh() -> Result<(), String> {
+    let since = EVIDENCE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|e| e.fingerprints_modified);
+    let fingerprints = load_fingerprints(since)?;
+    let user_agents = user_agent_platforms()?;
+
+    let mut guard = EVIDENCE.lock().unwrap_or_else(|e| e.into_inner());
+    let evidence = guard.get_or_insert_with(Evidence::default);
+    if let Some((fingerprints, modified)) = fingerprints {
+        log::debug!("Loaded OS fingerprints for {} devices", fingerprints.len());
+        evidence.fingerprints = fingerprints;
+        evidence.fingerprints_modified = Some(modified);
+    }
+    evidence.user_agents = user_agents;
+    Ok(())
+}
+
+/// Spawn the background task that keeps the OS evidence current
+pub fn spawn_os_detector() {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match tauri::async_runtime::spawn_blocking(refresh).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => log::debug!("OS detection refresh failed: {}", e),
+                Err(e) => log::warn!("OS detection task panicked: {}", e),
+            }
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    });
+}
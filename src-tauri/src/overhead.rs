@@ -0,0 +1,144 @@
+// Latency the monitor adds
+//
+// The overhead probe times the same HTTPS request to a few reference hosts
+// directly and through the proxy's localhost listener, every few minutes.
+// The difference is what interception costs each request. The probe raises
+// an alert when the median goes over the `overhead_alert_ms` setting; this
+// module summarizes the stored samples.
+
+use crate::clock;
+use crate::commands::load_settings;
+use crate::database::open_readonly;
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HostOverhead {
+    pub host: String,
+    /// Rounds where both the direct and the proxied request succeeded
+    pub samples: u32,
+    /// Rounds where the proxied request failed but the direct one didn't
+    pub proxy_failures: u32,
+    /// Medians over the rounds, in milliseconds
+    pub direct_ms: Option<f64>,
+    pub proxied_ms: Option<f64>,
+    pub overhead_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OverheadMetrics {
+    pub since: String,
+    pub hosts: Vec<HostOverhead>,
+    /// Median over hosts of each host's median overhead
+    pub overhead_ms: Option<f64>,
+    /// Overhead as a share of the direct round trip, 0-100+
+    pub overhead_percent: Option<f64>,
+    /// Overhead of the latest round (median over hosts)
+    pub latest_overhead_ms: Option<f64>,
+    pub latest_at: Option<String>,
+    /// 0 when alerts are off
+    pub threshold_ms: u32,
+    /// Whether the latest round was over the threshold
+    pub over_threshold: bool,
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+/// Direct vs proxied round trips over the last `hours`, by reference host
+pub fn metrics(hours: u32) -> Result<OverheadMetrics, String> {
+    let since = clock::utc_cutoff(chrono::Duration::hours(hours as i64));
+    let conn = open_readonly()?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, host, direct_ms, proxied_ms
+             FROM overhead_samples
+             WHERE timestamp >= ?1
+             ORDER BY timestamp",
+        )
+        .map_err(|e| format!("Failed to prepare overhead query: {}", e))?;
+    let rows = stmt
+        .query_map(params![since], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<f64>>(2)?,
+                row.get::<_, Option<f64>>(3)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query overhead samples: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read overhead samples: {}", e))?;
+
+    // Host -> (direct, proxied) of successful rounds, plus proxy-only failures
+    let mut by_host: BTreeMap<String, (Vec<f64>, Vec<f64>, u32)> = BTreeMap::new();
+    for (_, host, direct, proxied) in &rows {
+        let entry = by_host.entry(host.clone()).or_default();
+        match (direct, proxied) {
+            (Some(direct), Some(proxied)) => {
+                entry.0.push(*direct);
+                entry.1.push(*proxied);
+            }
+            (Some(_), None) => entry.2 += 1,
+            _ => {}
+        }
+    }
+
+    let hosts: Vec<HostOverhead> = by_host
+        .into_iter()
+        .map(|(host, (direct, proxied, proxy_failures))| {
+            let overheads = direct.iter().zip(&proxied).map(|(d, p)| p - d).collect();
+            HostOverhead {
+                host,
+                samples: direct.len() as u32,
+                proxy_failures,
+                direct_ms: median(direct),
+                proxied_ms: median(proxied),
+                overhead_ms: median(overheads),
+            }
+        })
+        .collect();
+
+    let overhead_ms = median(hosts.iter().filter_map(|h| h.overhead_ms).collect());
+    let direct_ms = median(hosts.iter().filter_map(|h| h.direct_ms).collect());
+    let overhead_percent = match (overhead_ms, direct_ms) {
+        (Some(overhead), Some(direct)) if direct > 0.0 => Some(overhead * 100.0 / direct),
+        _ => None,
+    };
+
+    // The latest round: each host's most recent successful sample
+    let mut latest: BTreeMap<&str, (&str, f64)> = BTreeMap::new();
+    for (timestamp, host, direct, proxied) in &rows {
+        if let (Some(direct), Some(proxied)) = (direct, proxied) {
+            latest.insert(host, (timestamp, proxied - direct));
+        }
+    }
+    let latest_overhead_ms = median(latest.values().map(|(_, o)| *o).collect());
+    let latest_at = latest.values().map(|(t, _)| t.to_string()).max();
+
+    let threshold_ms = load_settings().map(|s| s.overhead_alert_ms).unwrap_or(0);
+    let over_threshold = threshold_ms > 0 && latest_overhead_ms.is_some_and(|o| o > threshold_ms as f64);
+
+    Ok(OverheadMetrics {
+        since,
+        hosts,
+        overhead_ms,
+        overhead_percent,
+        latest_overhead_ms,
+        latest_at,
+        threshold_ms,
+        over_threshold,
+    })
+}
@@ -0,0 +1,323 @@
+// P2P and BitTorrent activity detection
+//
+// File sharing clients are a common source of both legal trouble and a
+// saturated uplink. They give themselves away in three ways: flows on the
+// BitTorrent ports (6881–6889, DHT included), lookups of trackers, DHT
+// bootstrap nodes and torrent index sites, and connections to many different
+// peers on high ports on both ends. Flows and DNS lookups are grouped per
+// device and scored 0–100 on those signals; devices at or above the alert
+// score raise an alert, at most once a day per device.
+
+use crate::activity::site_of;
+use crate::clock;
+use crate::database::{self, open_readonly};
+use crate::event_bus;
+use crate::i18n::t;
+use crate::state::AppState;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the background analysis runs
+const ANALYSIS_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// Window the background analysis looks at
+const ANALYSIS_HOURS: u32 = 6;
+
+/// Devices below this score are left out of the report
+const MIN_REPORT_SCORE: u32 = 25;
+
+/// Devices at or above this score raise an alert
+const ALERT_SCORE: u32 = 60;
+
+/// Ports registered to BitTorrent, including the usual DHT port
+const BITTORRENT_PORTS: std::ops::RangeInclusive<u16> = 6881..=6889;
+
+/// Trackers, DHT bootstrap nodes and torrent index sites
+const TRACKER_DOMAINS: &[&str] = &[
+    "opentrackr.org",
+    "openbittorrent.com",
+    "bittorrent.com",
+    "utorrent.com",
+    "transmissionbt.com",
+    "libtorrent.org",
+    "stealth.si",
+    "demonii.com",
+    "coppersurfer.tk",
+    "leechers-paradise.org",
+    "thepiratebay.org",
+    "1337x.to",
+    "rarbg.to",
+    "nyaa.si",
+    "yts.mx",
+    "torrentgalaxy.to",
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct P2pDevice {
+    /// Device ID, or IP for devices without one
+    pub device: String,
+    pub device_ip: String,
+    /// Flows on BitTorrent ports
+    pub bittorrent_flows: u64,
+    /// Distinct remote addresses reached from and on high ports
+    pub peers: usize,
+    pub tracker_lookups: u64,
+    pub trackers: Vec<String>,
+    /// Bytes over BitTorrent and peer-to-peer flows
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// 0–100
+    pub score: u32,
+    /// Which signals contributed, for display
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct P2pActivityReport {
+    pub hours: u32,
+    pub flows_analyzed: u64,
+    pub devices: Vec<P2pDevice>,
+}
+
+#[derive(Default)]
+struct DeviceStats {
+    device_ip: String,
+    bittorrent_flows: u64,
+    peers: HashSet<String>,
+    tracker_lookups: u64,
+    trackers: HashSet<String>,
+    bytes_in: u64,
+    bytes_out: u64,
+    first_seen: String,
+    last_seen: String,
+}
+
+impl DeviceStats {
+    fn seen(&mut self, device_ip: &str, timestamp: &str) {
+        if self.device_ip.is_empty() {
+            self.device_ip = device_ip.to_string();
+        }
+        if self.first_seen.is_empty() || timestamp < self.first_seen.as_str() {
+            self.first_seen = timestamp.to_string();
+        }
+        if timestamp > self.last_seen.as_str() {
+            self.last_seen = timestamp.to_string();
+        }
+    }
+}
+
+/// The tracker or torrent site a looked-up name belongs to, if any
+pub fn tracker_domain(name: &str) -> Option<String> {
+    let name = name.trim_end_matches('.').to_lowercase();
+    let known = TRACKER_DOMAINS
+        .iter()
+        .find(|d| name == **d || name.ends_with(&format!(".{}", d)));
+    match known {
+        Some(domain) => Some(domain.to_string()),
+        None if name.contains("torrent") => Some(site_of(&name)),
+        None => None,
+    }
+}
+
+fn score(device: String, stats: DeviceStats) -> P2pDevice {
+    let peers = stats.peers.len();
+    let port_score = (stats.bittorrent_flows as f64 / 10.0).clamp(0.0, 1.0);
+    let fanout_score = ((peers as f64 - 20.0) / 80.0).clamp(0.0, 1.0);
+    let tracker_score = (stats.tracker_lookups as f64 / 3.0).clamp(0.0, 1.0);
+
+    let mut trackers: Vec<String> = stats.trackers.into_iter().collect();
+    trackers.sort();
+
+    let mut reasons = Vec::new();
+    if port_score > 0.0 {
+        reasons.push(t("report.p2p.bittorrent_ports", &[("count", &stats.bittorrent_flows)]));
+    }
+    if fanout_score > 0.5 {
+        reasons.push(t("report.p2p.many_peers", &[("count", &peers)]));
+    }
+    if tracker_score > 0.0 {
+        reasons.push(t("report.p2p.trackers", &[("trackers", &trackers.join(", "))]));
+    }
+
+    P2pDevice {
+        device,
+        device_ip: stats.device_ip,
+        bittorrent_flows: stats.bittorrent_flows,
+        peers,
+        tracker_lookups: stats.tracker_lookups,
+        trackers,
+        bytes_in: stats.bytes_in,
+        bytes_out: stats.bytes_out,
+        first_seen: stats.first_seen,
+        last_seen: stats.last_seen,
+        score: (100.0 * (0.3 * port_score + 0.45 * fanout_score + 0.25 * tracker_score)).round() as u32,
+        reasons,
+    }
+}
+
+/// Score flows and lookups since `since` per device, most likely file sharing first
+pub fn report_from(conn: &Connection, device_id: Option<&str>, since: &str) -> Result<(u64, Vec<P2pDevice>), String> {
+    let mut devices: HashMap<String, DeviceStats> = HashMap::new();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, device_ip, device_port, remote_ip, remote_port, protocol, bytes_in, bytes_out, start_time
+             FROM flows
+             WHERE end_time >= ?1 AND (?2 IS NULL OR device_id = ?2)",
+        )
+        .map_err(|e| format!("Failed to prepare flow query: {}", e))?;
+    let rows = stmt
+        .query_map(params![since, device_id], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u16>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, u16>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, Option<i64>>(6)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(7)?.unwrap_or(0) as u64,
+                row.get::<_, String>(8)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query flows: {}", e))?;
+
+    let mut flows_analyzed = 0;
+    for row in rows {
+        let (device_id, device_ip, device_port, remote_ip, remote_port, protocol, bytes_in, bytes_out, start) =
+            row.map_err(|e| format!("Failed to read flow: {}", e))?;
+        flows_analyzed += 1;
+        let bittorrent = protocol == "bittorrent"
+            || BITTORRENT_PORTS.contains(&remote_port)
+            || BITTORRENT_PORTS.contains(&device_port);
+        // Clients and servers pair a high port with a well-known one; peers both use high ports
+        let peer_like = protocol == "other" && device_port >= 1024 && remote_port >= 1024;
+        if !bittorrent && !peer_like {
+            continue;
+        }
+
+        let stats = devices.entry(device_id.unwrap_or_else(|| device_ip.clone())).or_default();
+        stats.seen(&device_ip, &start);
+        if bittorrent {
+            stats.bittorrent_flows += 1;
+        }
+        stats.peers.insert(remote_ip);
+        stats.bytes_in += bytes_in;
+        stats.bytes_out += bytes_out;
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, device_ip, query_name, timestamp
+             FROM dns_queries
+             WHERE timestamp >= ?1 AND (?2 IS NULL OR device_id = ?2)",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map(params![since, device_id], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query DNS lookups: {}", e))?;
+    for row in rows {
+        let (device_id, device_ip, name, timestamp) = row.map_err(|e| format!("Failed to read DNS lookup: {}", e))?;
+        let Some(tracker) = tracker_domain(&name) else { continue };
+        let stats = devices.entry(device_id.unwrap_or_else(|| device_ip.clone())).or_default();
+        stats.seen(&device_ip, &timestamp);
+        stats.tracker_lookups += 1;
+        stats.trackers.insert(tracker);
+    }
+
+    let mut scored: Vec<P2pDevice> = devices
+        .into_iter()
+        .map(|(device, stats)| score(device, stats))
+        .filter(|d| d.score >= MIN_REPORT_SCORE)
+        .collect();
+    scored.sort_by(|a, b| b.score.cmp(&a.score).then((b.bytes_in + b.bytes_out).cmp(&(a.bytes_in + a.bytes_out))));
+    Ok((flows_analyzed, scored))
+}
+
+/// P2P activity in the last `hours`, per device
+pub fn report(device_id: Option<&str>, hours: u32) -> Result<P2pActivityReport, String> {
+    let conn = open_readonly()?;
+    let since = clock::utc_cutoff(chrono::Duration::hours(hours as i64));
+    let (flows_analyzed, devices) = report_from(&conn, device_id, &since)?;
+    Ok(P2pActivityReport {
+        hours,
+        flows_analyzed,
+        devices,
+    })
+}
+
+/// Alert on likely file sharing not already alerted in the last day
+fn raise_alerts(report: &P2pActivityReport) {
+    let since = clock::utc_cutoff(chrono::Duration::hours(24));
+    let recent: HashSet<String> = database::stored_alerts()
+        .iter()
+        .filter(|a| a.pointer("/metadata/p2p").and_then(|b| b.as_bool()) == Some(true))
+        .filter(|a| a.get("timestamp").and_then(|t| t.as_str()).is_some_and(|t| t >= since.as_str()))
+        .filter_map(|a| a.pointer("/metadata/device").and_then(|d| d.as_str()).map(|d| d.to_string()))
+        .collect();
+
+    for device in report.devices.iter().filter(|d| d.score >= ALERT_SCORE) {
+        if recent.contains(&device.device) {
+            continue;
+        }
+
+        let description = t(
+            "alert.p2p.description",
+            &[
+                ("device", &device.device),
+                ("peers", &device.peers),
+                ("megabytes", &format!("{:.1}", (device.bytes_in + device.bytes_out) as f64 / 1_048_576.0)),
+                ("reasons", &device.reasons.join("; ")),
+                ("score", &device.score),
+            ],
+        );
+        let mut metadata = json!(device);
+        metadata["p2p"] = json!(true);
+        // Devices without a known ID are listed by IP, which can't be attached to the alert
+        let device_id = Some(device.device.as_str()).filter(|d| d.parse::<std::net::IpAddr>().is_err());
+
+        log::warn!("Possible P2P file sharing on {} (score {})", device.device, device.score);
+        event_bus::raise_alert(
+            "medium",
+            "network",
+            &t("alert.p2p.title", &[("device", &device.device)]),
+            &description,
+            device_id,
+            Some(&metadata),
+        );
+    }
+}
+
+/// Spawn the background task that looks for P2P activity while monitoring runs
+pub fn spawn_p2p_detector(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(ANALYSIS_INTERVAL).await;
+
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if !handle.state::<AppState>().lifecycle.current().is_running() {
+                    return;
+                }
+                match report(None, ANALYSIS_HOURS) {
+                    Ok(report) => raise_alerts(&report),
+                    Err(e) => log::debug!("P2P analysis failed: {}", e),
+                }
+            })
+            .await;
+        }
+    });
+}
@@ -0,0 +1,345 @@
+// Parquet export
+//
+// Months of traffic are exported as a Hive-style partitioned Parquet dataset
+// for analysis outside the app: `date=YYYY-MM-DD/device=<id>/part-N.parquet`
+// under the export directory, readable in one go with
+// `pandas.read_parquet(path)` (the partition keys come back as columns).
+// Request and response bodies and headers are left out so the export stays
+// compact; everything else about each request is kept, Snappy-compressed.
+//
+// Rows are read in partition order and written in batches, so one partition's
+// writer is open at a time and memory stays bounded however much is exported.
+// The dataset is built next to the target and renamed into place once done, so
+// a failed or cancelled export leaves nothing behind.
+
+use crate::clock;
+use crate::database::open_readonly;
+use arrow_array::builder::{
+    BooleanBuilder, Int32Builder, Int64Builder, ListBuilder, StringBuilder, TimestampMicrosecondBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Rows buffered before they are written as a record batch
+const BATCH_ROWS: usize = 8192;
+
+/// Rows between progress reports
+const PROGRESS_EVERY: u64 = 5000;
+
+fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), true),
+        Field::new("device_id", DataType::Utf8, true),
+        Field::new("device_ip", DataType::Utf8, false),
+        Field::new("method", DataType::Utf8, false),
+        Field::new("url", DataType::Utf8, false),
+        Field::new("host", DataType::Utf8, false),
+        Field::new("path", DataType::Utf8, true),
+        Field::new("protocol", DataType::Utf8, true),
+        Field::new("status_code", DataType::Int32, true),
+        Field::new("content_type", DataType::Utf8, true),
+        Field::new("request_size", DataType::Int64, false),
+        Field::new("response_size", DataType::Int64, false),
+        Field::new("duration_ms", DataType::Int64, false),
+        Field::new("category", DataType::Utf8, true),
+        Field::new("blocked", DataType::Boolean, false),
+        Field::new("block_reason", DataType::Utf8, true),
+        Field::new("alerts", DataType::Int32, false),
+        Field::new("tags", DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))), false),
+    ]))
+}
+
+/// One traffic row as exported
+struct Row {
+    id: String,
+    timestamp: String,
+    device_id: Option<String>,
+    device_ip: String,
+    method: String,
+    url: String,
+    host: String,
+    path: Option<String>,
+    protocol: Option<String>,
+    status_code: Option<i64>,
+    content_type: Option<String>,
+    request_size: Option<i64>,
+    response_size: Option<i64>,
+    duration_ms: Option<i64>,
+    category: Option<String>,
+    blocked: Option<i64>,
+    block_reason: Option<String>,
+    alerts: Option<String>,
+    tags: Option<String>,
+}
+
+fn read_row(row: &rusqlite::Row) -> rusqlite::Result<Row> {
+    Ok(Row {
+        id: row.get("id")?,
+        timestamp: row.get("timestamp")?,
+        device_id: row.get("device_id")?,
+        device_ip: row.get("device_ip")?,
+        method: row.get("method")?,
+        url: row.get("url")?,
+        host: row.get("host")?,
+        path: row.get("path")?,
+        protocol: row.get("protocol")?,
+        status_code: row.get("status_code")?,
+        content_type: row.get("response_body_type")?,
+        request_size: row.get("request_size")?,
+        response_size: row.get("response_size")?,
+        duration_ms: row.get("duration_ms")?,
+        category: row.get("category")?,
+        blocked: row.get("blocked")?,
+        block_reason: row.get("block_reason")?,
+        alerts: row.get("alerts")?,
+        tags: row.get("tags")?,
+    })
+}
+
+/// Directory a row's partition is written to, relative to the dataset
+fn partition(row: &Row) -> PathBuf {
+    let date = match clock::parse_stored(&row.timestamp) {
+        Some(at) => at.format("%Y-%m-%d").to_string(),
+        None => "unknown".to_string(),
+    };
+    // Safe as a directory name on every platform
+    let device: String = row
+        .device_id
+        .as_deref()
+        .filter(|d| !d.is_empty())
+        .unwrap_or("unknown")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    Path::new(&format!("date={}", date)).join(format!("device={}", device))
+}
+
+fn to_batch(schema: &SchemaRef, rows: &[Row]) -> Result<RecordBatch, String> {
+    let mut id = StringBuilder::new();
+    let mut timestamp = TimestampMicrosecondBuilder::new().with_timezone("UTC");
+    let mut device_id = StringBuilder::new();
+    let mut device_ip = StringBuilder::new();
+    let mut method = StringBuilder::new();
+    let mut url = StringBuilder::new();
+    let mut host = StringBuilder::new();
+    let mut path = StringBuilder::new();
+    let mut protocol = StringBuilder::new();
+    let mut status_code = Int32Builder::new();
+    let mut content_type = StringBuilder::new();
+    let mut request_size = Int64Builder::new();
+    let mut response_size = Int64Builder::new();
+    let mut duration_ms = Int64Builder::new();
+    let mut category = StringBuilder::new();
+    let mut blocked = BooleanBuilder::new();
+    let mut block_reason = StringBuilder::new();
+    let mut alerts = Int32Builder::new();
+    let mut tags = ListBuilder::new(StringBuilder::new());
+
+    for row in rows {
+        id.append_value(&row.id);
+        timestamp.append_option(clock::parse_stored(&row.timestamp).map(|at| at.timestamp_micros()));
+        device_id.append_option(row.device_id.as_deref());
+        device_ip.append_value(&row.device_ip);
+        method.append_value(&row.method);
+        url.append_value(&row.url);
+        host.append_value(&row.host);
+        path.append_option(row.path.as_deref());
+        protocol.append_option(row.protocol.as_deref());
+        status_code.append_option(row.status_code.map(|s| s as i32));
+        content_type.append_option(row.content_type.as_deref());
+        request_size.append_value(row.request_size.unwrap_or(0));
+        response_size.append_value(row.response_size.unwrap_or(0));
+        duration_ms.append_value(row.duration_ms.unwrap_or(0));
+        category.append_option(row.category.as_deref());
+        blocked.append_value(row.blocked.unwrap_or(0) != 0);
+        block_reason.append_option(row.block_reason.as_deref());
+
+        let alert_count = row
+            .alerts
+            .as_deref()
+            .and_then(|a| serde_json::from_str::<Vec<Value>>(a).ok())
+            .map_or(0, |a| a.len());
+        alerts.append_value(alert_count as i32);
+
+        let row_tags: Vec<String> = row.tags.as_deref().and_then(|t| serde_json::from_str(t).ok()).unwrap_or_default();
+        for tag in row_tags {
+            tags.values().append_value(tag);
+        }
+        tags.append(true);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(id.finish()),
+        Arc::new(timestamp.finish()),
+        Arc::new(device_id.finish()),
+        Arc::new(device_ip.finish()),
+        Arc::new(method.finish()),
+        Arc::new(url.finish()),
+        Arc::new(host.finish()),
+        Arc::new(path.finish()),
+        Arc::new(protocol.finish()),
+        Arc::new(status_code.finish()),
+        Arc::new(content_type.finish()),
+        Arc::new(request_size.finish()),
+        Arc::new(response_size.finish()),
+        Arc::new(duration_ms.finish()),
+        Arc::new(category.finish()),
+        Arc::new(blocked.finish()),
+        Arc::new(block_reason.finish()),
+        Arc::new(alerts.finish()),
+        Arc::new(tags.finish()),
+    ];
+    RecordBatch::try_new(schema.clone(), columns).map_err(|e| format!("Failed to build record batch: {}", e))
+}
+
+/// Writes each partition's rows, one partition open at a time
+struct DatasetWriter {
+    root: PathBuf,
+    schema: SchemaRef,
+    current: Option<(PathBuf, ArrowWriter<File>)>,
+    /// Files written per partition, for partitions rows come back to
+    files: HashMap<PathBuf, usize>,
+    buffer: Vec<Row>,
+}
+
+impl DatasetWriter {
+    fn new(root: PathBuf) -> Self {
+        DatasetWriter { root, schema: schema(), current: None, files: HashMap::new(), buffer: Vec::new() }
+    }
+
+    fn push(&mut self, row: Row) -> Result<(), String> {
+        let partition = partition(&row);
+        if self.current.as_ref().is_none_or(|(current, _)| *current != partition) {
+            self.close()?;
+            self.open(partition)?;
+        }
+        self.buffer.push(row);
+        if self.buffer.len() >= BATCH_ROWS {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn open(&mut self, partition: PathBuf) -> Result<(), String> {
+        let dir = self.root.join(&partition);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create partition directory: {}", e))?;
+        let index = self.files.entry(partition.clone()).or_default();
+        let file = File::create(dir.join(format!("part-{}.parquet", index)))
+            .map_err(|e| format!("Failed to create Parquet file: {}", e))?;
+        *index += 1;
+
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let writer = ArrowWriter::try_new(file, self.schema.clone(), Some(properties))
+            .map_err(|e| format!("Failed to start Parquet file: {}", e))?;
+        self.current = Some((partition, writer));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        let Some((_, writer)) = self.current.as_mut() else { return Ok(()) };
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let batch = to_batch(&self.schema, &self.buffer)?;
+        writer.write(&batch).map_err(|e| format!("Failed to write Parquet data: {}", e))?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        self.flush()?;
+        if let Some((_, writer)) = self.current.take() {
+            writer.close().map_err(|e| format!("Failed to finish Parquet file: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Rows written and files per partition
+fn write_dataset(
+    conn: &Connection,
+    root: &Path,
+    total: u64,
+    on_progress: &mut impl FnMut(u64, u64) -> Result<(), String>,
+) -> Result<(u64, HashMap<PathBuf, usize>), String> {
+    let mut stmt = conn
+        .prepare("SELECT * FROM traffic ORDER BY substr(timestamp, 1, 10), device_id, timestamp")
+        .map_err(|e| format!("Failed to prepare export query: {}", e))?;
+    let rows = stmt.query_map([], read_row).map_err(|e| format!("Failed to query traffic: {}", e))?;
+
+    let mut writer = DatasetWriter::new(root.to_path_buf());
+    let mut written = 0u64;
+    for row in rows {
+        writer.push(row.map_err(|e| format!("Failed to read traffic: {}", e))?)?;
+        written += 1;
+        if written.is_multiple_of(PROGRESS_EVERY) {
+            on_progress(written, total)?;
+        }
+    }
+    writer.close()?;
+    Ok((written, writer.files))
+}
+
+/// Write every traffic row in `conn` as a partitioned dataset at `output`,
+/// calling `on_progress(written, total)` as it goes; an error from it stops
+/// the export
+pub fn export_from(
+    conn: &Connection,
+    output: &Path,
+    mut on_progress: impl FnMut(u64, u64) -> Result<(), String>,
+) -> Result<Value, String> {
+    if fs::read_dir(output).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(format!("Export directory is not empty: {}", output.display()));
+    }
+    let partial = output.with_file_name(format!(
+        "{}.part",
+        output.file_name().and_then(|n| n.to_str()).unwrap_or("export")
+    ));
+    if partial.exists() {
+        fs::remove_dir_all(&partial).map_err(|e| format!("Failed to remove unfinished export: {}", e))?;
+    }
+
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM traffic", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to count traffic: {}", e))?;
+
+    let (written, files) = match write_dataset(conn, &partial, total as u64, &mut on_progress) {
+        Ok(done) => done,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&partial);
+            return Err(e);
+        }
+    };
+
+    fs::create_dir_all(&partial).map_err(|e| format!("Failed to create export directory: {}", e))?;
+    if output.exists() {
+        fs::remove_dir(output).map_err(|e| format!("Failed to replace export directory: {}", e))?;
+    }
+    fs::rename(&partial, output).map_err(|e| format!("Failed to move export into place: {}", e))?;
+
+    Ok(json!({
+        "success": true,
+        "action": "exported",
+        "path": output.to_string_lossy(),
+        "format": "parquet",
+        "records": written,
+        "partitions": files.len(),
+        "files": files.values().sum::<usize>(),
+    }))
+}
+
+/// Export the capture database's traffic as a partitioned Parquet dataset
+pub fn export(output: &Path, on_progress: impl FnMut(u64, u64) -> Result<(), String>) -> Result<Value, String> {
+    let conn = open_readonly()?;
+    export_from(&conn, output, on_progress)
+}
@@ -0,0 +1,398 @@
+// Command and Python call performance
+//
+// Every Tauri command runs through `command` (which also applies the observer
+// capability check) and every Python script call through the script runner's
+// `timed`; both record their duration, outcome and payload size here. The
+// last RING_CAPACITY calls are kept in a ring buffer for
+// `get_performance_metrics`.
+//
+// While a command runs, time spent in Python calls and SQLite statements on
+// its task is added up, and serializing its result is timed on its own (the
+// result is serialized once more into a byte counter for that). That split
+// shows whether a slow command waits on Python, the database or serialization.
+//
+// Each command and script keeps a slow-moving baseline of its duration and a
+// fast-moving recent average. When the recent average climbs past
+// DEGRADED_FACTOR times the baseline, a warning is logged and kept until it
+// recovers.
+
+use crate::access;
+use crate::clock;
+use serde::Serialize;
+use std::cell::Cell;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Calls kept in the ring buffer
+const RING_CAPACITY: usize = 2_000;
+
+/// Degradation warnings kept for the metrics command
+const WARNING_CAPACITY: usize = 100;
+
+/// A single call slower than this is logged
+const SLOW_CALL: Duration = Duration::from_secs(2);
+
+/// Calls needed before a baseline is trusted
+const BASELINE_CALLS: u64 = 20;
+
+/// How far the recent average may rise above the baseline before warning
+const DEGRADED_FACTOR: f64 = 2.0;
+
+/// Recent averages below this never count as degraded, however small the baseline
+const DEGRADED_FLOOR_MS: f64 = 100.0;
+
+/// Weights of the newest call in the baseline and recent averages
+const BASELINE_WEIGHT: f64 = 0.02;
+const RECENT_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum CallKind {
+    Command,
+    Python,
+}
+
+impl CallKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            CallKind::Command => "command",
+            CallKind::Python => "python",
+        }
+    }
+}
+
+/// Time a command spent waiting on Python and SQLite
+#[derive(Debug, Default, Clone, Copy)]
+struct Breakdown {
+    python: Duration,
+    sqlite: Duration,
+}
+
+tokio::task_local! {
+    static BREAKDOWN: Cell<Breakdown>;
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CallSample {
+    pub kind: CallKind,
+    /// Command name, or "script:action" for Python calls
+    pub name: String,
+    pub at: String,
+    pub duration_ms: f64,
+    pub ok: bool,
+    /// Bytes of the serialized result (or error)
+    pub payload_bytes: usize,
+    /// Commands only: time spent in Python calls, SQLite statements and serializing the result
+    pub python_ms: f64,
+    pub sqlite_ms: f64,
+    pub serialize_ms: f64,
+}
+
+#[derive(Default)]
+struct Stats {
+    calls: u64,
+    errors: u64,
+    total_ms: f64,
+    max_ms: f64,
+    python_ms: f64,
+    sqlite_ms: f64,
+    serialize_ms: f64,
+    payload_bytes: u64,
+    baseline_ms: f64,
+    recent_ms: f64,
+    degraded: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CallStats {
+    pub kind: CallKind,
+    pub name: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    /// 95th percentile over the calls still in the ring buffer
+    pub p95_ms: f64,
+    pub avg_python_ms: f64,
+    pub avg_sqlite_ms: f64,
+    pub avg_serialize_ms: f64,
+    pub avg_payload_bytes: u64,
+    pub baseline_ms: f64,
+    pub recent_ms: f64,
+    pub degraded: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DegradationWarning {
+    pub kind: CallKind,
+    pub name: String,
+    pub at: String,
+    pub baseline_ms: f64,
+    pub recent_ms: f64,
+    /// Set once the recent average is back near the baseline
+    pub recovered_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PerformanceMetrics {
+    /// Slowest first
+    pub calls: Vec<CallStats>,
+    /// Newest first
+    pub recent: Vec<CallSample>,
+    pub warnings: Vec<DegradationWarning>,
+}
+
+#[derive(Default)]
+struct Recorder {
+    ring: VecDeque<CallSample>,
+    stats: BTreeMap<(CallKind, String), Stats>,
+    warnings: VecDeque<DegradationWarning>,
+}
+
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// A writer that only counts what it is given
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Bytes `value` takes as JSON
+pub fn serialized_len<T: Serialize + ?Sized>(value: &T) -> usize {
+    let mut counter = ByteCounter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+impl Recorder {
+    fn record(&mut self, sample: CallSample) {
+        let stats = self.stats.entry((sample.kind, sample.name.clone())).or_default();
+        stats.calls += 1;
+        if !sample.ok {
+            stats.errors += 1;
+        }
+        stats.total_ms += sample.duration_ms;
+        stats.max_ms = stats.max_ms.max(sample.duration_ms);
+        stats.python_ms += sample.python_ms;
+        stats.sqlite_ms += sample.sqlite_ms;
+        stats.serialize_ms += sample.serialize_ms;
+        stats.payload_bytes += sample.payload_bytes as u64;
+
+        if stats.calls == 1 {
+            stats.baseline_ms = sample.duration_ms;
+            stats.recent_ms = sample.duration_ms;
+        } else {
+            stats.recent_ms += (sample.duration_ms - stats.recent_ms) * RECENT_WEIGHT;
+            // A degraded call doesn't drag the baseline up with it
+            if !stats.degraded {
+                stats.baseline_ms += (sample.duration_ms - stats.baseline_ms) * BASELINE_WEIGHT;
+            }
+        }
+
+        if stats.calls >= BASELINE_CALLS {
+            let degraded = stats.recent_ms >= DEGRADED_FLOOR_MS && stats.recent_ms > stats.baseline_ms * DEGRADED_FACTOR;
+            if degraded && !stats.degraded {
+                log::warn!(
+                    "{} {} is slowing down: {:.0} ms recently against a {:.0} ms baseline",
+                    sample.kind.as_str(),
+                    sample.name,
+                    stats.recent_ms,
+                    stats.baseline_ms
+                );
+                if self.warnings.len() == WARNING_CAPACITY {
+                    self.warnings.pop_front();
+                }
+                self.warnings.push_back(DegradationWarning {
+                    kind: sample.kind,
+                    name: sample.name.clone(),
+                    at: sample.at.clone(),
+                    baseline_ms: stats.baseline_ms,
+                    recent_ms: stats.recent_ms,
+                    recovered_at: None,
+                });
+                stats.degraded = true;
+            } else if stats.degraded && stats.recent_ms <= stats.baseline_ms * (1.0 + DEGRADED_FACTOR) / 2.0 {
+                log::info!("{} {} is back to its usual speed", sample.kind.as_str(), sample.name);
+                if let Some(warning) = self
+                    .warnings
+                    .iter_mut()
+                    .rev()
+                    .find(|w| w.kind == sample.kind && w.name == sample.name)
+                {
+                    warning.recovered_at = Some(sample.at.clone());
+                }
+                stats.degraded = false;
+            }
+        }
+
+        if self.ring.len() == RING_CAPACITY {
+            self.ring.pop_front();
+        }
+        self.ring.push_back(sample);
+    }
+}
+
+fn record(sample: CallSample) {
+    if sample.duration_ms >= ms(SLOW_CALL) {
+        log::warn!(
+            "Slow {} {}: {:.0} ms (python {:.0} ms, sqlite {:.0} ms, serialize {:.0} ms)",
+            sample.kind.as_str(),
+            sample.name,
+            sample.duration_ms,
+            sample.python_ms,
+            sample.sqlite_ms,
+            sample.serialize_ms
+        );
+    }
+    RECORDER.lock().unwrap().get_or_insert_with(Recorder::default).record(sample);
+}
+
+async fn timed_command<T, E: Serialize + From<String>>(
+    name: &'static str,
+    body: impl Future<Output = Result<T, E>>,
+    size: impl FnOnce(&T) -> usize,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let (result, breakdown) = BREAKDOWN
+        .scope(Cell::default(), async {
+            // Observer sessions only get through for commands that read state
+            let result = match access::authorize(name) {
+                Ok(()) => body.await,
+                Err(e) => Err(E::from(e)),
+            };
+            (result, BREAKDOWN.with(Cell::get))
+        })
+        .await;
+
+    let serializing = Instant::now();
+    let payload_bytes = match &result {
+        Ok(value) => size(value),
+        Err(e) => serialized_len(e),
+    };
+    let serialize = serializing.elapsed();
+
+    record(CallSample {
+        kind: CallKind::Command,
+        name: name.to_string(),
+        at: clock::now_utc(),
+        duration_ms: ms(started.elapsed()),
+        ok: result.is_ok(),
+        payload_bytes,
+        python_ms: ms(breakdown.python),
+        sqlite_ms: ms(breakdown.sqlite),
+        serialize_ms: ms(serialize),
+    });
+    result
+}
+
+/// Run a Tauri command's body, recording how long it took and where the time went
+pub async fn command<T: Serialize>(
+    name: &'static str,
+    body: impl Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    timed_command(name, body, serialized_len).await
+}
+
+/// `command` for bodies with a typed error the frontend can tell apart
+pub async fn typed_command<T: Serialize, E: Serialize + From<String>>(
+    name: &'static str,
+    body: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    timed_command(name, body, serialized_len).await
+}
+
+/// `command` for bodies returning raw bytes, which go to the webview as they are
+pub async fn bytes_command(
+    name: &'static str,
+    body: impl Future<Output = Result<Vec<u8>, String>>,
+) -> Result<Vec<u8>, String> {
+    timed_command(name, body, Vec::len).await
+}
+
+/// Record a Python script call, and count it against the command it ran for
+pub fn record_python_call(name: String, elapsed: Duration, ok: bool, payload_bytes: usize) {
+    let _ = BREAKDOWN.try_with(|b| b.set(Breakdown { python: b.get().python + elapsed, ..b.get() }));
+    record(CallSample {
+        kind: CallKind::Python,
+        name,
+        at: clock::now_utc(),
+        duration_ms: ms(elapsed),
+        ok,
+        payload_bytes,
+        python_ms: 0.0,
+        sqlite_ms: 0.0,
+        serialize_ms: 0.0,
+    });
+}
+
+/// SQLite profile hook: count a finished statement against the running command
+pub fn record_sqlite(_sql: &str, elapsed: Duration) {
+    let _ = BREAKDOWN.try_with(|b| b.set(Breakdown { sqlite: b.get().sqlite + elapsed, ..b.get() }));
+}
+
+fn p95(durations: &mut [f64]) -> f64 {
+    if durations.is_empty() {
+        return 0.0;
+    }
+    durations.sort_by(f64::total_cmp);
+    durations[(durations.len() * 95).div_ceil(100) - 1]
+}
+
+/// Per-call stats, the newest `limit` calls and the degradation warnings
+pub fn metrics(limit: usize) -> PerformanceMetrics {
+    let recorder = RECORDER.lock().unwrap();
+    let Some(recorder) = recorder.as_ref() else {
+        return PerformanceMetrics { calls: vec![], recent: vec![], warnings: vec![] };
+    };
+
+    let mut durations: BTreeMap<(CallKind, &str), Vec<f64>> = BTreeMap::new();
+    for sample in &recorder.ring {
+        durations.entry((sample.kind, &sample.name)).or_default().push(sample.duration_ms);
+    }
+
+    let mut calls: Vec<CallStats> = recorder
+        .stats
+        .iter()
+        .map(|((kind, name), stats)| {
+            let per_call = |total: f64| total / stats.calls as f64;
+            CallStats {
+                kind: *kind,
+                name: name.clone(),
+                calls: stats.calls,
+                errors: stats.errors,
+                avg_ms: per_call(stats.total_ms),
+                max_ms: stats.max_ms,
+                p95_ms: durations.get_mut(&(*kind, name.as_str())).map_or(0.0, |d| p95(d)),
+                avg_python_ms: per_call(stats.python_ms),
+                avg_sqlite_ms: per_call(stats.sqlite_ms),
+                avg_serialize_ms: per_call(stats.serialize_ms),
+                avg_payload_bytes: stats.payload_bytes / stats.calls,
+                baseline_ms: stats.baseline_ms,
+                recent_ms: stats.recent_ms,
+                degraded: stats.degraded,
+            }
+        })
+        .collect();
+    calls.sort_by(|a, b| b.avg_ms.total_cmp(&a.avg_ms));
+
+    PerformanceMetrics {
+        calls,
+        recent: recorder.ring.iter().rev().take(limit).cloned().collect(),
+        warnings: recorder.warnings.iter().rev().cloned().collect(),
+    }
+}
@@ -0,0 +1,315 @@
+// Smart TV and IoT phone-home report
+//
+// What each TV, camera, speaker and other smart device talks to: the sites
+// it contacts (proxy requests, DNS lookups and non-web flows, attributed to a
+// site through the addresses its lookups returned), the countries its flows
+// go to, how much data it moves, and how much of that happens while it is
+// idle. A minute counts as active once the device moves ACTIVE_BYTES_PER_MINUTE
+// in it (someone streaming or casting); anything in the quieter minutes is the
+// device calling home on its own.
+
+use crate::activity::site_of;
+use crate::apps::UsageRange;
+use crate::commands::Device;
+use crate::database::open_readonly;
+use crate::geo;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Device types the report covers
+const IOT_TYPES: &[&str] = &["smart_tv", "iot", "printer", "camera", "speaker"];
+
+/// A minute with at least this much traffic is in use rather than idle
+const ACTIVE_BYTES_PER_MINUTE: u64 = 512 * 1024;
+
+/// Sites listed per device, most contacted first
+const MAX_DOMAINS: usize = 25;
+
+/// Flow protocols whose bytes the proxy already records
+const PROXIED_PROTOCOLS: &[&str] = &["http", "https"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ContactedDomain {
+    pub domain: String,
+    pub requests: u64,
+    pub lookups: u64,
+    pub bytes: u64,
+    /// Requests and lookups made while the device was idle
+    pub idle_contacts: u64,
+    pub countries: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CountryVolume {
+    /// ISO country code
+    pub country: String,
+    pub flows: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DevicePhoneHome {
+    pub device_id: String,
+    pub name: String,
+    pub device_type: String,
+    pub unique_domains: usize,
+    pub domains: Vec<ContactedDomain>,
+    pub countries: Vec<CountryVolume>,
+    pub requests: u64,
+    pub lookups: u64,
+    pub bytes: u64,
+    pub active_minutes: u64,
+    /// Minutes with some traffic, but not enough to be in use
+    pub idle_minutes: u64,
+    pub idle_bytes: u64,
+    pub idle_contacts: u64,
+    /// Share of requests and lookups made while idle, 0.0–1.0
+    pub idle_share: f64,
+    /// Sites contacted while idle
+    pub idle_domains: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PhoneHomeReport {
+    pub start: String,
+    pub end: String,
+    pub devices: Vec<DevicePhoneHome>,
+}
+
+#[derive(Default)]
+struct DomainAccumulator {
+    requests: u64,
+    lookups: u64,
+    bytes: u64,
+    idle_contacts: u64,
+    countries: BTreeSet<String>,
+}
+
+pub fn is_iot(device: &Device) -> bool {
+    IOT_TYPES.contains(&device.device_type.as_str())
+}
+
+/// Rows of a per-minute query: (key, minute, count, bytes)
+fn minute_rows(
+    conn: &Connection,
+    sql: &str,
+    device_id: &str,
+    start: &str,
+    end: &str,
+) -> Result<Vec<(String, String, u64, u64)>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| format!("Failed to prepare phone-home query: {}", e))?;
+    let rows = stmt
+        .query_map(params![device_id, start, end], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<i64>>(2)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+            ))
+        })
+        .map_err(|e| format!("Failed to query phone-home traffic: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read phone-home traffic: {}", e))
+}
+
+/// What a device contacted between `start` and `end`, and how much of it while idle
+pub fn device_report(conn: &Connection, device: &Device, start: &str, end: &str) -> Result<DevicePhoneHome, String> {
+    let id = device.id.as_str();
+
+    // Bytes per minute: passive counters see everything, the proxy only web traffic
+    let passive = minute_rows(
+        conn,
+        "SELECT NULL, minute, SUM(packets), SUM(bytes_in + bytes_out) FROM protocol_usage
+         WHERE device_id = ?1 AND minute >= substr(?2, 1, 16) AND minute < ?3
+         GROUP BY minute",
+        id,
+        start,
+        end,
+    )?;
+    let requests = minute_rows(
+        conn,
+        "SELECT host, substr(timestamp, 1, 16), COUNT(*), SUM(COALESCE(request_size, 0) + COALESCE(response_size, 0))
+         FROM traffic
+         WHERE device_id = ?1 AND timestamp >= ?2 AND timestamp < ?3
+         GROUP BY host, 2
+         UNION ALL
+         SELECT host, minute, SUM(requests), SUM(bytes_in + bytes_out)
+         FROM traffic_aggregates
+         WHERE device_id = ?1 AND minute >= substr(?2, 1, 16) AND minute < ?3
+         GROUP BY host, minute",
+        id,
+        start,
+        end,
+    )?;
+    let lookups = minute_rows(
+        conn,
+        "SELECT query_name, substr(timestamp, 1, 16), COUNT(*), 0 FROM dns_queries
+         WHERE device_id = ?1 AND timestamp >= ?2 AND timestamp < ?3
+         GROUP BY query_name, 2",
+        id,
+        start,
+        end,
+    )?;
+
+    let mut minute_bytes: HashMap<String, u64> = HashMap::new();
+    for (_, minute, _, bytes) in &passive {
+        minute_bytes.insert(minute.clone(), *bytes);
+    }
+    let mut proxied: HashMap<&str, u64> = HashMap::new();
+    for (_, minute, _, bytes) in &requests {
+        *proxied.entry(minute.as_str()).or_default() += bytes;
+    }
+    for (minute, bytes) in proxied {
+        let total = minute_bytes.entry(minute.to_string()).or_default();
+        *total = (*total).max(bytes);
+    }
+    let active: HashSet<&str> = minute_bytes
+        .iter()
+        .filter(|(_, bytes)| **bytes >= ACTIVE_BYTES_PER_MINUTE)
+        .map(|(minute, _)| minute.as_str())
+        .collect();
+
+    let mut domains: HashMap<String, DomainAccumulator> = HashMap::new();
+    for (host, minute, count, bytes) in &requests {
+        let domain = domains.entry(site_of(host)).or_default();
+        domain.requests += count;
+        domain.bytes += bytes;
+        if !active.contains(minute.as_str()) {
+            domain.idle_contacts += count;
+        }
+    }
+    for (name, minute, count, _) in &lookups {
+        let domain = domains.entry(site_of(name)).or_default();
+        domain.lookups += count;
+        if !active.contains(minute.as_str()) {
+            domain.idle_contacts += count;
+        }
+    }
+
+    // Addresses the device's lookups resolved to name the sites its flows go to
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT response_ip, query_name FROM dns_queries
+             WHERE device_id = ?1 AND timestamp >= ?2 AND timestamp < ?3 AND response_ip IS NOT NULL",
+        )
+        .map_err(|e| format!("Failed to prepare phone-home query: {}", e))?;
+    let sites_by_ip: HashMap<String, String> = stmt
+        .query_map(params![id, start, end], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Failed to query DNS answers: {}", e))?
+        .filter_map(Result::ok)
+        .map(|(ip, name)| (ip, site_of(&name)))
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT remote_ip, protocol, COUNT(*), SUM(bytes_in + bytes_out) FROM flows
+             WHERE device_id = ?1 AND start_time < ?3 AND end_time >= ?2
+             GROUP BY remote_ip, protocol",
+        )
+        .map_err(|e| format!("Failed to prepare phone-home query: {}", e))?;
+    let flows = stmt
+        .query_map(params![id, start, end], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)? as u64,
+                row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+            ))
+        })
+        .map_err(|e| format!("Failed to query flows: {}", e))?;
+    let mut countries: HashMap<String, CountryVolume> = HashMap::new();
+    for flow in flows {
+        let (remote_ip, protocol, count, bytes) = flow.map_err(|e| format!("Failed to read flow: {}", e))?;
+        let country = geo::country(&remote_ip);
+        if let Some(country) = &country {
+            let volume = countries.entry(country.clone()).or_insert_with(|| CountryVolume {
+                country: country.clone(),
+                flows: 0,
+                bytes: 0,
+            });
+            volume.flows += count;
+            volume.bytes += bytes;
+        }
+        let Some(site) = sites_by_ip.get(&remote_ip) else { continue };
+        let domain = domains.entry(site.clone()).or_default();
+        if !PROXIED_PROTOCOLS.contains(&protocol.as_str()) {
+            domain.bytes += bytes;
+        }
+        domain.countries.extend(country);
+    }
+
+    let total_contacts: u64 = domains.values().map(|d| d.requests + d.lookups).sum();
+    let idle_contacts: u64 = domains.values().map(|d| d.idle_contacts).sum();
+    let mut contacted: Vec<ContactedDomain> = domains
+        .into_iter()
+        .filter(|(domain, _)| !domain.is_empty())
+        .map(|(domain, d)| ContactedDomain {
+            domain,
+            requests: d.requests,
+            lookups: d.lookups,
+            bytes: d.bytes,
+            idle_contacts: d.idle_contacts,
+            countries: d.countries.into_iter().collect(),
+        })
+        .collect();
+    contacted.sort_by(|a, b| {
+        (b.requests + b.lookups)
+            .cmp(&(a.requests + a.lookups))
+            .then_with(|| a.domain.cmp(&b.domain))
+    });
+    let unique_domains = contacted.len();
+    let idle_domains = contacted.iter().filter(|d| d.idle_contacts > 0).count();
+    contacted.truncate(MAX_DOMAINS);
+
+    let mut countries: Vec<CountryVolume> = countries.into_values().collect();
+    countries.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.country.cmp(&b.country)));
+
+    let idle: Vec<u64> = minute_bytes
+        .iter()
+        .filter(|(minute, _)| !active.contains(minute.as_str()))
+        .map(|(_, bytes)| *bytes)
+        .collect();
+    Ok(DevicePhoneHome {
+        device_id: device.id.clone(),
+        name: device.hostname.clone().unwrap_or_else(|| device.ip.clone()),
+        device_type: device.device_type.clone(),
+        unique_domains,
+        domains: contacted,
+        countries,
+        requests: requests.iter().map(|r| r.2).sum(),
+        lookups: lookups.iter().map(|l| l.2).sum(),
+        bytes: minute_bytes.values().sum(),
+        active_minutes: active.len() as u64,
+        idle_minutes: idle.len() as u64,
+        idle_bytes: idle.iter().sum(),
+        idle_contacts,
+        idle_share: if total_contacts > 0 {
+            (idle_contacts as f64 / total_contacts as f64 * 100.0).round() / 100.0
+        } else {
+            0.0
+        },
+        idle_domains,
+    })
+}
+
+/// Phone-home activity of every IoT device in a range, chattiest while idle first
+pub fn report(devices: &[Device], range: UsageRange) -> Result<PhoneHomeReport, String> {
+    let (start, end) = range.bounds();
+    let conn = open_readonly()?;
+    let mut reports = devices
+        .iter()
+        .filter(|d| is_iot(d))
+        .map(|d| device_report(&conn, d, &start, &end))
+        .collect::<Result<Vec<_>, _>>()?;
+    reports.sort_by(|a, b| {
+        b.idle_contacts
+            .cmp(&a.idle_contacts)
+            .then(b.unique_domains.cmp(&a.unique_domains))
+    });
+    Ok(PhoneHomeReport {
+        start,
+        end,
+        devices: reports,
+    })
+}
@@ -0,0 +1,208 @@
+// Platform abstraction for privileged operations and OS-specific naming
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::OnceLock;
+
+/// Check whether the current process has administrator/root rights
+///
+/// Elevation can't change for a running process, so the probe runs once.
+pub fn is_elevated() -> bool {
+    static ELEVATED: OnceLock<bool> = OnceLock::new();
+    *ELEVATED.get_or_init(probe_elevated)
+}
+
+fn probe_elevated() -> bool {
+    #[cfg(windows)]
+    {
+        // `net session` only succeeds from an elevated token
+        Command::new("net")
+            .args(["session"])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+
+    #[cfg(unix)]
+    {
+        Command::new("id")
+            .arg("-u")
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8(o.stdout).ok())
+            .map(|uid| uid.trim() == "0")
+            .unwrap_or(false)
+    }
+}
+
+/// Build a command that runs `program` with elevated rights
+///
+/// Uses pkexec on Linux, osascript on macOS and an elevated PowerShell
+/// `Start-Process -Verb RunAs -Wait -PassThru` on Windows, which exits with
+/// the program's exit code. Each prompts the user.
+pub fn elevated_command(program: &Path, args: &[&str]) -> Command {
+    #[cfg(windows)]
+    {
+        windows_runas(program, args, true)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        macos_admin_shell(program, args, false)
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let mut cmd = Command::new("pkexec");
+        cmd.arg(program).args(args);
+        cmd
+    }
+}
+
+/// `do shell script ... with administrator privileges`, optionally backgrounded
+#[cfg(target_os = "macos")]
+fn macos_admin_shell(program: &Path, args: &[&str], background: bool) -> Command {
+    let mut shell_line = std::iter::once(program.display().to_string())
+        .chain(args.iter().map(|a| a.to_string()))
+        .map(|a| format!("'{}'", a.replace('\'', "'\\''")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if background {
+        shell_line.push_str(" &>/dev/null &");
+    }
+    let script = format!(
+        "do shell script \"{}\" with administrator privileges",
+        shell_line.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+
+    let mut cmd = Command::new("osascript");
+    cmd.args(["-e", &script]);
+    cmd
+}
+
+/// ShellExecute "runas" via PowerShell, optionally waiting for the child to exit
+#[cfg(windows)]
+fn windows_runas(program: &Path, args: &[&str], wait: bool) -> Command {
+    let arg_list = args
+        .iter()
+        .map(|a| format!("'{}'", a.replace('\'', "''")))
+        .collect::<Vec<_>>()
+        .join(",");
+    // A cancelled prompt is an error, so PowerShell exits non-zero
+    let mut script = format!(
+        "Start-Process -FilePath '{}' -Verb RunAs -ErrorAction Stop",
+        program.display().to_string().replace('\'', "''")
+    );
+    if !arg_list.is_empty() {
+        script.push_str(&format!(" -ArgumentList {}", arg_list));
+    }
+    // Start-Process succeeds whatever the child does, so pass its exit code on
+    if wait {
+        script = format!("$p = {} -Wait -PassThru; exit $p.ExitCode", script);
+    }
+
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+    cmd
+}
+
+/// Start a new, elevated copy of this executable, returning the launcher
+///
+/// The launcher exits non-zero if the prompt is cancelled. On Windows and
+/// macOS it exits once the copy is started; pkexec runs until the copy exits.
+pub fn relaunch_elevated(args: &[&str]) -> Result<Child, String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate executable: {}", e))?;
+
+    #[cfg(windows)]
+    let mut command = windows_runas(&exe, args, false);
+
+    // Background the app so osascript returns once the prompt is accepted
+    #[cfg(target_os = "macos")]
+    let mut command = macos_admin_shell(&exe, args, true);
+
+    // pkexec scrubs the environment, so pass the display through explicitly
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = {
+        let mut cmd = Command::new("pkexec");
+        cmd.arg("env");
+        for var in ["DISPLAY", "WAYLAND_DISPLAY", "XAUTHORITY", "XDG_RUNTIME_DIR"] {
+            if let Ok(value) = std::env::var(var) {
+                cmd.arg(format!("{}={}", var, value));
+            }
+        }
+        cmd.arg(&exe).args(args);
+        cmd
+    };
+
+    command
+        .spawn()
+        .map_err(|e| format!("Failed to request elevation: {}", e))
+}
+
+/// Human-readable name of the mechanism used to gain admin rights
+pub fn elevation_method() -> &'static str {
+    if cfg!(windows) {
+        "uac"
+    } else if cfg!(target_os = "macos") {
+        "osascript"
+    } else {
+        "pkexec"
+    }
+}
+
+/// Get the Python interpreter inside a virtualenv directory
+pub fn venv_python(venv: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts").join("python.exe")
+    } else {
+        venv.join("bin").join("python")
+    }
+}
+
+/// Name of the system Python executable to fall back to
+pub fn system_python() -> &'static str {
+    if cfg!(windows) {
+        "python"
+    } else {
+        "python3"
+    }
+}
+
+/// Best guess at the primary network interface name for this platform
+pub fn default_interface() -> String {
+    #[cfg(windows)]
+    {
+        "Wi-Fi".to_string()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // `route get default` prints "interface: en0"
+        Command::new("route")
+            .args(["-n", "get", "default"])
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8(o.stdout).ok())
+            .and_then(|out| {
+                out.lines()
+                    .find_map(|l| l.trim().strip_prefix("interface:").map(|s| s.trim().to_string()))
+            })
+            .unwrap_or_else(|| "en0".to_string())
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        // `ip route show default` prints "default via 192.168.1.1 dev wlan0 ..."
+        Command::new("ip")
+            .args(["route", "show", "default"])
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8(o.stdout).ok())
+            .and_then(|out| {
+                let mut parts = out.split_whitespace();
+                parts.find(|p| *p == "dev").and_then(|_| parts.next()).map(|s| s.to_string())
+            })
+            .unwrap_or_else(|| "wlan0".to_string())
+    }
+}
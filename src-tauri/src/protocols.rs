@@ -0,0 +1,133 @@
+// Traffic volume by protocol
+//
+// The bypass detector counts every packet between a LAN device and the
+// internet, by protocol: HTTP, HTTPS, DNS, QUIC (UDP/443) and other. Set
+// against the bytes the proxy recorded, this shows how much traffic never
+// reaches it, such as QUIC, pinned apps that fail the handshake, and
+// non-web protocols.
+
+use crate::apps::UsageRange;
+use crate::database::open_readonly;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+/// Protocols as counted by the bypass detector, in display order
+const PROTOCOLS: &[&str] = &["http", "https", "dns", "quic", "other"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProtocolVolume {
+    /// "http", "https", "dns", "quic" or "other"
+    pub protocol: String,
+    pub packets: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Share of all captured bytes, 0-100
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProtocolBreakdown {
+    pub start: String,
+    pub end: String,
+    pub device_id: Option<String>,
+    pub protocols: Vec<ProtocolVolume>,
+    /// All bytes captured passively
+    pub total_bytes: u64,
+    /// Bytes of requests and responses the proxy recorded
+    pub proxied_bytes: u64,
+    /// Captured bytes the proxy never saw (never negative)
+    pub unproxied_bytes: u64,
+}
+
+/// Passive capture volume by protocol, for a device (or all devices), with the proxy's share
+pub fn breakdown(device_id: Option<&str>, range: UsageRange) -> Result<ProtocolBreakdown, String> {
+    let (start, end) = range.bounds();
+    breakdown_between(device_id, start, end)
+}
+
+/// Passive capture volume between two UTC bounds
+pub fn breakdown_between(device_id: Option<&str>, start: String, end: String) -> Result<ProtocolBreakdown, String> {
+    breakdown_from(&open_readonly()?, device_id, start, end)
+}
+
+/// `breakdown_between` against an open database
+pub fn breakdown_from(
+    conn: &Connection,
+    device_id: Option<&str>,
+    start: String,
+    end: String,
+) -> Result<ProtocolBreakdown, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT protocol, SUM(packets), SUM(bytes_in), SUM(bytes_out)
+             FROM protocol_usage
+             WHERE minute >= substr(?1, 1, 16) AND minute < substr(?2, 1, 16) AND (?3 IS NULL OR device_id = ?3)
+             GROUP BY protocol",
+        )
+        .map_err(|e| format!("Failed to prepare protocol query: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, device_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(2)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+            ))
+        })
+        .map_err(|e| format!("Failed to query protocol usage: {}", e))?;
+
+    let mut protocols: Vec<ProtocolVolume> = PROTOCOLS
+        .iter()
+        .map(|p| ProtocolVolume {
+            protocol: p.to_string(),
+            packets: 0,
+            bytes_in: 0,
+            bytes_out: 0,
+            percent: 0.0,
+        })
+        .collect();
+    for row in rows {
+        let (protocol, packets, bytes_in, bytes_out) =
+            row.map_err(|e| format!("Failed to read protocol usage: {}", e))?;
+        let index = PROTOCOLS.iter().position(|p| *p == protocol).unwrap_or(PROTOCOLS.len() - 1);
+        let volume = &mut protocols[index];
+        volume.packets += packets;
+        volume.bytes_in += bytes_in;
+        volume.bytes_out += bytes_out;
+    }
+
+    let total_bytes: u64 = protocols.iter().map(|p| p.bytes_in + p.bytes_out).sum();
+    if total_bytes > 0 {
+        for volume in &mut protocols {
+            volume.percent = (volume.bytes_in + volume.bytes_out) as f64 * 100.0 / total_bytes as f64;
+        }
+    }
+
+    // Raw and rolled-up proxy traffic, as in app usage
+    let proxied_bytes: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(bytes), 0) FROM (
+                 SELECT COALESCE(request_size, 0) + COALESCE(response_size, 0) AS bytes
+                 FROM traffic
+                 WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_id = ?3)
+                 UNION ALL
+                 SELECT bytes_in + bytes_out
+                 FROM traffic_aggregates
+                 WHERE minute >= substr(?1, 1, 16) AND minute < substr(?2, 1, 16) AND (?3 IS NULL OR device_id = ?3)
+             )",
+            params![start, end, device_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to query proxied traffic: {}", e))?;
+    let proxied_bytes = proxied_bytes as u64;
+
+    Ok(ProtocolBreakdown {
+        start,
+        end,
+        device_id: device_id.map(str::to_string),
+        protocols,
+        total_bytes,
+        proxied_bytes,
+        unproxied_bytes: total_bytes.saturating_sub(proxied_bytes),
+    })
+}
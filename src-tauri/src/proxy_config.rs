@@ -0,0 +1,379 @@
+// HTTPS proxy configuration
+//
+// The app owns the proxy's configuration: the port it listens on, the CA it
+// signs with, the upstream proxy it forwards through and the hosts whose TLS
+// it passes through (the intercept exclusions). All of it is written to one
+// canonical file, `proxy.json` in the workspace config, which the proxy reads
+// at start (`--config`) and again on a `reload_config` command.
+//
+// The CA is handed over as a mitmproxy confdir holding `mitmproxy-ca.pem`
+// (key and certificate) and `mitmproxy-ca-cert.pem`, made from the latest
+// certificate `cert_generator.py` produced, so the proxy signs with the same
+// CA devices were asked to install. Traffic can be chained through an HTTP(S)
+// upstream proxy, except for domains on its bypass list, which connect
+// directly; SOCKS upstreams are refused because mitmproxy can only forward to
+// HTTP proxies. The upstream's password is not in the file; the proxy reads it
+// from the OS keychain. The upstream proxy and pass-through hosts apply live; the port and CA only when the proxy starts, so a sync that
+// changes them under a running proxy reports that a restart is needed.
+//
+// Before the proxy starts, its ports are checked: when something else already
+// listens on the configured port (or the probe port), the next free one is
+// used instead and written to the config, so the proxy and the overhead probe
+// agree on where it listens.
+
+use crate::commands::{broadcast_to_processes, load_settings, Device, Settings};
+use crate::intercept_exclusions;
+use crate::python::{get_project_root, run_python_script_with_input};
+use crate::secrets;
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const CONFIG_VERSION: u32 = 1;
+
+const UPSTREAM_SCRIPT: &str = "python/utils/upstream_proxy.py";
+
+/// Localhost port of the proxy's listener for the overhead probe
+const PROBE_PORT: u16 = 8081;
+
+/// Ports past a busy one tried before giving up
+const PORT_SEARCH: u16 = 20;
+
+/// Config the running proxy was started with
+static STARTED: Mutex<Option<Started>> = Mutex::new(None);
+
+#[derive(Clone)]
+struct Started {
+    /// Port from the settings, which may differ from the one bound
+    requested_port: u16,
+    config: ProxyConfig,
+}
+
+/// Ports the running proxy listens on
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct ProxyPorts {
+    pub listen: u16,
+    /// Localhost listener the overhead probe goes through
+    pub probe: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UpstreamProxy {
+    /// e.g. "http://proxy.lan:3128"
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Domains (and their subdomains) that connect directly instead
+    #[serde(default)]
+    pub bypass: Vec<String>,
+}
+
+/// Outcome of a request sent through an upstream proxy
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpstreamProxyTest {
+    pub ok: bool,
+    /// HTTP status of the test request, when it got an answer
+    pub status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ProxySettings {
+    pub port: u16,
+    /// Proxy outgoing traffic is forwarded through (None connects directly)
+    pub upstream: Option<UpstreamProxy>,
+}
+
+impl Default for ProxySettings {
+    fn default() -> Self {
+        ProxySettings { port: 8080, upstream: None }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PassthroughHost {
+    pub domain: String,
+    /// Only this device's connections pass through; every device's when None
+    pub device_ip: Option<String>,
+}
+
+/// What `proxy.json` holds
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProxyConfig {
+    pub version: u32,
+    pub listen_port: u16,
+    pub probe_port: u16,
+    /// mitmproxy confdir with the CA; None until a certificate is generated
+    pub confdir: Option<String>,
+    pub ca_cert: Option<String>,
+    pub ca_fingerprint: Option<String>,
+    pub upstream: Option<UpstreamProxy>,
+    pub tls_passthrough: Vec<PassthroughHost>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProxySyncStatus {
+    pub path: String,
+    pub config: ProxyConfig,
+    /// Whether a running proxy was told to reload the file
+    pub reloaded: bool,
+    /// The port or CA changed under a running proxy, which only a restart applies
+    pub restart_required: bool,
+}
+
+/// An entry of `cert_generator.py`'s metadata file
+#[derive(Debug, Deserialize)]
+struct CertificateRecord {
+    ca_cert_path: String,
+    ca_key_path: String,
+    #[serde(default)]
+    fingerprint: String,
+}
+
+pub fn validate(settings: &ProxySettings) -> Result<(), String> {
+    if settings.port == 0 || settings.port == PROBE_PORT {
+        return Err(format!("Invalid proxy port: {} ({} is kept for the overhead probe)", settings.port, PROBE_PORT));
+    }
+    match &settings.upstream {
+        Some(upstream) => validate_upstream(upstream),
+        None => Ok(()),
+    }
+}
+
+fn valid_domain(domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.');
+    domain.len() <= 253
+        && domain.contains('.')
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+pub fn validate_upstream(upstream: &UpstreamProxy) -> Result<(), String> {
+    if upstream.url.starts_with("socks") {
+        return Err(format!(
+            "Unsupported upstream proxy: {} (the HTTPS proxy can only forward to HTTP proxies)",
+            upstream.url
+        ));
+    }
+    let address = upstream
+        .url
+        .strip_prefix("http://")
+        .or_else(|| upstream.url.strip_prefix("https://"))
+        .map(|rest| rest.trim_end_matches('/'));
+    let valid = address
+        .and_then(|address| address.rsplit_once(':'))
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok_and(|p| p != 0));
+    if !valid {
+        return Err(format!("Invalid upstream proxy: {} (expected e.g. \"http://proxy.lan:3128\")", upstream.url));
+    }
+    if upstream.password.is_some() && upstream.username.is_none() {
+        return Err("An upstream proxy password needs a username".to_string());
+    }
+    if let Some(domain) = upstream.bypass.iter().find(|d| !valid_domain(d)) {
+        return Err(format!("Invalid bypass domain: {} (expected e.g. \"intranet.example\")", domain));
+    }
+    Ok(())
+}
+
+/// Send a request through `upstream` to check that it forwards traffic
+pub fn test_upstream(upstream: &UpstreamProxy, url: Option<&str>) -> Result<UpstreamProxyTest, String> {
+    validate_upstream(upstream)?;
+    let mut upstream = upstream.clone();
+    if upstream.password.is_none() && upstream.username.is_some() {
+        upstream.password = secrets::get_secret(secrets::UPSTREAM_PROXY_PASSWORD)?;
+    }
+    // The password goes on stdin, not the command line
+    let result = run_python_script_with_input(
+        UPSTREAM_SCRIPT,
+        &["--action", "test"],
+        &json!({ "upstream": upstream, "url": url }),
+    )?;
+    if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        return Err(error.to_string());
+    }
+    serde_json::from_value(result.get("test").cloned().unwrap_or_default())
+        .map_err(|e| format!("Failed to parse upstream proxy test: {}", e))
+}
+
+fn config_path() -> PathBuf {
+    workspace::config_dir().join("proxy.json")
+}
+
+fn certs_dir() -> PathBuf {
+    get_project_root().join("config").join("certs")
+}
+
+/// The certificate generated last, which is the one devices are given
+fn active_certificate() -> Result<Option<CertificateRecord>, String> {
+    let path = certs_dir().join("cert_metadata.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read certificate metadata: {}", e))?;
+    let mut records: Vec<CertificateRecord> =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse certificate metadata: {}", e))?;
+    Ok(records.pop())
+}
+
+/// Write a file only if its content changed
+fn write_if_changed(path: &PathBuf, content: &str) -> Result<(), String> {
+    if fs::read_to_string(path).is_ok_and(|current| current == content) {
+        return Ok(());
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Lay the certificate out as a mitmproxy confdir
+fn prepare_confdir(certificate: &CertificateRecord) -> Result<PathBuf, String> {
+    let cert = fs::read_to_string(&certificate.ca_cert_path)
+        .map_err(|e| format!("Failed to read CA certificate: {}", e))?;
+    let key = fs::read_to_string(&certificate.ca_key_path)
+        .map_err(|e| format!("Failed to read CA key: {}", e))?;
+
+    let confdir = certs_dir().join("mitmproxy");
+    fs::create_dir_all(&confdir).map_err(|e| format!("Failed to create proxy CA directory: {}", e))?;
+    write_if_changed(&confdir.join("mitmproxy-ca.pem"), &format!("{}{}", key, cert))?;
+    write_if_changed(&confdir.join("mitmproxy-ca-cert.pem"), &cert)?;
+    Ok(confdir)
+}
+
+/// The proxy configuration for the given settings and devices
+pub fn build(settings: &Settings, devices: &[Device]) -> Result<ProxyConfig, String> {
+    let certificate = active_certificate()?;
+    let confdir = certificate.as_ref().map(prepare_confdir).transpose()?;
+    let tls_passthrough = intercept_exclusions::resolve(&intercept_exclusions::load_exclusions()?, devices)
+        .into_iter()
+        .map(|(device_ip, domain)| PassthroughHost { domain, device_ip })
+        .collect();
+
+    Ok(ProxyConfig {
+        version: CONFIG_VERSION,
+        listen_port: settings.proxy.port,
+        probe_port: PROBE_PORT,
+        confdir: confdir.map(|dir| dir.to_string_lossy().to_string()),
+        ca_cert: certificate.as_ref().map(|c| c.ca_cert_path.clone()),
+        ca_fingerprint: certificate.map(|c| c.fingerprint).filter(|f| !f.is_empty()),
+        upstream: settings.proxy.upstream.clone().map(|mut upstream| {
+            for domain in &mut upstream.bypass {
+                *domain = domain.trim_end_matches('.').to_lowercase();
+            }
+            upstream
+        }),
+        tls_passthrough,
+    })
+}
+
+fn write(config: &ProxyConfig) -> Result<PathBuf, String> {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize proxy config: {}", e))?;
+    // Write then rename so the proxy never reads half a file
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, content).map_err(|e| format!("Failed to write proxy config: {}", e))?;
+    fs::rename(&temp, &path).map_err(|e| format!("Failed to write proxy config: {}", e))?;
+    Ok(path)
+}
+
+fn port_free(host: Ipv4Addr, port: u16) -> bool {
+    TcpListener::bind((host, port)).is_ok()
+}
+
+/// `preferred` if nothing listens on it, else the next free port after it
+fn free_port(host: Ipv4Addr, preferred: u16, taken: &[u16]) -> Option<u16> {
+    (0..=PORT_SEARCH)
+        .filter_map(|offset| preferred.checked_add(offset))
+        .find(|port| !taken.contains(port) && port_free(host, *port))
+}
+
+/// Ports for a proxy about to start, moving off any that are already in use
+pub fn pick_ports(preferred: u16) -> Result<ProxyPorts, String> {
+    let listen = free_port(Ipv4Addr::UNSPECIFIED, preferred, &[PROBE_PORT]).ok_or_else(|| {
+        format!(
+            "Proxy port {} is in use, and so is every port up to {}",
+            preferred,
+            preferred.saturating_add(PORT_SEARCH)
+        )
+    })?;
+    let probe = free_port(Ipv4Addr::LOCALHOST, PROBE_PORT, &[listen])
+        .ok_or_else(|| format!("Overhead probe port {} and the ports after it are in use", PROBE_PORT))?;
+
+    if listen != preferred {
+        log::warn!("Proxy port {} is in use; listening on {} instead", preferred, listen);
+    }
+    if probe != PROBE_PORT {
+        log::warn!("Probe port {} is in use; using {} instead", PROBE_PORT, probe);
+    }
+    Ok(ProxyPorts { listen, probe })
+}
+
+/// Write the config for a proxy about to start on free ports, returning the
+/// file's path and the ports
+pub fn prepare_start(devices: &[Device]) -> Result<(String, ProxyPorts), String> {
+    let settings = load_settings()?;
+    let ports = pick_ports(settings.proxy.port)?;
+    let mut config = build(&settings, devices)?;
+    config.listen_port = ports.listen;
+    config.probe_port = ports.probe;
+
+    let path = write(&config)?;
+    *STARTED.lock().unwrap() = Some(Started { requested_port: settings.proxy.port, config });
+    Ok((path.to_string_lossy().to_string(), ports))
+}
+
+/// Ports of the proxy started last, until monitoring stops
+pub fn bound_ports() -> Option<ProxyPorts> {
+    STARTED.lock().unwrap().as_ref().map(|started| ProxyPorts {
+        listen: started.config.listen_port,
+        probe: started.config.probe_port,
+    })
+}
+
+/// Forget the started proxy once monitoring stops
+pub fn stopped() {
+    *STARTED.lock().unwrap() = None;
+}
+
+/// Rewrite the config from the current settings and have a running proxy reload it
+pub fn sync(state: &AppState) -> Result<ProxySyncStatus, String> {
+    let devices = state.snapshot.devices(false)?.data;
+    let settings = load_settings()?;
+    let mut config = build(&settings, &devices)?;
+
+    let running = state.lifecycle.current().is_running();
+    let started = STARTED.lock().unwrap().clone();
+    let restart_required = match started {
+        Some(started) if running => {
+            // Keep the ports the proxy fell back to unless the port setting changed
+            if started.requested_port == settings.proxy.port {
+                config.listen_port = started.config.listen_port;
+                config.probe_port = started.config.probe_port;
+            }
+            started.requested_port != settings.proxy.port || started.config.ca_fingerprint != config.ca_fingerprint
+        }
+        _ => false,
+    };
+    let path = write(&config)?.to_string_lossy().to_string();
+
+    if running {
+        broadcast_to_processes(state, &json!({ "action": "reload_config", "path": path }));
+    }
+    if restart_required {
+        log::info!("Proxy port or CA changed; restart monitoring to apply them");
+    }
+
+    Ok(ProxySyncStatus { path, config, reloaded: running, restart_required })
+}
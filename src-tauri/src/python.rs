@@ -1,9 +1,16 @@
 // Python process management and IPC
 
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::process::{Child, Command, Stdio};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
 use anyhow::Result;
+use crate::metrics;
+use crate::performance;
+use crate::platform;
+use crate::workspace;
 use serde_json::Value;
 
 /// Get the project root directory
@@ -25,10 +32,14 @@ pub fn get_project_root() -> PathBuf {
         .to_path_buf()
 }
 
+/// The app's Python virtualenv
+pub fn venv_dir() -> PathBuf {
+    get_project_root().join("network_monitor_env")
+}
+
 /// Get the Python executable path
 pub fn get_python_path() -> PathBuf {
-    let root = get_project_root();
-    let venv_python = root.join("network_monitor_env").join("Scripts").join("python.exe");
+    let venv_python = platform::venv_python(&venv_dir());
     
     // If venv exists, use it
     if venv_python.exists() {
@@ -36,62 +47,280 @@ pub fn get_python_path() -> PathBuf {
     }
     
     // Fallback to system Python
-    PathBuf::from("python")
+    PathBuf::from(platform::system_python())
+}
+
+/// Point scripts at the active workspace's config and data directories
+fn workspace_env() -> [(&'static str, PathBuf); 2] {
+    [
+        ("NETWORK_MONITOR_CONFIG", workspace::config_dir()),
+        ("NETWORK_MONITOR_DATA", workspace::data_dir()),
+    ]
+}
+
+/// Runs Python scripts on behalf of the commands
+///
+/// Every script call goes through the active runner, which is `PythonRunner`
+/// except in tests, where `testing::MockScriptRunner` answers with canned JSON.
+/// Runners can be handed to other threads, as the database writer does.
+pub trait ScriptRunner: Send + Sync {
+    /// Run a script to completion and return its last JSON line
+    fn run(&self, script_path: &str, args: &[&str], input: Option<&Value>) -> Result<Value, String>;
+
+    /// Run a script that streams NDJSON records, returning its end summary
+    fn stream(
+        &self,
+        script_path: &str,
+        args: &[&str],
+        on_record: &mut dyn FnMut(Value) -> Result<(), String>,
+    ) -> Result<Value, String>;
+
+    /// Start a long-running script with piped stdin and stdout
+    fn start(&self, script_path: &str, args: &[&str]) -> Result<Child, String>;
+}
+
+/// Runs scripts with the project's Python interpreter
+pub struct PythonRunner;
+
+impl ScriptRunner for PythonRunner {
+    fn run(&self, script_path: &str, args: &[&str], input: Option<&Value>) -> Result<Value, String> {
+        run_script(script_path, args, input)
+    }
+
+    fn stream(
+        &self,
+        script_path: &str,
+        args: &[&str],
+        on_record: &mut dyn FnMut(Value) -> Result<(), String>,
+    ) -> Result<Value, String> {
+        stream_script(script_path, args, on_record)
+    }
+
+    fn start(&self, script_path: &str, args: &[&str]) -> Result<Child, String> {
+        let python = get_python_path();
+        let root = get_project_root();
+
+        log::info!("Starting Python script: {} with args: {:?}", script_path, args);
+
+        Command::new(&python)
+            .args(script_invocation(&root, script_path))
+            .args(args)
+            .current_dir(&root)
+            .envs(workspace_env())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// The runner script calls made on this thread go through
+pub fn runner() -> Arc<dyn ScriptRunner> {
+    #[cfg(test)]
+    if let Some(mock) = crate::testing::installed_runner() {
+        return mock;
+    }
+    Arc::new(PythonRunner)
 }
 
 /// Start a Python script as a background process
 pub fn start_python_script(script_path: &str, args: &[&str]) -> Result<Child> {
+    runner().start(script_path, args).map_err(anyhow::Error::msg)
+}
+
+/// Longest stdout line accepted from a script, so a runaway script can't exhaust memory
+const MAX_LINE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Interpreter arguments that run a script
+///
+/// Scripts under `python/` import their siblings relative to the package, so
+/// they run as modules (`-m python.dns.dns_blocker`) from the project root.
+pub fn script_invocation(root: &Path, script_path: &str) -> Vec<String> {
+    match script_path.strip_prefix("python/").and_then(|p| p.strip_suffix(".py")) {
+        Some(module) => vec!["-m".to_string(), format!("python.{}", module.replace('/', "."))],
+        None => vec![root.join(script_path).to_string_lossy().into_owned()],
+    }
+}
+
+fn spawn_script(script_path: &str, args: &[&str], stdin: Stdio) -> Result<Child, String> {
     let python = get_python_path();
     let root = get_project_root();
-    let full_path = root.join(script_path);
 
-    log::info!("Starting Python script: {:?} with args: {:?}", full_path, args);
+    log::info!("Running Python script: {} with args: {:?}", script_path, args);
 
-    let child = Command::new(&python)
-        .arg(&full_path)
+    Command::new(&python)
+        .args(script_invocation(&root, script_path))
         .args(args)
         .current_dir(&root)
+        .envs(workspace_env())
+        .stdin(stdin)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .stdin(Stdio::piped())
-        .spawn()?;
+        .spawn()
+        .map_err(|e| format!("Failed to run Python script: {}", e))
+}
+
+/// Feed a script's stdout to `on_line` one line at a time, then wait for it to exit
+fn read_lines(child: &mut Child, mut on_line: impl FnMut(&str) -> Result<(), String>) -> Result<(), String> {
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "Python script has no stdout".to_string())?;
+
+    // Drain stderr on its own thread so a chatty script can't block on a full pipe
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        })
+    });
 
-    Ok(child)
+    let mut reader = BufReader::new(stdout);
+    let mut buf = Vec::new();
+    let result = loop {
+        buf.clear();
+        match (&mut reader).take(MAX_LINE_BYTES + 1).read_until(b'\n', &mut buf) {
+            Ok(0) => break Ok(()),
+            Ok(n) if n as u64 > MAX_LINE_BYTES => {
+                break Err(format!("Python output line exceeds {} bytes", MAX_LINE_BYTES))
+            }
+            Ok(_) => {
+                if let Err(e) = on_line(String::from_utf8_lossy(&buf).trim_end()) {
+                    break Err(e);
+                }
+            }
+            Err(e) => break Err(format!("Failed to read Python output: {}", e)),
+        }
+    };
+
+    if result.is_err() {
+        let _ = child.kill();
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for Python script: {}", e))?;
+    let stderr = stderr_reader.and_then(|h| h.join().ok()).unwrap_or_default();
+
+    result?;
+    if !status.success() {
+        return Err(format!("Python script failed: {}", stderr));
+    }
+    Ok(())
+}
+
+/// Run a script call, recording its latency for the metrics endpoint and performance stats
+fn timed(script_path: &str, args: &[&str], call: impl FnOnce() -> Result<Value, String>) -> Result<Value, String> {
+    let started = Instant::now();
+    let result = call();
+    let elapsed = started.elapsed();
+    metrics::record_python_call(script_path, args, elapsed, result.is_ok());
+
+    let (script, action) = metrics::python_call_labels(script_path, args);
+    let name = if action.is_empty() { script } else { format!("{}:{}", script, action) };
+    let payload_bytes = match &result {
+        Ok(value) => performance::serialized_len(value),
+        Err(e) => e.len(),
+    };
+    performance::record_python_call(name, elapsed, result.is_ok(), payload_bytes);
+    result
 }
 
 /// Run a Python script and get JSON output
 pub fn run_python_script(script_path: &str, args: &[&str]) -> Result<Value, String> {
-    let python = get_python_path();
-    let root = get_project_root();
-    let full_path = root.join(script_path);
+    run_python_script_with(runner().as_ref(), script_path, args)
+}
 
-    log::info!("Running Python script: {:?} with args: {:?}", full_path, args);
+/// Run a Python script through a given runner, for calls made on another thread
+pub fn run_python_script_with(runner: &dyn ScriptRunner, script_path: &str, args: &[&str]) -> Result<Value, String> {
+    timed(script_path, args, || runner.run(script_path, args, None))
+}
 
-    let output = Command::new(&python)
-        .arg(&full_path)
-        .args(args)
-        .current_dir(&root)
-        .output()
-        .map_err(|e| format!("Failed to run Python script: {}", e))?;
+/// Run a Python script with a JSON document on stdin and get JSON output
+///
+/// For payloads that are too large or too sensitive (tokens, passwords) to
+/// pass on the command line.
+pub fn run_python_script_with_input(script_path: &str, args: &[&str], input: &Value) -> Result<Value, String> {
+    timed(script_path, args, || runner().run(script_path, args, Some(input)))
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Python script failed: {}", stderr));
+fn run_script(script_path: &str, args: &[&str], input: Option<&Value>) -> Result<Value, String> {
+    let stdin = if input.is_some() { Stdio::piped() } else { Stdio::null() };
+    let mut child = spawn_script(script_path, args, stdin)?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // Dropping stdin afterwards closes it, so the script sees EOF
+        let written = serde_json::to_writer(&mut stdin, input);
+        if let Err(e) = written {
+            let _ = child.kill();
+            return Err(format!("Failed to write script input: {}", e));
+        }
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    
-    // Find the last JSON line in output (scripts may output multiple JSON objects)
-    let json_str = stdout
-        .lines()
-        .filter(|line| line.starts_with('{') || line.starts_with('['))
-        .last()
-        .ok_or_else(|| "No JSON output from Python script".to_string())?;
-
-    serde_json::from_str(json_str)
+    // Keep only the last JSON line (scripts may output multiple JSON objects)
+    let mut last_json: Option<String> = None;
+    read_lines(&mut child, |line| {
+        if line.starts_with('{') || line.starts_with('[') {
+            last_json = Some(line.to_string());
+        }
+        Ok(())
+    })?;
+
+    let json_str = last_json.ok_or_else(|| "No JSON output from Python script".to_string())?;
+
+    serde_json::from_str(&json_str)
         .map_err(|e| format!("Failed to parse JSON: {} - Output: {}", e, json_str))
 }
 
+/// Run a Python script that streams NDJSON, handing each record to `on_record`
+///
+/// The script prints one `{"type": "record", "data": ...}` line per item and
+/// finishes with a `{"type": "end", ...}` summary, which is returned. Records
+/// are parsed as they arrive, so memory is bounded by the largest record
+/// rather than the whole result.
+pub fn stream_python_script(
+    script_path: &str,
+    args: &[&str],
+    mut on_record: impl FnMut(Value) -> Result<(), String>,
+) -> Result<Value, String> {
+    timed(script_path, args, || runner().stream(script_path, args, &mut on_record))
+}
+
+fn stream_script(
+    script_path: &str,
+    args: &[&str],
+    on_record: &mut dyn FnMut(Value) -> Result<(), String>,
+) -> Result<Value, String> {
+    let mut child = spawn_script(script_path, args, Stdio::null())?;
+
+    let mut summary: Option<Value> = None;
+    read_lines(&mut child, |line| {
+        if !line.starts_with('{') {
+            return Ok(());
+        }
+        let mut message: Value = serde_json::from_str(line)
+            .map_err(|e| format!("Failed to parse JSON record: {}", e))?;
+
+        match message.get("type").and_then(|t| t.as_str()) {
+            Some("record") => on_record(message.get_mut("data").map(Value::take).unwrap_or(Value::Null)),
+            // The end summary, or a plain response such as an early error
+            _ => {
+                summary = Some(message);
+                Ok(())
+            }
+        }
+    })?;
+
+    let summary = summary.ok_or_else(|| "Python stream ended without a summary".to_string())?;
+    if summary.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(summary)
+    } else {
+        let error = summary.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
 /// Run a database query script and return results
 pub fn query_database(action: &str, args: &[(&str, &str)]) -> Result<Value, String> {
     let mut script_args = vec!["--action", action];
@@ -104,6 +333,22 @@ pub fn query_database(action: &str, args: &[(&str, &str)]) -> Result<Value, Stri
     run_python_script("python/database/db_manager.py", &script_args)
 }
 
+/// Run a database query in streaming mode, handing each result row to `on_record`
+pub fn query_database_stream(
+    action: &str,
+    args: &[(&str, &str)],
+    on_record: impl FnMut(Value) -> Result<(), String>,
+) -> Result<Value, String> {
+    let mut script_args = vec!["--action", action, "--stream"];
+
+    for (key, value) in args {
+        script_args.push(key);
+        script_args.push(value);
+    }
+
+    stream_python_script("python/database/db_manager.py", &script_args, on_record)
+}
+
 /// Run a blocking engine command
 pub fn run_blocking_command(action: &str, args: &[(&str, &str)]) -> Result<Value, String> {
     let mut script_args = vec!["--action", action];
@@ -153,6 +398,33 @@ pub fn run_alert_command(action: &str, args: &[(&str, &str)]) -> Result<Value, S
     run_python_script("python/alerts/alert_engine.py", &script_args)
 }
 
+/// Raise an alert from a Rust-side detector through the alert engine
+pub fn create_alert(
+    severity: &str,
+    category: &str,
+    title: &str,
+    description: &str,
+    device_id: Option<&str>,
+    metadata: Option<&Value>,
+) -> Result<Value, String> {
+    let metadata = metadata.map(|m| m.to_string());
+    let mut args = vec![
+        ("--severity", severity),
+        ("--category", category),
+        ("--title", title),
+        ("--description", description),
+    ];
+
+    if let Some(device) = device_id {
+        args.push(("--device", device));
+    }
+    if let Some(ref m) = metadata {
+        args.push(("--metadata", m.as_str()));
+    }
+
+    run_alert_command("create", &args)
+}
+
 /// Send a command to a running Python process via stdin
 pub fn send_command_to_process(process: &mut Child, command: &Value) -> Result<(), String> {
     if let Some(ref mut stdin) = process.stdin {
@@ -0,0 +1,124 @@
+// Python environment check and repair
+//
+// The Python side runs from the `network_monitor_env` virtualenv, with the
+// versions pinned in `python/requirements.lock`. Checking reports each pinned
+// package as ok, missing or at another version. Repairing creates the venv if
+// it is missing or its interpreter no longer starts (or when asked to start
+// over), then reinstalls every package that isn't ok through pip, emitting
+// REPAIR_PROGRESS_EVENT per package.
+
+use crate::platform;
+use crate::python::{check_python, get_python_path, run_python_script, stream_python_script, venv_dir};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::process::Command;
+use tauri::{AppHandle, Emitter};
+
+const SCRIPT: &str = "python/utils/python_env.py";
+
+/// Event emitted at each step of a repair
+pub const REPAIR_PROGRESS_EVENT: &str = "python-env-repair-progress";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackageStatus {
+    pub name: String,
+    /// Version pinned in the lock file
+    pub required: String,
+    pub installed: Option<String>,
+    /// "ok", "missing" or "mismatch"
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PythonEnvironment {
+    pub python: String,
+    pub executable: String,
+    /// False when running on the system Python because the venv is missing
+    pub in_venv: bool,
+    /// Every pinned package is installed at its pinned version
+    pub healthy: bool,
+    pub packages: Vec<PackageStatus>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct RepairProgress {
+    /// "creating_venv", "installing", "installed", "failed", "verifying" or "done"
+    stage: String,
+    package: Option<String>,
+    index: Option<u64>,
+    total: Option<u64>,
+    error: Option<String>,
+}
+
+fn emit(app: &AppHandle, stage: &str) {
+    let _ = app.emit(
+        REPAIR_PROGRESS_EVENT,
+        RepairProgress { stage: stage.to_string(), package: None, index: None, total: None, error: None },
+    );
+}
+
+/// Check the installed packages against the lock file
+pub fn verify() -> Result<PythonEnvironment, String> {
+    let result = run_python_script(SCRIPT, &["--action", "verify"])?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        serde_json::from_value(result.get("environment").cloned().unwrap_or(Value::Null))
+            .map_err(|e| format!("Failed to parse Python environment: {}", e))
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Create the venv with the system Python, emptying it first if it exists
+fn create_venv() -> Result<(), String> {
+    let venv = venv_dir();
+    log::info!("Creating Python venv at {:?}", venv);
+
+    let output = Command::new(platform::system_python())
+        .args(["-m", "venv", "--clear"])
+        .arg(&venv)
+        .output()
+        .map_err(|e| format!("Failed to run Python to create the venv: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to create the Python venv: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Bring the venv in line with the lock file, recreating it if asked or broken
+pub fn repair(app: &AppHandle, recreate: bool) -> Result<PythonEnvironment, String> {
+    let venv_python = platform::venv_python(&venv_dir());
+    if recreate || !venv_python.exists() || check_python().is_err() {
+        emit(app, "creating_venv");
+        create_venv()?;
+    }
+    // Packages go into the venv, never the system Python
+    if get_python_path() != venv_python {
+        return Err(format!("Python venv has no interpreter at {:?}", venv_python));
+    }
+
+    stream_python_script(SCRIPT, &["--action", "repair"], |record| {
+        let text = |key: &str| record.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let count = |key: &str| record.get(key).and_then(|v| v.as_u64());
+        let _ = app.emit(
+            REPAIR_PROGRESS_EVENT,
+            RepairProgress {
+                stage: text("stage").unwrap_or_default(),
+                package: text("package"),
+                index: count("index"),
+                total: count("total"),
+                error: text("error"),
+            },
+        );
+        Ok(())
+    })?;
+
+    emit(app, "verifying");
+    let environment = verify()?;
+    emit(app, "done");
+    Ok(environment)
+}
@@ -0,0 +1,105 @@
+// QR code rendering
+//
+// Renders short text (a URL) as a QR code in PNG or SVG, with the four-module
+// quiet zone scanners expect around it.
+
+use qrcode::{Color, EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+
+/// Light modules around the code
+const QUIET_ZONE: usize = 4;
+
+/// Pixels per module in PNGs
+const MODULE_PIXELS: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl QrFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            QrFormat::Png => "image/png",
+            QrFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+/// Dark modules as rows, quiet zone included
+fn modules(text: &str) -> Result<Vec<Vec<bool>>, String> {
+    let code = QrCode::with_error_correction_level(text.as_bytes(), EcLevel::M)
+        .map_err(|e| format!("Failed to encode QR code: {}", e))?;
+    let width = code.width();
+    let colors = code.to_colors();
+    let size = width + 2 * QUIET_ZONE;
+
+    Ok((0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    let (x, y) = (x.wrapping_sub(QUIET_ZONE), y.wrapping_sub(QUIET_ZONE));
+                    x < width && y < width && colors[y * width + x] == Color::Dark
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn png(rows: &[Vec<bool>]) -> Result<Vec<u8>, String> {
+    let size = rows.len() * MODULE_PIXELS;
+    let mut pixels = Vec::with_capacity(size * size);
+    for row in rows {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|&dark| std::iter::repeat_n(if dark { 0 } else { 255 }, MODULE_PIXELS))
+            .collect();
+        for _ in 0..MODULE_PIXELS {
+            pixels.extend_from_slice(&line);
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("Failed to write QR code PNG: {}", e))?;
+    writer
+        .write_image_data(&pixels)
+        .map_err(|e| format!("Failed to write QR code PNG: {}", e))?;
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to write QR code PNG: {}", e))?;
+    Ok(out)
+}
+
+/// One path of unit squares, scaled by the viewer through the viewBox
+fn svg(rows: &[Vec<bool>]) -> Vec<u8> {
+    let size = rows.len();
+    let mut path = String::new();
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &dark) in row.iter().enumerate() {
+            if dark {
+                path.push_str(&format!("M{} {}h1v1h-1z", x, y));
+            }
+        }
+    }
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {size} {size}\" shape-rendering=\"crispEdges\">\
+         <rect width=\"{size}\" height=\"{size}\" fill=\"#fff\"/><path fill=\"#000\" d=\"{path}\"/></svg>"
+    )
+    .into_bytes()
+}
+
+pub fn render(text: &str, format: QrFormat) -> Result<Vec<u8>, String> {
+    let rows = modules(text)?;
+    match format {
+        QrFormat::Png => png(&rows),
+        QrFormat::Svg => Ok(svg(&rows)),
+    }
+}
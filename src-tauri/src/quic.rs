@@ -0,0 +1,158 @@
+// QUIC (HTTP/3) blocking
+//
+// QUIC runs over UDP/443, which the proxy never sees. Blocking it for a
+// device makes its browsers and apps fall back to TCP/TLS, which the proxy
+// can intercept. The blocking engine owns the policy: a global setting plus
+// per-device overrides either way. The QUIC blocker applies it and counts the
+// packets it rejected, which is the traffic downgraded to TCP.
+
+use crate::apps::UsageRange;
+use crate::commands::broadcast_to_processes;
+use crate::database::{device_ip, open_readonly};
+use crate::python::{run_blocking_command, run_python_script};
+use crate::state::AppState;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct QuicPolicy {
+    /// Block QUIC for devices without an override
+    pub block_quic: bool,
+    /// Device IP -> whether its QUIC is blocked, regardless of `block_quic`
+    #[serde(default)]
+    pub device_overrides: BTreeMap<String, bool>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QuicDeviceDowngrades {
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub packets: u64,
+    pub bytes: u64,
+    /// QUIC connections rejected
+    pub flows: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QuicDowngradeReport {
+    pub start: String,
+    pub end: String,
+    pub policy: QuicPolicy,
+    pub devices: Vec<QuicDeviceDowngrades>,
+    pub packets: u64,
+    pub bytes: u64,
+    pub flows: u64,
+}
+
+/// Load the QUIC policy from the blocking engine
+pub fn load_policy() -> Result<QuicPolicy, String> {
+    let result = run_blocking_command("quic-policy", &[])?;
+    let policy = result
+        .get("policy")
+        .cloned()
+        .ok_or_else(|| "Blocking engine returned no QUIC policy".to_string())?;
+
+    serde_json::from_value(policy).map_err(|e| format!("Failed to parse QUIC policy: {}", e))
+}
+
+/// Extra QUIC blocker arguments that apply the policy at startup
+pub fn blocker_args(policy: &QuicPolicy) -> Vec<String> {
+    let mut args = Vec::new();
+    if policy.block_quic {
+        args.push("--block-quic".to_string());
+    }
+    for (flag, blocked) in [("--block-device", true), ("--allow-device", false)] {
+        let ips: Vec<String> = policy
+            .device_overrides
+            .iter()
+            .filter(|(_, b)| **b == blocked)
+            .map(|(ip, _)| ip.clone())
+            .collect();
+        if !ips.is_empty() {
+            args.push(flag.to_string());
+            args.extend(ips);
+        }
+    }
+    args
+}
+
+/// Block or allow QUIC for every device without an override
+pub fn set_blocking(state: &AppState, enabled: bool) -> Result<(), String> {
+    let flag = if enabled { "true" } else { "false" };
+    let result = run_blocking_command("set-quic-blocking", &[("--enabled", flag)])?;
+    if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        return Err(error.to_string());
+    }
+
+    broadcast_to_processes(state, &json!({ "action": "set_quic_blocking", "enabled": enabled }));
+    log::info!("QUIC blocking set to {}", enabled);
+    Ok(())
+}
+
+/// Override a device's QUIC policy, or with None make it follow the global setting
+pub fn set_device(state: &AppState, device_id: &str, blocked: Option<bool>) -> Result<(), String> {
+    let conn = open_readonly()?;
+    let ip = device_ip(&conn, device_id)?.ok_or_else(|| format!("Device not found: {}", device_id))?;
+
+    let mut args = vec!["--action", "set-device-quic", "--device", &ip];
+    match blocked {
+        Some(true) => args.extend(["--enabled", "true"]),
+        Some(false) => args.extend(["--enabled", "false"]),
+        None => args.push("--clear"),
+    }
+    let result = run_python_script("python/blocking/blocker.py", &args)?;
+    if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        return Err(error.to_string());
+    }
+
+    broadcast_to_processes(state, &json!({
+        "action": "set_device_quic",
+        "device_ip": ip,
+        "blocked": blocked,
+    }));
+    log::info!("QUIC override for {} ({}) set to {:?}", device_id, ip, blocked);
+    Ok(())
+}
+
+/// QUIC traffic rejected (and so downgraded to TCP), by device, with the current policy
+pub fn report(device_id: Option<&str>, range: UsageRange) -> Result<QuicDowngradeReport, String> {
+    let (start, end) = range.bounds();
+    let conn = open_readonly()?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, device_ip, SUM(packets), SUM(bytes), SUM(flows)
+             FROM quic_downgrades
+             WHERE minute >= substr(?1, 1, 16) AND minute < ?2 AND (?3 IS NULL OR device_id = ?3)
+             GROUP BY device_ip
+             ORDER BY SUM(bytes) DESC",
+        )
+        .map_err(|e| format!("Failed to prepare QUIC query: {}", e))?;
+    let devices = stmt
+        .query_map(params![start, end, device_id], |row| {
+            Ok(QuicDeviceDowngrades {
+                device_id: row.get(0)?,
+                device_ip: row.get(1)?,
+                packets: row.get::<_, Option<i64>>(2)?.unwrap_or(0) as u64,
+                bytes: row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+                flows: row.get::<_, Option<i64>>(4)?.unwrap_or(0) as u64,
+            })
+        })
+        .map_err(|e| format!("Failed to query QUIC downgrades: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read QUIC downgrades: {}", e))?;
+
+    Ok(QuicDowngradeReport {
+        start,
+        end,
+        policy: load_policy()?,
+        packets: devices.iter().map(|d| d.packets).sum(),
+        bytes: devices.iter().map(|d| d.bytes).sum(),
+        flows: devices.iter().map(|d| d.flows).sum(),
+        devices,
+    })
+}
@@ -0,0 +1,162 @@
+// Quick actions for a command palette
+//
+// Common operations are listed in one registry with a title, a group and the
+// arguments they take, so a palette (and later hotkeys) can offer them
+// uniformly and run any of them by id. Running an action calls the same command
+// the rest of the UI uses; arguments arrive as a JSON object keyed by name.
+
+use crate::commands;
+use crate::i18n::t;
+use crate::state::AppState;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ActionArg {
+    pub name: &'static str,
+    /// "string", "boolean", "integer", "device" or "category"
+    pub kind: &'static str,
+    pub required: bool,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QuickAction {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub group: &'static str,
+    pub args: Vec<ActionArg>,
+}
+
+fn arg(name: &'static str, kind: &'static str, required: bool, description: &'static str) -> ActionArg {
+    ActionArg { name, kind, required, description }
+}
+
+fn action(
+    id: &'static str,
+    title: &'static str,
+    description: &'static str,
+    group: &'static str,
+    args: Vec<ActionArg>,
+) -> QuickAction {
+    QuickAction { id, title, description, group, args }
+}
+
+/// Every action the palette offers
+pub fn list() -> Vec<QuickAction> {
+    vec![
+        action("monitoring.start", "Start monitoring", "Start capturing traffic", "Monitoring", vec![]),
+        action("monitoring.stop", "Stop monitoring", "Stop capturing traffic", "Monitoring", vec![]),
+        action("devices.scan", "Scan for devices", "Scan the network for devices", "Devices", vec![]),
+        action(
+            "device.pause",
+            "Pause device",
+            "Stop monitoring a device",
+            "Devices",
+            vec![arg("device_id", "device", true, "Device to pause")],
+        ),
+        action(
+            "device.resume",
+            "Resume device",
+            "Monitor a paused device again",
+            "Devices",
+            vec![arg("device_id", "device", true, "Device to resume")],
+        ),
+        action(
+            "domain.block",
+            "Block domain",
+            "Block a domain, optionally for a limited time",
+            "Blocking",
+            vec![
+                arg("domain", "string", true, "Domain to block"),
+                arg("duration", "integer", false, "Minutes until the block expires"),
+            ],
+        ),
+        action(
+            "domain.unblock",
+            "Unblock domain",
+            "Remove a domain block",
+            "Blocking",
+            vec![arg("domain", "string", true, "Domain to unblock")],
+        ),
+        action(
+            "category.toggle",
+            "Toggle category",
+            "Block or unblock a content category",
+            "Blocking",
+            vec![
+                arg("category_id", "category", true, "Category to change"),
+                arg("enabled", "boolean", true, "Whether the category is blocked"),
+            ],
+        ),
+        action(
+            "guest_mode.set",
+            "Set guest mode",
+            "Turn guest mode on or off",
+            "Blocking",
+            vec![arg("enabled", "boolean", true, "Whether guest mode is on")],
+        ),
+        action("alerts.mark_all_read", "Mark all alerts read", "Acknowledge every alert", "Alerts", vec![]),
+        action("threat_feeds.update", "Update threat feeds", "Download the latest threat feeds", "Security", vec![]),
+        action(
+            "diagnostics.bundle",
+            "Generate diagnostics bundle",
+            "Collect logs and status into a zip for a bug report",
+            "System",
+            vec![],
+        ),
+        action("updates.check", "Check for updates", "Ask the update channel for a newer build", "System", vec![]),
+    ]
+}
+
+fn str_arg(args: &Value, name: &str) -> Result<String, String> {
+    args.get(name)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| t("error.missing_argument", &[("name", &name)]))
+}
+
+fn bool_arg(args: &Value, name: &str) -> Result<bool, String> {
+    args.get(name)
+        .and_then(|v| v.as_bool())
+        .ok_or_else(|| t("error.missing_argument", &[("name", &name)]))
+}
+
+/// Run an action by id, returning what its command returned
+pub async fn run(app: &AppHandle, id: &str, args: &Value) -> Result<Value, String> {
+    let state = app.state::<AppState>();
+
+    match id {
+        "monitoring.start" => commands::start_monitoring(state).await.map(|_| Value::Null).map_err(|e| e.to_string()),
+        "monitoring.stop" => commands::stop_monitoring(state).await.map(|_| Value::Null).map_err(|e| e.to_string()),
+        "devices.scan" => commands::refresh_devices(&state).map(|devices| json!(devices)),
+        "device.pause" => {
+            commands::set_device_monitoring(str_arg(args, "device_id")?, false).await.map(|_| Value::Null)
+        }
+        "device.resume" => {
+            commands::set_device_monitoring(str_arg(args, "device_id")?, true).await.map(|_| Value::Null)
+        }
+        "domain.block" => {
+            let duration = args.get("duration").and_then(|d| d.as_u64()).map(|d| d as u32);
+            commands::add_block_rule("domain".to_string(), str_arg(args, "domain")?, duration)
+                .await
+                .map(|_| Value::Null)
+        }
+        "domain.unblock" => commands::remove_block_rule("domain".to_string(), str_arg(args, "domain")?)
+            .await
+            .map(|_| Value::Null),
+        "category.toggle" => {
+            commands::toggle_category(str_arg(args, "category_id")?, bool_arg(args, "enabled")?)
+                .await
+                .map(|_| Value::Null)
+        }
+        "guest_mode.set" => commands::set_guest_mode(bool_arg(args, "enabled")?, state).await.map(|_| Value::Null),
+        "alerts.mark_all_read" => commands::mark_all_alerts_read().await.map(|_| Value::Null),
+        "threat_feeds.update" => commands::update_threat_feeds(Some(true)).await,
+        "diagnostics.bundle" => commands::generate_diagnostics_bundle(app.clone()).await.map(|b| json!(b)),
+        "updates.check" => commands::check_for_updates(app.clone()).await.map(|u| json!(u)),
+        other => Err(t("error.unknown_quick_action", &[("id", &other)])),
+    }
+}
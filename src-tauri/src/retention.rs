@@ -0,0 +1,80 @@
+// Data retention per data class
+//
+// Traffic, DNS lookups, alerts and captured bodies each have their own window
+// in the `retention` setting. The policy itself lives in
+// `python/database/retention.py`, which reads the same setting; this side runs
+// it from the maintenance task and lets the UI preview what it would delete
+// before applying it.
+
+use crate::db_writer;
+use crate::stats_cache;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How long each class of data is kept; 0 keeps it forever
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// Raw traffic, its aggregates, bypass and TLS records, protocol and QUIC counters, and overhead samples
+    pub traffic_days: u32,
+    pub dns_days: u32,
+    /// Alerts and the incidents grouping them
+    pub alerts_days: u32,
+    /// Request and response bodies, cleared from traffic that is otherwise kept
+    pub bodies_hours: u32,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        RetentionSettings {
+            traffic_days: 30,
+            dns_days: 90,
+            alerts_days: 365,
+            bodies_hours: 48,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetentionClass {
+    /// "traffic", "dns", "alerts" or "bodies"
+    pub class: String,
+    /// The window, e.g. "30d", "48h" or "forever"
+    pub keep: String,
+    /// UTC timestamp before which this class is removed (None if kept forever)
+    pub cutoff: Option<String>,
+    /// Rows (or alerts) removed; for bodies, traffic rows whose bodies are cleared
+    pub rows: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetentionReport {
+    /// True for a preview, when nothing was deleted
+    pub dry_run: bool,
+    pub classes: Vec<RetentionClass>,
+    pub total: u64,
+}
+
+fn run(action: &str) -> Result<RetentionReport, String> {
+    let result = db_writer::write("python/database/retention.py", &["--action", action])?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        serde_json::from_value(result.get("retention").cloned().unwrap_or(Value::Null))
+            .map_err(|e| format!("Failed to parse retention report: {}", e))
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// What applying the policy would delete right now
+pub fn preview() -> Result<RetentionReport, String> {
+    run("preview")
+}
+
+/// Delete everything past its retention window
+pub fn apply() -> Result<RetentionReport, String> {
+    let report = run("apply");
+    stats_cache::invalidate();
+    report
+}
@@ -0,0 +1,170 @@
+// OS keychain secrets
+//
+// Credentials (the SMTP password, the alert webhook URL, the MQTT and upstream
+// proxy passwords and the Home Assistant token) are kept in the OS keychain
+// under one service, the same one the Python side reads them back from, never
+// in the settings files. Older versions kept some of them in settings.json and
+// notifications.json; `migrate_plaintext` moves those into the keychain at
+// startup and only leaves one in place if the keychain can't be written.
+
+use crate::commands::{load_settings, save_settings, Settings};
+use crate::mqtt;
+use crate::proxy_config;
+use crate::state::AppState;
+use crate::workspace;
+use serde_json::Value;
+use std::fs;
+
+/// Keychain service every secret is stored under
+pub const SERVICE: &str = "network-monitor";
+
+pub const SMTP_PASSWORD: &str = "smtp_password";
+pub const WEBHOOK_URL: &str = "webhook_url";
+pub const MQTT_PASSWORD: &str = "mqtt_password";
+pub const UPSTREAM_PROXY_PASSWORD: &str = "upstream_proxy_password";
+pub const HOME_ASSISTANT_TOKEN: &str = "home_assistant";
+
+const KNOWN_SECRETS: &[&str] = &[
+    SMTP_PASSWORD,
+    WEBHOOK_URL,
+    MQTT_PASSWORD,
+    UPSTREAM_PROXY_PASSWORD,
+    HOME_ASSISTANT_TOKEN,
+];
+
+/// Notification config keys that moved to the keychain
+const NOTIFICATION_SECRETS: &[&str] = &[SMTP_PASSWORD, WEBHOOK_URL];
+
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if KNOWN_SECRETS.contains(&name) {
+        Ok(())
+    } else {
+        Err(format!("Unknown secret: {} (expected one of {})", name, KNOWN_SECRETS.join(", ")))
+    }
+}
+
+fn entry(name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, name).map_err(|e| format!("Failed to open OS keychain: {}", e))
+}
+
+/// Store a secret; an empty value removes it
+pub fn set_secret(name: &str, value: &str) -> Result<(), String> {
+    validate_name(name)?;
+    let entry = entry(name)?;
+    if value.is_empty() {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(format!("Failed to remove {} from the OS keychain: {}", name, e)),
+        }
+        log::info!("Removed secret {}", name);
+    } else {
+        entry
+            .set_password(value)
+            .map_err(|e| format!("Failed to save {} in the OS keychain: {}", name, e))?;
+        log::info!("Stored secret {}", name);
+    }
+    Ok(())
+}
+
+pub fn get_secret(name: &str) -> Result<Option<String>, String> {
+    validate_name(name)?;
+    match entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read {} from the OS keychain: {}", name, e)),
+    }
+}
+
+/// Names of the secrets that are stored; their values are never returned
+pub fn stored_names() -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    for name in KNOWN_SECRETS {
+        if get_secret(name)?.is_some() {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Move passwords typed into the settings into the keychain, returning the
+/// names of the secrets that were stored
+pub fn take_from_settings(settings: &mut Settings) -> Result<Vec<&'static str>, String> {
+    let mut stored = Vec::new();
+    if let Some(password) = settings.mqtt.password.take().filter(|p| !p.is_empty()) {
+        set_secret(MQTT_PASSWORD, &password)?;
+        stored.push(MQTT_PASSWORD);
+    }
+    if let Some(upstream) = settings.proxy.upstream.as_mut() {
+        if let Some(password) = upstream.password.take().filter(|p| !p.is_empty()) {
+            set_secret(UPSTREAM_PROXY_PASSWORD, &password)?;
+            stored.push(UPSTREAM_PROXY_PASSWORD);
+        }
+    }
+    Ok(stored)
+}
+
+/// Let whatever uses a secret pick up its new value
+pub fn apply(state: &AppState, name: &str) -> Result<(), String> {
+    match name {
+        MQTT_PASSWORD => mqtt::reconnect(),
+        UPSTREAM_PROXY_PASSWORD => {
+            proxy_config::sync(state)?;
+        }
+        // Read by the Python side each time it is needed
+        _ => {}
+    }
+    Ok(())
+}
+
+fn migrate_settings() -> Result<usize, String> {
+    let mut settings = load_settings()?;
+    let stored = take_from_settings(&mut settings)?;
+    if !stored.is_empty() {
+        save_settings(&settings)?;
+    }
+    Ok(stored.len())
+}
+
+fn migrate_notifications() -> Result<usize, String> {
+    let path = workspace::config_dir().join("notifications.json");
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read notification config: {}", e))?;
+    let mut config: Value =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse notification config: {}", e))?;
+    let Some(values) = config.as_object_mut() else {
+        return Ok(0);
+    };
+
+    let mut moved = 0;
+    for name in NOTIFICATION_SECRETS {
+        let Some(value) = values.get(*name).and_then(|v| v.as_str()).map(str::to_string) else {
+            continue;
+        };
+        if !value.is_empty() {
+            set_secret(name, &value)?;
+        }
+        values.remove(*name);
+        moved += 1;
+    }
+
+    if moved > 0 {
+        let content = serde_json::to_string_pretty(&config)
+            .map_err(|e| format!("Failed to serialize notification config: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write notification config: {}", e))?;
+    }
+    Ok(moved)
+}
+
+/// Move credentials older versions kept in plaintext into the keychain
+pub fn migrate_plaintext() {
+    for (source, result) in [("settings", migrate_settings()), ("notification config", migrate_notifications())] {
+        match result {
+            Ok(0) => {}
+            Ok(moved) => log::info!("Moved {} plaintext credential(s) from the {} to the OS keychain", moved, source),
+            Err(e) => log::warn!("Plaintext credentials in the {} were left in place: {}", source, e),
+        }
+    }
+}
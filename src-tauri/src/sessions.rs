@@ -0,0 +1,165 @@
+// Browsing sessions per device
+//
+// A device's requests (raw traffic and its rolled-up aggregates) are split
+// into sessions wherever it goes quiet for longer than SESSION_GAP. Each
+// session is labeled with the sites it spent the most requests on, grouped
+// as in the activity comparison, so a run of requests reads as "45 minutes
+// on youtube.com and googlevideo.com" instead of hundreds of rows.
+
+use crate::activity::site_of;
+use crate::apps::UsageRange;
+use crate::clock::{self, Zone};
+use crate::database::open_readonly;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Quiet time that ends a session
+const SESSION_GAP: Duration = Duration::minutes(10);
+
+/// Sites listed per session
+const TOP_SITES: usize = 5;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SessionSite {
+    pub site: String,
+    pub requests: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BrowsingSession {
+    pub start: String,
+    pub end: String,
+    pub duration_seconds: u64,
+    pub requests: u64,
+    pub bytes: u64,
+    /// Sites contacted in the session
+    pub site_count: usize,
+    /// The busiest sites, most requests first
+    pub top_sites: Vec<SessionSite>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceSessions {
+    pub device_id: String,
+    pub start: String,
+    pub end: String,
+    pub gap_minutes: i64,
+    /// Newest first
+    pub sessions: Vec<BrowsingSession>,
+    pub total_duration_seconds: u64,
+}
+
+struct OpenSession {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    requests: u64,
+    bytes: u64,
+    sites: HashMap<String, (u64, u64)>,
+}
+
+impl OpenSession {
+    fn new(at: DateTime<Utc>) -> Self {
+        OpenSession {
+            start: at,
+            end: at,
+            requests: 0,
+            bytes: 0,
+            sites: HashMap::new(),
+        }
+    }
+
+    fn close(self, zone: Zone) -> BrowsingSession {
+        let mut sites: Vec<SessionSite> = self
+            .sites
+            .into_iter()
+            .map(|(site, (requests, bytes))| SessionSite { site, requests, bytes })
+            .collect();
+        sites.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.site.cmp(&b.site)));
+        let site_count = sites.len();
+        sites.truncate(TOP_SITES);
+
+        BrowsingSession {
+            start: zone.at(self.start).to_rfc3339_opts(SecondsFormat::Secs, false),
+            end: zone.at(self.end).to_rfc3339_opts(SecondsFormat::Secs, false),
+            duration_seconds: (self.end - self.start).num_seconds().max(0) as u64,
+            requests: self.requests,
+            bytes: self.bytes,
+            site_count,
+            top_sites: sites,
+        }
+    }
+}
+
+/// A device's requests in a range, split into sessions at idle gaps
+pub fn sessions(device_id: &str, range: UsageRange) -> Result<DeviceSessions, String> {
+    let (start, end) = range.bounds();
+    let conn = open_readonly()?;
+    sessions_from(&conn, device_id, &start, &end, Zone::configured())
+}
+
+/// `sessions` against an open database, between two UTC query bounds
+pub fn sessions_from(conn: &Connection, device_id: &str, start: &str, end: &str, zone: Zone) -> Result<DeviceSessions, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT at, host, SUM(requests), SUM(bytes) FROM (
+                 SELECT substr(timestamp, 1, 19) AS at, host, 1 AS requests,
+                        COALESCE(request_size, 0) + COALESCE(response_size, 0) AS bytes
+                 FROM traffic
+                 WHERE timestamp >= ?1 AND timestamp < ?2 AND device_id = ?3 AND host IS NOT NULL
+                 UNION ALL
+                 SELECT minute, host, requests, bytes_in + bytes_out
+                 FROM traffic_aggregates
+                 WHERE minute >= substr(?1, 1, 16) AND minute < ?2 AND device_id = ?3 AND host IS NOT NULL
+             )
+             GROUP BY at, host
+             ORDER BY at",
+        )
+        .map_err(|e| format!("Failed to prepare session query: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, device_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<i64>>(2)?.unwrap_or(0) as u64,
+                row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+            ))
+        })
+        .map_err(|e| format!("Failed to query sessions: {}", e))?;
+
+    let mut sessions = Vec::new();
+    let mut current: Option<OpenSession> = None;
+    for row in rows {
+        let (at, host, requests, bytes) = row.map_err(|e| format!("Failed to read sessions: {}", e))?;
+        let Some(at) = clock::parse_stored(&at) else {
+            continue;
+        };
+        if host.is_empty() {
+            continue;
+        }
+
+        if current.as_ref().is_some_and(|s| at - s.end > SESSION_GAP) {
+            sessions.extend(current.take().map(|s| s.close(zone)));
+        }
+        let session = current.get_or_insert_with(|| OpenSession::new(at));
+        session.end = at;
+        session.requests += requests;
+        session.bytes += bytes;
+        let site = session.sites.entry(site_of(&host)).or_default();
+        site.0 += requests;
+        site.1 += bytes;
+    }
+    sessions.extend(current.map(|s| s.close(zone)));
+    sessions.reverse();
+
+    Ok(DeviceSessions {
+        device_id: device_id.to_string(),
+        start: start.to_string(),
+        end: end.to_string(),
+        gap_minutes: SESSION_GAP.num_minutes(),
+        total_duration_seconds: sessions.iter().map(|s| s.duration_seconds).sum(),
+        sessions,
+    })
+}
@@ -0,0 +1,195 @@
+// Cached snapshots of expensive queries, refreshed in the background
+
+use crate::commands::{fetch_devices, fetch_status, DashboardStats, Device, MonitoringStatus};
+use crate::state::AppState;
+use crate::stats_cache;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// How often the background task refreshes the snapshots
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Snapshots older than this are reported as stale
+const STALE_AFTER: Duration = Duration::from_secs(15);
+
+/// Forced refreshes within this window are served from the cache
+const MIN_FORCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A cached value together with the time it was captured
+struct Cached<T> {
+    data: T,
+    captured: Instant,
+    captured_at: DateTime<Utc>,
+}
+
+/// A cached value as returned to the frontend, with staleness metadata
+#[derive(Debug, Serialize)]
+pub struct Snapshot<T> {
+    pub data: T,
+    pub updated_at: String,
+    pub age_ms: u64,
+    pub is_stale: bool,
+}
+
+#[derive(Default)]
+pub struct SnapshotCache {
+    stats: Mutex<Option<Cached<DashboardStats>>>,
+    devices: Mutex<Option<Cached<Vec<Device>>>>,
+    status: Mutex<Option<Cached<MonitoringStatus>>>,
+}
+
+impl<T: Clone> Cached<T> {
+    fn new(data: T) -> Self {
+        Cached {
+            data,
+            captured: Instant::now(),
+            captured_at: Utc::now(),
+        }
+    }
+
+    fn to_snapshot(&self) -> Snapshot<T> {
+        let age = self.captured.elapsed();
+        Snapshot {
+            data: self.data.clone(),
+            updated_at: self.captured_at.to_rfc3339(),
+            age_ms: age.as_millis() as u64,
+            is_stale: age > STALE_AFTER,
+        }
+    }
+}
+
+/// The cached snapshot, unless it is empty or a (rate-limited) refresh is requested
+fn cached<T: Clone>(slot: &Mutex<Option<Cached<T>>>, force_refresh: bool) -> Option<Snapshot<T>> {
+    let cached = slot.lock().unwrap();
+    let c = cached.as_ref()?;
+    let debounced = c.captured.elapsed() < MIN_FORCE_INTERVAL;
+    (!force_refresh || debounced).then(|| c.to_snapshot())
+}
+
+fn store<T: Clone>(slot: &Mutex<Option<Cached<T>>>, data: T) -> Snapshot<T> {
+    let fresh = Cached::new(data);
+    let snapshot = fresh.to_snapshot();
+    *slot.lock().unwrap() = Some(fresh);
+    snapshot
+}
+
+/// Serve from the cache, fetching in place on a miss; for callers already off the async executor
+fn cached_or_fetch<T: Clone>(
+    slot: &Mutex<Option<Cached<T>>>,
+    force_refresh: bool,
+    fetch: impl FnOnce() -> Result<T, String>,
+) -> Result<Snapshot<T>, String> {
+    if let Some(snapshot) = cached(slot, force_refresh) {
+        return Ok(snapshot);
+    }
+    // Fetch without holding the lock so other readers aren't blocked on Python
+    Ok(store(slot, fetch()?))
+}
+
+/// Serve from the cache to a command, fetching on a blocking thread on a miss
+async fn cached_or_fetch_blocking<T: Clone + Send + 'static>(
+    slot: &Mutex<Option<Cached<T>>>,
+    force_refresh: bool,
+    fetch: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<Snapshot<T>, String> {
+    if let Some(snapshot) = cached(slot, force_refresh) {
+        return Ok(snapshot);
+    }
+    let data = tauri::async_runtime::spawn_blocking(fetch)
+        .await
+        .map_err(|e| format!("Failed to refresh snapshot: {}", e))??;
+    Ok(store(slot, data))
+}
+
+impl SnapshotCache {
+    pub fn stats(&self, force_refresh: bool) -> Result<Snapshot<DashboardStats>, String> {
+        cached_or_fetch(&self.stats, force_refresh, || stats_cache::current(&self.devices(false)?.data))
+    }
+
+    pub fn devices(&self, force_refresh: bool) -> Result<Snapshot<Vec<Device>>, String> {
+        cached_or_fetch(&self.devices, force_refresh, fetch_devices)
+    }
+
+    /// `devices` for async commands
+    pub async fn serve_devices(&self, force_refresh: bool) -> Result<Snapshot<Vec<Device>>, String> {
+        cached_or_fetch_blocking(&self.devices, force_refresh, fetch_devices).await
+    }
+
+    /// `stats` for async commands
+    pub async fn serve_stats(&self, app: AppHandle, force_refresh: bool) -> Result<Snapshot<DashboardStats>, String> {
+        cached_or_fetch_blocking(&self.stats, force_refresh, move || {
+            stats_cache::current(&app.state::<AppState>().snapshot.devices(false)?.data)
+        })
+        .await
+    }
+
+    /// The monitoring status for async commands
+    pub async fn serve_status(&self, app: AppHandle, force_refresh: bool) -> Result<Snapshot<MonitoringStatus>, String> {
+        // A start or stop since the snapshot was taken makes it wrong however young it is
+        let current = app.state::<AppState>().lifecycle.current();
+        {
+            let mut slot = self.status.lock().unwrap();
+            if slot.as_ref().is_some_and(|c| c.data.state != current) {
+                *slot = None;
+            }
+        }
+        cached_or_fetch_blocking(&self.status, force_refresh, move || Ok(fetch_status(&app.state::<AppState>())))
+            .await
+    }
+
+    /// Replace the cached device list (e.g. after a scan)
+    pub fn set_devices(&self, devices: Vec<Device>) {
+        *self.devices.lock().unwrap() = Some(Cached::new(devices));
+    }
+
+    /// Replace the cached stats (e.g. after a rebuild)
+    pub fn set_stats(&self, stats: DashboardStats) {
+        *self.stats.lock().unwrap() = Some(Cached::new(stats));
+    }
+
+    /// Drop the snapshots and the stats view so the next read fetches fresh data
+    pub fn clear(&self) {
+        *self.stats.lock().unwrap() = None;
+        *self.devices.lock().unwrap() = None;
+        *self.status.lock().unwrap() = None;
+        stats_cache::invalidate();
+    }
+
+    fn refresh(&self) {
+        let devices = match fetch_devices() {
+            Ok(devices) => {
+                self.set_devices(devices.clone());
+                devices
+            }
+            Err(e) => {
+                log::debug!("Device snapshot refresh failed: {}", e);
+                self.devices.lock().unwrap().as_ref().map(|c| c.data.clone()).unwrap_or_default()
+            }
+        };
+
+        match stats_cache::current(&devices) {
+            Ok(stats) => self.set_stats(stats),
+            Err(e) => log::debug!("Stats snapshot refresh failed: {}", e),
+        }
+    }
+}
+
+/// Spawn the background task that keeps the snapshots warm
+pub fn spawn_snapshot_refresher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                let state = handle.state::<AppState>();
+                state.snapshot.refresh();
+                store(&state.snapshot.status, fetch_status(&state));
+            })
+            .await;
+
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    });
+}
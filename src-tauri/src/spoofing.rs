@@ -0,0 +1,216 @@
+// ARP spoofing targets and safety checks
+//
+// By default every monitored device that is intercepted is spoofed. A target
+// selection narrows that to the chosen devices. The gateway and routers are
+// never spoofed whatever the selection says, since that would cut the whole
+// network off, and the ARP gateway also refuses its own address and the
+// gateway it detects. Spoofing only starts once IP forwarding is confirmed on,
+// as spoofed devices without forwarding lose their connection entirely.
+
+use crate::commands::{broadcast_to_processes, Device};
+use crate::interception::redirected;
+use crate::python::run_python_script;
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SpoofSelection {
+    /// Devices to spoof; every intercepted device when None
+    #[serde(default)]
+    pub device_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SpoofTarget {
+    pub device_id: String,
+    pub ip: String,
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ExcludedTarget {
+    pub device_id: String,
+    pub ip: String,
+    /// "gateway", "infrastructure", "not_selected" or "not_intercepted"
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SpoofTargets {
+    pub selection: SpoofSelection,
+    pub gateway_ip: Option<String>,
+    pub targets: Vec<SpoofTarget>,
+    pub excluded: Vec<ExcludedTarget>,
+}
+
+fn get_selection_path() -> PathBuf {
+    workspace::config_dir().join("spoof_targets.json")
+}
+
+pub fn load_selection() -> Result<SpoofSelection, String> {
+    let path = get_selection_path();
+    if !path.exists() {
+        return Ok(SpoofSelection::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read spoof targets: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse spoof targets: {}", e))
+}
+
+fn save_selection(selection: &SpoofSelection) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(selection)
+        .map_err(|e| format!("Failed to serialize spoof targets: {}", e))?;
+    fs::write(get_selection_path(), content)
+        .map_err(|e| format!("Failed to write spoof targets: {}", e))
+}
+
+/// The default gateway's IP, if it can be detected
+pub fn gateway_ip() -> Option<String> {
+    match run_python_script("python/utils/network_utils.py", &["--action", "get-gateway"]) {
+        Ok(result) => result.get("gateway_ip").and_then(|g| g.as_str()).map(str::to_string),
+        Err(e) => {
+            log::warn!("Failed to detect gateway: {}", e);
+            None
+        }
+    }
+}
+
+/// Why a device is left alone, or None if it is spoofed
+fn exclusion(device: &Device, selection: &SpoofSelection, gateway: Option<&str>) -> Option<&'static str> {
+    if Some(device.ip.as_str()) == gateway {
+        Some("gateway")
+    } else if device.device_type == "router" {
+        Some("infrastructure")
+    } else if !redirected(device) {
+        Some("not_intercepted")
+    } else if selection.device_ids.as_ref().is_some_and(|ids| !ids.contains(&device.id)) {
+        Some("not_selected")
+    } else {
+        None
+    }
+}
+
+/// Split devices into spoof targets and excluded devices
+pub fn resolve(devices: &[Device], selection: &SpoofSelection, gateway: Option<&str>) -> SpoofTargets {
+    let mut targets = Vec::new();
+    let mut excluded = Vec::new();
+    for device in devices.iter().filter(|d| !d.ip.is_empty()) {
+        match exclusion(device, selection, gateway) {
+            None => targets.push(SpoofTarget {
+                device_id: device.id.clone(),
+                ip: device.ip.clone(),
+                hostname: device.hostname.clone(),
+            }),
+            Some(reason) => excluded.push(ExcludedTarget {
+                device_id: device.id.clone(),
+                ip: device.ip.clone(),
+                reason: reason.to_string(),
+            }),
+        }
+    }
+
+    SpoofTargets {
+        selection: selection.clone(),
+        gateway_ip: gateway.map(str::to_string),
+        targets,
+        excluded,
+    }
+}
+
+/// Whether a device should be spoofed now, for live changes (the ARP gateway
+/// itself refuses the gateway)
+pub fn should_spoof(device: &Device) -> bool {
+    let selection = load_selection().unwrap_or_else(|e| {
+        log::warn!("Failed to load spoof targets, spoofing every intercepted device: {}", e);
+        SpoofSelection::default()
+    });
+    exclusion(device, &selection, None).is_none()
+}
+
+/// Extra ARP gateway arguments naming the devices to spoof and the infrastructure to leave alone
+pub fn arp_args(devices: &[Device]) -> Vec<String> {
+    let selection = load_selection().unwrap_or_else(|e| {
+        log::warn!("Failed to load spoof targets, spoofing every intercepted device: {}", e);
+        SpoofSelection::default()
+    });
+    let resolved = resolve(devices, &selection, gateway_ip().as_deref());
+
+    let mut args = Vec::new();
+    if !resolved.targets.is_empty() {
+        args.push("--targets".to_string());
+        args.extend(resolved.targets.into_iter().map(|t| t.ip));
+    }
+    let protected: Vec<String> = resolved
+        .excluded
+        .into_iter()
+        .filter(|e| e.reason == "gateway" || e.reason == "infrastructure")
+        .map(|e| e.ip)
+        .collect();
+    if !protected.is_empty() {
+        args.push("--protect".to_string());
+        args.extend(protected);
+    }
+    args
+}
+
+/// Enable IP forwarding on the interface, refusing to go on if it isn't in effect
+pub fn ensure_forwarding(interface: &str) -> Result<(), String> {
+    let result = run_python_script(
+        "python/utils/network_utils.py",
+        &["--action", "ensure-forwarding", "--interface", interface],
+    )?;
+    if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        return Err(error.to_string());
+    }
+
+    if result.get("ip_forwarding_enabled").and_then(|e| e.as_bool()).unwrap_or(false) {
+        Ok(())
+    } else {
+        let message = result.get("message").and_then(|m| m.as_str()).unwrap_or("unknown reason");
+        Err(format!(
+            "IP forwarding could not be enabled on '{}' ({}). Refusing to start ARP spoofing, which would cut the targeted devices off the network.",
+            interface, message
+        ))
+    }
+}
+
+/// Current targets and exclusions
+pub fn status(state: &AppState) -> Result<SpoofTargets, String> {
+    let devices = state.snapshot.devices(false)?.data;
+    Ok(resolve(&devices, &load_selection()?, gateway_ip().as_deref()))
+}
+
+/// Spoof only these devices (every intercepted device when None) and apply it to a running gateway
+pub fn set_targets(state: &AppState, device_ids: Option<Vec<String>>) -> Result<SpoofTargets, String> {
+    let devices = state.snapshot.devices(false)?.data;
+    if let Some(ids) = &device_ids {
+        if let Some(unknown) = ids.iter().find(|id| !devices.iter().any(|d| &d.id == *id)) {
+            return Err(format!("Device not found: {}", unknown));
+        }
+    }
+
+    let selection = SpoofSelection { device_ids };
+    save_selection(&selection)?;
+    let resolved = resolve(&devices, &selection, gateway_ip().as_deref());
+
+    broadcast_to_processes(state, &json!({
+        "action": "set_targets",
+        "targets": resolved
+            .targets
+            .iter()
+            .map(|t| json!({ "ip": t.ip, "hostname": t.hostname }))
+            .collect::<Vec<_>>(),
+    }));
+    log::info!(
+        "Spoofing {} devices ({} excluded)",
+        resolved.targets.len(),
+        resolved.excluded.len()
+    );
+    Ok(resolved)
+}
@@ -1,12 +1,43 @@
 // Application state management
 
+use crate::cert_server::CertServer;
+use crate::dhcp::DhcpMonitor;
+use crate::lifecycle::Lifecycle;
+use crate::operations::Operations;
+use crate::snapshot::SnapshotCache;
+use std::collections::HashMap;
 use std::process::Child;
+use std::sync::atomic::AtomicU64;
 use std::sync::Mutex;
 use std::time::Instant;
+use tauri::async_runtime::JoinHandle;
 
 pub struct AppState {
-    pub is_monitoring: Mutex<bool>,
+    pub lifecycle: Lifecycle,
     pub python_processes: Mutex<Vec<Child>>,
     pub current_profile: Mutex<String>,
     pub start_time: Mutex<Option<Instant>>,
+    pub traffic_subscriptions: Mutex<HashMap<String, JoinHandle<()>>>,
+    pub next_subscription_id: AtomicU64,
+    pub snapshot: SnapshotCache,
+    pub dhcp: DhcpMonitor,
+    pub operations: Operations,
+    pub cert_server: CertServer,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        AppState {
+            lifecycle: Lifecycle::default(),
+            python_processes: Mutex::new(Vec::new()),
+            current_profile: Mutex::new(String::from("hp_printer")),
+            start_time: Mutex::new(None),
+            traffic_subscriptions: Mutex::new(HashMap::new()),
+            next_subscription_id: AtomicU64::new(0),
+            snapshot: SnapshotCache::default(),
+            dhcp: DhcpMonitor::default(),
+            operations: Operations::default(),
+            cert_server: CertServer::default(),
+        }
+    }
 }
@@ -0,0 +1,402 @@
+// State snapshots for support
+//
+// When the window shows something odd on a user's machine, a state snapshot
+// captures what it was showing: the lifecycle state, the cached devices and
+// dashboard stats, recent traffic, DNS and alerts, the display settings, the
+// recent log and command timings. Everything that identifies the household is
+// replaced on the way out: MAC and IP addresses, hostnames, device IDs,
+// domains, tags and notes get stable placeholders (the same device is
+// "host-3" everywhere, in alert text and log lines included), URLs lose their
+// path and query, and the home directory is shortened to "~".
+//
+// A debug build imports a snapshot through demo mode, so the UI is answered
+// from it like from the generated household, with timestamps moved forward
+// so the snapshot was taken just now.
+
+use crate::clock;
+use crate::commands::{self, load_settings, save_settings, Alert, DashboardStats, Device, DnsQuery, TrafficEntry};
+use crate::demo::{self, DemoStatus, Replay};
+use crate::diagnostics;
+use crate::performance;
+use crate::state::AppState;
+use crate::workspace;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Format version written to snapshots
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Recent traffic entries and DNS queries kept
+const RECENT_ROWS: u32 = 500;
+
+/// Command timings kept
+const RECENT_CALLS: usize = 100;
+
+/// Settings that change what the window shows
+const DISPLAY_SETTINGS: &[&str] = &[
+    "theme",
+    "locale",
+    "timezone",
+    "ui_push",
+    "blocking_enabled",
+    "stealth_enabled",
+    "notifications_enabled",
+    "device_profile",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StateSnapshot {
+    pub version: u32,
+    /// UTC
+    pub taken_at: String,
+    pub app_version: String,
+    pub os: String,
+    /// "stopped", "starting", "running" or "stopping"
+    pub monitoring_state: String,
+    pub uptime: u64,
+    pub profile: String,
+    /// The user was looking at demo data
+    pub demo: bool,
+    pub settings: Value,
+    pub devices: Vec<Device>,
+    pub stats: Option<DashboardStats>,
+    pub traffic: Vec<TrafficEntry>,
+    pub dns: Vec<DnsQuery>,
+    pub alerts: Vec<Alert>,
+    pub logs: Vec<String>,
+    pub performance: Value,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SnapshotExport {
+    pub path: String,
+    pub size: u64,
+    pub devices: usize,
+    pub traffic: usize,
+    pub alerts: usize,
+}
+
+/// Stable placeholders for identifying values
+#[derive(Default)]
+pub struct Anonymizer {
+    replacements: HashMap<String, String>,
+    counts: HashMap<&'static str, u32>,
+}
+
+impl Anonymizer {
+    fn placeholder(&mut self, kind: &'static str, value: &str, make: impl FnOnce(u32) -> String) -> String {
+        if value.is_empty() {
+            return String::new();
+        }
+        if let Some(known) = self.replacements.get(value) {
+            return known.clone();
+        }
+        let count = self.counts.entry(kind).or_default();
+        *count += 1;
+        let replacement = make(*count);
+        self.replacements.insert(value.to_string(), replacement.clone());
+        replacement
+    }
+
+    /// A locally administered MAC
+    pub fn mac(&mut self, mac: &str) -> String {
+        self.placeholder("mac", mac, |n| format!("02:00:00:00:{:02x}:{:02x}", n >> 8 & 0xff, n & 0xff))
+    }
+
+    /// Private addresses become 10.0.x.y and public ones 198.18.x.y (a range never routed)
+    pub fn ip(&mut self, ip: &str) -> String {
+        match ip.parse::<IpAddr>() {
+            Ok(IpAddr::V4(v4)) if v4.is_private() || v4.is_loopback() || v4.is_link_local() => {
+                self.placeholder("private_ip", ip, |n| format!("10.0.{}.{}", n / 254, n % 254 + 1))
+            }
+            Ok(IpAddr::V4(_)) => self.placeholder("public_ip", ip, |n| format!("198.18.{}.{}", n / 254, n % 254 + 1)),
+            Ok(IpAddr::V6(_)) => self.placeholder("ipv6", ip, |n| format!("2001:db8::{:x}", n)),
+            Err(_) => self.domain(ip),
+        }
+    }
+
+    pub fn hostname(&mut self, hostname: &str) -> String {
+        self.placeholder("hostname", hostname, |n| format!("host-{}", n))
+    }
+
+    pub fn device_id(&mut self, id: &str) -> String {
+        self.placeholder("device", id, |n| format!("device-{}", n))
+    }
+
+    pub fn domain(&mut self, domain: &str) -> String {
+        self.placeholder("domain", domain, |n| format!("site-{}.example", n))
+    }
+
+    pub fn tag(&mut self, tag: &str) -> String {
+        self.placeholder("tag", tag, |n| format!("tag-{}", n))
+    }
+
+    /// A URL with an anonymized host and no path or query
+    pub fn url(&mut self, url: &str) -> String {
+        let (scheme, rest) = url.split_once("://").unwrap_or(("https", url));
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) && !host.contains(':') => (host, Some(port)),
+            _ => (authority, None),
+        };
+        let host = self.ip(host);
+        match port {
+            Some(port) => format!("{}://{}:{}/", scheme, host, port),
+            None => format!("{}://{}/", scheme, host),
+        }
+    }
+
+    /// Text with every value replaced so far swapped for its placeholder
+    pub fn text(&self, text: &str) -> String {
+        let mut known: Vec<(&String, &String)> = self.replacements.iter().collect();
+        // Longer values first, so "10.0.0.12" isn't rewritten as "10.0.0.1" plus a digit
+        known.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(b.0)));
+        let mut text = text.to_string();
+        for (value, replacement) in known {
+            if text.contains(value.as_str()) {
+                text = text.replace(value.as_str(), replacement);
+            }
+        }
+        if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+            let home = home.to_string_lossy();
+            if home.len() > 1 {
+                text = text.replace(home.as_ref(), "~");
+            }
+        }
+        text
+    }
+
+    pub fn device(&mut self, device: &Device) -> Device {
+        Device {
+            id: self.device_id(&device.id),
+            mac: self.mac(&device.mac),
+            ip: self.ip(&device.ip),
+            hostname: device.hostname.as_deref().map(|h| self.hostname(h)),
+            ..device.clone()
+        }
+    }
+
+    pub fn traffic(&mut self, entry: &TrafficEntry) -> TrafficEntry {
+        TrafficEntry {
+            device_id: entry.device_id.as_deref().map(|id| self.device_id(id)),
+            device_ip: self.ip(&entry.device_ip),
+            url: self.url(&entry.url),
+            host: self.ip(&entry.host),
+            path: entry.path.as_ref().map(|_| "/".to_string()),
+            tags: entry.tags.iter().map(|t| self.tag(t)).collect(),
+            note: None,
+            ..entry.clone()
+        }
+    }
+
+    pub fn dns(&mut self, query: &DnsQuery) -> DnsQuery {
+        DnsQuery {
+            device_id: query.device_id.as_deref().map(|id| self.device_id(id)),
+            device_ip: self.ip(&query.device_ip),
+            query_name: self.domain(query.query_name.trim_end_matches('.')),
+            response_ip: query.response_ip.as_deref().map(|ip| self.ip(ip)),
+            ..query.clone()
+        }
+    }
+
+    /// Alert text is rewritten with what's known, so devices and traffic go first
+    pub fn alert(&mut self, alert: &Alert) -> Alert {
+        let url = alert.url.as_deref().map(|u| self.url(u));
+        Alert {
+            device_id: alert.device_id.as_deref().map(|id| self.device_id(id)),
+            title: self.text(&alert.title),
+            description: self.text(&alert.description),
+            url,
+            ..alert.clone()
+        }
+    }
+
+    pub fn stats(&mut self, stats: &DashboardStats) -> DashboardStats {
+        let mut stats = stats.clone();
+        for top in &mut stats.top_domains {
+            top.domain = self.ip(&top.domain);
+        }
+        stats
+    }
+}
+
+/// Replace everything identifying in a snapshot
+pub fn anonymize(snapshot: StateSnapshot) -> StateSnapshot {
+    let mut anonymizer = Anonymizer::default();
+    let devices = snapshot.devices.iter().map(|d| anonymizer.device(d)).collect();
+    let traffic = snapshot.traffic.iter().map(|t| anonymizer.traffic(t)).collect();
+    let dns = snapshot.dns.iter().map(|q| anonymizer.dns(q)).collect();
+    let stats = snapshot.stats.as_ref().map(|s| anonymizer.stats(s));
+    let alerts = snapshot.alerts.iter().map(|a| anonymizer.alert(a)).collect();
+    let logs = snapshot.logs.iter().map(|line| anonymizer.text(line)).collect();
+    let performance = serde_json::from_str(&anonymizer.text(&snapshot.performance.to_string())).unwrap_or(Value::Null);
+
+    StateSnapshot {
+        devices,
+        traffic,
+        dns,
+        stats,
+        alerts,
+        logs,
+        performance,
+        ..snapshot
+    }
+}
+
+/// Capture what the window is showing, before anonymizing
+pub async fn capture(state: &AppState, app_version: &str) -> Result<StateSnapshot, String> {
+    let lifecycle = state.lifecycle.current();
+    let uptime = state.start_time.lock().unwrap().map(|t| t.elapsed().as_secs()).unwrap_or(0);
+    let profile = state.current_profile.lock().unwrap().clone();
+
+    let settings = serde_json::to_value(load_settings()?).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    let settings: serde_json::Map<String, Value> = DISPLAY_SETTINGS
+        .iter()
+        .filter_map(|key| settings.get(*key).map(|v| (key.to_string(), v.clone())))
+        .collect();
+
+    // Partial data still helps, so a part that can't be read is left empty
+    let devices = state.snapshot.devices(false).map(|s| s.data).unwrap_or_else(|e| {
+        log::warn!("State snapshot without devices: {}", e);
+        Vec::new()
+    });
+    let stats = state.snapshot.stats(false).map(|s| s.data).ok();
+    let traffic = commands::get_traffic(Some(RECENT_ROWS), None, None).await.unwrap_or_default();
+    let dns = commands::get_dns_queries(Some(RECENT_ROWS), None).await.unwrap_or_default();
+    let alerts = commands::get_alerts(None).await.unwrap_or_default();
+
+    Ok(StateSnapshot {
+        version: SNAPSHOT_VERSION,
+        taken_at: clock::now_utc(),
+        app_version: app_version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        monitoring_state: lifecycle.to_string(),
+        uptime,
+        profile,
+        demo: demo::status().enabled,
+        settings: Value::Object(settings),
+        devices,
+        stats,
+        traffic,
+        dns,
+        alerts,
+        logs: diagnostics::recent_logs(),
+        performance: json!(performance::metrics(RECENT_CALLS)),
+    })
+}
+
+/// Write an anonymized snapshot to `path`, or to `snapshots/` in the data directory
+pub async fn export(state: &AppState, app_version: &str, path: Option<&str>) -> Result<SnapshotExport, String> {
+    let snapshot = anonymize(capture(state, app_version).await?);
+
+    let path = match path {
+        Some(path) => Path::new(path).to_path_buf(),
+        None => {
+            let dir = workspace::data_dir().join("snapshots");
+            fs::create_dir_all(&dir).map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+            dir.join(format!("state_{}.json", chrono::Local::now().format("%Y%m%d_%H%M%S")))
+        }
+    };
+    let content =
+        serde_json::to_string_pretty(&snapshot).map_err(|e| format!("Failed to serialize state snapshot: {}", e))?;
+    fs::write(&path, &content).map_err(|e| format!("Failed to write state snapshot: {}", e))?;
+
+    log::info!("Wrote state snapshot to {}", path.display());
+    Ok(SnapshotExport {
+        path: path.to_string_lossy().to_string(),
+        size: content.len() as u64,
+        devices: snapshot.devices.len(),
+        traffic: snapshot.traffic.len(),
+        alerts: snapshot.alerts.len(),
+    })
+}
+
+/// A snapshot's data with every timestamp moved by `shift`, in UTC
+pub fn replay_data(snapshot: StateSnapshot, shift: chrono::Duration) -> Replay {
+    let moved = |timestamp: &str| match clock::parse_stored(timestamp) {
+        Some(at) => (at + shift).to_rfc3339_opts(chrono::SecondsFormat::Micros, false),
+        None => timestamp.to_string(),
+    };
+
+    let mut stats = snapshot.stats;
+    if let Some(stats) = stats.as_mut() {
+        for hour in &mut stats.traffic_by_hour {
+            hour.start = moved(&hour.start);
+        }
+    }
+    Replay {
+        taken_at: snapshot.taken_at,
+        devices: snapshot
+            .devices
+            .into_iter()
+            .map(|d| Device {
+                first_seen: moved(&d.first_seen),
+                last_seen: moved(&d.last_seen),
+                ..d
+            })
+            .collect(),
+        traffic: snapshot
+            .traffic
+            .into_iter()
+            .map(|t| TrafficEntry {
+                timestamp: moved(&t.timestamp),
+                ..t
+            })
+            .collect(),
+        dns: snapshot
+            .dns
+            .into_iter()
+            .map(|q| DnsQuery {
+                timestamp: moved(&q.timestamp),
+                ..q
+            })
+            .collect(),
+        alerts: snapshot
+            .alerts
+            .into_iter()
+            .map(|a| Alert {
+                timestamp: moved(&a.timestamp),
+                last_seen: moved(&a.last_seen),
+                ..a
+            })
+            .collect(),
+        stats,
+    }
+}
+
+/// Show a snapshot in place of the database; debug builds only
+pub fn import(state: &AppState, path: &str) -> Result<DemoStatus, String> {
+    if !cfg!(debug_assertions) {
+        return Err("State snapshots can only be imported in debug builds".to_string());
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read state snapshot: {}", e))?;
+    let snapshot: StateSnapshot =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse state snapshot: {}", e))?;
+    if snapshot.version > SNAPSHOT_VERSION {
+        return Err(format!("State snapshot version {} is newer than this build reads", snapshot.version));
+    }
+
+    let taken_at = clock::parse_stored(&snapshot.taken_at).ok_or_else(|| "State snapshot has no valid time".to_string())?;
+    log::info!(
+        "Importing state snapshot from {} ({} {}, monitoring {})",
+        snapshot.taken_at,
+        snapshot.os,
+        snapshot.app_version,
+        snapshot.monitoring_state
+    );
+    let status = demo::replay(replay_data(snapshot, Utc::now() - taken_at));
+
+    // A restart goes back to the database rather than to generated demo data
+    let mut settings = load_settings()?;
+    settings.demo_seed = None;
+    save_settings(&settings)?;
+
+    state.snapshot.clear();
+    Ok(status)
+}
@@ -0,0 +1,275 @@
+// Materialized dashboard stats
+//
+// Recomputing the dashboard means summing the whole traffic history, which
+// on a busy network takes longer than the snapshot refresh interval. Instead
+// the totals, request counts per host and traffic per minute of the last day
+// are kept here and brought up to date from the rows written since the last
+// refresh (past the highest rowid counted), so a refresh costs as much as the
+// new traffic and `get_stats` only reads. Minutes rather than hours are kept
+// so the chart can bucket them into hours of the configured zone, half-hour
+// offsets included.
+//
+// Rollups, retention and cleanup move and delete rows, so the view is dropped
+// after maintenance and rebuilt on the next refresh, as it is when a table
+// shrinks under it and on `rebuild_stats`.
+
+use crate::clock::{self, Zone};
+use crate::commands::{fetch_stats, DashboardStats, Device, HourlyTraffic, TopDomain};
+use crate::database::{self, open_readonly};
+use crate::demo;
+use chrono::{DateTime, Duration, Timelike, Utc};
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Domains listed on the dashboard
+const TOP_DOMAINS: usize = 10;
+
+/// Minutes kept for the hourly chart: a day, plus the hour it starts in
+const MINUTE_WINDOW_HOURS: i64 = 25;
+
+static VIEW: Mutex<Option<StatsView>> = Mutex::new(None);
+
+#[derive(Debug, Default, Clone, Copy)]
+struct MinuteTraffic {
+    requests: u64,
+    bytes_in: u64,
+    bytes_out: u64,
+}
+
+/// Running totals over the traffic tables
+#[derive(Debug, Default)]
+pub struct StatsView {
+    /// Highest traffic rowid and flow id counted
+    traffic_rowid: i64,
+    flow_id: i64,
+    requests: u64,
+    blocked: u64,
+    bytes_in: u64,
+    bytes_out: u64,
+    hosts: HashMap<String, u64>,
+    /// Traffic per UTC minute ("YYYY-MM-DDTHH:MM") in the chart window
+    minutes: BTreeMap<String, MinuteTraffic>,
+}
+
+fn window_start(now: DateTime<Utc>) -> String {
+    clock::utc_bound(now - Duration::hours(MINUTE_WINDOW_HOURS)).chars().take(16).collect()
+}
+
+impl StatsView {
+    /// Count everything in the database
+    pub fn build(conn: &Connection, now: DateTime<Utc>) -> Result<StatsView, String> {
+        let mut view = StatsView::default();
+
+        // Rolled-up traffic only changes in maintenance, after which the view is rebuilt
+        let mut stmt = conn
+            .prepare(
+                "SELECT minute, host, SUM(requests), SUM(bytes_in), SUM(bytes_out), SUM(blocked)
+                 FROM traffic_aggregates GROUP BY minute, host",
+            )
+            .map_err(|e| format!("Failed to prepare stats query: {}", e))?;
+        let since = window_start(now);
+        view.add_rows(&mut stmt, params![], &since)?;
+
+        view.apply_new(conn, now)?;
+        Ok(view)
+    }
+
+    /// Count (minute, host, requests, bytes in, bytes out, blocked) rows
+    fn add_rows(
+        &mut self,
+        stmt: &mut rusqlite::Statement,
+        params: impl rusqlite::Params,
+        since: &str,
+    ) -> Result<(), String> {
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?.unwrap_or(0) as u64,
+                    row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+                    row.get::<_, Option<i64>>(4)?.unwrap_or(0) as u64,
+                    row.get::<_, Option<i64>>(5)?.unwrap_or(0) as u64,
+                ))
+            })
+            .map_err(|e| format!("Failed to query traffic stats: {}", e))?;
+        for row in rows {
+            let (minute, host, requests, bytes_in, bytes_out, blocked) =
+                row.map_err(|e| format!("Failed to read traffic stats: {}", e))?;
+            self.requests += requests;
+            self.blocked += blocked;
+            self.bytes_in += bytes_in;
+            self.bytes_out += bytes_out;
+            *self.hosts.entry(host).or_default() += requests;
+            if minute.as_str() >= since {
+                let traffic = self.minutes.entry(minute).or_default();
+                traffic.requests += requests;
+                traffic.bytes_in += bytes_in;
+                traffic.bytes_out += bytes_out;
+            }
+        }
+        Ok(())
+    }
+
+    /// Count rows written since the last call; false when rows went missing and the view needs a rebuild
+    pub fn apply_new(&mut self, conn: &Connection, now: DateTime<Utc>) -> Result<bool, String> {
+        let max_id = |sql: &str| -> Result<i64, String> {
+            conn.query_row(sql, [], |row| row.get(0))
+                .map_err(|e| format!("Failed to query stats high-water mark: {}", e))
+        };
+        let traffic_rowid = max_id("SELECT COALESCE(MAX(rowid), 0) FROM traffic")?;
+        let flow_id = max_id("SELECT COALESCE(MAX(id), 0) FROM flows")?;
+        if traffic_rowid < self.traffic_rowid || flow_id < self.flow_id {
+            return Ok(false);
+        }
+
+        let since = window_start(now);
+        if traffic_rowid > self.traffic_rowid {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT substr(timestamp, 1, 16), host, COUNT(*), SUM(COALESCE(response_size, 0)),
+                            SUM(COALESCE(request_size, 0)), SUM(blocked)
+                     FROM traffic WHERE rowid > ?1 AND rowid <= ?2
+                     GROUP BY 1, host",
+                )
+                .map_err(|e| format!("Failed to prepare stats query: {}", e))?;
+            let counted = self.traffic_rowid;
+            self.add_rows(&mut stmt, params![counted, traffic_rowid], &since)?;
+            self.traffic_rowid = traffic_rowid;
+        }
+
+        // Non-web traffic the proxy never sees counts towards bandwidth
+        if flow_id > self.flow_id {
+            let (bytes_in, bytes_out): (Option<i64>, Option<i64>) = conn
+                .query_row(
+                    "SELECT SUM(bytes_in), SUM(bytes_out) FROM flows
+                     WHERE id > ?1 AND id <= ?2 AND protocol NOT IN ('http', 'https')",
+                    params![self.flow_id, flow_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map_err(|e| format!("Failed to query flow stats: {}", e))?;
+            self.bytes_in += bytes_in.unwrap_or(0) as u64;
+            self.bytes_out += bytes_out.unwrap_or(0) as u64;
+            self.flow_id = flow_id;
+        }
+
+        self.minutes = self.minutes.split_off(&since);
+        Ok(true)
+    }
+
+    /// Traffic totals, top domains and the last 24 hours of the zone, oldest first.
+    /// Device and alert counts are left for the caller.
+    pub fn dashboard(&self, zone: Zone, now: DateTime<Utc>) -> DashboardStats {
+        let mut top_domains: Vec<TopDomain> = self
+            .hosts
+            .iter()
+            .map(|(domain, count)| TopDomain {
+                domain: domain.clone(),
+                count: *count,
+            })
+            .collect();
+        top_domains.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.domain.cmp(&b.domain)));
+        top_domains.truncate(TOP_DOMAINS);
+
+        // Step back from the current local hour in UTC, where every hour is an hour
+        let local = zone.at(now);
+        let current = local
+            .with_minute(0)
+            .and_then(|t| t.with_second(0))
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(local)
+            .with_timezone(&Utc);
+        let first = current - Duration::hours(23);
+        let mut traffic_by_hour: Vec<HourlyTraffic> = (0..24)
+            .map(|i| {
+                let start = first + Duration::hours(i);
+                HourlyTraffic {
+                    hour: zone.at(start).hour(),
+                    start: zone.display(&clock::utc_bound(start)),
+                    requests: 0,
+                    bytes_in: 0,
+                    bytes_out: 0,
+                }
+            })
+            .collect();
+        for (minute, traffic) in &self.minutes {
+            let Some(at) = clock::parse_stored(minute).filter(|at| *at >= first) else { continue };
+            if let Some(hour) = traffic_by_hour.get_mut((at - first).num_hours() as usize) {
+                hour.requests += traffic.requests;
+                hour.bytes_in += traffic.bytes_in;
+                hour.bytes_out += traffic.bytes_out;
+            }
+        }
+
+        DashboardStats {
+            total_devices: 0,
+            online_devices: 0,
+            total_requests: self.requests,
+            blocked_requests: self.blocked,
+            total_alerts: 0,
+            unresolved_alerts: 0,
+            total_bandwidth: self.bytes_in + self.bytes_out,
+            bytes_in: self.bytes_in,
+            bytes_out: self.bytes_out,
+            top_domains,
+            traffic_by_hour,
+        }
+    }
+}
+
+/// Fill in what the view doesn't count
+fn with_counts(mut stats: DashboardStats, devices: &[Device]) -> DashboardStats {
+    let alerts = database::stored_alerts();
+    stats.total_devices = devices.len() as u32;
+    stats.online_devices = devices.iter().filter(|d| d.is_online).count() as u32;
+    stats.total_alerts = alerts.len() as u32;
+    stats.unresolved_alerts = alerts
+        .iter()
+        .filter(|a| !a.get("acknowledged").and_then(|b| b.as_bool()).unwrap_or(false))
+        .count() as u32;
+    stats
+}
+
+/// Dashboard stats, bringing the view up to date (or building it) first
+pub fn current(devices: &[Device]) -> Result<DashboardStats, String> {
+    if let Some(stats) = demo::stats() {
+        return Ok(stats);
+    }
+    // Before the database exists there is nothing to materialize
+    let Ok(conn) = open_readonly() else { return fetch_stats() };
+
+    let now = Utc::now();
+    let mut guard = VIEW.lock().unwrap_or_else(|e| e.into_inner());
+    let mut view = guard.take();
+    if let Some(existing) = view.as_mut() {
+        if !existing.apply_new(&conn, now)? {
+            view = None;
+        }
+    }
+    let view = match view {
+        Some(view) => view,
+        None => {
+            let started = Instant::now();
+            let view = StatsView::build(&conn, now)?;
+            log::info!("Built dashboard stats in {} ms", started.elapsed().as_millis());
+            view
+        }
+    };
+
+    let stats = view.dashboard(Zone::configured(), now);
+    *guard = Some(view);
+    Ok(with_counts(stats, devices))
+}
+
+/// Drop the view, so the next refresh recounts everything
+pub fn invalidate() {
+    *VIEW.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Recount everything now
+pub fn rebuild(devices: &[Device]) -> Result<DashboardStats, String> {
+    invalidate();
+    current(devices)
+}
@@ -0,0 +1,132 @@
+// Local network detection and scan ranges
+//
+// Device scans cover every network this machine is connected to (each
+// interface's subnet, VLAN interfaces included), found from the interface
+// configuration and routing table by `network_utils.py`, instead of a /24
+// around one address. Behind double NAT the outer router's network is added
+// too. Users can add CIDR ranges of their own (`scan_ranges` in settings);
+// ranges on a connected network are swept with ARP, routed ones are pinged.
+
+use crate::commands::{load_settings, save_settings};
+use crate::python::run_python_script;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+const SCRIPT: &str = "python/utils/network_utils.py";
+
+/// Largest manual range (a /16)
+const MIN_PREFIX: u8 = 16;
+
+const MAX_RANGES: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DetectedNetwork {
+    pub cidr: String,
+    /// Interface it is reached on; None for routed networks
+    pub interface: Option<String>,
+    /// This machine's address in it
+    pub address: Option<String>,
+    pub gateway: Option<String>,
+    pub vlan_id: Option<u16>,
+    /// What a scan covers, a /24 of networks too large to sweep
+    pub scan_cidr: String,
+    pub truncated: bool,
+    /// Not directly connected, so only pinged and without MAC addresses
+    pub routed: bool,
+    /// "detected", "upstream" (the outer router's network behind double NAT) or "manual"
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NetworkDetection {
+    pub networks: Vec<DetectedNetwork>,
+    /// None when traceroute couldn't tell
+    pub double_nat: Option<bool>,
+    pub upstream_gateway: Option<String>,
+}
+
+/// Parse a CIDR range and return it with the host bits cleared ("10.0.5.7/24" -> "10.0.5.0/24")
+pub fn normalize_range(range: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid range: {} (expected e.g. \"192.168.20.0/24\")", range);
+    let (address, prefix) = range.trim().split_once('/').ok_or_else(invalid)?;
+    let address: Ipv4Addr = address.parse().map_err(|_| invalid())?;
+    let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+    if prefix > 32 {
+        return Err(invalid());
+    }
+    if prefix < MIN_PREFIX {
+        return Err(format!("Range {} is too large to scan (at most a /{})", range, MIN_PREFIX));
+    }
+
+    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+    Ok(format!("{}/{}", Ipv4Addr::from(u32::from(address) & mask), prefix))
+}
+
+/// Normalize and de-duplicate the manual ranges before they are saved
+pub fn validate_ranges(ranges: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for range in ranges {
+        let range = normalize_range(range)?;
+        if !normalized.contains(&range) {
+            normalized.push(range);
+        }
+    }
+    if normalized.len() > MAX_RANGES {
+        return Err(format!("At most {} scan ranges can be added", MAX_RANGES));
+    }
+    Ok(normalized)
+}
+
+/// `--range` arguments for the manual ranges in settings
+pub fn range_args() -> Vec<String> {
+    let ranges = match load_settings() {
+        Ok(settings) => settings.scan_ranges,
+        Err(e) => {
+            log::warn!("Failed to load scan ranges, scanning detected networks only: {}", e);
+            vec![]
+        }
+    };
+    ranges.into_iter().flat_map(|range| ["--range".to_string(), range]).collect()
+}
+
+/// Every network a scan covers, manual ranges included
+pub fn detect() -> Result<NetworkDetection, String> {
+    let mut args = vec!["--action".to_string(), "detect-networks".to_string()];
+    args.extend(range_args());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = run_python_script(SCRIPT, &args)?;
+    if !result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        return Err(error.to_string());
+    }
+    serde_json::from_value(result).map_err(|e| format!("Failed to parse detected networks: {}", e))
+}
+
+pub fn add_range(range: &str) -> Result<Vec<String>, String> {
+    let range = normalize_range(range)?;
+    let mut settings = load_settings()?;
+    if settings.scan_ranges.contains(&range) {
+        return Ok(settings.scan_ranges);
+    }
+    settings.scan_ranges.push(range.clone());
+    settings.scan_ranges = validate_ranges(&settings.scan_ranges)?;
+    save_settings(&settings)?;
+
+    log::info!("Added scan range {}", range);
+    Ok(settings.scan_ranges)
+}
+
+pub fn remove_range(range: &str) -> Result<Vec<String>, String> {
+    let range = normalize_range(range)?;
+    let mut settings = load_settings()?;
+    let before = settings.scan_ranges.len();
+    settings.scan_ranges.retain(|r| r != &range);
+    if settings.scan_ranges.len() == before {
+        return Err(format!("Scan range not found: {}", range));
+    }
+    save_settings(&settings)?;
+
+    log::info!("Removed scan range {}", range);
+    Ok(settings.scan_ranges)
+}
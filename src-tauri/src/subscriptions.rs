@@ -0,0 +1,155 @@
+// Live traffic subscriptions (tail mode)
+//
+// Each subscription tails the traffic table and sends what matched its filter
+// as one event per `traffic_interval_ms`, holding at most `traffic_max_batch`
+// entries. During a traffic storm the newest entries win: older ones past the
+// batch limit are dropped, and a backlog of more than MAX_SCAN_ROWS rows is
+// skipped without being read, so the window never receives more than one
+// bounded event per interval. Each event says how many entries were left out.
+
+use crate::clock::Zone;
+use crate::commands::{load_settings, TrafficFilter, TrafficStreamEvent};
+use crate::database::{latest_traffic_rowid, open_readonly, traffic_after_rowid};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
+
+/// Event name used for pushing subscribed traffic to the frontend
+pub const TRAFFIC_STREAM_EVENT: &str = "traffic-stream";
+
+/// Most rows read from the database per tick; older unread rows are skipped
+const MAX_SCAN_ROWS: i64 = 5_000;
+
+/// How often a subscription rereads the push settings
+const SETTINGS_REFRESH: Duration = Duration::from_secs(5);
+
+/// Bounds of the configurable push interval and batch size
+const MIN_INTERVAL_MS: u64 = 100;
+const MAX_INTERVAL_MS: u64 = 10_000;
+const MAX_BATCH: usize = 5_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PushSettings {
+    /// How often each traffic subscription sends what arrived, in milliseconds
+    pub traffic_interval_ms: u64,
+    /// Most entries per traffic event; older ones beyond it are dropped
+    pub traffic_max_batch: usize,
+}
+
+impl Default for PushSettings {
+    fn default() -> Self {
+        PushSettings {
+            traffic_interval_ms: 500,
+            traffic_max_batch: 200,
+        }
+    }
+}
+
+pub fn validate(settings: &PushSettings) -> Result<(), String> {
+    if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&settings.traffic_interval_ms) {
+        return Err(format!(
+            "Traffic push interval must be between {} and {} ms",
+            MIN_INTERVAL_MS, MAX_INTERVAL_MS
+        ));
+    }
+    if !(1..=MAX_BATCH).contains(&settings.traffic_max_batch) {
+        return Err(format!("Traffic batch size must be between 1 and {}", MAX_BATCH));
+    }
+    Ok(())
+}
+
+fn push_settings() -> PushSettings {
+    load_settings().map(|s| s.ui_push).unwrap_or_default()
+}
+
+/// Spawn a background task that tails the traffic table and emits matching entries
+pub fn spawn_traffic_subscription(
+    app: AppHandle,
+    subscription_id: String,
+    filter: TrafficFilter,
+) -> JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        // Only stream entries that arrive after the subscription was created
+        let mut last_rowid: Option<i64> = None;
+        let mut push = push_settings();
+        let mut push_read = Instant::now();
+        // Entries left out since the last event
+        let mut dropped: u64 = 0;
+
+        loop {
+            tokio::time::sleep(Duration::from_millis(push.traffic_interval_ms)).await;
+            if push_read.elapsed() >= SETTINGS_REFRESH {
+                push = push_settings();
+                push_read = Instant::now();
+            }
+
+            // The database may not exist until the proxy writes its first entry
+            let conn = match open_readonly() {
+                Ok(conn) => conn,
+                Err(_) => continue,
+            };
+
+            let latest = match latest_traffic_rowid(&conn) {
+                Ok(rowid) => rowid,
+                Err(e) => {
+                    log::warn!("Subscription {}: {}", subscription_id, e);
+                    continue;
+                }
+            };
+            let Some(mut cursor) = last_rowid else {
+                last_rowid = Some(latest);
+                continue;
+            };
+
+            if latest - cursor > MAX_SCAN_ROWS {
+                let skipped = latest - MAX_SCAN_ROWS - cursor;
+                log::debug!("Subscription {}: skipping {} rows to keep up", subscription_id, skipped);
+                dropped += skipped as u64;
+                cursor = latest - MAX_SCAN_ROWS;
+            }
+
+            let rows = match traffic_after_rowid(&conn, cursor, MAX_SCAN_ROWS as u32) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    log::warn!("Subscription {}: {}", subscription_id, e);
+                    continue;
+                }
+            };
+
+            last_rowid = Some(rows.last().map_or(cursor, |(rowid, _)| *rowid));
+
+            let mut entries: Vec<_> = rows
+                .into_iter()
+                .map(|(_, entry)| entry)
+                .filter(|entry| filter.matches(entry))
+                .collect();
+
+            if entries.is_empty() {
+                continue;
+            }
+
+            // Keep the newest entries
+            let excess = entries.len().saturating_sub(push.traffic_max_batch);
+            entries.drain(..excess);
+            dropped += excess as u64;
+
+            let zone = Zone::configured();
+            for entry in &mut entries {
+                entry.timestamp = zone.display(&entry.timestamp);
+            }
+
+            let event = TrafficStreamEvent {
+                subscription_id: subscription_id.clone(),
+                entries,
+                dropped,
+            };
+
+            match app.emit(TRAFFIC_STREAM_EVENT, event) {
+                Ok(()) => dropped = 0,
+                Err(e) => log::warn!("Failed to emit traffic for {}: {}", subscription_id, e),
+            }
+        }
+    })
+}
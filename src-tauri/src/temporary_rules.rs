@@ -0,0 +1,57 @@
+// Expiry of temporary (time-boxed) block and allow rules
+//
+// The blocking engine keeps each rule's expiry; this task has it drop the rules
+// whose time is up and pushes the removals to the running proxy.
+
+use crate::commands::broadcast_to_processes;
+use crate::python::run_blocking_command;
+use crate::state::AppState;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often expired rules are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Proxy command that undoes an expired rule, if the proxy enforces that kind
+fn proxy_command(rule_type: &str, value: &str) -> Option<Value> {
+    match rule_type {
+        "domain" => Some(json!({ "action": "remove_block", "domain": value })),
+        "category" => Some(json!({ "action": "unblock_category", "category": value })),
+        "allow" => Some(json!({ "action": "disallow_domain", "domain": value })),
+        _ => None,
+    }
+}
+
+fn prune_expired(state: &AppState) -> Result<(), String> {
+    let result = run_blocking_command("prune-expired", &[])?;
+    let expired = result.get("expired").and_then(|e| e.as_array()).cloned().unwrap_or_default();
+
+    for rule in expired {
+        let rule_type = rule.get("rule_type").and_then(|t| t.as_str()).unwrap_or("");
+        let value = rule.get("value").and_then(|v| v.as_str()).unwrap_or("");
+        log::info!("Temporary {} rule for {} expired", rule_type, value);
+
+        if let Some(command) = proxy_command(rule_type, value) {
+            broadcast_to_processes(state, &command);
+        }
+    }
+    Ok(())
+}
+
+/// Spawn the background task that prunes expired rules
+pub fn spawn_rule_expiry(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = prune_expired(&handle.state::<AppState>()) {
+                    log::debug!("Pruning expired rules failed: {}", e);
+                }
+            })
+            .await;
+
+            tokio::time::sleep(PRUNE_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,204 @@
+// Test support: a mock script runner with canned output
+//
+// Commands reach Python only through `python::ScriptRunner`. Installing a
+// `MockScriptRunner` on the test's thread answers those calls from canned
+// JSON instead, keyed by script and `--action`, and records every call so a
+// test can check what would have been run. Calls nobody set up fail the same
+// way a missing script would. Work a call queues for another thread, like a
+// database write, takes the mock along.
+
+use crate::python::ScriptRunner;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+thread_local! {
+    static INSTALLED: RefCell<Option<Arc<MockScriptRunner>>> = const { RefCell::new(None) };
+}
+
+/// The mock installed on this thread, if any
+pub fn installed_runner() -> Option<Arc<dyn ScriptRunner>> {
+    INSTALLED.with(|installed| installed.borrow().clone().map(|mock| mock as Arc<dyn ScriptRunner>))
+}
+
+/// What a mocked script does when called
+#[derive(Clone)]
+enum Canned {
+    /// Print this JSON and exit
+    Output(Value),
+    /// Stream these records, then this end summary
+    Stream(Vec<Value>, Value),
+    /// Fail to run, with this error
+    Fail(String),
+    /// Start as a long-running process
+    Process,
+}
+
+struct Expectation {
+    script: String,
+    action: Option<String>,
+    canned: Canned,
+}
+
+/// A script call the mock received
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub script: String,
+    pub args: Vec<String>,
+}
+
+impl Call {
+    /// The value following `flag` in the arguments
+    pub fn arg(&self, flag: &str) -> Option<&str> {
+        let index = self.args.iter().position(|a| a == flag)?;
+        self.args.get(index + 1).map(String::as_str)
+    }
+}
+
+#[derive(Default)]
+pub struct MockScriptRunner {
+    expectations: Mutex<Vec<Expectation>>,
+    calls: Mutex<Vec<Call>>,
+}
+
+/// Keeps the mock installed until dropped
+pub struct MockGuard(Arc<MockScriptRunner>);
+
+impl std::ops::Deref for MockGuard {
+    type Target = MockScriptRunner;
+
+    fn deref(&self) -> &MockScriptRunner {
+        &self.0
+    }
+}
+
+impl Drop for MockGuard {
+    fn drop(&mut self) {
+        INSTALLED.with(|installed| installed.borrow_mut().take());
+    }
+}
+
+impl MockScriptRunner {
+    /// Route this thread's script calls to a fresh mock
+    pub fn install() -> MockGuard {
+        let mock = Arc::new(MockScriptRunner::default());
+        INSTALLED.with(|installed| *installed.borrow_mut() = Some(mock.clone()));
+        MockGuard(mock)
+    }
+
+    fn expect(&self, script: &str, action: Option<&str>, canned: Canned) -> &Self {
+        self.expectations.lock().unwrap().push(Expectation {
+            script: script.to_string(),
+            action: action.map(str::to_string),
+            canned,
+        });
+        self
+    }
+
+    /// `script --action <action>` prints `output`
+    pub fn on(&self, script: &str, action: &str, output: Value) -> &Self {
+        self.expect(script, Some(action), Canned::Output(output))
+    }
+
+    /// `script --action <action>` streams `records` and then `summary`
+    pub fn on_stream(&self, script: &str, action: &str, records: Vec<Value>, summary: Value) -> &Self {
+        self.expect(script, Some(action), Canned::Stream(records, summary))
+    }
+
+    /// `script --action <action>` can't be run
+    pub fn fail(&self, script: &str, action: &str, error: &str) -> &Self {
+        self.expect(script, Some(action), Canned::Fail(error.to_string()))
+    }
+
+    /// `script` can't be started
+    pub fn fail_start(&self, script: &str, error: &str) -> &Self {
+        self.expect(script, None, Canned::Fail(error.to_string()))
+    }
+
+    /// `script` starts as a background process, whatever its arguments
+    pub fn on_start(&self, script: &str) -> &Self {
+        self.expect(script, None, Canned::Process)
+    }
+
+    /// Every call so far, in order
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Calls to one script
+    pub fn calls_to(&self, script: &str) -> Vec<Call> {
+        self.calls().into_iter().filter(|c| c.script == script).collect()
+    }
+
+    fn answer(&self, script_path: &str, args: &[&str]) -> Result<Canned, String> {
+        let call = Call {
+            script: script_path.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        };
+        let action = call.arg("--action").map(str::to_string);
+        self.calls.lock().unwrap().push(call);
+
+        self.expectations
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.script == script_path && (e.action.is_none() || e.action == action))
+            .map(|e| e.canned.clone())
+            .ok_or_else(|| format!("Python script failed: no canned output for {} {:?}", script_path, args))
+    }
+}
+
+impl ScriptRunner for MockScriptRunner {
+    fn run(&self, script_path: &str, args: &[&str], _input: Option<&Value>) -> Result<Value, String> {
+        match self.answer(script_path, args)? {
+            Canned::Output(output) => Ok(output),
+            Canned::Stream(_, summary) => Ok(summary),
+            Canned::Fail(error) => Err(error),
+            Canned::Process => Err(format!("{} is a long-running script", script_path)),
+        }
+    }
+
+    fn stream(
+        &self,
+        script_path: &str,
+        args: &[&str],
+        on_record: &mut dyn FnMut(Value) -> Result<(), String>,
+    ) -> Result<Value, String> {
+        let (records, summary) = match self.answer(script_path, args)? {
+            Canned::Stream(records, summary) => (records, summary),
+            Canned::Output(output) => (vec![], output),
+            Canned::Fail(error) => return Err(error),
+            Canned::Process => return Err(format!("{} is a long-running script", script_path)),
+        };
+        for record in records {
+            on_record(record)?;
+        }
+
+        // Same handling of the summary as the real stream
+        if summary.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+            Ok(summary)
+        } else {
+            let error = summary.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+            Err(error.to_string())
+        }
+    }
+
+    fn start(&self, script_path: &str, args: &[&str]) -> Result<Child, String> {
+        match self.answer(script_path, args)? {
+            // A real child that exits straight away: the test binary listing its tests
+            Canned::Process => std::env::current_exe()
+                .and_then(|exe| {
+                    Command::new(exe)
+                        .arg("--list")
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .spawn()
+                })
+                .map_err(|e| e.to_string()),
+            Canned::Fail(error) => Err(error),
+            _ => Err(format!("{} is not a long-running script", script_path)),
+        }
+    }
+}
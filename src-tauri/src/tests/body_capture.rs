@@ -0,0 +1,66 @@
+use crate::body_capture::{self, BodyCaptureSettings};
+
+#[test]
+fn body_size_limit_is_checked_at_its_boundaries() {
+    let with_limit = |max_bytes| BodyCaptureSettings { max_bytes, ..BodyCaptureSettings::default() };
+    let limit = 50 * 1024 * 1024;
+
+    assert!(body_capture::validate(&with_limit(0)).is_err());
+    assert!(body_capture::validate(&with_limit(1)).is_ok());
+    assert!(body_capture::validate(&with_limit(limit)).is_ok());
+    assert!(body_capture::validate(&with_limit(limit + 1)).is_err());
+}
+
+#[test]
+fn content_type_rules_and_excluded_hosts_are_validated() {
+    let with_types = |types: &[&str]| BodyCaptureSettings {
+        skip_content_types: types.iter().map(|t| t.to_string()).collect(),
+        ..BodyCaptureSettings::default()
+    };
+    assert!(body_capture::validate(&with_types(&["image/*", "application/pdf", "application/vnd.ms-excel"])).is_ok());
+    for invalid in ["image", "*/*", "image/", "/png", "image/png; charset=utf-8"] {
+        assert!(body_capture::validate(&with_types(&[invalid])).is_err(), "{} was accepted", invalid);
+    }
+
+    let with_hosts = |hosts: &[&str]| BodyCaptureSettings {
+        excluded_hosts: hosts.iter().map(|h| h.to_string()).collect(),
+        ..BodyCaptureSettings::default()
+    };
+    assert!(body_capture::validate(&with_hosts(&["bank.example", "online-banking.example.co.uk"])).is_ok());
+    for invalid in ["", "https://bank.example", "bank..example", "bank.example/login"] {
+        assert!(body_capture::validate(&with_hosts(&[invalid])).is_err(), "{} was accepted", invalid);
+    }
+}
+
+#[test]
+fn proxy_args_carry_the_policy_whether_capture_is_on_or_off() {
+    let defaults = BodyCaptureSettings::default();
+    let args = body_capture::proxy_args(&defaults);
+    assert!(!args.contains(&"--no-bodies".to_string()));
+    assert_eq!(args[..2], ["--max-body-size", "1048576"]);
+    let skipped: Vec<&str> = args
+        .windows(2)
+        .filter(|pair| pair[0] == "--skip-body-type")
+        .map(|pair| pair[1].as_str())
+        .collect();
+    assert_eq!(skipped, ["image/*", "video/*", "audio/*", "font/*"]);
+
+    let disabled = BodyCaptureSettings {
+        enabled: false,
+        max_bytes: 4096,
+        skip_content_types: vec!["Application/PDF".to_string()],
+        excluded_hosts: vec!["Bank.Example".to_string()],
+    };
+    assert_eq!(
+        body_capture::proxy_args(&disabled),
+        [
+            "--no-bodies",
+            "--max-body-size",
+            "4096",
+            "--skip-body-type",
+            "application/pdf",
+            "--no-body-host",
+            "bank.example"
+        ]
+    );
+}
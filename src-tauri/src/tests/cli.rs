@@ -0,0 +1,55 @@
+use super::{fixture, DB_SCRIPT};
+use crate::cli::{self, CliCommand};
+use crate::testing::MockScriptRunner;
+use serde_json::json;
+
+#[test]
+fn cli_parses_subcommands_and_runs_them_like_the_gui() {
+    let args = |line: &str| -> Vec<String> { line.split_whitespace().map(str::to_string).collect() };
+
+    assert_eq!(cli::parse_args(&args("")), Ok(CliCommand::Help));
+    assert_eq!(cli::parse_args(&args("status")), Ok(CliCommand::Status));
+    assert_eq!(
+        cli::parse_args(&args("block tiktok.com --minutes 30")),
+        Ok(CliCommand::Block {
+            rule_type: "domain".to_string(),
+            value: "tiktok.com".to_string(),
+            minutes: Some(30),
+        })
+    );
+    assert_eq!(
+        cli::parse_args(&args("unblock gaming --type category")),
+        Ok(CliCommand::Unblock {
+            rule_type: "category".to_string(),
+            value: "gaming".to_string(),
+        })
+    );
+    assert!(cli::parse_args(&args("block")).is_err());
+    assert!(cli::parse_args(&args("traffic --limit ten")).is_err());
+    assert!(cli::parse_args(&args("traffic --search x --device y")).is_err());
+    assert!(cli::parse_args(&args("scan --fast yes")).is_err());
+    assert!(cli::parse_args(&args("reboot")).is_err());
+
+    let mock = MockScriptRunner::install();
+    let records = fixture(include_str!("fixtures/traffic.json")).as_array().unwrap().clone();
+    mock.on_stream(DB_SCRIPT, "traffic", records, json!({ "type": "end", "success": true, "count": 3 }));
+    mock.on_stream(
+        DB_SCRIPT,
+        "export",
+        vec![json!({ "type": "progress", "written": 2, "total": 2 })],
+        json!({ "type": "end", "success": true, "count": 2 }),
+    );
+
+    let traffic = cli::parse_args(&args("traffic --limit 5 --device dev_a483e7102030")).unwrap();
+    let entries = cli::execute(traffic).unwrap();
+    assert_eq!(entries.as_array().map(Vec::len), Some(2));
+
+    let export = cli::parse_args(&args("export /tmp/out.csv --format csv")).unwrap();
+    assert_eq!(cli::execute(export).unwrap()["count"], 2);
+
+    let calls = mock.calls_to(DB_SCRIPT);
+    assert_eq!(calls[0].arg("--limit"), Some("5"));
+    assert_eq!(calls[0].arg("--device"), Some("dev_a483e7102030"));
+    assert_eq!(calls[1].arg("--format"), Some("csv"));
+    assert_eq!(calls[1].arg("--output"), Some("/tmp/out.csv"));
+}
@@ -0,0 +1,171 @@
+use super::device;
+use crate::commands;
+use crate::geo;
+use crate::ip_conflicts;
+use crate::p2p;
+use crate::phone_home;
+
+#[test]
+fn p2p_activity_is_scored_from_ports_peers_and_trackers() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE flows (
+            start_time TEXT, end_time TEXT, device_id TEXT, device_ip TEXT, device_port INTEGER, remote_ip TEXT,
+            remote_port INTEGER, protocol TEXT, bytes_in INTEGER, bytes_out INTEGER
+        );
+        CREATE TABLE dns_queries (timestamp TEXT, device_id TEXT, device_ip TEXT, query_name TEXT);
+        INSERT INTO dns_queries VALUES
+            ('2026-01-05T10:00:00+00:00', 'laptop', '192.168.1.30', 'tracker.opentrackr.org.'),
+            ('2026-01-05T10:00:01+00:00', 'laptop', '192.168.1.30', 'router.bittorrent.com'),
+            ('2026-01-05T10:00:02+00:00', 'laptop', '192.168.1.30', 'mytorrentsite.example'),
+            ('2026-01-05T10:00:03+00:00', 'phone', '192.168.1.31', 'tracker.analytics.example');",
+    )
+    .unwrap();
+    // 120 peers on high ports, a few of them on the BitTorrent ports
+    for i in 0..120 {
+        let port = if i < 5 { 6881 } else { 40_000 + i };
+        conn.execute(
+            "INSERT INTO flows VALUES ('2026-01-05T10:01:00+00:00', '2026-01-05T10:02:00+00:00', 'laptop',
+             '192.168.1.30', 51413, ?1, ?2, CASE WHEN ?2 = 6881 THEN 'bittorrent' ELSE 'other' END, 1000, 500)",
+            rusqlite::params![format!("198.51.100.{}", i), port],
+        )
+        .unwrap();
+    }
+    // A busy browser talks to many servers, but on well-known ports
+    for i in 0..50 {
+        conn.execute(
+            "INSERT INTO flows VALUES ('2026-01-05T10:01:00+00:00', '2026-01-05T10:02:00+00:00', 'phone',
+             '192.168.1.31', 50000, ?1, 443, 'https', 1000, 500)",
+            [format!("203.0.113.{}", i)],
+        )
+        .unwrap();
+    }
+
+    let (flows, devices) = p2p::report_from(&conn, None, "2026-01-05T00:00:00+00:00").unwrap();
+    assert_eq!(flows, 170);
+    assert_eq!(devices.len(), 1);
+    let laptop = &devices[0];
+    assert_eq!(laptop.device, "laptop");
+    assert_eq!(laptop.peers, 120);
+    assert_eq!(laptop.bittorrent_flows, 5);
+    assert_eq!(laptop.trackers, ["bittorrent.com", "mytorrentsite.example", "opentrackr.org"]);
+    assert_eq!(laptop.bytes_in, 120_000);
+    assert_eq!(laptop.score, 85);
+    assert_eq!(laptop.reasons.len(), 3);
+
+    assert_eq!(p2p::tracker_domain("tracker.analytics.example"), None);
+}
+
+#[test]
+fn iot_phone_home_separates_idle_from_active_minutes() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE protocol_usage (minute TEXT, device_id TEXT, packets INTEGER, bytes_in INTEGER, bytes_out INTEGER);
+        CREATE TABLE traffic (timestamp TEXT, device_id TEXT, host TEXT, request_size INTEGER, response_size INTEGER);
+        CREATE TABLE traffic_aggregates (minute TEXT, device_id TEXT, host TEXT, requests INTEGER, bytes_in INTEGER, bytes_out INTEGER);
+        CREATE TABLE dns_queries (timestamp TEXT, device_id TEXT, query_name TEXT, response_ip TEXT);
+        CREATE TABLE flows (start_time TEXT, end_time TEXT, device_id TEXT, remote_ip TEXT, protocol TEXT, bytes_in INTEGER, bytes_out INTEGER);
+        -- 20:00 someone is streaming; 03:00 the TV is on its own
+        INSERT INTO protocol_usage VALUES
+            ('2026-01-05T20:00', 'tv', 900, 40000000, 200000),
+            ('2026-01-05T03:00', 'tv', 10, 6000, 2000);
+        INSERT INTO traffic VALUES
+            ('2026-01-05T20:00:10+00:00', 'tv', 'api.netflix.com', 500, 4000),
+            ('2026-01-05T03:00:05+00:00', 'tv', 'acr.samsungacr.com', 800, 200),
+            ('2026-01-05T03:00:06+00:00', 'tv', 'log.samsungacr.com', 900, 100);
+        INSERT INTO traffic_aggregates VALUES ('2026-01-04T23:00', 'tv', 'api.netflix.com', 1, 10, 10);
+        INSERT INTO dns_queries VALUES
+            ('2026-01-05T03:00:01+00:00', 'tv', 'ntp.samsungcloud.com', '198.51.100.9'),
+            ('2026-01-05T20:00:01+00:00', 'tv', 'nflxvideo.net', NULL);
+        INSERT INTO flows VALUES
+            ('2026-01-05T03:00:01+00:00', '2026-01-05T03:00:02+00:00', 'tv', '198.51.100.9', 'ntp', 90, 90),
+            ('2026-01-05T03:00:05+00:00', '2026-01-05T03:00:06+00:00', 'tv', '198.51.100.10', 'https', 300, 1700);",
+    )
+    .unwrap();
+    let tv = commands::Device {
+        hostname: Some("Living Room TV".to_string()),
+        vendor: Some("Samsung".to_string()),
+        ..device("tv", "192.168.1.40", "smart_tv")
+    };
+    assert!(phone_home::is_iot(&tv));
+
+    let report = phone_home::device_report(&conn, &tv, "2026-01-05T00:00:00+00:00", "2026-01-06T00:00:00+00:00").unwrap();
+    assert_eq!(report.name, "Living Room TV");
+    assert_eq!(report.active_minutes, 1);
+    assert_eq!(report.idle_minutes, 1);
+    assert_eq!(report.idle_bytes, 8000);
+    assert_eq!((report.requests, report.lookups), (3, 2));
+    // Both ACR requests and the NTP lookup came while nobody was watching
+    assert_eq!(report.idle_contacts, 3);
+    assert_eq!(report.idle_share, 0.6);
+    assert_eq!(report.unique_domains, 4);
+    assert_eq!(report.idle_domains, 2);
+    assert_eq!(report.domains[0].domain, "samsungacr.com");
+    assert_eq!(report.domains[0].requests, 2);
+    // NTP flow bytes reach the site through the lookup's answer
+    let ntp = report.domains.iter().find(|d| d.domain == "samsungcloud.com").unwrap();
+    assert_eq!(ntp.bytes, 180);
+
+    let ranges = geo::parse_ranges("198.51.100.0,198.51.100.255,nl\n2001:db8::,2001:db8::ffff,DE\nnot,a,range");
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(geo::country_in(&ranges, "198.51.100.9".parse().unwrap()), Some("NL"));
+    assert_eq!(geo::country_in(&ranges, "2001:db8::1".parse().unwrap()), Some("DE"));
+    assert_eq!(geo::country_in(&ranges, "203.0.113.1".parse().unwrap()), None);
+}
+
+#[test]
+fn ip_conflicts_and_flapping_leases_suggest_reservations() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE device_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, timestamp TEXT NOT NULL, device_id TEXT NOT NULL,
+            mac_address TEXT NOT NULL, event TEXT NOT NULL, old_value TEXT, new_value TEXT
+        );
+        INSERT INTO device_events (timestamp, device_id, mac_address, event, old_value, new_value) VALUES
+            ('2026-01-05T08:00:00+00:00', 'printer', '00:00:00:00:00:01', 'ip_conflict', '00:00:00:00:00:02', '192.168.1.50'),
+            ('2026-01-05T08:00:00+00:00', 'laptop', '00:00:00:00:00:02', 'ip_conflict', '00:00:00:00:00:01', '192.168.1.50'),
+            ('2026-01-06T09:00:00+00:00', 'printer', '00:00:00:00:00:01', 'ip_conflict', '00:00:00:00:00:09', '192.168.1.50'),
+            ('2026-01-05T10:00:00+00:00', 'phone', '00:00:00:00:00:03', 'ip_changed', '192.168.1.60', '192.168.1.61'),
+            ('2026-01-05T12:00:00+00:00', 'phone', '00:00:00:00:00:03', 'ip_changed', '192.168.1.61', '192.168.1.60'),
+            ('2026-01-06T10:00:00+00:00', 'phone', '00:00:00:00:00:03', 'ip_changed', '192.168.1.60', '192.168.1.62'),
+            ('2026-01-06T11:00:00+00:00', 'laptop', '00:00:00:00:00:02', 'ip_changed', '192.168.1.49', '192.168.1.50');",
+    )
+    .unwrap();
+    let since = "2026-01-01T00:00:00+00:00";
+
+    let conflicts = ip_conflicts::conflicts_from(&conn, since).unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].ip, "192.168.1.50");
+    assert_eq!(conflicts[0].macs, ["00:00:00:00:00:01", "00:00:00:00:00:02", "00:00:00:00:00:09"]);
+    assert_eq!(conflicts[0].device_ids, ["laptop", "printer"]);
+    assert_eq!(conflicts[0].days, 2);
+
+    // One change is an ordinary new lease; three in the window is flapping
+    let flapping = ip_conflicts::flapping_from(&conn, since).unwrap();
+    assert_eq!(flapping.len(), 1);
+    assert_eq!(flapping[0].changes, 3);
+    assert_eq!(flapping[0].addresses, ["192.168.1.60", "192.168.1.61", "192.168.1.62"]);
+
+    let devices = [
+        device("laptop", "192.168.1.50", "computer"),
+        device("printer", "192.168.1.50", "printer"),
+        device("phone", "192.168.1.62", "phone"),
+        device("tv", "192.168.1.70", "smart_tv"),
+        device("desktop", "192.168.1.80", "computer"),
+    ];
+    let reservations = ip_conflicts::suggest_reservations(&devices, &conflicts, &flapping);
+    let summary: Vec<(&str, Option<&str>, &str)> = reservations
+        .iter()
+        .map(|r| (r.device_id.as_str(), r.ip.as_deref(), r.reason.as_str()))
+        .collect();
+    // The printer keeps the contested address; the laptop gets a new one
+    assert_eq!(
+        summary,
+        [
+            ("laptop", None, "conflict"),
+            ("printer", Some("192.168.1.50"), "conflict"),
+            ("phone", Some("192.168.1.62"), "flapping"),
+            ("tv", Some("192.168.1.70"), "fixed_role"),
+        ]
+    );
+}
@@ -0,0 +1,88 @@
+use super::same_instant;
+use crate::{clock, domains};
+use serde_json::json;
+
+#[test]
+fn domain_details_cover_subdomains_and_bucket_days_in_the_configured_zone() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE traffic (
+            device_id TEXT, device_ip TEXT, host TEXT, category TEXT, timestamp TEXT,
+            request_size INTEGER, response_size INTEGER, blocked INTEGER
+        );
+        CREATE TABLE traffic_aggregates (
+            device_id TEXT, device_ip TEXT, host TEXT, category TEXT, minute TEXT,
+            requests INTEGER, bytes_in INTEGER, bytes_out INTEGER, blocked INTEGER
+        );
+        CREATE TABLE devices (id TEXT, hostname TEXT);
+        CREATE TABLE dns_queries (timestamp TEXT, query_name TEXT, response_ip TEXT);
+        -- 23:30 UTC is already the next day in Paris
+        INSERT INTO traffic VALUES
+            ('laptop', '192.168.1.30', 'www.mybank.com', 'finance', '2026-01-05T10:00:00+00:00', 100, 900, 0),
+            ('laptop', '192.168.1.30', 'mybank.com', 'finance', '2026-01-05T23:30:00+00:00', 50, 50, 0),
+            ('phone', '192.168.1.31', 'API.MyBank.com', NULL, '2026-01-05T11:00:00+00:00', 10, 10, 1),
+            ('laptop', '192.168.1.30', 'notmybank.com', 'finance', '2026-01-05T12:00:00+00:00', 1, 1, 0);
+        INSERT INTO traffic_aggregates VALUES
+            ('laptop', '192.168.1.30', 'www.mybank.com', 'finance', '2026-01-04T12:00', 3, 100, 200, 0);
+        INSERT INTO devices VALUES ('laptop', 'Work Laptop'), ('phone', '');
+        INSERT INTO dns_queries VALUES
+            ('2026-01-05T09:59:59+00:00', 'mybank.com.', '203.0.113.5'),
+            ('2026-01-05T23:29:59+00:00', 'www.mybank.com', '203.0.113.5'),
+            ('2026-01-05T10:59:59+00:00', 'api.mybank.com', NULL),
+            ('2026-01-05T11:59:59+00:00', 'notmybank.com', '198.51.100.1');",
+    )
+    .unwrap();
+    let alerts = vec![
+        json!({
+            "id": "a1", "timestamp": "2026-01-05T11:00:01+00:00", "severity": "high", "title": "Known phishing host",
+            "source_device": "phone", "domain": "api.mybank.com",
+            "metadata": {"threat_intel": true, "indicator": "api.mybank.com", "feeds": ["urlhaus"]}
+        }),
+        json!({"id": "a2", "timestamp": "2026-01-05T10:00:01+00:00", "severity": "low", "title": "New domain", "domain": "mybank.com"}),
+        json!({
+            "id": "a3", "timestamp": "2026-01-05T12:00:01+00:00", "severity": "high", "title": "Lookalike",
+            "domain": "notmybank.com", "metadata": {"threat_intel": true}
+        }),
+    ];
+
+    let zone = clock::Zone::Named(chrono_tz::Europe::Paris);
+    let details = domains::details_from(&conn, " MyBank.com. ", &alerts, zone).unwrap();
+
+    assert_eq!(details.domain, "mybank.com");
+    assert_eq!(details.total_requests, 6);
+    assert_eq!(details.total_bytes, 1420);
+    assert_eq!(details.blocked_requests, 1);
+    assert!(same_instant(details.first_seen.as_deref().unwrap(), "2026-01-04T12:00:00+00:00"));
+    assert!(same_instant(details.last_seen.as_deref().unwrap(), "2026-01-05T23:30:00+00:00"));
+
+    let hosts: Vec<(&str, u64)> = details.hosts.iter().map(|h| (h.host.as_str(), h.requests)).collect();
+    assert_eq!(hosts, [("www.mybank.com", 4), ("api.mybank.com", 1), ("mybank.com", 1)]);
+
+    let laptop = &details.devices[0];
+    assert_eq!(laptop.device_id.as_deref(), Some("laptop"));
+    assert_eq!(laptop.hostname.as_deref(), Some("Work Laptop"));
+    assert_eq!((laptop.requests, laptop.bytes, laptop.blocked), (5, 1400, 0));
+    let phone = &details.devices[1];
+    assert_eq!(phone.hostname, None);
+    assert_eq!((phone.requests, phone.bytes, phone.blocked), (1, 20, 1));
+
+    let timeline: Vec<(&str, u64)> = details.timeline.iter().map(|d| (d.date.as_str(), d.requests)).collect();
+    assert_eq!(timeline, [("2026-01-04", 3), ("2026-01-05", 2), ("2026-01-06", 1)]);
+
+    assert_eq!(details.categories.len(), 1);
+    assert_eq!((details.categories[0].category.as_str(), details.categories[0].requests), ("finance", 5));
+
+    assert_eq!(details.dns_lookups, 3);
+    assert_eq!(details.resolved_ips.len(), 1);
+    assert_eq!((details.resolved_ips[0].ip.as_str(), details.resolved_ips[0].lookups), ("203.0.113.5", 2));
+
+    assert_eq!(details.alert_count, 2);
+    assert_eq!(details.threat_hits.len(), 1);
+    let hit = &details.threat_hits[0];
+    assert_eq!(hit.alert_id, "a1");
+    assert_eq!(hit.device_id.as_deref(), Some("phone"));
+    assert_eq!(hit.indicator.as_deref(), Some("api.mybank.com"));
+    assert_eq!(hit.feeds, ["urlhaus"]);
+
+    assert!(domains::details_from(&conn, " . ", &alerts, zone).is_err());
+}
@@ -0,0 +1,198 @@
+use super::{fixture, ALERT_SCRIPT, DB_SCRIPT};
+use crate::access;
+use crate::api_keys::{self, ApiScope};
+use crate::bandwidth;
+use crate::commands;
+use crate::db_writer;
+use crate::proxy_config::{self, ProxySettings, UpstreamProxy};
+use crate::secrets;
+use crate::subscriptions::{self, PushSettings};
+use crate::testing::MockScriptRunner;
+use serde_json::json;
+use tauri::async_runtime::block_on;
+
+#[test]
+fn script_failures_propagate() {
+    let mock = MockScriptRunner::install();
+    mock.fail(ALERT_SCRIPT, "list", "Python script failed: Traceback (most recent call last)");
+
+    let error = block_on(commands::get_alerts(None)).unwrap_err();
+    assert_eq!(error, "Python script failed: Traceback (most recent call last)");
+}
+
+#[test]
+fn unsuccessful_responses_surface_their_error() {
+    let mock = MockScriptRunner::install();
+    mock.on(ALERT_SCRIPT, "acknowledge", json!({ "success": false, "error": "Alert not found" }));
+
+    let error = block_on(commands::mark_alert_read("alert_missing".to_string())).unwrap_err();
+    assert_eq!(error, "Alert not found");
+    assert_eq!(mock.calls_to(ALERT_SCRIPT)[0].arg("--id"), Some("alert_missing"));
+}
+
+#[test]
+fn unsuccessful_responses_without_a_message() {
+    let mock = MockScriptRunner::install();
+    mock.on(ALERT_SCRIPT, "acknowledge-all", json!({ "success": false }));
+
+    let error = block_on(commands::mark_all_alerts_read()).unwrap_err();
+    assert_eq!(error, "Unknown error");
+}
+
+#[test]
+fn stats_are_empty_before_the_database_exists() {
+    let mock = MockScriptRunner::install();
+    mock.on(DB_SCRIPT, "stats", json!({ "success": false, "error": "no such table: traffic" }));
+
+    let stats = commands::fetch_stats().unwrap();
+    assert_eq!(stats.total_devices, 0);
+    assert_eq!(stats.total_requests, 0);
+    assert!(stats.top_domains.is_empty());
+    assert!(stats.traffic_by_hour.is_empty());
+}
+
+#[test]
+fn stats_fail_when_the_script_cannot_run() {
+    let mock = MockScriptRunner::install();
+    mock.fail(DB_SCRIPT, "stats", "Failed to run Python script: No such file or directory");
+
+    assert!(commands::fetch_stats().is_err());
+}
+
+#[test]
+fn locked_writes_are_retried() {
+    let mock = MockScriptRunner::install();
+    mock.on(DB_SCRIPT, "update-device", json!({ "success": false, "error": "database is locked" }));
+
+    let error = block_on(commands::set_device_monitoring("dev_1".to_string(), false)).unwrap_err();
+    assert_eq!(error, "database is locked");
+    assert_eq!(mock.calls_to(DB_SCRIPT).len(), 5);
+}
+
+#[test]
+fn stream_errors_discard_partial_results() {
+    let mock = MockScriptRunner::install();
+    let records = fixture(include_str!("fixtures/traffic.json")).as_array().unwrap().clone();
+    mock.on_stream(DB_SCRIPT, "traffic", records, json!({ "type": "end", "success": false, "error": "database is locked" }));
+
+    let error = block_on(commands::get_traffic(None, None, None)).unwrap_err();
+    assert_eq!(error, "database is locked");
+}
+
+#[test]
+fn malformed_capabilities_are_reported() {
+    let mock = MockScriptRunner::install();
+    mock.on(
+        "python/utils/network_utils.py",
+        "validate-interface",
+        json!({ "success": true, "capabilities": { "name": "eth0", "exists": "yes" } }),
+    );
+
+    let error = commands::check_interface("eth0").unwrap_err();
+    assert!(error.starts_with("Failed to parse interface capabilities"), "{}", error);
+}
+
+#[test]
+fn malformed_proxy_settings_are_rejected() {
+    let upstream = |url: &str| ProxySettings {
+        upstream: Some(UpstreamProxy { url: url.to_string(), username: None, password: None, bypass: vec![] }),
+        ..ProxySettings::default()
+    };
+
+    assert!(proxy_config::validate(&upstream("http://proxy.lan:3128")).is_ok());
+    assert!(proxy_config::validate(&upstream("proxy.lan:3128")).is_err());
+    assert!(proxy_config::validate(&upstream("http://proxy.lan")).is_err());
+    assert!(proxy_config::validate(&upstream("socks5://proxy.lan:1080")).is_err());
+    assert!(proxy_config::validate(&ProxySettings { port: 8081, upstream: None }).is_err());
+}
+
+#[test]
+fn push_rates_outside_the_limits_are_rejected() {
+    assert!(subscriptions::validate(&PushSettings::default()).is_ok());
+    let too_fast = PushSettings { traffic_interval_ms: 10, ..PushSettings::default() };
+    assert!(subscriptions::validate(&too_fast).is_err());
+    let empty_batches = PushSettings { traffic_max_batch: 0, ..PushSettings::default() };
+    assert!(subscriptions::validate(&empty_batches).is_err());
+}
+
+#[test]
+fn bandwidth_limits_outside_the_bounds_are_rejected() {
+    assert!(bandwidth::validate_limit(5_000).is_ok());
+    assert!(bandwidth::validate_limit(0).is_err());
+    assert!(bandwidth::validate_limit(u32::MAX).is_err());
+}
+
+#[test]
+fn observer_tokens_only_allow_reading() {
+    for command in ["get_devices", "search_traffic", "subscribe_traffic"] {
+        assert!(access::is_read_only(command), "{} should be allowed", command);
+    }
+    for command in ["stop_monitoring", "add_block_rule", "update_settings", "revoke_observer_token"] {
+        assert!(!access::is_read_only(command), "{} should be refused", command);
+    }
+
+    let observer = access::create_token("Living room screen").unwrap();
+    assert!(access::authorize_daemon("status", Some(&observer.token)).is_ok());
+    assert!(access::authorize_daemon("stop", Some(&observer.token)).is_err());
+    assert!(access::authorize_daemon("stop", None).is_err());
+    assert!(access::authorize_daemon("status", None).is_err());
+    assert!(access::authorize_daemon("stop", Some("not-a-token")).is_err());
+    let owner = access::owner_token().unwrap();
+    assert_eq!(access::owner_token().unwrap(), owner);
+    assert!(access::authorize_daemon("stop", Some(&owner)).is_ok());
+
+    access::revoke_token(&observer.id).unwrap();
+    assert!(access::authorize_daemon("status", Some(&observer.token)).is_err());
+}
+
+#[test]
+fn api_keys_are_limited_to_their_scopes_and_rate() {
+    assert_eq!(api_keys::required_scope("get_alerts"), ApiScope::ReadOnly);
+    assert_eq!(api_keys::required_scope("status"), ApiScope::ReadOnly);
+    assert_eq!(api_keys::required_scope("resolve_alert"), ApiScope::AlertManagement);
+    assert_eq!(api_keys::required_scope("stop"), ApiScope::Admin);
+    assert_eq!(api_keys::required_scope("set_alert_sound"), ApiScope::Admin);
+
+    assert!(api_keys::create_key("Dashboard", vec![], None, None).is_err());
+    assert!(api_keys::create_key("Dashboard", vec![ApiScope::ReadOnly], Some("2020-01-01T00:00:00Z"), None).is_err());
+    assert!(api_keys::create_key("Dashboard", vec![ApiScope::ReadOnly], None, Some(0)).is_err());
+
+    let key = api_keys::create_key("Dashboard", vec![ApiScope::ReadOnly], None, Some(2)).unwrap();
+    assert!(api_keys::authenticate(&key.key, ApiScope::ReadOnly).is_ok());
+    assert!(api_keys::authenticate(&key.key, ApiScope::AlertManagement).is_err());
+    assert!(api_keys::authenticate(&key.key, ApiScope::ReadOnly).is_ok());
+    let error = api_keys::authenticate(&key.key, ApiScope::ReadOnly).unwrap_err();
+    assert!(error.contains("Rate limit"), "{}", error);
+
+    let listed = api_keys::list_keys().unwrap().into_iter().find(|k| k.id == key.id).unwrap();
+    assert!(listed.last_used_at.is_some());
+    assert_ne!(listed.key, key.key);
+
+    api_keys::revoke_key(&key.id).unwrap();
+    assert!(api_keys::authenticate(&key.key, ApiScope::ReadOnly).is_err());
+}
+
+#[test]
+fn unknown_secret_names_are_rejected() {
+    assert!(secrets::validate_name(secrets::SMTP_PASSWORD).is_ok());
+    let error = secrets::set_secret("smtp_pass", "hunter2").unwrap_err();
+    assert!(error.contains("Unknown secret: smtp_pass"), "{}", error);
+    assert!(secrets::get_secret("../settings").is_err());
+}
+
+#[test]
+fn database_writes_run_with_the_callers_runner() {
+    let mock = MockScriptRunner::install();
+    mock.on(DB_SCRIPT, "cleanup", json!({ "success": true, "deleted": 3 }));
+
+    // From an async command, and from plain blocking code
+    let cleaned = block_on(commands::cleanup_database(30)).unwrap();
+    assert_eq!(cleaned["deleted"], 3);
+    let cleaned = db_writer::write(DB_SCRIPT, &["--action", "cleanup", "--days", "7"]).unwrap();
+    assert_eq!(cleaned["deleted"], 3);
+    assert_eq!(mock.calls_to(DB_SCRIPT)[1].arg("--days"), Some("7"));
+
+    // Blocking calls inside async code don't stall the executor or panic
+    let result = block_on(async { db_writer::write(DB_SCRIPT, &["--action", "cleanup", "--days", "1"]) });
+    assert!(result.is_ok());
+}
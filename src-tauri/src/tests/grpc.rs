@@ -0,0 +1,44 @@
+use crate::grpc::{self, GrpcSettings};
+
+#[test]
+fn grpc_settings_need_tls_off_loopback() {
+    let mut settings = GrpcSettings {
+        enabled: true,
+        ..GrpcSettings::default()
+    };
+    assert!(grpc::validate(&settings).is_ok());
+
+    settings.bind_address = "0.0.0.0".to_string();
+    assert!(grpc::validate(&settings).unwrap_err().contains("TLS"));
+
+    // Only one half of the identity, or a file that isn't there
+    let cert = std::env::temp_dir().join(format!("grpc-cert-{}.pem", std::process::id()));
+    std::fs::write(&cert, "cert").unwrap();
+    settings.tls_cert_path = Some(cert.to_string_lossy().into_owned());
+    assert!(grpc::validate(&settings).is_err());
+    settings.tls_key_path = Some("/nonexistent/grpc-key.pem".to_string());
+    assert!(grpc::validate(&settings).unwrap_err().contains("not found"));
+    settings.tls_key_path = settings.tls_cert_path.clone();
+    assert!(grpc::validate(&settings).is_ok());
+    let _ = std::fs::remove_file(&cert);
+
+    settings.bind_address = "monitor.lan".to_string();
+    assert!(grpc::validate(&settings).is_err());
+}
+
+#[cfg(feature = "grpc")]
+#[test]
+fn grpc_rules_come_from_the_blocking_config() {
+    let config = json!({
+        "blocked_domains": ["tiktok.com"],
+        "blocked_categories": [{ "id": "gaming", "name": "Gaming", "severity": "low" }],
+        "blocked_keywords": ["vape"],
+        "whitelisted_domains": ["school.edu"]
+    });
+    let rules: Vec<(String, String)> = crate::grpc_server::rules_from_config(&config)
+        .into_iter()
+        .map(|r| (r.rule_type, r.value))
+        .collect();
+    let expected = [("domain", "tiktok.com"), ("category", "gaming"), ("keyword", "vape"), ("allow", "school.edu")];
+    assert_eq!(rules, expected.map(|(t, v)| (t.to_string(), v.to_string())));
+}
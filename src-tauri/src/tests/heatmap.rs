@@ -0,0 +1,46 @@
+use crate::{clock, heatmap};
+use chrono::NaiveDate;
+
+#[test]
+fn heatmap_buckets_by_local_day_and_hour_in_a_half_hour_zone() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE traffic (timestamp TEXT, device_id TEXT, request_size INTEGER, response_size INTEGER);
+        CREATE TABLE traffic_aggregates (minute TEXT, device_id TEXT, requests INTEGER, bytes_in INTEGER, bytes_out INTEGER);
+        INSERT INTO traffic VALUES
+            -- just before the range, local midnight starting it, and local midnight ending it
+            ('2026-01-04T18:29:59+00:00', 'laptop', 1, 1),
+            ('2026-01-04T18:30:00+00:00', 'laptop', 10, 20),
+            ('2026-01-06T18:30:00+00:00', 'laptop', 1, 1),
+            -- 15:15 local
+            ('2026-01-05T09:45:00+00:00', 'laptop', 40, 60),
+            ('2026-01-05T10:00:00+00:00', 'phone', 5, 5);
+        INSERT INTO traffic_aggregates VALUES
+            -- 15:59 and 16:00 local
+            ('2026-01-05T10:29', 'laptop', 4, 20, 20),
+            ('2026-01-05T10:30', 'laptop', 2, 5, 5);",
+    )
+    .unwrap();
+    let zone = clock::Zone::Named(chrono_tz::Asia::Kolkata);
+    let day = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let (start, end) = zone.utc_bounds(day(5), day(7));
+    assert_eq!((start.as_str(), end.as_str()), ("2026-01-04T18:30:00", "2026-01-06T18:30:00"));
+
+    let map = heatmap::heatmap_from(&conn, Some("laptop"), &start, &end, zone).unwrap();
+
+    let dates: Vec<&str> = map.days.iter().map(|d| d.date.as_str()).collect();
+    assert_eq!(dates, ["2026-01-05", "2026-01-06"]);
+    let busy: Vec<(usize, u64, u64)> = (0..24)
+        .filter(|&h| map.days[0].requests[h] > 0)
+        .map(|h| (h, map.days[0].requests[h], map.days[0].bytes[h]))
+        .collect();
+    assert_eq!(busy, [(0, 1, 30), (15, 5, 140), (16, 2, 10)]);
+    assert!(map.days[1].requests.iter().all(|&r| r == 0));
+
+    assert_eq!((map.total_requests, map.total_bytes), (8, 180));
+    assert_eq!((map.max_requests, map.max_bytes), (5, 140));
+
+    let everyone = heatmap::heatmap_from(&conn, None, &start, &end, zone).unwrap();
+    assert_eq!(everyone.total_requests, 9);
+    assert_eq!(everyone.days[0].requests[15], 6);
+}
@@ -0,0 +1,44 @@
+use super::device;
+use crate::intercept_exclusions::{self, InterceptExclusion};
+
+#[test]
+fn excluded_domains_are_normalized_before_they_are_stored() {
+    for (input, expected) in [
+        ("MyBank.com", "mybank.com"),
+        ("  mybank.com.  ", "mybank.com"),
+        ("https://Online.MyBank.com/login?next=/", "online.mybank.com"),
+        ("http://portal.clinic.org:8443/records#latest", "portal.clinic.org"),
+        ("clinic.org/", "clinic.org"),
+    ] {
+        assert_eq!(intercept_exclusions::normalize_domain(input).as_deref(), Ok(expected), "{}", input);
+    }
+
+    for input in ["", "   ", ".", "https://", "localhost", "my bank.com", "mybank..com", "https:///path"] {
+        assert!(intercept_exclusions::normalize_domain(input).is_err(), "{:?} was accepted", input);
+    }
+}
+
+#[test]
+fn device_exclusions_follow_the_device_ip() {
+    let exclusion = |domain: &str, device_id: Option<&str>| InterceptExclusion {
+        domain: domain.to_string(),
+        device_id: device_id.map(str::to_string),
+        created_at: String::new(),
+    };
+    let exclusions = [
+        exclusion("mybank.com", None),
+        exclusion("clinic.org", Some("phone")),
+        // Gone from the device list, or not given an address yet
+        exclusion("insurer.com", Some("old_tablet")),
+        exclusion("pharmacy.com", Some("new_laptop")),
+    ];
+    let devices = [device("phone", "192.168.1.20", "phone"), device("new_laptop", "", "computer")];
+
+    assert_eq!(
+        intercept_exclusions::resolve(&exclusions, &devices),
+        [
+            (None, "mybank.com".to_string()),
+            (Some("192.168.1.20".to_string()), "clinic.org".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,281 @@
+use super::fixture;
+use crate::commands::{begin_monitoring, end_monitoring};
+use crate::event_bus::{self, BusEvent};
+use crate::lifecycle::{LifecycleError, MonitoringState};
+use crate::operations::{self, OperationState, OperationStatus};
+use crate::proxy_config;
+use crate::state::AppState;
+use crate::testing::MockScriptRunner;
+use serde_json::json;
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::test::{mock_app, MockRuntime};
+use tauri::{App, Manager};
+
+const NETWORK_UTILS: &str = "python/utils/network_utils.py";
+const ARP_GATEWAY: &str = "python/arp/arp_gateway.py";
+const HTTPS_PROXY: &str = "python/https/transparent_proxy.py";
+const DNS_CAPTURE: &str = "python/dns/dns_capture.py";
+const BYPASS_DETECTOR: &str = "python/alerts/bypass_detector.py";
+const DNS_BLOCKER: &str = "python/dns/dns_blocker.py";
+const QUIC_BLOCKER: &str = "python/blocking/quic_blocker.py";
+const OVERHEAD_PROBE: &str = "python/https/overhead_probe.py";
+const BLOCKER: &str = "python/blocking/blocker.py";
+
+const CAPTURE_SCRIPTS: &[&str] = &[
+    ARP_GATEWAY,
+    HTTPS_PROXY,
+    DNS_CAPTURE,
+    BYPASS_DETECTOR,
+    DNS_BLOCKER,
+    QUIC_BLOCKER,
+    OVERHEAD_PROBE,
+];
+
+fn app() -> App<MockRuntime> {
+    let app = mock_app();
+    app.manage(AppState::default());
+    app
+}
+
+fn usable_interface(mock: &MockScriptRunner) {
+    mock.on(NETWORK_UTILS, "validate-interface", fixture(include_str!("fixtures/interface.json")));
+    mock.on(NETWORK_UTILS, "ensure-forwarding", json!({ "success": true, "ip_forwarding_enabled": true }));
+}
+
+#[test]
+fn start_then_stop() {
+    let mock = MockScriptRunner::install();
+    usable_interface(&mock);
+    for script in CAPTURE_SCRIPTS {
+        mock.on_start(script);
+    }
+    mock.on(BLOCKER, "config", json!({ "success": true, "config": { "whitelisted_domains": ["school.edu"] } }));
+    let app = app();
+    let state = app.state::<AppState>();
+
+    begin_monitoring(&state).unwrap();
+    assert_eq!(state.lifecycle.current(), MonitoringState::Running);
+    assert_eq!(state.python_processes.lock().unwrap().len(), CAPTURE_SCRIPTS.len());
+    assert!(state.start_time.lock().unwrap().is_some());
+
+    // Capture runs on the interface that was checked
+    let interface = mock.calls_to(NETWORK_UTILS)[0].arg("--interface").unwrap().to_string();
+    assert_eq!(mock.calls_to(ARP_GATEWAY)[0].arg("--interface"), Some(interface.as_str()));
+    assert_eq!(mock.calls_to(HTTPS_PROXY)[0].arg("--action"), Some("start"));
+    // Allowlisted domains are let through from the start
+    assert_eq!(mock.calls_to(HTTPS_PROXY)[0].arg("--allow"), Some("school.edu"));
+    // The overhead probe is told which probe port the proxy was given
+    assert!(mock.calls_to(OVERHEAD_PROBE)[0].arg("--proxy-port").is_some());
+
+    let error = begin_monitoring(&state).unwrap_err();
+    assert_eq!(error, LifecycleError::AlreadyRunning);
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({ "kind": "already_running", "message": "Monitoring is already running" })
+    );
+
+    end_monitoring(&state).unwrap();
+    assert_eq!(state.lifecycle.current(), MonitoringState::Stopped);
+    assert!(state.python_processes.lock().unwrap().is_empty());
+    assert!(state.start_time.lock().unwrap().is_none());
+
+    let error = end_monitoring(&state).unwrap_err();
+    assert_eq!(error, LifecycleError::AlreadyStopped);
+}
+
+#[test]
+fn optional_helpers_failing_to_start_dont_stop_capture() {
+    let mock = MockScriptRunner::install();
+    usable_interface(&mock);
+    let optional = [BYPASS_DETECTOR, QUIC_BLOCKER, OVERHEAD_PROBE];
+    for script in CAPTURE_SCRIPTS {
+        if optional.contains(script) {
+            mock.fail_start(script, "No such file or directory");
+        } else {
+            mock.on_start(script);
+        }
+    }
+    let app = app();
+    let state = app.state::<AppState>();
+
+    begin_monitoring(&state).unwrap();
+    assert_eq!(state.lifecycle.current(), MonitoringState::Running);
+    assert_eq!(
+        state.python_processes.lock().unwrap().len(),
+        CAPTURE_SCRIPTS.len() - optional.len()
+    );
+    end_monitoring(&state).unwrap();
+}
+
+#[test]
+fn failed_spawn_rolls_back() {
+    let mock = MockScriptRunner::install();
+    usable_interface(&mock);
+    mock.on_start(ARP_GATEWAY).on_start(HTTPS_PROXY);
+    mock.fail_start(DNS_CAPTURE, "No such file or directory");
+    let app = app();
+    let state = app.state::<AppState>();
+
+    let error = begin_monitoring(&state).unwrap_err();
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({ "kind": "failed", "message": "Failed to start DNS capture: No such file or directory" })
+    );
+    assert_eq!(state.lifecycle.current(), MonitoringState::Stopped);
+    assert!(state.python_processes.lock().unwrap().is_empty());
+    assert!(mock.calls_to(BYPASS_DETECTOR).is_empty());
+
+    // A failed start can be retried
+    let error = begin_monitoring(&state).unwrap_err().to_string();
+    assert!(error.starts_with("Failed to start DNS capture"), "{}", error);
+}
+
+#[test]
+fn unusable_interface_starts_nothing() {
+    let mock = MockScriptRunner::install();
+    let mut interface = fixture(include_str!("fixtures/interface.json"));
+    interface["capabilities"]["exists"] = json!(false);
+    mock.on(NETWORK_UTILS, "validate-interface", interface);
+    let app = app();
+    let state = app.state::<AppState>();
+
+    let error = begin_monitoring(&state).unwrap_err().to_string();
+    assert!(error.contains("does not exist"), "{}", error);
+    assert!(error.contains("eth0, wlan0"), "{}", error);
+    assert_eq!(state.lifecycle.current(), MonitoringState::Stopped);
+    for script in CAPTURE_SCRIPTS {
+        assert!(mock.calls_to(script).is_empty(), "{} was started", script);
+    }
+}
+
+#[test]
+fn missing_capture_driver_blocks_start() {
+    let mock = MockScriptRunner::install();
+    let mut interface = fixture(include_str!("fixtures/interface.json"));
+    interface["capabilities"]["capture_driver_ok"] = json!(false);
+    interface["capabilities"]["capture_driver"] = json!({
+        "required": true, "installed": false, "driver": null, "version": null, "winpcap_compatible": false,
+        "bundled_installer": null, "download_url": "https://npcap.com/dist/npcap-1.79.exe"
+    });
+    mock.on(NETWORK_UTILS, "validate-interface", interface);
+    let app = app();
+    let state = app.state::<AppState>();
+
+    let error = begin_monitoring(&state).unwrap_err().to_string();
+    assert!(error.contains("Install capture driver"), "{}", error);
+    assert_eq!(state.lifecycle.current(), MonitoringState::Stopped);
+    for script in CAPTURE_SCRIPTS {
+        assert!(mock.calls_to(script).is_empty(), "{} was started", script);
+    }
+}
+
+#[test]
+fn forwarding_off_refuses_to_spoof() {
+    let mock = MockScriptRunner::install();
+    mock.on(NETWORK_UTILS, "validate-interface", fixture(include_str!("fixtures/interface.json")));
+    mock.on(NETWORK_UTILS, "ensure-forwarding", json!({
+        "success": true,
+        "ip_forwarding_enabled": false,
+        "message": "Registry error: access denied"
+    }));
+    let app = app();
+    let state = app.state::<AppState>();
+
+    let error = begin_monitoring(&state).unwrap_err().to_string();
+    assert!(error.contains("Refusing to start ARP spoofing"), "{}", error);
+    assert_eq!(state.lifecycle.current(), MonitoringState::Stopped);
+    for script in CAPTURE_SCRIPTS {
+        assert!(mock.calls_to(script).is_empty(), "{} was started", script);
+    }
+}
+
+#[test]
+fn interface_check_failure_returns_to_stopped() {
+    let mock = MockScriptRunner::install();
+    mock.fail(NETWORK_UTILS, "validate-interface", "Python script failed: ModuleNotFoundError: No module named 'psutil'");
+    let app = app();
+    let state = app.state::<AppState>();
+
+    let error = begin_monitoring(&state).unwrap_err().to_string();
+    assert!(error.contains("psutil"), "{}", error);
+    assert_eq!(state.lifecycle.current(), MonitoringState::Stopped);
+}
+
+/// Poll an operation until it leaves Running
+fn finished(state: &AppState, id: &str) -> OperationStatus {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let status = state.operations.status(id).unwrap();
+        if status.state != OperationState::Running || Instant::now() > deadline {
+            return status;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn operations_complete_or_stay_cancelled() {
+    let app = app();
+    let state = app.state::<AppState>();
+
+    let done = operations::start(app.handle(), "export", |_| Ok(json!({ "records": 3 })));
+    let status = finished(&state, &done);
+    assert_eq!(status.state, OperationState::Completed);
+    assert_eq!(status.result, Some(json!({ "records": 3 })));
+    assert!(status.finished_at.is_some());
+
+    // Work that outlives its cancellation has its result discarded
+    let (release, wait) = mpsc::channel::<()>();
+    let (seen_tx, seen) = mpsc::channel();
+    let slow = operations::start(app.handle(), "device_scan", move |progress| {
+        let _ = wait.recv();
+        seen_tx.send(progress.is_cancelled()).unwrap();
+        Ok(json!([]))
+    });
+    assert_ne!(slow, done);
+    assert_eq!(operations::cancel(app.handle(), &slow).unwrap().state, OperationState::Cancelled);
+    release.send(()).unwrap();
+    assert!(seen.recv_timeout(Duration::from_secs(5)).unwrap());
+    std::thread::sleep(Duration::from_millis(50));
+    let status = state.operations.status(&slow).unwrap();
+    assert_eq!(status.state, OperationState::Cancelled);
+    assert!(status.result.is_none());
+
+    assert!(operations::cancel(app.handle(), "op-missing").is_err());
+}
+
+#[test]
+fn busy_proxy_ports_fall_back_to_free_ones() {
+    let busy = TcpListener::bind(("0.0.0.0", 0)).unwrap();
+    let port = busy.local_addr().unwrap().port();
+
+    let ports = proxy_config::pick_ports(port).unwrap();
+    assert_ne!(ports.listen, port);
+    assert!(ports.listen > port);
+    assert_ne!(ports.probe, ports.listen);
+}
+
+#[test]
+fn transitions_and_raised_alerts_go_through_the_bus() {
+    let mut events = event_bus::subscribe();
+    let app = app();
+    let state = app.state::<AppState>();
+    state.lifecycle.transition(MonitoringState::Starting).unwrap();
+    event_bus::raise_alert("high", "security", "Bus test alert", "Raised by a test", Some("dev_bus"), None);
+
+    let mut saw_transition = false;
+    let mut saw_alert = false;
+    while let Ok(event) = events.try_recv() {
+        match event {
+            BusEvent::Lifecycle(t) if t.to == MonitoringState::Starting => saw_transition = true,
+            BusEvent::AlertRaised(alert) if alert.title == "Bus test alert" => {
+                assert_eq!(alert.device_id.as_deref(), Some("dev_bus"));
+                saw_alert = true;
+            }
+            _ => {}
+        }
+    }
+    assert!(saw_transition && saw_alert);
+}
@@ -0,0 +1,52 @@
+// Command tests against canned script output
+//
+// Each test installs a `MockScriptRunner`, so commands run end to end -
+// argument building, JSON parsing, lifecycle transitions and error handling -
+// without Python, root or a network. Fixtures under `fixtures/` are captured
+// from the Python side's real output.
+
+mod body_capture;
+mod cli;
+mod device_reports;
+mod domains;
+mod errors;
+mod grpc;
+mod heatmap;
+mod intercept_exclusions;
+mod lifecycle;
+mod notifications;
+mod parsing;
+mod protocols;
+mod sessions;
+mod state_snapshot;
+mod usage_cost;
+mod users;
+mod watch_lists;
+mod widgets;
+
+use crate::clock;
+use crate::commands::Device;
+use serde_json::{json, Value};
+
+const DB_SCRIPT: &str = "python/database/db_manager.py";
+const ALERT_SCRIPT: &str = "python/alerts/alert_engine.py";
+
+fn fixture(text: &str) -> Value {
+    serde_json::from_str(text).expect("fixture is valid JSON")
+}
+
+/// An online, monitored device named after its id, with no traffic counted yet
+fn device(id: &str, ip: &str, device_type: &str) -> Device {
+    serde_json::from_value(json!({
+        "id": id, "mac": format!("mac-{}", id), "ip": ip, "hostname": id, "vendor": null,
+        "device_type": device_type, "first_seen": "", "last_seen": "", "is_online": true,
+        "is_monitored": true, "has_certificate": false, "total_bytes": 0, "bytes_in": 0, "bytes_out": 0,
+        "blocked_requests": 0, "alerts": 0
+    }))
+    .expect("device is valid")
+}
+
+/// Whether a displayed timestamp is the same instant as a stored one
+fn same_instant(displayed: &str, stored: &str) -> bool {
+    clock::parse_stored(displayed).is_some() && clock::parse_stored(displayed) == clock::parse_stored(stored)
+}
@@ -0,0 +1,53 @@
+use crate::notifications::{self, AlertSound};
+use crate::testing::MockScriptRunner;
+use serde_json::json;
+
+#[test]
+fn quiet_hours_and_alert_sounds_go_through_the_notifier() {
+    const NOTIFIER_SCRIPT: &str = "python/alerts/notifier.py";
+    let sound = json!({ "enabled": true, "sound": null, "volume": 1.0 });
+    let status = json!({
+        "success": true,
+        "status": {
+            "enabled": true,
+            "sound_enabled": true,
+            "min_severity": "medium",
+            "sounds": { "low": sound, "medium": sound, "high": { "enabled": false, "sound": null, "volume": 0.5 }, "critical": sound },
+            "quiet_hours": { "start": 23, "end": 7, "active": false },
+            "hourly_count": 0
+        }
+    });
+    let mock = MockScriptRunner::install();
+    mock.on(NOTIFIER_SCRIPT, "quiet-hours", status.clone());
+    mock.on(NOTIFIER_SCRIPT, "sound", status);
+    mock.on(
+        NOTIFIER_SCRIPT,
+        "preview-sound",
+        json!({ "success": true, "preview": { "severity": "critical", "enabled": true, "sound": null, "volume": 1.0, "played": true } }),
+    );
+
+    let settings = notifications::set_quiet_hours(true, None, None).unwrap();
+    assert_eq!((settings.quiet_hours.start, settings.quiet_hours.end), (Some(23), Some(7)));
+    assert_eq!(settings.sounds["high"].volume, 0.5);
+    notifications::set_quiet_hours(false, None, None).unwrap();
+
+    let muted = AlertSound { enabled: false, sound: None, volume: 0.5 };
+    notifications::set_alert_sound("high", &muted).unwrap();
+    let preview = notifications::preview("critical").unwrap();
+    assert!(preview.played && preview.sound.enabled);
+
+    let calls = mock.calls_to(NOTIFIER_SCRIPT);
+    assert_eq!(calls.len(), 4);
+    assert_eq!((calls[0].arg("--enabled"), calls[0].arg("--start")), (Some("true"), None));
+    assert_eq!(calls[1].arg("--enabled"), Some("false"));
+    assert_eq!(calls[2].arg("--severity"), Some("high"));
+    assert_eq!(serde_json::from_str::<AlertSound>(calls[2].arg("--value").unwrap()).unwrap(), muted);
+
+    // Rejected before the notifier runs
+    assert!(notifications::set_quiet_hours(true, Some(24), Some(7)).is_err());
+    assert!(notifications::set_quiet_hours(true, Some(7), Some(7)).is_err());
+    assert!(notifications::set_alert_sound("urgent", &muted).is_err());
+    assert!(notifications::set_alert_sound("low", &AlertSound { volume: 1.5, ..muted.clone() }).is_err());
+    assert!(notifications::preview("loud").is_err());
+    assert_eq!(mock.calls_to(NOTIFIER_SCRIPT).len(), 4);
+}
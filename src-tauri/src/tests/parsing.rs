@@ -0,0 +1,637 @@
+use super::{fixture, same_instant, ALERT_SCRIPT, DB_SCRIPT};
+use crate::advisories;
+use crate::alert_evidence;
+use crate::annotations;
+use crate::cert_guide::{platform_from_user_agent, Platform};
+use crate::database;
+use crate::clock;
+use crate::commands;
+use crate::diagnostics;
+use crate::discovery::{self, DeviceService};
+use crate::enrichment::{self, Kind};
+use crate::flows;
+use crate::interception::InterceptionMode;
+use crate::inventory;
+use crate::investigations::{self, EvidenceKind};
+use crate::media;
+use crate::os_detection::{self, Source};
+use crate::parquet_export;
+use crate::performance::{self, CallKind};
+use crate::proxy_config::UpstreamProxy;
+use crate::python::script_invocation;
+use crate::qr::{self, QrFormat};
+use crate::stats_cache;
+use crate::subnets;
+use crate::testing::MockScriptRunner;
+use serde_json::json;
+use tauri::async_runtime::block_on;
+
+#[test]
+fn devices_accept_current_and_legacy_keys() {
+    let mock = MockScriptRunner::install();
+    mock.on(DB_SCRIPT, "devices", fixture(include_str!("fixtures/devices.json")));
+
+    let devices = commands::fetch_devices().unwrap();
+
+    // The entry without an id is skipped
+    assert_eq!(devices.len(), 2);
+
+    let phone = &devices[0];
+    assert_eq!(phone.mac, "a4:83:e7:10:20:30");
+    assert_eq!(phone.ip, "192.168.1.20");
+    assert_eq!(phone.vendor.as_deref(), Some("Apple"));
+    assert_eq!(phone.device_type, "phone");
+    assert_eq!(phone.total_bytes, 52_428_800);
+    assert!(phone.has_certificate);
+    assert_eq!(phone.interception_mode, InterceptionMode::MetadataOnly);
+    assert!(same_instant(&phone.last_seen, "2024-03-10T18:30:00+00:00"));
+
+    let legacy = &devices[1];
+    assert_eq!(legacy.mac, "f8:bc:12:aa:bb:cc");
+    assert_eq!(legacy.ip, "192.168.1.21");
+    assert_eq!(legacy.vendor.as_deref(), Some("Dell"));
+    assert_eq!(legacy.hostname, None);
+    assert_eq!(legacy.device_type, "unknown");
+    assert!(legacy.is_monitored);
+    assert_eq!(legacy.interception_mode, InterceptionMode::Full);
+    assert!(!legacy.is_online);
+    // Naive timestamps from before UTC storage are read as UTC
+    assert!(same_instant(&legacy.first_seen, "2024-03-01T09:00:00+00:00"));
+    assert_eq!(legacy.last_seen, "");
+}
+
+#[test]
+fn alerts_fill_in_defaults_and_skip_incomplete_entries() {
+    let mock = MockScriptRunner::install();
+    mock.on(ALERT_SCRIPT, "list", fixture(include_str!("fixtures/alerts.json")));
+
+    let alerts = block_on(commands::get_alerts(None)).unwrap();
+    assert_eq!(alerts.len(), 2);
+
+    let keyword = &alerts[0];
+    assert_eq!(keyword.device_id.as_deref(), Some("dev_a483e7102030"));
+    assert_eq!(keyword.matched_keywords, Some(vec!["vape".to_string()]));
+    assert_eq!(keyword.occurrences, 3);
+    assert!(same_instant(&keyword.last_seen, "2024-03-10T18:34:00+00:00"));
+    assert!(!keyword.is_read);
+
+    let legacy = &alerts[1];
+    assert_eq!(legacy.description, "");
+    assert_eq!(legacy.url, None);
+    assert_eq!(legacy.occurrences, 1);
+    assert_eq!(legacy.last_seen, legacy.timestamp);
+    assert!(legacy.is_read && legacy.is_resolved);
+}
+
+#[test]
+fn unread_filter_drops_acknowledged_alerts() {
+    let mock = MockScriptRunner::install();
+    mock.on(ALERT_SCRIPT, "list", fixture(include_str!("fixtures/alerts.json")));
+
+    let alerts = block_on(commands::get_alerts(Some(true))).unwrap();
+    let ids: Vec<&str> = alerts.iter().map(|a| a.id.as_str()).collect();
+    assert_eq!(ids, ["alert_20240310_183000_000001"]);
+}
+
+#[test]
+fn stats_combine_bandwidth_and_skip_malformed_hours() {
+    let mock = MockScriptRunner::install();
+    mock.on(DB_SCRIPT, "stats", fixture(include_str!("fixtures/stats.json")));
+
+    let stats = commands::fetch_stats().unwrap();
+    assert_eq!(stats.total_devices, 3);
+    assert_eq!(stats.online_devices, 2);
+    assert_eq!(stats.total_requests, 1500);
+    assert_eq!(stats.blocked_requests, 42);
+    assert_eq!(stats.unresolved_alerts, 2);
+    assert_eq!(stats.total_bandwidth, 1_250_000);
+    assert_eq!((stats.bytes_in, stats.bytes_out), (1_000_000, 250_000));
+    assert_eq!(stats.top_domains.len(), 2);
+
+    assert_eq!(stats.traffic_by_hour.len(), 2);
+    assert_eq!(stats.traffic_by_hour[0].hour, 17);
+    assert_eq!(stats.traffic_by_hour[0].requests, 120);
+    assert_eq!(stats.traffic_by_hour[0].bytes_out, 40_000);
+    assert_eq!(stats.traffic_by_hour[1].bytes_in, 0);
+    assert!(same_instant(&stats.traffic_by_hour[1].start, "2024-03-10T18:00:00+00:00"));
+}
+
+#[test]
+fn traffic_streams_records_with_filters() {
+    let mock = MockScriptRunner::install();
+    let records = fixture(include_str!("fixtures/traffic.json")).as_array().unwrap().clone();
+    mock.on_stream(DB_SCRIPT, "traffic", records, json!({ "type": "end", "success": true, "count": 3 }));
+
+    let entries = block_on(commands::get_traffic(Some(50), None, Some("dev_a483e7102030".to_string()))).unwrap();
+
+    let call = &mock.calls_to(DB_SCRIPT)[0];
+    assert!(call.args.contains(&"--stream".to_string()));
+    assert_eq!(call.arg("--limit"), Some("50"));
+    assert_eq!(call.arg("--device"), Some("dev_a483e7102030"));
+
+    // The record without an id is skipped
+    assert_eq!(entries.len(), 2);
+
+    let search = &entries[0];
+    assert_eq!(search.method, "POST");
+    assert_eq!(search.status_code, Some(200));
+    assert_eq!(search.content_type.as_deref(), Some("text/html"));
+    assert_eq!(search.duration, 140);
+    assert!(search.has_alert);
+    assert!(same_instant(&search.timestamp, "2024-03-10T18:30:00+00:00"));
+
+    let blocked = &entries[1];
+    assert_eq!(blocked.method, "GET");
+    assert_eq!(blocked.device_id, None);
+    assert_eq!(blocked.status_code, None);
+    assert!(blocked.is_blocked);
+    assert!(!blocked.has_alert);
+}
+
+#[test]
+fn dns_queries_pass_only_given_filters() {
+    let mock = MockScriptRunner::install();
+    mock.on(DB_SCRIPT, "dns", fixture(include_str!("fixtures/dns.json")));
+
+    let queries = block_on(commands::get_dns_queries(Some(10), None)).unwrap();
+
+    let call = &mock.calls_to(DB_SCRIPT)[0];
+    assert_eq!(call.arg("--limit"), Some("10"));
+    assert_eq!(call.arg("--device"), None);
+
+    assert_eq!(queries.len(), 2);
+    assert_eq!(queries[0].query_type, "AAAA");
+    assert_eq!(queries[0].response_ttl, Some(300));
+    assert_eq!(queries[1].device_id, None);
+    assert!(queries[1].blocked);
+    assert_eq!(queries[1].block_reason.as_deref(), Some("Category: ads"));
+}
+
+#[test]
+fn thumbnail_keys_match_the_proxy() {
+    // Values from python/https/thumbnails.py's thumbnail_key
+    assert_eq!(media::thumbnail_key("https://example.com/a.png"), "fa378f653a3984b6");
+    assert_eq!(media::thumbnail_key(""), "cbf29ce484222325");
+    assert!(media::thumbnail("../settings").is_err());
+}
+
+#[test]
+fn cert_qr_codes_render_as_png_and_svg() {
+    let url = "http://192.168.1.20:8888/?t=0123456789abcdef0123456789abcdef";
+    let png = qr::render(url, QrFormat::Png).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let svg = String::from_utf8(qr::render(url, QrFormat::Svg).unwrap()).unwrap();
+    assert!(svg.starts_with("<svg") && svg.contains("h1v1h-1z"), "{}", svg);
+    assert!(qr::render(&"x".repeat(8000), QrFormat::Png).is_err());
+}
+
+#[test]
+fn install_guides_follow_the_user_agent() {
+    let cases = [
+        ("Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15", Some(Platform::Ios)),
+        ("Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 Chrome/124.0 Mobile", Some(Platform::Android)),
+        ("Mozilla/5.0 (X11; CrOS x86_64 14541.0.0) AppleWebKit/537.36 Chrome/124.0", Some(Platform::Chromeos)),
+        ("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 Edg/124.0", Some(Platform::Windows)),
+        ("Mozilla/5.0 (Macintosh; Intel Mac OS X 14_4) AppleWebKit/605.1.15 Safari/605.1.15", Some(Platform::Macos)),
+        ("Mozilla/5.0 (X11; Ubuntu; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0", Some(Platform::Linux)),
+        ("okhttp/4.12.0", None),
+    ];
+    for (user_agent, platform) in cases {
+        assert_eq!(platform_from_user_agent(user_agent), platform, "{}", user_agent);
+    }
+}
+
+#[test]
+fn escalated_incidents_parse_alongside_older_ones() {
+    let mock = MockScriptRunner::install();
+    let incident = |id: &str| {
+        json!({
+            "id": id, "device": "dev1", "category": "privacy", "severity": "high", "title": "Credentials exposed",
+            "first_seen": "2024-03-10T18:00:00+00:00", "last_seen": "2024-03-10T18:20:00+00:00",
+            "alert_count": 3, "status": "open", "resolved_at": null
+        })
+    };
+    let mut escalated = incident("incident_2");
+    escalated["severity"] = json!("critical");
+    escalated["escalated_by"] = json!("burst");
+    escalated["escalated_at"] = json!("2024-03-10T18:20:00+00:00");
+    mock.on(ALERT_SCRIPT, "incidents", json!({ "success": true, "incidents": [escalated, incident("incident_1")] }));
+
+    let incidents = block_on(commands::get_incidents(None, None)).unwrap();
+    assert_eq!(incidents[0].escalated_by.as_deref(), Some("burst"));
+    assert_eq!(incidents[1].escalated_by, None);
+    assert_eq!(incidents[1].escalated_at, None);
+}
+
+#[test]
+fn upstream_proxy_tests_report_the_round_trip() {
+    let mock = MockScriptRunner::install();
+    mock.on(
+        "python/utils/upstream_proxy.py",
+        "test",
+        json!({ "success": true, "test": { "ok": false, "status": 407, "latency_ms": 42, "error": "HTTP 407 Proxy Authentication Required" } }),
+    );
+    let upstream = UpstreamProxy {
+        url: "http://proxy.office.example:3128".to_string(),
+        username: Some("me".to_string()),
+        password: Some("secret".to_string()),
+        bypass: vec!["intranet.example".to_string()],
+    };
+
+    let test = block_on(commands::test_upstream_proxy(Some(upstream), None)).unwrap();
+    assert!(!test.ok);
+    assert_eq!(test.status, Some(407));
+    assert_eq!(test.latency_ms, Some(42));
+    // Credentials stay off the command line
+    assert!(mock.calls_to("python/utils/upstream_proxy.py")[0].arg("--password").is_none());
+}
+
+#[test]
+fn enrichment_lookups_are_computed_once() {
+    let mut computed = 0;
+    let mut geo = |ip: &str| {
+        computed += 1;
+        Some(format!("{{\"ip\":\"{}\",\"country\":\"NL\"}}", ip))
+    };
+
+    let first = enrichment::lookup(Kind::Geo, "198.51.100.77", &mut geo);
+    let again = enrichment::lookup(Kind::Geo, "198.51.100.77", &mut geo);
+    assert_eq!(first, again);
+    assert_eq!(computed, 1);
+
+    // Nothing found is remembered too
+    let mut unknown = 0;
+    assert_eq!(enrichment::lookup(Kind::Geo, "192.0.2.250", |_| { unknown += 1; None }), None);
+    assert_eq!(enrichment::lookup(Kind::Geo, "192.0.2.250", |_| { unknown += 1; None }), None);
+    assert_eq!(unknown, 1);
+    assert!(enrichment::stats().kinds.iter().any(|k| k.kind == Kind::Geo && k.memory_hits >= 2));
+}
+
+#[test]
+fn command_timings_include_their_python_calls() {
+    let mock = MockScriptRunner::install();
+    mock.on(ALERT_SCRIPT, "list", fixture(include_str!("fixtures/alerts.json")));
+    block_on(commands::get_alerts(None)).unwrap();
+
+    let metrics = performance::metrics(usize::MAX);
+    let command = metrics
+        .recent
+        .iter()
+        .find(|call| call.kind == CallKind::Command && call.name == "get_alerts")
+        .unwrap();
+    assert!(command.ok);
+    assert!(command.payload_bytes > 0);
+    assert!(command.python_ms <= command.duration_ms);
+    assert!(metrics
+        .calls
+        .iter()
+        .any(|stats| stats.kind == CallKind::Python && stats.name.ends_with(":list")));
+}
+
+#[test]
+fn tags_are_normalized_before_they_are_stored() {
+    let mock = MockScriptRunner::install();
+    mock.on(DB_SCRIPT, "annotate-traffic", json!({ "success": true, "action": "annotated" }));
+
+    let tags = vec!["Reviewed".to_string(), " false  positive ".to_string(), "reviewed".to_string()];
+    let stored = block_on(commands::tag_traffic_entry("t1".to_string(), tags)).unwrap();
+    assert_eq!(stored, ["reviewed", "false-positive"]);
+    assert_eq!(
+        mock.calls_to(DB_SCRIPT)[0].arg("--tags"),
+        Some(r#"["reviewed","false-positive"]"#)
+    );
+
+    let (tags, rest) = annotations::split_query("example.com TAG:Suspicious");
+    assert_eq!((tags, rest.as_str()), (vec!["suspicious".to_string()], "example.com"));
+}
+
+#[test]
+fn investigations_export_the_devices_they_reference() {
+    let mock = MockScriptRunner::install();
+    mock.on(DB_SCRIPT, "devices", fixture(include_str!("fixtures/devices.json")));
+
+    let case = block_on(commands::create_investigation("  Late-night traffic ".to_string())).unwrap();
+    assert_eq!(case.name, "Late-night traffic");
+    let id = case.id.clone();
+
+    let device = "dev_a483e7102030".to_string();
+    block_on(commands::attach_to_investigation(id.clone(), EvidenceKind::Device, device.clone())).unwrap();
+    let case = block_on(commands::attach_to_investigation(id.clone(), EvidenceKind::Device, device)).unwrap();
+    assert_eq!(case.devices.len(), 1);
+    assert!(block_on(commands::attach_to_investigation(id.clone(), EvidenceKind::Device, "dev_gone".to_string())).is_err());
+
+    block_on(commands::add_investigation_note(id.clone(), "Started after the router reboot".to_string())).unwrap();
+    let bundle = investigations::bundle(&id).unwrap();
+    assert_eq!(bundle["devices"][0]["hostname"], "Emmas-iPhone");
+    assert_eq!(bundle["investigation"]["notes"][0]["text"], "Started after the router reboot");
+
+    block_on(commands::delete_investigation(id.clone())).unwrap();
+    assert!(investigations::get(&id).is_err());
+}
+
+#[test]
+fn alert_evidence_is_kept_until_the_alert_is_deleted() {
+    let alert = json!({ "id": "alert_evidence_test", "timestamp": "2026-01-05T10:00:00+00:00" });
+    let evidence = alert_evidence::capture(&alert, true).unwrap();
+    assert_eq!(evidence.window_start, "2026-01-05T09:58:00");
+    assert_eq!(evidence.window_end, "2026-01-05T10:02:00");
+    // Nothing to match on and no URL to render
+    assert!(evidence.traffic.is_empty() && evidence.dns.is_empty());
+    assert!(evidence.screenshot_url.is_none() && evidence.screenshot_error.is_none());
+
+    let stored = block_on(commands::get_alert_evidence("alert_evidence_test".to_string())).unwrap();
+    assert_eq!(stored.captured_at, evidence.captured_at);
+    assert!(block_on(commands::get_alert_evidence("../alerts".to_string())).is_err());
+
+    alert_evidence::delete("alert_evidence_test").unwrap();
+    assert!(block_on(commands::get_alert_evidence("alert_evidence_test".to_string())).is_err());
+}
+
+#[test]
+fn scan_ranges_are_normalized_to_their_network() {
+    assert_eq!(subnets::normalize_range(" 192.168.20.77/24").unwrap(), "192.168.20.0/24");
+    assert_eq!(subnets::normalize_range("10.1.2.3/32").unwrap(), "10.1.2.3/32");
+    assert!(subnets::normalize_range("10.0.0.0/8").is_err());
+    assert!(subnets::normalize_range("10.0.0.0/33").is_err());
+    assert!(subnets::normalize_range("fd00::/64").is_err());
+    assert!(subnets::normalize_range("192.168.1.0").is_err());
+
+    let ranges = ["10.20.0.0/24".to_string(), "10.20.0.9/24".to_string()];
+    assert_eq!(subnets::validate_ranges(&ranges).unwrap(), vec!["10.20.0.0/24"]);
+}
+
+#[test]
+fn os_detection_weighs_user_agents_above_fingerprints() {
+    assert!(os_detection::combine(&[]).is_none());
+
+    // A TTL of 64 with Apple's option order means iOS on a phone, macOS otherwise
+    assert_eq!(os_detection::resolve_family("apple", "phone"), Some(Platform::Ios));
+    assert_eq!(os_detection::resolve_family("apple", "computer"), Some(Platform::Macos));
+    assert_eq!(os_detection::resolve_family("unknown", "phone"), None);
+
+    let os = os_detection::combine(&[(Source::Tcp, Platform::Linux), (Source::Dhcp, Platform::Linux)]).unwrap();
+    assert_eq!(os.platform, Platform::Linux);
+    assert_eq!(os.confidence, 0.45);
+
+    let os = os_detection::combine(&[
+        (Source::UserAgent, Platform::Android),
+        (Source::Dhcp, Platform::Linux),
+        (Source::Tcp, Platform::Linux),
+    ])
+    .unwrap();
+    assert_eq!(os.platform, Platform::Android);
+    assert_eq!(os.sources, vec![Source::UserAgent]);
+    assert_eq!(os.confidence, 0.55);
+}
+
+#[test]
+fn parquet_export_partitions_traffic_by_date_and_device() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE traffic (
+            id TEXT PRIMARY KEY, timestamp TEXT NOT NULL, device_id TEXT, device_ip TEXT NOT NULL,
+            method TEXT NOT NULL, url TEXT NOT NULL, host TEXT NOT NULL, path TEXT, protocol TEXT,
+            status_code INTEGER, response_body_type TEXT, request_size INTEGER, response_size INTEGER,
+            duration_ms INTEGER, category TEXT, blocked INTEGER, block_reason TEXT, alerts TEXT, tags TEXT
+        );
+        INSERT INTO traffic VALUES
+            ('t1', '2026-01-05T10:00:00', 'dev-1', '192.168.1.20', 'GET', 'https://a.com/', 'a.com', '/', 'https',
+             200, 'text/html', 100, 2000, 30, 'social', 0, NULL, '[]', '[\"homework\"]'),
+            ('t2', '2026-01-05T11:00:00', 'dev-1', '192.168.1.20', 'GET', 'https://b.com/', 'b.com', '/', 'https',
+             NULL, NULL, NULL, NULL, NULL, NULL, 1, 'blocklist', '[{\"id\": \"a1\"}]', NULL),
+            ('t3', '2026-01-06T09:00:00', 'aa:bb', '192.168.1.21', 'POST', 'https://c.com/x', 'c.com', '/x', 'https',
+             201, 'application/json', 50, 10, 5, NULL, 0, NULL, '[]', '[]');",
+    )
+    .unwrap();
+
+    let output = std::env::temp_dir().join(format!("parquet-export-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&output);
+    let result = parquet_export::export_from(&conn, &output, |_, _| Ok(())).unwrap();
+    assert_eq!(result["records"], 3);
+    assert_eq!(result["partitions"], 2);
+
+    let file = std::fs::File::open(output.join("date=2026-01-05/device=dev-1/part-0.parquet")).unwrap();
+    let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)
+        .unwrap()
+        .build()
+        .unwrap();
+    let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+    assert_eq!(rows, 2);
+    // Device ids are made safe as directory names
+    assert!(output.join("date=2026-01-06/device=aa_bb/part-0.parquet").exists());
+
+    // Never written over
+    assert!(parquet_export::export_from(&conn, &output, |_, _| Ok(())).is_err());
+    std::fs::remove_dir_all(&output).unwrap();
+}
+
+#[test]
+fn advertised_services_name_and_type_devices() {
+    let services: Vec<DeviceService> = serde_json::from_value(json!([
+        {"kind": "smb", "type": "_smb._tcp", "name": "NAS-4F2A", "port": 445, "host": "nas.local", "source": "mdns"},
+        {
+            "kind": "chromecast", "type": "_googlecast._tcp", "name": "Chromecast-9d1c", "port": 8009,
+            "host": "9d1c.local", "source": "mdns", "details": {"fn": "Living Room TV", "md": "Chromecast"}
+        }
+    ]))
+    .unwrap();
+
+    // The cast name beats the share name, and the friendly name the instance name
+    assert_eq!(discovery::advertised_name(&services).as_deref(), Some("Living Room TV"));
+    assert_eq!(discovery::advertised_type(&services), Some("smart_tv"));
+    assert_eq!(discovery::advertised_type(&services[..1]), None);
+    assert_eq!(discovery::advertised_name(&[]), None);
+}
+
+#[test]
+fn flows_overlapping_a_range_are_listed_newest_first() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE flows (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, start_time TEXT NOT NULL, end_time TEXT NOT NULL,
+            device_id TEXT, device_ip TEXT NOT NULL, device_port INTEGER NOT NULL, remote_ip TEXT NOT NULL,
+            remote_port INTEGER NOT NULL, transport TEXT NOT NULL, protocol TEXT NOT NULL, packets INTEGER,
+            bytes_in INTEGER, bytes_out INTEGER, duration_ms INTEGER
+        );
+        INSERT INTO flows (start_time, end_time, device_id, device_ip, device_port, remote_ip, remote_port,
+                           transport, protocol, packets, bytes_in, bytes_out, duration_ms) VALUES
+            ('2026-01-05T09:58:00+00:00', '2026-01-05T10:03:00+00:00', 'dev-1', '192.168.1.20', 51000,
+             '203.0.113.5', 22, 'tcp', 'ssh', 40, 9000, 3000, 300000),
+            ('2026-01-05T10:10:00+00:00', '2026-01-05T10:11:00+00:00', 'dev-1', '192.168.1.20', 3074,
+             '198.51.100.7', 3074, 'udp', 'gaming', 500, 250000, 80000, 60000),
+            ('2026-01-05T10:20:00+00:00', '2026-01-05T10:20:05+00:00', 'dev-2', '192.168.1.21', 40000,
+             '198.51.100.8', 6881, 'tcp', 'bittorrent', 10, NULL, 700, 5000),
+            ('2026-01-05T12:00:00+00:00', '2026-01-05T12:00:01+00:00', 'dev-1', '192.168.1.20', 52000,
+             '203.0.113.9', 443, 'tcp', 'https', 3, 100, 100, 1000);",
+    )
+    .unwrap();
+    let (start, end) = ("2026-01-05T10:00:00+00:00", "2026-01-05T11:00:00+00:00");
+
+    // A flow that started before the range but ran into it is included
+    let all = flows::flows_from(&conn, None, start, end, 100).unwrap();
+    let protocols: Vec<&str> = all.iter().map(|f| f.protocol.as_str()).collect();
+    assert_eq!(protocols, ["bittorrent", "gaming", "ssh"]);
+    assert_eq!(all[0].bytes_in, 0);
+    assert_eq!(all[1].remote_port, 3074);
+    assert_eq!(all[2].duration_ms, 300_000);
+
+    let device = flows::flows_from(&conn, Some("dev-1"), start, end, 1).unwrap();
+    assert_eq!(device.len(), 1);
+    assert_eq!(device[0].protocol, "gaming");
+}
+
+#[test]
+fn firmware_advisories_match_products_and_affected_versions() {
+    let hints = advisories::parse_hints(include_str!("../../../config/firmware_advisories.json")).unwrap();
+    let banners = |list: &[&str]| list.iter().map(|b| b.to_string()).collect::<Vec<_>>();
+
+    // The libupnp version comes from the SSDP server header
+    let old = advisories::matching(&hints, &banners(&["Linux/2.6.21, UPnP/1.0, Portable SDK for UPnP devices/1.6.6"]), None, None);
+    assert_eq!(old.len(), 1);
+    assert_eq!(old[0].id, "CVE-2012-5958");
+    assert_eq!(old[0].firmware.as_deref(), Some("1.6.6"));
+    assert!(old[0].confirmed);
+    assert!(advisories::matching(&hints, &banners(&["Linux/4.9 UPnP/1.0 Portable SDK for UPnP devices/1.14.6"]), None, None).is_empty());
+
+    // A camera's firmware field decides, and the vendor has to match
+    let camera = banners(&["Hikvision", "DS-2CD2032-I"]);
+    let affected = advisories::matching(&hints, &camera, None, Some("V5.3.0 build 150513"));
+    assert_eq!(affected.iter().map(|a| a.id.as_str()).collect::<Vec<_>>(), ["CVE-2017-7921"]);
+    assert!(advisories::matching(&hints, &camera, None, Some("V5.5.0 build 170725")).is_empty());
+    let unknown = advisories::matching(&hints, &camera, None, None);
+    assert!(!unknown[0].confirmed);
+    assert!(advisories::matching(&hints, &banners(&["DS-2CD2032-I"]), Some("Acme"), None).is_empty());
+    assert_eq!(advisories::version_in("miniupnpd/1.0."), Some("1.0".to_string()));
+}
+
+#[test]
+fn inventory_events_are_filtered_by_device_and_range() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE device_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, timestamp TEXT NOT NULL, device_id TEXT NOT NULL,
+            mac_address TEXT NOT NULL, event TEXT NOT NULL, old_value TEXT, new_value TEXT
+        );
+        INSERT INTO device_events (timestamp, device_id, mac_address, event, old_value, new_value) VALUES
+            ('2026-01-01T08:00:00+00:00', 'dev_a483e7102030', 'a4:83:e7:10:20:30', 'new_device', NULL, '192.168.1.20'),
+            ('2026-01-03T08:00:00+00:00', 'dev_a483e7102030', 'a4:83:e7:10:20:30', 'ip_changed', '192.168.1.20', '192.168.1.34'),
+            ('2026-01-03T09:00:00+00:00', 'dev_001122334455', '00:11:22:33:44:55', 'new_device', NULL, '192.168.1.40'),
+            ('2026-01-12T08:00:00+00:00', 'dev_a483e7102030', 'a4:83:e7:10:20:30', 'absent', '2026-01-03T08:00:00+00:00', NULL);",
+    )
+    .unwrap();
+
+    let changelog = inventory::events_from(&conn, Some("dev_a483e7102030"), None, None, 100).unwrap();
+    let events: Vec<&str> = changelog.iter().map(|e| e.event.as_str()).collect();
+    assert_eq!(events, ["absent", "ip_changed", "new_device"]);
+    assert_eq!(changelog[1].old_value.as_deref(), Some("192.168.1.20"));
+    assert_eq!(changelog[0].new_value, None);
+
+    let range = inventory::events_from(
+        &conn,
+        None,
+        Some("2026-01-02T00:00:00+00:00"),
+        Some("2026-01-10T00:00:00+00:00"),
+        100,
+    )
+    .unwrap();
+    let devices: Vec<&str> = range.iter().map(|e| e.device_id.as_str()).collect();
+    assert_eq!(devices, ["dev_001122334455", "dev_a483e7102030"]);
+}
+
+#[test]
+fn stats_view_counts_new_rows_like_a_rebuild() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE traffic (
+            id TEXT PRIMARY KEY, timestamp TEXT NOT NULL, host TEXT NOT NULL,
+            request_size INTEGER, response_size INTEGER, blocked INTEGER DEFAULT 0
+        );
+        CREATE TABLE traffic_aggregates (
+            minute TEXT NOT NULL, device_ip TEXT NOT NULL, host TEXT NOT NULL, category TEXT,
+            requests INTEGER, bytes_in INTEGER, bytes_out INTEGER, blocked INTEGER
+        );
+        CREATE TABLE flows (id INTEGER PRIMARY KEY AUTOINCREMENT, protocol TEXT NOT NULL, bytes_in INTEGER, bytes_out INTEGER);
+        INSERT INTO traffic_aggregates VALUES
+            ('2025-12-01T08:00', '192.168.1.20', 'example.com', NULL, 40, 4000, 400, 2);
+        INSERT INTO traffic VALUES
+            ('a', '2026-01-05T10:15:00+00:00', 'example.com', 100, 1000, 0),
+            ('b', '2026-01-05T11:40:00+00:00', 'ads.example.net', 50, NULL, 1);
+        INSERT INTO flows (protocol, bytes_in, bytes_out) VALUES ('ssh', 7000, 3000), ('https', 900, 90);",
+    )
+    .unwrap();
+    let now = chrono::DateTime::parse_from_rfc3339("2026-01-05T12:30:00+00:00").unwrap().with_timezone(&chrono::Utc);
+    let zone = clock::Zone::Named(chrono_tz::UTC);
+
+    let mut view = stats_cache::StatsView::build(&conn, now).unwrap();
+    conn.execute_batch(
+        "INSERT INTO traffic VALUES
+            ('c', '2026-01-05T12:05:00+00:00', 'example.com', 10, 20, 0),
+            ('d', '2026-01-05T12:06:00+00:00', 'new.example.org', 5, 5, 0);
+        INSERT INTO flows (protocol, bytes_in, bytes_out) VALUES ('gaming', 500, 100);",
+    )
+    .unwrap();
+    assert!(view.apply_new(&conn, now).unwrap());
+
+    let stats = view.dashboard(zone, now);
+    let rebuilt = stats_cache::StatsView::build(&conn, now).unwrap().dashboard(zone, now);
+    assert_eq!(stats.total_requests, 44);
+    assert_eq!(stats.blocked_requests, 3);
+    // Web flows are already counted by the proxy
+    assert_eq!((stats.bytes_in, stats.bytes_out), (4000 + 1025 + 7500, 400 + 165 + 3100));
+    assert_eq!(stats.top_domains[0].domain, "example.com");
+    assert_eq!(stats.top_domains[0].count, 42);
+    assert_eq!(serde_json::to_value(&stats).unwrap(), serde_json::to_value(&rebuilt).unwrap());
+
+    // Only the last day is charted, oldest hour first
+    assert_eq!(stats.traffic_by_hour.len(), 24);
+    let last: Vec<u64> = stats.traffic_by_hour[21..].iter().map(|h| h.requests).collect();
+    assert_eq!(last, [1, 1, 2]);
+    assert_eq!(stats.traffic_by_hour[23].hour, 12);
+
+    // Rows removed under the view call for a rebuild
+    conn.execute_batch("DELETE FROM traffic WHERE id IN ('c', 'd')").unwrap();
+    assert!(!view.apply_new(&conn, now).unwrap());
+}
+
+#[test]
+fn package_scripts_run_as_modules() {
+    let root = std::path::Path::new("/opt/netmon");
+    assert_eq!(script_invocation(root, "python/dns/dns_blocker.py"), ["-m", "python.dns.dns_blocker"]);
+    assert_eq!(script_invocation(root, "python/main.py"), ["-m", "python.main"]);
+    assert_eq!(script_invocation(root, "tools/probe.py"), ["/opt/netmon/tools/probe.py"]);
+}
+
+#[test]
+fn log_lines_hide_secrets() {
+    let line = r#"Updating settings: MqttSettings { host: "broker", password: Some("hunter2"), port: 1883 }"#;
+    assert_eq!(
+        diagnostics::redact_line(line),
+        r#"Updating settings: MqttSettings { host: "broker", password: Some([redacted]), port: 1883 }"#
+    );
+    assert_eq!(
+        diagnostics::redact_line(r#"{"api_key":"a\"bc","user":"me"}"#),
+        r#"{"api_key":[redacted],"user":"me"}"#
+    );
+    assert_eq!(diagnostics::redact_line("GET /feed?token=xyz&page=2"), "GET /feed?token=[redacted]&page=2");
+    assert_eq!(diagnostics::redact_line("password: None, tokens left 3"), "password: None, tokens left 3");
+
+    let settings = json!({ "mqtt": { "password": "hunter2", "host": "broker" } });
+    assert_eq!(diagnostics::redacted(&settings)["mqtt"]["password"], "[redacted]");
+}
+
+#[test]
+fn short_search_terms_match_wildcards_literally() {
+    assert_eq!(database::like_pattern("a_b"), r"%a\_b%");
+    assert_eq!(database::like_pattern("5%"), r"%5\%%");
+    assert_eq!(database::like_pattern(r"c:\x"), r"%c:\\x%");
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    let matches = |value: &str, term: &str| -> bool {
+        conn.query_row("SELECT ?1 LIKE ?2 ESCAPE '\\'", [value, &database::like_pattern(term)], |r| r.get(0))
+            .unwrap()
+    };
+    assert!(matches("/a_b/", "a_b"));
+    assert!(!matches("/axb/", "a_b"));
+    assert!(!matches("anything", "%"));
+    assert!(!database::has_search_index(&conn));
+}
@@ -0,0 +1,90 @@
+use crate::protocols;
+
+#[test]
+fn protocol_usage_lands_in_its_bucket_and_unknown_names_count_as_other() {
+    // (protocol as the bypass detector stores it, bucket it is shown under)
+    let cases = [
+        ("http", "http"),
+        ("https", "https"),
+        ("dns", "dns"),
+        ("quic", "quic"),
+        ("other", "other"),
+        ("ssh", "other"),
+        ("", "other"),
+        ("HTTPS", "other"),
+    ];
+
+    for (stored, bucket) in cases {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE protocol_usage (minute TEXT, device_id TEXT, protocol TEXT, packets INTEGER, bytes_in INTEGER, bytes_out INTEGER);
+            CREATE TABLE traffic (timestamp TEXT, device_id TEXT, request_size INTEGER, response_size INTEGER);
+            CREATE TABLE traffic_aggregates (minute TEXT, device_id TEXT, bytes_in INTEGER, bytes_out INTEGER);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO protocol_usage VALUES ('2026-01-05T10:00', 'laptop', ?1, 3, 600, 400)",
+            [stored],
+        )
+        .unwrap();
+
+        let breakdown =
+            protocols::breakdown_from(&conn, None, "2026-01-05T00:00:00".to_string(), "2026-01-06T00:00:00".to_string())
+                .unwrap();
+        let order: Vec<&str> = breakdown.protocols.iter().map(|p| p.protocol.as_str()).collect();
+        assert_eq!(order, ["http", "https", "dns", "quic", "other"]);
+        for volume in &breakdown.protocols {
+            let expected = if volume.protocol == bucket { (3, 600, 400, 100.0) } else { (0, 0, 0, 0.0) };
+            assert_eq!(
+                (volume.packets, volume.bytes_in, volume.bytes_out, volume.percent),
+                expected,
+                "{:?} in {}",
+                stored,
+                volume.protocol
+            );
+        }
+    }
+}
+
+#[test]
+fn protocol_breakdown_sets_proxied_bytes_against_passive_capture() {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE protocol_usage (minute TEXT, device_id TEXT, protocol TEXT, packets INTEGER, bytes_in INTEGER, bytes_out INTEGER);
+        CREATE TABLE traffic (timestamp TEXT, device_id TEXT, request_size INTEGER, response_size INTEGER);
+        CREATE TABLE traffic_aggregates (minute TEXT, device_id TEXT, bytes_in INTEGER, bytes_out INTEGER);
+        INSERT INTO protocol_usage VALUES
+            ('2026-01-05T10:00', 'laptop', 'https', 10, 6000, 1500),
+            ('2026-01-05T10:00', 'laptop', 'quic', 5, 2000, 500),
+            ('2026-01-05T10:01', 'phone', 'https', 1, 100, 100),
+            -- the minute the range ends at is outside it
+            ('2026-01-06T00:00', 'laptop', 'https', 1, 100, 100);
+        INSERT INTO traffic VALUES ('2026-01-05T10:00:05+00:00', 'laptop', 500, 4000);
+        INSERT INTO traffic_aggregates VALUES ('2026-01-05T09:00', 'laptop', 2000, 500);",
+    )
+    .unwrap();
+
+    let breakdown = protocols::breakdown_from(
+        &conn,
+        Some("laptop"),
+        "2026-01-05T00:00:00".to_string(),
+        "2026-01-06T00:00:00".to_string(),
+    )
+    .unwrap();
+    assert_eq!(breakdown.total_bytes, 10_000);
+    assert_eq!(breakdown.proxied_bytes, 7_000);
+    assert_eq!(breakdown.unproxied_bytes, 3_000);
+    assert_eq!(breakdown.protocols[1].percent, 75.0);
+    assert_eq!(breakdown.protocols[3].percent, 25.0);
+
+    // The proxy can record more than passive capture saw; nothing goes negative
+    conn.execute_batch("INSERT INTO traffic VALUES ('2026-01-05T11:00:00+00:00', 'laptop', 0, 9000)").unwrap();
+    let breakdown = protocols::breakdown_from(
+        &conn,
+        Some("laptop"),
+        "2026-01-05T00:00:00".to_string(),
+        "2026-01-06T00:00:00".to_string(),
+    )
+    .unwrap();
+    assert_eq!(breakdown.unproxied_bytes, 0);
+}
@@ -0,0 +1,81 @@
+use crate::{clock, sessions};
+use chrono::NaiveDate;
+
+fn traffic(rows: &str) -> rusqlite::Connection {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(&format!(
+        "CREATE TABLE traffic (timestamp TEXT, device_id TEXT, host TEXT, request_size INTEGER, response_size INTEGER);
+        CREATE TABLE traffic_aggregates (minute TEXT, device_id TEXT, host TEXT, requests INTEGER, bytes_in INTEGER, bytes_out INTEGER);
+        {}",
+        rows
+    ))
+    .unwrap();
+    conn
+}
+
+#[test]
+fn a_gap_of_exactly_the_session_gap_continues_the_session() {
+    let conn = traffic(
+        "INSERT INTO traffic VALUES
+            ('2026-01-05T10:00:00+00:00', 'laptop', 'www.example.com', 100, 900),
+            ('2026-01-05T10:10:00+00:00', 'laptop', 'cdn.example.com', 100, 900),
+            -- one second past the gap
+            ('2026-01-05T10:20:01+00:00', 'laptop', 'example.org', 10, 10),
+            ('2026-01-05T10:20:30+00:00', 'phone', 'example.org', 10, 10);
+        INSERT INTO traffic_aggregates VALUES
+            ('2026-01-05T10:30', 'laptop', 'example.org', 4, 20, 20),
+            ('2026-01-05T10:41', 'laptop', 'example.net', 2, 5, 5);",
+    );
+    let zone = clock::Zone::Named(chrono_tz::UTC);
+    let report =
+        sessions::sessions_from(&conn, "laptop", "2026-01-05T00:00:00", "2026-01-06T00:00:00", zone).unwrap();
+
+    assert_eq!(report.gap_minutes, 10);
+    // Newest first
+    let spans: Vec<(&str, &str, u64, u64)> = report
+        .sessions
+        .iter()
+        .map(|s| (s.start.as_str(), s.end.as_str(), s.duration_seconds, s.requests))
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            ("2026-01-05T10:41:00+00:00", "2026-01-05T10:41:00+00:00", 0, 2),
+            ("2026-01-05T10:20:01+00:00", "2026-01-05T10:30:00+00:00", 599, 5),
+            ("2026-01-05T10:00:00+00:00", "2026-01-05T10:10:00+00:00", 600, 2),
+        ]
+    );
+    assert_eq!(report.total_duration_seconds, 1199);
+
+    let first = &report.sessions[2];
+    assert_eq!(first.site_count, 1);
+    assert_eq!(first.top_sites[0].site, "example.com");
+    assert_eq!((first.top_sites[0].requests, first.top_sites[0].bytes), (2, 2000));
+}
+
+#[test]
+fn sessions_across_a_dst_change_use_real_elapsed_time() {
+    // Paris springs forward at 02:00 local (01:00 UTC) on 2026-03-29
+    let zone = clock::Zone::Named(chrono_tz::Europe::Paris);
+    let day = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let (start, end) = zone.utc_bounds(day(28), day(30));
+    assert_eq!((start.as_str(), end.as_str()), ("2026-03-27T23:00:00", "2026-03-29T22:00:00"));
+
+    let conn = traffic(
+        "INSERT INTO traffic VALUES
+            -- just before local midnight on the 28th, and the local midnight ending the range
+            ('2026-03-27T22:59:59+00:00', 'laptop', 'example.com', 1, 1),
+            ('2026-03-29T22:00:00+00:00', 'laptop', 'example.com', 1, 1),
+            -- 01:55 CET, then 03:04 CEST: nine minutes apart
+            ('2026-03-29T00:55:00+00:00', 'laptop', 'example.com', 1, 1),
+            ('2026-03-29T01:04:00+00:00', 'laptop', 'example.com', 1, 1);",
+    );
+    let report = sessions::sessions_from(&conn, "laptop", &start, &end, zone).unwrap();
+
+    assert_eq!(report.sessions.len(), 1);
+    let session = &report.sessions[0];
+    assert_eq!(session.start, "2026-03-29T01:55:00+01:00");
+    assert_eq!(session.end, "2026-03-29T03:04:00+02:00");
+    assert_eq!(session.duration_seconds, 540);
+    assert_eq!(session.requests, 2);
+}
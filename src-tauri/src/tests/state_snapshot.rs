@@ -0,0 +1,68 @@
+use super::same_instant;
+use crate::state_snapshot::{self, StateSnapshot};
+use serde_json::json;
+
+#[test]
+fn state_snapshots_hide_identities_and_replay_from_now() {
+    let snapshot: StateSnapshot = serde_json::from_value(json!({
+        "version": state_snapshot::SNAPSHOT_VERSION,
+        "taken_at": "2026-03-01T10:00:00+00:00",
+        "app_version": "1.0.0",
+        "os": "linux",
+        "monitoring_state": "running",
+        "uptime": 120,
+        "profile": "home",
+        "demo": false,
+        "settings": { "theme": "dark" },
+        "devices": [{
+            "id": "dev_a483e7102030", "mac": "a4:83:e7:10:20:30", "ip": "192.168.1.12",
+            "hostname": "kids-ipad", "vendor": "Apple", "device_type": "tablet",
+            "first_seen": "2026-02-01T08:00:00+00:00", "last_seen": "2026-03-01T09:59:00+00:00",
+            "is_online": true, "is_monitored": true, "has_certificate": false,
+            "total_bytes": 10, "bytes_in": 8, "bytes_out": 2, "blocked_requests": 0, "alerts": 1
+        }],
+        "stats": null,
+        "traffic": [{
+            "id": "t1", "timestamp": "2026-03-01T09:58:00+00:00", "device_id": "dev_a483e7102030",
+            "device_ip": "192.168.1.12", "method": "GET", "url": "https://www.tiktok.com/@someone?lang=en",
+            "host": "www.tiktok.com", "path": "/@someone", "status_code": 200, "content_type": null,
+            "request_size": 1, "response_size": 2, "duration": 3, "is_blocked": false, "has_alert": true,
+            "category": "social", "tags": ["homework"], "note": "asked about this"
+        }],
+        "dns": [],
+        "alerts": [{
+            "id": "a1", "timestamp": "2026-03-01T09:58:01+00:00", "device_id": "dev_a483e7102030",
+            "severity": "high", "category": "social", "title": "kids-ipad visited www.tiktok.com",
+            "description": "192.168.1.12 (kids-ipad) opened www.tiktok.com", "url": "https://www.tiktok.com/@someone",
+            "matched_keywords": null, "occurrences": 1, "last_seen": "2026-03-01T09:58:01+00:00", "is_read": false, "is_resolved": false
+        }],
+        "logs": ["Scan found kids-ipad at 192.168.1.12 (a4:83:e7:10:20:30)"],
+        "performance": {}
+    }))
+    .unwrap();
+
+    let anonymized = state_snapshot::anonymize(snapshot);
+    let device = &anonymized.devices[0];
+    let entry = &anonymized.traffic[0];
+    assert_eq!(device.hostname.as_deref(), Some("host-1"));
+    assert_eq!(device.ip, "10.0.0.2");
+    assert_eq!(device.mac, "02:00:00:00:00:01");
+    assert_eq!(entry.device_id.as_deref(), Some(device.id.as_str()));
+    assert_eq!(entry.url, format!("https://{}/", entry.host));
+    assert_eq!((entry.path.as_deref(), entry.note.as_deref()), (Some("/"), None));
+    assert_eq!(entry.tags, ["tag-1"]);
+
+    // Free text uses the same placeholders and leaks nothing
+    let alert = &anonymized.alerts[0];
+    assert_eq!(alert.title, format!("host-1 visited {}", entry.host));
+    let text = serde_json::to_string(&anonymized).unwrap();
+    for secret in ["kids-ipad", "192.168.1.12", "a4:83:e7", "tiktok", "someone", "homework", "dev_a483e7102030"] {
+        assert!(!text.contains(secret), "{} left in the snapshot", secret);
+    }
+
+    // Replayed as if taken an hour later
+    let replay = state_snapshot::replay_data(anonymized, chrono::Duration::hours(1));
+    assert!(same_instant(&replay.traffic[0].timestamp, "2026-03-01T10:58:00Z"));
+    assert!(same_instant(&replay.devices[0].last_seen, "2026-03-01T10:59:00Z"));
+    assert!(same_instant(&replay.alerts[0].last_seen, "2026-03-01T10:58:01Z"));
+}
@@ -0,0 +1,48 @@
+use crate::usage_cost::{self, CostModel};
+
+#[test]
+fn usage_cost_projects_the_billing_cycle_against_the_cap() {
+    let at = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+
+    // Cycles start at midnight on the billing day, before or after it in the month
+    let (start, end) = usage_cost::billing_cycle(at("2026-03-20 09:00"), 15);
+    assert_eq!((start, end), (at("2026-03-15 00:00"), at("2026-04-15 00:00")));
+    let (start, end) = usage_cost::billing_cycle(at("2026-01-03 23:59"), 15);
+    assert_eq!((start, end), (at("2025-12-15 00:00"), at("2026-01-15 00:00")));
+    assert_eq!(usage_cost::billing_cycle(at("2026-02-01 00:00"), 1).0, at("2026-02-01 00:00"));
+
+    let model = CostModel {
+        metered: true,
+        price_per_gb: 2.5,
+        monthly_cap_gb: Some(100.0),
+        billing_cycle_day: 15,
+        ..CostModel::default()
+    };
+    let day = chrono::Duration::days(1);
+    let span = |bytes: u64| ("start".to_string(), "end".to_string(), bytes);
+    // 40 GB in the first 10 days of a 30-day cycle heads for 120 GB
+    let usage = usage_cost::estimate(&model, span(4_000_000_000), span(40_000_000_000), day * 10, day * 30);
+    assert_eq!(usage.cost, 10.0);
+    assert_eq!(usage.cycle_cost, 100.0);
+    assert_eq!(usage.projected_bytes, 120_000_000_000);
+    assert_eq!(usage.projected_cost, 300.0);
+    assert_eq!(usage.cap_used_percent, Some(40.0));
+    assert!(usage.projected_over_cap);
+
+    // Minutes into a cycle aren't extrapolated as if they were the whole rate
+    assert_eq!(usage_cost::project(1_000, chrono::Duration::minutes(1), chrono::Duration::hours(2)), 2_000);
+    let uncapped = CostModel { monthly_cap_gb: None, ..model.clone() };
+    let usage = usage_cost::estimate(&uncapped, span(0), span(40_000_000_000), day * 10, day * 30);
+    assert_eq!((usage.cap_bytes, usage.projected_over_cap), (None, false));
+
+    assert!(usage_cost::validate(&model).is_ok());
+    for invalid in [
+        CostModel { billing_cycle_day: 31, ..model.clone() },
+        CostModel { billing_cycle_day: 0, ..model.clone() },
+        CostModel { price_per_gb: -1.0, ..model.clone() },
+        CostModel { monthly_cap_gb: Some(0.0), ..model.clone() },
+        CostModel { currency: " ".to_string(), ..model.clone() },
+    ] {
+        assert!(usage_cost::validate(&invalid).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use crate::users;
+use crate::workspace;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+#[test]
+fn capture_data_is_shared_unless_users_keep_their_own() {
+    let database = Path::new("workspace").join("database");
+    assert_eq!(users::data_dir(&database, true), database);
+    assert_eq!(
+        users::data_dir(&database, false),
+        database.join("users").join(users::current())
+    );
+
+    // Tests run in a scratch workspace, never the real one
+    assert!(workspace::data_dir().starts_with(workspace::root()));
+}
+
+#[test]
+fn settings_schema_splits_user_and_shared_settings() {
+    let settings = json!({ "theme": "dark", "hotkeys": {}, "network_interface": "eth0", "usage_cost": {} });
+    let schema = users::schema(&settings);
+
+    let scopes: Vec<(&str, &str)> = schema.settings.iter().map(|s| (s.key.as_str(), s.scope.as_str())).collect();
+    assert_eq!(
+        scopes,
+        [("hotkeys", "user"), ("network_interface", "shared"), ("theme", "user"), ("usage_cost", "shared")]
+    );
+    assert_eq!(schema.user, users::current());
+    assert!(Path::new(&schema.user_config_dir).ends_with(Path::new("users").join(users::current())));
+    assert_eq!(Path::new(&schema.shared_config_dir), workspace::config_dir());
+}
+
+#[test]
+fn user_settings_overlay_only_their_own_keys() {
+    let saved: Map<String, Value> = json!({ "start_minimized": true, "network_interface": "eth9" })
+        .as_object()
+        .cloned()
+        .unwrap();
+    users::save_settings(&saved).unwrap();
+
+    let mut settings = json!({ "start_minimized": false, "network_interface": "eth0" }).as_object().cloned().unwrap();
+    users::overlay_settings(&mut settings).unwrap();
+    // Shared settings saved by a user don't override the workspace's
+    assert_eq!(settings["start_minimized"], true);
+    assert_eq!(settings["network_interface"], "eth0");
+}
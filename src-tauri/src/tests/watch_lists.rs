@@ -0,0 +1,75 @@
+use crate::{clock, watch_lists};
+use std::collections::HashMap;
+
+fn matches() -> rusqlite::Connection {
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE watch_matches (
+            timestamp TEXT NOT NULL, traffic_id TEXT, list_id TEXT NOT NULL, term TEXT NOT NULL, device_id TEXT,
+            device_ip TEXT NOT NULL, host TEXT, url TEXT, location TEXT NOT NULL, snippet TEXT
+        );
+        INSERT INTO watch_matches VALUES
+            ('2026-01-05T10:00:00+00:00', 't1', 'self_harm', 'hopeless', 'tablet', '192.168.1.20',
+             'search.example', 'https://search.example/?q=h*******', 'url', 'feeling h******* again'),
+            ('2026-01-05T10:00:00+00:00', 't1', 'self_harm', 'hopeless', 'tablet', '192.168.1.20',
+             'search.example', 'https://search.example/?q=h*******', 'response', NULL),
+            -- 23:30 UTC is the 6th in Paris
+            ('2026-01-05T23:30:00+00:00', 't2', 'self_harm', 'goodbye', 'tablet', '192.168.1.20',
+             'chat.example', 'https://chat.example/', 'request', NULL),
+            ('2026-01-05T11:00:00+00:00', 't3', 'self_harm', 'hopeless', NULL, '192.168.1.99',
+             'forum.example', 'https://forum.example/', 'response', NULL),
+            ('2026-01-05T12:00:00+00:00', 't4', 'gambling', 'casino', 'laptop', '192.168.1.30',
+             'ads.example', 'https://ads.example/casino', 'url', 'casino'),
+            ('2026-01-07T12:00:00+00:00', 't5', 'gambling', 'casino', 'laptop', '192.168.1.30',
+             'ads.example', 'https://ads.example/casino', 'url', 'casino');",
+    )
+    .unwrap();
+    conn
+}
+
+#[test]
+fn watch_matches_filter_by_list_and_device_newest_first() {
+    let conn = matches();
+    let zone = clock::Zone::Named(chrono_tz::UTC);
+    let (start, end) = ("2026-01-05T00:00:00", "2026-01-07T00:00:00");
+
+    let all = watch_lists::matches_from(&conn, None, None, start, end, zone).unwrap();
+    let traffic: Vec<&str> = all.iter().filter_map(|m| m.traffic_id.as_deref()).collect();
+    assert_eq!(traffic, ["t2", "t4", "t3", "t1", "t1"]);
+
+    let self_harm = watch_lists::matches_from(&conn, Some("self_harm"), None, start, end, zone).unwrap();
+    assert_eq!(self_harm.len(), 4);
+    assert!(self_harm.iter().all(|m| m.list_id == "self_harm"));
+
+    let tablet = watch_lists::matches_from(&conn, Some("self_harm"), Some("tablet"), start, end, zone).unwrap();
+    let found: Vec<(&str, &str)> = tablet.iter().map(|m| (m.term.as_str(), m.location.as_str())).collect();
+    assert_eq!(found, [("goodbye", "request"), ("hopeless", "url"), ("hopeless", "response")]);
+    assert_eq!(tablet[1].snippet.as_deref(), Some("feeling h******* again"));
+    assert_eq!(tablet[0].timestamp, "2026-01-05T23:30:00+00:00");
+
+    let laptop = watch_lists::matches_from(&conn, Some("self_harm"), Some("laptop"), start, end, zone).unwrap();
+    assert!(laptop.is_empty());
+}
+
+#[test]
+fn watch_rollup_counts_per_list_device_term_and_local_day() {
+    let conn = matches();
+    let names = HashMap::from([("self_harm".to_string(), "Self-harm".to_string())]);
+    let zone = clock::Zone::Named(chrono_tz::Europe::Paris);
+    let rollup = watch_lists::rollup_from(&conn, &names, "2026-01-04T23:00:00", "2026-01-07T23:00:00", zone).unwrap();
+
+    assert_eq!(rollup.total_matches, 6);
+    let lists: Vec<(&str, &str, u64)> =
+        rollup.lists.iter().map(|l| (l.list_id.as_str(), l.name.as_str(), l.matches)).collect();
+    assert_eq!(lists, [("self_harm", "Self-harm", 4), ("gambling", "gambling", 2)]);
+
+    let counts = |counts: &[watch_lists::RollupCount]| -> Vec<(String, u64)> {
+        counts.iter().map(|c| (c.key.clone(), c.matches)).collect()
+    };
+    let self_harm = &rollup.lists[0];
+    // A match without a device id is counted under its address
+    assert_eq!(counts(&self_harm.devices), [("tablet".to_string(), 3), ("192.168.1.99".to_string(), 1)]);
+    assert_eq!(counts(&self_harm.terms), [("hopeless".to_string(), 3), ("goodbye".to_string(), 1)]);
+    assert_eq!(counts(&self_harm.days), [("2026-01-05".to_string(), 3), ("2026-01-06".to_string(), 1)]);
+    assert_eq!(counts(&rollup.lists[1].days), [("2026-01-05".to_string(), 1), ("2026-01-07".to_string(), 1)]);
+}
@@ -0,0 +1,46 @@
+use super::device;
+use crate::clock;
+use crate::widgets;
+use serde_json::json;
+
+#[test]
+fn widgets_chart_alert_trends_risk_and_group_bandwidth() {
+    let alerts = [
+        json!({ "timestamp": "2026-01-05T09:00:00+00:00", "severity": "high", "device_id": "laptop" }),
+        json!({ "timestamp": "2026-01-05T23:30:00+00:00", "severity": "low", "device_id": "laptop" }),
+        json!({ "timestamp": "2026-01-03T10:00:00+00:00", "severity": "critical", "device_id": "tv", "acknowledged": true }),
+        json!({ "timestamp": "2026-01-04T10:00:00+00:00", "severity": "medium", "device_id": "tv" }),
+        json!({ "timestamp": "2025-11-01T10:00:00+00:00", "severity": "critical", "device_id": "tv" }),
+    ];
+    let now = chrono::DateTime::parse_from_rfc3339("2026-01-06T08:00:00+00:00").unwrap().with_timezone(&chrono::Utc);
+
+    // The late alert falls on the next day an hour east of UTC
+    let zone = clock::Zone::Named(chrono_tz::Europe::Paris);
+    let trend = widgets::alert_trend(&alerts, 3, zone, now);
+    let days: Vec<(&str, u64)> = trend.iter().map(|d| (d.date.as_str(), d.total)).collect();
+    assert_eq!(days, [("2026-01-04", 1), ("2026-01-05", 1), ("2026-01-06", 1)]);
+    assert_eq!(trend[1].severities.get("high"), Some(&1));
+
+    let devices = [
+        device("laptop", "192.168.1.2", "computer"),
+        device("tv", "192.168.1.3", "smart_tv"),
+        device("desktop", "192.168.1.4", "computer"),
+    ];
+
+    // Acknowledged and old alerts don't count
+    let risk = widgets::device_risk(&devices, &alerts, "2025-12-07T00:00:00");
+    let scores: Vec<(&str, u32)> = risk.iter().map(|r| (r.device_id.as_str(), r.score)).collect();
+    assert_eq!(scores, [("laptop", 23), ("tv", 8)]);
+
+    let bytes = std::collections::HashMap::from([
+        ("laptop".to_string(), (1000, 100)),
+        ("desktop".to_string(), (500, 50)),
+        ("tv".to_string(), (5000, 10)),
+    ]);
+    let groups = widgets::group_bandwidth(&devices, &bytes, |d| vec![d.device_type.clone()]);
+    let summary: Vec<(&str, u32, u64)> = groups.iter().map(|g| (g.group.as_str(), g.devices, g.bytes_in)).collect();
+    assert_eq!(summary, [("smart_tv", 1, 5000), ("computer", 2, 1500)]);
+
+    assert!(widgets::data("no_such_widget", &json!({}), &devices).is_err());
+    assert!(widgets::list().iter().any(|w| w.id == "bandwidth_by_group"));
+}
@@ -0,0 +1,96 @@
+// Threat intelligence feed matching
+//
+// The Python side downloads the configured IOC feeds and matches them against
+// captured traffic, raising alerts with feed attribution. This task keeps the
+// feeds current and has new captures checked while monitoring runs.
+
+use crate::python::run_python_script;
+use crate::state::AppState;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+const SCRIPT: &str = "python/alerts/threat_intel.py";
+
+/// How often new captures are matched against the feeds
+const SCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often feeds are checked for being due an update (each has its own interval)
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+fn run_threat_command(args: &[&str]) -> Result<Value, String> {
+    let result = run_python_script(SCRIPT, args)?;
+
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(result)
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+/// Configured feeds with their indicator counts and last update
+pub fn feeds() -> Result<Value, String> {
+    Ok(run_threat_command(&["--action", "feeds"])?
+        .get("feeds")
+        .cloned()
+        .unwrap_or(Value::Array(vec![])))
+}
+
+/// Download feeds that are due, or all of them with `force`
+pub fn update_feeds(force: bool) -> Result<Value, String> {
+    let mut args = vec!["--action", "update"];
+    if force {
+        args.push("--force");
+    }
+    Ok(run_threat_command(&args)?.get("updated").cloned().unwrap_or(Value::Array(vec![])))
+}
+
+/// Match the last `days` days of stored history against the feeds
+pub fn rescan_history(days: u32) -> Result<Value, String> {
+    let days = days.to_string();
+    run_threat_command(&["--action", "rescan", "--days", &days])
+}
+
+/// Spawn the background task that updates feeds and matches new captures
+pub fn spawn_threat_intel(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_update: Option<Instant> = None;
+
+        loop {
+            let handle = app.clone();
+            let update_due = last_update.is_none_or(|at| at.elapsed() >= UPDATE_CHECK_INTERVAL);
+
+            let ran = tauri::async_runtime::spawn_blocking(move || {
+                if !handle.state::<AppState>().lifecycle.current().is_running() {
+                    return false;
+                }
+
+                if update_due {
+                    match update_feeds(false) {
+                        Ok(updated) if updated.as_array().is_some_and(|u| !u.is_empty()) => {
+                            log::info!("Threat feeds updated: {}", updated)
+                        }
+                        Ok(_) => {}
+                        Err(e) => log::warn!("Threat feed update failed: {}", e),
+                    }
+                }
+                match run_threat_command(&["--action", "scan"]) {
+                    Ok(result) if result.get("alerts").and_then(|a| a.as_u64()).unwrap_or(0) > 0 => {
+                        log::warn!("Threat feed matches in new traffic: {}", result)
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::debug!("Threat feed scan failed: {}", e),
+                }
+                true
+            })
+            .await
+            .unwrap_or(false);
+
+            if ran && update_due {
+                last_update = Some(Instant::now());
+            }
+            tokio::time::sleep(SCAN_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,320 @@
+// SSL/TLS hygiene report
+//
+// The proxy records the TLS version, cipher and server certificate of the
+// connections it intercepts; the bypass detector records the highest version
+// each device offers in ClientHellos it sees passively, which also covers
+// traffic the proxy can't intercept. Both land in `tls_sessions` and are
+// summarized here. Expired and self-signed certificates and devices still
+// speaking TLS 1.0/1.1 raise alerts, at most once a day per finding.
+
+use crate::apps::UsageRange;
+use crate::clock;
+use crate::database::{self, open_readonly};
+use crate::event_bus;
+use crate::i18n::t;
+use crate::state::AppState;
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the background check runs
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Cipher name fragments that mark a cipher suite as weak
+const WEAK_CIPHER_MARKERS: &[&str] = &["RC4", "3DES", "DES-CBC", "_DES_", "NULL", "EXPORT", "EXP-", "MD5", "anon", "ADH"];
+
+/// Protocol versions considered obsolete
+const LEGACY_VERSIONS: &[&str] = &["SSLv2", "SSLv3", "TLSv1", "TLSv1.0", "TLSv1.1"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct VersionCount {
+    pub version: String,
+    pub sessions: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WeakCipher {
+    pub cipher: String,
+    pub hosts: Vec<String>,
+    pub devices: Vec<String>,
+    pub sessions: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CertificateIssue {
+    pub host: String,
+    /// "expired" or "self_signed"
+    pub issue: String,
+    pub subject: Option<String>,
+    pub issuer: Option<String>,
+    pub not_after: Option<String>,
+    pub devices: Vec<String>,
+    pub last_seen: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LegacyDevice {
+    pub device_id: String,
+    /// Obsolete versions the device used or offered at most
+    pub versions: Vec<String>,
+    pub hosts: Vec<String>,
+    pub sessions: u64,
+    pub last_seen: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TlsReport {
+    pub start: String,
+    pub end: String,
+    pub sessions: u64,
+    /// Negotiated (proxy) or highest offered (passive) versions
+    pub versions: Vec<VersionCount>,
+    pub weak_ciphers: Vec<WeakCipher>,
+    pub certificate_issues: Vec<CertificateIssue>,
+    pub legacy_devices: Vec<LegacyDevice>,
+}
+
+struct Session {
+    timestamp: String,
+    device: String,
+    host: String,
+    client_version: Option<String>,
+    server_version: Option<String>,
+    cipher: Option<String>,
+    subject: Option<String>,
+    issuer: Option<String>,
+    not_after: Option<String>,
+    self_signed: bool,
+}
+
+fn is_weak_cipher(cipher: &str) -> bool {
+    WEAK_CIPHER_MARKERS.iter().any(|m| cipher.contains(m))
+}
+
+fn is_legacy(version: &str) -> bool {
+    LEGACY_VERSIONS.contains(&version)
+}
+
+/// Whether the certificate had expired when the session was seen
+fn is_expired(not_after: &str, timestamp: &str) -> bool {
+    match (clock::parse_stored(not_after), clock::parse_stored(timestamp)) {
+        (Some(expires), Some(seen)) => expires < seen,
+        _ => false,
+    }
+}
+
+fn sessions(start: &str, end: &str) -> Result<Vec<Session>, String> {
+    let conn = open_readonly()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, COALESCE(device_id, device_ip), host, client_tls_version, server_tls_version,
+                    cipher, cert_subject, cert_issuer, cert_not_after, self_signed
+             FROM tls_sessions
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             ORDER BY timestamp",
+        )
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok(Session {
+                timestamp: row.get(0)?,
+                device: row.get(1)?,
+                host: row.get(2)?,
+                client_version: row.get(3)?,
+                server_version: row.get(4)?,
+                cipher: row.get(5)?,
+                subject: row.get(6)?,
+                issuer: row.get(7)?,
+                not_after: row.get(8)?,
+                self_signed: row.get::<_, Option<i64>>(9)?.unwrap_or(0) != 0,
+            })
+        })
+        .map_err(|e| format!("Failed to query TLS sessions: {}", e))?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read TLS session: {}", e))
+}
+
+/// Summarize TLS versions, weak ciphers, bad certificates and legacy-TLS devices in a range
+pub fn report(range: UsageRange) -> Result<TlsReport, String> {
+    let (start, end) = range.bounds();
+    let sessions = sessions(&start, &end)?;
+
+    let mut versions: HashMap<String, u64> = HashMap::new();
+    let mut ciphers: BTreeMap<String, (BTreeSet<String>, BTreeSet<String>, u64)> = BTreeMap::new();
+    let mut certs: BTreeMap<(String, &'static str), CertificateIssue> = BTreeMap::new();
+    let mut legacy: BTreeMap<String, LegacyDevice> = BTreeMap::new();
+
+    for s in &sessions {
+        let version = s.server_version.as_ref().or(s.client_version.as_ref());
+        if let Some(version) = version {
+            *versions.entry(version.clone()).or_default() += 1;
+        }
+
+        if let Some(cipher) = s.cipher.as_ref().filter(|c| is_weak_cipher(c)) {
+            let entry = ciphers.entry(cipher.clone()).or_default();
+            entry.0.insert(s.host.clone());
+            entry.1.insert(s.device.clone());
+            entry.2 += 1;
+        }
+
+        let expired = s.not_after.as_deref().is_some_and(|at| is_expired(at, &s.timestamp));
+        for (flagged, issue) in [(expired, "expired"), (s.self_signed, "self_signed")] {
+            if !flagged {
+                continue;
+            }
+            let entry = certs.entry((s.host.clone(), issue)).or_insert_with(|| CertificateIssue {
+                host: s.host.clone(),
+                issue: issue.to_string(),
+                subject: s.subject.clone(),
+                issuer: s.issuer.clone(),
+                not_after: s.not_after.clone(),
+                devices: Vec::new(),
+                last_seen: s.timestamp.clone(),
+            });
+            if !entry.devices.contains(&s.device) {
+                entry.devices.push(s.device.clone());
+            }
+            entry.last_seen = s.timestamp.clone();
+        }
+
+        // The device's own version: what it negotiated with the proxy, or offered on the wire
+        if let Some(version) = s.client_version.as_ref().filter(|v| is_legacy(v)) {
+            let entry = legacy.entry(s.device.clone()).or_insert_with(|| LegacyDevice {
+                device_id: s.device.clone(),
+                versions: Vec::new(),
+                hosts: Vec::new(),
+                sessions: 0,
+                last_seen: s.timestamp.clone(),
+            });
+            if !entry.versions.contains(version) {
+                entry.versions.push(version.clone());
+            }
+            if !entry.hosts.contains(&s.host) {
+                entry.hosts.push(s.host.clone());
+            }
+            entry.sessions += 1;
+            entry.last_seen = s.timestamp.clone();
+        }
+    }
+
+    let mut versions: Vec<VersionCount> = versions
+        .into_iter()
+        .map(|(version, sessions)| VersionCount { version, sessions })
+        .collect();
+    versions.sort_by_key(|v| std::cmp::Reverse(v.sessions));
+
+    let mut weak_ciphers: Vec<WeakCipher> = ciphers
+        .into_iter()
+        .map(|(cipher, (hosts, devices, sessions))| WeakCipher {
+            cipher,
+            hosts: hosts.into_iter().collect(),
+            devices: devices.into_iter().collect(),
+            sessions,
+        })
+        .collect();
+    weak_ciphers.sort_by_key(|c| std::cmp::Reverse(c.sessions));
+
+    let mut legacy_devices: Vec<LegacyDevice> = legacy.into_values().collect();
+    legacy_devices.sort_by_key(|d| std::cmp::Reverse(d.sessions));
+
+    Ok(TlsReport {
+        start,
+        end,
+        sessions: sessions.len() as u64,
+        versions,
+        weak_ciphers,
+        certificate_issues: certs.into_values().collect(),
+        legacy_devices,
+    })
+}
+
+/// Alert on bad certificates and legacy-TLS devices not already alerted in the last day
+fn raise_alerts(report: &TlsReport) {
+    let since = clock::utc_cutoff(chrono::Duration::hours(24));
+    let recent: HashSet<String> = database::stored_alerts()
+        .iter()
+        .filter(|a| a.get("timestamp").and_then(|t| t.as_str()).is_some_and(|t| t >= since.as_str()))
+        .filter_map(|a| a.pointer("/metadata/tls_finding").and_then(|f| f.as_str()).map(|f| f.to_string()))
+        .collect();
+
+    let mut alerts = Vec::new();
+    for issue in &report.certificate_issues {
+        let devices = issue.devices.join(", ");
+        let (title, description) = if issue.issue == "expired" {
+            let date = issue.not_after.clone().unwrap_or_else(|| t("common.earlier", &[]));
+            (
+                t("alert.tls_expired.title", &[("host", &issue.host)]),
+                t(
+                    "alert.tls_expired.description",
+                    &[("host", &issue.host), ("date", &date), ("devices", &devices)],
+                ),
+            )
+        } else {
+            (
+                t("alert.tls_self_signed.title", &[("host", &issue.host)]),
+                t("alert.tls_self_signed.description", &[("host", &issue.host), ("devices", &devices)]),
+            )
+        };
+        let mut metadata = json!(issue);
+        metadata["tls_finding"] = json!(format!("{}|{}", issue.issue, issue.host));
+        alerts.push(("high", title, description, None, metadata));
+    }
+
+    for device in &report.legacy_devices {
+        let description = t(
+            "alert.tls_legacy.description",
+            &[
+                ("device", &device.device_id),
+                ("versions", &device.versions.join(", ")),
+                ("sessions", &device.sessions),
+                ("hosts", &device.hosts.iter().take(3).cloned().collect::<Vec<_>>().join(", ")),
+            ],
+        );
+        let mut metadata = json!(device);
+        metadata["tls_finding"] = json!(format!("legacy|{}", device.device_id));
+        // Devices without a known ID are listed by IP, which can't be attached to the alert
+        let attach = device.device_id.parse::<std::net::IpAddr>().is_err().then_some(device.device_id.as_str());
+        alerts.push((
+            "medium",
+            t("alert.tls_legacy.title", &[("device", &device.device_id)]),
+            description,
+            attach,
+            metadata,
+        ));
+    }
+
+    for (severity, title, description, device, metadata) in alerts {
+        let finding = metadata["tls_finding"].as_str().unwrap_or_default().to_string();
+        if recent.contains(&finding) {
+            continue;
+        }
+        log::warn!("{}", title);
+        event_bus::raise_alert(severity, "security", &title, &description, device, Some(&metadata));
+    }
+}
+
+/// Spawn the background task that alerts on critical TLS findings while monitoring runs
+pub fn spawn_tls_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if !handle.state::<AppState>().lifecycle.current().is_running() {
+                    return;
+                }
+                match report(UsageRange::Last24Hours) {
+                    Ok(report) => raise_alerts(&report),
+                    Err(e) => log::debug!("TLS hygiene check failed: {}", e),
+                }
+            })
+            .await;
+        }
+    });
+}
@@ -0,0 +1,300 @@
+// Network topology graph built from scan, ARP and traceroute data
+//
+// Devices on the gateway's subnet hang off the gateway; off-subnet devices
+// (VLANs, double NAT) are reached through the hops a traceroute finds. A hop
+// that is a scanned device is drawn as that device: a switch when its vendor
+// makes infrastructure, a router otherwise. Pings and traceroutes run on a
+// small pool of threads, however many devices there are.
+
+use crate::commands::Device;
+use crate::python::run_python_script;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+use std::process::Command;
+use std::thread;
+
+/// Vendors whose devices are usually infrastructure rather than clients
+const INFRASTRUCTURE_VENDORS: &[&str] = &[
+    "cisco", "netgear", "tp-link", "ubiquiti", "aruba", "juniper", "mikrotik", "d-link", "zyxel",
+];
+
+/// Maximum hops traced towards off-subnet devices
+const MAX_TRACE_HOPS: &str = "5";
+
+/// Most pings or traceroutes running at once
+const MAX_PROBES: usize = 16;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TopologyNode {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+    pub ip: Option<String>,
+    pub mac: Option<String>,
+    pub subnet: Option<String>,
+    pub vendor: Option<String>,
+    pub device_type: Option<String>,
+    pub is_online: bool,
+    pub latency_ms: Option<f64>,
+    pub inferred: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TopologyLink {
+    pub source: String,
+    pub target: String,
+    pub kind: String,
+    pub latency_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SubnetGroup {
+    pub cidr: String,
+    pub gateway: Option<String>,
+    pub node_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkTopology {
+    pub nodes: Vec<TopologyNode>,
+    pub links: Vec<TopologyLink>,
+    pub subnets: Vec<SubnetGroup>,
+    pub generated_at: String,
+}
+
+/// Group an address into its /24 (the scanner's own assumption for home networks)
+fn subnet_of(ip: &str) -> Option<String> {
+    let addr: Ipv4Addr = ip.parse().ok()?;
+    let o = addr.octets();
+    Some(format!("{}.{}.{}.0/24", o[0], o[1], o[2]))
+}
+
+/// Extract a round-trip time from a line of `ping` output ("time=1.23 ms", "time<1ms")
+fn parse_ping_time(output: &str) -> Option<f64> {
+    output.lines().find_map(|line| {
+        let idx = line.find("time")?;
+        let rest = line[idx + 4..].trim_start_matches(['=', '<']);
+        let number: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        number.parse().ok()
+    })
+}
+
+/// Measure the RTT to a host with a single ping
+fn ping_latency(ip: &str) -> Option<f64> {
+    let output = if cfg!(windows) {
+        Command::new("ping").args(["-n", "1", "-w", "1000", ip]).output()
+    } else {
+        Command::new("ping").args(["-c", "1", "-W", "1", ip]).output()
+    }
+    .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    parse_ping_time(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Trace the route to a host, returning intermediate hop addresses (excluding the target)
+fn trace_hops(ip: &str) -> Vec<String> {
+    let output = if cfg!(windows) {
+        Command::new("tracert").args(["-d", "-h", MAX_TRACE_HOPS, "-w", "500", ip]).output()
+    } else {
+        Command::new("traceroute").args(["-n", "-m", MAX_TRACE_HOPS, "-w", "1", ip]).output()
+    };
+
+    let Ok(output) = output else {
+        return vec![];
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            line.split_whitespace()
+                .find_map(|token| token.trim_matches(['[', ']']).parse::<Ipv4Addr>().ok())
+        })
+        .map(|addr| addr.to_string())
+        .filter(|hop| hop != ip)
+        .collect()
+}
+
+fn is_infrastructure(device: &Device) -> bool {
+    let vendor = device.vendor.as_deref().unwrap_or("").to_lowercase();
+    INFRASTRUCTURE_VENDORS.iter().any(|v| vendor.contains(v))
+}
+
+/// Probe every item on at most MAX_PROBES threads, keeping the input order
+fn probe_all<T: Sync, R: Send + Default>(items: &[T], probe: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let per_worker = items.len().div_ceil(MAX_PROBES).max(1);
+    let probe = &probe;
+    thread::scope(|scope| {
+        let workers: Vec<_> = items
+            .chunks(per_worker)
+            .map(|chunk| (chunk.len(), scope.spawn(move || chunk.iter().map(probe).collect::<Vec<R>>())))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|(len, worker)| worker.join().unwrap_or_else(|_| (0..len).map(|_| R::default()).collect()))
+            .collect()
+    })
+}
+
+/// Build the topology graph for the given devices
+pub fn build_topology(devices: &[Device]) -> NetworkTopology {
+    let gateway = run_python_script("python/utils/network_utils.py", &["--action", "get-gateway"]).ok();
+    let gateway_ip = gateway
+        .as_ref()
+        .and_then(|g| g.get("gateway_ip"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let gateway_mac = gateway
+        .as_ref()
+        .and_then(|g| g.get("gateway_mac"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let gateway_subnet = gateway_ip.as_deref().and_then(subnet_of);
+
+    // The gateway shows up in scans too; don't draw it twice
+    let devices: Vec<&Device> = devices.iter().filter(|d| Some(&d.ip) != gateway_ip.as_ref()).collect();
+
+    // A LAN ping is the last-hop latency
+    let latencies = probe_all(&devices, |d| if d.is_online { ping_latency(&d.ip) } else { None });
+    let routes = probe_all(&devices, |d| {
+        let off_subnet = gateway_ip.is_some() && subnet_of(&d.ip) != gateway_subnet;
+        if off_subnet { trace_hops(&d.ip) } else { vec![] }
+    });
+
+    let mut nodes = Vec::new();
+    let mut links: Vec<TopologyLink> = Vec::new();
+    let mut subnets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    if let Some(ref ip) = gateway_ip {
+        nodes.push(TopologyNode {
+            id: "gateway".to_string(),
+            kind: "gateway".to_string(),
+            label: format!("Gateway ({})", ip),
+            ip: Some(ip.clone()),
+            mac: gateway_mac.clone(),
+            subnet: gateway_subnet.clone(),
+            vendor: None,
+            device_type: Some("router".to_string()),
+            is_online: true,
+            latency_ms: ping_latency(ip),
+            inferred: false,
+        });
+        if let Some(ref subnet) = gateway_subnet {
+            subnets.entry(subnet.clone()).or_default().push("gateway".to_string());
+        }
+    }
+
+    for (device, latency) in devices.iter().zip(&latencies) {
+        let subnet = subnet_of(&device.ip);
+        nodes.push(TopologyNode {
+            id: device.id.clone(),
+            kind: "device".to_string(),
+            label: device.hostname.clone().unwrap_or_else(|| device.ip.clone()),
+            ip: Some(device.ip.clone()),
+            mac: Some(device.mac.clone()),
+            subnet: subnet.clone(),
+            vendor: device.vendor.clone(),
+            device_type: Some(device.device_type.clone()),
+            is_online: device.is_online,
+            latency_ms: *latency,
+            inferred: false,
+        });
+        if let Some(s) = subnet {
+            subnets.entry(s).or_default().push(device.id.clone());
+        }
+    }
+
+    let scanned: HashMap<&str, &Device> = devices.iter().map(|d| (d.ip.as_str(), *d)).collect();
+
+    if gateway_ip.is_some() {
+        for ((device, latency), hops) in devices.iter().zip(&latencies).zip(routes) {
+            if subnet_of(&device.ip) == gateway_subnet {
+                links.push(TopologyLink {
+                    source: "gateway".to_string(),
+                    target: device.id.clone(),
+                    kind: "lan".to_string(),
+                    latency_ms: *latency,
+                });
+                continue;
+            }
+
+            // Off-subnet (VLAN / double NAT): chain the traced hops between gateway and device
+            let mut previous = "gateway".to_string();
+            for hop in hops {
+                if Some(&hop) == gateway_ip.as_ref() {
+                    continue;
+                }
+                let hop_id = match scanned.get(hop.as_str()) {
+                    Some(hop_device) => hop_device.id.clone(),
+                    None => format!("hop-{}", hop),
+                };
+                if hop_id == device.id {
+                    continue;
+                }
+                if !nodes.iter().any(|n| n.id == hop_id) {
+                    nodes.push(TopologyNode {
+                        id: hop_id.clone(),
+                        kind: "router".to_string(),
+                        label: hop.clone(),
+                        ip: Some(hop.clone()),
+                        mac: None,
+                        subnet: subnet_of(&hop),
+                        vendor: None,
+                        device_type: Some("router".to_string()),
+                        is_online: true,
+                        latency_ms: None,
+                        inferred: true,
+                    });
+                }
+                if !links.iter().any(|l| l.source == previous && l.target == hop_id) {
+                    links.push(TopologyLink {
+                        source: previous.clone(),
+                        target: hop_id.clone(),
+                        kind: "route".to_string(),
+                        latency_ms: None,
+                    });
+                }
+                previous = hop_id;
+            }
+            links.push(TopologyLink {
+                source: previous,
+                target: device.id.clone(),
+                kind: "route".to_string(),
+                latency_ms: *latency,
+            });
+        }
+    }
+
+    // Scanned devices that traffic was traced through are infrastructure
+    for node in nodes.iter_mut() {
+        let Some(device) = devices.iter().find(|d| d.id == node.id) else { continue };
+        if links.iter().any(|l| l.source == node.id) {
+            let switch = is_infrastructure(device) && device.device_type != "router";
+            node.kind = if switch { "switch" } else { "router" }.to_string();
+            node.inferred = true;
+        }
+    }
+
+    let subnets = subnets
+        .into_iter()
+        .map(|(cidr, node_ids)| SubnetGroup {
+            gateway: if Some(&cidr) == gateway_subnet.as_ref() { gateway_ip.clone() } else { None },
+            cidr,
+            node_ids,
+        })
+        .collect();
+
+    NetworkTopology {
+        nodes,
+        links,
+        subnets,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    }
+}
@@ -0,0 +1,218 @@
+// Tracker and ad-tech exposure report
+//
+// Contacted hosts (proxy traffic, its rollups and DNS lookups) are matched
+// against a tracker database in DuckDuckGo Tracker Radar (`tds.json`) format,
+// then summed per owning company. A trimmed database ships in
+// `config/tracker_radar.json`; dropping the full `tds.json` there in its place
+// extends coverage without code changes.
+
+use crate::apps::UsageRange;
+use crate::database::open_readonly;
+use crate::python::get_project_root;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+
+#[derive(Debug, Deserialize, Clone)]
+struct TrackerOwner {
+    name: String,
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct Tracker {
+    owner: Option<TrackerOwner>,
+    #[serde(default)]
+    categories: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct TrackerDatabase {
+    #[serde(default)]
+    trackers: HashMap<String, Tracker>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CompanyExposure {
+    pub company: String,
+    pub display_name: String,
+    pub categories: Vec<String>,
+    pub requests: u64,
+    pub tracker_domains: Vec<String>,
+    pub devices: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceTrackerSummary {
+    pub device_id: String,
+    pub requests: u64,
+    pub tracker_requests: u64,
+    pub companies: usize,
+    /// Display names of the companies contacted most, busiest first
+    pub top_companies: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrackerReport {
+    pub device_id: Option<String>,
+    pub start: String,
+    pub end: String,
+    pub total_requests: u64,
+    pub tracker_requests: u64,
+    /// Share of requests that went to trackers, 0.0–1.0
+    pub tracker_share: f64,
+    pub companies: Vec<CompanyExposure>,
+    /// Per-device breakdown (only when the report covers all devices)
+    pub devices: Vec<DeviceTrackerSummary>,
+}
+
+fn load_database() -> Result<TrackerDatabase, String> {
+    let path = get_project_root().join("config").join("tracker_radar.json");
+    if !path.exists() {
+        return Ok(TrackerDatabase::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read tracker database: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse tracker database: {}", e))
+}
+
+/// Find the tracker entry for a host: the host itself or its nearest listed parent
+fn lookup<'a>(db: &'a TrackerDatabase, host: &str) -> Option<(&'a str, &'a Tracker)> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let mut candidate = host.as_str();
+    loop {
+        if let Some((domain, tracker)) = db.trackers.get_key_value(candidate) {
+            return Some((domain.as_str(), tracker));
+        }
+        candidate = candidate.split_once('.')?.1;
+    }
+}
+
+#[derive(Default)]
+struct CompanyAccumulator {
+    display_name: String,
+    categories: BTreeSet<String>,
+    requests: u64,
+    domains: BTreeSet<String>,
+    devices: HashMap<String, u64>,
+}
+
+/// Which tracker companies a device (or every device) talked to, and how often
+pub fn report(device_id: Option<&str>, range: UsageRange) -> Result<TrackerReport, String> {
+    let db = load_database()?;
+    let conn = open_readonly()?;
+    let (start, end) = range.bounds();
+
+    let mut companies: HashMap<String, CompanyAccumulator> = HashMap::new();
+    let mut device_requests: HashMap<String, (u64, u64)> = HashMap::new();
+
+    // DNS lookups count too: trackers in apps the proxy can't decrypt still resolve their hosts
+    let queries = [
+        "SELECT COALESCE(device_id, device_ip), host, COUNT(*)
+         FROM traffic
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_id = ?3)
+         GROUP BY 1, host",
+        "SELECT COALESCE(device_id, device_ip), host, SUM(requests)
+         FROM traffic_aggregates
+         WHERE minute >= substr(?1, 1, 16) AND minute < ?2 AND (?3 IS NULL OR device_id = ?3)
+         GROUP BY 1, host",
+        "SELECT COALESCE(device_id, device_ip), query_name, COUNT(*)
+         FROM dns_queries
+         WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_id = ?3)
+         GROUP BY 1, query_name",
+    ];
+
+    for sql in queries {
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map(params![start, end, device_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to query traffic: {}", e))?;
+
+        for row in rows {
+            let (device, host, count) = row.map_err(|e| format!("Failed to read traffic: {}", e))?;
+            let count = count.max(0) as u64;
+            let totals = device_requests.entry(device.clone()).or_default();
+            totals.0 += count;
+
+            let Some((domain, tracker)) = lookup(&db, &host) else {
+                continue;
+            };
+            let Some(owner) = &tracker.owner else {
+                continue;
+            };
+            totals.1 += count;
+
+            let company = companies.entry(owner.name.clone()).or_default();
+            company.display_name = owner.display_name.clone().unwrap_or_else(|| owner.name.clone());
+            company.categories.extend(tracker.categories.iter().cloned());
+            company.requests += count;
+            company.domains.insert(domain.to_string());
+            *company.devices.entry(device).or_default() += count;
+        }
+    }
+
+    let mut devices: Vec<DeviceTrackerSummary> = Vec::new();
+    if device_id.is_none() {
+        for (device, (requests, tracker_requests)) in &device_requests {
+            let mut contacted: Vec<(&CompanyAccumulator, u64)> = companies
+                .values()
+                .filter_map(|c| c.devices.get(device).map(|n| (c, *n)))
+                .collect();
+            contacted.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+
+            devices.push(DeviceTrackerSummary {
+                device_id: device.clone(),
+                requests: *requests,
+                tracker_requests: *tracker_requests,
+                companies: contacted.len(),
+                top_companies: contacted.iter().take(5).map(|(c, _)| c.display_name.clone()).collect(),
+            });
+        }
+        devices.sort_by_key(|d| std::cmp::Reverse(d.tracker_requests));
+    }
+
+    let mut companies: Vec<CompanyExposure> = companies
+        .into_iter()
+        .map(|(name, acc)| {
+            let mut company_devices: Vec<(String, u64)> = acc.devices.into_iter().collect();
+            company_devices.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+            CompanyExposure {
+                company: name,
+                display_name: acc.display_name,
+                categories: acc.categories.into_iter().collect(),
+                requests: acc.requests,
+                tracker_domains: acc.domains.into_iter().collect(),
+                devices: company_devices.into_iter().map(|(d, _)| d).collect(),
+            }
+        })
+        .collect();
+    companies.sort_by_key(|c| std::cmp::Reverse(c.requests));
+
+    let total_requests: u64 = device_requests.values().map(|(n, _)| n).sum();
+    let tracker_requests: u64 = device_requests.values().map(|(_, n)| n).sum();
+
+    Ok(TrackerReport {
+        device_id: device_id.map(|d| d.to_string()),
+        start,
+        end,
+        total_requests,
+        tracker_requests,
+        tracker_share: if total_requests > 0 {
+            ((tracker_requests as f64 / total_requests as f64) * 1000.0).round() / 1000.0
+        } else {
+            0.0
+        },
+        companies,
+        devices,
+    })
+}
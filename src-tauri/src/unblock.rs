@@ -0,0 +1,350 @@
+// Unblock request workflow
+//
+// The block page's form posts back through the proxy, which queues the request
+// in `unblock_requests.jsonl`. A background task turns queued requests into
+// `unblock_request` alerts and notifies the parent, who can approve (for a
+// while, or for good) or deny them from the app. Approval allowlists the
+// domain, as a temporary rule for time-boxed approvals.
+//
+// Decisions are kept next to the alert store in `unblock_decisions.json`.
+
+use crate::audit;
+use crate::clock;
+use crate::commands::{broadcast_to_processes, load_settings};
+use crate::database;
+use crate::i18n::t;
+use crate::python::{create_alert, run_alert_command, run_blocking_command, run_python_script};
+use crate::state::AppState;
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often queued requests are collected
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+pub const UNBLOCK_REQUEST_CATEGORY: &str = "unblock_request";
+
+/// Frontend event for each new request
+const UNBLOCK_REQUEST_EVENT: &str = "unblock-request";
+
+/// Serializes read-modify-write of the decisions file
+static DECISIONS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UnblockStatus {
+    Pending,
+    Approved,
+    Denied,
+    /// A time-boxed approval that has run out
+    Expired,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Decision {
+    alert_id: String,
+    host: String,
+    status: UnblockStatus,
+    decided_at: String,
+    expires_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct DecisionsFile {
+    #[serde(default)]
+    decisions: Vec<Decision>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UnblockRequest {
+    pub id: String,
+    pub timestamp: String,
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub host: String,
+    pub url: String,
+    pub reason: String,
+    pub category: String,
+    pub message: String,
+    pub status: UnblockStatus,
+    pub decided_at: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+fn get_queue_path() -> PathBuf {
+    workspace::data_dir().join("unblock_requests.jsonl")
+}
+
+fn get_decisions_path() -> PathBuf {
+    workspace::data_dir().join("unblock_decisions.json")
+}
+
+fn load_decisions() -> Result<DecisionsFile, String> {
+    let path = get_decisions_path();
+    if !path.exists() {
+        return Ok(DecisionsFile::default());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read unblock decisions: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse unblock decisions: {}", e))
+}
+
+fn save_decisions(file: &DecisionsFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize unblock decisions: {}", e))?;
+    fs::write(get_decisions_path(), content).map_err(|e| format!("Failed to write unblock decisions: {}", e))
+}
+
+fn check_success(result: Value) -> Result<(), String> {
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(())
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+fn notify(title: &str, message: &str) {
+    if !load_settings().map(|s| s.notifications_enabled).unwrap_or(true) {
+        return;
+    }
+
+    let args = ["--action", "send", "--title", title, "--message", message, "--severity", "low"];
+    if let Err(e) = run_python_script("python/alerts/notifier.py", &args) {
+        log::warn!("Failed to send unblock request notification: {}", e);
+    }
+}
+
+/// Turn queued unblock requests into alerts and notify about them
+fn collect_requests(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    let queue = get_queue_path();
+    let processing = queue.with_extension("jsonl.processing");
+
+    // Move the queue aside first so requests arriving meanwhile aren't lost.
+    // A leftover batch from an interrupted pass is finished first.
+    if !processing.exists() {
+        if !queue.exists() {
+            return Ok(());
+        }
+        fs::rename(&queue, &processing).map_err(|e| format!("Failed to take unblock requests: {}", e))?;
+    }
+    let content = fs::read_to_string(&processing).map_err(|e| format!("Failed to read unblock requests: {}", e))?;
+    let devices = state.snapshot.devices(false)?.data;
+
+    let mut created = Vec::new();
+    for request in content.lines().filter_map(|l| serde_json::from_str::<Value>(l).ok()) {
+        let text = |key: &str| request.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let host = text("host");
+        let device = devices.iter().find(|d| d.ip == text("device_ip"));
+        let device_name = device
+            .and_then(|d| d.hostname.clone())
+            .unwrap_or_else(|| text("device_ip"));
+
+        let (reason, message) = (text("reason"), text("message"));
+        let mut args: Vec<(&str, &dyn std::fmt::Display)> =
+            vec![("device", &device_name), ("host", &host), ("reason", &reason)];
+        let description = if message.is_empty() {
+            t("alert.unblock_request.description", &args)
+        } else {
+            args.push(("message", &message));
+            t("alert.unblock_request.description_message", &args)
+        };
+
+        match create_alert(
+            "low",
+            UNBLOCK_REQUEST_CATEGORY,
+            &t("alert.unblock_request.title", &[("host", &host)]),
+            &description,
+            device.map(|d| d.id.as_str()),
+            Some(&request),
+        ) {
+            Ok(result) => {
+                if let Some(request) = result.get("alert").and_then(request_from_alert) {
+                    created.push(request);
+                }
+            }
+            Err(e) => log::warn!("Failed to record unblock request for {}: {}", host, e),
+        }
+    }
+
+    fs::remove_file(&processing).map_err(|e| format!("Failed to clear unblock requests: {}", e))?;
+
+    for request in &created {
+        if let Err(e) = app.emit(UNBLOCK_REQUEST_EVENT, request) {
+            log::warn!("Failed to emit unblock request: {}", e);
+        }
+    }
+    match created.as_slice() {
+        [] => {}
+        [request] => notify("Unblock request", &format!("Someone asked to unblock {}", request.host)),
+        requests => notify("Unblock requests", &format!("{} new requests to unblock sites", requests.len())),
+    }
+
+    Ok(())
+}
+
+fn request_from_alert(alert: &Value) -> Option<UnblockRequest> {
+    if alert.get("category").and_then(|c| c.as_str()) != Some(UNBLOCK_REQUEST_CATEGORY) {
+        return None;
+    }
+
+    let text = |pointer: &str| alert.pointer(pointer).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    Some(UnblockRequest {
+        id: text("/id"),
+        timestamp: text("/timestamp"),
+        device_id: alert.get("source_device").and_then(|d| d.as_str()).map(|d| d.to_string()),
+        device_ip: text("/metadata/device_ip"),
+        host: text("/metadata/host"),
+        url: text("/metadata/url"),
+        reason: text("/metadata/reason"),
+        category: text("/metadata/category"),
+        message: text("/metadata/message"),
+        status: UnblockStatus::Pending,
+        decided_at: None,
+        expires_at: None,
+    })
+}
+
+/// Unblock requests, newest first, optionally only those with one status
+pub fn requests(status: Option<UnblockStatus>) -> Result<Vec<UnblockRequest>, String> {
+    let decisions = load_decisions()?.decisions;
+    let now = clock::now_utc();
+
+    let mut requests: Vec<UnblockRequest> = database::stored_alerts()
+        .iter()
+        .filter_map(request_from_alert)
+        .map(|mut request| {
+            if let Some(decision) = decisions.iter().find(|d| d.alert_id == request.id) {
+                let expired = decision.expires_at.as_ref().is_some_and(|at| *at <= now);
+                request.status = match decision.status {
+                    UnblockStatus::Approved if expired => UnblockStatus::Expired,
+                    status => status,
+                };
+                request.decided_at = Some(decision.decided_at.clone());
+                request.expires_at = decision.expires_at.clone();
+            }
+            request
+        })
+        .filter(|r| status.is_none_or(|s| r.status == s))
+        .collect();
+
+    requests.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    Ok(requests)
+}
+
+fn pending_request(id: &str) -> Result<UnblockRequest, String> {
+    let request = requests(None)?
+        .into_iter()
+        .find(|r| r.id == id)
+        .ok_or_else(|| format!("Unblock request not found: {}", id))?;
+
+    if request.status != UnblockStatus::Pending {
+        return Err(format!("Unblock request was already {:?}", request.status).to_lowercase());
+    }
+    if request.host.is_empty() {
+        return Err("Unblock request has no host".to_string());
+    }
+    Ok(request)
+}
+
+fn record_decision(request: &UnblockRequest, status: UnblockStatus, expires_at: Option<String>) -> Result<(), String> {
+    {
+        let _guard = DECISIONS_LOCK.lock().unwrap();
+        let mut file = load_decisions()?;
+        file.decisions.retain(|d| d.alert_id != request.id);
+        file.decisions.push(Decision {
+            alert_id: request.id.clone(),
+            host: request.host.clone(),
+            status,
+            decided_at: clock::now_utc(),
+            expires_at,
+        });
+        save_decisions(&file)?;
+    }
+
+    run_alert_command("acknowledge", &[("--id", &request.id)])?;
+    Ok(())
+}
+
+/// Extra proxy arguments that let allowlisted domains through at startup
+pub fn proxy_args() -> Vec<String> {
+    match run_blocking_command("config", &[]) {
+        Ok(result) => result
+            .pointer("/config/whitelisted_domains")
+            .and_then(|d| d.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|d| d.as_str())
+            .flat_map(|domain| ["--allow".to_string(), domain.to_string()])
+            .collect(),
+        Err(e) => {
+            log::warn!("Failed to load allowed domains: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Approve a request: allow its domain, for `duration_minutes` or (None) for good
+pub fn approve(state: &AppState, id: &str, duration_minutes: Option<u32>) -> Result<(), String> {
+    let request = pending_request(id)?;
+
+    let duration = duration_minutes.map(|minutes| minutes.to_string());
+    let mut args = vec![("--domain", request.host.as_str())];
+    if let Some(ref minutes) = duration {
+        args.push(("--duration", minutes));
+    }
+
+    let result = run_blocking_command("whitelist", &args)?;
+    let expires_at = result.get("expires_at").and_then(|e| e.as_str()).map(|e| e.to_string());
+    check_success(result)?;
+    broadcast_to_processes(state, &json!({ "action": "allow_domain", "domain": request.host }));
+
+    record_decision(&request, UnblockStatus::Approved, expires_at.clone())?;
+
+    let summary = match expires_at {
+        Some(ref until) => format!("Approved unblock request for {} until {}", request.host, until),
+        None => format!("Approved unblock request for {}", request.host),
+    };
+    if let Err(e) = audit::record("unblock", &summary, json!(request)) {
+        log::warn!("Failed to audit unblock approval: {}", e);
+    }
+
+    log::info!("{}", summary);
+    Ok(())
+}
+
+/// Deny a request, leaving its domain blocked
+pub fn deny(id: &str) -> Result<(), String> {
+    let request = pending_request(id)?;
+    record_decision(&request, UnblockStatus::Denied, None)?;
+
+    let summary = format!("Denied unblock request for {}", request.host);
+    if let Err(e) = audit::record("unblock", &summary, json!(request)) {
+        log::warn!("Failed to audit unblock denial: {}", e);
+    }
+
+    log::info!("{}", summary);
+    Ok(())
+}
+
+/// Spawn the background task that collects unblock requests
+pub fn spawn_unblock_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if let Err(e) = collect_requests(&handle, &handle.state::<AppState>()) {
+                    log::debug!("Unblock request collection failed: {}", e);
+                }
+            })
+            .await;
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
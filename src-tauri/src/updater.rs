@@ -0,0 +1,177 @@
+// In-app update checks and self-update
+//
+// Releases publish a signed `latest.json` manifest per channel. Checking asks
+// the channel's manifest whether a newer build exists and returns its
+// changelog; downloading fetches the bundle, which the updater verifies against
+// the public key in tauri.conf.json before it is kept; installing replaces the
+// running build and restarts. A download that fails verification is discarded.
+// Builds without a public key configured can't verify anything, so checking
+// and downloading refuse to run in them.
+
+use crate::commands::load_settings;
+use crate::i18n::t;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// Event emitted while an update downloads
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "update-download-progress";
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/Vasanthakumar5M/network-management-system/releases/latest/download/latest.json";
+
+/// Beta builds are published as prereleases, which `releases/latest` skips
+const BETA_ENDPOINT: &str =
+    "https://github.com/Vasanthakumar5M/network-management-system/releases/download/updater-beta/latest.json";
+
+/// The update found by the last check, and its bundle once downloaded
+static PENDING: Mutex<Option<(Update, Option<Vec<u8>>)>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub channel: String,
+    pub current_version: String,
+    pub version: Option<String>,
+    pub date: Option<String>,
+    /// Release notes of the new version
+    pub changelog: Option<String>,
+    pub downloaded: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct DownloadProgress {
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+fn endpoint(channel: &str) -> Result<&'static str, String> {
+    match channel {
+        "stable" => Ok(STABLE_ENDPOINT),
+        "beta" => Ok(BETA_ENDPOINT),
+        other => Err(t("error.unknown_update_channel", &[("channel", &other)])),
+    }
+}
+
+/// Refuse to update unless this build has the release public key to verify bundles with
+fn ensure_pubkey(app: &AppHandle) -> Result<(), String> {
+    let pubkey = app
+        .config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|key| key.as_str())
+        .unwrap_or("");
+    if pubkey.trim().is_empty() {
+        Err(t("error.updater_not_configured", &[]))
+    } else {
+        Ok(())
+    }
+}
+
+/// Ask the configured channel for a newer build
+pub async fn check(app: &AppHandle) -> Result<UpdateInfo, String> {
+    ensure_pubkey(app)?;
+    let channel = load_settings()?.update_channel;
+    let url = endpoint(&channel)?
+        .parse()
+        .map_err(|e| format!("Invalid update endpoint: {}", e))?;
+
+    let update = app
+        .updater_builder()
+        .endpoints(vec![url])
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to set up updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let info = UpdateInfo {
+        available: update.is_some(),
+        channel,
+        current_version: app.package_info().version.to_string(),
+        version: update.as_ref().map(|u| u.version.clone()),
+        date: update.as_ref().and_then(|u| u.date).map(|d| d.to_string()),
+        changelog: update.as_ref().and_then(|u| u.body.clone()),
+        downloaded: false,
+    };
+
+    let mut pending = PENDING.lock().map_err(|e| e.to_string())?;
+    *pending = update.map(|u| {
+        // Keep an earlier download of the same version
+        let bytes = pending
+            .take()
+            .filter(|(previous, _)| previous.version == u.version)
+            .and_then(|(_, bytes)| bytes);
+        (u, bytes)
+    });
+
+    Ok(UpdateInfo {
+        downloaded: pending.as_ref().is_some_and(|(_, bytes)| bytes.is_some()),
+        ..info
+    })
+}
+
+/// Download and verify the update found by the last check
+pub async fn download(app: &AppHandle) -> Result<UpdateInfo, String> {
+    ensure_pubkey(app)?;
+    let checked = PENDING.lock().map_err(|e| e.to_string())?.as_ref().map(|(update, _)| update.clone());
+    let update = match checked {
+        Some(update) => update,
+        None => {
+            check(app).await?;
+            PENDING
+                .lock()
+                .map_err(|e| e.to_string())?
+                .as_ref()
+                .map(|(update, _)| update.clone())
+                .ok_or("No update available")?
+        }
+    };
+
+    log::info!("Downloading update {}", update.version);
+    let mut downloaded: u64 = 0;
+    let bytes = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app.emit(DOWNLOAD_PROGRESS_EVENT, DownloadProgress { downloaded, total });
+            },
+            || log::info!("Update download finished"),
+        )
+        .await
+        .map_err(|e| format!("Failed to download update: {}", e))?;
+
+    let info = UpdateInfo {
+        available: true,
+        channel: load_settings()?.update_channel,
+        current_version: app.package_info().version.to_string(),
+        version: Some(update.version.clone()),
+        date: update.date.map(|d| d.to_string()),
+        changelog: update.body.clone(),
+        downloaded: true,
+    };
+    *PENDING.lock().map_err(|e| e.to_string())? = Some((update, Some(bytes)));
+    Ok(info)
+}
+
+/// Install the downloaded update and restart into it
+pub fn install(app: &AppHandle) -> Result<(), String> {
+    let mut pending = PENDING.lock().map_err(|e| e.to_string())?;
+    let (update, bytes) = match pending.take() {
+        Some((update, Some(bytes))) => (update, bytes),
+        other => {
+            *pending = other;
+            return Err(t("error.no_update_downloaded", &[]));
+        }
+    };
+    drop(pending);
+
+    log::info!("Installing update {}", update.version);
+    update
+        .install(&bytes)
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+    app.restart()
+}
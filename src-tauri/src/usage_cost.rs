@@ -0,0 +1,297 @@
+// Metered connection cost
+//
+// On a metered connection, the bytes the network moved are what it costs. The
+// cost model in settings gives a price per GB (10^9 bytes, as providers
+// count), an optional monthly data cap and the day of the month the billing
+// cycle starts. Usage is what the passive capture counted, or what the proxy
+// recorded if that is more; the month-end projection extends the cycle's
+// average rate so far to its end. A background check raises an alert once a
+// cycle when the projection goes over the cap, and again when usage does.
+
+use crate::apps::UsageRange;
+use crate::clock::{self, Zone};
+use crate::commands::load_settings;
+use crate::event_bus;
+use crate::i18n::t;
+use crate::protocols;
+use crate::workspace;
+use chrono::{Datelike, Months, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How often usage is checked against the cap
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Bytes in a billed GB
+const GB: f64 = 1e9;
+
+/// Shortest part of a cycle a projection is made from, so a quiet first hour doesn't extrapolate wildly
+const MIN_ELAPSED_SECS: i64 = 3600;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct CostModel {
+    /// Estimate costs and watch the cap
+    pub metered: bool,
+    pub price_per_gb: f64,
+    /// Shown with costs ("USD", "EUR", ...)
+    pub currency: String,
+    /// Data included per billing cycle, in GB (None for no cap)
+    pub monthly_cap_gb: Option<f64>,
+    /// Day of the month the billing cycle starts, 1-28
+    pub billing_cycle_day: u32,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            metered: false,
+            price_per_gb: 0.0,
+            currency: "USD".to_string(),
+            monthly_cap_gb: None,
+            billing_cycle_day: 1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UsageCost {
+    pub metered: bool,
+    pub currency: String,
+    /// The requested range as UTC bounds (the billing cycle so far by default)
+    pub start: String,
+    pub end: String,
+    pub bytes: u64,
+    pub cost: f64,
+    /// The current billing cycle as UTC bounds
+    pub cycle_start: String,
+    pub cycle_end: String,
+    pub cycle_bytes: u64,
+    pub cycle_cost: f64,
+    /// Bytes by the end of the cycle at the rate so far
+    pub projected_bytes: u64,
+    pub projected_cost: f64,
+    pub cap_bytes: Option<u64>,
+    /// Share of the cap used so far, 0-100+
+    pub cap_used_percent: Option<f64>,
+    pub projected_over_cap: bool,
+}
+
+/// Alerts raised for the current cycle
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct CapAlerts {
+    cycle_start: String,
+    projected: bool,
+    exceeded: bool,
+}
+
+pub fn validate(model: &CostModel) -> Result<(), String> {
+    if !model.price_per_gb.is_finite() || model.price_per_gb < 0.0 {
+        return Err(format!("Invalid price per GB: {}", model.price_per_gb));
+    }
+    if let Some(cap) = model.monthly_cap_gb {
+        if !cap.is_finite() || cap <= 0.0 {
+            return Err(format!("Invalid monthly data cap: {} GB", cap));
+        }
+    }
+    if !(1..=28).contains(&model.billing_cycle_day) {
+        return Err(format!(
+            "Billing cycle day must be between 1 and 28, got {}",
+            model.billing_cycle_day
+        ));
+    }
+    if model.currency.trim().is_empty() {
+        return Err("Currency can't be empty".to_string());
+    }
+    Ok(())
+}
+
+/// Start and end of the billing cycle `now` falls in, as wall-clock times
+pub fn billing_cycle(now: NaiveDateTime, day: u32) -> (NaiveDateTime, NaiveDateTime) {
+    let today = now.date();
+    let this_month = today.with_day(day.clamp(1, 28)).unwrap_or(today);
+    let start = if today >= this_month { this_month } else { this_month - Months::new(1) };
+    (start.and_time(NaiveTime::MIN), (start + Months::new(1)).and_time(NaiveTime::MIN))
+}
+
+/// Bytes by the end of a cycle if it goes on at the average rate so far
+pub fn project(used: u64, elapsed: chrono::Duration, length: chrono::Duration) -> u64 {
+    let elapsed = elapsed.num_seconds().max(MIN_ELAPSED_SECS);
+    let length = length.num_seconds().max(elapsed);
+    (used as f64 * length as f64 / elapsed as f64).round() as u64
+}
+
+fn cost(model: &CostModel, bytes: u64) -> f64 {
+    (bytes as f64 / GB * model.price_per_gb * 100.0).round() / 100.0
+}
+
+/// Costs and cap use from measured bytes; `elapsed` and `length` describe the cycle
+pub fn estimate(
+    model: &CostModel,
+    (start, end, bytes): (String, String, u64),
+    (cycle_start, cycle_end, cycle_bytes): (String, String, u64),
+    elapsed: chrono::Duration,
+    length: chrono::Duration,
+) -> UsageCost {
+    let projected_bytes = project(cycle_bytes, elapsed, length);
+    let cap_bytes = model.monthly_cap_gb.map(|gb| (gb * GB) as u64);
+    UsageCost {
+        metered: model.metered,
+        currency: model.currency.clone(),
+        start,
+        end,
+        bytes,
+        cost: cost(model, bytes),
+        cycle_start,
+        cycle_end,
+        cycle_bytes,
+        cycle_cost: cost(model, cycle_bytes),
+        projected_bytes,
+        projected_cost: cost(model, projected_bytes),
+        cap_bytes,
+        cap_used_percent: cap_bytes.map(|cap| cycle_bytes as f64 * 100.0 / cap as f64),
+        projected_over_cap: cap_bytes.is_some_and(|cap| projected_bytes > cap),
+    }
+}
+
+/// All bytes the network moved between two UTC bounds
+fn usage_bytes(start: &str, end: &str) -> Result<u64, String> {
+    let breakdown = protocols::breakdown_between(None, start.to_string(), end.to_string())?;
+    Ok(breakdown.total_bytes.max(breakdown.proxied_bytes))
+}
+
+/// Estimated cost of a range (the billing cycle so far by default) and the month-end projection
+pub fn usage_cost(range: Option<UsageRange>) -> Result<UsageCost, String> {
+    let model = load_settings()?.usage_cost;
+    let zone = Zone::configured();
+    let now = zone.now();
+    let (cycle_start, cycle_end) = billing_cycle(now, model.billing_cycle_day);
+
+    let (start, until) = zone.utc_bounds(cycle_start, now);
+    let cycle_bytes = usage_bytes(&start, &until)?;
+    let range = match range {
+        Some(range) => {
+            let (start, end) = range.bounds();
+            let bytes = usage_bytes(&start, &end)?;
+            (start, end, bytes)
+        }
+        None => (start.clone(), until, cycle_bytes),
+    };
+    let (_, end) = zone.utc_bounds(cycle_start, cycle_end);
+
+    Ok(estimate(
+        &model,
+        range,
+        (start, end, cycle_bytes),
+        now - cycle_start,
+        cycle_end - cycle_start,
+    ))
+}
+
+fn get_alerts_path() -> PathBuf {
+    workspace::config_dir().join("usage_cost.json")
+}
+
+fn load_alerts() -> CapAlerts {
+    fs::read_to_string(get_alerts_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_alerts(alerts: &CapAlerts) -> Result<(), String> {
+    let content =
+        serde_json::to_string_pretty(alerts).map_err(|e| format!("Failed to serialize usage alerts: {}", e))?;
+    fs::write(get_alerts_path(), content).map_err(|e| format!("Failed to write usage alerts: {}", e))
+}
+
+fn gigabytes(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / GB)
+}
+
+/// Alert once a cycle when usage is projected over the cap, and once when it goes over
+fn check_cap() -> Result<(), String> {
+    let model = load_settings()?.usage_cost;
+    if !model.metered || model.monthly_cap_gb.is_none() {
+        return Ok(());
+    }
+    let usage = usage_cost(None)?;
+    let Some(cap) = usage.cap_bytes else { return Ok(()) };
+
+    let mut alerts = load_alerts();
+    if alerts.cycle_start != usage.cycle_start {
+        alerts = CapAlerts {
+            cycle_start: usage.cycle_start.clone(),
+            ..CapAlerts::default()
+        };
+    }
+    let end = Zone::configured().display(&usage.cycle_end);
+    let days_left = clock::parse_stored(&usage.cycle_end)
+        .map(|end| (end - chrono::Utc::now()).num_days().max(0))
+        .unwrap_or(0);
+    let metadata = json!({ "usage": usage, "model": model });
+
+    if usage.cycle_bytes > cap && !alerts.exceeded {
+        log::info!("Data cap exceeded: {} of {} bytes", usage.cycle_bytes, cap);
+        event_bus::raise_alert(
+            "high",
+            "custom",
+            &t("alert.usage_cap.title", &[]),
+            &t(
+                "alert.usage_cap.description",
+                &[
+                    ("used", &gigabytes(usage.cycle_bytes)),
+                    ("cap", &gigabytes(cap)),
+                    ("days", &days_left),
+                    ("cost", &format!("{:.2} {}", usage.cycle_cost, usage.currency)),
+                ],
+            ),
+            None,
+            Some(&metadata),
+        );
+        alerts.exceeded = true;
+        alerts.projected = true;
+    } else if usage.projected_over_cap && !alerts.projected {
+        log::info!("Data cap projected to be exceeded: {} of {} bytes", usage.projected_bytes, cap);
+        event_bus::raise_alert(
+            "medium",
+            "custom",
+            &t("alert.usage_projection.title", &[]),
+            &t(
+                "alert.usage_projection.description",
+                &[
+                    ("used", &gigabytes(usage.cycle_bytes)),
+                    ("cap", &gigabytes(cap)),
+                    ("projected", &gigabytes(usage.projected_bytes)),
+                    ("cost", &format!("{:.2} {}", usage.projected_cost, usage.currency)),
+                    ("end", &end),
+                ],
+            ),
+            None,
+            Some(&metadata),
+        );
+        alerts.projected = true;
+    } else {
+        return Ok(());
+    }
+    save_alerts(&alerts)
+}
+
+/// Spawn the background task that watches usage against the data cap
+pub fn spawn_cap_watcher() {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let _ = tauri::async_runtime::spawn_blocking(|| {
+                if let Err(e) = check_cap() {
+                    log::debug!("Data cap check failed: {}", e);
+                }
+            })
+            .await;
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
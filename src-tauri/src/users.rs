@@ -0,0 +1,262 @@
+// Per-user configuration on shared PCs
+//
+// Several OS users can run the monitor on one machine. What is about one
+// person's desktop (theme, notifications, hotkeys, autostart), their names
+// and notes for devices, and their saved searches live under
+// `config/users/<user>/` of the active workspace. Everything that drives
+// capture stays in the workspace's shared `settings.json`, which the Python
+// processes read. The capture database is shared as well unless the
+// `shared_capture_database` setting is turned off, in which case each user
+// gets their own under `database/users/<user>/`.
+
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// Settings kept per OS user; every other setting is shared
+pub const USER_SETTINGS: &[&str] = &[
+    "theme",
+    "notifications_enabled",
+    "autostart",
+    "start_minimized",
+    "hotkeys",
+    "ui_push",
+];
+
+/// Name used when the OS user can't be determined
+const FALLBACK_USER: &str = "default";
+
+/// Whether the capture database is shared, loaded from settings on first use
+static SHARED_DATABASE: RwLock<Option<bool>> = RwLock::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DeviceMetadata {
+    /// Name shown instead of the hostname
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedSearch {
+    pub name: String,
+    /// Query as passed to `search_traffic`
+    pub query: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SettingScope {
+    pub key: String,
+    /// "user" or "shared"
+    pub scope: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SettingsSchema {
+    pub user: String,
+    pub user_config_dir: String,
+    pub shared_config_dir: String,
+    pub shared_capture_database: bool,
+    pub settings: Vec<SettingScope>,
+}
+
+/// The OS user running the app, reduced to a safe directory name
+pub fn current() -> String {
+    static USER: OnceLock<String> = OnceLock::new();
+    USER.get_or_init(|| {
+        let name = ["USERNAME", "USER", "LOGNAME"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+            .unwrap_or_default();
+        // Windows user names are case-insensitive
+        let name: String = name
+            .trim()
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+            .collect();
+        if name.is_empty() || name.chars().all(|c| c == '.') {
+            FALLBACK_USER.to_string()
+        } else {
+            name
+        }
+    })
+    .clone()
+}
+
+/// Config directory of the current user in the active workspace
+pub fn config_dir() -> PathBuf {
+    workspace::config_dir().join("users").join(current())
+}
+
+/// The current user's data directory under a workspace's `database/`
+pub fn data_dir(shared_dir: &Path, shared: bool) -> PathBuf {
+    if shared {
+        shared_dir.to_path_buf()
+    } else {
+        shared_dir.join("users").join(current())
+    }
+}
+
+/// Where a setting is stored
+pub fn scope(key: &str) -> &'static str {
+    if USER_SETTINGS.contains(&key) {
+        "user"
+    } else {
+        "shared"
+    }
+}
+
+fn read_object(path: &Path, what: &str) -> Result<Map<String, Value>, String> {
+    if !path.exists() {
+        return Ok(Map::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", what, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", what, e))
+}
+
+fn write_json<T: Serialize>(path: PathBuf, value: &T, what: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize {}: {}", what, e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", what, e))
+}
+
+/// Overlay the current user's settings on the shared ones
+///
+/// A user who hasn't saved settings yet sees the shared file's values.
+pub fn overlay_settings(settings: &mut Map<String, Value>) -> Result<(), String> {
+    let user = read_object(&config_dir().join("settings.json"), "user settings")?;
+    for (key, value) in user {
+        if scope(&key) == "user" {
+            settings.insert(key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Save the user-scoped part of `settings` for the current user
+pub fn save_settings(settings: &Map<String, Value>) -> Result<(), String> {
+    let user: Map<String, Value> = settings
+        .iter()
+        .filter(|(key, _)| scope(key) == "user")
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    write_json(config_dir().join("settings.json"), &user, "user settings")
+}
+
+/// Whether every user shares the workspace's capture database
+pub fn shares_capture_database() -> bool {
+    if let Some(shared) = *SHARED_DATABASE.read().unwrap() {
+        return shared;
+    }
+
+    let shared = read_object(&workspace::config_dir().join("settings.json"), "settings")
+        .ok()
+        .and_then(|settings| settings.get("shared_capture_database").and_then(|s| s.as_bool()))
+        .unwrap_or(true);
+    *SHARED_DATABASE.write().unwrap() = Some(shared);
+    shared
+}
+
+/// Forget cached settings after they change or the workspace switches
+pub fn reload() {
+    *SHARED_DATABASE.write().unwrap() = None;
+}
+
+/// Every setting with the scope it is stored in
+pub fn schema(settings: &Value) -> SettingsSchema {
+    let settings = settings
+        .as_object()
+        .map(|s| {
+            s.keys()
+                .map(|key| SettingScope { key: key.clone(), scope: scope(key).to_string() })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    SettingsSchema {
+        user: current(),
+        user_config_dir: config_dir().to_string_lossy().to_string(),
+        shared_config_dir: workspace::config_dir().to_string_lossy().to_string(),
+        shared_capture_database: shares_capture_database(),
+        settings,
+    }
+}
+
+fn device_metadata_path() -> PathBuf {
+    config_dir().join("device_metadata.json")
+}
+
+/// The current user's names, notes and tags, by device ID
+pub fn load_device_metadata() -> Result<BTreeMap<String, DeviceMetadata>, String> {
+    let path = device_metadata_path();
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read device metadata: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse device metadata: {}", e))
+}
+
+/// Set a device's metadata for the current user (None clears it)
+pub fn set_device_metadata(device_id: &str, metadata: Option<DeviceMetadata>) -> Result<(), String> {
+    let mut all = load_device_metadata()?;
+    match metadata {
+        Some(metadata) => all.insert(device_id.to_string(), metadata),
+        None => all.remove(device_id),
+    };
+    write_json(device_metadata_path(), &all, "device metadata")
+}
+
+fn saved_searches_path() -> PathBuf {
+    config_dir().join("saved_searches.json")
+}
+
+/// The current user's saved searches
+pub fn load_saved_searches() -> Result<Vec<SavedSearch>, String> {
+    let path = saved_searches_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read saved searches: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse saved searches: {}", e))
+}
+
+/// Save a search under a name, replacing one with the same name
+pub fn save_search(name: &str, query: &str) -> Result<SavedSearch, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Saved searches need a name".to_string());
+    }
+
+    let search = SavedSearch {
+        name: name.to_string(),
+        query: query.to_string(),
+        created_at: crate::clock::now_utc(),
+    };
+    let mut searches = load_saved_searches()?;
+    searches.retain(|s| s.name != name);
+    searches.push(search.clone());
+    write_json(saved_searches_path(), &searches, "saved searches")?;
+    Ok(search)
+}
+
+/// Delete a saved search by name
+pub fn delete_search(name: &str) -> Result<(), String> {
+    let mut searches = load_saved_searches()?;
+    let before = searches.len();
+    searches.retain(|s| s.name != name);
+    if searches.len() == before {
+        return Err(format!("Saved search not found: {}", name));
+    }
+    write_json(saved_searches_path(), &searches, "saved searches")
+}
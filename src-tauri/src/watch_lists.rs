@@ -0,0 +1,302 @@
+// Keyword watch lists
+//
+// Watch-only keyword lists alert on a match without blocking anything. Each
+// list applies to named device groups (or every device) and sets how much of
+// the surrounding text is kept. The Python monitor checks new captures while
+// monitoring runs, raises alerts and stores every match with its context in
+// `watch_matches`; this side reads those back as a listing and a weekly rollup.
+
+use crate::apps::UsageRange;
+use crate::clock::{self, Zone};
+use crate::database::open_readonly;
+use crate::db_writer;
+use crate::python::run_python_script;
+use crate::state::AppState;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const SCRIPT: &str = "python/alerts/watch_lists.py";
+
+/// How often new captures are checked
+const SCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most matches returned by one listing
+const MAX_MATCHES: u32 = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Redaction {
+    /// Keep the surrounding text as seen
+    None,
+    /// Mask the matched term in the snippet and URL
+    MaskTerm,
+    /// Keep no snippet, and the URL without its query string
+    NoSnippet,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WatchList {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    /// Matched as whole words, case-insensitively
+    pub terms: Vec<String>,
+    /// "low", "medium", "high" or "critical"
+    pub severity: String,
+    /// Alert category, e.g. "self_harm" or "custom"
+    pub category: String,
+    /// Device groups the list applies to; empty for every device
+    pub groups: Vec<String>,
+    pub redaction: Redaction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WatchListConfig {
+    pub enabled: bool,
+    /// Group name to device ids
+    pub groups: BTreeMap<String, Vec<String>>,
+    pub lists: Vec<WatchList>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WatchMatch {
+    pub timestamp: String,
+    pub traffic_id: Option<String>,
+    pub list_id: String,
+    pub term: String,
+    pub device_id: Option<String>,
+    pub device_ip: String,
+    pub host: Option<String>,
+    pub url: Option<String>,
+    /// "url", "request" or "response"
+    pub location: String,
+    /// Surrounding text, redacted as the list asks
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RollupCount {
+    pub key: String,
+    pub matches: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WatchListRollup {
+    pub list_id: String,
+    pub name: String,
+    pub matches: u64,
+    /// Busiest first
+    pub devices: Vec<RollupCount>,
+    pub terms: Vec<RollupCount>,
+    /// Matches per local day, oldest first
+    pub days: Vec<RollupCount>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WatchRollup {
+    pub start: String,
+    pub end: String,
+    pub total_matches: u64,
+    /// Lists with matches, most first
+    pub lists: Vec<WatchListRollup>,
+}
+
+fn check(result: Value) -> Result<Value, String> {
+    if result.get("success").and_then(|s| s.as_bool()).unwrap_or(false) {
+        Ok(result)
+    } else {
+        let error = result.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown error");
+        Err(error.to_string())
+    }
+}
+
+fn parse_config(result: Value) -> Result<WatchListConfig, String> {
+    let config = result.get("config").cloned().unwrap_or(Value::Null);
+    serde_json::from_value(config).map_err(|e| format!("Failed to parse watch lists: {}", e))
+}
+
+pub fn load_config() -> Result<WatchListConfig, String> {
+    parse_config(check(run_python_script(SCRIPT, &["--action", "config"])?)?)
+}
+
+/// Save the lists and groups; fails on duplicate ids or unknown groups
+pub fn set_config(config: &WatchListConfig) -> Result<WatchListConfig, String> {
+    let json = serde_json::to_string(config).map_err(|e| format!("Failed to serialize watch lists: {}", e))?;
+    parse_config(check(run_python_script(SCRIPT, &["--action", "set-config", "--config", &json])?)?)
+}
+
+/// Stored matches with their context, newest first
+pub fn matches(
+    list_id: Option<&str>,
+    device_id: Option<&str>,
+    range: UsageRange,
+) -> Result<Vec<WatchMatch>, String> {
+    let (start, end) = range.bounds();
+    let conn = open_readonly()?;
+    matches_from(&conn, list_id, device_id, &start, &end, Zone::configured())
+}
+
+/// `matches` against an open database, between two UTC query bounds
+pub fn matches_from(
+    conn: &Connection,
+    list_id: Option<&str>,
+    device_id: Option<&str>,
+    start: &str,
+    end: &str,
+    zone: Zone,
+) -> Result<Vec<WatchMatch>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, traffic_id, list_id, term, device_id, device_ip, host, url, location, snippet
+             FROM watch_matches
+             WHERE timestamp >= ?1 AND timestamp < ?2
+               AND (?3 IS NULL OR list_id = ?3) AND (?4 IS NULL OR device_id = ?4)
+             ORDER BY timestamp DESC
+             LIMIT ?5",
+        )
+        .map_err(|e| format!("Failed to prepare watch match query: {}", e))?;
+    let matches = stmt
+        .query_map(params![start, end, list_id, device_id, MAX_MATCHES], |row| {
+            Ok(WatchMatch {
+                timestamp: zone.display(&row.get::<_, String>(0)?),
+                traffic_id: row.get(1)?,
+                list_id: row.get(2)?,
+                term: row.get(3)?,
+                device_id: row.get(4)?,
+                device_ip: row.get(5)?,
+                host: row.get(6)?,
+                url: row.get(7)?,
+                location: row.get(8)?,
+                snippet: row.get(9)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query watch matches: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read watch matches: {}", e))?;
+    Ok(matches)
+}
+
+#[derive(Default)]
+struct ListAccumulator {
+    matches: u64,
+    devices: HashMap<String, u64>,
+    terms: HashMap<String, u64>,
+    days: BTreeMap<String, u64>,
+}
+
+fn sorted_counts(counts: HashMap<String, u64>) -> Vec<RollupCount> {
+    let mut counts: Vec<RollupCount> = counts
+        .into_iter()
+        .map(|(key, matches)| RollupCount { key, matches })
+        .collect();
+    counts.sort_by(|a, b| b.matches.cmp(&a.matches).then_with(|| a.key.cmp(&b.key)));
+    counts
+}
+
+/// Matches per list over a range, by device, term and local day
+pub fn rollup(range: UsageRange) -> Result<WatchRollup, String> {
+    let (start, end) = range.bounds();
+    let names: HashMap<String, String> = load_config()
+        .map(|config| config.lists.into_iter().map(|l| (l.id, l.name)).collect())
+        .unwrap_or_default();
+    let conn = open_readonly()?;
+    rollup_from(&conn, &names, &start, &end, Zone::configured())
+}
+
+/// `rollup` against an open database, with list names by id
+pub fn rollup_from(
+    conn: &Connection,
+    names: &HashMap<String, String>,
+    start: &str,
+    end: &str,
+    zone: Zone,
+) -> Result<WatchRollup, String> {
+    // One row per list, device, term and UTC hour
+    let mut stmt = conn
+        .prepare(
+            "SELECT list_id, COALESCE(device_id, device_ip), term, substr(timestamp, 1, 13), COUNT(*)
+             FROM watch_matches
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             GROUP BY list_id, COALESCE(device_id, device_ip), term, substr(timestamp, 1, 13)",
+        )
+        .map_err(|e| format!("Failed to prepare watch rollup query: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)? as u64,
+            ))
+        })
+        .map_err(|e| format!("Failed to query watch rollup: {}", e))?;
+
+    let mut lists: HashMap<String, ListAccumulator> = HashMap::new();
+    for row in rows {
+        let (list_id, device, term, hour, count) = row.map_err(|e| format!("Failed to read watch rollup: {}", e))?;
+        let list = lists.entry(list_id).or_default();
+        list.matches += count;
+        *list.devices.entry(device).or_default() += count;
+        *list.terms.entry(term).or_default() += count;
+        if let Some(at) = clock::parse_stored(&format!("{}:00", hour)) {
+            *list
+                .days
+                .entry(zone.at(at).date_naive().format("%Y-%m-%d").to_string())
+                .or_default() += count;
+        }
+    }
+
+    let mut lists: Vec<WatchListRollup> = lists
+        .into_iter()
+        .map(|(list_id, list)| WatchListRollup {
+            name: names.get(&list_id).cloned().unwrap_or_else(|| list_id.clone()),
+            list_id,
+            matches: list.matches,
+            devices: sorted_counts(list.devices),
+            terms: sorted_counts(list.terms),
+            days: list
+                .days
+                .into_iter()
+                .map(|(key, matches)| RollupCount { key, matches })
+                .collect(),
+        })
+        .collect();
+    lists.sort_by(|a, b| b.matches.cmp(&a.matches).then_with(|| a.list_id.cmp(&b.list_id)));
+
+    Ok(WatchRollup {
+        start: start.to_string(),
+        end: end.to_string(),
+        total_matches: lists.iter().map(|l| l.matches).sum(),
+        lists,
+    })
+}
+
+/// Spawn the background task that checks new captures against the watch lists
+pub fn spawn_watch_list_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || {
+                if !handle.state::<AppState>().lifecycle.current().is_running() {
+                    return;
+                }
+                // Matches are stored, so the scan goes through the writer queue
+                match db_writer::write(SCRIPT, &["--action", "scan"]).and_then(check) {
+                    Ok(result) if result.get("alerts").and_then(|a| a.as_u64()).unwrap_or(0) > 0 => {
+                        log::info!("Watch list matches in new traffic: {}", result)
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::debug!("Watch list scan failed: {}", e),
+                }
+            })
+            .await;
+
+            tokio::time::sleep(SCAN_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,340 @@
+// Dashboard widget data
+//
+// A dashboard the user arranges asks for each widget's data by id instead of
+// through a command per widget. Widgets are listed in one registry with the
+// parameters they take, like the quick actions, and `data` runs the provider
+// for an id with its parameters as a JSON object keyed by name. A new widget
+// is an entry in `list` and an arm in `data`.
+
+use crate::apps::UsageRange;
+use crate::clock::{self, Zone};
+use crate::commands::Device;
+use crate::database::{self, open_readonly};
+use crate::i18n::t;
+use crate::users;
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Points an unresolved alert of each severity adds to a device's risk
+const SEVERITY_RISK: &[(&str, u32)] = &[("critical", 40), ("high", 20), ("medium", 8), ("low", 3)];
+
+/// Days of alerts the risk score looks at
+const RISK_DAYS: i64 = 30;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WidgetParam {
+    pub name: &'static str,
+    /// "range", "integer", "device" or one of the listed `choices`
+    pub kind: &'static str,
+    pub required: bool,
+    pub description: &'static str,
+    pub choices: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Widget {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub params: Vec<WidgetParam>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DomainCount {
+    pub domain: String,
+    pub requests: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct AlertDay {
+    /// Day in the configured zone, YYYY-MM-DD
+    pub date: String,
+    pub total: u64,
+    /// Alerts per severity
+    pub severities: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceRisk {
+    pub device_id: String,
+    pub name: String,
+    /// 0–100
+    pub score: u32,
+    /// Unresolved alerts per severity in the last RISK_DAYS
+    pub alerts: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct GroupBandwidth {
+    pub group: String,
+    pub devices: u32,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+fn param(name: &'static str, kind: &'static str, required: bool, description: &'static str) -> WidgetParam {
+    WidgetParam { name, kind, required, description, choices: Vec::new() }
+}
+
+fn widget(id: &'static str, title: &'static str, description: &'static str, params: Vec<WidgetParam>) -> Widget {
+    Widget { id, title, description, params }
+}
+
+/// Every widget a dashboard can show
+pub fn list() -> Vec<Widget> {
+    vec![
+        widget(
+            "top_domains",
+            "Top domains",
+            "Most requested domains",
+            vec![
+                param("range", "range", false, "Time window (default today)"),
+                param("device_id", "device", false, "Only this device"),
+                param("limit", "integer", false, "Domains shown (default 10)"),
+            ],
+        ),
+        widget(
+            "alert_trends",
+            "Alert trends",
+            "Alerts per day by severity",
+            vec![param("days", "integer", false, "Days shown (default 14)")],
+        ),
+        widget(
+            "device_risk",
+            "Device risk",
+            "Devices ranked by their unresolved alerts",
+            vec![param("limit", "integer", false, "Devices shown (default 10)")],
+        ),
+        widget(
+            "bandwidth_by_group",
+            "Bandwidth by group",
+            "Traffic per device type or tag",
+            vec![
+                param("range", "range", false, "Time window (default today)"),
+                WidgetParam {
+                    choices: vec!["device_type", "tag"],
+                    ..param("group_by", "choice", false, "How devices are grouped (default device_type)")
+                },
+            ],
+        ),
+    ]
+}
+
+fn range_param(params: &Value) -> Result<UsageRange, String> {
+    match params.get("range") {
+        None | Some(Value::Null) => Ok(UsageRange::default()),
+        Some(range) => serde_json::from_value(range.clone()).map_err(|e| format!("Invalid range: {}", e)),
+    }
+}
+
+fn int_param(params: &Value, name: &str, default: u64) -> u64 {
+    params.get(name).and_then(|v| v.as_u64()).unwrap_or(default)
+}
+
+fn device_name(devices: &[Device], device_id: &str) -> String {
+    devices
+        .iter()
+        .find(|d| d.id == device_id)
+        .map(|d| d.hostname.clone().unwrap_or_else(|| d.ip.clone()))
+        .unwrap_or_else(|| device_id.to_string())
+}
+
+/// Most requested hosts between `start` and `end`, raw and rolled-up traffic together
+pub fn top_domains_from(
+    conn: &Connection,
+    device_id: Option<&str>,
+    start: &str,
+    end: &str,
+    limit: u32,
+) -> Result<Vec<DomainCount>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT host, SUM(requests), SUM(bytes) FROM (
+                SELECT host, 1 AS requests, COALESCE(request_size, 0) + COALESCE(response_size, 0) AS bytes
+                FROM traffic WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_id = ?3)
+                UNION ALL
+                SELECT host, requests, bytes_in + bytes_out FROM traffic_aggregates
+                WHERE minute >= substr(?1, 1, 16) AND minute < ?2 AND (?3 IS NULL OR device_id = ?3)
+             )
+             GROUP BY host ORDER BY 2 DESC, host LIMIT ?4",
+        )
+        .map_err(|e| format!("Failed to prepare top domains query: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, device_id, limit], |row| {
+            Ok(DomainCount {
+                domain: row.get(0)?,
+                requests: row.get::<_, Option<i64>>(1)?.unwrap_or(0) as u64,
+                bytes: row.get::<_, Option<i64>>(2)?.unwrap_or(0) as u64,
+            })
+        })
+        .map_err(|e| format!("Failed to query top domains: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read top domains: {}", e))
+}
+
+/// Stored alerts per day of the zone over the last `days`, oldest first, empty days included
+pub fn alert_trend(alerts: &[Value], days: u32, zone: Zone, now: DateTime<Utc>) -> Vec<AlertDay> {
+    let today = zone.at(now).date_naive();
+    let mut trend: BTreeMap<String, AlertDay> = (0..days as i64)
+        .map(|ago| {
+            let date = (today - Duration::days(ago)).format("%Y-%m-%d").to_string();
+            (date.clone(), AlertDay { date, ..Default::default() })
+        })
+        .collect();
+
+    for alert in alerts {
+        let Some(at) = alert.get("timestamp").and_then(|t| t.as_str()).and_then(clock::parse_stored) else {
+            continue;
+        };
+        let date = zone.at(at).format("%Y-%m-%d").to_string();
+        let Some(day) = trend.get_mut(&date) else { continue };
+        let severity = alert.get("severity").and_then(|s| s.as_str()).unwrap_or("low");
+        day.total += 1;
+        *day.severities.entry(severity.to_string()).or_default() += 1;
+    }
+    trend.into_values().collect()
+}
+
+/// Devices with unresolved alerts since `since`, riskiest first
+pub fn device_risk(devices: &[Device], alerts: &[Value], since: &str) -> Vec<DeviceRisk> {
+    let mut by_device: BTreeMap<String, BTreeMap<String, u64>> = BTreeMap::new();
+    for alert in alerts {
+        if alert.get("acknowledged").and_then(|b| b.as_bool()).unwrap_or(false) {
+            continue;
+        }
+        let recent = alert.get("timestamp").and_then(|t| t.as_str()).is_some_and(|t| t >= since);
+        let device_id = alert.get("device_id").or(alert.get("source_device")).and_then(|d| d.as_str());
+        let (true, Some(device_id)) = (recent, device_id) else { continue };
+        let severity = alert.get("severity").and_then(|s| s.as_str()).unwrap_or("low");
+        *by_device
+            .entry(device_id.to_string())
+            .or_default()
+            .entry(severity.to_string())
+            .or_default() += 1;
+    }
+
+    let mut risks: Vec<DeviceRisk> = by_device
+        .into_iter()
+        .map(|(device_id, alerts)| {
+            let points: u64 = alerts
+                .iter()
+                .map(|(severity, count)| {
+                    let weight = SEVERITY_RISK.iter().find(|(s, _)| s == severity).map_or(1, |(_, w)| *w);
+                    weight as u64 * count
+                })
+                .sum();
+            DeviceRisk {
+                name: device_name(devices, &device_id),
+                device_id,
+                score: points.min(100) as u32,
+                alerts,
+            }
+        })
+        .collect();
+    risks.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    risks
+}
+
+/// Bytes (in, out) per device between `start` and `end`, non-web flows included
+pub fn device_bytes_from(conn: &Connection, start: &str, end: &str) -> Result<HashMap<String, (u64, u64)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_id, SUM(bytes_in), SUM(bytes_out) FROM (
+                SELECT device_id, response_size AS bytes_in, request_size AS bytes_out FROM traffic
+                WHERE timestamp >= ?1 AND timestamp < ?2
+                UNION ALL
+                SELECT device_id, bytes_in, bytes_out FROM traffic_aggregates
+                WHERE minute >= substr(?1, 1, 16) AND minute < ?2
+                UNION ALL
+                SELECT device_id, bytes_in, bytes_out FROM flows
+                WHERE start_time >= ?1 AND start_time < ?2 AND protocol NOT IN ('http', 'https')
+             )
+             WHERE device_id IS NOT NULL
+             GROUP BY device_id",
+        )
+        .map_err(|e| format!("Failed to prepare bandwidth query: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                (
+                    row.get::<_, Option<i64>>(1)?.unwrap_or(0) as u64,
+                    row.get::<_, Option<i64>>(2)?.unwrap_or(0) as u64,
+                ),
+            ))
+        })
+        .map_err(|e| format!("Failed to query bandwidth: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read bandwidth: {}", e))
+}
+
+/// Device bytes summed per group, busiest first; a device counts in each of its tags
+pub fn group_bandwidth(
+    devices: &[Device],
+    bytes: &HashMap<String, (u64, u64)>,
+    groups_of: impl Fn(&Device) -> Vec<String>,
+) -> Vec<GroupBandwidth> {
+    let mut groups: BTreeMap<String, GroupBandwidth> = BTreeMap::new();
+    for device in devices {
+        let (bytes_in, bytes_out) = bytes.get(&device.id).copied().unwrap_or_default();
+        for group in groups_of(device) {
+            let entry = groups.entry(group.clone()).or_insert_with(|| GroupBandwidth {
+                group,
+                devices: 0,
+                bytes_in: 0,
+                bytes_out: 0,
+            });
+            entry.devices += 1;
+            entry.bytes_in += bytes_in;
+            entry.bytes_out += bytes_out;
+        }
+    }
+    let mut groups: Vec<GroupBandwidth> = groups.into_values().collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.bytes_in + g.bytes_out));
+    groups
+}
+
+/// Data for a widget by id, with its parameters keyed by name
+pub fn data(widget_id: &str, params: &Value, devices: &[Device]) -> Result<Value, String> {
+    match widget_id {
+        "top_domains" => {
+            let (start, end) = range_param(params)?.bounds();
+            let device_id = params.get("device_id").and_then(|d| d.as_str());
+            let limit = int_param(params, "limit", 10) as u32;
+            top_domains_from(&open_readonly()?, device_id, &start, &end, limit).map(|d| json!(d))
+        }
+        "alert_trends" => {
+            let days = int_param(params, "days", 14).clamp(1, 366) as u32;
+            Ok(json!(alert_trend(&database::stored_alerts(), days, Zone::configured(), Utc::now())))
+        }
+        "device_risk" => {
+            let since = clock::utc_cutoff(Duration::days(RISK_DAYS));
+            let mut risks = device_risk(devices, &database::stored_alerts(), &since);
+            risks.truncate(int_param(params, "limit", 10) as usize);
+            Ok(json!(risks))
+        }
+        "bandwidth_by_group" => {
+            let (start, end) = range_param(params)?.bounds();
+            let bytes = device_bytes_from(&open_readonly()?, &start, &end)?;
+            match params.get("group_by").and_then(|g| g.as_str()).unwrap_or("device_type") {
+                "device_type" => Ok(json!(group_bandwidth(devices, &bytes, |d| vec![d.device_type.clone()]))),
+                "tag" => {
+                    let metadata = users::load_device_metadata()?;
+                    Ok(json!(group_bandwidth(devices, &bytes, |d| {
+                        metadata
+                            .get(&d.id)
+                            .map(|m| m.tags.clone())
+                            .filter(|tags| !tags.is_empty())
+                            .unwrap_or_else(|| vec!["untagged".to_string()])
+                    })))
+                }
+                other => Err(t("error.invalid_widget_param", &[("name", &"group_by"), ("value", &other)])),
+            }
+        }
+        other => Err(t("error.unknown_widget", &[("id", &other)])),
+    }
+}
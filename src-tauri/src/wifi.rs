@@ -0,0 +1,405 @@
+// Wi-Fi environment scan and channel recommendations
+//
+// Lists nearby networks with their channel, signal and security using the
+// OS tools (`netsh wlan` on Windows, NetworkManager's `nmcli` or nl80211 via
+// `iw` on Linux), then scores how crowded each channel is. 2.4 GHz channels
+// overlap their neighbours, so nearby networks count against every channel
+// within four of theirs, weighted by signal; 5/6 GHz channels only count exact
+// matches.
+
+use crate::clock;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::process::Command;
+
+/// Non-overlapping 2.4 GHz channels worth recommending
+const CHANNELS_24: &[u32] = &[1, 6, 11];
+
+/// 5 GHz channels usable without DFS in most regions
+const CHANNELS_5: &[u32] = &[36, 40, 44, 48, 149, 153, 157, 161];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WifiNetwork {
+    /// Empty for hidden networks
+    pub ssid: String,
+    pub bssid: String,
+    pub channel: u32,
+    pub frequency_mhz: Option<u32>,
+    /// "2.4GHz", "5GHz" or "6GHz"
+    pub band: String,
+    pub signal_percent: u8,
+    pub signal_dbm: i32,
+    /// e.g. "WPA2-Personal", "WPA3", "Open"
+    pub security: String,
+    /// Whether this machine is connected to this access point
+    pub connected: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ChannelUsage {
+    pub band: String,
+    pub channel: u32,
+    pub networks: usize,
+    /// Signal-weighted interference from networks on or overlapping the channel
+    pub congestion: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ChannelRecommendation {
+    pub band: String,
+    /// Channel of the connected network in this band, if any
+    pub current_channel: Option<u32>,
+    pub recommended_channel: u32,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WifiEnvironment {
+    pub networks: Vec<WifiNetwork>,
+    pub channels: Vec<ChannelUsage>,
+    pub recommendations: Vec<ChannelRecommendation>,
+    pub scanned_at: String,
+}
+
+fn channel_from_frequency(mhz: u32) -> Option<u32> {
+    match mhz {
+        2484 => Some(14),
+        2412..=2472 => Some((mhz - 2407) / 5),
+        5000..=5895 => Some((mhz - 5000) / 5),
+        5955..=7115 => Some((mhz - 5950) / 5),
+        _ => None,
+    }
+}
+
+fn band_of(channel: u32, frequency_mhz: Option<u32>) -> &'static str {
+    match frequency_mhz {
+        Some(f) if f >= 5925 => "6GHz",
+        Some(f) if f >= 5000 => "5GHz",
+        Some(_) => "2.4GHz",
+        None if channel <= 14 => "2.4GHz",
+        None => "5GHz",
+    }
+}
+
+fn percent_to_dbm(percent: u8) -> i32 {
+    percent as i32 / 2 - 100
+}
+
+fn dbm_to_percent(dbm: f64) -> u8 {
+    ((dbm + 100.0) * 2.0).clamp(0.0, 100.0) as u8
+}
+
+fn field<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let (key, value) = line.split_once(':')?;
+    (key.trim() == name || key.trim().starts_with(&format!("{} ", name))).then(|| value.trim())
+}
+
+/// Parse `netsh wlan show networks mode=bssid`; one entry per access point
+fn parse_netsh(output: &str, connected_bssid: Option<&str>) -> Vec<WifiNetwork> {
+    let mut networks = Vec::new();
+    let mut ssid = String::new();
+    let mut security = String::new();
+    let mut current: Option<WifiNetwork> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(value) = field(trimmed, "SSID") {
+            networks.extend(current.take());
+            ssid = value.to_string();
+        } else if let Some(value) = field(trimmed, "Authentication") {
+            security = if value == "Open" { "Open".to_string() } else { value.to_string() };
+        } else if trimmed.starts_with("BSSID") {
+            networks.extend(current.take());
+            // "BSSID 1 : aa:bb:cc:dd:ee:ff" — the value itself contains colons
+            let bssid = trimmed.split_once(" : ").map(|(_, v)| v.trim()).unwrap_or("").to_lowercase();
+            current = Some(WifiNetwork {
+                ssid: ssid.clone(),
+                connected: connected_bssid.is_some_and(|c| c.eq_ignore_ascii_case(&bssid)),
+                bssid,
+                channel: 0,
+                frequency_mhz: None,
+                band: String::new(),
+                signal_percent: 0,
+                signal_dbm: -100,
+                security: security.clone(),
+            });
+        } else if let Some(network) = current.as_mut() {
+            if let Some(value) = field(trimmed, "Signal") {
+                network.signal_percent = value.trim_end_matches('%').parse().unwrap_or(0);
+                network.signal_dbm = percent_to_dbm(network.signal_percent);
+            } else if let Some(value) = field(trimmed, "Channel") {
+                network.channel = value.parse().unwrap_or(0);
+            } else if let Some(value) = field(trimmed, "Band") {
+                network.band = value.replace(' ', "");
+            }
+        }
+    }
+    networks.extend(current);
+
+    for network in &mut networks {
+        if network.band.is_empty() {
+            network.band = band_of(network.channel, None).to_string();
+        }
+    }
+    networks
+}
+
+/// Split an `nmcli -t` line on unescaped colons
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    fields.last_mut().unwrap().push(next);
+                }
+            }
+            ':' => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// Parse `nmcli -t -f IN-USE,SSID,BSSID,CHAN,FREQ,SIGNAL,SECURITY dev wifi list`
+fn parse_nmcli(output: &str) -> Vec<WifiNetwork> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields = split_terse(line);
+            let [in_use, ssid, bssid, channel, freq, signal, security] = fields.as_slice() else {
+                return None;
+            };
+            let channel: u32 = channel.parse().ok()?;
+            let frequency_mhz = freq.split_whitespace().next().and_then(|f| f.parse().ok());
+            let signal_percent: u8 = signal.parse().unwrap_or(0);
+
+            Some(WifiNetwork {
+                ssid: ssid.clone(),
+                bssid: bssid.to_lowercase(),
+                channel,
+                frequency_mhz,
+                band: band_of(channel, frequency_mhz).to_string(),
+                signal_percent,
+                signal_dbm: percent_to_dbm(signal_percent),
+                security: if security.is_empty() || security == "--" { "Open".to_string() } else { security.clone() },
+                connected: in_use == "*",
+            })
+        })
+        .collect()
+}
+
+/// Parse `iw dev <iface> scan` (nl80211); one "BSS" block per access point
+fn parse_iw(output: &str) -> Vec<WifiNetwork> {
+    let mut networks = Vec::new();
+    let mut current: Option<WifiNetwork> = None;
+    let mut wpa = (false, false, false);
+
+    let finish = |network: Option<WifiNetwork>, wpa: (bool, bool, bool)| {
+        network.map(|mut n| {
+            n.security = match wpa {
+                (_, _, true) => "WPA3",
+                (_, true, _) => "WPA2",
+                (true, _, _) => "WPA",
+                _ => "Open",
+            }
+            .to_string();
+            if n.channel == 0 {
+                n.channel = n.frequency_mhz.and_then(channel_from_frequency).unwrap_or(0);
+            }
+            n.band = band_of(n.channel, n.frequency_mhz).to_string();
+            n
+        })
+    };
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = line.strip_prefix("BSS ") {
+            networks.extend(finish(current.take(), wpa));
+            wpa = (false, false, false);
+            let bssid: String = rest.chars().take(17).collect();
+            current = Some(WifiNetwork {
+                ssid: String::new(),
+                bssid: bssid.to_lowercase(),
+                channel: 0,
+                frequency_mhz: None,
+                band: String::new(),
+                signal_percent: 0,
+                signal_dbm: -100,
+                security: String::new(),
+                connected: rest.contains("associated"),
+            });
+        } else if let Some(network) = current.as_mut() {
+            if let Some(value) = trimmed.strip_prefix("freq:") {
+                network.frequency_mhz = value.trim().split('.').next().and_then(|f| f.parse().ok());
+            } else if let Some(value) = trimmed.strip_prefix("signal:") {
+                let dbm: f64 = value.trim().trim_end_matches("dBm").trim().parse().unwrap_or(-100.0);
+                network.signal_dbm = dbm.round() as i32;
+                network.signal_percent = dbm_to_percent(dbm);
+            } else if let Some(value) = trimmed.strip_prefix("SSID:") {
+                network.ssid = value.trim().to_string();
+            } else if let Some(value) = trimmed.strip_prefix("DS Parameter set: channel") {
+                network.channel = value.trim().parse().unwrap_or(0);
+            } else if let Some(value) = trimmed.strip_prefix("* primary channel:") {
+                network.channel = value.trim().parse().unwrap_or(network.channel);
+            } else if trimmed.starts_with("WPA:") {
+                wpa.0 = true;
+            } else if trimmed.starts_with("RSN:") {
+                wpa.1 = true;
+            } else if trimmed.contains("Authentication suites:") && trimmed.contains("SAE") {
+                wpa.2 = true;
+            }
+        }
+    }
+    networks.extend(finish(current, wpa));
+    networks
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn scan_networks() -> Result<Vec<WifiNetwork>, String> {
+    if cfg!(windows) {
+        let connected = run("netsh", &["wlan", "show", "interfaces"]).ok().and_then(|out| {
+            out.lines()
+                .find(|l| l.trim().starts_with("BSSID") || l.trim().starts_with("AP BSSID"))
+                .and_then(|l| l.split_once(" : "))
+                .map(|(_, v)| v.trim().to_string())
+        });
+        let output = run("netsh", &["wlan", "show", "networks", "mode=bssid"])?;
+        Ok(parse_netsh(&output, connected.as_deref()))
+    } else if cfg!(target_os = "linux") {
+        let nmcli = run(
+            "nmcli",
+            &["-t", "-f", "IN-USE,SSID,BSSID,CHAN,FREQ,SIGNAL,SECURITY", "dev", "wifi", "list", "--rescan", "auto"],
+        );
+        if let Ok(output) = nmcli {
+            return Ok(parse_nmcli(&output));
+        }
+
+        // Without NetworkManager, scan through nl80211 directly (needs root)
+        let devices = run("iw", &["dev"])?;
+        let interface = devices
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("Interface "))
+            .ok_or("No wireless interface found")?;
+        let output = run("iw", &["dev", interface, "scan"])?;
+        Ok(parse_iw(&output))
+    } else {
+        Err("Wi-Fi scanning is not supported on this platform".to_string())
+    }
+}
+
+fn congestion(networks: &[WifiNetwork], band: &str, channel: u32) -> (usize, f64) {
+    let mut count = 0;
+    let mut score = 0.0;
+    for network in networks.iter().filter(|n| n.band == band) {
+        let distance = network.channel.abs_diff(channel);
+        let overlap = if band == "2.4GHz" {
+            if distance > 4 {
+                continue;
+            }
+            1.0 - distance as f64 / 5.0
+        } else if distance == 0 {
+            1.0
+        } else {
+            continue;
+        };
+        if distance == 0 {
+            count += 1;
+        }
+        score += overlap * network.signal_percent as f64 / 100.0;
+    }
+    (count, (score * 100.0).round() / 100.0)
+}
+
+fn recommend(networks: &[WifiNetwork], band: &str, candidates: &[u32]) -> Option<ChannelRecommendation> {
+    if !networks.iter().any(|n| n.band == band) {
+        return None;
+    }
+
+    // Our own access point doesn't interfere with itself
+    let connected = networks.iter().find(|n| n.connected && n.band == band);
+    let others: Vec<WifiNetwork> = networks
+        .iter()
+        .filter(|n| !connected.is_some_and(|c| c.ssid == n.ssid && !c.ssid.is_empty()))
+        .cloned()
+        .collect();
+
+    let (best, best_score) = candidates
+        .iter()
+        .map(|&c| (c, congestion(&others, band, c).1))
+        .min_by(|a, b| a.1.total_cmp(&b.1))?;
+    let current_channel = connected.map(|c| c.channel);
+
+    let reason = match current_channel {
+        Some(current) if current == best => format!("Channel {} is already the least congested in {}", current, band),
+        Some(current) => {
+            let current_score = congestion(&others, band, current).1;
+            if current_score - best_score < 0.3 {
+                format!(
+                    "Channel {} is about as congested as the alternatives; switching to {} gains little",
+                    current, best
+                )
+            } else {
+                format!(
+                    "Channel {} has interference {:.2} from nearby networks; channel {} has {:.2}",
+                    current, current_score, best, best_score
+                )
+            }
+        }
+        None => format!("Channel {} is the least congested in {}", best, band),
+    };
+
+    Some(ChannelRecommendation {
+        band: band.to_string(),
+        current_channel,
+        recommended_channel: best,
+        reason,
+    })
+}
+
+/// Scan nearby Wi-Fi networks and suggest less congested channels
+pub fn scan() -> Result<WifiEnvironment, String> {
+    let mut networks = scan_networks()?;
+    networks.sort_by(|a, b| b.connected.cmp(&a.connected).then(b.signal_percent.cmp(&a.signal_percent)));
+
+    let in_use: BTreeSet<(String, u32)> = networks.iter().map(|n| (n.band.clone(), n.channel)).collect();
+    let channels = in_use
+        .into_iter()
+        .map(|(band, channel)| {
+            let (networks, congestion) = congestion(&networks, &band, channel);
+            ChannelUsage {
+                band,
+                channel,
+                networks,
+                congestion,
+            }
+        })
+        .collect();
+
+    let recommendations = [("2.4GHz", CHANNELS_24), ("5GHz", CHANNELS_5)]
+        .into_iter()
+        .filter_map(|(band, candidates)| recommend(&networks, band, candidates))
+        .collect();
+
+    Ok(WifiEnvironment {
+        networks,
+        channels,
+        recommendations,
+        scanned_at: clock::now_utc(),
+    })
+}
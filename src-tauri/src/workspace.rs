@@ -0,0 +1,183 @@
+// Named configuration workspaces
+//
+// Each workspace has its own settings, blocklists, schedules and capture
+// database. The "default" workspace is the top-level `config/` and `database/`
+// directories; others live under `workspaces/<name>/`. Shared reference data
+// (device profiles, VPN endpoint lists, the CA certificate) stays in the
+// top-level `config/` regardless of the active workspace.
+//
+// Per-OS-user settings and data within a workspace are handled by `users`.
+//
+// Python scripts find the active workspace through the NETWORK_MONITOR_CONFIG
+// and NETWORK_MONITOR_DATA environment variables set on every spawn.
+
+use crate::users;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// Config files copied into a new workspace from the one it was created from
+const SEEDED_FILES: &[&str] = &[
+    "settings.json",
+    "blocklist.json",
+    "schedules.json",
+    "alerts.json",
+    "keywords.json",
+    "notifications.json",
+];
+
+/// Active workspace name, loaded from disk on first use
+static ACTIVE: RwLock<Option<String>> = RwLock::new(None);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ActiveFile {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub active: bool,
+    pub path: String,
+}
+
+/// Directory the default workspace and `workspaces/` live in: the project
+/// root, or a scratch directory per test run so tests leave the real config alone
+pub fn root() -> PathBuf {
+    #[cfg(test)]
+    {
+        static SCRATCH: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+        SCRATCH
+            .get_or_init(|| {
+                let dir = std::env::temp_dir().join(format!("network-monitor-tests-{}", std::process::id()));
+                let _ = fs::create_dir_all(dir.join("config"));
+                let _ = fs::create_dir_all(dir.join("database"));
+                dir
+            })
+            .clone()
+    }
+    #[cfg(not(test))]
+    crate::python::get_project_root()
+}
+
+fn workspaces_root() -> PathBuf {
+    root().join("workspaces")
+}
+
+fn active_path() -> PathBuf {
+    workspaces_root().join("active.json")
+}
+
+fn workspace_root(name: &str) -> PathBuf {
+    if name == DEFAULT_WORKSPACE {
+        root()
+    } else {
+        workspaces_root().join(name)
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err("Workspace names may only contain letters, digits, '-' and '_'".to_string())
+    }
+}
+
+/// Name of the active workspace
+pub fn active() -> String {
+    if let Some(ref name) = *ACTIVE.read().unwrap() {
+        return name.clone();
+    }
+
+    let name = fs::read_to_string(active_path())
+        .ok()
+        .and_then(|content| serde_json::from_str::<ActiveFile>(&content).ok())
+        .map(|f| f.name)
+        .filter(|name| validate_name(name).is_ok() && workspace_root(name).exists())
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string());
+
+    *ACTIVE.write().unwrap() = Some(name.clone());
+    name
+}
+
+/// Config directory of the active workspace
+pub fn config_dir() -> PathBuf {
+    workspace_root(&active()).join("config")
+}
+
+/// Data directory (capture database, alert store, audit log) of the active
+/// workspace, or of the current OS user in it when users don't share one
+pub fn data_dir() -> PathBuf {
+    users::data_dir(&workspace_root(&active()).join("database"), users::shares_capture_database())
+}
+
+/// List the default workspace and every workspace created so far
+pub fn list() -> Result<Vec<WorkspaceInfo>, String> {
+    let mut names = vec![DEFAULT_WORKSPACE.to_string()];
+
+    let root = workspaces_root();
+    if root.exists() {
+        let entries = fs::read_dir(&root).map_err(|e| format!("Failed to read workspaces: {}", e))?;
+        let mut others: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|name| name != DEFAULT_WORKSPACE && validate_name(name).is_ok())
+            .collect();
+        others.sort();
+        names.extend(others);
+    }
+
+    let active = active();
+    Ok(names
+        .into_iter()
+        .map(|name| WorkspaceInfo {
+            active: name == active,
+            path: workspace_root(&name).to_string_lossy().to_string(),
+            name,
+        })
+        .collect())
+}
+
+/// Make `name` the active workspace, creating it (seeded with the current
+/// workspace's configuration) if it doesn't exist yet
+pub fn switch(name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    let current = active();
+    if name == current {
+        return Ok(());
+    }
+
+    let root = workspace_root(name);
+    if !root.exists() {
+        let config = root.join("config");
+        fs::create_dir_all(&config).map_err(|e| format!("Failed to create workspace: {}", e))?;
+        fs::create_dir_all(root.join("database")).map_err(|e| format!("Failed to create workspace: {}", e))?;
+
+        let source = config_dir();
+        for file in SEEDED_FILES {
+            let from = source.join(file);
+            if from.exists() {
+                fs::copy(&from, config.join(file)).map_err(|e| format!("Failed to copy {}: {}", file, e))?;
+            }
+        }
+        log::info!("Created workspace '{}' from '{}'", name, current);
+    }
+
+    fs::create_dir_all(workspaces_root()).map_err(|e| format!("Failed to create workspaces dir: {}", e))?;
+    let marker = serde_json::to_string_pretty(&ActiveFile { name: name.to_string() })
+        .map_err(|e| format!("Failed to serialize workspace: {}", e))?;
+    fs::write(active_path(), marker).map_err(|e| format!("Failed to save active workspace: {}", e))?;
+
+    *ACTIVE.write().unwrap() = Some(name.to_string());
+    users::reload();
+    log::info!("Switched to workspace '{}'", name);
+    Ok(())
+}